@@ -1,5 +1,56 @@
 use super::{fn_mod_flags_to_string, Ast, NodeIndex, NodeKind, NodeType};
 use rustc_span::SourceMap;
+use std::collections::HashMap;
+
+/// 通用的 AST walker：默认按 [`Ast::child_nodes`] 递归遍历所有子节点，
+/// 只需覆盖 `enter` / `leave` 即可在进入、离开每个节点时执行操作。
+pub trait AstWalker {
+    /// 遍历一个节点及其全部后代；无效节点 (`0`) 被忽略
+    fn walk_node(&mut self, ast: &Ast, node: NodeIndex) {
+        let Some(kind) = ast.get_node_kind(node) else {
+            return;
+        };
+        self.enter(ast, node, kind);
+        for child in ast.child_nodes(node) {
+            self.walk_node(ast, child);
+        }
+        self.leave(ast, node, kind);
+    }
+
+    /// 进入节点时调用，先于其子节点
+    fn enter(&mut self, _ast: &Ast, _node: NodeIndex, _kind: NodeKind) {}
+
+    /// 离开节点时调用，晚于其子节点
+    fn leave(&mut self, _ast: &Ast, _node: NodeIndex, _kind: NodeKind) {}
+}
+
+/// 按节点类型统计节点数量的 walker
+#[derive(Debug, Default)]
+pub struct NodeKindCounter {
+    pub counts: HashMap<NodeKind, usize>,
+}
+
+impl NodeKindCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 某一类节点的数量
+    pub fn count(&self, kind: NodeKind) -> usize {
+        self.counts.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// 节点总数
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+}
+
+impl AstWalker for NodeKindCounter {
+    fn enter(&mut self, _ast: &Ast, _node: NodeIndex, kind: NodeKind) {
+        *self.counts.entry(kind).or_default() += 1;
+    }
+}
 
 /// AST visitor trait，用于遍历 AST 并对每个节点执行操作
 pub trait AstVisitor {
@@ -0,0 +1,184 @@
+//! Compact binary form of an [`Ast`], for caching parsed files between runs.
+//!
+//! The encoding is a header followed by little-endian `u32`s:
+//!
+//! ```text
+//! magic "FLAST" version
+//! node_count children_count root
+//! node_count × (kind span.lo span.hi children_start children_end parent)
+//! children_count × child
+//! doc_count
+//! doc_count × (node span.lo span.hi)
+//! ```
+//!
+//! Spans are stored as raw byte positions, so a decoded tree is only valid
+//! against a source map in which the file starts at the same position it
+//! had when the tree was encoded.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use rustc_span::{BytePos, Span};
+
+use crate::{Ast, NodeIndex, NodeKind};
+
+const MAGIC: &[u8; 5] = b"FLAST";
+const VERSION: u32 = 3;
+
+/// Why [`Ast::decode`] rejected its input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input does not start with the encoding's magic bytes.
+    BadMagic,
+    /// The input was written by another version of the encoding.
+    UnsupportedVersion(u32),
+    /// The input ends before the data its header announces.
+    Truncated,
+    /// Bytes are left over after the last child.
+    TrailingBytes(usize),
+    /// A node's kind is not a [`NodeKind`].
+    InvalidKind { node: NodeIndex, raw: u32 },
+    /// A node's child slots or parent, or the node a doc comment is
+    /// attached to, lie outside the tree.
+    IndexOutOfRange(NodeIndex),
+    /// The root is not a node of the tree.
+    InvalidRoot(NodeIndex),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::BadMagic => write!(f, "not an encoded AST"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported AST encoding version {}", version)
+            }
+            DecodeError::Truncated => write!(f, "encoded AST is truncated"),
+            DecodeError::TrailingBytes(count) => {
+                write!(f, "{} unexpected byte(s) after the encoded AST", count)
+            }
+            DecodeError::InvalidKind { node, raw } => {
+                write!(f, "node {} has invalid kind {}", node, raw)
+            }
+            DecodeError::IndexOutOfRange(node) => {
+                write!(f, "node {} refers outside the tree", node)
+            }
+            DecodeError::InvalidRoot(root) => write!(f, "root {} is not a node", root),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl NodeKind {
+    /// The kind whose discriminant is `raw`.
+    pub fn from_raw(raw: u32) -> Option<NodeKind> {
+        if raw <= NodeKind::ExpandArg as u32 {
+            // SAFETY: `NodeKind` is `repr(u32)` with discriminants running
+            // from `Invalid = 0` to the last variant, `ExpandArg`.
+            Some(unsafe { std::mem::transmute::<u32, NodeKind>(raw) })
+        } else {
+            None
+        }
+    }
+}
+
+impl Ast {
+    /// Encode the tree in the format described in the [module docs](self).
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out =
+            Vec::with_capacity(MAGIC.len() + 4 * (5 + 6 * self.nodes.len() + self.children.len()));
+        out.extend_from_slice(MAGIC);
+        let mut put = |value: u32| out.extend_from_slice(&value.to_le_bytes());
+        put(VERSION);
+        put(self.nodes.len() as u32);
+        put(self.children.len() as u32);
+        put(self.root);
+        for i in 0..self.nodes.len() {
+            put(self.nodes[i] as u32);
+            put(self.spans[i].lo().0);
+            put(self.spans[i].hi().0);
+            put(self.children_start[i]);
+            put(self.children_end[i]);
+            put(self.parents[i]);
+        }
+        for &child in &self.children {
+            put(child);
+        }
+        put(self.doc_comments.len() as u32);
+        for (&node, span) in &self.doc_comments {
+            put(node);
+            put(span.lo().0);
+            put(span.hi().0);
+        }
+        out
+    }
+
+    /// Decode a tree written by [`Ast::encode`].
+    pub fn decode(bytes: &[u8]) -> Result<Ast, DecodeError> {
+        let rest = bytes.strip_prefix(MAGIC).ok_or(DecodeError::BadMagic)?;
+        let mut words = rest.chunks_exact(4);
+        let trailing = words.remainder().len();
+        let mut next = || {
+            words
+                .next()
+                .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+                .ok_or(DecodeError::Truncated)
+        };
+
+        let version = next()?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let node_count = next()? as usize;
+        let children_count = next()? as usize;
+        let root = next()?;
+        if rest.len() / 4 < 5 + 6 * node_count + children_count {
+            return Err(DecodeError::Truncated);
+        }
+
+        let mut ast = Ast {
+            nodes: Vec::with_capacity(node_count),
+            spans: Vec::with_capacity(node_count),
+            children_start: Vec::with_capacity(node_count),
+            children_end: Vec::with_capacity(node_count),
+            parents: Vec::with_capacity(node_count),
+            children: Vec::with_capacity(children_count),
+            doc_comments: BTreeMap::new(),
+            root,
+        };
+        for node in 0..node_count as NodeIndex {
+            let raw = next()?;
+            let kind = NodeKind::from_raw(raw).ok_or(DecodeError::InvalidKind { node, raw })?;
+            let (lo, hi) = (next()?, next()?);
+            let (start, end, parent) = (next()?, next()?, next()?);
+            if start > end || end as usize > children_count || parent as usize >= node_count {
+                return Err(DecodeError::IndexOutOfRange(node));
+            }
+            ast.nodes.push(kind);
+            ast.spans.push(Span::new(BytePos(lo), BytePos(hi)));
+            ast.children_start.push(start);
+            ast.children_end.push(end);
+            ast.parents.push(parent);
+        }
+        for _ in 0..children_count {
+            ast.children.push(next()?);
+        }
+        for _ in 0..next()? {
+            let node = next()?;
+            if node as usize >= node_count {
+                return Err(DecodeError::IndexOutOfRange(node));
+            }
+            let (lo, hi) = (next()?, next()?);
+            ast.attach_doc_comment(node, Span::new(BytePos(lo), BytePos(hi)));
+        }
+
+        let leftover = words.len() * 4 + trailing;
+        if leftover != 0 {
+            return Err(DecodeError::TrailingBytes(leftover));
+        }
+        if root as usize >= node_count {
+            return Err(DecodeError::InvalidRoot(root));
+        }
+        Ok(ast)
+    }
+}
@@ -1,6 +1,9 @@
+use std::collections::BTreeMap;
 use std::fmt::Display;
 
 pub mod ast_visitor;
+pub mod encode;
+pub use encode::DecodeError;
 use rustc_span::{SourceMap, Span};
 
 /// Node index type, for future extensibility
@@ -18,25 +21,42 @@ pub struct Ast {
     pub nodes: Vec<NodeKind>,
     pub spans: Vec<Span>,
     pub children_start: Vec<NodeIndex>,
+    // children[children_start[i]..children_end[i]] 即节点 i 自己的子节点槽位
+    pub children_end: Vec<NodeIndex>,
+    // parents[i] 为节点 i 的父节点, 0 表示没有父节点 (根节点或尚未被引用)
+    pub parents: Vec<NodeIndex>,
 
     pub children: Vec<NodeIndex>,
 
+    // 附着在条目节点上的文档注释 (`///` 或 `/** */`), 值为注释本身的 span
+    pub doc_comments: BTreeMap<NodeIndex, Span>,
+
     pub root: NodeIndex, // 根节点索引
 }
 
 impl Ast {
     pub fn new() -> Self {
+        Self::with_capacity(0, 0)
+    }
+
+    /// 预留 `nodes` 个节点和 `children` 个子节点槽位的空间 (均不含保留的 0 号位)
+    pub fn with_capacity(nodes: usize, children: usize) -> Self {
         let mut result = Ast {
-            nodes: Vec::new(),
-            spans: Vec::new(),
-            children_start: Vec::new(),
-            children: Vec::new(),
+            nodes: Vec::with_capacity(nodes + 1),
+            spans: Vec::with_capacity(nodes + 1),
+            children_start: Vec::with_capacity(nodes + 1),
+            children_end: Vec::with_capacity(nodes + 1),
+            parents: Vec::with_capacity(nodes + 1),
+            children: Vec::with_capacity(children + 1),
+            doc_comments: BTreeMap::new(),
             root: 0,
         };
 
         result.nodes.push(NodeKind::Invalid);
         result.spans.push(Span::default());
         result.children_start.push(0);
+        result.children_end.push(0);
+        result.parents.push(0);
         result.children.push(0); // 添加一个无效的子节点索引
         result
     }
@@ -68,19 +88,40 @@ impl Ast {
         self.nodes.push(descriptor.kind);
         self.spans.push(descriptor.span);
         self.children_start.push(children_start_pos);
+        self.children_end.push(self.children.len() as NodeIndex);
+        self.parents.push(0);
+        // 子节点总是先于父节点创建; 列表中的子节点记到本节点而不是长度槽位上
+        let Ast {
+            children, parents, ..
+        } = self;
+        let slots = descriptor.kind.node_type().slots();
+        let values = &children[children_start_pos as usize..];
+        for child in slot_nodes(children, slots, values) {
+            if let Some(parent) = parents.get_mut(child as usize) {
+                *parent = node_index;
+            }
+        }
         node_index
     }
 
-    /// 获取节点的子节点，切片的长度不代表子节点的数量，子节点的真正数量由节点类型决定，
-    /// 检查children长度的行为没有意义。
-    /// 子节点位于切片的前 N 个元素
+    /// 释放多余的容量, 在整个文件解析完成后调用
+    pub fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+        self.spans.shrink_to_fit();
+        self.children_start.shrink_to_fit();
+        self.children_end.shrink_to_fit();
+        self.parents.shrink_to_fit();
+        self.children.shrink_to_fit();
+    }
+
+    /// 获取节点的子节点槽位，切片只包含属于该节点的槽位。
+    /// 多子节点槽位存放的是长度索引，用 [`Ast::get_multi_child_slice`] 展开。
     pub fn get_children(&self, node_index: NodeIndex) -> &[NodeIndex] {
         if node_index == 0 || node_index > self.nodes.len() as NodeIndex {
             return &[];
         }
         let start = self.children_start[node_index as usize] as usize;
-        // 计算结束位置
-        let end = self.children.len();
+        let end = self.children_end[node_index as usize] as usize;
         &self.children[start..end]
     }
 
@@ -103,6 +144,24 @@ impl Ast {
         ))
     }
 
+    /// 获取节点的父节点。一个节点被多个节点引用时, 记录最后创建的那个
+    pub fn parent_of(&self, node: NodeIndex) -> Option<NodeIndex> {
+        match self.parents.get(node as usize) {
+            Some(&parent) if node != 0 && parent != 0 => Some(parent),
+            _ => None,
+        }
+    }
+
+    /// 把 `span` 处的文档注释附着到节点上, 覆盖之前附着的注释
+    pub fn attach_doc_comment(&mut self, node: NodeIndex, span: Span) {
+        self.doc_comments.insert(node, span);
+    }
+
+    /// 获取附着在节点上的文档注释的 span
+    pub fn doc_comment_span(&self, node: NodeIndex) -> Option<Span> {
+        self.doc_comments.get(&node).copied()
+    }
+
     /// 获取节点的 span
     pub fn get_span(&self, node_index: NodeIndex) -> Option<Span> {
         if node_index == 0 || node_index > self.nodes.len() as NodeIndex {
@@ -173,7 +232,7 @@ impl Child {
 }
 
 #[repr(u32)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum NodeKind {
     Invalid = 0,
 
@@ -252,8 +311,8 @@ pub enum NodeKind {
     TypedWith,        // a, b
     Subtype,          // a, b (TODO: needs `<:` token)
     TraitBound,       // a, b
-    FieldMethodBound, // TODO
-    DeclarationBound, // TODO
+    FieldMethodBound, // no children yet, see `node_type`
+    DeclarationBound, // no children yet, see `node_type`
 
     Projection,  // a, b (expr . id)
     TakeView,    // a, b (expr ' id)
@@ -349,12 +408,13 @@ pub enum NodeKind {
     RangeFromToInclusivePattern,  // a, b
     PropertyPattern,              // a, b (id: pattern)
     ListRestPattern,              // a (...id)
+    StructRestPattern,            // (...) ignores the remaining fields
     StructPattern,                // N
     ListPattern,                  // N
     TuplePattern,                 // N
-    BitVecBinPattern,             // TODO
-    BitVecOctPattern,             // TODO
-    BitVecHexPattern,             // TODO
+    BitVecBinPattern,             // N (Int | TypeBoundDeclClause | ExprAsPattern)
+    BitVecOctPattern,             // N (Int | TypeBoundDeclClause | ExprAsPattern)
+    BitVecHexPattern,             // N (Int | TypeBoundDeclClause | ExprAsPattern)
     AsyncPattern,                 // a
     NotPattern,                   // a
     TypeBindPattern,              // a
@@ -452,8 +512,8 @@ pub enum NodeKind {
     Invariant, // a, b
     // decreases(: label_id)? expr
     Decreases, // a, b
-    // TODO
-    Outcomes, // TODO
+    // outcomes outcome_case+ (syntax not settled, see `node_type`)
+    Outcomes, // no children yet
     // requires(: label_id)? expr
     Requires, // a, b
     // ensures(: label_id)? expr
@@ -519,6 +579,8 @@ pub enum NodeKind {
     UnsafeBlock, // a
     // comptime { statement* }
     ComptimeBlock, // a
+    // quote { statement* }
+    QuoteBlock, // a
 
     // others
     FileScope, // N
@@ -630,7 +692,7 @@ impl NodeKind {
             // No children
             Invalid | Id | Str | Int | Real | Char | Bool | Unit | Symbol | Wildcard
             | SelfLower | SelfCap | Null | Undefined | SelfParam | SelfRefParam | ItselfParam
-            | ItselfRefParam | TypeDeclClause | RangeFull => NodeType::NoChild,
+            | ItselfRefParam | TypeDeclClause | RangeFull | StructRestPattern => NodeType::NoChild,
 
             // Single child (a)
             BoolNot
@@ -667,6 +729,7 @@ impl NodeKind {
             | AsyncBlock
             | UnsafeBlock
             | ComptimeBlock
+            | QuoteBlock
             | ComptimeParam
             | ErrorParam
             | CatchParam
@@ -742,6 +805,8 @@ impl NodeKind {
             | TraitBoundDeclClause
             | ProjectionPath
             | PathAsBind
+            | IfGuardPattern
+            | AsBindPattern
             | TypeBoundParam
             | TraitBoundParam
             | VarargParam
@@ -766,10 +831,8 @@ impl NodeKind {
             | Ensures => NodeType::DoubleChildren,
 
             // Triple children (a, b, c)
-            ConstDecl | ConstDef | LetDecl | IfStatement | WhileStatement | IfGuardPattern
-            | AndIsPattern | AsBindPattern | OptionalDeclClause | OptionalParam | StructField => {
-                NodeType::TripleChildren
-            }
+            ConstDecl | ConstDef | LetDecl | IfStatement | WhileStatement | AndIsPattern
+            | OptionalDeclClause | OptionalParam | StructField => NodeType::TripleChildren,
 
             // Quadruple children (a, b, c, d)
             IfIsMatch | WhileIsMatch | ForStatement => NodeType::QuadrupleChildren,
@@ -815,23 +878,103 @@ impl NodeKind {
             FnType => NodeType::FnTypeChildren,     // flags_u32, abi_node, N
             ProjectionMultiPath => NodeType::SingleWithMultiChildren, // a, N
 
-            // TODO items
-            FieldMethodBound | DeclarationBound | Outcomes => NodeType::SingleChild,
+            // The parser does not build these yet and their syntax is still
+            // open, so they carry no children: a node of these kinds is only
+            // its span. Give them a real layout together with the parser rule.
+            FieldMethodBound | DeclarationBound | Outcomes => NodeType::NoChild,
         }
     }
 }
 
 impl Ast {
     pub fn get_multi_child_slice(&self, slice_len_index: NodeIndex) -> Option<&[NodeIndex]> {
-        if slice_len_index == 0 || slice_len_index >= self.children.len() as NodeIndex {
-            return None;
+        multi_child_slice(&self.children, slice_len_index)
+    }
+}
+
+/// The node list stored at `slice_len_index` of `children`: a length
+/// followed by that many node indexes.
+fn multi_child_slice(children: &[NodeIndex], slice_len_index: NodeIndex) -> Option<&[NodeIndex]> {
+    if slice_len_index == 0 || slice_len_index >= children.len() as NodeIndex {
+        return None;
+    }
+    let slice_len_index = slice_len_index as usize;
+    let count = children[slice_len_index] as usize;
+    children.get(slice_len_index + 1..slice_len_index + count + 1)
+}
+
+/// What a child slot of a node holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChildSlot {
+    /// A single child node.
+    Node,
+    /// The length index of a list of child nodes.
+    Multi,
+    /// A raw value that is not a node index.
+    Raw,
+}
+
+impl NodeType {
+    /// The child slots of a node of this type, in storage order.
+    fn slots(self) -> &'static [ChildSlot] {
+        use ChildSlot::*;
+        match self {
+            NodeType::NoChild => &[],
+            NodeType::SingleChild => &[Node],
+            NodeType::DoubleChildren => &[Node, Node],
+            NodeType::TripleChildren => &[Node, Node, Node],
+            NodeType::QuadrupleChildren => &[Node, Node, Node, Node],
+            NodeType::MultiChildren => &[Multi],
+            NodeType::SingleWithMultiChildren => &[Node, Multi],
+            NodeType::DoubleWithMultiChildren => &[Node, Node, Multi],
+            NodeType::TripleWithMultiChildren => &[Node, Node, Node, Multi],
+            NodeType::FunctionDefChildren => &[Node, Multi, Node, Node, Multi, Node],
+            NodeType::NormalFormDefChildren | NodeType::AlgebraicEffectChildren => {
+                &[Node, Multi, Node, Multi, Node]
+            }
+            NodeType::TypeDefChildren => &[Node, Multi, Node],
+            NodeType::TraitDefChildren
+            | NodeType::ImplTraitDefChildren
+            | NodeType::ExtendTraitDefChildren => &[Node, Node, Multi, Node],
+            NodeType::TypeAliasChildren => &[Node, Multi, Node],
+            NodeType::AssocDeclChildren => &[Node, Multi, Node, Node, Multi],
+            NodeType::FnTypeChildren => &[Raw, Node, Multi],
         }
-        let slice_len_index = slice_len_index as usize;
-        let count = self.children[slice_len_index] as usize;
-        Some(&self.children[slice_len_index + 1..slice_len_index + count + 1])
     }
 }
 
+impl Ast {
+    /// The child nodes of `node_index` in storage order, with node lists
+    /// flattened in place and absent (`0`) children left out.
+    pub fn child_nodes(&self, node_index: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+        let slots = self
+            .get_node_kind(node_index)
+            .map_or(&[][..], |kind| kind.node_type().slots());
+        slot_nodes(&self.children, slots, self.get_children(node_index))
+    }
+}
+
+/// The nodes held in the child slots `values` laid out as `slots`, with the
+/// node lists they point to in `children` flattened in place.
+fn slot_nodes<'a>(
+    children: &'a [NodeIndex],
+    slots: &'static [ChildSlot],
+    values: &'a [NodeIndex],
+) -> impl Iterator<Item = NodeIndex> + 'a {
+    slots
+        .iter()
+        .zip(values)
+        .flat_map(move |(slot, value)| {
+            let nodes = match slot {
+                ChildSlot::Node => std::slice::from_ref(value),
+                ChildSlot::Multi => multi_child_slice(children, *value).unwrap_or(&[]),
+                ChildSlot::Raw => &[],
+            };
+            nodes.iter().copied()
+        })
+        .filter(|&node| node != 0)
+}
+
 impl Display for NodeKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
@@ -849,7 +992,9 @@ impl Ast {
             if let Some(content) = &source_file.src {
                 let byte_start = (span.lo().0 - source_file.start_pos.0) as usize;
                 let byte_end = (span.hi().0 - source_file.start_pos.0) as usize;
-                Some(content[byte_start..byte_end].trim().to_string())
+                content
+                    .get(byte_start..byte_end)
+                    .map(|text| text.trim().to_string())
             } else {
                 eprintln!("Error: Source file content not available");
                 None
@@ -859,396 +1004,636 @@ impl Ast {
         }
     }
 
-    // TODO: 记得改进unwarp
-    pub fn dump_to_s_expression(&self, node_index: NodeIndex, source_map: &SourceMap) -> String {
+    /// The text of the doc comment attached to `node`, with the `///`,
+    /// `/**` and `*/` markers removed and one line per source line. `None`
+    /// when the comment's span no longer lies within its file's text.
+    pub fn doc_comment(&self, node: NodeIndex, source_map: &SourceMap) -> Option<String> {
+        let span = self.doc_comment_span(node)?;
+        let source_file = source_map.lookup_source_file(span.lo());
+        let content = source_file.src.as_ref()?;
+        let byte_start = (span.lo().0 - source_file.start_pos.0) as usize;
+        let byte_end = (span.hi().0 - source_file.start_pos.0) as usize;
+        let raw = content.get(byte_start..byte_end)?;
+
+        let lines: Vec<&str> = if let Some(block) = raw.strip_prefix("/**") {
+            block
+                .strip_suffix("*/")
+                .unwrap_or(block)
+                .lines()
+                .map(|line| {
+                    let line = line.trim();
+                    line.strip_prefix('*').unwrap_or(line).trim()
+                })
+                .collect()
+        } else {
+            raw.lines()
+                .map(|line| {
+                    let line = line.trim();
+                    let line = line.strip_prefix("///").unwrap_or(line);
+                    line.strip_prefix(' ').unwrap_or(line)
+                })
+                .collect()
+        };
+        let text = lines.join("\n");
+        Some(text.trim_matches('\n').to_string())
+    }
+
+    /// Dump the subtree rooted at `node_index` as an S-expression.
+    ///
+    /// A malformed or partially built subtree is reported as a [`DumpError`]
+    /// naming the offending node rather than a panic.
+    pub fn dump_to_s_expression(
+        &self,
+        node_index: NodeIndex,
+        source_map: &SourceMap,
+    ) -> Result<String, DumpError> {
         if node_index == 0 {
-            return "(<invalid node>)".to_string();
+            return Ok("(<invalid node>)".to_string());
         }
-        if let Some(kind) = self.get_node_kind(node_index) {
-            match kind.node_type() {
-                NodeType::NoChild => {
-                    let source_file =
-                        source_map.lookup_source_file(self.get_span(node_index).unwrap().lo());
-
-                    let source_content = match &source_file.src {
-                        Some(content) => content.as_str(),
-                        None => {
-                            eprintln!("Error: Source file content not available");
-                            return "<invalid source>".to_string();
-                        }
-                    };
-
-                    let byte_start = (self.get_span(node_index).unwrap().lo().0
-                        - source_file.start_pos.0) as usize;
-                    let byte_end = (self.get_span(node_index).unwrap().hi().0
-                        - source_file.start_pos.0) as usize;
-                    format!("({} {})", kind, source_content[byte_start..byte_end].trim())
-                }
-                NodeType::SingleChild => {
-                    let children = self.get_children(node_index);
-                    let child_index = children[0];
-                    format!(
-                        "({} {})",
-                        kind,
-                        self.dump_to_s_expression(child_index, source_map)
-                    )
-                }
-                NodeType::DoubleChildren => {
-                    let children = self.get_children(node_index);
-                    format!(
-                        "({} {} {})",
-                        kind,
-                        self.dump_to_s_expression(children[0], source_map),
-                        self.dump_to_s_expression(children[1], source_map)
-                    )
-                }
-                NodeType::TripleChildren => {
-                    let children = self.get_children(node_index);
-                    format!(
-                        "({} {} {} {})",
-                        kind,
-                        self.dump_to_s_expression(children[0], source_map),
-                        self.dump_to_s_expression(children[1], source_map),
-                        self.dump_to_s_expression(children[2], source_map)
-                    )
-                }
-                NodeType::QuadrupleChildren => {
-                    let children = self.get_children(node_index);
-                    format!(
-                        "({} {} {} {} {})",
-                        kind,
-                        self.dump_to_s_expression(children[0], source_map),
-                        self.dump_to_s_expression(children[1], source_map),
-                        self.dump_to_s_expression(children[2], source_map),
-                        self.dump_to_s_expression(children[3], source_map)
-                    )
-                }
-                NodeType::MultiChildren => {
-                    let elements = self.get_children(node_index)[0];
-                    let child_nodes = self.get_multi_child_slice(elements).unwrap();
-                    let children_str = child_nodes
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    format!("({} {})", kind, children_str)
-                }
-                NodeType::SingleWithMultiChildren => {
-                    let children = self.get_children(node_index);
-                    let first_child = children[0];
-                    let multi_children_node = children[1];
-                    let multi_children = self.get_multi_child_slice(multi_children_node).unwrap();
-                    let multi_children_str = multi_children
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    format!(
-                        "({} {} {})",
-                        kind,
-                        self.dump_to_s_expression(first_child, source_map),
-                        multi_children_str
-                    )
-                }
-                NodeType::DoubleWithMultiChildren => {
-                    let children = self.get_children(node_index);
-                    let first_child = children[0];
-                    let second_child = children[1];
-                    let multi_children_node = children[2];
-                    let multi_children = self.get_multi_child_slice(multi_children_node).unwrap();
-                    let multi_children_str = multi_children
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    format!(
-                        "({} {} {} {})",
-                        kind,
-                        self.dump_to_s_expression(first_child, source_map),
-                        self.dump_to_s_expression(second_child, source_map),
-                        multi_children_str
-                    )
-                }
-                NodeType::TripleWithMultiChildren => {
-                    let children = self.get_children(node_index);
-                    let first_child = children[0];
-                    let second_child = children[1];
-                    let third_child = children[2];
-                    let multi_children_node = children[3];
-                    let multi_children = self.get_multi_child_slice(multi_children_node).unwrap();
-                    let multi_children_str = multi_children
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    format!(
-                        "({} {} {} {} {})",
-                        kind,
-                        self.dump_to_s_expression(first_child, source_map),
-                        self.dump_to_s_expression(second_child, source_map),
-                        self.dump_to_s_expression(third_child, source_map),
-                        multi_children_str
-                    )
-                }
+        let Some(kind) = self.get_node_kind(node_index) else {
+            return Err(DumpError::InvalidNode(node_index));
+        };
+        Ok(match kind.node_type() {
+            NodeType::NoChild => {
+                let span = self
+                    .get_span(node_index)
+                    .ok_or(DumpError::MissingSpan(node_index))?;
+                let source_file = source_map.lookup_source_file(span.lo());
+                let source_content = source_file
+                    .src
+                    .as_ref()
+                    .ok_or(DumpError::MissingSource(node_index))?;
+
+                let byte_start = span.lo().0.wrapping_sub(source_file.start_pos.0) as usize;
+                let byte_end = span.hi().0.wrapping_sub(source_file.start_pos.0) as usize;
+                let text = source_content
+                    .get(byte_start..byte_end)
+                    .ok_or(DumpError::SpanOutOfSource(node_index))?;
+                format!("({} {})", kind, text.trim())
+            }
+            NodeType::SingleChild => {
+                let children = self.dump_children(node_index, kind)?;
+                let child_index = children[0];
+                format!(
+                    "({} {})",
+                    kind,
+                    self.dump_to_s_expression(child_index, source_map)?
+                )
+            }
+            NodeType::DoubleChildren => {
+                let children = self.dump_children(node_index, kind)?;
+                format!(
+                    "({} {} {})",
+                    kind,
+                    self.dump_to_s_expression(children[0], source_map)?,
+                    self.dump_to_s_expression(children[1], source_map)?
+                )
+            }
+            NodeType::TripleChildren => {
+                let children = self.dump_children(node_index, kind)?;
+                format!(
+                    "({} {} {} {})",
+                    kind,
+                    self.dump_to_s_expression(children[0], source_map)?,
+                    self.dump_to_s_expression(children[1], source_map)?,
+                    self.dump_to_s_expression(children[2], source_map)?
+                )
+            }
+            NodeType::QuadrupleChildren => {
+                let children = self.dump_children(node_index, kind)?;
+                format!(
+                    "({} {} {} {} {})",
+                    kind,
+                    self.dump_to_s_expression(children[0], source_map)?,
+                    self.dump_to_s_expression(children[1], source_map)?,
+                    self.dump_to_s_expression(children[2], source_map)?,
+                    self.dump_to_s_expression(children[3], source_map)?
+                )
+            }
+            NodeType::MultiChildren => {
+                let elements = self.dump_children(node_index, kind)?[0];
+                let children_str = self.dump_child_list(node_index, elements, source_map)?;
+                format!("({} {})", kind, children_str)
+            }
+            NodeType::SingleWithMultiChildren => {
+                let children = self.dump_children(node_index, kind)?;
+                let first_child = children[0];
+                let multi_children_node = children[1];
+                let multi_children_str =
+                    self.dump_child_list(node_index, multi_children_node, source_map)?;
+                format!(
+                    "({} {} {})",
+                    kind,
+                    self.dump_to_s_expression(first_child, source_map)?,
+                    multi_children_str
+                )
+            }
+            NodeType::DoubleWithMultiChildren => {
+                let children = self.dump_children(node_index, kind)?;
+                let first_child = children[0];
+                let second_child = children[1];
+                let multi_children_node = children[2];
+                let multi_children_str =
+                    self.dump_child_list(node_index, multi_children_node, source_map)?;
+                format!(
+                    "({} {} {} {})",
+                    kind,
+                    self.dump_to_s_expression(first_child, source_map)?,
+                    self.dump_to_s_expression(second_child, source_map)?,
+                    multi_children_str
+                )
+            }
+            NodeType::TripleWithMultiChildren => {
+                let children = self.dump_children(node_index, kind)?;
+                let first_child = children[0];
+                let second_child = children[1];
+                let third_child = children[2];
+                let multi_children_node = children[3];
+                let multi_children_str =
+                    self.dump_child_list(node_index, multi_children_node, source_map)?;
+                format!(
+                    "({} {} {} {} {})",
+                    kind,
+                    self.dump_to_s_expression(first_child, source_map)?,
+                    self.dump_to_s_expression(second_child, source_map)?,
+                    self.dump_to_s_expression(third_child, source_map)?,
+                    multi_children_str
+                )
+            }
 
-                // Complex children patterns
-                NodeType::FunctionDefChildren => {
-                    // a, N, b, c, N, d (id, params, return_type, handles_effect, clauses, body)
-                    let children = self.get_children(node_index);
-                    let id = children[0];
-                    let params_node = children[1];
-                    let return_type = children[2];
-                    let handles_effect = children[3];
-                    let clauses_node = children[4];
-                    let body = children[5];
-
-                    let params = self.get_multi_child_slice(params_node).unwrap();
-                    let params_str = params
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    let clauses = self.get_multi_child_slice(clauses_node).unwrap();
-                    let clauses_str = clauses
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-
-                    format!(
-                        "({} {} [{}] {} {} [{}] {})",
-                        kind,
-                        self.dump_to_s_expression(id, source_map),
-                        params_str,
-                        self.dump_to_s_expression(return_type, source_map),
-                        self.dump_to_s_expression(handles_effect, source_map),
-                        clauses_str,
-                        self.dump_to_s_expression(body, source_map)
-                    )
-                }
+            // Complex children patterns
+            NodeType::FunctionDefChildren => {
+                // a, N, b, c, N, d (id, params, return_type, handles_effect, clauses, body)
+                let children = self.dump_children(node_index, kind)?;
+                let id = children[0];
+                let params_node = children[1];
+                let return_type = children[2];
+                let handles_effect = children[3];
+                let clauses_node = children[4];
+                let body = children[5];
+
+                let params_str = self.dump_child_list(node_index, params_node, source_map)?;
+                let clauses_str = self.dump_child_list(node_index, clauses_node, source_map)?;
+
+                format!(
+                    "({} {} [{}] {} {} [{}] {})",
+                    kind,
+                    self.dump_to_s_expression(id, source_map)?,
+                    params_str,
+                    self.dump_to_s_expression(return_type, source_map)?,
+                    self.dump_to_s_expression(handles_effect, source_map)?,
+                    clauses_str,
+                    self.dump_to_s_expression(body, source_map)?
+                )
+            }
 
-                NodeType::NormalFormDefChildren => {
-                    // a, N, b, N, c (id, type_params, return_type, clauses, body)
-                    let children = self.get_children(node_index);
-                    let id = children[0];
-                    let type_params_node = children[1];
-                    let return_type = children[2];
-                    let clauses_node = children[3];
-                    let body = children[4];
-
-                    let type_params = self.get_multi_child_slice(type_params_node).unwrap();
-                    let type_params_str = type_params
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    let clauses = self.get_multi_child_slice(clauses_node).unwrap();
-                    let clauses_str = clauses
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-
-                    format!(
-                        "({} {} <{}> {} [{}] {})",
-                        kind,
-                        self.dump_to_s_expression(id, source_map),
-                        type_params_str,
-                        self.dump_to_s_expression(return_type, source_map),
-                        clauses_str,
-                        self.dump_to_s_expression(body, source_map)
-                    )
-                }
+            NodeType::NormalFormDefChildren => {
+                // a, N, b, N, c (id, type_params, return_type, clauses, body)
+                let children = self.dump_children(node_index, kind)?;
+                let id = children[0];
+                let type_params_node = children[1];
+                let return_type = children[2];
+                let clauses_node = children[3];
+                let body = children[4];
+
+                let type_params_str =
+                    self.dump_child_list(node_index, type_params_node, source_map)?;
+                let clauses_str = self.dump_child_list(node_index, clauses_node, source_map)?;
+
+                format!(
+                    "({} {} <{}> {} [{}] {})",
+                    kind,
+                    self.dump_to_s_expression(id, source_map)?,
+                    type_params_str,
+                    self.dump_to_s_expression(return_type, source_map)?,
+                    clauses_str,
+                    self.dump_to_s_expression(body, source_map)?
+                )
+            }
 
-                NodeType::AlgebraicEffectChildren => {
-                    // a, N, b, N, c (id, params, return_type, clauses, body)
-                    let children = self.get_children(node_index);
-                    let id = children[0];
-                    let params_node = children[1];
-                    let return_type = children[2];
-                    let clauses_node = children[3];
-                    let body = children[4];
-
-                    let params = self.get_multi_child_slice(params_node).unwrap();
-                    let params_str = params
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    let clauses = self.get_multi_child_slice(clauses_node).unwrap();
-                    let clauses_str = clauses
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-
-                    format!(
-                        "({} {} [{}] {} [{}] {})",
-                        kind,
-                        self.dump_to_s_expression(id, source_map),
-                        params_str,
-                        self.dump_to_s_expression(return_type, source_map),
-                        clauses_str,
-                        self.dump_to_s_expression(body, source_map)
-                    )
-                }
+            NodeType::AlgebraicEffectChildren => {
+                // a, N, b, N, c (id, params, return_type, clauses, body)
+                let children = self.dump_children(node_index, kind)?;
+                let id = children[0];
+                let params_node = children[1];
+                let return_type = children[2];
+                let clauses_node = children[3];
+                let body = children[4];
+
+                let params_str = self.dump_child_list(node_index, params_node, source_map)?;
+                let clauses_str = self.dump_child_list(node_index, clauses_node, source_map)?;
+
+                format!(
+                    "({} {} [{}] {} [{}] {})",
+                    kind,
+                    self.dump_to_s_expression(id, source_map)?,
+                    params_str,
+                    self.dump_to_s_expression(return_type, source_map)?,
+                    clauses_str,
+                    self.dump_to_s_expression(body, source_map)?
+                )
+            }
 
-                NodeType::TypeDefChildren => {
-                    // a, N, b (id, clauses, body)
-                    let children = self.get_children(node_index);
-                    let id = children[0];
-                    let clauses_node = children[1];
-                    let body = children[2];
-
-                    let clauses = self.get_multi_child_slice(clauses_node).unwrap();
-                    let clauses_str = clauses
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-
-                    format!(
-                        "({} {} [{}] {})",
-                        kind,
-                        self.dump_to_s_expression(id, source_map),
-                        clauses_str,
-                        self.dump_to_s_expression(body, source_map)
-                    )
-                }
+            NodeType::TypeDefChildren => {
+                // a, N, b (id, clauses, body)
+                let children = self.dump_children(node_index, kind)?;
+                let id = children[0];
+                let clauses_node = children[1];
+                let body = children[2];
+
+                let clauses_str = self.dump_child_list(node_index, clauses_node, source_map)?;
+
+                format!(
+                    "({} {} [{}] {})",
+                    kind,
+                    self.dump_to_s_expression(id, source_map)?,
+                    clauses_str,
+                    self.dump_to_s_expression(body, source_map)?
+                )
+            }
 
-                NodeType::TraitDefChildren => {
-                    // a, b, N, c (id, super_trait, clauses, body)
-                    let children = self.get_children(node_index);
-                    let id = children[0];
-                    let super_trait = children[1];
-                    let clauses_node = children[2];
-                    let body = children[3];
-
-                    let clauses = self.get_multi_child_slice(clauses_node).unwrap();
-                    let clauses_str = clauses
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-
-                    format!(
-                        "({} {} {} [{}] {})",
-                        kind,
-                        self.dump_to_s_expression(id, source_map),
-                        self.dump_to_s_expression(super_trait, source_map),
-                        clauses_str,
-                        self.dump_to_s_expression(body, source_map)
-                    )
-                }
+            NodeType::TraitDefChildren => {
+                // a, b, N, c (id, super_trait, clauses, body)
+                let children = self.dump_children(node_index, kind)?;
+                let id = children[0];
+                let super_trait = children[1];
+                let clauses_node = children[2];
+                let body = children[3];
+
+                let clauses_str = self.dump_child_list(node_index, clauses_node, source_map)?;
+
+                format!(
+                    "({} {} {} [{}] {})",
+                    kind,
+                    self.dump_to_s_expression(id, source_map)?,
+                    self.dump_to_s_expression(super_trait, source_map)?,
+                    clauses_str,
+                    self.dump_to_s_expression(body, source_map)?
+                )
+            }
 
-                NodeType::ImplTraitDefChildren | NodeType::ExtendTraitDefChildren => {
-                    // a, b, N, c (trait, type, clauses, body)
-                    let children = self.get_children(node_index);
-                    let trait_expr = children[0];
-                    let type_expr = children[1];
-                    let clauses_node = children[2];
-                    let body = children[3];
-
-                    let clauses = self.get_multi_child_slice(clauses_node).unwrap();
-                    let clauses_str = clauses
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-
-                    format!(
-                        "({} {} {} [{}] {})",
-                        kind,
-                        self.dump_to_s_expression(trait_expr, source_map),
-                        self.dump_to_s_expression(type_expr, source_map),
-                        clauses_str,
-                        self.dump_to_s_expression(body, source_map)
-                    )
-                }
+            NodeType::ImplTraitDefChildren | NodeType::ExtendTraitDefChildren => {
+                // a, b, N, c (trait, type, clauses, body)
+                let children = self.dump_children(node_index, kind)?;
+                let trait_expr = children[0];
+                let type_expr = children[1];
+                let clauses_node = children[2];
+                let body = children[3];
+
+                let clauses_str = self.dump_child_list(node_index, clauses_node, source_map)?;
+
+                format!(
+                    "({} {} {} [{}] {})",
+                    kind,
+                    self.dump_to_s_expression(trait_expr, source_map)?,
+                    self.dump_to_s_expression(type_expr, source_map)?,
+                    clauses_str,
+                    self.dump_to_s_expression(body, source_map)?
+                )
+            }
 
-                NodeType::TypeAliasChildren => {
-                    // a, N, b (id, type_params, type)
-                    let children = self.get_children(node_index);
-                    let id = children[0];
-                    let type_params_node = children[1];
-                    let type_expr = children[2];
-
-                    let type_params = self.get_multi_child_slice(type_params_node).unwrap();
-                    let type_params_str = type_params
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-
-                    format!(
-                        "({} {} <{}> {})",
-                        kind,
-                        self.dump_to_s_expression(id, source_map),
-                        type_params_str,
-                        self.dump_to_s_expression(type_expr, source_map)
-                    )
-                }
+            NodeType::TypeAliasChildren => {
+                // a, N, b (id, type_params, type)
+                let children = self.dump_children(node_index, kind)?;
+                let id = children[0];
+                let type_params_node = children[1];
+                let type_expr = children[2];
+
+                let type_params_str =
+                    self.dump_child_list(node_index, type_params_node, source_map)?;
+
+                format!(
+                    "({} {} <{}> {})",
+                    kind,
+                    self.dump_to_s_expression(id, source_map)?,
+                    type_params_str,
+                    self.dump_to_s_expression(type_expr, source_map)?
+                )
+            }
 
-                NodeType::FnTypeChildren => {
-                    // flags_u32, abi_node, N (modifier_flags, abi_str_node, parameter_types)
-                    let children = self.get_children(node_index);
-                    let flags = children[0]; // raw u32 bitmask, NOT a node index
-                    let abi_node = children[1];
-                    let params_node = children[2];
-
-                    let mods_str = fn_mod_flags_to_string(flags);
-                    let params = self.get_multi_child_slice(params_node).unwrap();
-                    let params_str = params
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-
-                    if abi_node != 0 {
-                        let abi = self.dump_to_s_expression(abi_node, source_map);
-                        format!("(FnType [{}] {} [{}])", mods_str, abi, params_str)
-                    } else if !mods_str.is_empty() {
-                        format!("(FnType [{}] [{}])", mods_str, params_str)
-                    } else {
-                        format!("(FnType [{}])", params_str)
-                    }
+            NodeType::FnTypeChildren => {
+                // flags_u32, abi_node, N (modifier_flags, abi_str_node, parameter_types)
+                let children = self.dump_children(node_index, kind)?;
+                let flags = children[0]; // raw u32 bitmask, NOT a node index
+                let abi_node = children[1];
+                let params_node = children[2];
+
+                let mods_str = fn_mod_flags_to_string(flags);
+                let params_str = self.dump_child_list(node_index, params_node, source_map)?;
+
+                if abi_node != 0 {
+                    let abi = self.dump_to_s_expression(abi_node, source_map)?;
+                    format!("(FnType [{}] {} [{}])", mods_str, abi, params_str)
+                } else if !mods_str.is_empty() {
+                    format!("(FnType [{}] [{}])", mods_str, params_str)
+                } else {
+                    format!("(FnType [{}])", params_str)
                 }
+            }
 
-                NodeType::AssocDeclChildren => {
-                    // a, N, b, c, N (id, params, type, default, clauses)
-                    let children = self.get_children(node_index);
-                    let id = children[0];
-                    let params_node = children[1];
-                    let type_expr = children[2];
-                    let default_expr = children[3];
-                    let clauses_node = children[4];
-
-                    let params = self.get_multi_child_slice(params_node).unwrap();
-                    let params_str = params
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    let clauses = self.get_multi_child_slice(clauses_node).unwrap();
-                    let clauses_str = clauses
-                        .iter()
-                        .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
-                        .collect::<Vec<_>>()
-                        .join(" ");
-
-                    format!(
-                        "({} {} <{}> {} {} [{}])",
-                        kind,
-                        self.dump_to_s_expression(id, source_map),
-                        params_str,
-                        self.dump_to_s_expression(type_expr, source_map),
-                        self.dump_to_s_expression(default_expr, source_map),
-                        clauses_str
-                    )
-                }
+            NodeType::AssocDeclChildren => {
+                // a, N, b, c, N (id, params, type, default, clauses)
+                let children = self.dump_children(node_index, kind)?;
+                let id = children[0];
+                let params_node = children[1];
+                let type_expr = children[2];
+                let default_expr = children[3];
+                let clauses_node = children[4];
+
+                let params_str = self.dump_child_list(node_index, params_node, source_map)?;
+                let clauses_str = self.dump_child_list(node_index, clauses_node, source_map)?;
+
+                format!(
+                    "({} {} <{}> {} {} [{}])",
+                    kind,
+                    self.dump_to_s_expression(id, source_map)?,
+                    params_str,
+                    self.dump_to_s_expression(type_expr, source_map)?,
+                    self.dump_to_s_expression(default_expr, source_map)?,
+                    clauses_str
+                )
             }
-        } else {
-            format!("Invalid node index: {}", node_index)
+        })
+    }
+
+    /// The child slots of `node_index`, checked against what its kind
+    /// stores.
+    fn dump_children(
+        &self,
+        node_index: NodeIndex,
+        kind: NodeKind,
+    ) -> Result<&[NodeIndex], DumpError> {
+        let children = self.get_children(node_index);
+        let expected = kind.node_type().slots().len();
+        if children.len() < expected {
+            return Err(DumpError::MissingChildren {
+                node: node_index,
+                expected,
+                found: children.len(),
+            });
+        }
+        Ok(children)
+    }
+
+    /// Dump the child list stored at `list` of `node_index`, space
+    /// separated.
+    fn dump_child_list(
+        &self,
+        node_index: NodeIndex,
+        list: NodeIndex,
+        source_map: &SourceMap,
+    ) -> Result<String, DumpError> {
+        let children = self
+            .get_multi_child_slice(list)
+            .ok_or(DumpError::MissingChildList(node_index))?;
+        let dumped = children
+            .iter()
+            .map(|&child_index| self.dump_to_s_expression(child_index, source_map))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(dumped.join(" "))
+    }
+}
+
+/// Why [`Ast::dump_to_s_expression`] could not dump a subtree. Each variant
+/// carries the offending node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DumpError {
+    /// The index does not name a node of the tree.
+    InvalidNode(NodeIndex),
+    /// A leaf node has no span to take its text from.
+    MissingSpan(NodeIndex),
+    /// The source text of a leaf node is not loaded.
+    MissingSource(NodeIndex),
+    /// A leaf node's span lies outside its source text.
+    SpanOutOfSource(NodeIndex),
+    /// The node stores fewer child slots than its kind needs.
+    MissingChildren {
+        node: NodeIndex,
+        expected: usize,
+        found: usize,
+    },
+    /// A child list slot of the node does not point at a list.
+    MissingChildList(NodeIndex),
+}
+
+impl DumpError {
+    /// The node that could not be dumped.
+    pub fn node(&self) -> NodeIndex {
+        match *self {
+            DumpError::InvalidNode(node)
+            | DumpError::MissingSpan(node)
+            | DumpError::MissingSource(node)
+            | DumpError::SpanOutOfSource(node)
+            | DumpError::MissingChildren { node, .. }
+            | DumpError::MissingChildList(node) => node,
+        }
+    }
+}
+
+impl Display for DumpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DumpError::InvalidNode(node) => write!(f, "invalid node index: {}", node),
+            DumpError::MissingSpan(node) => write!(f, "node {} has no span", node),
+            DumpError::MissingSource(node) => {
+                write!(f, "source text of node {} is not available", node)
+            }
+            DumpError::SpanOutOfSource(node) => {
+                write!(f, "span of node {} lies outside its source text", node)
+            }
+            DumpError::MissingChildren {
+                node,
+                expected,
+                found,
+            } => write!(
+                f,
+                "node {} has {} child slot(s), expected {}",
+                node, found, expected
+            ),
+            DumpError::MissingChildList(node) => {
+                write!(f, "node {} has an invalid child list", node)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DumpError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sibling_nodes_report_only_their_own_children() {
+        let mut ast = Ast::new();
+        let span = Span::default();
+        let a = NodeBuilder::new(NodeKind::Id, span).build(&mut ast);
+        let b = NodeBuilder::new(NodeKind::Id, span).build(&mut ast);
+        let add = NodeBuilder::new(NodeKind::Add, span)
+            .add_single_child(a)
+            .add_single_child(b)
+            .build(&mut ast);
+        let list = NodeBuilder::new(NodeKind::ListOf, span)
+            .add_multiple_children(vec![a, b, add])
+            .build(&mut ast);
+        let neg = NodeBuilder::new(NodeKind::Negative, span)
+            .add_single_child(a)
+            .build(&mut ast);
+
+        assert_eq!(ast.get_children(a), &[] as &[NodeIndex]);
+        assert_eq!(ast.get_children(add), &[a, b]);
+        assert_eq!(ast.get_children(list).len(), 1);
+        assert_eq!(
+            ast.get_multi_child_slice(ast.get_children(list)[0]),
+            Some(&[a, b, add][..])
+        );
+        assert_eq!(ast.get_children(neg), &[a]);
+    }
+
+    #[test]
+    fn single_children_map_back_to_their_parent() {
+        let mut ast = Ast::new();
+        let span = Span::default();
+        let a = NodeBuilder::new(NodeKind::Id, span).build(&mut ast);
+        let b = NodeBuilder::new(NodeKind::Id, span).build(&mut ast);
+        let add = NodeBuilder::new(NodeKind::Add, span)
+            .add_single_child(a)
+            .add_single_child(b)
+            .build(&mut ast);
+        let neg = NodeBuilder::new(NodeKind::Negative, span)
+            .add_single_child(add)
+            .build(&mut ast);
+
+        assert_eq!(ast.parent_of(a), Some(add));
+        assert_eq!(ast.parent_of(b), Some(add));
+        assert_eq!(ast.parent_of(add), Some(neg));
+        assert_eq!(ast.parent_of(neg), None);
+        assert_eq!(ast.parent_of(0), None);
+        assert_eq!(ast.parent_of(99), None);
+    }
+
+    #[test]
+    fn list_children_map_back_to_the_node_not_the_count_slot() {
+        let mut ast = Ast::new();
+        let span = Span::default();
+        let callee = NodeBuilder::new(NodeKind::Id, span).build(&mut ast);
+        let x = NodeBuilder::new(NodeKind::Id, span).build(&mut ast);
+        let y = NodeBuilder::new(NodeKind::Int, span).build(&mut ast);
+        let call = NodeBuilder::new(NodeKind::Application, span)
+            .add_single_child(callee)
+            .add_multiple_children(vec![x, y])
+            .build(&mut ast);
+
+        assert_eq!(ast.parent_of(callee), Some(call));
+        assert_eq!(ast.parent_of(x), Some(call));
+        assert_eq!(ast.parent_of(y), Some(call));
+        assert_eq!(ast.parent_of(call), None);
+    }
+
+    #[test]
+    fn shrinking_keeps_nodes_and_children() {
+        let mut ast = Ast::with_capacity(64, 128);
+        let span = Span::default();
+        let callee = NodeBuilder::new(NodeKind::Id, span).build(&mut ast);
+        let x = NodeBuilder::new(NodeKind::Id, span).build(&mut ast);
+        let y = NodeBuilder::new(NodeKind::Int, span).build(&mut ast);
+        let call = NodeBuilder::new(NodeKind::Application, span)
+            .add_single_child(callee)
+            .add_multiple_children(vec![x, y])
+            .build(&mut ast);
+        ast.root = call;
+        let before = ast.clone();
+        let nodes_capacity = ast.nodes.capacity();
+        let children_capacity = ast.children.capacity();
+        assert!(nodes_capacity > ast.nodes.len());
+
+        ast.shrink_to_fit();
+        assert!(ast.nodes.capacity() < nodes_capacity);
+        assert!(ast.children.capacity() < children_capacity);
+        assert_eq!(ast.nodes, before.nodes);
+        assert_eq!(ast.spans, before.spans);
+        assert_eq!(ast.children, before.children);
+        assert_eq!(ast.children_start, before.children_start);
+        assert_eq!(ast.children_end, before.children_end);
+        assert_eq!(ast.parents, before.parents);
+        assert!(ast.child_nodes(call).eq([callee, x, y]));
+        assert_eq!(ast.parent_of(y), Some(call));
+    }
+
+    #[test]
+    fn dumping_a_malformed_tree_reports_the_offending_node() {
+        use rustc_span::{BytePos, FileName, source_map::FilePathMapping};
+
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(FileName::Custom("dump".into()), "a + b".into());
+        let at = |lo: u32, hi: u32| {
+            Span::new(file.start_pos + BytePos(lo), file.start_pos + BytePos(hi))
+        };
+
+        let mut ast = Ast::new();
+        let a = NodeBuilder::new(NodeKind::Id, at(0, 1)).build(&mut ast);
+        let b = NodeBuilder::new(NodeKind::Id, at(4, 5)).build(&mut ast);
+        let add = NodeBuilder::new(NodeKind::Add, at(0, 5))
+            .add_single_child(a)
+            .add_single_child(b)
+            .build(&mut ast);
+        assert_eq!(
+            ast.dump_to_s_expression(add, &source_map),
+            Ok("(Add (Id a) (Id b))".to_string())
+        );
+
+        let half = NodeBuilder::new(NodeKind::Add, at(0, 5))
+            .add_single_child(a)
+            .build(&mut ast);
+        let outside = NodeBuilder::new(NodeKind::Id, at(4, 50)).build(&mut ast);
+        let list = NodeBuilder::new(NodeKind::ListOf, at(0, 5))
+            .add_multiple_children(vec![a, outside])
+            .build(&mut ast);
+        assert_eq!(
+            ast.dump_to_s_expression(half, &source_map),
+            Err(DumpError::MissingChildren {
+                node: half,
+                expected: 2,
+                found: 1
+            })
+        );
+        let err = ast.dump_to_s_expression(list, &source_map).unwrap_err();
+        assert_eq!(err, DumpError::SpanOutOfSource(outside));
+        assert_eq!(err.node(), outside);
+        assert_eq!(
+            ast.dump_to_s_expression(99, &source_map),
+            Err(DumpError::InvalidNode(99))
+        );
+    }
+
+    #[test]
+    fn childless_bound_and_clause_kinds_dump_their_source() {
+        use rustc_span::{BytePos, FileName, source_map::FilePathMapping};
+
+        let src = "T: Len; T; outcomes case blur => x";
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(FileName::Custom("dump".into()), src.into());
+        let at = |lo: usize, hi: usize| {
+            Span::new(
+                file.start_pos + BytePos(lo as u32),
+                file.start_pos + BytePos(hi as u32),
+            )
+        };
+
+        let mut ast = Ast::new();
+        for (kind, text) in [
+            (NodeKind::FieldMethodBound, "T: Len"),
+            (NodeKind::DeclarationBound, "T;"),
+            (NodeKind::Outcomes, "outcomes case blur => x"),
+        ] {
+            assert_eq!(kind.node_type(), NodeType::NoChild);
+            let lo = src.find(text).unwrap();
+            let node = NodeBuilder::new(kind, at(lo, lo + text.len())).build(&mut ast);
+            assert_eq!(ast.get_children(node), &[] as &[NodeIndex]);
+            assert_eq!(
+                ast.dump_to_s_expression(node, &source_map),
+                Ok(format!("({} {})", kind, text))
+            );
         }
     }
 }
@@ -0,0 +1,144 @@
+//! Cyclic type aliases.
+//!
+//! A `typealias` stands for its right-hand side, so expanding an alias that
+//! refers back to itself, directly (`typealias A = *A`) or through other
+//! aliases (`typealias A = B; typealias B = A`), never ends. A cycle is
+//! reported once, at the alias of the cycle that is defined first, so later
+//! passes can expand the aliases of an error-free package without a depth
+//! limit.
+//!
+//! The names in an alias's right-hand side are resolved when it is lowered,
+//! and an edge goes to the alias each one resolves to, so aliases that share
+//! a name in different modules are not confused; nominal types break a
+//! cycle, so only references to other aliases are followed.
+
+use std::collections::HashMap;
+
+use diagnostic::FlurryError;
+use hir::{
+    ItemKind, OwnerId, Package,
+    common::{DefId, Ident},
+    expr::{Expr, ExprKind},
+};
+use rustc_span::BytePos;
+
+use crate::{LoweringContext, error::LoweringError};
+
+impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
+    /// Report every cycle among the package's type aliases.
+    pub(crate) fn check_alias_cycles(&self) {
+        let aliases: Vec<(OwnerId, Ident, &'hir Expr<'hir>)> = self
+            .package
+            .owners()
+            .filter_map(|(owner, info)| {
+                let item = info.node.as_item()?;
+                match item.kind {
+                    ItemKind::TypeAlias(ty) => Some((owner, item.ident.clone(), ty)),
+                    _ => None,
+                }
+            })
+            .collect();
+        let index: HashMap<OwnerId, usize> = aliases
+            .iter()
+            .enumerate()
+            .map(|(i, (owner, ..))| (*owner, i))
+            .collect();
+        let edges: Vec<Vec<usize>> = aliases
+            .iter()
+            .map(|(_, _, ty)| {
+                let mut targets = Vec::new();
+                referenced_defs(self.package, ty, &mut |def| {
+                    if let Some(owner) = self.package.def_owner(def)
+                        && let Some(&target) = index.get(&owner)
+                        && !targets.contains(&target)
+                    {
+                        targets.push(target);
+                    }
+                });
+                targets
+            })
+            .collect();
+
+        let mut search = CycleSearch {
+            edges: &edges,
+            state: vec![State::Unvisited; aliases.len()],
+            path: Vec::new(),
+            cycles: Vec::new(),
+        };
+        for alias in 0..aliases.len() {
+            search.visit(alias);
+        }
+
+        for cycle in search.cycles {
+            let names: Vec<String> = cycle
+                .iter()
+                .chain(cycle.first())
+                .map(|&i| format!("`{}`", aliases[i].1.name))
+                .collect();
+            let first = &aliases[cycle[0]].1;
+            let msg = if cycle.len() == 1 {
+                format!("type alias `{}` refers to itself", first.name)
+            } else {
+                format!("type aliases refer to each other: {}", names.join(" -> "))
+            };
+            LoweringError::cyclic_type_alias(msg, first.span).emit(self.diag_ctx, BytePos(0));
+        }
+    }
+}
+
+/// Call `f` with every definition a name in `ty` was resolved to.
+fn referenced_defs(package: &Package<'_>, ty: &Expr<'_>, f: &mut impl FnMut(DefId)) {
+    let def = match &ty.kind {
+        ExprKind::Path(path) => path.res.or_else(|| package.type_res(ty.hir_id)),
+        _ => package.type_res(ty.hir_id),
+    };
+    if let Some(def) = def {
+        f(def);
+    }
+    for sub in ty.subexprs() {
+        referenced_defs(package, sub, f);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Unvisited,
+    OnPath,
+    Done,
+}
+
+/// A depth-first search for the cycles of the alias graph.
+struct CycleSearch<'a> {
+    edges: &'a [Vec<usize>],
+    state: Vec<State>,
+    /// The aliases from the root of the search to the current one.
+    path: Vec<usize>,
+    /// Each cycle found, starting at its earliest alias.
+    cycles: Vec<Vec<usize>>,
+}
+
+impl CycleSearch<'_> {
+    fn visit(&mut self, alias: usize) {
+        if self.state[alias] != State::Unvisited {
+            return;
+        }
+        self.state[alias] = State::OnPath;
+        self.path.push(alias);
+        let edges = self.edges;
+        for &target in &edges[alias] {
+            match self.state[target] {
+                State::Unvisited => self.visit(target),
+                State::OnPath => {
+                    let start = self.path.iter().position(|&a| a == target).unwrap();
+                    let mut cycle = self.path[start..].to_vec();
+                    let earliest = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap();
+                    cycle.rotate_left(earliest);
+                    self.cycles.push(cycle);
+                }
+                State::Done => {}
+            }
+        }
+        self.path.pop();
+        self.state[alias] = State::Done;
+    }
+}
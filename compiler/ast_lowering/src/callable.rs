@@ -0,0 +1,834 @@
+//! Local types, lambda types and checking calls to values of function type.
+//!
+//! There is no type checker behind lowering yet, so only what the syntax
+//! states is used. A local's type is its annotation, or what can be read
+//! off its initializer: a literal, another local, a lambda, or a call whose
+//! callee has a `fn(..)` type. Calls to a value of `fn(..)` type have their
+//! arity checked, and literal or lambda arguments are checked against the
+//! declared parameter types.
+//!
+//! An object literal has a record type, `{x: Int, y: Int}`, which is also
+//! how a record type is written. Records are typed structurally: a record
+//! with more fields can be used where one with fewer is expected, and each
+//! shared field may itself be a subtype (see [`is_subtype`]).
+//!
+//! An effect-qualified type, `#{IO} T`, is compatible with another when its
+//! effects are among the other's and the types are compatible (see
+//! [`effect`](crate::effect)).
+//!
+//! Type aliases are expanded before types are compared or shown (see
+//! [`normalize_type`]), so a parameter of type `Id`, with
+//! `typealias Id = Int`, takes an integer. A `newtype` is only compatible
+//! with itself: its values and those of its underlying type convert into
+//! each other with an explicit cast, `x.as(T)`, only.
+//!
+//! Calls to a function by name are checked once the whole file is lowered:
+//! `comptime` parameters must be given compile-time constants, and
+//! `implicit` parameters are filled in from context, so they take no
+//! positional argument; a local of the parameter's type must be in scope at
+//! the call. The positional arguments bind the leading
+//! parameters in order, then a keyword argument, `.name = value`, binds the
+//! parameter of that name; keyword arguments come after all positional ones.
+//! A parameter given two arguments, or none, is reported.
+//!
+//! [`is_subtype`]: LoweringContext::is_subtype
+//! [`normalize_type`]: LoweringContext::normalize_type
+
+use std::collections::HashSet;
+
+use hir::{
+    common::{Arg, BinOp, Ident, Symbol, TyParamKind},
+    expr::{Expr, ExprKind, FieldExpr},
+    item::{FnSig, ItemKind},
+    pattern::Pattern,
+};
+use resolve::{NameLookup, ScopeId, scope::ScopeKind};
+use rustc_span::Span;
+
+use crate::{
+    LoweringContext,
+    effect::{effect_row, unhandled_effects},
+};
+
+/// The parameter and return types of a `fn(..)` type.
+#[derive(Debug, Clone)]
+pub(crate) struct Callable<'hir> {
+    /// Parameter types; `None` for parameters that are not positional.
+    pub(crate) params: Vec<Option<&'hir Expr<'hir>>>,
+    /// Parameter names, for the parameters declared with one.
+    pub(crate) names: Vec<Option<Symbol>>,
+    /// Whether the last parameter is variadic, `...name: T`, and takes any
+    /// number of trailing positional arguments.
+    pub(crate) variadic: bool,
+    /// The declared return type, if any.
+    pub(crate) ret: Option<&'hir Expr<'hir>>,
+}
+
+impl<'hir> Callable<'hir> {
+    /// The callable described by a type expression, if it is a `fn(..)` type.
+    /// A closure qualification `^env fn(..)` is looked through.
+    pub(crate) fn of_type(ty: &Expr<'hir>) -> Option<Self> {
+        let (params, ret) = match &ty.kind {
+            ExprKind::TyFn { params, ret, .. } => (params, ret),
+            ExprKind::TyClosureQualified(_, inner) => return Self::of_type(inner),
+            _ => return None,
+        };
+        let names = params
+            .iter()
+            .map(|param| match &param.kind {
+                TyParamKind::PositionalDependencyCatched(ident, _)
+                | TyParamKind::Optional(ident, ..)
+                | TyParamKind::Varadic(ident, _) => Some(ident.name),
+                _ => None,
+            })
+            .collect();
+        let variadic = params
+            .last()
+            .is_some_and(|param| matches!(param.kind, TyParamKind::Varadic(..)));
+        let params = params
+            .iter()
+            .map(|param| match &param.kind {
+                TyParamKind::Positional(ty) | TyParamKind::PositionalDependencyCatched(_, ty) => {
+                    Some(*ty)
+                }
+                _ => None,
+            })
+            .collect();
+        Some(Callable {
+            params,
+            names,
+            variadic,
+            ret: *ret,
+        })
+    }
+}
+
+/// A call to a function by name, whose signature may not be lowered yet.
+pub(crate) struct DeferredCall<'hir> {
+    callee: Symbol,
+    /// The scope the call is written in, which the callee is resolved from.
+    scope: ScopeId,
+    /// Each positional argument's span and whether it is a compile-time
+    /// constant.
+    args: Vec<(Span, bool)>,
+    /// Each keyword argument's name, the span of its value and whether that
+    /// is a compile-time constant.
+    named: Vec<(Ident, Span, bool)>,
+    /// The types of the locals in scope at the call, for its implicit
+    /// arguments.
+    in_scope: Vec<&'hir Expr<'hir>>,
+    span: Span,
+}
+
+impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
+    /// Bring a local into scope with its declared type, or the type of its
+    /// initializer when it has no annotation. Untyped locals are recorded
+    /// too, so they shadow outer ones. A local initialized with quoted code
+    /// is remembered for `splice`.
+    pub(crate) fn declare_local(
+        &mut self,
+        name: Symbol,
+        ty: Option<&'hir Expr<'hir>>,
+        init: Option<&Expr<'hir>>,
+    ) {
+        let ty = ty.or_else(|| init.and_then(|init| self.infer_type(init)));
+        let quoted = init.and_then(|init| self.quoted_block(init));
+        self.forget_quoted_locals();
+        if let Some(block) = quoted {
+            self.quoted_locals.push((self.locals.len(), block));
+        }
+        self.locals.push((name, ty));
+    }
+
+    /// Bring every name bound by `pat` into scope, typed as far as the
+    /// type `ty` of the matched value tells.
+    pub(crate) fn declare_pattern_bindings(
+        &mut self,
+        pat: &Pattern<'hir>,
+        ty: Option<&'hir Expr<'hir>>,
+    ) {
+        self.forget_quoted_locals();
+        let mut bindings = Vec::new();
+        self.pattern_binding_types(pat, ty, &mut bindings);
+        self.locals.extend(bindings);
+    }
+
+    /// Drop the quoted locals that have gone out of scope, before their
+    /// slots in `locals` are reused.
+    fn forget_quoted_locals(&mut self) {
+        let len = self.locals.len();
+        self.quoted_locals.retain(|&(index, _)| index < len);
+    }
+
+    pub(crate) fn is_local(&self, name: Symbol) -> bool {
+        self.locals.iter().any(|(local, _)| *local == name)
+    }
+
+    fn local_type(&self, name: Symbol) -> Option<&'hir Expr<'hir>> {
+        self.locals
+            .iter()
+            .rev()
+            .find(|(local, _)| *local == name)
+            .and_then(|(_, ty)| *ty)
+    }
+
+    /// The type of an already lowered expression, if the syntax tells it.
+    /// Literal and operator types are built on demand, hence `&mut`.
+    pub(crate) fn infer_type(&mut self, expr: &Expr<'hir>) -> Option<&'hir Expr<'hir>> {
+        match &expr.kind {
+            ExprKind::Lit(lit) => Some(self.primitive_type(literal_type(&lit.kind), expr.span)),
+            ExprKind::Binary(op, lhs, rhs) => match op {
+                BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem => {
+                    self.infer_type(lhs).or_else(|| self.infer_type(rhs))
+                }
+                BinOp::Eq
+                | BinOp::Ne
+                | BinOp::Lt
+                | BinOp::Le
+                | BinOp::Gt
+                | BinOp::Ge
+                | BinOp::And
+                | BinOp::Or => Some(self.primitive_type("Bool", expr.span)),
+            },
+            ExprKind::Forall { .. } | ExprKind::Exists { .. } => {
+                Some(self.primitive_type("Bool", expr.span))
+            }
+            ExprKind::Object(elems, fields) => self.record_type(elems, fields, expr.span),
+            _ => self.type_of(expr),
+        }
+        .filter(|ty| !matches!(ty.kind, ExprKind::TyPlaceholder))
+    }
+
+    fn primitive_type(&mut self, name: &str, span: Span) -> &'hir Expr<'hir> {
+        let ty = Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::Ident(Symbol::intern(name)),
+            span,
+        };
+        self.arena.alloc_expr(ty)
+    }
+
+    /// The record type of an object literal: each `name: value` field has
+    /// the type of its value and each punned `name` the type of that local.
+    /// Fields whose type is unknown are `_`; other positional entries leave
+    /// the literal untyped.
+    fn record_type(
+        &mut self,
+        elems: &[Expr<'hir>],
+        fields: &[FieldExpr<'hir>],
+        span: Span,
+    ) -> Option<&'hir Expr<'hir>> {
+        let mut field_tys = Vec::with_capacity(elems.len() + fields.len());
+        for elem in elems {
+            let ExprKind::Ident(name) = elem.kind else {
+                return None;
+            };
+            let ty = match self.local_type(name) {
+                Some(ty) => ty,
+                None => self.placeholder_type(elem.span),
+            };
+            field_tys.push(FieldExpr {
+                ident: Ident::new(name, elem.span),
+                expr: ty,
+                span: elem.span,
+            });
+        }
+        for field in fields {
+            let ty = match self.infer_type(field.expr) {
+                Some(ty) => ty,
+                None => self.placeholder_type(field.expr.span),
+            };
+            field_tys.push(FieldExpr {
+                ident: field.ident.clone(),
+                expr: ty,
+                span: field.span,
+            });
+        }
+        let ty = Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::Object(&[], self.arena.alloc_field_expr_slice(field_tys)),
+            span,
+        };
+        Some(self.arena.alloc_expr(ty))
+    }
+
+    fn placeholder_type(&mut self, span: Span) -> &'hir Expr<'hir> {
+        let ty = Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::TyPlaceholder,
+            span,
+        };
+        self.arena.alloc_expr(ty)
+    }
+
+    /// The type of a value whose type is already recorded somewhere: a
+    /// typed local, a lambda, a cast, or a call to a value of `fn(..)` type.
+    fn type_of(&self, value: &Expr<'hir>) -> Option<&'hir Expr<'hir>> {
+        match &value.kind {
+            ExprKind::Ident(name) => self.local_type(*name),
+            ExprKind::SelfValue => self.local_type(Symbol::intern("self")),
+            ExprKind::Lambda { ty, .. } => Some(*ty),
+            ExprKind::Cast(_, ty) => Some(*ty),
+            ExprKind::Projection(base, field) => self.field_type(self.type_of(base)?, field.name),
+            ExprKind::Application(callee, _) => Callable::of_type(self.type_of(callee)?)?.ret,
+            _ => None,
+        }
+    }
+
+    fn callable_of_value(&self, value: &Expr<'hir>) -> Option<Callable<'hir>> {
+        Callable::of_type(self.type_of(value)?)
+    }
+
+    /// Check the arguments of a call whose callee has a `fn(..)` type
+    /// against that type.
+    pub(crate) fn check_call(
+        &mut self,
+        callee: &Expr<'hir>,
+        args: &[Arg<'hir>],
+        call: &Expr<'hir>,
+    ) {
+        let misplaced = args
+            .iter()
+            .skip_while(|arg| !matches!(arg, Arg::Named(..)))
+            .find_map(|arg| match arg {
+                Arg::Positional(expr) => Some(expr),
+                _ => None,
+            });
+        if let Some(misplaced) = misplaced {
+            self.emit_misordered_argument(
+                "positional arguments must come before keyword arguments",
+                misplaced.span,
+            );
+            return;
+        }
+
+        let Some(callable) = self.callable_of_value(callee) else {
+            self.defer_call(callee, args, call);
+            return;
+        };
+        let name = match &callee.kind {
+            ExprKind::Ident(name) => format!("`{}`", name),
+            _ => "this function".to_string(),
+        };
+
+        if args
+            .iter()
+            .any(|arg| matches!(arg, Arg::Expand(_) | Arg::Implicit(_)))
+        {
+            // Spread arguments: the count is not known here.
+            return;
+        }
+        let positional = args
+            .iter()
+            .filter(|arg| matches!(arg, Arg::Positional(_)))
+            .count();
+        // Optional and variadic parameters need no argument, and a variadic
+        // one takes any number.
+        let required = callable.params.iter().flatten().count();
+        let too_many = !callable.variadic && positional > callable.params.len();
+        let too_few = positional == args.len() && positional < required;
+        if too_many || too_few {
+            let takes = if callable.variadic {
+                format!("at least {}", required)
+            } else if required < callable.params.len() {
+                format!("{} to {}", required, callable.params.len())
+            } else {
+                required.to_string()
+            };
+            self.emit_argument_count_mismatch(
+                &format!(
+                    "{} takes {} argument(s) but {} were supplied",
+                    name,
+                    takes,
+                    args.len()
+                ),
+                call.span,
+            );
+            return;
+        }
+
+        // Positional arguments bind the leading parameters in order; keyword
+        // arguments then bind the parameters they name.
+        let mut bound: Vec<Option<&Expr<'hir>>> = vec![None; callable.params.len()];
+        for (slot, arg) in bound.iter_mut().zip(args) {
+            if let Arg::Positional(expr) = arg {
+                *slot = Some(*expr);
+            }
+        }
+        let mut misnamed = false;
+        for arg in args {
+            let Arg::Named(ident, expr) = arg else {
+                continue;
+            };
+            match callable.names.iter().position(|n| *n == Some(ident.name)) {
+                Some(index) if bound[index].is_some() => {
+                    misnamed = true;
+                    self.emit_duplicate_argument(
+                        &format!("{} is given `{}` more than once", name, ident.name),
+                        ident.span,
+                    );
+                }
+                Some(index) => bound[index] = Some(*expr),
+                None => {
+                    misnamed = true;
+                    self.emit_unknown_parameter(
+                        &format!("{} has no parameter `{}`", name, ident.name),
+                        ident.span,
+                    );
+                }
+            }
+        }
+
+        // A misnamed keyword argument was likely meant for a missing
+        // parameter, which is then not reported again.
+        let missing: Vec<String> = bound
+            .iter()
+            .zip(callable.params.iter().zip(&callable.names))
+            .enumerate()
+            .filter(|(_, (arg, (param, _)))| arg.is_none() && param.is_some())
+            .map(|(index, (_, (_, param_name)))| match param_name {
+                Some(param_name) => format!("`{}`", param_name),
+                None => format!("#{}", index + 1),
+            })
+            .collect();
+        if !missing.is_empty() && !misnamed {
+            self.emit_argument_count_mismatch(
+                &format!("{} is missing an argument for {}", name, missing.join(", ")),
+                call.span,
+            );
+        }
+
+        for (arg, param) in bound.iter().zip(&callable.params) {
+            let (Some(arg), Some(param)) = (*arg, *param) else {
+                continue;
+            };
+            let param = self.normalize_type(param);
+            let found = self.infer_type(arg).map(|found| self.normalize_type(found));
+            if let Some(found) = self.argument_mismatch(arg, found, param) {
+                self.emit_mismatched_argument(
+                    &format!("expected `{}`, found {}", type_name(param), found),
+                    arg.span,
+                );
+            }
+        }
+    }
+
+    /// Remember a call to a function by name for [`Self::check_deferred_calls`].
+    fn defer_call(&mut self, callee: &Expr<'hir>, args: &[Arg<'hir>], call: &Expr<'hir>) {
+        let ExprKind::Ident(name) = &callee.kind else {
+            return;
+        };
+        if self.is_local(*name) {
+            return;
+        }
+        let mut positional = Vec::new();
+        let mut named = Vec::new();
+        for arg in args {
+            match arg {
+                Arg::Positional(expr) => {
+                    positional.push((expr.span, self.is_comptime_constant(expr)))
+                }
+                Arg::Named(ident, expr) => {
+                    named.push((ident.clone(), expr.span, self.is_comptime_constant(expr)))
+                }
+                _ => return,
+            }
+        }
+        let mut shadowed = HashSet::new();
+        let in_scope = self
+            .locals
+            .iter()
+            .rev()
+            .filter(|(name, _)| shadowed.insert(*name))
+            .filter_map(|(_, ty)| *ty)
+            .collect();
+        self.deferred_calls.push(DeferredCall {
+            callee: *name,
+            scope: self.file_scope,
+            args: positional,
+            named,
+            in_scope,
+            span: call.span,
+        });
+    }
+
+    /// Check the calls to named functions against their signatures, now that
+    /// every function of the file is lowered. Overloaded names are skipped.
+    pub(crate) fn check_deferred_calls(&mut self) {
+        for call in std::mem::take(&mut self.deferred_calls) {
+            let Some(sig) = self.callee_sig(&call) else {
+                continue;
+            };
+
+            let (implicit, explicit): (Vec<_>, Vec<_>) = sig
+                .params
+                .iter()
+                .partition(|(_, param)| param.is_implicit());
+            let supplied = call.args.len() + call.named.len();
+            if !implicit.is_empty() && supplied > explicit.len() {
+                let names: Vec<String> = implicit
+                    .iter()
+                    .map(|(ident, _)| format!("`{}`", ident.name))
+                    .collect();
+                self.emit_argument_count_mismatch(
+                    &format!(
+                        "`{}` takes {} argument(s) but {} were supplied; {} resolved from context",
+                        call.callee,
+                        explicit.len(),
+                        supplied,
+                        names.join(", ")
+                    ),
+                    call.span,
+                );
+                continue;
+            }
+
+            for (ident, param) in &implicit {
+                let Some(&ty) = param.exprs().first() else {
+                    continue;
+                };
+                let expected = type_name(self.normalize_type(ty));
+                let resolved = call
+                    .in_scope
+                    .iter()
+                    .any(|&found| type_name(self.normalize_type(found)) == expected);
+                if !resolved {
+                    self.emit_unresolved_implicit(
+                        &format!(
+                            "no value of type `{}` is in scope for `{}` of `{}`",
+                            expected, ident.name, call.callee
+                        ),
+                        call.span,
+                    );
+                }
+            }
+
+            // Positional arguments bind the leading parameters in order;
+            // keyword arguments then bind the parameters they name.
+            let mut bound: Vec<Option<(Span, bool)>> = vec![None; explicit.len()];
+            for (slot, arg) in bound.iter_mut().zip(&call.args) {
+                *slot = Some(*arg);
+            }
+            let mut misnamed = false;
+            for (ident, span, constant) in &call.named {
+                match explicit
+                    .iter()
+                    .position(|(param, _)| param.name == ident.name)
+                {
+                    Some(index) if bound[index].is_some() => {
+                        misnamed = true;
+                        self.emit_duplicate_argument(
+                            &format!("`{}` is given `{}` more than once", call.callee, ident.name),
+                            ident.span,
+                        );
+                    }
+                    Some(index) => bound[index] = Some((*span, *constant)),
+                    None => {
+                        misnamed = true;
+                        self.emit_unknown_parameter(
+                            &format!("`{}` has no parameter `{}`", call.callee, ident.name),
+                            ident.span,
+                        );
+                    }
+                }
+            }
+
+            let missing: Vec<String> = bound
+                .iter()
+                .zip(&explicit)
+                .filter(|(arg, (_, param))| {
+                    arg.is_none()
+                        && matches!(
+                            param.kind,
+                            TyParamKind::Positional(_)
+                                | TyParamKind::PositionalDependencyCatched(..)
+                        )
+                })
+                .map(|(_, (ident, _))| format!("`{}`", ident.name))
+                .collect();
+            if !missing.is_empty() && !misnamed {
+                self.emit_argument_count_mismatch(
+                    &format!(
+                        "`{}` is missing an argument for {}",
+                        call.callee,
+                        missing.join(", ")
+                    ),
+                    call.span,
+                );
+            }
+
+            for (arg, (ident, param)) in bound.iter().zip(&explicit) {
+                let Some((span, constant)) = arg else {
+                    continue;
+                };
+                if param.is_comptime() && !constant {
+                    self.emit_non_constant_comptime_argument(
+                        &format!(
+                            "`{}` of `{}` must be known at compile time",
+                            ident.name, call.callee
+                        ),
+                        *span,
+                    );
+                }
+            }
+        }
+    }
+
+    /// The signature of the function `call` resolves to from where it is
+    /// written, unless the name is overloaded there: another function of the
+    /// same name is defined in the same scope.
+    fn callee_sig(&self, call: &DeferredCall<'hir>) -> Option<&'hir FnSig<'hir>> {
+        let NameLookup::Single(res) = self.resolver.lookup_name(call.callee.as_str(), call.scope)
+        else {
+            return None;
+        };
+        let ItemKind::Fn(sig, _) = &self.package.def_item(res.def_id)?.kind else {
+            return None;
+        };
+        let scopes = self.resolver.scope_tree();
+        let overloaded = scopes
+            .get(res.defined_in)?
+            .children
+            .iter()
+            .filter_map(|&child| scopes.get(child))
+            .filter(|scope| scope.kind == ScopeKind::FnBody && scope.owner_def != res.def_id)
+            .filter_map(|scope| self.package.def_item(scope.owner_def))
+            .any(|item| item.ident.name == call.callee);
+        (!overloaded).then_some(sig)
+    }
+
+    /// Describe `arg`, whose type is `found` if known, if it evidently
+    /// cannot have type `param`.
+    fn argument_mismatch(
+        &self,
+        arg: &Expr<'hir>,
+        found: Option<&'hir Expr<'hir>>,
+        param: &'hir Expr<'hir>,
+    ) -> Option<String> {
+        if let Some(expected) = Callable::of_type(param) {
+            let found = self.callable_of_value(arg);
+            return match (&arg.kind, found) {
+                (ExprKind::Lit(lit), _) => Some(format!("`{}`", literal_type(&lit.kind))),
+                (_, Some(found)) if found.params.len() != expected.params.len() => Some(format!(
+                    "a function taking {} argument(s)",
+                    found.params.len()
+                )),
+                _ => None,
+            };
+        }
+
+        if let Some(found) =
+            found.filter(|found| is_effect_qualified(found) || is_effect_qualified(param))
+        {
+            let unhandled = unhandled_effects(effect_row(found).0, effect_row(param).0);
+            if !unhandled.is_empty() {
+                let unhandled: Vec<String> = unhandled
+                    .iter()
+                    .map(|effect| format!("`{}`", type_name(effect)))
+                    .collect();
+                return Some(format!(
+                    "`{}` with unhandled effect(s) {}",
+                    type_name(found),
+                    unhandled.join(", ")
+                ));
+            }
+            return (!self.is_subtype(found, param)).then(|| format!("`{}`", type_name(found)));
+        }
+        if let Some(found) = found.filter(|found| is_record(found) || is_record(param)) {
+            return (!self.is_subtype(found, param)).then(|| format!("`{}`", type_name(found)));
+        }
+        if let Some(found) = found.filter(|found| self.is_newtype(found)) {
+            return (!self.is_subtype(found, param)).then(|| format!("`{}`", type_name(found)));
+        }
+
+        let ExprKind::Ident(expected) = &param.kind else {
+            return None;
+        };
+        if self.is_newtype(param) {
+            return match (&arg.kind, found) {
+                (ExprKind::Lit(lit), _) => Some(format!("`{}`", literal_type(&lit.kind))),
+                (ExprKind::Lambda { .. }, _) => Some("a lambda".to_string()),
+                (_, Some(found)) if !self.is_subtype(found, param) => {
+                    Some(format!("`{}`", type_name(found)))
+                }
+                _ => None,
+            };
+        }
+        let family = PrimitiveFamily::of_name(expected.as_str())?;
+        match &arg.kind {
+            ExprKind::Lit(lit) if !family.accepts(&lit.kind) => {
+                Some(format!("`{}`", literal_type(&lit.kind)))
+            }
+            ExprKind::Lambda { .. } => Some("a lambda".to_string()),
+            _ => None,
+        }
+    }
+
+    /// Whether a value of type `sub` can be used where `sup` is expected.
+    ///
+    /// Both are compared with their aliases expanded. A record is a subtype
+    /// of another if it has every field of it (width), each with a subtype of
+    /// that field's type (depth). Primitive types must be of the same family,
+    /// except that an integer can be used as a float, and a newtype only
+    /// matches itself. A pointer only matches a pointer to a subtype. An
+    /// effect-qualified type must perform no effect the expected one does
+    /// not. Types the syntax alone cannot compare are assumed compatible.
+    pub(crate) fn is_subtype(&self, sub: &'hir Expr<'hir>, sup: &'hir Expr<'hir>) -> bool {
+        let (sub, sup) = (self.normalize_type(sub), self.normalize_type(sup));
+        match (&sub.kind, &sup.kind) {
+            (ExprKind::EffectQualifiedType(..), _) | (_, ExprKind::EffectQualifiedType(..)) => {
+                let ((sub_effects, sub), (sup_effects, sup)) = (effect_row(sub), effect_row(sup));
+                unhandled_effects(sub_effects, sup_effects).is_empty() && self.is_subtype(sub, sup)
+            }
+            (ExprKind::Object(_, sub_fields), ExprKind::Object(_, sup_fields)) => {
+                sup_fields.iter().all(|expected| {
+                    sub_fields
+                        .iter()
+                        .find(|found| found.ident.name == expected.ident.name)
+                        .is_some_and(|found| self.is_subtype(found.expr, expected.expr))
+                })
+            }
+            (ExprKind::Object(..), ExprKind::Ident(name))
+            | (ExprKind::TyPtr(_), ExprKind::Ident(name)) => {
+                PrimitiveFamily::of_name(name.as_str()).is_none() && !self.is_newtype(sup)
+            }
+            (ExprKind::Ident(name), ExprKind::Object(..))
+            | (ExprKind::Ident(name), ExprKind::TyPtr(_)) => {
+                PrimitiveFamily::of_name(name.as_str()).is_none() && !self.is_newtype(sub)
+            }
+            (ExprKind::TyPtr(sub), ExprKind::TyPtr(sup)) => self.is_subtype(sub, sup),
+            (ExprKind::Ident(found), ExprKind::Ident(expected))
+                if found == expected && self.type_def(sub) == self.type_def(sup) =>
+            {
+                true
+            }
+            (ExprKind::Ident(_), ExprKind::Ident(_))
+                if self.is_newtype(sub) || self.is_newtype(sup) =>
+            {
+                false
+            }
+            (ExprKind::Ident(found), ExprKind::Ident(expected)) => {
+                match (
+                    PrimitiveFamily::of_name(found.as_str()),
+                    PrimitiveFamily::of_name(expected.as_str()),
+                ) {
+                    (Some(found), Some(expected)) => {
+                        found == expected
+                            || (found, expected)
+                                == (PrimitiveFamily::Integer, PrimitiveFamily::Float)
+                    }
+                    _ => true,
+                }
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Groups of builtin type names that a literal can take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PrimitiveFamily {
+    Integer,
+    Float,
+    String,
+    Bool,
+    Char,
+}
+
+impl PrimitiveFamily {
+    pub(crate) fn of_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "Int" | "Integer" | "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32"
+            | "u64" | "usize" => PrimitiveFamily::Integer,
+            "Float" | "f32" | "f64" => PrimitiveFamily::Float,
+            "String" | "Str" | "str" => PrimitiveFamily::String,
+            "Bool" | "bool" => PrimitiveFamily::Bool,
+            "Char" | "char" => PrimitiveFamily::Char,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn is_numeric(self) -> bool {
+        matches!(self, PrimitiveFamily::Integer | PrimitiveFamily::Float)
+    }
+
+    fn accepts(self, lit: &hir::common::LitKind) -> bool {
+        use hir::common::LitKind;
+        matches!(
+            (self, lit),
+            (PrimitiveFamily::Integer, LitKind::Integer(_))
+                // An integer literal is also a valid float.
+                | (PrimitiveFamily::Float, LitKind::Integer(_) | LitKind::Float(_))
+                | (PrimitiveFamily::String, LitKind::String(_))
+                | (PrimitiveFamily::Bool, LitKind::Bool(_))
+                | (PrimitiveFamily::Char, LitKind::Char(_))
+        )
+    }
+}
+
+fn is_record(ty: &Expr<'_>) -> bool {
+    matches!(ty.kind, ExprKind::Object(..))
+}
+
+fn is_effect_qualified(ty: &Expr<'_>) -> bool {
+    matches!(ty.kind, ExprKind::EffectQualifiedType(..))
+}
+
+fn literal_type(lit: &hir::common::LitKind) -> &'static str {
+    use hir::common::LitKind;
+    match lit {
+        LitKind::Integer(_) => "Int",
+        LitKind::Float(_) => "Float",
+        LitKind::String(_) => "String",
+        LitKind::Bool(_) => "Bool",
+        LitKind::Char(_) => "Char",
+        LitKind::Symbol(_) => "Symbol",
+    }
+}
+
+/// A short rendering of a type expression for diagnostics.
+pub(crate) fn type_name(ty: &Expr<'_>) -> String {
+    match &ty.kind {
+        ExprKind::Ident(name) => name.to_string(),
+        ExprKind::Path(path) => path.to_string(),
+        ExprKind::TyPtr(inner) => format!("*{}", type_name(inner)),
+        ExprKind::EffectQualifiedType(effects, ty) => {
+            format!("#{} {}", type_name(effects), type_name(ty))
+        }
+        ExprKind::TyEffectRow(effects) => {
+            let effects: Vec<String> = effects.iter().map(type_name).collect();
+            format!("{{{}}}", effects.join(", "))
+        }
+        ExprKind::Object(_, fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|field| format!("{}: {}", field.ident.name, type_name(field.expr)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+        ExprKind::NFApplication(callee, args) => {
+            let args: Vec<String> = args
+                .iter()
+                .map(|arg| match arg {
+                    Arg::Positional(ty) => type_name(ty),
+                    _ => "_".to_string(),
+                })
+                .collect();
+            format!("{}<{}>", type_name(callee), args.join(", "))
+        }
+        ExprKind::TyFn { params, ret, .. } => {
+            let params: Vec<String> = params
+                .iter()
+                .map(|param| match &param.kind {
+                    TyParamKind::Positional(ty) => type_name(ty),
+                    _ => "_".to_string(),
+                })
+                .collect();
+            match ret {
+                Some(ret) => format!("fn({}) -> {}", params.join(", "), type_name(ret)),
+                None => format!("fn({})", params.join(", ")),
+            }
+        }
+        _ => "_".to_string(),
+    }
+}
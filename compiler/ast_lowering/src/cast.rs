@@ -0,0 +1,160 @@
+//! Checking casts, `x.as(T)`.
+//!
+//! A cast converts a value between types that mean the same kind of thing:
+//! numbers of any width, `Bool` or `Char` to an integer and an integer to
+//! `Char`, a newtype and its representation, a type and its sub- or
+//! supertypes, and, in `unsafe` code, a pointer to another pointer or to an
+//! integer and back. Other casts, such as a struct to an integer, are
+//! errors. A cast to a narrower number may lose data and is warned about.
+//!
+//! As with call arguments, only what the syntax tells is used: a cast of a
+//! value whose type is not known, or to a type that is neither a builtin
+//! nor an item lowered so far, is accepted.
+
+use hir::{
+    ItemKind,
+    common::DefId,
+    expr::{Expr, ExprKind},
+};
+use rustc_span::Span;
+
+use crate::{
+    LoweringContext,
+    callable::{PrimitiveFamily, type_name},
+};
+
+/// What a cast can make of a type.
+#[derive(Debug, Clone, Copy)]
+enum CastType {
+    /// A builtin primitive, with its width in bits if it has a fixed one.
+    Primitive(PrimitiveFamily, Option<u32>),
+    Pointer,
+    Record,
+    /// A struct or enum of the package, by its definition.
+    Nominal(DefId),
+    /// A type the syntax alone cannot judge.
+    Unknown,
+}
+
+impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
+    /// Check the cast of `value` to `target`.
+    pub(crate) fn check_cast(&mut self, value: &Expr<'hir>, target: &'hir Expr<'hir>, span: Span) {
+        let Some(source) = self.infer_type(value) else {
+            return;
+        };
+        let (from, to) = (self.normalize_type(source), self.normalize_type(target));
+        let describe = || format!("`{}` to `{}`", type_name(from), type_name(to));
+
+        let from_repr = self.representation(from);
+        let to_repr = self.representation(to);
+        match (self.cast_type(from_repr), self.cast_type(to_repr)) {
+            (CastType::Unknown, _) | (_, CastType::Unknown) => {}
+            (
+                CastType::Primitive(from_family, from_bits),
+                CastType::Primitive(to_family, to_bits),
+            ) => {
+                if from_family.is_numeric() && to_family.is_numeric() {
+                    let narrows = match (from_bits, to_bits) {
+                        (Some(from_bits), Some(to_bits)) => to_bits < from_bits,
+                        (None, Some(_)) => true,
+                        _ => false,
+                    };
+                    let truncates = from_family == PrimitiveFamily::Float
+                        && to_family == PrimitiveFamily::Integer;
+                    if truncates || (from_family == to_family && narrows) {
+                        self.warn_lossy_cast(
+                            &format!("casting {} may lose data", describe()),
+                            span,
+                        );
+                    }
+                } else if !converts(from_family, to_family) {
+                    self.emit_invalid_cast(&format!("cannot cast {}", describe()), span);
+                }
+            }
+            (CastType::Pointer, CastType::Pointer)
+            | (CastType::Pointer, CastType::Primitive(PrimitiveFamily::Integer, _))
+            | (CastType::Primitive(PrimitiveFamily::Integer, _), CastType::Pointer) => {
+                if !self.in_unsafe_context() {
+                    self.emit_invalid_cast(
+                        &format!("casting {} is only allowed in `unsafe` code", describe()),
+                        span,
+                    );
+                }
+            }
+            (CastType::Record, CastType::Record)
+                if self.is_subtype(from_repr, to_repr) || self.is_subtype(to_repr, from_repr) => {}
+            (CastType::Nominal(from_def), CastType::Nominal(to_def)) if from_def == to_def => {}
+            _ => self.emit_invalid_cast(&format!("cannot cast {}", describe()), span),
+        }
+    }
+
+    /// `ty` with the newtypes at its head replaced by their representation.
+    fn representation(&self, ty: &'hir Expr<'hir>) -> &'hir Expr<'hir> {
+        let mut ty = ty;
+        let mut unwrapped = Vec::new();
+        while let Some(def) = self.type_def(ty) {
+            if unwrapped.contains(&def) {
+                break;
+            }
+            let Some(repr) = self.newtype_representation(def) else {
+                break;
+            };
+            unwrapped.push(def);
+            ty = self.normalize_type(repr);
+        }
+        ty
+    }
+
+    fn newtype_representation(&self, def: DefId) -> Option<&'hir Expr<'hir>> {
+        match self.package.def_item(def)?.kind {
+            ItemKind::Newtype(repr) => Some(repr),
+            _ => None,
+        }
+    }
+
+    fn cast_type(&self, ty: &Expr<'hir>) -> CastType {
+        match &ty.kind {
+            ExprKind::TyPtr(_) => CastType::Pointer,
+            ExprKind::Object(..) => CastType::Record,
+            ExprKind::Ident(name) => {
+                if let Some(family) = PrimitiveFamily::of_name(name.as_str()) {
+                    return CastType::Primitive(family, bit_width(name.as_str()));
+                }
+                let nominal = self.type_def(ty).filter(|&def| {
+                    self.package.def_item(def).is_some_and(|item| {
+                        matches!(item.kind, ItemKind::Struct(_) | ItemKind::Enum(_))
+                    })
+                });
+                match nominal {
+                    Some(def) => CastType::Nominal(def),
+                    None => CastType::Unknown,
+                }
+            }
+            _ => CastType::Unknown,
+        }
+    }
+}
+
+/// Whether a value of a primitive type of family `from` that is not a
+/// number can be cast to one of family `to`.
+fn converts(from: PrimitiveFamily, to: PrimitiveFamily) -> bool {
+    from == to
+        || matches!(
+            (from, to),
+            (
+                PrimitiveFamily::Bool | PrimitiveFamily::Char,
+                PrimitiveFamily::Integer
+            ) | (PrimitiveFamily::Integer, PrimitiveFamily::Char)
+        )
+}
+
+/// The width of a fixed-size number type; `Int` and `Float` have none.
+fn bit_width(name: &str) -> Option<u32> {
+    Some(match name {
+        "i8" | "u8" => 8,
+        "i16" | "u16" => 16,
+        "i32" | "u32" | "f32" => 32,
+        "i64" | "u64" | "f64" | "isize" | "usize" => 64,
+        _ => return None,
+    })
+}
@@ -0,0 +1,157 @@
+//! Coherence: at most one `impl` of a trait may apply to any type.
+//!
+//! Types are only known as written, so two impls of a trait conflict when
+//! their trait references and self types unify syntactically. The clause
+//! parameters of each impl (`impl Show for Vec<T> where T`) stand for any
+//! type, which is how a generic impl overlaps a concrete one.
+
+use std::collections::HashMap;
+
+use hir::{
+    ImplDef,
+    common::{Arg, Symbol},
+    expr::{Expr, ExprKind},
+};
+use rustc_span::Span;
+
+use crate::{LoweringContext, callable::type_name};
+
+impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
+    /// Report every pair of trait impls in the package that could apply to
+    /// the same type.
+    pub(crate) fn check_coherence(&self) {
+        let trait_impls: Vec<&ImplDef<'hir>> = self
+            .package
+            .impls_matching(|def| def.trait_ref.is_some())
+            .into_iter()
+            .filter_map(|owner| self.package.get_impl(owner))
+            .collect();
+
+        for (i, first) in trait_impls.iter().enumerate() {
+            for second in &trait_impls[i + 1..] {
+                if !impls_overlap(first, second) {
+                    continue;
+                }
+                let trait_ref = second.trait_ref.expect("filtered to trait impls");
+                self.emit_conflicting_impls(
+                    &format!(
+                        "`{}` is already implemented for `{}`",
+                        type_name(trait_ref),
+                        type_name(second.self_ty)
+                    ),
+                    impl_head_span(first),
+                    impl_head_span(second),
+                );
+            }
+        }
+    }
+}
+
+/// Whether one type could satisfy both impls.
+fn impls_overlap<'hir>(first: &ImplDef<'hir>, second: &ImplDef<'hir>) -> bool {
+    let (Some(first_trait), Some(second_trait)) = (first.trait_ref, second.trait_ref) else {
+        return false;
+    };
+    let mut unifier = Unifier {
+        params: [clause_param_names(first), clause_param_names(second)],
+        bindings: HashMap::new(),
+    };
+    unifier.unify((0, first_trait), (1, second_trait))
+        && unifier.unify((0, first.self_ty), (1, second.self_ty))
+}
+
+fn clause_param_names(def: &ImplDef<'_>) -> Vec<Symbol> {
+    def.clause_params
+        .iter()
+        .map(|param| param.name.name)
+        .collect()
+}
+
+/// `Show for Int` in `impl Show for Int { .. }`.
+fn impl_head_span(def: &ImplDef<'_>) -> Span {
+    match def.trait_ref {
+        Some(trait_ref) => trait_ref.span.to(def.self_ty.span),
+        None => def.self_ty.span,
+    }
+}
+
+/// A type expression on one side of the comparison: `0` for the first
+/// impl, `1` for the second. The side scopes clause parameter names, so the
+/// `T` of one impl is a different variable from the `T` of the other.
+type Side<'a, 'hir> = (usize, &'a Expr<'hir>);
+
+/// Syntactic unification of type expressions whose clause parameters are
+/// variables.
+struct Unifier<'a, 'hir> {
+    /// Clause parameter names of each side.
+    params: [Vec<Symbol>; 2],
+    bindings: HashMap<(usize, Symbol), Side<'a, 'hir>>,
+}
+
+impl<'a, 'hir> Unifier<'a, 'hir> {
+    /// The clause parameter `ty` names on its side, if it is one.
+    fn variable(&self, (side, ty): Side<'a, 'hir>) -> Option<Symbol> {
+        match ty.kind {
+            ExprKind::Ident(name) if self.params[side].contains(&name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Follow bindings until an unbound variable or a non-variable type.
+    fn resolve(&self, mut ty: Side<'a, 'hir>) -> Side<'a, 'hir> {
+        while let Some(name) = self.variable(ty) {
+            match self.bindings.get(&(ty.0, name)) {
+                Some(&bound) => ty = bound,
+                None => break,
+            }
+        }
+        ty
+    }
+
+    fn unify(&mut self, a: Side<'a, 'hir>, b: Side<'a, 'hir>) -> bool {
+        let (a, b) = (self.resolve(a), self.resolve(b));
+        if let Some(name) = self.variable(a) {
+            if a.0 != b.0 || self.variable(b) != Some(name) {
+                self.bindings.insert((a.0, name), b);
+            }
+            return true;
+        }
+        if let Some(name) = self.variable(b) {
+            self.bindings.insert((b.0, name), a);
+            return true;
+        }
+
+        match (&a.1.kind, &b.1.kind) {
+            (ExprKind::Ident(x), ExprKind::Ident(y)) => x == y,
+            (ExprKind::NFApplication(f, xs), ExprKind::NFApplication(g, ys))
+            | (ExprKind::Application(f, xs), ExprKind::Application(g, ys)) => {
+                self.unify((a.0, f), (b.0, g)) && self.unify_args((a.0, xs), (b.0, ys))
+            }
+            (ExprKind::TyPtr(x), ExprKind::TyPtr(y))
+            | (ExprKind::TyOptional(x), ExprKind::TyOptional(y)) => self.unify((a.0, x), (b.0, y)),
+            (ExprKind::Tuple(xs), ExprKind::Tuple(ys)) => {
+                xs.len() == ys.len()
+                    && xs
+                        .iter()
+                        .zip(ys.iter())
+                        .all(|(x, y)| self.unify((a.0, x), (b.0, y)))
+            }
+            _ => a.1.type_key() == b.1.type_key(),
+        }
+    }
+
+    fn unify_args(
+        &mut self,
+        (a_side, xs): (usize, &'a [Arg<'hir>]),
+        (b_side, ys): (usize, &'a [Arg<'hir>]),
+    ) -> bool {
+        xs.len() == ys.len()
+            && xs.iter().zip(ys).all(|pair| match pair {
+                (Arg::Positional(x), Arg::Positional(y)) => self.unify((a_side, x), (b_side, y)),
+                (Arg::Named(n, x), Arg::Named(m, y)) => {
+                    n.name == m.name && self.unify((a_side, x), (b_side, y))
+                }
+                _ => false,
+            })
+    }
+}
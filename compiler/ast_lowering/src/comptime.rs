@@ -0,0 +1,262 @@
+//! The built-in functions that run entirely at compile time, over the
+//! evaluator in [`hir::consts`].
+//!
+//! ## Quoting and splicing
+//!
+//! `quote { .. }` is a compile-time value standing for its block, which is
+//! not lowered where it is written. `splice(q)` lowers the block in place of
+//! the call, once per splice, so the spliced code belongs to the body it is
+//! spliced into and keeps the spans of the quoted source.
+//!
+//! Splicing is hygienic for the bindings the quoted code introduces: each
+//! one is renamed to `name#mark`, with a mark fresh for every splice, so it
+//! can neither capture nor shadow a name at the splice site. Names the
+//! quoted code uses without binding them refer to what is in scope where it
+//! is spliced.
+
+use ast::NodeIndex;
+use hir::{
+    ConstValue, ValueRes,
+    common::{Arg, BinOp, Ident, Lit, Symbol, UnOp},
+    consts::eval_unary,
+    expr::{Expr, ExprKind},
+};
+use intrinsic::BuiltinFn;
+use resolve::NameLookup;
+use rustc_span::Span;
+
+use crate::{LoweringContext, callable::PrimitiveFamily};
+
+impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
+    /// The value of `expr` at compile time, where it is known; see
+    /// [`Package::eval_const`].
+    ///
+    /// [`Package::eval_const`]: hir::Package::eval_const
+    pub(crate) fn eval_const(&self, expr: &Expr<'hir>) -> Option<ConstValue> {
+        self.package
+            .eval_const(expr, &|name| self.value_res(name))
+            .value()
+    }
+
+    /// Whether `expr` is known at compile time; see [`Package::is_const`].
+    ///
+    /// [`Package::is_const`]: hir::Package::is_const
+    pub(crate) fn is_comptime_constant(&self, expr: &Expr<'hir>) -> bool {
+        self.package.is_const(expr, &|name| self.value_res(name))
+    }
+
+    /// What `name`, used as a value, refers to here: a local of the body
+    /// being lowered, or else what it resolves to from the file's scope.
+    pub(crate) fn value_res(&self, name: Symbol) -> ValueRes {
+        if let Some(index) = self.locals.iter().rposition(|(local, _)| *local == name) {
+            return ValueRes::Local {
+                comptime: self.comptime_locals.contains(&index),
+            };
+        }
+        match self.resolver.lookup_name(name.as_str(), self.file_scope) {
+            NameLookup::Single(res) => ValueRes::Def(res.def_id),
+            NameLookup::Ambiguous(_) | NameLookup::NotFound => ValueRes::Unresolved,
+        }
+    }
+
+    /// Apply the unary `op` to the lowered `operand`, folding it away where
+    /// possible: over a literal it becomes the resulting literal, and over
+    /// the same operator it cancels out, so `--x` and `not not x` are `x`.
+    pub(crate) fn fold_unary(&mut self, op: UnOp, operand: Expr<'hir>, span: Span) -> Expr<'hir> {
+        let folded = match &operand.kind {
+            ExprKind::Lit(_) => self
+                .eval_const(&operand)
+                .and_then(|value| eval_unary(op, value))
+                .map(ConstValue::into_lit_kind),
+            ExprKind::Unary(inner_op, inner) if *inner_op == op => {
+                return Expr {
+                    span,
+                    ..(*inner).clone()
+                };
+            }
+            _ => None,
+        };
+        let kind = match folded {
+            Some(kind) => ExprKind::Lit(Lit { kind, span }),
+            None => ExprKind::Unary(op, self.arena.alloc_expr(operand)),
+        };
+        Expr {
+            hir_id: self.next_hir_id(),
+            kind,
+            span,
+        }
+    }
+
+    /// The built-in function `callee` names, unless a local or an item
+    /// shadows it.
+    pub(crate) fn builtin_callee(&self, callee: &Expr<'hir>) -> Option<BuiltinFn> {
+        match &callee.kind {
+            ExprKind::Ident(name) if self.value_res(*name) == ValueRes::Unresolved => {
+                BuiltinFn::lookup(*name)
+            }
+            _ => None,
+        }
+    }
+
+    /// Lower a call to a comptime built-in by evaluating it; the call itself
+    /// leaves no runtime code, only the code a `splice` expands to.
+    pub(crate) fn lower_comptime_builtin(
+        &mut self,
+        builtin: BuiltinFn,
+        args: &[Arg<'hir>],
+        span: Span,
+    ) -> Expr<'hir> {
+        match (builtin, args) {
+            (BuiltinFn::StaticAssert, [Arg::Positional(cond), Arg::Positional(msg)]) => {
+                self.check_static_assert(cond, msg)
+            }
+            (BuiltinFn::Splice, [Arg::Positional(quoted)]) => {
+                return self.lower_splice(quoted, span);
+            }
+            _ => self.emit_argument_count_mismatch(
+                &format!(
+                    "`{}` takes {} argument(s) but {} were supplied",
+                    builtin.name(),
+                    builtin.arity(),
+                    args.len()
+                ),
+                span,
+            ),
+        }
+        Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::Unit,
+            span,
+        }
+    }
+
+    /// Reject an integer `/` or `%` whose divisor is known at compile time
+    /// to be zero. Divisors only known at run time are left alone, and so is
+    /// a float dividend, whose division by zero is well defined.
+    pub(crate) fn check_const_divisor(
+        &mut self,
+        op: BinOp,
+        lhs: &Expr<'hir>,
+        divisor: &Expr<'hir>,
+    ) {
+        if !matches!(op, BinOp::Div | BinOp::Rem) || self.is_float(lhs) {
+            return;
+        }
+        if self.eval_const(divisor) == Some(ConstValue::Int(0)) {
+            self.emit_division_by_zero(
+                &format!("the divisor of `{}` is always zero", op),
+                divisor.span,
+            );
+        }
+    }
+
+    /// Whether `expr` is a float: by its type where that is known, or else
+    /// by its value at compile time.
+    fn is_float(&mut self, expr: &Expr<'hir>) -> bool {
+        match self.infer_type(expr).map(|ty| self.normalize_type(ty)) {
+            Some(Expr {
+                kind: ExprKind::Ident(name),
+                ..
+            }) => PrimitiveFamily::of_name(name.as_str()) == Some(PrimitiveFamily::Float),
+            _ => matches!(self.eval_const(expr), Some(ConstValue::Float(_))),
+        }
+    }
+
+    /// The quoted block `expr` stands for: a `quote { .. }` expression, or a
+    /// local bound to one.
+    pub(crate) fn quoted_block(&self, expr: &Expr<'hir>) -> Option<NodeIndex> {
+        match &expr.kind {
+            ExprKind::Quote => self.quotes.get(&expr.hir_id).copied(),
+            ExprKind::Ident(name) => {
+                let index = self.locals.iter().rposition(|(local, _)| local == name)?;
+                self.quoted_locals
+                    .iter()
+                    .find(|(local, _)| *local == index)
+                    .map(|&(_, block)| block)
+            }
+            _ => None,
+        }
+    }
+
+    /// Lower the block `quoted` stands for in place of `splice(quoted)`,
+    /// renaming the bindings it introduces.
+    fn lower_splice(&mut self, quoted: &Expr<'hir>, span: Span) -> Expr<'hir> {
+        let Some(block_node) = self.quoted_block(quoted) else {
+            self.emit_non_constant_comptime_argument(
+                "`splice` expects quoted code, such as `quote { .. }` or a local bound to it",
+                quoted.span,
+            );
+            return self.invalid_expr(span);
+        };
+        if self.splicing.iter().any(|&(block, _)| block == block_node) {
+            self.emit_recursive_splice("the quoted code splices itself", span);
+            return self.invalid_expr(span);
+        }
+
+        self.splicing.push((block_node, self.next_splice_mark));
+        self.next_splice_mark += 1;
+        let outer_renames = self.splice_renames.len();
+        let block = self.lower_block(block_node);
+        self.splice_renames.truncate(outer_renames);
+        self.splicing.pop();
+
+        Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::Splice(self.arena.alloc_block(block)),
+            span,
+        }
+    }
+
+    /// The identifier a binding is introduced with: inside spliced code, a
+    /// fresh name remembered for the uses of the binding.
+    pub(crate) fn hygienic_binding(&mut self, ident: Ident) -> Ident {
+        let Some(&(_, mark)) = self.splicing.last() else {
+            return ident;
+        };
+        let fresh = Symbol::intern(&format!("{}#{}", ident.name.as_str(), mark));
+        self.splice_renames.push((ident.name, fresh));
+        Ident::new(fresh, ident.span)
+    }
+
+    /// The name a use of `name` refers to: inside spliced code, the fresh
+    /// name of a binding the spliced code introduced, while it is in scope.
+    pub(crate) fn hygienic_use(&self, name: Symbol) -> Symbol {
+        self.splice_renames
+            .iter()
+            .rev()
+            .find(|(original, fresh)| *original == name && self.is_local(*fresh))
+            .map_or(name, |&(_, fresh)| fresh)
+    }
+
+    fn invalid_expr(&mut self, span: Span) -> Expr<'hir> {
+        Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::Invalid,
+            span,
+        }
+    }
+
+    fn check_static_assert(&self, cond: &Expr<'hir>, msg: &Expr<'hir>) {
+        match self.eval_const(cond) {
+            Some(ConstValue::Bool(true)) => {}
+            Some(ConstValue::Bool(false)) => {
+                let msg = match self.eval_const(msg) {
+                    Some(ConstValue::Str(msg)) => msg,
+                    _ => "condition is false".to_string(),
+                };
+                self.emit_static_assertion_failed(&msg, cond.span);
+            }
+            Some(other) => self.emit_mismatched_argument(
+                &format!(
+                    "`static_assert` expects a bool condition, found {}",
+                    other.kind_name()
+                ),
+                cond.span,
+            ),
+            None => self.emit_non_constant_comptime_argument(
+                "the condition of `static_assert` must be known at compile time",
+                cond.span,
+            ),
+        }
+    }
+}
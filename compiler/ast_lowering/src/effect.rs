@@ -0,0 +1,65 @@
+//! Effect-qualified types, `#{IO, State} T`.
+//!
+//! The effects written before the type are lowered to an effect row, the
+//! set of effects that computing the `T` may perform. Rows are compared as
+//! sets, so the order effects are written in does not matter. A
+//! computation performing fewer effects can be used where one performing
+//! more is expected: `#{IO} T` is a subtype of `#{IO, State} T`, and a
+//! plain `T`, which performs none, of both. The other way round, the
+//! effects missing from the expected row would be left unhandled.
+
+use ast::{NodeIndex, NodeKind};
+use hir::expr::{Expr, ExprKind};
+
+use crate::LoweringContext;
+
+impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
+    /// Lower the effects of `#effects T`: a braced list, or a single
+    /// effect, to an [`ExprKind::TyEffectRow`].
+    pub(crate) fn lower_effect_row(&mut self, node: NodeIndex) -> Expr<'hir> {
+        let span = self.ast.get_span(node).unwrap_or_default();
+        let effects = match self.ast.get_node(node) {
+            Some((NodeKind::Object, _, children)) => {
+                let effect_nodes = self.ast.get_multi_child_slice(children[0]).unwrap_or(&[]);
+                effect_nodes
+                    .iter()
+                    .map(|&effect| self.lower_expr(effect))
+                    .collect()
+            }
+            _ => vec![self.lower_expr(node)],
+        };
+        Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::TyEffectRow(self.arena.alloc_expr_slice(effects)),
+            span,
+        }
+    }
+}
+
+/// The effects a value of type `ty` is computed with, and the type of the
+/// value itself. A type without an effect row performs no effects.
+pub(crate) fn effect_row<'hir>(ty: &'hir Expr<'hir>) -> (&'hir [Expr<'hir>], &'hir Expr<'hir>) {
+    match &ty.kind {
+        ExprKind::EffectQualifiedType(row, ty) => match &row.kind {
+            ExprKind::TyEffectRow(effects) => (*effects, *ty),
+            _ => (std::slice::from_ref(*row), *ty),
+        },
+        _ => (&[], ty),
+    }
+}
+
+/// The effects of the row `found` that the row `expected` lacks, which
+/// nothing would handle if a `found` computation were used for an
+/// `expected` one.
+pub(crate) fn unhandled_effects<'a, 'hir>(
+    found: &'a [Expr<'hir>],
+    expected: &[Expr<'hir>],
+) -> Vec<&'a Expr<'hir>> {
+    found
+        .iter()
+        .filter(|effect| {
+            let key = effect.type_key();
+            !expected.iter().any(|handled| handled.type_key() == key)
+        })
+        .collect()
+}
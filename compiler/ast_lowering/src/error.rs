@@ -6,17 +6,41 @@
 use diagnostic::{DiagnosticBuilder, DiagnosticContext, FlurryError};
 use rustc_span::{BytePos, Span};
 
-// Lowering errors use the E2xxx range:
-//   E2001  – unsupported AST node during lowering
-//   E2002  – invalid AST structure (missing expected children)
-//   E2003  – unsupported clause kind
-//   E2004  – invalid function parameter
-//   E2005  – invalid pattern form
-//   E2006  – invalid item in context
-//   E2007  – invalid enum variant
-//   E2008  – invalid struct field
-//   E2009  – missing identifier
-//   E2010  – invalid type expression
+// Lowering errors use the E3xxx range, after the parser's E2xxx:
+//   E3001  – unsupported AST node during lowering
+//   E3002  – invalid AST structure (missing expected children)
+//   E3003  – unsupported clause kind
+//   E3004  – invalid function parameter
+//   E3005  – invalid pattern form
+//   E3006  – invalid item in context
+//   E3007  – invalid enum variant
+//   E3008  – invalid struct field
+//   E3009  – missing identifier
+//   E3010  – invalid type expression
+//   E3011  – pattern does not fit the scrutinee
+//   E3012  – or-pattern alternatives bind different names
+//   E3013  – destructuring pattern names an unknown field
+//   E3014  – binding inside a negated pattern
+//   E3015  – call with the wrong number of arguments
+//   E3016  – argument does not fit the parameter type
+//   E3017  – two impls of one trait apply to the same type
+//   E3018  – `self` receiver on a function that is not a method
+//   E3019  – comptime parameter given a value only known at run time
+//   E3020  – catch arm on a value that has no error set
+//   E3021  – `static_assert` condition is false
+//   E3022  – integer division by a constant zero
+//   E3023  – quoted code that splices itself
+//   E3024  – type alias that expands to itself
+//   E3025  – cast between types that cannot be converted
+//   E3026  – field access naming no field or method of a struct
+//   E3027  – positional argument after a keyword argument
+//   E3028  – keyword argument naming no parameter of the callee
+//   E3029  – parameter given more than one argument
+//   E3030  – implicit parameter with no value of its type in scope
+//   E3031  – integer literal too large for any integer
+
+/// Base error code for lowering errors.
+pub const LOWERING_ERROR_BASE: u32 = 3000;
 
 /// A lowering error carrying enough information to produce a full diagnostic.
 #[derive(Debug)]
@@ -47,6 +71,56 @@ pub enum LoweringErrorKind {
     MissingIdentifier,
     /// A type expression that we cannot lower.
     InvalidTypeExpr(String),
+    /// A pattern destructures a kind of value the scrutinee cannot be.
+    MismatchedPattern(String),
+    /// The alternatives of an or-pattern do not bind the same names.
+    InconsistentOrBindings(String),
+    /// A struct pattern names a field the struct does not have.
+    UnknownPatternField(String),
+    /// A `not` pattern whose inner pattern binds names.
+    BindingInNotPattern(String),
+    /// A call to a value of function type supplies the wrong number of
+    /// arguments.
+    ArgumentCountMismatch(String),
+    /// A call argument cannot have the declared parameter type.
+    MismatchedArgument(String),
+    /// Two impls of the same trait could apply to the same type. Carries
+    /// the span of the earlier impl, which gets a label of its own.
+    ConflictingImpls(String, Span),
+    /// A `self` or `itself` parameter on a function outside an `impl`,
+    /// `trait`, `struct` or `enum` body.
+    ReceiverOutsideMethod(String),
+    /// An argument for a `comptime` parameter that is not a compile-time
+    /// constant.
+    NonConstantComptimeArgument(String),
+    /// A `catch` arm eliminating errors from a value whose type is not
+    /// error-qualified.
+    CatchWithoutErrors(String),
+    /// A `static_assert` whose condition evaluated to false; holds the
+    /// assertion's message.
+    StaticAssertionFailed(String),
+    /// An integer `/` or `%` whose divisor is a compile-time zero.
+    DivisionByZero(String),
+    /// A `splice` of quoted code inside that same code's splice.
+    RecursiveSplice(String),
+    /// A type alias whose expansion leads back to itself.
+    CyclicTypeAlias(String),
+    /// A cast `x.as(T)` between types whose values cannot be converted.
+    InvalidCast(String),
+    /// A field access `x.f` on a struct that has no field or method `f`.
+    UnknownField(String),
+    /// A positional argument following a keyword argument, `f(.a = 1, 2)`.
+    MisorderedArgument(String),
+    /// A keyword argument `.name = value` naming no parameter of the callee.
+    UnknownParameter(String),
+    /// A parameter given an argument both positionally and by keyword, or by
+    /// two keyword arguments.
+    DuplicateArgument(String),
+    /// A call to a function with an `implicit` parameter where no local of
+    /// the parameter's type is in scope.
+    UnresolvedImplicit(String),
+    /// An integer literal whose value does not fit in 64 bits.
+    IntegerOverflow(String),
 }
 
 impl LoweringError {
@@ -93,21 +167,132 @@ impl LoweringError {
     pub fn invalid_type_expr(msg: impl Into<String>, span: Span) -> Self {
         Self::new(LoweringErrorKind::InvalidTypeExpr(msg.into()), span)
     }
+
+    pub fn mismatched_pattern(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::MismatchedPattern(msg.into()), span)
+    }
+
+    pub fn inconsistent_or_bindings(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::InconsistentOrBindings(msg.into()), span)
+    }
+
+    pub fn unknown_pattern_field(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::UnknownPatternField(msg.into()), span)
+    }
+
+    pub fn binding_in_not_pattern(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::BindingInNotPattern(msg.into()), span)
+    }
+
+    pub fn argument_count_mismatch(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::ArgumentCountMismatch(msg.into()), span)
+    }
+
+    pub fn mismatched_argument(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::MismatchedArgument(msg.into()), span)
+    }
+
+    pub fn receiver_outside_method(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::ReceiverOutsideMethod(msg.into()), span)
+    }
+
+    pub fn non_constant_comptime_argument(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(
+            LoweringErrorKind::NonConstantComptimeArgument(msg.into()),
+            span,
+        )
+    }
+
+    pub fn catch_without_errors(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::CatchWithoutErrors(msg.into()), span)
+    }
+
+    pub fn static_assertion_failed(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::StaticAssertionFailed(msg.into()), span)
+    }
+
+    pub fn division_by_zero(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::DivisionByZero(msg.into()), span)
+    }
+
+    pub fn recursive_splice(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::RecursiveSplice(msg.into()), span)
+    }
+
+    pub fn cyclic_type_alias(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::CyclicTypeAlias(msg.into()), span)
+    }
+
+    pub fn invalid_cast(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::InvalidCast(msg.into()), span)
+    }
+
+    pub fn unknown_field(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::UnknownField(msg.into()), span)
+    }
+
+    pub fn misordered_argument(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::MisorderedArgument(msg.into()), span)
+    }
+
+    pub fn unknown_parameter(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::UnknownParameter(msg.into()), span)
+    }
+
+    pub fn duplicate_argument(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::DuplicateArgument(msg.into()), span)
+    }
+
+    pub fn unresolved_implicit(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::UnresolvedImplicit(msg.into()), span)
+    }
+
+    pub fn integer_overflow(msg: impl Into<String>, span: Span) -> Self {
+        Self::new(LoweringErrorKind::IntegerOverflow(msg.into()), span)
+    }
+
+    pub fn conflicting_impls(msg: impl Into<String>, first_impl: Span, span: Span) -> Self {
+        Self::new(
+            LoweringErrorKind::ConflictingImpls(msg.into(), first_impl),
+            span,
+        )
+    }
 }
 
 impl FlurryError for LoweringError {
     fn error_code(&self) -> u32 {
         match &self.kind {
-            LoweringErrorKind::UnsupportedNode(_) => 2001,
-            LoweringErrorKind::MalformedAst(_) => 2002,
-            LoweringErrorKind::UnsupportedClause(_) => 2003,
-            LoweringErrorKind::InvalidParameter(_) => 2004,
-            LoweringErrorKind::InvalidPattern(_) => 2005,
-            LoweringErrorKind::InvalidItemInContext(_) => 2006,
-            LoweringErrorKind::InvalidEnumVariant(_) => 2007,
-            LoweringErrorKind::InvalidStructField(_) => 2008,
-            LoweringErrorKind::MissingIdentifier => 2009,
-            LoweringErrorKind::InvalidTypeExpr(_) => 2010,
+            LoweringErrorKind::UnsupportedNode(_) => LOWERING_ERROR_BASE + 1,
+            LoweringErrorKind::MalformedAst(_) => LOWERING_ERROR_BASE + 2,
+            LoweringErrorKind::UnsupportedClause(_) => LOWERING_ERROR_BASE + 3,
+            LoweringErrorKind::InvalidParameter(_) => LOWERING_ERROR_BASE + 4,
+            LoweringErrorKind::InvalidPattern(_) => LOWERING_ERROR_BASE + 5,
+            LoweringErrorKind::InvalidItemInContext(_) => LOWERING_ERROR_BASE + 6,
+            LoweringErrorKind::InvalidEnumVariant(_) => LOWERING_ERROR_BASE + 7,
+            LoweringErrorKind::InvalidStructField(_) => LOWERING_ERROR_BASE + 8,
+            LoweringErrorKind::MissingIdentifier => LOWERING_ERROR_BASE + 9,
+            LoweringErrorKind::InvalidTypeExpr(_) => LOWERING_ERROR_BASE + 10,
+            LoweringErrorKind::MismatchedPattern(_) => LOWERING_ERROR_BASE + 11,
+            LoweringErrorKind::InconsistentOrBindings(_) => LOWERING_ERROR_BASE + 12,
+            LoweringErrorKind::UnknownPatternField(_) => LOWERING_ERROR_BASE + 13,
+            LoweringErrorKind::BindingInNotPattern(_) => LOWERING_ERROR_BASE + 14,
+            LoweringErrorKind::ArgumentCountMismatch(_) => LOWERING_ERROR_BASE + 15,
+            LoweringErrorKind::MismatchedArgument(_) => LOWERING_ERROR_BASE + 16,
+            LoweringErrorKind::ConflictingImpls(..) => LOWERING_ERROR_BASE + 17,
+            LoweringErrorKind::ReceiverOutsideMethod(_) => LOWERING_ERROR_BASE + 18,
+            LoweringErrorKind::NonConstantComptimeArgument(_) => LOWERING_ERROR_BASE + 19,
+            LoweringErrorKind::CatchWithoutErrors(_) => LOWERING_ERROR_BASE + 20,
+            LoweringErrorKind::StaticAssertionFailed(_) => LOWERING_ERROR_BASE + 21,
+            LoweringErrorKind::DivisionByZero(_) => LOWERING_ERROR_BASE + 22,
+            LoweringErrorKind::RecursiveSplice(_) => LOWERING_ERROR_BASE + 23,
+            LoweringErrorKind::CyclicTypeAlias(_) => LOWERING_ERROR_BASE + 24,
+            LoweringErrorKind::InvalidCast(_) => LOWERING_ERROR_BASE + 25,
+            LoweringErrorKind::UnknownField(_) => LOWERING_ERROR_BASE + 26,
+            LoweringErrorKind::MisorderedArgument(_) => LOWERING_ERROR_BASE + 27,
+            LoweringErrorKind::UnknownParameter(_) => LOWERING_ERROR_BASE + 28,
+            LoweringErrorKind::DuplicateArgument(_) => LOWERING_ERROR_BASE + 29,
+            LoweringErrorKind::UnresolvedImplicit(_) => LOWERING_ERROR_BASE + 30,
+            LoweringErrorKind::IntegerOverflow(_) => LOWERING_ERROR_BASE + 31,
         }
     }
 
@@ -123,6 +308,27 @@ impl FlurryError for LoweringError {
             LoweringErrorKind::InvalidStructField(_) => "invalid struct field",
             LoweringErrorKind::MissingIdentifier => "missing identifier",
             LoweringErrorKind::InvalidTypeExpr(_) => "invalid type expression",
+            LoweringErrorKind::MismatchedPattern(_) => "mismatched pattern",
+            LoweringErrorKind::InconsistentOrBindings(_) => "inconsistent or-pattern bindings",
+            LoweringErrorKind::UnknownPatternField(_) => "unknown field in pattern",
+            LoweringErrorKind::BindingInNotPattern(_) => "binding in `not` pattern",
+            LoweringErrorKind::ArgumentCountMismatch(_) => "wrong number of arguments",
+            LoweringErrorKind::MismatchedArgument(_) => "mismatched argument type",
+            LoweringErrorKind::ConflictingImpls(..) => "conflicting implementation",
+            LoweringErrorKind::ReceiverOutsideMethod(_) => "receiver outside of a method",
+            LoweringErrorKind::NonConstantComptimeArgument(_) => "non-constant comptime argument",
+            LoweringErrorKind::CatchWithoutErrors(_) => "catch without errors",
+            LoweringErrorKind::StaticAssertionFailed(_) => "static assertion failed",
+            LoweringErrorKind::DivisionByZero(_) => "division by zero",
+            LoweringErrorKind::RecursiveSplice(_) => "recursive splice",
+            LoweringErrorKind::CyclicTypeAlias(_) => "cyclic type alias",
+            LoweringErrorKind::InvalidCast(_) => "invalid cast",
+            LoweringErrorKind::UnknownField(_) => "unknown field",
+            LoweringErrorKind::MisorderedArgument(_) => "misordered argument",
+            LoweringErrorKind::UnknownParameter(_) => "unknown parameter",
+            LoweringErrorKind::DuplicateArgument(_) => "duplicate argument",
+            LoweringErrorKind::UnresolvedImplicit(_) => "unresolved implicit argument",
+            LoweringErrorKind::IntegerOverflow(_) => "integer literal out of range",
         }
     }
 
@@ -156,12 +362,78 @@ impl FlurryError for LoweringError {
             LoweringErrorKind::InvalidTypeExpr(msg) => {
                 format!("invalid type expression: {}", msg)
             }
+            LoweringErrorKind::MismatchedPattern(msg) => {
+                format!("mismatched pattern: {}", msg)
+            }
+            LoweringErrorKind::InconsistentOrBindings(msg) => {
+                format!("or-pattern alternatives bind different names: {}", msg)
+            }
+            LoweringErrorKind::UnknownPatternField(msg) => {
+                format!("unknown field in pattern: {}", msg)
+            }
+            LoweringErrorKind::BindingInNotPattern(msg) => {
+                format!("`not` patterns cannot bind names: {}", msg)
+            }
+            LoweringErrorKind::ArgumentCountMismatch(msg) => {
+                format!("wrong number of arguments: {}", msg)
+            }
+            LoweringErrorKind::MismatchedArgument(msg) => {
+                format!("mismatched argument type: {}", msg)
+            }
+            LoweringErrorKind::ConflictingImpls(msg, _) => {
+                format!("conflicting implementations: {}", msg)
+            }
+            LoweringErrorKind::ReceiverOutsideMethod(msg) => {
+                format!("receiver outside of a method: {}", msg)
+            }
+            LoweringErrorKind::NonConstantComptimeArgument(msg) => {
+                format!("comptime argument is not a constant: {}", msg)
+            }
+            LoweringErrorKind::CatchWithoutErrors(msg) => {
+                format!("nothing to catch: {}", msg)
+            }
+            LoweringErrorKind::StaticAssertionFailed(msg) => {
+                format!("static assertion failed: {}", msg)
+            }
+            LoweringErrorKind::DivisionByZero(msg) => {
+                format!("division by zero: {}", msg)
+            }
+            LoweringErrorKind::RecursiveSplice(msg) => {
+                format!("recursive splice: {}", msg)
+            }
+            LoweringErrorKind::CyclicTypeAlias(msg) => {
+                format!("cyclic type alias: {}", msg)
+            }
+            LoweringErrorKind::InvalidCast(msg) => {
+                format!("invalid cast: {}", msg)
+            }
+            LoweringErrorKind::UnknownField(msg) => {
+                format!("unknown field: {}", msg)
+            }
+            LoweringErrorKind::MisorderedArgument(msg) => {
+                format!("misordered argument: {}", msg)
+            }
+            LoweringErrorKind::UnknownParameter(msg) => {
+                format!("unknown parameter: {}", msg)
+            }
+            LoweringErrorKind::DuplicateArgument(msg) => {
+                format!("duplicate argument: {}", msg)
+            }
+            LoweringErrorKind::UnresolvedImplicit(msg) => {
+                format!("unresolved implicit argument: {}", msg)
+            }
+            LoweringErrorKind::IntegerOverflow(msg) => {
+                format!("integer literal out of range: {}", msg)
+            }
         };
 
-        DiagnosticBuilder::error(message)
+        let mut builder = DiagnosticBuilder::error(message)
             .with_code(self.error_code())
             .with_primary_span(self.span)
-            .with_error_label(self.span, self.error_name().to_string())
-            .emit(diag_ctx);
+            .with_error_label(self.span, self.error_name().to_string());
+        if let LoweringErrorKind::ConflictingImpls(_, first_impl) = &self.kind {
+            builder = builder.with_note_label(*first_impl, "first implementation here".to_string());
+        }
+        builder.emit(diag_ctx);
     }
 }
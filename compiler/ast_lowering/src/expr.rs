@@ -1,19 +1,20 @@
 //! Expression lowering — AST expression nodes → HIR [`Expr`].
 
 use ast::{NodeIndex, NodeKind};
+use diagnostic::FlurryError;
 use hir::{
-    body::Body,
+    body::{Body, Param},
     common::{
-        Arg, BinOp, Ident, Lit, LitKind, Path, PathAnchor, PathSegment, Symbol, TyParam,
-        TyParamKind, UnOp,
+        Arg, BinOp, Ident, Lit, LitKind, Path, PathAnchor, PathSegment, Symbol, TPARAM_CATCH,
+        TyParam, TyParamKind, UnOp,
     },
     decl::LetDecl,
-    expr::{Block, ClosureParam, CondictionArm, Expr, ExprKind},
+    expr::{Block, ClosureParam, CondictionArm, Expr, ExprKind, FieldExpr},
     pattern::{Pattern, PatternArm, PatternKind},
 };
 use rustc_span::Span;
 
-use crate::LoweringContext;
+use crate::{LoweringContext, SurroundingContext};
 
 pub(crate) enum ExprContext {
     // comptime contexts
@@ -46,7 +47,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         match kind {
             NodeKind::Id => Expr {
                 hir_id: self.next_hir_id(),
-                kind: ExprKind::Ident(self.node_to_symbol(node)),
+                kind: ExprKind::Ident(self.hygienic_use(self.node_to_symbol(node))),
                 span,
             },
             NodeKind::SelfLower => Expr {
@@ -63,15 +64,21 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 let base_expr = self.lower_expr(children[0]);
                 let base_ref = self.arena.alloc_expr(base_expr);
                 let field_ident = self.node_to_ident(children[1]);
+                let hir_id = self.next_hir_id();
+                self.defer_field_access(hir_id, base_ref, field_ident.clone());
                 Expr {
-                    hir_id: self.next_hir_id(),
+                    hir_id,
                     kind: ExprKind::Projection(base_ref, field_ident),
                     span,
                 }
             }
             NodeKind::Int => {
                 let text = self.source_text(node);
-                let val = text.replace("_", "").parse::<i64>().unwrap_or(0);
+                let val = int_literal_value(&text).unwrap_or_else(|| {
+                    let msg = format!("`{}` does not fit in 64 bits", text);
+                    self.emit_integer_overflow(&msg, span);
+                    0
+                });
                 self.make_lit_expr(LitKind::Integer(val), span)
             }
             NodeKind::Real => {
@@ -81,12 +88,16 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             }
             NodeKind::Str => {
                 let text = self.source_text(node);
-                // Strip surrounding quotes if present
-                let inner = text
-                    .strip_prefix('"')
-                    .and_then(|s| s.strip_suffix('"'))
-                    .unwrap_or(&text);
-                self.make_lit_expr(LitKind::String(inner.to_string()), span)
+                let value = match lex::str_literal_value(&text) {
+                    Ok(value) => value,
+                    Err(err) => {
+                        // Escape positions are relative to the literal.
+                        err.emit(self.diag_ctx, span.lo());
+                        let (_, contents) = lex::StrStyle::of(&text);
+                        text[contents].to_string()
+                    }
+                };
+                self.make_lit_expr(LitKind::String(value), span)
             }
             NodeKind::Char => {
                 let text = self.source_text(node);
@@ -142,6 +153,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 let op = self.lower_binop(kind);
                 let lhs = self.lower_expr(children[0]);
                 let rhs = self.lower_expr(children[1]);
+                self.check_const_divisor(op, &lhs, &rhs);
                 let lhs_ref = self.arena.alloc_expr(lhs);
                 let rhs_ref = self.arena.alloc_expr(rhs);
                 Expr {
@@ -152,21 +164,11 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             }
             NodeKind::Negative => {
                 let inner = self.lower_expr(children[0]);
-                let inner_ref = self.arena.alloc_expr(inner);
-                Expr {
-                    hir_id: self.next_hir_id(),
-                    kind: ExprKind::Unary(UnOp::Neg, inner_ref),
-                    span,
-                }
+                self.fold_unary(UnOp::Neg, inner, span)
             }
             NodeKind::BoolNot => {
                 let inner = self.lower_expr(children[0]);
-                let inner_ref = self.arena.alloc_expr(inner);
-                Expr {
-                    hir_id: self.next_hir_id(),
-                    kind: ExprKind::Unary(UnOp::Not, inner_ref),
-                    span,
-                }
+                self.fold_unary(UnOp::Not, inner, span)
             }
             NodeKind::Refer => {
                 let inner = self.lower_expr(children[0]);
@@ -208,11 +210,16 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                     })
                     .collect();
                 let args_slice = self.arena.alloc_arg_slice(args);
-                Expr {
+                if let Some(builtin) = self.builtin_callee(callee_ref).filter(|b| b.is_comptime()) {
+                    return self.lower_comptime_builtin(builtin, args_slice, span);
+                }
+                let call = Expr {
                     hir_id: self.next_hir_id(),
                     kind: ExprKind::Application(callee_ref, args_slice),
                     span,
-                }
+                };
+                self.check_call(callee_ref, args_slice, &call);
+                call
             }
             NodeKind::ExtendedApplication => {
                 let callee = self.lower_expr(children[0]);
@@ -268,9 +275,57 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                     span,
                 }
             }
+            NodeKind::Object => {
+                let elems_node = children[0];
+                let entry_nodes = self.ast.get_multi_child_slice(elems_node).unwrap_or(&[]);
+                let mut elems = Vec::new();
+                let mut fields = Vec::new();
+                for &entry in entry_nodes {
+                    match self.ast.get_node(entry) {
+                        // `name: expr`
+                        Some((NodeKind::Property, field_span, property)) => {
+                            let ident = self.node_to_ident(property[0]);
+                            let expr = self.lower_expr(property[1]);
+                            fields.push(FieldExpr {
+                                ident,
+                                expr: self.arena.alloc_expr(expr),
+                                span: field_span,
+                            });
+                        }
+                        _ => elems.push(self.lower_expr(entry)),
+                    }
+                }
+                Expr {
+                    hir_id: self.next_hir_id(),
+                    kind: ExprKind::Object(
+                        self.arena.alloc_expr_slice(elems),
+                        self.arena.alloc_field_expr_slice(fields),
+                    ),
+                    span,
+                }
+            }
+            NodeKind::QuoteBlock => {
+                let hir_id = self.next_hir_id();
+                self.quotes.insert(hir_id, children[0]);
+                Expr {
+                    hir_id,
+                    kind: ExprKind::Quote,
+                    span,
+                }
+            }
+            NodeKind::UnsafeBlock => {
+                self.push_surrounding_ctx(SurroundingContext::Unsafe);
+                let block = self.lower_block(node);
+                self.pop_surrounding_ctx();
+                let block_ref = self.arena.alloc_block(block);
+                Expr {
+                    hir_id: self.next_hir_id(),
+                    kind: ExprKind::Block(block_ref),
+                    span,
+                }
+            }
             NodeKind::Block
             | NodeKind::DoBlock
-            | NodeKind::UnsafeBlock
             | NodeKind::AsyncBlock
             | NodeKind::ComptimeBlock => {
                 let block = self.lower_block(node);
@@ -301,16 +356,14 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             NodeKind::PostMatch => {
                 let scrutinee = self.lower_expr(children[0]);
                 let scrutinee_ref = self.arena.alloc_expr(scrutinee);
-                let arms_node = children[1];
-                let arm_nodes = self.ast.get_multi_child_slice(arms_node).unwrap_or(&[]);
-                let arms: Vec<_> = arm_nodes.iter().map(|&n| self.lower_match_arm(n)).collect();
-                let arms_slice = self.arena.alloc_arm_slice(arms);
+                let arms_slice = self.lower_match_arms(scrutinee_ref, children[1]);
                 Expr {
                     hir_id: self.next_hir_id(),
                     kind: ExprKind::Match(scrutinee_ref, arms_slice),
                     span,
                 }
             }
+            NodeKind::ErrorElimination => self.lower_error_elimination(node, span),
             NodeKind::Assign => {
                 let lhs = self.lower_expr(children[0]);
                 let rhs = self.lower_expr(children[1]);
@@ -335,6 +388,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 };
                 let lhs = self.lower_expr(children[0]);
                 let rhs = self.lower_expr(children[1]);
+                self.check_const_divisor(op, &lhs, &rhs);
                 let lhs_ref = self.arena.alloc_expr(lhs);
                 let rhs_ref = self.arena.alloc_expr(rhs);
                 Expr {
@@ -421,6 +475,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 let ty = self.lower_expr(children[1]);
                 let expr_ref = self.arena.alloc_expr(expr);
                 let ty_ref = self.arena.alloc_expr(ty);
+                self.check_cast(expr_ref, ty_ref, span);
                 Expr {
                     hir_id: self.next_hir_id(),
                     kind: ExprKind::Cast(expr_ref, ty_ref),
@@ -428,6 +483,9 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 }
             }
             NodeKind::Lambda => self.lower_lambda_expr(node, span),
+            NodeKind::BoolForall | NodeKind::BoolExists => self.lower_quantifier(node, span),
+            NodeKind::ForallType => self.lower_forall_type(node, span),
+            NodeKind::PipePrepend => self.lower_pipe(children[0], children[1], span),
             NodeKind::PostLambda => {
                 // Lower as a call with the lambda as the last argument
                 let callee = self.lower_expr(children[0]);
@@ -460,15 +518,73 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                     span,
                 }
             }
-            NodeKind::FnType => self.lower_fn_type_expr(node, span),
+            NodeKind::FnType => self.lower_fn_type_expr(node, None, span),
+            NodeKind::ClosureQualifiedType => {
+                // ClosureQualifiedType: a, b  (^closure_expr type_expr)
+                let env = self.lower_expr(children[0]);
+                let env_ref = self.arena.alloc_expr(env);
+                let ty = self.lower_expr(children[1]);
+                let ty_ref = self.arena.alloc_expr(ty);
+                Expr {
+                    hir_id: self.next_hir_id(),
+                    kind: ExprKind::TyClosureQualified(env_ref, ty_ref),
+                    span,
+                }
+            }
+            NodeKind::ErrorQualifiedType => {
+                // ErrorQualifiedType: a, b  (!error_list type_expr)
+                let errors = self.lower_expr(children[0]);
+                let errors_ref = self.arena.alloc_expr(errors);
+                let ty = self.lower_expr(children[1]);
+                let ty_ref = self.arena.alloc_expr(ty);
+                Expr {
+                    hir_id: self.next_hir_id(),
+                    kind: ExprKind::ErrorQualifiedType(errors_ref, ty_ref),
+                    span,
+                }
+            }
+            NodeKind::EffectQualifiedType => {
+                // EffectQualifiedType: a, b  (#effect_list type_expr)
+                let effects = self.lower_effect_row(children[0]);
+                let effects_ref = self.arena.alloc_expr(effects);
+                let ty = self.lower_expr(children[1]);
+                let ty_ref = self.arena.alloc_expr(ty);
+                Expr {
+                    hir_id: self.next_hir_id(),
+                    kind: ExprKind::EffectQualifiedType(effects_ref, ty_ref),
+                    span,
+                }
+            }
+            NodeKind::Arrow
+                if matches!(
+                    self.ast.get_node(children[0]),
+                    Some((NodeKind::FnType, _, _))
+                ) =>
+            {
+                // `fn(A, B) -> R`: the return type belongs to the `fn` type.
+                let output = self.lower_expr(children[1]);
+                let output_ref = self.arena.alloc_expr(output);
+                self.lower_fn_type_expr(children[0], Some(output_ref), span)
+            }
             NodeKind::Arrow => {
+                // `A -> B`: a `fn` type of one parameter.
                 let input = self.lower_expr(children[0]);
-                let output = self.lower_expr(children[1]);
                 let input_ref = self.arena.alloc_expr(input);
+                let output = self.lower_expr(children[1]);
                 let output_ref = self.arena.alloc_expr(output);
+                let param = TyParam::new(
+                    self.next_hir_id(),
+                    TyParamKind::Positional(input_ref),
+                    input_ref.span,
+                );
                 Expr {
                     hir_id: self.next_hir_id(),
-                    kind: ExprKind::TyFnArrow(input_ref, output_ref),
+                    kind: ExprKind::TyFn {
+                        modifiers: 0,
+                        abi: None,
+                        params: self.arena.alloc_ty_param_slice(vec![param]),
+                        ret: Some(output_ref),
+                    },
                     span,
                 }
             }
@@ -486,6 +602,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 let arm_true = PatternArm {
                     hir_id: self.next_hir_id(),
                     pat,
+                    guard: None,
                     body: true_ref,
                     span,
                 };
@@ -496,6 +613,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 let arm_false = PatternArm {
                     hir_id: self.next_hir_id(),
                     pat: wild_pat,
+                    guard: None,
                     body: false_ref,
                     span,
                 };
@@ -520,6 +638,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 let arm_then = PatternArm {
                     hir_id: self.next_hir_id(),
                     pat,
+                    guard: None,
                     body: then_ref,
                     span,
                 };
@@ -538,6 +657,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 let arm_else = PatternArm {
                     hir_id: self.next_hir_id(),
                     pat: wild_pat,
+                    guard: None,
                     body: else_ref,
                     span,
                 };
@@ -555,9 +675,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             NodeKind::IfMatch => {
                 let scrutinee = self.lower_expr(children[0]);
                 let scrutinee_ref = self.arena.alloc_expr(scrutinee);
-                let arm_nodes = self.ast.get_multi_child_slice(children[1]).unwrap_or(&[]);
-                let arms: Vec<_> = arm_nodes.iter().map(|&n| self.lower_match_arm(n)).collect();
-                let arms_slice = self.arena.alloc_arm_slice(arms);
+                let arms_slice = self.lower_match_arms(scrutinee_ref, children[1]);
                 Expr {
                     hir_id: self.next_hir_id(),
                     kind: ExprKind::Match(scrutinee_ref, arms_slice),
@@ -699,6 +817,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 let arm_body = PatternArm {
                     hir_id: self.next_hir_id(),
                     pat,
+                    guard: None,
                     body: body_ref,
                     span,
                 };
@@ -713,6 +832,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 let arm_break = PatternArm {
                     hir_id: self.next_hir_id(),
                     pat: wild_pat,
+                    guard: None,
                     body: break_ref,
                     span,
                 };
@@ -744,9 +864,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             NodeKind::WhileMatch => {
                 let scrutinee = self.lower_expr(children[1]);
                 let scrutinee_ref = self.arena.alloc_expr(scrutinee);
-                let arm_nodes = self.ast.get_multi_child_slice(children[2]).unwrap_or(&[]);
-                let arms: Vec<_> = arm_nodes.iter().map(|&n| self.lower_match_arm(n)).collect();
-                let arms_slice = self.arena.alloc_arm_slice(arms);
+                let arms_slice = self.lower_match_arms(scrutinee_ref, children[2]);
                 let match_expr = Expr {
                     hir_id: self.next_hir_id(),
                     kind: ExprKind::Match(scrutinee_ref, arms_slice),
@@ -823,6 +941,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
     fn lower_stmts_to_block(&mut self, stmt_nodes: &[NodeIndex], span: Span) -> Block<'hir> {
         let mut stmts: Vec<Expr<'hir>> = Vec::new();
         let mut trailing_expr: Option<&'hir Expr<'hir>> = None;
+        let outer_locals = self.locals.len();
 
         for (i, &stmt_node) in stmt_nodes.iter().enumerate() {
             if stmt_node == 0 {
@@ -864,6 +983,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 // Let / Const declarations → ExprKind::Let
                 Some(NodeKind::LetDecl | NodeKind::ConstDecl) => {
                     let let_decl = self.lower_let_decl(stmt_node);
+                    self.declare_local(let_decl.name.name, let_decl.ty, let_decl.init);
                     let let_ref = self.arena.alloc_let_decl(let_decl);
                     let stmt_span = self.ast.get_span(stmt_node).unwrap_or(span);
                     stmts.push(Expr {
@@ -912,6 +1032,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                                 | NodeKind::ImplDef
                                 | NodeKind::ImplTraitDef
                                 | NodeKind::TypealiasDef
+                                | NodeKind::NewtypeDef
                         )
                     ) {
                         let owner_id = self.lower_item_in_block(def_node);
@@ -954,6 +1075,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             }
         }
 
+        self.locals.truncate(outer_locals);
         let stmts_slice = self.arena.alloc_expr_slice(stmts);
         Block {
             hir_id: self.next_hir_id(),
@@ -1049,12 +1171,16 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
 
         // some? => body
         let loop_pat = self.lower_pattern(pat_node);
+        let loop_pat = self.arena.alloc_pattern(loop_pat);
         let some_pat = Pattern {
             hir_id: self.next_hir_id(),
-            kind: PatternKind::OptionSome(self.arena.alloc_pattern(loop_pat)),
+            kind: PatternKind::OptionSome(loop_pat),
             span,
         };
+        let outer_locals = self.locals.len();
+        self.declare_pattern_bindings(loop_pat, None);
         let body_block = self.lower_block(body_node);
+        self.locals.truncate(outer_locals);
         let body_block_ref = self.arena.alloc_block(body_block);
         let body_block_expr = Expr {
             hir_id: self.next_hir_id(),
@@ -1065,6 +1191,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         let arm_some = PatternArm {
             hir_id: self.next_hir_id(),
             pat: some_pat,
+            guard: None,
             body: body_expr_ref,
             span,
         };
@@ -1084,6 +1211,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         let arm_none = PatternArm {
             hir_id: self.next_hir_id(),
             pat: none_pat,
+            guard: None,
             body: break_ref,
             span,
         };
@@ -1137,7 +1265,8 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         // LetDecl.name: Ident — for simple name bindings; complex patterns
         // are not yet representable in LetDecl (see ambiguities table).
         let name = if children[0] != 0 {
-            self.node_to_ident(children[0])
+            let ident = self.node_to_ident(children[0]);
+            self.hygienic_binding(ident)
         } else {
             Ident::new(Symbol::intern("_"), span)
         };
@@ -1197,7 +1326,97 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         }
     }
 
-    fn lower_match_arm(&mut self, node: NodeIndex) -> PatternArm<'hir> {
+    /// Lower the `case_arm*` list of a match-like expression and check the
+    /// arms against the (already lowered) scrutinee.
+    fn lower_match_arms(
+        &mut self,
+        scrutinee: &Expr<'hir>,
+        arms_node: NodeIndex,
+    ) -> &'hir [PatternArm<'hir>] {
+        let scrutinee_ty = self.infer_type(scrutinee);
+        let arm_nodes = self.ast.get_multi_child_slice(arms_node).unwrap_or(&[]);
+        let arms: Vec<_> = arm_nodes
+            .iter()
+            .map(|&n| self.lower_match_arm(n, scrutinee_ty))
+            .collect();
+        self.check_match_arms(scrutinee, scrutinee_ty, &arms);
+        self.arena.alloc_arm_slice(arms)
+    }
+
+    fn lower_error_elimination(&mut self, node: NodeIndex, span: Span) -> Expr<'hir> {
+        // ErrorElimination: a, N  (expr, catch_arm | case_arm*)
+        let children = self.ast.get_children(node);
+        let expr = self.lower_expr(children[0]);
+        let expr_ref = self.arena.alloc_expr(expr);
+
+        // The catch parameter has the type of the error set being
+        // eliminated, as far as the syntax tells it.
+        let expr_ty = self.infer_type(expr_ref);
+        let errors = match expr_ty.map(|ty| &ty.kind) {
+            Some(ExprKind::ErrorQualifiedType(errors, _)) => Some(*errors),
+            _ => None,
+        };
+
+        let arm_nodes = self.ast.get_multi_child_slice(children[1]).unwrap_or(&[]);
+        let mut arms = Vec::new();
+        let mut catch = None;
+        for &arm_node in arm_nodes {
+            let Some((NodeKind::CatchArm, arm_span, arm_children)) = self.ast.get_node(arm_node)
+            else {
+                arms.push(self.lower_match_arm(arm_node, expr_ty));
+                continue;
+            };
+            if catch.is_some() {
+                self.emit_malformed("error elimination with more than one catch arm", arm_span);
+                continue;
+            }
+            if errors.is_none() && expr_ty.is_some() {
+                self.emit_catch_without_errors(
+                    "the eliminated value's type has no error set",
+                    expr_ref.span,
+                );
+            }
+
+            // CatchArm: a, b  (id, body)
+            let name = self.node_to_ident(arm_children[0]);
+            let param = Param {
+                hir_id: self.next_hir_id(),
+                span: name.span,
+                name,
+                ty: errors,
+                attrs: &[],
+                flags: TPARAM_CATCH,
+            };
+            let outer_locals = self.locals.len();
+            self.declare_local(param.name.name, param.ty, None);
+            let body = self.lower_expr(arm_children[1]);
+            self.locals.truncate(outer_locals);
+            catch = Some((
+                self.arena.alloc_param(param) as &_,
+                self.arena.alloc_expr(body) as &_,
+            ));
+        }
+        self.check_match_arms(expr_ref, expr_ty, &arms);
+
+        Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::ErrorElimination {
+                expr: expr_ref,
+                arms: self.arena.alloc_arm_slice(arms),
+                catch,
+            },
+            span,
+        }
+    }
+
+    /// Lower one `case_arm` of a match whose scrutinee has the type
+    /// `scrutinee_ty`, if known; the names its pattern binds are typed
+    /// accordingly in the arm's guard and body.
+    fn lower_match_arm(
+        &mut self,
+        node: NodeIndex,
+        scrutinee_ty: Option<&'hir Expr<'hir>>,
+    ) -> PatternArm<'hir> {
         let Some((NodeKind::CaseArm, span, children)) = self.ast.get_node(node) else {
             unreachable!(
                 "not a pattern case arm node or invalid pattern arm node: no such node index {:?}",
@@ -1206,27 +1425,29 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         };
 
         // CaseArm: a, b  (pattern, body)
-        let (pat, body) = {
-            // Strip IfGuard wrapper — guards not yet representable in PatternArm
-            let pat_node = children[0];
-            let pat = if self.ast.get_node_kind(pat_node) == Some(NodeKind::IfGuardPattern) {
-                let guard_children = self.ast.get_children(pat_node);
-                if !guard_children.is_empty() {
-                    self.lower_pattern(guard_children[0])
-                } else {
-                    self.lower_pattern(pat_node)
-                }
-            } else {
-                self.lower_pattern(pat_node)
-            };
-            let body = self.lower_expr(children[1]);
-            let body_ref = self.arena.alloc_expr(body);
-            (pat, body_ref)
+        // A top-level `pat if cond` (IfGuardPattern: a, b) becomes the arm's
+        // guard; the arm only fires when `pat` matches and `cond` holds.
+        let pat_node = children[0];
+        let outer_locals = self.locals.len();
+        let (pat, guard) = if self.ast.get_node_kind(pat_node) == Some(NodeKind::IfGuardPattern) {
+            let guard_children = self.ast.get_children(pat_node);
+            let pat = self.lower_pattern(guard_children[0]);
+            self.declare_pattern_bindings(&pat, scrutinee_ty);
+            let cond = self.lower_expr(guard_children[1]);
+            (pat, Some(self.arena.alloc_expr(cond) as &_))
+        } else {
+            let pat = self.lower_pattern(pat_node);
+            self.declare_pattern_bindings(&pat, scrutinee_ty);
+            (pat, None)
         };
+        let body = self.lower_expr(children[1]);
+        self.locals.truncate(outer_locals);
+        let body = self.arena.alloc_expr(body);
 
         PatternArm {
             hir_id: self.next_hir_id(),
             pat,
+            guard,
             body,
             span,
         }
@@ -1261,7 +1482,79 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         };
 
         // Create a body for the closure
+        let outer_locals = self.locals.len();
+        for param in params_slice {
+            if let PatternKind::Binding(_, ident, None) = &param.pat.kind {
+                self.declare_local(ident.name, param.ty, None);
+            }
+        }
         let body_expr = self.lower_expr(body_node);
+        let ret_ty = ret_ty.or_else(|| self.infer_type(&body_expr));
+        self.locals.truncate(outer_locals);
+
+        // Captures: the locals of enclosing bodies that the body uses.
+        let param_names: Vec<Symbol> = params_slice
+            .iter()
+            .flat_map(|param| param.pat.bindings())
+            .map(|ident| ident.name)
+            .collect();
+        let captures: Vec<Ident> = body_expr
+            .free_variables()
+            .into_iter()
+            .filter(|ident| !param_names.contains(&ident.name) && self.is_local(ident.name))
+            .collect();
+        let captures = self.arena.alloc_ident_slice(captures);
+
+        // The lambda's type: unannotated parameters are left as `_`, and a
+        // lambda with captures gets a closure-qualified type.
+        let ty_params: Vec<TyParam<'hir>> = params_slice
+            .iter()
+            .map(|param| {
+                let ty = param.ty.unwrap_or_else(|| {
+                    self.arena.alloc_expr(Expr {
+                        hir_id: self.next_hir_id(),
+                        kind: ExprKind::TyPlaceholder,
+                        span: param.span,
+                    })
+                });
+                TyParam::new(self.next_hir_id(), TyParamKind::Positional(ty), param.span)
+            })
+            .collect();
+        let ty = Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::TyFn {
+                modifiers: 0,
+                abi: None,
+                params: self.arena.alloc_ty_param_slice(ty_params),
+                ret: ret_ty,
+            },
+            span,
+        };
+        let mut ty = self.arena.alloc_expr(ty);
+        if !captures.is_empty() {
+            // `^(captures) fn(..)`: the lambda is only valid while the
+            // locals it captures are.
+            let env: Vec<Expr<'hir>> = captures
+                .iter()
+                .map(|capture| Expr {
+                    hir_id: self.next_hir_id(),
+                    kind: ExprKind::Ident(capture.name),
+                    span: capture.span,
+                })
+                .collect();
+            let env = Expr {
+                hir_id: self.next_hir_id(),
+                kind: ExprKind::Tuple(self.arena.alloc_expr_slice(env)),
+                span,
+            };
+            let qualified = Expr {
+                hir_id: self.next_hir_id(),
+                kind: ExprKind::TyClosureQualified(self.arena.alloc_expr(env), ty),
+                span,
+            };
+            ty = self.arena.alloc_expr(qualified);
+        }
+
         let body_expr_ref = self.arena.alloc_expr(body_expr);
         let body = Body {
             params: &[],
@@ -1272,7 +1565,90 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
 
         Expr {
             hir_id: closure_hir_id,
-            kind: ExprKind::Closure(params_slice, ret_ty, body_id),
+            kind: ExprKind::Lambda {
+                params: params_slice,
+                body: body_id,
+                captures,
+                ret: ret_ty,
+                ty,
+            },
+            span,
+        }
+    }
+
+    /// Lower `forall x: T => body` or `exists x: T => body`. The bound
+    /// variables are locals of the body only.
+    fn lower_quantifier(&mut self, node: NodeIndex, span: Span) -> Expr<'hir> {
+        // BoolForall / BoolExists: a, N  (body, type_bound_params)
+        let Some((kind, _, children)) = self.ast.get_node(node) else {
+            unreachable!("invalid quantifier node: no such node index {:?}", node);
+        };
+
+        let param_nodes = self.ast.get_multi_child_slice(children[1]).unwrap_or(&[]);
+        let params: Vec<ClosureParam<'hir>> = param_nodes
+            .iter()
+            .map(|&p| self.lower_closure_param(p))
+            .collect();
+        let params = self.arena.alloc_closure_param_slice(params);
+
+        let outer_locals = self.locals.len();
+        for param in params {
+            match &param.pat.kind {
+                PatternKind::Binding(_, ident, None) => {
+                    self.declare_local(ident.name, param.ty, None)
+                }
+                _ => self.declare_pattern_bindings(&param.pat, param.ty),
+            }
+        }
+        let body = self.lower_expr(children[0]);
+        self.locals.truncate(outer_locals);
+        let body = self.arena.alloc_expr(body);
+
+        let kind = match kind {
+            NodeKind::BoolForall => ExprKind::Forall { params, body },
+            _ => ExprKind::Exists { params, body },
+        };
+        Expr {
+            hir_id: self.next_hir_id(),
+            kind,
+            span,
+        }
+    }
+
+    /// Lower the polymorphic type `forall<T, ..> body`. Each parameter
+    /// binds a type variable that is in scope for the later parameters and
+    /// the body.
+    fn lower_forall_type(&mut self, node: NodeIndex, span: Span) -> Expr<'hir> {
+        // ForallType: a, N  (body, params)
+        let Some((NodeKind::ForallType, _, children)) = self.ast.get_node(node) else {
+            unreachable!(
+                "not a forall type node or invalid forall type node: no such node index {:?}",
+                node
+            );
+        };
+
+        let outer_locals = self.locals.len();
+        let param_nodes = self.ast.get_multi_child_slice(children[1]).unwrap_or(&[]);
+        let mut params = Vec::with_capacity(param_nodes.len());
+        for &p in param_nodes {
+            let Some((ident, mut param)) = self.lower_fn_param(p) else {
+                continue;
+            };
+            if let TyParamKind::Positional(ty) = param.kind {
+                param.kind = TyParamKind::PositionalDependencyCatched(ident.clone(), ty);
+            }
+            self.declare_local(ident.name, None, None);
+            params.push(param);
+        }
+        let body = self.lower_expr(children[0]);
+        self.locals.truncate(outer_locals);
+
+        Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::TyForall {
+                params: self.arena.alloc_ty_param_slice(params),
+                body: self.arena.alloc_expr(body),
+            },
             span,
         }
     }
@@ -1314,7 +1690,12 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         }
     }
 
-    fn lower_fn_type_expr(&mut self, node: NodeIndex, span: Span) -> Expr<'hir> {
+    fn lower_fn_type_expr(
+        &mut self,
+        node: NodeIndex,
+        ret: Option<&'hir Expr<'hir>>,
+        span: Span,
+    ) -> Expr<'hir> {
         // FnType: flags_u32, abi_node, N  (modifier_flags, abi_str_node, parameter_types)
         let Some((NodeKind::FnType, _, children)) = self.ast.get_node(node) else {
             unreachable!(
@@ -1323,31 +1704,62 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             );
         };
 
-        let _flags = children[0]; // raw u32 bitmask
-        let _abi_node = children[1];
+        let modifiers = children[0]; // raw u32 bitmask
+        let abi_node = children[1];
         let params_multi = children[2];
 
-        let param_nodes = self.ast.get_multi_child_slice(params_multi).unwrap_or(&[]);
+        let abi = (abi_node != 0).then(|| {
+            let text = self.source_text(abi_node);
+            let inner = text
+                .strip_prefix('"')
+                .and_then(|s| s.strip_suffix('"'))
+                .unwrap_or(&text);
+            Symbol::intern(inner)
+        });
 
-        // Lower each parameter type (including return type as the last entry)
-        // as a `TyParamKind::Positional`. By convention, the last TyParam is the return type.
+        let param_nodes = self.ast.get_multi_child_slice(params_multi).unwrap_or(&[]);
         let ty_params: Vec<TyParam<'hir>> = param_nodes
             .iter()
-            .map(|&n| {
-                let ty_expr = self.lower_expr(n);
-                let ty_ref = self.arena.alloc_expr(ty_expr);
-                TyParam::new(self.next_hir_id(), TyParamKind::Positional(ty_ref), span)
-            })
+            .map(|&n| self.lower_fn_type_param(n, span))
             .collect();
 
-        let params_slice = self.arena.alloc_ty_param_slice(ty_params);
+        let params = self.arena.alloc_ty_param_slice(ty_params);
         Expr {
             hir_id: self.next_hir_id(),
-            kind: ExprKind::TyFn(params_slice),
+            kind: ExprKind::TyFn {
+                modifiers,
+                abi,
+                params,
+                ret,
+            },
             span,
         }
     }
 
+    /// Lower a parameter of a `fn(..)` type: `...name: T` is variadic and
+    /// `.name: T = default` optional; anything else is the parameter's type.
+    fn lower_fn_type_param(&mut self, node: NodeIndex, span: Span) -> TyParam<'hir> {
+        let kind = match self.ast.get_node(node) {
+            Some((NodeKind::VarargDeclClause, _, children)) => {
+                let ident = self.node_to_ident(children[0]);
+                let ty = self.lower_expr(children[1]);
+                TyParamKind::Varadic(ident, self.arena.alloc_expr(ty))
+            }
+            Some((NodeKind::OptionalDeclClause, _, children)) => {
+                let ident = self.node_to_ident(children[0]);
+                let ty = self.lower_expr(children[1]);
+                let ty = self.arena.alloc_expr(ty);
+                let default = self.lower_expr(children[2]);
+                TyParamKind::Optional(ident, ty, self.arena.alloc_expr(default))
+            }
+            _ => {
+                let ty = self.lower_expr(node);
+                TyParamKind::Positional(self.arena.alloc_expr(ty))
+            }
+        };
+        TyParam::new(self.next_hir_id(), kind, span)
+    }
+
     fn lower_extend_args(&mut self, args_multi: NodeIndex) -> &'hir [Arg<'hir>] {
         let arg_nodes = self.ast.get_multi_child_slice(args_multi).unwrap_or(&[]);
         let args: Vec<Arg<'hir>> = arg_nodes
@@ -1452,3 +1864,21 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         }
     }
 }
+
+/// The value of an integer literal as written: decimal, or with a
+/// `0b` / `0o` / `0x` prefix, and with `_` separators anywhere after it;
+/// `None` when it does not fit in 64 bits. A literal above `i64::MAX`
+/// keeps its bits, so `0xFFFF_FFFF_FFFF_FFFF` is `-1` and the operand of
+/// `-9223372036854775808` is `i64::MIN`.
+fn int_literal_value(text: &str) -> Option<i64> {
+    let digits = text.replace('_', "");
+    let (radix, digits) = match digits.get(..2) {
+        Some("0b" | "0B") => (2, &digits[2..]),
+        Some("0o" | "0O") => (8, &digits[2..]),
+        Some("0x" | "0X") => (16, &digits[2..]),
+        _ => (10, digits.as_str()),
+    };
+    u64::from_str_radix(digits, radix)
+        .ok()
+        .map(|value| value as i64)
+}
@@ -0,0 +1,127 @@
+//! Field access, `value.field`.
+//!
+//! When the type of `value` is known from the syntax and resolves to a
+//! struct of the package, `field` must be one of the struct's fields or one
+//! of its methods: an item of the struct's body, of an `impl` for it, or of
+//! a trait it implements. A struct may be defined after the code using it,
+//! so accesses are checked once every item of the file has been lowered;
+//! the field an access refers to is then recorded with
+//! [`Package::record_field_res`]. Accesses to a value whose type is not
+//! known are left as written.
+//!
+//! [`Package::record_field_res`]: hir::Package::record_field_res
+
+use hir::{
+    FieldRes, HirId, Item, ItemKind, StructDef,
+    common::{DefId, Ident, Symbol},
+    expr::{Expr, ExprKind},
+};
+use resolve::NameLookup;
+
+use crate::LoweringContext;
+
+/// An access to a field of a value whose type is the definition `ty`.
+pub(crate) struct DeferredField {
+    /// The field access expression.
+    access: HirId,
+    ty: DefId,
+    field: Ident,
+}
+
+impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
+    /// Remember the access `access` of `field` on `base` for
+    /// [`Self::check_deferred_fields`].
+    pub(crate) fn defer_field_access(&mut self, access: HirId, base: &Expr<'hir>, field: Ident) {
+        let Some(ty) = self.infer_type(base) else {
+            return;
+        };
+        if let Some(ty) = self.accessed_type_def(ty) {
+            self.deferred_fields
+                .push(DeferredField { access, ty, field });
+        }
+    }
+
+    /// Check the field accesses of the file against the structs of the
+    /// package, now that every item is lowered, and record the field each
+    /// access refers to.
+    pub(crate) fn check_deferred_fields(&mut self) {
+        for DeferredField { access, ty, field } in std::mem::take(&mut self.deferred_fields) {
+            let Some((item, def)) = self.struct_def(ty) else {
+                continue;
+            };
+            if let Some(index) = def.fields.iter().position(|f| f.ident.name == field.name) {
+                self.package
+                    .record_field_res(access, FieldRes { def: ty, index });
+                continue;
+            }
+            if self.has_method(ty, def, field.name) {
+                continue;
+            }
+            self.emit_unknown_field(
+                &format!("no field `{}` on type `{}`", field.name, item.ident.name),
+                field.span,
+            );
+        }
+    }
+
+    /// The declared type of the field `field` of a value of type `ty`, if
+    /// `ty` is a struct lowered so far that has one. The fields of a
+    /// generic struct mention its clause parameters and are not given.
+    pub(crate) fn field_type(
+        &self,
+        ty: &'hir Expr<'hir>,
+        field: Symbol,
+    ) -> Option<&'hir Expr<'hir>> {
+        let (_, def) = self.struct_def(self.accessed_type_def(ty)?)?;
+        if !def.clause_params.is_empty() {
+            return None;
+        }
+        def.fields
+            .iter()
+            .find(|f| f.ident.name == field)
+            .map(|f| f.ty)
+    }
+
+    /// The definition of the type whose fields a value of type `ty` has: a
+    /// pointer is accessed through. A type name lowered as a type was
+    /// resolved then; others are resolved from the file's scope.
+    fn accessed_type_def(&self, ty: &'hir Expr<'hir>) -> Option<DefId> {
+        let mut ty = self.normalize_type(ty);
+        if let ExprKind::TyPtr(target) = ty.kind {
+            ty = self.normalize_type(target);
+        }
+        if let Some(def) = self.package.type_res(ty.hir_id) {
+            return Some(def);
+        }
+        match self
+            .resolver
+            .lookup_name(ty.head_name()?.as_str(), self.file_scope)
+        {
+            NameLookup::Single(res) => Some(res.def_id),
+            _ => None,
+        }
+    }
+
+    /// The struct the definition `def` was lowered to, if it is one.
+    fn struct_def(&self, def: DefId) -> Option<(&'hir Item<'hir>, &'hir StructDef<'hir>)> {
+        let item = self.package.def_item(def)?;
+        match &item.kind {
+            ItemKind::Struct(def) => Some((item, def)),
+            _ => None,
+        }
+    }
+
+    /// Whether the struct `ty`, defined by `def`, has an item or a
+    /// [method](hir::Package::methods_of) `name`.
+    fn has_method(&self, ty: DefId, def: &StructDef<'hir>, name: Symbol) -> bool {
+        def.nested_items.iter().any(|&owner| {
+            self.package
+                .item(owner)
+                .is_some_and(|item| item.ident.name == name)
+        }) || self
+            .package
+            .methods_of(ty)
+            .iter()
+            .any(|&(method, _)| method == name)
+    }
+}
@@ -20,8 +20,8 @@ use ast::{NodeIndex, NodeKind};
 use hir::{
     body::{Body, Param},
     common::{
-        Ident, Symbol, TPARAM_COMPTIME, TPARAM_ERROR, TPARAM_IMPLICIT, TPARAM_LAMBDA, TPARAM_QUOTE,
-        TyParam, TyParamKind,
+        DefId, Ident, Symbol, TPARAM_ASSOC, TPARAM_CATCH, TPARAM_COMPTIME, TPARAM_ERROR,
+        TPARAM_IMPLICIT, TPARAM_LAMBDA, TPARAM_QUOTE, TyParam, TyParamKind,
     },
     expr::{Expr, ExprKind},
     hir_id::{HirId, ItemLocalId, OwnerId},
@@ -30,16 +30,22 @@ use hir::{
     owner::{OwnerInfo, OwnerNode, OwnerNodes},
 };
 use middle::ty;
+use resolve::ScopeId;
 use rustc_span::Span;
 
-use crate::LoweringContext;
+use crate::{LoweringContext, SurroundingContext};
+
+/// Attributes the compiler understands on parameters (`^noalias x: T`).
+const KNOWN_PARAM_ATTRIBUTES: &[&str] = &["noalias"];
 
 impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
-    /// Lower a `FileScope` (the root AST node) into the HIR [`Package`].
+    /// Lower the items of a `FileScope` (the root AST node) as items of the
+    /// module `module`, and return them.
     ///
-    /// Every top-level statement/definition in the file becomes an item
-    /// (or statement) inside the root module.
-    pub fn lower_file_scope(&mut self, root_node: NodeIndex) {
+    /// Every top-level definition in the file becomes an item; the module
+    /// item itself is inserted by the caller with [`Self::insert_module`],
+    /// as several files can make up one module.
+    pub fn lower_file_scope(&mut self, root_node: NodeIndex, module: OwnerId) -> Vec<OwnerId> {
         let Some((kind, span, children)) = self.ast.get_node(root_node) else {
             unreachable!(
                 "expected FileScope as root node, but no such node index {:?}",
@@ -49,20 +55,16 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
 
         if kind != NodeKind::FileScope {
             self.emit_malformed("expected FileScope as root node", span);
-            return;
+            return Vec::new();
         }
 
         if children.is_empty() {
-            return;
+            return Vec::new();
         }
 
         let elems_node = children[0];
         let elem_nodes = self.ast.get_multi_child_slice(elems_node).unwrap_or(&[]);
-
-        // Allocate the root module owner.
-        let root_owner_id = self.package.alloc_owner_id();
-        self.package.root_mod = root_owner_id;
-        self.current_owner = root_owner_id;
+        self.current_owner = module;
 
         // Lower each top-level node.
         let mut item_ids = Vec::new();
@@ -72,21 +74,33 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             if elem == 0 {
                 continue;
             }
+            // Imports are already resolved into the module tree, and a
+            // `mod id` statement's items live in the file it links to.
+            if matches!(
+                self.ast.get_node_kind(elem),
+                Some(NodeKind::UseStatement | NodeKind::ModStatement)
+            ) {
+                continue;
+            }
 
             let owner = self.lower_top_level_node(elem);
             item_ids.push(owner);
         }
+        item_ids
+    }
 
-        // Build the root module item.
-        self.current_owner = root_owner_id;
-        self.reset_hir_id_counter();
-
-        let mod_def = ModDef { items: item_ids };
-        let ident = Ident::new(Symbol::intern("<root>"), span);
+    /// Insert the module item `owner`, named `name`, that holds `items`.
+    pub(crate) fn insert_module(
+        &mut self,
+        owner: OwnerId,
+        name: Symbol,
+        items: Vec<OwnerId>,
+        span: Span,
+    ) {
         let item = Item {
-            owner_id: root_owner_id,
-            ident,
-            kind: ItemKind::Mod(mod_def),
+            owner_id: owner,
+            ident: Ident::new(name, span),
+            kind: ItemKind::Mod(ModDef { items }),
             span,
         };
         let item_ref = self.arena.alloc_item(item);
@@ -94,7 +108,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             node: OwnerNode::Item(item_ref),
             nodes: OwnerNodes::new(),
         };
-        self.package.insert_owner(root_owner_id, owner_info);
+        self.package.insert_owner(owner, owner_info);
     }
 
     /// Lower a single top-level AST node (definition or statement) into an
@@ -102,13 +116,15 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
     fn lower_top_level_node(&mut self, node: NodeIndex) -> OwnerId {
         println!(
             "lowering top-level node: {}",
-            self.ast.dump_to_s_expression(node, self.source_map)
+            self.ast
+                .dump_to_s_expression(node, self.source_map)
+                .unwrap_or_else(|err| err.to_string())
         );
         let Some((kind, span, children)) = self.ast.get_node(node) else {
             unreachable!("invalid top-level node: no such node index {:?}", node);
         };
 
-        match kind {
+        let owner = match kind {
             NodeKind::Function => self.lower_function(node),
             NodeKind::NormalFormDef => self.lower_normal_form_def(node),
             NodeKind::StructDef => self.lower_struct_def(node),
@@ -116,13 +132,30 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             NodeKind::TraitDef => self.lower_trait_def(node),
             NodeKind::ImplDef => self.lower_impl_def(node),
             NodeKind::ImplTraitDef => self.lower_impl_trait_def(node),
-            NodeKind::TypealiasDef => self.lower_type_alias(node),
+            NodeKind::TypealiasDef | NodeKind::NewtypeDef => self.lower_type_alias(node),
             NodeKind::ModuleDef => self.lower_module_def(node),
             // NodeKind::UseStatement => self.lower_use_statement(node),
 
             // Attribute-wrapped definitions
-            NodeKind::Attribute | NodeKind::AttributeSetTrue => {
-                self.lower_top_level_node(children[1])
+            NodeKind::Attribute => self.lower_top_level_node(children[1]),
+            // Keyword-modified definitions, `unsafe fn ..`: the keyword is
+            // in scope while the definition is lowered, and a function
+            // records the ones that are its modifiers.
+            NodeKind::AttributeSetTrue => {
+                let keyword = self.node_to_ident(children[0]).name;
+                if self.modifies_function(children[1]) {
+                    match keyword.as_str() {
+                        "__flurry_kw_pure" => self.fn_modifiers.is_pure = true,
+                        "__flurry_kw_comptime" => self.fn_modifiers.is_comptime = true,
+                        "__flurry_kw_extern" => self.fn_modifiers.is_extern = true,
+                        _ => {}
+                    }
+                }
+                self.push_surrounding_ctx(SurroundingContext::AttributeSetTrue(keyword));
+                let owner = self.lower_top_level_node(children[1]);
+                self.pop_surrounding_ctx();
+                self.fn_modifiers = FnModifiers::default();
+                owner
             }
 
             NodeKind::ConstDef => self.lower_const_def(node),
@@ -132,11 +165,32 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             // Visibility modified definitions, TODO: handle the visibility modifier properly instead of just skipping it
             NodeKind::Pub | NodeKind::Private => self.lower_top_level_node(children[0]),
 
-            other => unreachable!(
-                "unexpected top-level node kind {} at {:?}",
-                self.ast.dump_to_s_expression(node, self.source_map),
-                span
-            ),
+            // Definitions the parser accepts but lowering does not handle
+            // yet, and statements where a definition is expected: reported,
+            // and kept as an error item so the rest of the package lowers.
+            other => {
+                self.emit_unsupported_node(&format!("{:?}", other), span);
+                self.make_error_item(span)
+            }
+        };
+        if let Some(&def) = self.defs.get(&node) {
+            self.package.record_def_owner(def, owner);
+        }
+        owner
+    }
+
+    /// Whether the definition `node`, under any further keywords and
+    /// attributes, is a function, whose modifiers the keywords are.
+    fn modifies_function(&self, node: NodeIndex) -> bool {
+        let mut node = node;
+        loop {
+            match self.ast.get_node(node) {
+                Some((NodeKind::Function, _, _)) => return true,
+                Some((NodeKind::Attribute | NodeKind::AttributeSetTrue, _, children)) => {
+                    node = children[1]
+                }
+                _ => return false,
+            }
         }
     }
 
@@ -169,10 +223,9 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         // Identifier
         let ident = self.node_to_ident(id_node);
 
-        // Modifiers — check if the first param slot encodes flags
-        // (In the AST, fn modifiers are encoded via Attribute/AttributeSetTrue
-        //  wrapping, but we can also check source text for keywords)
-        let modifiers = FnModifiers::default();
+        // Modifiers, taken so the functions nested in the body start
+        // without any
+        let modifiers = std::mem::take(&mut self.fn_modifiers);
 
         // Parameters
         let param_nodes = self.ast.get_multi_child_slice(params_multi).unwrap_or(&[]);
@@ -180,8 +233,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
 
         // Return type
         let return_ty = if return_type_node != 0 {
-            let ty_expr = self.lower_expr(return_type_node);
-            Some(self.arena.alloc_expr(ty_expr) as &_)
+            Some(self.lower_type(return_type_node))
         } else {
             None
         };
@@ -204,14 +256,40 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             span,
         };
 
-        // Body
+        // Body; locals of an enclosing body are not visible inside.
+        let outer_locals = std::mem::take(&mut self.locals);
+        let outer_comptime = std::mem::take(&mut self.comptime_locals);
+        let outer_quoted = std::mem::take(&mut self.quoted_locals);
+        let mut receiver = None;
+        for (ident, param) in params_slice {
+            if param.is_comptime() {
+                self.comptime_locals.push(self.locals.len());
+            }
+            let ty = match &param.kind {
+                TyParamKind::Positional(ty)
+                | TyParamKind::PositionalDependencyCatched(_, ty)
+                | TyParamKind::Optional(_, ty, _) => Some(*ty),
+                TyParamKind::Itself { is_ref } => {
+                    receiver = self.receiver_type(ident, *is_ref, param.span);
+                    receiver
+                }
+                _ => None,
+            };
+            self.declare_local(ident.name, ty, None);
+        }
+        // Functions nested in the body are not methods.
+        let outer_receiver = self.receiver_ty.take();
         let body_expr = self.lower_expr(body_node);
         let body_expr_ref = self.arena.alloc_expr(body_expr);
+        self.receiver_ty = outer_receiver;
+        self.locals = outer_locals;
+        self.comptime_locals = outer_comptime;
+        self.quoted_locals = outer_quoted;
 
         // Build Body with params
         let body_params: Vec<Param<'hir>> = param_nodes
             .iter()
-            .map(|&p| self.lower_body_param(p))
+            .map(|&p| self.lower_body_param(p, receiver))
             .collect();
         let body_params_slice = self.arena.alloc_param_slice(body_params);
         let body = Body {
@@ -277,8 +355,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         let clause_constraints = self.arena.alloc_clause_slice(lowered.constraints);
 
         let return_ty = if return_type_node != 0 {
-            let ty_expr = self.lower_expr(return_type_node);
-            Some(self.arena.alloc_expr(ty_expr) as &_)
+            Some(self.lower_type(return_type_node))
         } else {
             None
         };
@@ -353,7 +430,10 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         let clause_constraints = self.arena.alloc_clause_slice(lowered.constraints);
 
         // Body: a Block containing struct fields and nested definitions
+        let named = self.named_type(&ident);
+        let outer_receiver = self.receiver_ty.replace(named);
         let (fields, nested_items) = self.lower_struct_body(body_node);
+        self.receiver_ty = outer_receiver;
         let fields_slice = self.arena.alloc_field_def_slice(fields);
 
         let struct_def = StructDef {
@@ -414,6 +494,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                     NodeKind::Function
                     | NodeKind::NormalFormDef
                     | NodeKind::TypealiasDef
+                    | NodeKind::NewtypeDef
                     | NodeKind::ConstDef
                     | NodeKind::ImplDef
                     | NodeKind::ImplTraitDef,
@@ -503,7 +584,10 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         let clause_params = self.arena.alloc_clause_param_slice(lowered.params);
         let clause_constraints = self.arena.alloc_clause_slice(lowered.constraints);
 
+        let named = self.named_type(&ident);
+        let outer_receiver = self.receiver_ty.replace(named);
         let (variants, nested_items) = self.lower_enum_body(body_node);
+        self.receiver_ty = outer_receiver;
         let variants_slice = self.arena.alloc_variant_slice(variants);
 
         let enum_def = EnumDef {
@@ -575,7 +659,20 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                         span,
                     });
                 }
-                Some(NodeKind::Function | NodeKind::TypealiasDef | NodeKind::ConstDef) => {
+                Some(
+                    NodeKind::Function
+                    | NodeKind::NormalFormDef
+                    | NodeKind::TypealiasDef
+                    | NodeKind::NewtypeDef
+                    | NodeKind::ConstDef
+                    | NodeKind::ImplDef
+                    | NodeKind::ImplTraitDef,
+                ) => {
+                    let owner = self.lower_top_level_node(elem);
+                    nested_items.push(owner);
+                }
+                // Attribute- and keyword-wrapped definitions, `pure fn ..`
+                Some(NodeKind::Attribute | NodeKind::AttributeSetTrue) => {
                     let owner = self.lower_top_level_node(elem);
                     nested_items.push(owner);
                 }
@@ -690,7 +787,10 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         let clause_constraints = self.arena.alloc_clause_slice(lowered.constraints);
 
         // Lower body items
+        let self_ty = self.self_type(span);
+        let outer_receiver = self.receiver_ty.replace(self_ty);
         let body_items = self.lower_trait_body(body_node);
+        self.receiver_ty = outer_receiver;
 
         let trait_def = TraitDef {
             clause_params,
@@ -784,7 +884,9 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         let clause_params = self.arena.alloc_clause_param_slice(lowered.params);
         let clause_constraints = self.arena.alloc_clause_slice(lowered.constraints);
 
+        let outer_receiver = self.receiver_ty.replace(self_ty_ref);
         let body_items = self.lower_impl_body(body_node);
+        self.receiver_ty = outer_receiver;
 
         let impl_def = ImplDef {
             self_ty: self_ty_ref,
@@ -793,6 +895,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             clause_constraints,
             items: body_items,
         };
+        self.register_impl(owner_id, &impl_def);
 
         let item = Item {
             owner_id,
@@ -836,6 +939,8 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
 
         let trait_ = self.lower_expr(trait_node);
         let trait_ref = self.arena.alloc_expr(trait_);
+        // Recorded for `Package::methods_of`, which follows the trait.
+        self.resolve_type_head(trait_ref);
         let self_ty = self.lower_expr(type_node);
         let self_ty_ref = self.arena.alloc_expr(self_ty);
 
@@ -844,7 +949,9 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         let clause_params = self.arena.alloc_clause_param_slice(lowered.params);
         let clause_constraints = self.arena.alloc_clause_slice(lowered.constraints);
 
+        let outer_receiver = self.receiver_ty.replace(self_ty_ref);
         let body_items = self.lower_impl_body(body_node);
+        self.receiver_ty = outer_receiver;
 
         let impl_def = ImplDef {
             self_ty: self_ty_ref,
@@ -853,6 +960,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             clause_constraints,
             items: body_items,
         };
+        self.register_impl(owner_id, &impl_def);
 
         let item = Item {
             owner_id,
@@ -873,13 +981,69 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         owner_id
     }
 
+    /// The scope the resolver opened for the inline module `node`.
+    fn module_scope(&self, node: NodeIndex) -> Option<ScopeId> {
+        let def = *self.defs.get(&node)?;
+        let scopes = self.resolver.scope_tree();
+        scopes
+            .get(self.file_scope)?
+            .children
+            .iter()
+            .copied()
+            .find(|&child| {
+                scopes
+                    .get(child)
+                    .is_some_and(|scope| scope.owner_def == def)
+            })
+    }
+
+    /// Index the `impl` block `owner_id` under the definition its self type
+    /// names. A blanket impl, whose self type is one of its clause
+    /// parameters, and an impl for a type without a definition, such as a
+    /// pointer, are not indexed.
+    fn register_impl(&mut self, owner_id: OwnerId, def: &ImplDef<'hir>) {
+        let Some(name) = def.self_ty.head_name() else {
+            return;
+        };
+        if def
+            .clause_params
+            .iter()
+            .any(|param| param.name.name == name)
+        {
+            return;
+        }
+        if let Some(self_ty) = self.resolve_type_head(def.self_ty) {
+            self.package.register_impl(self_ty, owner_id);
+        }
+    }
+
+    /// The definition the type `ty` is named after, without its arguments:
+    /// that of `Vec` for `Vec<Int>`. A name resolved here is recorded as
+    /// [`Self::lower_type`] records one.
+    fn resolve_type_head(&mut self, ty: &'hir Expr<'hir>) -> Option<DefId> {
+        match &ty.kind {
+            ExprKind::Ident(name) => {
+                let def = self.resolve_type_name(*name, ty.span)?.def_id;
+                self.package.record_type_res(ty.hir_id, def);
+                Some(def)
+            }
+            ExprKind::Path(path) => path.res,
+            ExprKind::Application(head, _)
+            | ExprKind::ExtendedApplication(head, _)
+            | ExprKind::NFApplication(head, _) => self.resolve_type_head(head),
+            _ => None,
+        }
+    }
+
     fn lower_impl_body(&mut self, body_node: NodeIndex) -> Vec<OwnerId> {
         self.lower_trait_body(body_node) // Same structure
     }
 
-    /// Lower `TypealiasDef`: a, N, b  (id, type_params, type_expr)
+    /// Lower `TypealiasDef` or `NewtypeDef`: a, N, b  (id, type_params, type_expr)
     fn lower_type_alias(&mut self, node: NodeIndex) -> OwnerId {
-        let Some((NodeKind::TypealiasDef, span, children)) = self.ast.get_node(node) else {
+        let Some((kind @ (NodeKind::TypealiasDef | NodeKind::NewtypeDef), span, children)) =
+            self.ast.get_node(node)
+        else {
             unreachable!("invalid type alias node or no such node index {:?}", node);
         };
 
@@ -903,11 +1067,15 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
 
         let type_expr = self.lower_expr(type_expr_node);
         let type_expr_ref = self.arena.alloc_expr(type_expr);
+        self.resolve_names_in_type(type_expr_ref);
 
         let item = Item {
             owner_id,
             ident,
-            kind: ItemKind::TypeAlias(type_expr_ref),
+            kind: match kind {
+                NodeKind::NewtypeDef => ItemKind::Newtype(type_expr_ref),
+                _ => ItemKind::TypeAlias(type_expr_ref),
+            },
             span,
         };
         let item_ref = self.arena.alloc_item(item);
@@ -955,6 +1123,11 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             _ => vec![],
         };
 
+        // The module's items resolve names from its own scope.
+        let outer_scope = self.file_scope;
+        if let Some(scope) = self.module_scope(node) {
+            self.file_scope = scope;
+        }
         let mut item_ids = Vec::new();
         for &elem in &elem_nodes {
             if elem != 0 {
@@ -962,6 +1135,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 item_ids.push(owner);
             }
         }
+        self.file_scope = outer_scope;
 
         let mod_def = ModDef { items: item_ids };
         let item = Item {
@@ -996,7 +1170,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         params
     }
 
-    fn lower_fn_param(&mut self, node: NodeIndex) -> Option<(Ident, TyParam<'hir>)> {
+    pub(crate) fn lower_fn_param(&mut self, node: NodeIndex) -> Option<(Ident, TyParam<'hir>)> {
         let Some((kind, span, children)) = self.ast.get_node(node) else {
             unreachable!(
                 "invalid function parameter node: no such node index {:?}",
@@ -1008,24 +1182,21 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             NodeKind::TypeBoundParam => {
                 // id : type
                 let ident = self.node_to_ident(children[0]);
-                let ty = self.lower_expr(children[1]);
-                let ty_ref = self.arena.alloc_expr(ty);
+                let ty_ref = self.lower_type(children[1]);
                 let tp = TyParam::new(self.next_hir_id(), TyParamKind::Positional(ty_ref), span);
                 Some((ident, tp))
             }
             NodeKind::TraitBoundParam => {
                 // id :- type
                 let ident = self.node_to_ident(children[0]);
-                let ty = self.lower_expr(children[1]);
-                let ty_ref = self.arena.alloc_expr(ty);
+                let ty_ref = self.lower_type(children[1]);
                 let tp = TyParam::new(self.next_hir_id(), TyParamKind::Positional(ty_ref), span);
                 Some((ident, tp))
             }
             NodeKind::OptionalParam => {
                 // .id : type = default
                 let ident = self.node_to_ident(children[0]);
-                let ty = self.lower_expr(children[1]);
-                let ty_ref = self.arena.alloc_expr(ty);
+                let ty_ref = self.lower_type(children[1]);
                 let default = self.lower_expr(children[2]);
                 let default_ref = self.arena.alloc_expr(default);
                 let tp = TyParam::new(
@@ -1038,8 +1209,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             NodeKind::VarargParam => {
                 // ...id : type
                 let ident = self.node_to_ident(children[0]);
-                let ty = self.lower_expr(children[1]);
-                let ty_ref = self.arena.alloc_expr(ty);
+                let ty_ref = self.lower_type(children[1]);
                 let tp = TyParam::new(
                     self.next_hir_id(),
                     TyParamKind::Varadic(ident.clone(), ty_ref),
@@ -1065,6 +1235,12 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 );
                 Some((ident, tp))
             }
+            NodeKind::ItselfParam | NodeKind::ItselfRefParam => {
+                let ident = Ident::new(Symbol::intern("itself"), span);
+                let is_ref = kind == NodeKind::ItselfRefParam;
+                let tp = TyParam::new(self.next_hir_id(), TyParamKind::Itself { is_ref }, span);
+                Some((ident, tp))
+            }
             NodeKind::ComptimeParam => self.lower_fn_param(children[0]).map(|(i, mut tp)| {
                 tp.flags |= TPARAM_COMPTIME;
                 (i, tp)
@@ -1081,14 +1257,20 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 tp.flags |= TPARAM_ERROR;
                 (i, tp)
             }),
-            NodeKind::CatchParam => {
-                // No dedicated TPARAM flag yet; lower the inner param unchanged.
-                self.lower_fn_param(children[0])
-            }
+            NodeKind::CatchParam => self.lower_fn_param(children[0]).map(|(i, mut tp)| {
+                tp.flags |= TPARAM_CATCH;
+                (i, tp)
+            }),
             NodeKind::QuoteParam => self.lower_fn_param(children[0]).map(|(i, mut tp)| {
                 tp.flags |= TPARAM_QUOTE;
                 (i, tp)
             }),
+            NodeKind::AssocParam => self.lower_fn_param(children[0]).map(|(i, mut tp)| {
+                tp.flags |= TPARAM_ASSOC;
+                (i, tp)
+            }),
+            // The attribute itself is recorded on the body parameter.
+            NodeKind::AttrParam | NodeKind::Attribute => self.lower_fn_param(children[1]),
             NodeKind::Id => {
                 // Bare identifier param (no type annotation)
                 let ident = self.node_to_ident(node);
@@ -1108,8 +1290,13 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         }
     }
 
-    /// Lower an AST parameter node into a Body [`Param`].
-    fn lower_body_param(&mut self, node: NodeIndex) -> Param<'hir> {
+    /// Lower an AST parameter node into a Body [`Param`]. A `self` or
+    /// `itself` receiver gets the type `receiver`.
+    fn lower_body_param(
+        &mut self,
+        node: NodeIndex,
+        receiver: Option<&'hir Expr<'hir>>,
+    ) -> Param<'hir> {
         let Some((kind, span, children)) = self.ast.get_node(node) else {
             unreachable!(
                 "invalid function parameter node: no such node index {:?}",
@@ -1118,6 +1305,17 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         };
 
         let (name, ty) = match kind {
+            NodeKind::AttrParam | NodeKind::Attribute => {
+                // ^attr param
+                let attr = self.lower_param_attribute(children[0]);
+                let mut param = self.lower_body_param(children[1], receiver);
+                let attrs: Vec<_> = std::iter::once(attr)
+                    .chain(param.attrs.iter().cloned())
+                    .collect();
+                param.attrs = self.arena.alloc_expr_slice(attrs);
+                param.span = span;
+                return param;
+            }
             NodeKind::TypeBoundParam | NodeKind::TraitBoundParam => {
                 let name = self.node_to_ident(children[0]);
                 let ty = if children[1] != 0 {
@@ -1129,15 +1327,34 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 (name, ty)
             }
             NodeKind::SelfParam | NodeKind::SelfRefParam => {
-                (Ident::new(Symbol::intern("self"), span), None)
+                (Ident::new(Symbol::intern("self"), span), receiver)
+            }
+            NodeKind::ItselfParam | NodeKind::ItselfRefParam => {
+                (Ident::new(Symbol::intern("itself"), span), receiver)
             }
             NodeKind::ComptimeParam
             | NodeKind::ImplicitParam
             | NodeKind::LambdaParam
             | NodeKind::ErrorParam
             | NodeKind::CatchParam
-            | NodeKind::QuoteParam => (Ident::new(Symbol::intern("_"), span), None),
-            NodeKind::OptionalParam => {
+            | NodeKind::QuoteParam
+            | NodeKind::AssocParam => {
+                let mut param = self.lower_body_param(children[0], receiver);
+                param.flags |= match kind {
+                    NodeKind::ComptimeParam => TPARAM_COMPTIME,
+                    NodeKind::ImplicitParam => TPARAM_IMPLICIT,
+                    NodeKind::LambdaParam => TPARAM_LAMBDA,
+                    NodeKind::ErrorParam => TPARAM_ERROR,
+                    NodeKind::CatchParam => TPARAM_CATCH,
+                    NodeKind::QuoteParam => TPARAM_QUOTE,
+                    NodeKind::AssocParam => TPARAM_ASSOC,
+                    _ => 0,
+                };
+                param.span = span;
+                return param;
+            }
+            NodeKind::OptionalParam | NodeKind::VarargParam => {
+                // .id : type = default, ...id : type
                 let name = self.node_to_ident(children[0]);
                 let ty = if children[1] != 0 {
                     let ty_expr = self.lower_expr(children[1]);
@@ -1157,10 +1374,24 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             hir_id: self.next_hir_id(),
             name,
             ty,
+            attrs: &[],
+            flags: 0,
             span,
         }
     }
 
+    /// Lower the attribute expression of an `AttrParam`, warning when it
+    /// names an attribute the compiler does not know.
+    fn lower_param_attribute(&mut self, node: NodeIndex) -> Expr<'hir> {
+        if let Some((NodeKind::Id, span, _)) = self.ast.get_node(node) {
+            let name = self.node_to_symbol(node);
+            if !KNOWN_PARAM_ATTRIBUTES.contains(&name.as_str()) {
+                self.warn_unknown_attribute(name.as_str(), span);
+            }
+        }
+        self.lower_expr(node)
+    }
+
     fn lower_const_def(&mut self, node: NodeIndex) -> OwnerId {
         let Some((NodeKind::ConstDef | NodeKind::ConstDecl, span, children)) =
             self.ast.get_node(node)
@@ -1208,6 +1439,56 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         owner_id
     }
 
+    /// The type of the receiver `ident` of the function being lowered: the
+    /// enclosing type, or a pointer to it for `*self`. Reports the receiver
+    /// if there is no enclosing type.
+    fn receiver_type(
+        &mut self,
+        ident: &Ident,
+        is_ref: bool,
+        span: Span,
+    ) -> Option<&'hir Expr<'hir>> {
+        let Some(target) = self.receiver_ty else {
+            self.emit_receiver_outside_method(
+                &format!(
+                    "`{}` can only be a parameter of a function in an `impl`, `trait`, `struct` or `enum` body",
+                    ident.name
+                ),
+                span,
+            );
+            return None;
+        };
+        if !is_ref {
+            return Some(target);
+        }
+        let ptr = Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::TyPtr(target),
+            span,
+        };
+        Some(self.arena.alloc_expr(ptr))
+    }
+
+    /// `Self`, the receiver type inside a trait.
+    fn self_type(&mut self, span: Span) -> &'hir Expr<'hir> {
+        let ty = Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::TySelf,
+            span,
+        };
+        self.arena.alloc_expr(ty)
+    }
+
+    /// The type named by the struct or enum `ident`.
+    fn named_type(&mut self, ident: &Ident) -> &'hir Expr<'hir> {
+        let ty = Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::Ident(ident.name),
+            span: ident.span,
+        };
+        self.arena.alloc_expr(ty)
+    }
+
     /// Create an error item (returns an OwnerId that maps to `ItemKind::Err`).
     fn make_error_item(&mut self, span: Span) -> OwnerId {
         let owner_id = self.package.alloc_owner_id();
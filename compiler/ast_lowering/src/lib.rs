@@ -33,25 +33,45 @@
 //!    produces a recovery node (`ExprKind::Invalid`, `PatternKind::Err`,
 //!    `ItemKind::Err`).
 
+mod alias_cycles;
+mod callable;
+mod cast;
 mod clause;
+mod coherence;
+mod comptime;
+mod effect;
 mod error;
 mod expr;
+mod field;
 mod item;
+mod lint;
+mod normalize;
 mod pattern;
+mod pipe;
 pub mod providers;
+#[cfg(test)]
+mod tests;
+mod type_names;
 
 pub use error::{LoweringError, LoweringErrorKind};
 pub use providers::set_providers;
 
+use std::collections::HashMap;
+
 use ast::{Ast, NodeIndex};
+use callable::DeferredCall;
 use diagnostic::{DiagnosticContext, FlurryError};
+use field::DeferredField;
 use hir::{
     HirArena, Package,
     body::Body,
-    common::{Ident, Symbol},
+    common::{BindingMode, DefId, Ident, Symbol},
+    expr::Expr,
     hir_id::{BodyId, HirId, ItemLocalId, OwnerId},
+    item::FnModifiers,
 };
-use resolve::{Resolver, ScopeId};
+use pattern::DeferredPattern;
+use resolve::{Resolution, Resolver, ScopeId};
 use rustc_span::{SourceMap, Span};
 
 /// Lower a single file's AST into HIR, appending definitions to `package`.
@@ -67,6 +87,10 @@ use rustc_span::{SourceMap, Span};
 /// - `resolver`     – the early name resolver built from the module tree.
 /// - `file_scope`   – the scope that owns the top-level definitions of this
 ///                    file (used as the starting point for name resolution).
+///
+/// An item that fails to lower is reported and kept as an error item, and
+/// lowering goes on with its siblings; the returned [`LoweringSummary`]
+/// tells how much of the file lowered.
 pub fn lower_to_hir<'hir>(
     ast: &Ast,
     arena: &'hir HirArena,
@@ -75,11 +99,171 @@ pub fn lower_to_hir<'hir>(
     package: &mut Package<'hir>,
     resolver: &Resolver<'_>,
     file_scope: ScopeId,
-) {
+) -> LoweringSummary {
+    let before = LoweringSummary::of(package, diag_ctx);
     let mut ctx = LoweringContext::new(
         ast, arena, source_map, diag_ctx, package, resolver, file_scope,
     );
-    ctx.lower_file_scope(ast.root);
+    let root = ctx.package.alloc_owner_id();
+    ctx.package.root_mod = root;
+    let items = ctx.lower_file(ast.root, root);
+    let span = ast.get_span(ast.root).unwrap_or_default();
+    ctx.insert_module(root, Symbol::intern("<root>"), items, span);
+    ctx.check_package();
+    LoweringSummary::of(package, diag_ctx).since(before)
+}
+
+/// How much of a file [`lower_to_hir`] lowered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoweringSummary {
+    /// Items added to the package, counting the error items kept in place
+    /// of those that failed.
+    pub items: usize,
+    /// Items that failed to lower.
+    pub failed_items: usize,
+    /// Errors reported while lowering.
+    pub errors: usize,
+}
+
+impl LoweringSummary {
+    /// Whether the file lowered without errors.
+    pub fn is_ok(&self) -> bool {
+        self.errors == 0
+    }
+
+    /// The totals of `package` and `diag_ctx` so far.
+    fn of(package: &Package<'_>, diag_ctx: &DiagnosticContext<'_>) -> Self {
+        let items: Vec<_> = package
+            .owners()
+            .filter_map(|(_, info)| info.node.as_item())
+            .collect();
+        Self {
+            items: items.len(),
+            failed_items: items
+                .iter()
+                .filter(|item| matches!(item.kind, hir::ItemKind::Invalid))
+                .count(),
+            errors: diag_ctx.error_count(),
+        }
+    }
+
+    fn since(self, before: Self) -> Self {
+        Self {
+            items: self.items - before.items,
+            failed_items: self.failed_items - before.failed_items,
+            errors: self.errors - before.errors,
+        }
+    }
+}
+
+/// Lower every file of one top-level package into a fresh [`Package`].
+///
+/// `package` indexes [`ModuleTree::packages`](resolve::ModuleTree::packages)
+/// of the tree `resolver` was built from, and `vfs` must be that package's
+/// VFS. The files are lowered as [`lower_to_hir`] lowers one, each into the
+/// module item of its scope, and those modules are nested as their scopes
+/// are under a single [`Package::root_mod`]; a directory without an entry
+/// file still gets a module of its own. Checks that look at the whole
+/// package run once, after every file.
+///
+/// Each package of a workspace is lowered into its own [`Package`], and
+/// the [`HirId`]s of every package start from the same first owner, so a
+/// [`HirId`] only means something together with its package. Paths into
+/// another package keep the [`DefId`](hir::common::DefId) the resolver
+/// found for them.
+pub fn lower_package<'hir>(
+    vfs: &vfs::Vfs,
+    package: usize,
+    arena: &'hir HirArena,
+    source_map: &SourceMap,
+    diag_ctx: &DiagnosticContext<'_>,
+    resolver: &Resolver<'_>,
+) -> Package<'hir> {
+    let tree = resolver.module_tree();
+    let mut hir_package = Package::new();
+    let root = hir_package.alloc_owner_id();
+    hir_package.root_mod = root;
+    let Some(package_scope) = tree.packages.get(package).map(|package| package.scope) else {
+        return hir_package;
+    };
+
+    // The module item of each scope that files were lowered into, in the
+    // order the modules were created, with their span and items.
+    let mut modules: Vec<(ScopeId, OwnerId, Span, Vec<OwnerId>)> =
+        vec![(package_scope, root, Span::default(), Vec::new())];
+    let mut last_file = None;
+    for (file_id, _) in vfs.files() {
+        let (Some(ast), Some(file_scope)) =
+            (vfs.get_ast(file_id), tree.file_scope(package, file_id))
+        else {
+            continue;
+        };
+        let index = match modules.iter().position(|(scope, ..)| *scope == file_scope) {
+            Some(index) => index,
+            None => {
+                let owner = hir_package.alloc_owner_id();
+                modules.push((file_scope, owner, Span::default(), Vec::new()));
+                modules.len() - 1
+            }
+        };
+        let mut ctx = LoweringContext::new(
+            ast,
+            arena,
+            source_map,
+            diag_ctx,
+            &mut hir_package,
+            resolver,
+            file_scope,
+        );
+        let items = ctx.lower_file(ast.root, modules[index].1);
+        let (_, _, span, module_items) = &mut modules[index];
+        if span.is_dummy() {
+            *span = ast.get_span(ast.root).unwrap_or_default();
+        }
+        module_items.extend(items);
+        last_file = Some((ast, file_scope));
+    }
+
+    // Nest each module in that of its parent scope, creating the modules
+    // of directories that only hold other modules.
+    let mut index = 1;
+    while index < modules.len() {
+        let (scope, owner, ..) = modules[index];
+        if let Some(parent) = tree.scope_tree.get(scope).and_then(|scope| scope.parent) {
+            let parent_index = match modules.iter().position(|(scope, ..)| *scope == parent) {
+                Some(parent_index) => parent_index,
+                None => {
+                    let parent_owner = hir_package.alloc_owner_id();
+                    modules.push((parent, parent_owner, Span::default(), Vec::new()));
+                    modules.len() - 1
+                }
+            };
+            modules[parent_index].3.push(owner);
+        }
+        index += 1;
+    }
+
+    let Some((ast, file_scope)) = last_file else {
+        return hir_package;
+    };
+    let mut ctx = LoweringContext::new(
+        ast,
+        arena,
+        source_map,
+        diag_ctx,
+        &mut hir_package,
+        resolver,
+        file_scope,
+    );
+    for (scope, owner, span, items) in modules {
+        let name = match tree.scope_tree.get(scope).and_then(|scope| scope.name) {
+            Some(name) if scope != package_scope => name,
+            _ => Symbol::intern("<root>"),
+        };
+        ctx.insert_module(owner, name, items, span);
+    }
+    ctx.check_package();
+    hir_package
 }
 
 /// Mutable context threaded through all lowering functions.
@@ -101,10 +285,75 @@ pub struct LoweringContext<'hir, 'ast> {
 
     /// Early name resolver – performs module-level name lookup.
     pub(crate) resolver: &'ast Resolver<'ast>,
-    /// The scope that owns the top-level names of the file being lowered.
+    /// The scope that owns the top-level names of the file being lowered,
+    /// or, while an inline `mod` is lowered, that of the module.
     pub(crate) file_scope: ScopeId,
+    /// The file being lowered, with the scope of its top-level names.
+    pub(crate) file: Option<(vfs::FileId, ScopeId)>,
+    /// The definition the resolver made of each item node of the file.
+    defs: HashMap<NodeIndex, DefId>,
 
     pub(crate) surrouding_ctx: Vec<SurroundingContext>,
+
+    /// The `pure`, `comptime` and `extern` keywords written before the
+    /// definition being lowered, taken by the function they modify.
+    pub(crate) fn_modifiers: FnModifiers,
+
+    /// Destructuring patterns whose shape can only be checked once every
+    /// item of the file has been lowered.
+    pub(crate) deferred_patterns: Vec<DeferredPattern>,
+
+    /// How the names bound by the pattern being lowered capture their
+    /// value; switched to by-reference inside a `ref` pattern.
+    pub(crate) binding_mode: BindingMode,
+
+    /// Locals of the body being lowered, innermost last, with their type
+    /// where the syntax tells it.
+    pub(crate) locals: Vec<(Symbol, Option<&'hir Expr<'hir>>)>,
+
+    /// Indices into `locals` of the `comptime` parameters of the function
+    /// being lowered.
+    pub(crate) comptime_locals: Vec<usize>,
+
+    /// Calls to named functions, checked against the callee's signature
+    /// once every item of the file has been lowered.
+    pub(crate) deferred_calls: Vec<DeferredCall<'hir>>,
+
+    /// Accesses to fields of values whose type names a struct, checked
+    /// once every item of the file has been lowered.
+    pub(crate) deferred_fields: Vec<DeferredField>,
+
+    /// Names piped into with `|>` that are not locals or builtins, with the
+    /// scope they were written in, checked to name something once every
+    /// item of the file has been lowered.
+    pub(crate) pipe_targets: Vec<(Ident, ScopeId)>,
+
+    /// The type a `self` receiver stands for in the methods being lowered:
+    /// the target of the enclosing `impl`, the enclosing struct or enum, or
+    /// `Self` in a trait. `None` outside such bodies.
+    pub(crate) receiver_ty: Option<&'hir Expr<'hir>>,
+
+    /// Type names already resolved, with the outcome; see [`type_names`].
+    pub(crate) type_names: HashMap<(ScopeId, Symbol), Option<Resolution>>,
+
+    /// The block of each `quote { .. }` expression, by the expression's id.
+    /// Quoted code is only lowered where it is spliced.
+    pub(crate) quotes: HashMap<HirId, NodeIndex>,
+
+    /// Indices into `locals` of the locals bound to quoted code, with the
+    /// quoted block.
+    pub(crate) quoted_locals: Vec<(usize, NodeIndex)>,
+
+    /// The quoted blocks being spliced, innermost last, each with the mark
+    /// its bindings are renamed with; see [`comptime`].
+    pub(crate) splicing: Vec<(NodeIndex, u32)>,
+
+    /// The mark the next splice gets.
+    next_splice_mark: u32,
+
+    /// Bindings introduced by the code being spliced, with the fresh names
+    /// they were given.
+    pub(crate) splice_renames: Vec<(Symbol, Symbol)>,
 }
 
 impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
@@ -127,10 +376,50 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             next_local_id: 0,
             resolver,
             file_scope,
+            file: resolver
+                .module_tree()
+                .scope_file(file_scope)
+                .map(|file| (file, file_scope)),
+            defs: resolver.module_tree().file_defs(file_scope),
             surrouding_ctx: Vec::new(),
+            fn_modifiers: FnModifiers::default(),
+            deferred_patterns: Vec::new(),
+            binding_mode: BindingMode::ByValue,
+            locals: Vec::new(),
+            comptime_locals: Vec::new(),
+            deferred_calls: Vec::new(),
+            deferred_fields: Vec::new(),
+            pipe_targets: Vec::new(),
+            receiver_ty: None,
+            type_names: HashMap::new(),
+            quotes: HashMap::new(),
+            quoted_locals: Vec::new(),
+            splicing: Vec::new(),
+            next_splice_mark: 0,
+            splice_renames: Vec::new(),
         }
     }
 
+    /// Lower the file whose root node is `root` into the module `module`
+    /// and run the checks that need every item of the file; return the
+    /// file's items.
+    fn lower_file(&mut self, root: NodeIndex, module: OwnerId) -> Vec<OwnerId> {
+        let items = self.lower_file_scope(root, module);
+        self.check_deferred_patterns();
+        self.check_deferred_calls();
+        self.check_deferred_fields();
+        self.check_pipe_targets();
+        items
+    }
+
+    /// Run the checks that look at the whole package, once every file of
+    /// it is lowered.
+    fn check_package(&self) {
+        self.check_coherence();
+        self.check_alias_cycles();
+        self.check_lints();
+    }
+
     /// Allocate the next [`HirId`] within the current owner.
     pub(crate) fn next_hir_id(&mut self) -> HirId {
         let local = ItemLocalId::new(self.next_local_id);
@@ -189,6 +478,15 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         self.surrouding_ctx.pop();
     }
 
+    /// Whether the code being lowered is in an `unsafe` block or function.
+    pub(crate) fn in_unsafe_context(&self) -> bool {
+        self.surrouding_ctx.iter().any(|ctx| match ctx {
+            SurroundingContext::Unsafe => true,
+            SurroundingContext::AttributeSetTrue(name) => name.as_str() == "__flurry_kw_unsafe",
+            SurroundingContext::Attribute(_) => false,
+        })
+    }
+
     pub(crate) fn emit_unsupported_node(&self, name: &str, span: Span) {
         let err = LoweringError::unsupported_node(name, span);
         err.emit(self.diag_ctx, rustc_span::BytePos(0));
@@ -214,6 +512,131 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         err.emit(self.diag_ctx, rustc_span::BytePos(0));
     }
 
+    pub(crate) fn emit_mismatched_pattern(&self, msg: &str, span: Span) {
+        let err = LoweringError::mismatched_pattern(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_inconsistent_or_bindings(&self, msg: &str, span: Span) {
+        let err = LoweringError::inconsistent_or_bindings(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_unknown_pattern_field(&self, msg: &str, span: Span) {
+        let err = LoweringError::unknown_pattern_field(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_binding_in_not_pattern(&self, msg: &str, span: Span) {
+        let err = LoweringError::binding_in_not_pattern(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_argument_count_mismatch(&self, msg: &str, span: Span) {
+        let err = LoweringError::argument_count_mismatch(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_mismatched_argument(&self, msg: &str, span: Span) {
+        let err = LoweringError::mismatched_argument(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_conflicting_impls(&self, msg: &str, first_impl: Span, span: Span) {
+        let err = LoweringError::conflicting_impls(msg, first_impl, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_receiver_outside_method(&self, msg: &str, span: Span) {
+        let err = LoweringError::receiver_outside_method(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_non_constant_comptime_argument(&self, msg: &str, span: Span) {
+        let err = LoweringError::non_constant_comptime_argument(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_catch_without_errors(&self, msg: &str, span: Span) {
+        let err = LoweringError::catch_without_errors(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_static_assertion_failed(&self, msg: &str, span: Span) {
+        let err = LoweringError::static_assertion_failed(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_division_by_zero(&self, msg: &str, span: Span) {
+        let err = LoweringError::division_by_zero(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_recursive_splice(&self, msg: &str, span: Span) {
+        let err = LoweringError::recursive_splice(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_invalid_cast(&self, msg: &str, span: Span) {
+        let err = LoweringError::invalid_cast(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_unknown_field(&self, msg: &str, span: Span) {
+        let err = LoweringError::unknown_field(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_misordered_argument(&self, msg: &str, span: Span) {
+        let err = LoweringError::misordered_argument(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_unknown_parameter(&self, msg: &str, span: Span) {
+        let err = LoweringError::unknown_parameter(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_duplicate_argument(&self, msg: &str, span: Span) {
+        let err = LoweringError::duplicate_argument(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_unresolved_implicit(&self, msg: &str, span: Span) {
+        let err = LoweringError::unresolved_implicit(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn emit_integer_overflow(&self, msg: &str, span: Span) {
+        let err = LoweringError::integer_overflow(msg, span);
+        err.emit(self.diag_ctx, rustc_span::BytePos(0));
+    }
+
+    pub(crate) fn warn_unknown_attribute(&self, name: &str, span: Span) {
+        let message = format!("unknown attribute `{}`", name);
+        self.diag_ctx
+            .warning(message.clone())
+            .with_warning_label(span, message)
+            .with_primary_span(span)
+            .emit(self.diag_ctx);
+    }
+
+    pub(crate) fn warn_bool_comparison(&self, message: &str, span: Span) {
+        self.diag_ctx
+            .warning(message.to_string())
+            .with_warning_label(span, "comparison with a bool literal".to_string())
+            .with_primary_span(span)
+            .emit(self.diag_ctx);
+    }
+
+    pub(crate) fn warn_lossy_cast(&self, message: &str, span: Span) {
+        self.diag_ctx
+            .warning(message.to_string())
+            .with_warning_label(span, "narrowing cast".to_string())
+            .with_primary_span(span)
+            .emit(self.diag_ctx);
+    }
+
     pub(crate) fn emit_invalid_item(&self, msg: &str, span: Span) {
         let err = LoweringError::invalid_item(msg, span);
         err.emit(self.diag_ctx, rustc_span::BytePos(0));
@@ -234,4 +657,6 @@ pub(crate) enum SurroundingContext {
     // pure comptime fn(...) => AttributeSetTrue("__flurry_keyword_pure", AttributeSetTrue("__flurry_keyword_comptime", Definition.fn)),
     AttributeSetTrue(Symbol),
     Attribute(HirId),
+    /// An `unsafe { .. }` block.
+    Unsafe,
 }
@@ -0,0 +1,50 @@
+//! Lints over the lowered package: warnings about code that is valid but
+//! could be written more simply. Each lint scans every expression through
+//! [`Package::all_exprs`](hir::Package::all_exprs).
+
+use hir::{
+    common::{BinOp, LitKind},
+    expr::{Expr, ExprKind},
+};
+
+use crate::LoweringContext;
+
+impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
+    /// Run every lint over the package.
+    pub(crate) fn check_lints(&self) {
+        for expr in self.package.all_exprs() {
+            self.lint_bool_comparison(expr);
+        }
+    }
+
+    /// `x == true` is just `x`, and `x == false` is `not x`; likewise for
+    /// `!=` and with the literal on the left.
+    fn lint_bool_comparison(&self, expr: &Expr<'hir>) {
+        let ExprKind::Binary(op @ (BinOp::Eq | BinOp::Ne), lhs, rhs) = &expr.kind else {
+            return;
+        };
+        let Some(value) = bool_literal(rhs).or_else(|| bool_literal(lhs)) else {
+            return;
+        };
+        let keeps_operand = (*op == BinOp::Eq) == value;
+        let suggestion = if keeps_operand {
+            "use the operand directly"
+        } else {
+            "negate the operand with `not`"
+        };
+        self.warn_bool_comparison(
+            &format!("comparison with `{}` is redundant; {}", value, suggestion),
+            expr.span,
+        );
+    }
+}
+
+fn bool_literal(expr: &Expr<'_>) -> Option<bool> {
+    match &expr.kind {
+        ExprKind::Lit(lit) => match lit.kind {
+            LitKind::Bool(value) => Some(value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,91 @@
+//! Expanding type aliases.
+//!
+//! A `typealias` is another name for its right-hand side, so before two
+//! types are compared, or a type is shown in a diagnostic, a name that
+//! stands for an alias is replaced by what it stands for: with
+//! `typealias Id = Int`, a parameter of type `Id` takes an integer. A
+//! `newtype` is a distinct type with the same representation and is not
+//! expanded; only its own name has it.
+//!
+//! A type name is taken to the definition it resolves to: the one recorded
+//! when it was lowered as a type, or else the one it names from the file's
+//! scope, so two items that share a name in different modules are told
+//! apart. Cycles
+//! are reported by [`check_alias_cycles`], which runs once the package is
+//! lowered, so expansion stops at an alias it has already passed through
+//! instead of relying on the package being free of them.
+//!
+//! [`check_alias_cycles`]: LoweringContext::check_alias_cycles
+
+use hir::{
+    ItemKind,
+    common::{DefId, Symbol},
+    expr::{Expr, ExprKind},
+};
+use resolve::NameLookup;
+
+use crate::{LoweringContext, callable::PrimitiveFamily};
+
+impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
+    /// `ty` with the aliases at its head expanded, until it is not the name
+    /// of an alias. The parts of a compound type are left as written; see
+    /// [`Self::is_subtype`] for how they are compared.
+    pub(crate) fn normalize_type(&self, ty: &'hir Expr<'hir>) -> &'hir Expr<'hir> {
+        let mut ty = ty;
+        let mut expanded = Vec::new();
+        while let Some(def) = self.type_def(ty) {
+            if expanded.contains(&def) {
+                break;
+            }
+            let Some(target) = self.alias_target(def) else {
+                break;
+            };
+            expanded.push(def);
+            ty = target;
+        }
+        ty
+    }
+
+    /// Whether the type `ty` is written as the name of a `newtype`.
+    pub(crate) fn is_newtype(&self, ty: &Expr<'_>) -> bool {
+        self.type_def(ty)
+            .and_then(|def| self.package.def_item(def))
+            .is_some_and(|item| matches!(item.kind, ItemKind::Newtype(_)))
+    }
+
+    /// The definition the type `ty` names, if it is a plain name that
+    /// resolves to one. Primitive names have none.
+    pub(crate) fn type_def(&self, ty: &Expr<'_>) -> Option<DefId> {
+        if let Some(def) = self.package.type_res(ty.hir_id) {
+            return Some(def);
+        }
+        let name = type_name_of(ty)?;
+        if PrimitiveFamily::of_name(name.as_str()).is_some() {
+            return None;
+        }
+        if let Some(cached) = self.type_names.get(&(self.file_scope, name)) {
+            return cached.as_ref().map(|res| res.def_id);
+        }
+        match self.resolver.lookup_name(name.as_str(), self.file_scope) {
+            NameLookup::Single(res) => Some(res.def_id),
+            _ => None,
+        }
+    }
+
+    /// The right-hand side of the type alias `def`, if it is one.
+    fn alias_target(&self, def: DefId) -> Option<&'hir Expr<'hir>> {
+        match self.package.def_item(def)?.kind {
+            ItemKind::TypeAlias(ty) => Some(ty),
+            _ => None,
+        }
+    }
+}
+
+/// The name a type is written as, if it is a plain name.
+fn type_name_of(ty: &Expr<'_>) -> Option<Symbol> {
+    match &ty.kind {
+        ExprKind::Ident(name) => Some(*name),
+        ExprKind::Path(path) if path.segments.len() == 1 => Some(path.segments[0].ident.name),
+        _ => None,
+    }
+}
@@ -2,12 +2,15 @@
 
 use ast::{NodeIndex, NodeKind};
 use hir::{
-    common::BindingMode,
-    pattern::{FieldPat, Pattern, PatternKind},
+    common::{BindingMode, Ident, Symbol},
+    expr::{Expr, ExprKind},
+    item::{Item, ItemKind, Variant, VariantKind},
+    pattern::{FieldPat, Pattern, PatternArm, PatternKind},
 };
+use resolve::{NameLookup, ScopeId};
 use rustc_span::Span;
 
-use crate::LoweringContext;
+use crate::{LoweringContext, callable::type_name, effect::effect_row};
 
 impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
     /// Lower an AST node in pattern position into an HIR [`Pattern`].
@@ -27,9 +30,10 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             // identifier as binding
             NodeKind::Id => {
                 let ident = self.node_to_ident(node);
+                let ident = self.hygienic_binding(ident);
                 Pattern {
                     hir_id: self.next_hir_id(),
-                    kind: PatternKind::Binding(BindingMode::ByValue, ident, None),
+                    kind: PatternKind::Binding(self.binding_mode, ident, None),
                     span,
                 }
             }
@@ -45,9 +49,55 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 }
             }
 
-            // `ref pattern`
+            // `pat?` — the `Some` side of an optional value
+            NodeKind::OptionSomePattern => {
+                let inner = self.lower_pattern(children[0]);
+                let inner_ref = self.arena.alloc_pattern(inner);
+                Pattern {
+                    hir_id: self.next_hir_id(),
+                    kind: PatternKind::OptionSome(inner_ref),
+                    span,
+                }
+            }
+
+            // `pat!` — the `Ok` side of a result value
+            NodeKind::ErrorOkPattern => {
+                let inner = self.lower_pattern(children[0]);
+                let inner_ref = self.arena.alloc_pattern(inner);
+                Pattern {
+                    hir_id: self.next_hir_id(),
+                    kind: PatternKind::ErrorOk(inner_ref),
+                    span,
+                }
+            }
+
+            // `error pat` — the `Err` side of a result value
+            NodeKind::ErrorPattern => {
+                let inner = self.lower_pattern(children[0]);
+                let inner_ref = self.arena.alloc_pattern(inner);
+                Pattern {
+                    hir_id: self.next_hir_id(),
+                    kind: PatternKind::ErrorErr(inner_ref),
+                    span,
+                }
+            }
+
+            // `null` — the empty side of an optional value
+            NodeKind::Null => Pattern {
+                hir_id: self.next_hir_id(),
+                kind: PatternKind::OptionNull,
+                span,
+            },
+
+            // `ref pat`: every name bound inside `pat` binds by reference.
+            // A plain `ref x` is just a by-reference binding.
             NodeKind::RefPattern => {
+                let outer_mode = std::mem::replace(&mut self.binding_mode, BindingMode::ByRef);
                 let inner = self.lower_pattern(children[0]);
+                self.binding_mode = outer_mode;
+                if let PatternKind::Binding(_, _, None) = inner.kind {
+                    return Pattern { span, ..inner };
+                }
                 let inner_ref = self.arena.alloc_pattern(inner);
                 Pattern {
                     hir_id: self.next_hir_id(),
@@ -56,11 +106,44 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 }
             }
 
-            // `a | b` (or-pattern)
+            // `async pat`: awaits the scrutinee and matches its result
+            NodeKind::AsyncPattern => {
+                let inner = self.lower_pattern(children[0]);
+                let inner_ref = self.arena.alloc_pattern(inner);
+                Pattern {
+                    hir_id: self.next_hir_id(),
+                    kind: PatternKind::Async(inner_ref),
+                    span,
+                }
+            }
+
+            // `not pat`: matches when `pat` does not, so it cannot bind
+            NodeKind::NotPattern => {
+                let inner = self.lower_pattern(children[0]);
+                let bound = inner.bindings();
+                if !bound.is_empty() {
+                    let names: Vec<String> = bound.iter().map(|i| format!("`{}`", i.name)).collect();
+                    self.emit_binding_in_not_pattern(
+                        &format!("{} would be bound only when the pattern fails", names.join(", ")),
+                        inner.span,
+                    );
+                }
+                let inner_ref = self.arena.alloc_pattern(inner);
+                Pattern {
+                    hir_id: self.next_hir_id(),
+                    kind: PatternKind::Not(inner_ref),
+                    span,
+                }
+            }
+
+            // `a or b` (or-pattern); nested alternatives are flattened so
+            // `a or b or c` lowers to a single `Or([a, b, c])`.
             NodeKind::OrPattern => {
-                let lhs = self.lower_pattern(children[0]);
-                let rhs = self.lower_pattern(children[1]);
-                let pats = self.arena.alloc_pattern_slice(vec![lhs, rhs]);
+                let mut alt_nodes = Vec::new();
+                self.collect_or_alternatives(node, &mut alt_nodes);
+                let alts: Vec<_> = alt_nodes.iter().map(|&n| self.lower_pattern(n)).collect();
+                self.check_or_bindings(&alts);
+                let pats = self.arena.alloc_pattern_slice(alts);
                 Pattern {
                     hir_id: self.next_hir_id(),
                     kind: PatternKind::Or(pats),
@@ -68,14 +151,14 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 }
             }
 
-            // `pat as name`
+            // `pat as name`: AsBindPattern(pattern, id)
             NodeKind::AsBindPattern => {
-                let name_ident = self.node_to_ident(children[0]);
-                let inner = self.lower_pattern(children[1]);
+                let inner = self.lower_pattern(children[0]);
+                let name_ident = self.node_to_ident(children[1]);
                 let inner_ref = self.arena.alloc_pattern(inner);
                 Pattern {
                     hir_id: self.next_hir_id(),
-                    kind: PatternKind::Binding(BindingMode::ByValue, name_ident, Some(inner_ref)),
+                    kind: PatternKind::Binding(self.binding_mode, name_ident, Some(inner_ref)),
                     span,
                 }
             }
@@ -93,15 +176,37 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 }
             }
 
-            // [a, b, c] list pattern
+            // [a, b, ...rest] list pattern; the rest pattern must come last
             NodeKind::ListPattern => {
                 let elems_node = children[0];
                 let elem_nodes = self.ast.get_multi_child_slice(elems_node).unwrap_or(&[]);
-                let pats: Vec<_> = elem_nodes.iter().map(|&n| self.lower_pattern(n)).collect();
+                let mut pats = Vec::new();
+                let mut rest = None;
+                for (i, &elem) in elem_nodes.iter().enumerate() {
+                    let Some((NodeKind::ListRestPattern, rest_span, rest_children)) =
+                        self.ast.get_node(elem)
+                    else {
+                        pats.push(self.lower_pattern(elem));
+                        continue;
+                    };
+                    if i + 1 != elem_nodes.len() {
+                        self.emit_invalid_pattern(
+                            "a `...rest` pattern must be the last element of a list pattern",
+                            rest_span,
+                        );
+                    }
+                    let ident = self.node_to_ident(rest_children[0]);
+                    let rest_pat = Pattern {
+                        hir_id: self.next_hir_id(),
+                        kind: PatternKind::Binding(self.binding_mode, ident, None),
+                        span: rest_span,
+                    };
+                    rest = Some(self.arena.alloc_pattern(rest_pat) as &_);
+                }
                 let pats_slice = self.arena.alloc_pattern_slice(pats);
                 Pattern {
                     hir_id: self.next_hir_id(),
-                    kind: PatternKind::Tuple(pats_slice),
+                    kind: PatternKind::List(pats_slice, rest),
                     span,
                 }
             }
@@ -114,6 +219,14 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 let args_node = children[1];
                 let arg_nodes = self.ast.get_multi_child_slice(args_node).unwrap_or(&[]);
                 let sub_pats: Vec<_> = arg_nodes.iter().map(|&n| self.lower_pattern(n)).collect();
+                if let Some(path) = pattern_path(callee_ref) {
+                    self.deferred_patterns.push(DeferredPattern::Arity {
+                        path,
+                        arity: sub_pats.len(),
+                        scope: self.file_scope,
+                        span,
+                    });
+                }
                 let sub_pats_slice = self.arena.alloc_pattern_slice(sub_pats);
 
                 Pattern {
@@ -128,41 +241,40 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 let callee = self.lower_pattern(children[0]);
                 let callee_ref = self.arena.alloc_pattern(callee);
 
-                let fields_node = children[1];
-                let field_nodes = self.ast.get_multi_child_slice(fields_node).unwrap_or(&[]);
-                let field_pats: Vec<_> = field_nodes
-                    .iter()
-                    .map(|&n| self.lower_field_pattern(n))
-                    .collect();
+                let (field_pats, has_rest) = self.lower_field_patterns(children[1]);
+                if let Some(path) = pattern_path(callee_ref) {
+                    self.deferred_patterns.push(DeferredPattern::Fields {
+                        path,
+                        fields: field_pats.iter().map(|f| f.ident.clone()).collect(),
+                        has_rest,
+                        scope: self.file_scope,
+                        span,
+                    });
+                }
                 let field_pats_slice = self.arena.alloc_field_pat_slice(field_pats);
 
                 Pattern {
                     hir_id: self.next_hir_id(),
-                    kind: PatternKind::Struct(callee_ref, field_pats_slice, false),
+                    kind: PatternKind::Struct(callee_ref, field_pats_slice, has_rest),
                     span,
                 }
             }
 
             // { field: pat, ... } struct pattern
             NodeKind::StructPattern => {
-                let fields_node = children[0];
-                let field_nodes = self.ast.get_multi_child_slice(fields_node).unwrap_or(&[]);
-                let field_pats: Vec<_> = field_nodes
-                    .iter()
-                    .map(|&n| self.lower_field_pattern(n))
-                    .collect();
+                let (field_pats, has_rest) = self.lower_field_patterns(children[0]);
                 let field_pats_slice = self.arena.alloc_field_pat_slice(field_pats);
 
                 // Use an empty path for anonymous struct patterns
                 let wild = self.arena.alloc_pattern(Pattern {
                     hir_id: self.next_hir_id(),
                     kind: PatternKind::Wild,
-                    span: span,
+                    span,
                 });
 
                 Pattern {
                     hir_id: self.next_hir_id(),
-                    kind: PatternKind::Struct(wild, field_pats_slice, false),
+                    kind: PatternKind::Struct(wild, field_pats_slice, has_rest),
                     span,
                 }
             }
@@ -213,18 +325,30 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
             }
 
             // < expr > pattern (expression-as-pattern)
+            // `< expr >`: equality against the embedded expression's value
             NodeKind::ExprAsPattern => {
                 let expr = self.lower_expr(children[0]);
                 let expr_ref = self.arena.alloc_expr(expr);
                 Pattern {
                     hir_id: self.next_hir_id(),
-                    kind: PatternKind::Const(expr_ref),
+                    kind: PatternKind::Comptime(expr_ref),
+                    span,
+                }
+            }
+
+            // `'T`: type test against the named type
+            NodeKind::TypeBindPattern => {
+                let ty = self.lower_expr(children[0]);
+                let ty_ref = self.arena.alloc_expr(ty);
+                Pattern {
+                    hir_id: self.next_hir_id(),
+                    kind: PatternKind::Type(ty_ref),
                     span,
                 }
             }
 
-            // Unit / null / undefined
-            NodeKind::Unit | NodeKind::Null | NodeKind::Undefined => {
+            // Unit / undefined
+            NodeKind::Unit | NodeKind::Undefined => {
                 let expr = self.lower_expr(node);
                 let expr_ref = self.arena.alloc_expr(expr);
                 Pattern {
@@ -247,6 +371,13 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 }
             }
 
+            // `pat if cond` is only meaningful as a whole match arm, where
+            // `lower_match_arm` turns it into the arm's guard.
+            NodeKind::IfGuardPattern => {
+                self.emit_invalid_pattern("`if` guards are only allowed on a whole match arm", span);
+                self.make_error_pattern(span)
+            }
+
             other => {
                 self.emit_invalid_pattern(&format!("{:?}", other), span);
                 self.make_error_pattern(span)
@@ -254,6 +385,28 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         }
     }
 
+    /// Lower the `{ … }` body of a struct pattern.  Returns the field
+    /// patterns and whether a trailing `...` ignores the remaining fields.
+    fn lower_field_patterns(&mut self, fields_node: NodeIndex) -> (Vec<FieldPat<'hir>>, bool) {
+        let field_nodes = self.ast.get_multi_child_slice(fields_node).unwrap_or(&[]);
+        let mut field_pats = Vec::with_capacity(field_nodes.len());
+        let mut has_rest = false;
+        for (i, &node) in field_nodes.iter().enumerate() {
+            let Some((NodeKind::StructRestPattern, rest_span, _)) = self.ast.get_node(node) else {
+                field_pats.push(self.lower_field_pattern(node));
+                continue;
+            };
+            if i + 1 != field_nodes.len() {
+                self.emit_invalid_pattern(
+                    "a `...` pattern must be the last element of a struct pattern",
+                    rest_span,
+                );
+            }
+            has_rest = true;
+        }
+        (field_pats, has_rest)
+    }
+
     /// Lower a field pattern node (`id: pattern`).
     fn lower_field_pattern(&mut self, node: NodeIndex) -> FieldPat<'hir> {
         let Some((kind, span, children)) = self.ast.get_node(node) else {
@@ -271,7 +424,7 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
                 let ident = self.node_to_ident(node);
                 let pat = Pattern {
                     hir_id: self.next_hir_id(),
-                    kind: PatternKind::Binding(BindingMode::ByValue, ident.clone(), None),
+                    kind: PatternKind::Binding(self.binding_mode, ident.clone(), None),
                     span,
                 };
                 FieldPat { ident, pat, span }
@@ -279,6 +432,370 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         }
     }
 
+    /// Check that the arms of a `match` agree on the kind of value the
+    /// scrutinee is.
+    ///
+    /// An `ok`/`error` arm implies a result scrutinee, a `some`/`null` arm an
+    /// optional one and an `async` arm an awaitable one.  Mixing families,
+    /// using any of them against a literal scrutinee, or against a
+    /// scrutinee whose type `scrutinee_ty` is known to be of another form,
+    /// can never match and is reported.
+    pub(crate) fn check_match_arms(
+        &self,
+        scrutinee: &Expr<'hir>,
+        scrutinee_ty: Option<&'hir Expr<'hir>>,
+        arms: &[PatternArm<'hir>],
+    ) {
+        let mut expected: Option<PatternFamily> = None;
+
+        for arm in arms {
+            self.check_pattern_arity(scrutinee, &arm.pat);
+
+            let Some(family) = PatternFamily::of(&arm.pat) else {
+                continue;
+            };
+
+            if matches!(scrutinee.kind, ExprKind::Lit(_)) {
+                self.emit_mismatched_pattern(
+                    &format!("{} pattern cannot match a literal scrutinee", family.name()),
+                    arm.pat.span,
+                );
+                continue;
+            }
+
+            if let Some(ty) = scrutinee_ty
+                && !self.family_admits(family, ty)
+            {
+                self.emit_mismatched_pattern(
+                    &format!(
+                        "{} pattern cannot match a value of type `{}`",
+                        family.name(),
+                        type_name(ty)
+                    ),
+                    arm.pat.span,
+                );
+                continue;
+            }
+
+            match expected {
+                None => expected = Some(family),
+                Some(prev) if prev != family => self.emit_mismatched_pattern(
+                    &format!(
+                        "{} pattern in a match whose earlier arms destructure {} value",
+                        family.name(),
+                        prev.article_name(),
+                    ),
+                    arm.pat.span,
+                ),
+                Some(_) => {}
+            }
+        }
+    }
+
+    /// Whether a value of type `ty` can be destructured by a pattern of
+    /// `family`.  A type named by a local, such as a type parameter, may
+    /// be of any form.
+    fn family_admits(&self, family: PatternFamily, ty: &'hir Expr<'hir>) -> bool {
+        let ty = self.normalize_type(ty);
+        match &ty.kind {
+            ExprKind::Ident(name) if self.is_local(*name) => true,
+            ExprKind::TyPlaceholder => true,
+            ExprKind::TyClosureQualified(_, inner) => self.family_admits(family, inner),
+            _ => match family {
+                PatternFamily::Result => matches!(ty.kind, ExprKind::ErrorQualifiedType(..)),
+                PatternFamily::Optional => matches!(ty.kind, ExprKind::TyOptional(_)),
+                PatternFamily::Async => is_async_type(ty),
+            },
+        }
+    }
+
+    /// Collect the type of each name `pat` binds when it matches a value of
+    /// type `ty`: the payload of a result, optional or async value for
+    /// `pat!`, `pat?` and `async pat`, the error set for `error pat`, and a
+    /// pointer to the matched part for a by-reference binding.  Names in
+    /// parts whose type is not known here are left untyped.
+    pub(crate) fn pattern_binding_types(
+        &mut self,
+        pat: &Pattern<'hir>,
+        ty: Option<&'hir Expr<'hir>>,
+        out: &mut Vec<(Symbol, Option<&'hir Expr<'hir>>)>,
+    ) {
+        let ty = ty.map(|ty| self.normalize_type(ty));
+        let kind = ty.map(|ty| &ty.kind);
+        match &pat.kind {
+            PatternKind::Binding(mode, ident, sub) => {
+                let bound = match mode {
+                    BindingMode::ByRef => ty.map(|ty| self.pointer_type(ty, pat.span)),
+                    BindingMode::ByValue => ty,
+                };
+                out.push((ident.name, bound));
+                if let Some(sub) = sub {
+                    self.pattern_binding_types(sub, ty, out);
+                }
+            }
+            PatternKind::ErrorOk(inner) => {
+                let ok = match kind {
+                    Some(ExprKind::ErrorQualifiedType(_, ok)) => Some(*ok),
+                    _ => None,
+                };
+                self.pattern_binding_types(inner, ok, out);
+            }
+            PatternKind::ErrorErr(inner) => {
+                let errors = match kind {
+                    Some(ExprKind::ErrorQualifiedType(errors, _)) => Some(*errors),
+                    _ => None,
+                };
+                self.pattern_binding_types(inner, errors, out);
+            }
+            PatternKind::OptionSome(inner) => {
+                let some = match kind {
+                    Some(ExprKind::TyOptional(some)) => Some(*some),
+                    _ => None,
+                };
+                self.pattern_binding_types(inner, some, out);
+            }
+            PatternKind::Async(inner) => {
+                let result = match ty {
+                    Some(ty) if is_async_type(ty) => Some(effect_row(ty).1),
+                    _ => None,
+                };
+                self.pattern_binding_types(inner, result, out);
+            }
+            PatternKind::Ref(inner) => self.pattern_binding_types(inner, ty, out),
+            _ => pat.walk_bindings(&mut |ident| out.push((ident.name, None))),
+        }
+    }
+
+    /// The pointer type `*ty`.
+    fn pointer_type(&mut self, ty: &'hir Expr<'hir>, span: Span) -> &'hir Expr<'hir> {
+        let ptr = Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::TyPtr(ty),
+            span,
+        };
+        self.arena.alloc_expr(ptr)
+    }
+
+    /// Check a tuple or list pattern against a tuple / list literal
+    /// scrutinee: a tuple pattern must have the literal's arity, and a list
+    /// pattern may not have more fixed elements than the literal.
+    fn check_pattern_arity(&self, scrutinee: &Expr<'hir>, pat: &Pattern<'hir>) {
+        match (&scrutinee.kind, &pat.kind) {
+            (ExprKind::Tuple(elems), PatternKind::Tuple(pats)) if elems.len() != pats.len() => {
+                self.emit_mismatched_pattern(
+                    &format!(
+                        "tuple pattern has {} element(s) but the scrutinee has {}",
+                        pats.len(),
+                        elems.len()
+                    ),
+                    pat.span,
+                );
+            }
+            (ExprKind::List(elems), PatternKind::List(pats, rest)) => {
+                let too_long = pats.len() > elems.len();
+                let too_short = rest.is_none() && pats.len() < elems.len();
+                if too_long || too_short {
+                    self.emit_mismatched_pattern(
+                        &format!(
+                            "list pattern has {} fixed element(s) but the scrutinee has {}",
+                            pats.len(),
+                            elems.len()
+                        ),
+                        pat.span,
+                    );
+                }
+            }
+            (_, PatternKind::Binding(_, _, Some(inner))) => self.check_pattern_arity(scrutinee, inner),
+            (_, PatternKind::Or(alts)) => {
+                for alt in alts.iter() {
+                    self.check_pattern_arity(scrutinee, alt);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Check the deferred struct / variant patterns against the item
+    /// definitions of the package, now that all items have been lowered.
+    ///
+    /// A one-segment path names a struct; `Enum.Variant` names a variant.
+    /// Paths are resolved from the scope the pattern is written in; those
+    /// that do not name a definition lowered so far are left to later passes.
+    pub(crate) fn check_deferred_patterns(&mut self) {
+        for deferred in std::mem::take(&mut self.deferred_patterns) {
+            match deferred {
+                DeferredPattern::Fields {
+                    path,
+                    fields,
+                    has_rest,
+                    scope,
+                    span,
+                } => {
+                    let Some((name, known)) = self.lookup_pattern_fields(&path, scope) else {
+                        continue;
+                    };
+                    for field in &fields {
+                        if !known.contains(&field.name) {
+                            self.emit_unknown_pattern_field(
+                                &format!("`{}` has no field named `{}`", name, field.name),
+                                field.span,
+                            );
+                        }
+                    }
+                    let missing: Vec<String> = known
+                        .iter()
+                        .filter(|k| !fields.iter().any(|f| f.name == **k))
+                        .map(|k| format!("`{}`", k))
+                        .collect();
+                    if !has_rest && !missing.is_empty() {
+                        self.emit_mismatched_pattern(
+                            &format!(
+                                "pattern on `{}` does not mention {}; add `...` to ignore the remaining fields",
+                                name,
+                                missing.join(", ")
+                            ),
+                            span,
+                        );
+                    }
+                }
+                DeferredPattern::Arity {
+                    path,
+                    arity,
+                    scope,
+                    span,
+                } => {
+                    let Some(expected) = self.lookup_variant_arity(&path, scope) else {
+                        continue;
+                    };
+                    if expected != arity {
+                        self.emit_mismatched_pattern(
+                            &format!(
+                                "`{}` has {} field(s) but the pattern has {}",
+                                join_path(&path),
+                                expected,
+                                arity
+                            ),
+                            span,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Field names of the struct (or struct-like enum variant) named by `path`.
+    fn lookup_pattern_fields(
+        &self,
+        path: &[Symbol],
+        scope: ScopeId,
+    ) -> Option<(String, Vec<Symbol>)> {
+        let fields = match path {
+            [_] => match &self.resolve_item(path, scope)?.kind {
+                ItemKind::Struct(def) => def.fields,
+                _ => return None,
+            },
+            [enum_path @ .., variant] => {
+                match self.find_variant(enum_path, *variant, scope)?.kind {
+                    VariantKind::Struct(fields) => fields,
+                    _ => return None,
+                }
+            }
+            [] => return None,
+        };
+        Some((join_path(path), fields.iter().map(|f| f.ident.name).collect()))
+    }
+
+    /// Payload arity of the tuple-like enum variant named by `Enum.Variant`.
+    fn lookup_variant_arity(&self, path: &[Symbol], scope: ScopeId) -> Option<usize> {
+        let [enum_path @ .., variant] = path else {
+            return None;
+        };
+        match self.find_variant(enum_path, *variant, scope)?.kind {
+            VariantKind::Tuple(elems) => Some(elems.len()),
+            _ => None,
+        }
+    }
+
+    /// The item `path` names from `scope`, if it has been lowered.
+    fn resolve_item(&self, path: &[Symbol], scope: ScopeId) -> Option<&'hir Item<'hir>> {
+        let def = match path {
+            [name] => match self.resolver.lookup_name(name.as_str(), scope) {
+                NameLookup::Single(res) => res.def_id,
+                _ => return None,
+            },
+            _ => {
+                let segments: Vec<String> = path.iter().map(|s| s.to_string()).collect();
+                self.resolver
+                    .resolve_path(&segments, scope, Span::default())
+                    .ok()?
+                    .def_id
+            }
+        };
+        self.package.def_item(def)
+    }
+
+    fn find_variant(
+        &self,
+        enum_path: &[Symbol],
+        variant: Symbol,
+        scope: ScopeId,
+    ) -> Option<&'hir Variant<'hir>> {
+        if enum_path.is_empty() {
+            return None;
+        }
+        match &self.resolve_item(enum_path, scope)?.kind {
+            ItemKind::Enum(def) => def.variants.iter().find(|v| v.ident.name == variant),
+            _ => None,
+        }
+    }
+
+    /// Collect the leaf alternatives of a (possibly nested) `OrPattern`.
+    fn collect_or_alternatives(&self, node: NodeIndex, out: &mut Vec<NodeIndex>) {
+        if self.ast.get_node_kind(node) == Some(NodeKind::OrPattern) {
+            let children = self.ast.get_children(node);
+            self.collect_or_alternatives(children[0], out);
+            self.collect_or_alternatives(children[1], out);
+        } else {
+            out.push(node);
+        }
+    }
+
+    /// Check that every alternative of an or-pattern binds the same names
+    /// as the first one; whichever alternative matches, the arm body sees
+    /// the same set of variables.
+    fn check_or_bindings(&self, alts: &[Pattern<'hir>]) {
+        let Some((first, rest)) = alts.split_first() else {
+            return;
+        };
+        let expected = first.bindings();
+
+        for alt in rest {
+            let found = alt.bindings();
+            let missing: Vec<_> = expected
+                .iter()
+                .filter(|e| !found.iter().any(|f| f.name == e.name))
+                .map(|e| format!("`{}`", e.name))
+                .collect();
+            let extra: Vec<_> = found
+                .iter()
+                .filter(|f| !expected.iter().any(|e| e.name == f.name))
+                .map(|f| format!("`{}`", f.name))
+                .collect();
+            if missing.is_empty() && extra.is_empty() {
+                continue;
+            }
+
+            let mut parts = Vec::new();
+            if !missing.is_empty() {
+                parts.push(format!("missing {}", missing.join(", ")));
+            }
+            if !extra.is_empty() {
+                parts.push(format!("not bound in the first alternative: {}", extra.join(", ")));
+            }
+            self.emit_inconsistent_or_bindings(&parts.join("; "), alt.span);
+        }
+    }
+
     /// Create an error pattern (used as a recovery node).
     pub(crate) fn make_error_pattern(&mut self, span: Span) -> Pattern<'hir> {
         Pattern {
@@ -288,3 +805,99 @@ impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
         }
     }
 }
+
+/// A destructuring pattern whose shape is checked against the package's
+/// item definitions after the whole file has been lowered.
+pub(crate) enum DeferredPattern {
+    /// `Path { field: pat, … }` – every field must exist on the target, and
+    /// unless the pattern ends in `...` every field of the target must be
+    /// mentioned.
+    Fields {
+        path: Vec<Symbol>,
+        fields: Vec<Ident>,
+        has_rest: bool,
+        /// The scope the pattern is written in, which `path` resolves from.
+        scope: ScopeId,
+        span: Span,
+    },
+    /// `Path(pat, …)` – the arity must match the target variant.
+    Arity {
+        path: Vec<Symbol>,
+        arity: usize,
+        scope: ScopeId,
+        span: Span,
+    },
+}
+
+/// The name path of a constructor pattern (`Point`, `Shape.Circle`), if the
+/// callee is a plain name or a projection chain over one.
+fn pattern_path(pat: &Pattern<'_>) -> Option<Vec<Symbol>> {
+    match &pat.kind {
+        PatternKind::Binding(_, ident, None) => Some(vec![ident.name]),
+        PatternKind::Projection(base, ident) => {
+            let mut path = pattern_path(base)?;
+            path.push(ident.name);
+            Some(path)
+        }
+        _ => None,
+    }
+}
+
+fn join_path(path: &[Symbol]) -> String {
+    path.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(".")
+}
+
+/// Whether `ty` is an async value's type, `#Async T`: one computed by
+/// performing the `Async` effect.
+fn is_async_type(ty: &Expr<'_>) -> bool {
+    let ExprKind::EffectQualifiedType(row, _) = &ty.kind else {
+        return false;
+    };
+    let effects = match &row.kind {
+        ExprKind::TyEffectRow(effects) => *effects,
+        _ => std::slice::from_ref(*row),
+    };
+    effects
+        .iter()
+        .any(|effect| matches!(effect.kind, ExprKind::Ident(name) if name.as_str() == "Async"))
+}
+
+/// The kind of value a pattern destructures, as far as its syntax tells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PatternFamily {
+    /// `pat!` / `error pat`
+    Result,
+    /// `pat?` / `null`
+    Optional,
+    /// `async pat`
+    Async,
+}
+
+impl PatternFamily {
+    fn of(pat: &Pattern<'_>) -> Option<Self> {
+        match &pat.kind {
+            PatternKind::ErrorOk(_) | PatternKind::ErrorErr(_) => Some(PatternFamily::Result),
+            PatternKind::OptionSome(_) | PatternKind::OptionNull => Some(PatternFamily::Optional),
+            PatternKind::Async(_) => Some(PatternFamily::Async),
+            PatternKind::Binding(_, _, Some(inner)) | PatternKind::Not(inner) => Self::of(inner),
+            PatternKind::Or(alts) => alts.iter().find_map(Self::of),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            PatternFamily::Result => "result",
+            PatternFamily::Optional => "optional",
+            PatternFamily::Async => "async",
+        }
+    }
+
+    fn article_name(self) -> &'static str {
+        match self {
+            PatternFamily::Result => "a result",
+            PatternFamily::Optional => "an optional",
+            PatternFamily::Async => "an async",
+        }
+    }
+}
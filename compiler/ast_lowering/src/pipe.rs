@@ -0,0 +1,143 @@
+//! Lowering the pipe operator `a |> f`.
+//!
+//! The left operand becomes the first argument of the right one: `a |> f`
+//! is `f(a)` and `a |> f(b)` is `f(a, b)`. When `f` is a method of the left
+//! operand's type, the pipe is the method call `a.f(b)` instead, so
+//! `xs |> len` finds `List::len`. The type is what [`infer_type`] reads off
+//! the syntax, and its methods are those declared in its body or in an
+//! inherent `impl` of the file being lowered.
+//!
+//! A name piped into that is neither a method, a local, a builtin nor a
+//! name in scope where it is written is reported once every item of the
+//! file has been lowered.
+//!
+//! [`infer_type`]: LoweringContext::infer_type
+
+use ast::{NodeIndex, NodeKind};
+use diagnostic::FlurryError;
+use hir::{
+    common::{Arg, Ident, Symbol},
+    expr::{Expr, ExprKind},
+};
+use intrinsic::BuiltinFn;
+use resolve::{BindingKind, ImplKind, NameLookup, ResolveError};
+use rustc_span::Span;
+
+use crate::LoweringContext;
+
+impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
+    /// Lower `lhs |> rhs`, where `rhs` is a callee or a call.
+    pub(crate) fn lower_pipe(&mut self, lhs: NodeIndex, rhs: NodeIndex, span: Span) -> Expr<'hir> {
+        let receiver = self.lower_expr(lhs);
+        let (callee_node, arg_nodes) = match self.ast.get_node(rhs) {
+            Some((NodeKind::Application, _, children)) => (
+                children[0],
+                self.ast.get_multi_child_slice(children[1]).unwrap_or(&[]),
+            ),
+            _ => (rhs, &[][..]),
+        };
+        let callee = self.lower_expr(callee_node);
+        let mut args: Vec<Arg<'hir>> = arg_nodes
+            .iter()
+            .map(|&n| {
+                let e = self.lower_expr(n);
+                Arg::Positional(self.arena.alloc_expr(e))
+            })
+            .collect();
+
+        if let ExprKind::Ident(name) = callee.kind
+            && !self.is_local(name)
+        {
+            if self.is_method_of(&receiver, name) {
+                let receiver_ref = self.arena.alloc_expr(receiver);
+                let method = Expr {
+                    hir_id: self.next_hir_id(),
+                    kind: ExprKind::Projection(receiver_ref, Ident::new(name, callee.span)),
+                    span: callee.span,
+                };
+                let method_ref = self.arena.alloc_expr(method);
+                return Expr {
+                    hir_id: self.next_hir_id(),
+                    kind: ExprKind::Application(method_ref, self.arena.alloc_arg_slice(args)),
+                    span,
+                };
+            }
+            if BuiltinFn::lookup(name).is_none() {
+                self.pipe_targets
+                    .push((Ident::new(name, callee.span), self.file_scope));
+            }
+        }
+
+        args.insert(0, Arg::Positional(self.arena.alloc_expr(receiver)));
+        let callee_ref = self.arena.alloc_expr(callee);
+        let args_slice = self.arena.alloc_arg_slice(args);
+        let call = Expr {
+            hir_id: self.next_hir_id(),
+            kind: ExprKind::Application(callee_ref, args_slice),
+            span,
+        };
+        self.check_call(callee_ref, args_slice, &call);
+        call
+    }
+
+    /// Whether `name` is a method of the type `receiver` is known to have.
+    fn is_method_of(&mut self, receiver: &Expr<'hir>, name: Symbol) -> bool {
+        let Some(ty) = self.infer_type(receiver) else {
+            return false;
+        };
+        let ExprKind::Ident(ty_name) = ty.kind else {
+            return false;
+        };
+
+        let path = [ty_name.to_string(), name.to_string()];
+        if let Ok(res) = self.resolver.resolve_path(&path, self.file_scope, ty.span)
+            && res.kind == BindingKind::Function
+        {
+            return true;
+        }
+
+        let Some(ty_def) = self.resolve_type_name(ty_name, ty.span) else {
+            return false;
+        };
+        let Some((file, file_scope)) = self.file else {
+            return false;
+        };
+        let scopes = self.resolver.scope_tree();
+        self.resolver
+            .module_tree()
+            .impls
+            .iter()
+            // Only this file's impls have their node in `self.ast`. File
+            // ids are per package, so the impl must also be below the
+            // file's scope.
+            .filter(|d| {
+                d.kind == ImplKind::Inherent
+                    && d.file_id == file
+                    && scopes
+                        .ancestors(d.owner_scope)
+                        .any(|scope| scope.id == file_scope)
+            })
+            .filter(|d| {
+                let target = self.source_text(self.ast.get_children(d.ast_node)[0]);
+                let res = self.resolver.lookup_name(&target, d.owner_scope);
+                matches!(res, NameLookup::Single(res) if res.def_id == ty_def.def_id)
+            })
+            .filter_map(|d| scopes.get(d.impl_scope)?.items.get_direct(name.as_str()))
+            .any(|binding| binding.kind == BindingKind::Function)
+    }
+
+    /// Report the names piped into that name nothing from where they were
+    /// written.
+    pub(crate) fn check_pipe_targets(&mut self) {
+        for (target, scope) in std::mem::take(&mut self.pipe_targets) {
+            if self.resolver.lookup_name(target.name.as_str(), scope) != NameLookup::NotFound {
+                continue;
+            }
+            ResolveError::UnresolvedName {
+                name: target.name.to_string(),
+                span: target.span,
+            }
+            .emit(self.diag_ctx, rustc_span::BytePos(0));
+        }
+    }
+}
@@ -0,0 +1,2682 @@
+//! Lowering tests: run the front end (parse → resolve → lower) over a
+//! source snippet and inspect the resulting HIR and diagnostics.
+
+use std::path::PathBuf;
+
+use diagnostic::{
+    DiagnosticContext, FlurryError,
+    testing::{assert_diagnostic, assert_registered, primary_span_text},
+};
+use hir::{
+    ExprKind, HirArena, Package, ValueRes, VariantKind, VariantPayload, arms_are_exhaustive,
+    common::{Arg, BindingMode, LitKind, Symbol, UnOp},
+    expr::Expr,
+    pattern::{PatternArm, PatternKind},
+};
+use resolve::{BindingKind, NameLookup, ResolveError, Resolver, ScopeId};
+use rustc_span::{BytePos, FileName, SourceFile, SourceMap, Span, source_map::FilePathMapping};
+
+/// Everything produced by running the front end over a test snippet.
+struct Lowered<'a> {
+    package: &'a Package<'a>,
+    diag: &'a DiagnosticContext<'a>,
+    resolver: &'a Resolver<'a>,
+    file_scope: ScopeId,
+    source_file: &'a SourceFile,
+    summary: crate::LoweringSummary,
+}
+
+/// Lower `src` as the entry file of a single-file package and hand the
+/// resulting package and the diagnostic context to `f`.
+fn lower_source<R>(src: &str, f: impl FnOnce(&Package<'_>, &DiagnosticContext<'_>) -> R) -> R {
+    lower_source_full(src, |lowered| f(lowered.package, lowered.diag))
+}
+
+/// Like [`lower_source`], but also hands out the resolver and source file.
+fn lower_source_full<R>(src: &str, f: impl FnOnce(Lowered<'_>) -> R) -> R {
+    let source_map = SourceMap::new(FilePathMapping::empty());
+    let source_file =
+        source_map.new_source_file(FileName::Custom("main.fl".into()), src.to_string());
+    let diag_ctx = DiagnosticContext::new(&source_map);
+
+    let mut vfs = vfs::Vfs::new("test", PathBuf::from("."));
+    let file_id = vfs.add_file(PathBuf::from("main.fl"), source_file.clone());
+    let module_tree =
+        resolve::build_module_tree_with(&source_map, &diag_ctx, &mut vfs, build_options());
+    let file_scope = module_tree.file_scope(0, file_id).unwrap_or(ScopeId::ROOT);
+    let resolver = Resolver::new(&module_tree);
+    let ast = vfs.get_ast(file_id).expect("AST not found");
+
+    let arena = HirArena::new();
+    let mut package = Package::new();
+    let summary = crate::lower_to_hir(
+        ast,
+        &arena,
+        &source_map,
+        &diag_ctx,
+        &mut package,
+        &resolver,
+        file_scope,
+    );
+
+    f(Lowered {
+        package: &package,
+        diag: &diag_ctx,
+        resolver: &resolver,
+        file_scope,
+        source_file: &source_file,
+        summary,
+    })
+}
+
+/// The options the driver builds module trees with, so items named after
+/// a builtin are warned about.
+fn build_options() -> resolve::BuildOptions {
+    resolve::BuildOptions {
+        builtin_names: &intrinsic::BUILTIN_NAMES,
+    }
+}
+
+/// Build the module tree of a package made of `files`, given as
+/// `(relative path, source)` pairs.
+fn build_tree<R>(
+    files: &[(&str, &str)],
+    f: impl FnOnce(&resolve::ModuleTree, &[vfs::FileId], &DiagnosticContext<'_>) -> R,
+) -> R {
+    let source_map = SourceMap::new(FilePathMapping::empty());
+    let diag_ctx = DiagnosticContext::new(&source_map);
+    let mut vfs = vfs::Vfs::new("test", PathBuf::from("."));
+    let file_ids: Vec<_> = files
+        .iter()
+        .map(|(path, src)| {
+            let source_file =
+                source_map.new_source_file(FileName::Custom(path.to_string()), src.to_string());
+            vfs.add_file(PathBuf::from(path), source_file)
+        })
+        .collect();
+    let module_tree =
+        resolve::build_module_tree_with(&source_map, &diag_ctx, &mut vfs, build_options());
+    f(&module_tree, &file_ids, &diag_ctx)
+}
+
+/// The dotted module path of the scope holding `file`'s items, below the
+/// package scope.
+fn file_module_path(tree: &resolve::ModuleTree, file: vfs::FileId) -> String {
+    let scope = tree.packages[0].file_scopes[&file];
+    let mut names: Vec<String> = tree
+        .scope_tree
+        .ancestors(scope)
+        .take_while(|scope| scope.kind == resolve::scope::ScopeKind::Module)
+        .filter_map(|scope| scope.name.map(|name| name.to_string()))
+        .collect();
+    names.reverse();
+    names.join(".")
+}
+
+/// Error codes of all emitted diagnostics, in emission order.
+fn codes(diag: &DiagnosticContext<'_>) -> Vec<u32> {
+    // SAFETY: no diagnostics are emitted while the slice is borrowed.
+    let diagnostics = unsafe { diag.diagnostics() };
+    diagnostics.iter().filter_map(|d| d.code).collect()
+}
+
+/// Messages of all emitted diagnostics, in emission order.
+fn messages(diag: &DiagnosticContext<'_>) -> Vec<String> {
+    // SAFETY: as in `codes`.
+    let diagnostics = unsafe { diag.diagnostics() };
+    diagnostics.iter().map(|d| d.message.clone()).collect()
+}
+
+/// Collect the arms of every `match` expression in the package's bodies,
+/// in source order.
+fn match_arms<'hir>(package: &Package<'hir>) -> Vec<&'hir [PatternArm<'hir>]> {
+    fn walk<'hir>(expr: &'hir Expr<'hir>, out: &mut Vec<&'hir [PatternArm<'hir>]>) {
+        match &expr.kind {
+            ExprKind::Match(scrutinee, arms) => {
+                walk(scrutinee, out);
+                out.push(arms);
+                for arm in arms.iter() {
+                    walk(arm.body, out);
+                }
+            }
+            ExprKind::Block(block) | ExprKind::Loop(block) => {
+                for stmt in block.stmts {
+                    walk(stmt, out);
+                }
+                if let Some(e) = block.expr {
+                    walk(e, out);
+                }
+            }
+            ExprKind::Let(decl) => {
+                if let Some(init) = decl.init {
+                    walk(init, out);
+                }
+            }
+            ExprKind::Semi(e) => walk(e, out),
+            _ => {}
+        }
+    }
+
+    let mut bodies: Vec<_> = package.bodies().collect();
+    bodies.sort_by_key(|(_, body)| body.value.span.lo());
+    let mut out = Vec::new();
+    for (_, body) in bodies {
+        walk(body.value, &mut out);
+    }
+    out
+}
+
+#[test]
+fn result_patterns_lower_to_ok_and_err() {
+    let src = "fn f(r: !ParseError Int) {\n    let y = r match {\n        x! => x,\n        error e => 0,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", codes(diag));
+        let matches = match_arms(package);
+        let arms = matches[0];
+        assert!(matches!(
+            arms[0].pat.kind,
+            PatternKind::ErrorOk(inner) if matches!(inner.kind, PatternKind::Binding(..))
+        ));
+        assert!(matches!(
+            arms[1].pat.kind,
+            PatternKind::ErrorErr(inner) if matches!(inner.kind, PatternKind::Binding(..))
+        ));
+    });
+}
+
+#[test]
+fn optional_patterns_lower_to_some_and_null() {
+    let src = "fn f(o: ?Int) {\n    let y = o match {\n        x? => x,\n        null => 0,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", codes(diag));
+        let arms = match_arms(package)[0];
+        assert!(matches!(arms[0].pat.kind, PatternKind::OptionSome(_)));
+        assert!(matches!(arms[1].pat.kind, PatternKind::OptionNull));
+    });
+}
+
+#[test]
+fn result_and_optional_arms_do_not_mix() {
+    let src = "fn f(r: !ParseError Int) {\n    let y = r match {\n        x! => x,\n        null => 0,\n    };\n    let z = 1 match {\n        x? => x,\n        _ => 0,\n    };\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), vec![3011, 3011]);
+    });
+}
+
+#[test]
+fn result_and_optional_arms_need_a_scrutinee_of_that_type() {
+    let src = "fn f(r: Int, o: !ParseError Int) {\n    let y = r match {\n        x! => x,\n        error e => 0,\n    };\n    let z = o match {\n        x? => x,\n        _ => 0,\n    };\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), vec![3011, 3011, 3011]);
+        assert!(messages(diag)[0].contains("result pattern cannot match a value of type `Int`"));
+        assert_diagnostic(diag, 3011, "error e");
+        assert!(messages(diag)[2].contains("optional pattern"));
+    });
+}
+
+#[test]
+fn as_bind_pattern_binds_whole_value_and_parts() {
+    let src = "fn f(o: ?Int) {\n    let y = o match {\n        x? as whole => whole,\n        null => 0,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", codes(diag));
+        let pat = &match_arms(package)[0][0].pat;
+        let PatternKind::Binding(_, whole, Some(inner)) = &pat.kind else {
+            panic!("expected an as-binding, got {:?}", pat.kind);
+        };
+        assert_eq!(&*whole.name, "whole");
+        assert!(matches!(inner.kind, PatternKind::OptionSome(_)));
+        let names: Vec<String> = pat.bindings().iter().map(|i| i.name.to_string()).collect();
+        assert_eq!(names, ["whole", "x"]);
+    });
+}
+
+#[test]
+fn or_pattern_alternatives_bind_the_same_names() {
+    let src = "fn f(p: Int) {\n    let y = p match {\n        (a, 1) or (a, 2) or (a, 3) => a,\n        _ => 0,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", codes(diag));
+        let PatternKind::Or(alts) = match_arms(package)[0][0].pat.kind else {
+            panic!("expected an or-pattern");
+        };
+        assert_eq!(alts.len(), 3);
+    });
+}
+
+#[test]
+fn or_pattern_with_inconsistent_bindings_is_reported() {
+    let src = "fn f(p: Int) {\n    let y = p match {\n        (a, 1) or (b, 2) or (a, 3) => 0,\n        _ => 0,\n    };\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), vec![3012]);
+        let messages = messages(diag);
+        assert!(messages[0].contains("missing `a`"));
+        assert!(messages[0].contains("`b`"));
+    });
+}
+
+#[test]
+fn guarded_arm_keeps_pattern_and_guard() {
+    let src = "fn f(n: Int) {\n    let y = n match {\n        x if x > 0 => x,\n        _ => 0,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", codes(diag));
+        let arms = match_arms(package)[0];
+        assert!(matches!(arms[0].pat.kind, PatternKind::Binding(_, _, None)));
+        assert!(matches!(
+            arms[0].guard.map(|g| &g.kind),
+            Some(ExprKind::Binary(hir::BinOp::Gt, ..))
+        ));
+        assert!(!arms[0].is_total());
+        assert!(arms[1].guard.is_none());
+        assert!(arms_are_exhaustive(arms));
+    });
+}
+
+#[test]
+fn guarded_wildcard_is_not_exhaustive() {
+    let src = "fn f(n: Int, r: !ParseError Int, c: Bool) {\n    let y = n match {\n        1 => 1,\n        _ if c => 0,\n    };\n    let z = r match {\n        x! if c => x,\n        error e => 0,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", codes(diag));
+        let matches = match_arms(package);
+        assert!(!arms_are_exhaustive(matches[0]));
+        assert!(!arms_are_exhaustive(matches[1]));
+    });
+}
+
+#[test]
+fn struct_pattern_fields_are_checked_against_the_definition() {
+    let src = "struct Point {\n    x: Int,\n    y: Int,\n}\n\nfn f(p: Point) {\n    let a = p match {\n        Point { x: 0, y } => y,\n        Point { x, y, z } => x,\n    };\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), vec![3013]);
+        assert_diagnostic(diag, 3013, "z");
+    });
+}
+
+#[test]
+fn variant_pattern_arity_is_checked_against_the_definition() {
+    let src = "enum Shape {\n    Circle(Int),\n    Rect(Int, Int),\n}\n\nfn f(s: Shape) {\n    let a = s match {\n        Shape.Circle(r) => r,\n        Shape.Rect(w) => w,\n    };\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), vec![3011]);
+        assert!(messages(diag)[0].contains("`Shape.Rect` has 2 field(s)"));
+    });
+}
+
+#[test]
+fn variant_patterns_are_checked_against_the_enum_their_path_resolves_to() {
+    let src = "mod one {\n    enum Shape {\n        Circle(Int),\n    }\n\n    fn f(s: Shape) {\n        let a = s match {\n            Shape.Circle(r) => r,\n        };\n    }\n}\n\nmod two {\n    enum Shape {\n        Circle(Int, Int),\n    }\n\n    fn f(s: Shape) {\n        let a = s match {\n            Shape.Circle(x, y) => x,\n            Shape.Circle(r) => r,\n        };\n    }\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), vec![3011], "{:?}", messages(diag));
+        assert!(messages(diag)[0].contains("`Shape.Circle` has 2 field(s) but the pattern has 1"));
+        assert_diagnostic(diag, 3011, "Shape.Circle(r)");
+    });
+}
+
+#[test]
+fn tuple_and_list_patterns_match_the_scrutinee_length() {
+    let src = "fn f() {\n    let a = (1, 2) match {\n        (x, y) => x,\n        (x, y, z) => x,\n    };\n    let b = [1, 2] match {\n        [x, ...rest] => x,\n        [x, y, z] => x,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert_eq!(codes(diag), vec![3011, 3011]);
+        let arms = match_arms(package)[1];
+        let PatternKind::List(prefix, Some(rest)) = arms[0].pat.kind else {
+            panic!("expected a list pattern with a rest binding");
+        };
+        assert_eq!(prefix.len(), 1);
+        assert!(matches!(rest.kind, PatternKind::Binding(_, ref name, None) if &*name.name == "rest"));
+    });
+}
+
+#[test]
+fn struct_pattern_binds_named_and_punned_fields() {
+    let src = "struct Point {\n    x: Int,\n    y: Int,\n}\n\nfn f(p: Point) {\n    let a = p match {\n        Point { x: 0, y } => y,\n        Point { x: px, y: py } => px,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", codes(diag));
+        let arms = match_arms(package)[0];
+        let PatternKind::Struct(_, fields, false) = arms[0].pat.kind else {
+            panic!("expected a struct pattern without rest");
+        };
+        assert!(matches!(fields[0].pat.kind, PatternKind::Const(_)));
+        // `y` is punned: it binds the field `y` to a variable `y`.
+        assert!(matches!(
+            fields[1].pat.kind,
+            PatternKind::Binding(_, ref name, None) if &*name.name == "y"
+        ));
+        let names: Vec<String> = arms[1].pat.bindings().iter().map(|i| i.name.to_string()).collect();
+        assert_eq!(names, ["px", "py"]);
+    });
+}
+
+#[test]
+fn struct_rest_pattern_ignores_remaining_fields() {
+    let src = "struct Point {\n    x: Int,\n    y: Int,\n    z: Int,\n}\n\nfn f(p: Point) {\n    let a = p match {\n        Point { x, ... } => x,\n        Point { y } => y,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert_eq!(codes(diag), vec![3011]);
+        assert_diagnostic(diag, 3011, "Point { y }");
+        let arms = match_arms(package)[0];
+        let PatternKind::Struct(_, fields, true) = arms[0].pat.kind else {
+            panic!("expected a struct pattern with rest");
+        };
+        assert_eq!(fields.len(), 1);
+    });
+}
+
+#[test]
+fn expr_as_pattern_compares_against_the_expression() {
+    let src = "fn f(n: Int, limit: Int) {\n    let y = n match {\n        < limit > => 1,\n        < 1 + 1 > => 2,\n        _ => 0,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", codes(diag));
+        let arms = match_arms(package)[0];
+        assert!(matches!(
+            arms[0].pat.kind,
+            PatternKind::Comptime(expr) if matches!(expr.kind, ExprKind::Ident(_))
+        ));
+        assert!(matches!(
+            arms[1].pat.kind,
+            PatternKind::Comptime(expr) if matches!(expr.kind, ExprKind::Binary(..))
+        ));
+        assert!(!arms[0].pat.is_irrefutable());
+    });
+}
+
+#[test]
+fn type_bind_pattern_tests_the_scrutinee_type() {
+    let src = "fn f(v: Any) {\n    let y = v match {\n        'Int as n => n,\n        'Str => 0,\n        _ => 0,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", codes(diag));
+        let arms = match_arms(package)[0];
+        let PatternKind::Binding(_, name, Some(inner)) = &arms[0].pat.kind else {
+            panic!("expected an as-binding, got {:?}", arms[0].pat.kind);
+        };
+        assert_eq!(&*name.name, "n");
+        assert!(matches!(inner.kind, PatternKind::Type(_)));
+        assert!(matches!(arms[1].pat.kind, PatternKind::Type(_)));
+        assert!(arms[1].pat.bindings().is_empty());
+        assert!(!arms[1].pat.is_irrefutable());
+    });
+}
+
+#[test]
+fn not_pattern_excludes_a_case() {
+    let src = "fn f(o: ?Int) {\n    let y = o match {\n        not null => 1,\n        null => 0,\n    };\n    let z = o match {\n        not 0 => 1,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", codes(diag));
+        let matches = match_arms(package);
+        let PatternKind::Not(inner) = matches[0][0].pat.kind else {
+            panic!("expected a not-pattern, got {:?}", matches[0][0].pat.kind);
+        };
+        assert!(matches!(inner.kind, PatternKind::OptionNull));
+        assert!(!matches[0][0].pat.is_irrefutable());
+        assert!(arms_are_exhaustive(matches[0]));
+        assert!(!arms_are_exhaustive(matches[1]));
+    });
+}
+
+#[test]
+fn not_pattern_cannot_bind() {
+    let src = "fn f(p: Int) {\n    let y = p match {\n        not (a, 1) => 0,\n        _ => 1,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert_eq!(codes(diag), vec![3014]);
+        assert!(messages(diag)[0].contains("`a`"));
+        assert!(match_arms(package)[0][0].pat.bindings().is_empty());
+    });
+}
+
+#[test]
+fn ref_pattern_binds_by_reference() {
+    let src = "fn f(p: Int) {\n    let show: fn({x: Int}) -> Int = |r| 0;\n    let y = p match {\n        ref x => show({x}),\n    };\n    let z = p match {\n        ref (a, b) => a,\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert_eq!(codes(diag), vec![3016]);
+        assert!(
+            messages(diag)[0].contains("expected `{x: Int}`, found `{x: *Int}`"),
+            "{:?}",
+            messages(diag)
+        );
+        let matches = match_arms(package);
+        assert!(matches!(
+            matches[0][0].pat.kind,
+            PatternKind::Binding(BindingMode::ByRef, ref name, None) if &*name.name == "x"
+        ));
+        let PatternKind::Ref(inner) = matches[1][0].pat.kind else {
+            panic!("expected a ref pattern, got {:?}", matches[1][0].pat.kind);
+        };
+        let PatternKind::Tuple(elems) = inner.kind else {
+            panic!("expected a tuple pattern");
+        };
+        assert!(
+            elems
+                .iter()
+                .all(|e| matches!(e.kind, PatternKind::Binding(BindingMode::ByRef, _, None)))
+        );
+        assert!(arms_are_exhaustive(matches[1]));
+    });
+}
+
+#[test]
+fn async_pattern_requires_an_async_scrutinee() {
+    let src = "fn f(t: Int, u: #{Async} Int) {\n    let show: fn({x: Bool}) -> Int = |r| 0;\n    let y = t match {\n        async x => x,\n    };\n    let z = 1 match {\n        async x => x,\n    };\n    let w = u match {\n        async x => show({x}),\n    };\n}\n";
+    lower_source(src, |package, diag| {
+        assert_eq!(codes(diag), vec![3011, 3011, 3016]);
+        assert!(messages(diag)[0].contains("async pattern cannot match a value of type `Int`"));
+        assert!(messages(diag)[1].contains("async pattern cannot match a literal"));
+        assert!(
+            messages(diag)[2].contains("expected `{x: Bool}`, found `{x: Int}`"),
+            "{:?}",
+            messages(diag)
+        );
+        let arms = match_arms(package)[2];
+        let PatternKind::Async(inner) = arms[0].pat.kind else {
+            panic!("expected an async pattern, got {:?}", arms[0].pat.kind);
+        };
+        assert!(matches!(
+            inner.kind,
+            PatternKind::Binding(BindingMode::ByValue, _, None)
+        ));
+        assert!(arms_are_exhaustive(arms));
+    });
+}
+
+#[test]
+fn symbol_index_survives_a_save_and_reload() {
+    let src = "mod utils {\n    fn double(x: Int) {\n        x\n    }\n}\n\nfn helper(x: Int) {\n    x\n}\n\nfn main() {\n    helper(1);\n    utils.double(2);\n    let helper = 2;\n    helper\n}\n";
+    let (index, file_key) = lower_source_full(src, |lowered| {
+        let mut index = middle::SymbolIndex::new();
+        index.update_file(
+            lowered.source_file,
+            lowered.package,
+            lowered.resolver,
+            lowered.file_scope,
+        );
+        (index, middle::symbol_index::file_key(lowered.source_file))
+    });
+
+    let path = std::env::temp_dir().join(format!("luna-symbol-index-{}.txt", std::process::id()));
+    index.save(&path).unwrap();
+    let reloaded = middle::SymbolIndex::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(reloaded, index);
+
+    let (file, def) = reloaded
+        .definition("utils.double")
+        .expect("definition not indexed");
+    assert_eq!(file, file_key);
+    assert_eq!(def.kind, middle::symbol_index::SymbolKind::Function);
+    assert!(src[def.lo as usize..def.hi as usize].contains("fn double(x: Int)"));
+
+    let refs = reloaded.find_references("helper");
+    assert_eq!(
+        refs.len(),
+        1,
+        "the local `helper` is not a reference: {:?}",
+        refs
+    );
+    assert_eq!(&src[refs[0].lo as usize..refs[0].hi as usize], "helper");
+    let refs = reloaded.find_references("utils.double");
+    assert_eq!(refs.len(), 1);
+    assert_eq!(
+        &src[refs[0].lo as usize..refs[0].hi as usize],
+        "utils.double"
+    );
+}
+
+#[test]
+fn symbol_index_resolves_a_body_from_its_own_module() {
+    let src = "fn helper(x: Int) {\n    x\n}\n\nmod inner {\n    fn helper(x: Int) {\n        x\n    }\n\n    fn run() {\n        helper(1)\n    }\n}\n\nfn main() {\n    helper(2)\n}\n";
+    let index = lower_source_full(src, |lowered| {
+        let mut index = middle::SymbolIndex::new();
+        index.update_file(
+            lowered.source_file,
+            lowered.package,
+            lowered.resolver,
+            lowered.file_scope,
+        );
+        index
+    });
+
+    let refs = index.find_references("inner.helper");
+    assert_eq!(refs.len(), 1, "{:?}", refs);
+    assert_eq!(&src[refs[0].lo as usize..refs[0].hi as usize], "helper");
+    assert!(src[..refs[0].lo as usize].ends_with("fn run() {\n        "));
+
+    let refs = index.find_references("helper");
+    assert_eq!(refs.len(), 1, "{:?}", refs);
+    assert!(src[..refs[0].lo as usize].ends_with("fn main() {\n    "));
+}
+
+/// The owners of every `fn` named `name`, in definition order.
+fn fns_named(package: &Package<'_>, name: &str) -> Vec<hir::HirId> {
+    package
+        .owners()
+        .filter_map(|(owner, _)| package.item(owner))
+        .filter(|item| {
+            matches!(item.kind, hir::ItemKind::Fn(..)) && item.ident.name.as_str() == name
+        })
+        .map(|item| item.hir_id())
+        .collect()
+}
+
+#[test]
+fn overloads_get_distinct_mangled_names() {
+    let src = "mod math {\n    fn add(a: Int, b: Int) {\n        a\n    }\n    fn add(a: Float) {\n        a\n    }\n}\n";
+    lower_source(src, |package, _| {
+        let adds = fns_named(package, "add");
+        assert_eq!(adds.len(), 2);
+        let first = package.mangle(adds[0]);
+        let second = package.mangle(adds[1]);
+        assert_ne!(first, second);
+        assert_eq!(first, package.mangle(adds[0]), "mangling is deterministic");
+    });
+}
+
+#[test]
+fn demangling_round_trips_the_qualified_path() {
+    let src = "mod geometry {\n    mod shapes {\n        fn area(w: Int, h: Int) {\n            w\n        }\n    }\n}\n";
+    lower_source(src, |package, _| {
+        let area = fns_named(package, "area")[0];
+        let mangled = package.mangle(area);
+        assert!(
+            mangled.starts_with("_F8geometry6shapes4areaEh"),
+            "{mangled}"
+        );
+        assert_eq!(
+            Package::demangle(&mangled).as_deref(),
+            Some("geometry.shapes.area")
+        );
+        assert_eq!(Package::demangle("area"), None);
+    });
+}
+
+/// The declared type of each parameter of the `fn` named `name`.
+fn param_types<'a>(package: &'a Package<'a>, name: &str) -> Vec<&'a Expr<'a>> {
+    let item = package
+        .owners()
+        .filter_map(|(owner, _)| package.item(owner))
+        .find(|item| item.ident.name.as_str() == name)
+        .expect("no such fn");
+    let hir::ItemKind::Fn(sig, _) = &item.kind else {
+        panic!("`{name}` is not a fn");
+    };
+    sig.params
+        .iter()
+        .map(|(_, param)| match &param.kind {
+            hir::common::TyParamKind::PositionalDependencyCatched(_, ty)
+            | hir::common::TyParamKind::Positional(ty) => *ty,
+            other => panic!("unexpected parameter {other:?}"),
+        })
+        .collect()
+}
+
+fn ident_name<'a>(expr: &'a Expr<'_>) -> &'a str {
+    match &expr.kind {
+        ExprKind::Ident(name) => name.as_str(),
+        other => panic!("expected an identifier, got {other:?}"),
+    }
+}
+
+#[test]
+fn fn_type_keeps_its_params_and_return_type() {
+    let src = "fn apply(f: fn(Int, Int) -> Int) {\n    f\n}\n";
+    lower_source(src, |package, _| {
+        let tys = param_types(package, "apply");
+        let ExprKind::TyFn {
+            modifiers,
+            abi,
+            params,
+            ret,
+        } = &tys[0].kind
+        else {
+            panic!("expected a fn type, got {:?}", tys[0].kind);
+        };
+        assert_eq!(*modifiers, 0);
+        assert_eq!(*abi, None);
+        let params: Vec<_> = params
+            .iter()
+            .map(|p| match &p.kind {
+                hir::common::TyParamKind::Positional(ty) => ident_name(ty),
+                other => panic!("unexpected fn type param {other:?}"),
+            })
+            .collect();
+        assert_eq!(params, ["Int", "Int"]);
+        assert_eq!(ret.map(ident_name), Some("Int"));
+    });
+}
+
+#[test]
+fn fn_type_preserves_modifiers_and_abi() {
+    let src = "fn run(g: pure extern \"C\" fn(Int)) {\n    g\n}\n";
+    lower_source(src, |package, _| {
+        let tys = param_types(package, "run");
+        let ExprKind::TyFn {
+            modifiers,
+            abi,
+            params,
+            ret,
+        } = &tys[0].kind
+        else {
+            panic!("expected a fn type, got {:?}", tys[0].kind);
+        };
+        assert_eq!(*modifiers, ast::FN_MOD_PURE | ast::FN_MOD_EXTERN);
+        assert_eq!(
+            abi.map(|abi| abi.as_str().to_string()).as_deref(),
+            Some("C")
+        );
+        assert_eq!(params.len(), 1);
+        assert!(ret.is_none());
+    });
+}
+
+#[test]
+fn calling_a_stored_lambda_with_matching_arguments_is_accepted() {
+    let src = "fn main() {\n    let add: fn(Int, Int) -> Int = |a, b| a + b;\n    add(1, 2)\n}\n";
+    lower_source(src, |_, diag| {
+        let codes = codes(diag);
+        assert!(
+            !codes.contains(&3015) && !codes.contains(&3016),
+            "{codes:?}"
+        );
+    });
+}
+
+#[test]
+fn calling_a_stored_lambda_checks_arity_and_argument_types() {
+    let src = "fn main() {\n    let add: fn(Int, Int) -> Int = |a, b| a + b;\n    add(1, \"two\");\n    add(1)\n}\n";
+    lower_source(src, |_, diag| {
+        assert_diagnostic(diag, 3016, "\"two\"");
+        assert!(
+            messages(diag)
+                .iter()
+                .any(|m| m.contains("`add` takes 2 argument(s) but 1 were supplied")),
+            "{:?}",
+            messages(diag)
+        );
+    });
+}
+
+#[test]
+fn a_defaulted_param_of_a_fn_type_needs_no_argument() {
+    let src = "fn run(pad: fn(Int, .width: Int = 8) -> Int) {\n    pad(1);\n    pad(1, 2);\n    pad(1, 2, 3)\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3015], "{:?}", messages(diag));
+        assert!(messages(diag)[0].contains("`pad` takes 1 to 2 argument(s) but 3 were supplied"));
+        assert_diagnostic(diag, 3015, "pad(1, 2, 3)");
+    });
+}
+
+#[test]
+fn a_variadic_param_of_a_fn_type_takes_any_number_of_arguments() {
+    let src = "fn run(sum: fn(Int, ...rest: Int) -> Int) {\n    sum(1);\n    sum(1, 2, 3, 4);\n    sum()\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3015], "{:?}", messages(diag));
+        assert!(
+            messages(diag)[0].contains("`sum` takes at least 1 argument(s) but 0 were supplied")
+        );
+        assert_diagnostic(diag, 3015, "sum()");
+    });
+}
+
+#[test]
+fn calling_the_result_of_a_higher_order_call_uses_its_return_type() {
+    let src =
+        "fn run(make: fn(Int) -> (fn(Int) -> Int)) {\n    let inc = make(1);\n    inc(\"x\")\n}\n";
+    lower_source(src, |_, diag| {
+        assert_diagnostic(diag, 3016, "\"x\"");
+        assert!(
+            messages(diag)
+                .iter()
+                .any(|m| m.contains("expected `Int`, found `String`")),
+            "{:?}",
+            messages(diag)
+        );
+    });
+}
+
+#[test]
+fn shadowing_a_callable_local_forgets_its_type() {
+    let src =
+        "fn main() {\n    let f: fn(Int) -> Int = |a| a;\n    let f = g;\n    f(\"x\", 2)\n}\n";
+    lower_source(src, |_, diag| {
+        let codes = codes(diag);
+        assert!(
+            !codes.contains(&3015) && !codes.contains(&3016),
+            "{codes:?}"
+        );
+    });
+}
+
+#[test]
+fn record_with_extra_fields_is_accepted_where_fewer_are_expected() {
+    let src = "fn main() {\n    let show: fn({x: Int}) -> Int = |p| p.x;\n    show({x: 1, y: 2});\n    let x = 1;\n    let y = 2;\n    show({x, y})\n}\n";
+    lower_source(src, |_, diag| {
+        assert!(!codes(diag).contains(&3016), "{:?}", messages(diag));
+    });
+}
+
+#[test]
+fn record_missing_an_expected_field_is_rejected() {
+    let src = "fn main() {\n    let show: fn({x: Int}) -> Int = |p| p.x;\n    show({y: 2})\n}\n";
+    lower_source(src, |_, diag| {
+        assert_diagnostic(diag, 3016, "{y: 2}");
+        assert!(
+            messages(diag)
+                .iter()
+                .any(|m| m.contains("expected `{x: Int}`, found `{y: Int}`")),
+            "{:?}",
+            messages(diag)
+        );
+    });
+}
+
+#[test]
+fn nested_record_fields_are_compared_structurally() {
+    let src = "fn main() {\n    let at: fn({pos: {x: Int}}) -> Int = |p| p.pos.x;\n    at({pos: {x: 1, y: 2}, id: 3});\n    at({pos: {y: 2}})\n}\n";
+    lower_source(src, |_, diag| {
+        assert_diagnostic(diag, 3016, "{pos: {y: 2}}");
+        assert_eq!(codes(diag).iter().filter(|&&code| code == 3016).count(), 1);
+        assert!(
+            messages(diag)
+                .iter()
+                .any(|m| m.contains("expected `{pos: {x: Int}}`, found `{pos: {y: Int}}`")),
+            "{:?}",
+            messages(diag)
+        );
+    });
+}
+
+/// The initializer of `let <name> = ..` in any body.
+fn let_init<'a>(package: &'a Package<'a>, name: &str) -> Option<&'a Expr<'a>> {
+    package
+        .bodies()
+        .filter_map(|(_, body)| match &body.value.kind {
+            ExprKind::Block(block) => Some(block.stmts.iter()),
+            _ => None,
+        })
+        .flatten()
+        .find_map(|stmt| match &stmt.kind {
+            ExprKind::Let(decl) if decl.name.name.as_str() == name => decl.init,
+            _ => None,
+        })
+}
+
+/// The lambda bound by `let <name> = |..| ..` in any body.
+fn let_bound_lambda<'a>(package: &'a Package<'a>, name: &str) -> &'a Expr<'a> {
+    let_init(package, name)
+        .filter(|init| matches!(init.kind, ExprKind::Lambda { .. }))
+        .expect("no such let-bound lambda")
+}
+
+#[test]
+fn lambda_captures_the_outer_locals_it_uses() {
+    let src = "fn main(base: Int) {\n    let n = 10;\n    let unused = 1;\n    let add_n = |x| print(x + n + base);\n    add_n(1)\n}\n";
+    lower_source(src, |package, _| {
+        let ExprKind::Lambda { captures, .. } = &let_bound_lambda(package, "add_n").kind else {
+            unreachable!()
+        };
+        let captures: Vec<&str> = captures.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(captures, ["n", "base"]);
+    });
+}
+
+#[test]
+fn lambda_type_is_inferred_from_params_and_body() {
+    let src = "fn main() {\n    let n = 10;\n    let add_n = |x| n + x;\n    add_n(1, 2)\n}\n";
+    lower_source(src, |package, diag| {
+        let ExprKind::Lambda { ty, ret, .. } = &let_bound_lambda(package, "add_n").kind else {
+            unreachable!()
+        };
+        assert_eq!(ret.map(ident_name), Some("Int"));
+        // `^(n) fn(_) -> Int`: the capture qualifies the type.
+        let ExprKind::TyClosureQualified(env, ty) = &ty.kind else {
+            panic!("expected a closure-qualified type, got {:?}", ty.kind);
+        };
+        assert!(
+            matches!(&env.kind, ExprKind::Tuple([n]) if ident_name(n) == "n"),
+            "{:?}",
+            env.kind
+        );
+        let ExprKind::TyFn { params, ret, .. } = &ty.kind else {
+            panic!("expected a fn type, got {:?}", ty.kind);
+        };
+        assert_eq!(params.len(), 1);
+        assert!(matches!(
+            &params[0].kind,
+            hir::common::TyParamKind::Positional(Expr {
+                kind: ExprKind::TyPlaceholder,
+                ..
+            })
+        ));
+        assert_eq!(ret.map(ident_name), Some("Int"));
+
+        // The inferred type is what calls are checked against.
+        assert!(
+            messages(diag)
+                .iter()
+                .any(|m| m.contains("`add_n` takes 1 argument(s) but 2 were supplied")),
+            "{:?}",
+            messages(diag)
+        );
+    });
+}
+
+/// The parameter and return types of the arrow type `ty`.
+fn arrow_parts<'a, 'hir>(ty: &'a Expr<'hir>) -> (&'a Expr<'hir>, &'a Expr<'hir>) {
+    let ExprKind::TyFn {
+        params: [param],
+        ret: Some(ret),
+        ..
+    } = &ty.kind
+    else {
+        panic!("expected a fn type of one parameter, got {:?}", ty.kind);
+    };
+    let hir::common::TyParamKind::Positional(input) = &param.kind else {
+        panic!("expected a positional parameter, got {:?}", param.kind);
+    };
+    (input, ret)
+}
+
+#[test]
+fn an_arrow_type_is_a_fn_type_of_one_parameter() {
+    let src = "fn test(p: Int -> Bool) {\n    p(1)\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        let tys = param_types(package, "test");
+        let (input, output) = arrow_parts(tys[0]);
+        assert_eq!(ident_name(input), "Int");
+        assert_eq!(ident_name(output), "Bool");
+    });
+
+    // Calls through an arrow-typed value are checked like any `fn` type.
+    let src = "fn test(p: Int -> Bool) {\n    p(1, 2)\n}\n";
+    lower_source(src, |_, diag| {
+        assert!(
+            messages(diag)
+                .iter()
+                .any(|m| m.contains("`p` takes 1 argument(s) but 2 were supplied")),
+            "{:?}",
+            messages(diag)
+        );
+    });
+}
+
+#[test]
+fn chained_arrow_types_associate_to_the_right() {
+    let src = "fn curry(f: Int -> Int -> Int) {\n    0\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        let tys = param_types(package, "curry");
+        let (first, rest) = arrow_parts(tys[0]);
+        assert_eq!(ident_name(first), "Int");
+        let (second, result) = arrow_parts(rest);
+        assert_eq!(ident_name(second), "Int");
+        assert_eq!(ident_name(result), "Int");
+    });
+}
+
+#[test]
+fn closure_qualified_type_keeps_its_environment() {
+    let src = "fn run(counter: Int, f: ^counter fn(Int) -> Int) {\n    f(\"x\")\n}\n";
+    lower_source(src, |package, diag| {
+        let tys = param_types(package, "run");
+        let ExprKind::TyClosureQualified(env, ty) = &tys[1].kind else {
+            panic!("expected a closure-qualified type, got {:?}", tys[1].kind);
+        };
+        assert_eq!(ident_name(env), "counter");
+        assert!(matches!(ty.kind, ExprKind::TyFn { .. }), "{:?}", ty.kind);
+
+        assert!(!codes(diag).contains(&3001), "{:?}", messages(diag));
+        // Calls look through the qualification to the fn type.
+        assert_diagnostic(diag, 3016, "\"x\"");
+    });
+}
+
+#[test]
+fn hir_dumps_are_byte_identical() {
+    let src = "mod shapes {\n    struct Point { x: Int, y: Int }\n    fn origin() {\n        0\n    }\n}\n\nfn area(w: Int, h: Int) {\n    let scale = |k| k * w;\n    scale(h)\n}\n\nfn main() {\n    area(1, 2)\n}\n";
+    let (first, ids) = lower_source(src, |package, _| {
+        let dump = package.to_string();
+        assert_eq!(dump, package.to_string());
+        assert_eq!(dump, package.dump_to_lisp());
+        let ids: Vec<_> = package.bodies().map(|(id, _)| *id).collect();
+        (dump, ids)
+    });
+    assert!(ids.windows(2).all(|w| w[0] < w[1]), "{ids:?}");
+
+    let second = lower_source(src, |package, _| package.to_string());
+    assert_eq!(first, second);
+}
+
+#[test]
+fn name_from_two_glob_imports_is_ambiguous() {
+    let src = "mod a {\n    fn foo() {\n        1\n    }\n}\n\nmod b {\n    fn foo() {\n        2\n    }\n}\n\nuse a.*;\nuse b.*;\n\nfn main() {\n    foo()\n}\n";
+    lower_source_full(src, |lowered| {
+        let offset = src.rfind("foo").unwrap() as u32;
+        let lo = lowered.source_file.start_pos + BytePos(offset);
+        let span = Span::new(lo, lo + BytePos(3));
+
+        let err = lowered
+            .resolver
+            .resolve_name("foo", lowered.file_scope, span)
+            .unwrap_err();
+        let ResolveError::AmbiguousName { candidates, .. } = &err else {
+            panic!("expected an ambiguity, got {err:?}");
+        };
+        let origins: Vec<&str> = candidates.iter().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(origins, ["a", "b"]);
+
+        err.emit(lowered.diag, BytePos(0));
+        assert_diagnostic(lowered.diag, 4010, "foo");
+        assert!(
+            messages(lowered.diag)
+                .iter()
+                .any(|m| m == "`foo` is ambiguous: it is glob-imported from `a` and `b`"),
+            "{:?}",
+            messages(lowered.diag)
+        );
+    });
+}
+
+#[test]
+fn mod_statement_links_a_sibling_file() {
+    let files = [
+        ("main.fl", "mod shapes;\n\nfn main() {\n    1\n}\n"),
+        ("shapes.fl", "mod circle;\n\nfn unit() {\n    1\n}\n"),
+        ("circle.fl", "fn area() {\n    3\n}\n"),
+    ];
+    build_tree(&files, |tree, ids, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        assert_eq!(file_module_path(tree, ids[1]), "shapes");
+        // `circle` is declared by `shapes`, not a module of the package.
+        assert_eq!(file_module_path(tree, ids[2]), "shapes.circle");
+        let circle_modules = tree
+            .scope_tree
+            .iter()
+            .filter(|scope| scope.name.is_some_and(|name| name.as_str() == "circle"))
+            .count();
+        assert_eq!(circle_modules, 1);
+    });
+}
+
+#[test]
+fn a_duplicate_definition_points_at_the_first_one() {
+    let files = [(
+        "main.fl",
+        "struct Point {\n    x: Int,\n}\n\nstruct Point {\n    y: Int,\n}\n",
+    )];
+    build_tree(&files, |tree, _, diag| {
+        let duplicate = tree
+            .errors
+            .iter()
+            .find(|e| matches!(e, ResolveError::DuplicateDefinition { .. }))
+            .expect("no duplicate definition");
+        duplicate.emit(diag, BytePos(0));
+
+        // SAFETY: nothing is emitted while the slice is borrowed.
+        let diagnostics = unsafe { diag.diagnostics() };
+        let emitted = diagnostics.iter().find(|d| d.code == Some(4007)).unwrap();
+        let snippet = |span| diag.source_map().span_to_snippet(span).unwrap();
+        assert!(snippet(emitted.primary_span.unwrap()).contains("y: Int"));
+        let [(first, message)] = emitted.related.as_slice() else {
+            panic!("expected one related location, got {:?}", emitted.related);
+        };
+        assert_eq!(message, "first defined here");
+        assert!(snippet(*first).contains("x: Int"));
+    });
+}
+
+#[test]
+fn mod_statement_links_a_directory_entry_file() {
+    let files = [
+        ("main.fl", "mod shapes;\n\nfn main() {\n    1\n}\n"),
+        ("shapes/mod.fl", "mod circle;\n\nfn unit() {\n    1\n}\n"),
+        ("shapes/circle.fl", "fn area() {\n    3\n}\n"),
+    ];
+    build_tree(&files, |tree, ids, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        // `mod.fl` is the body of `shapes` itself.
+        assert_eq!(file_module_path(tree, ids[1]), "shapes");
+        assert_eq!(file_module_path(tree, ids[2]), "shapes.circle");
+        let shapes = tree.packages[0].file_scopes[&ids[1]];
+        let items = &tree.scope_tree.get(shapes).unwrap().items;
+        assert!(items.get_direct("unit").is_some());
+    });
+}
+
+#[test]
+fn mod_statement_links_a_sibling_directory() {
+    let files = [
+        ("main.fl", "mod net;\n\nfn main() {\n    1\n}\n"),
+        ("net/http.fl", "fn get() {\n    1\n}\n"),
+        ("net/tcp.fl", "fn connect() {\n    1\n}\n"),
+    ];
+    build_tree(&files, |tree, ids, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        assert_eq!(file_module_path(tree, ids[1]), "net.http");
+        assert_eq!(file_module_path(tree, ids[2]), "net.tcp");
+    });
+}
+
+#[test]
+fn mod_statement_links_a_directory_tree() {
+    let files = [
+        ("main.fl", "mod net;\n\nfn main() {\n    1\n}\n"),
+        ("net/lib.fl", "mod tcp;\n\nfn version() {\n    1\n}\n"),
+        ("net/tcp.fl", "fn connect() {\n    1\n}\n"),
+        ("net/tls/cert.fl", "fn verify() {\n    1\n}\n"),
+    ];
+    build_tree(&files, |tree, ids, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        // The entry file holds the items of `net` itself.
+        assert_eq!(file_module_path(tree, ids[1]), "net");
+        assert_eq!(file_module_path(tree, ids[2]), "net.tcp");
+        assert_eq!(file_module_path(tree, ids[3]), "net.tls.cert");
+        let tcp_modules = tree
+            .scope_tree
+            .iter()
+            .filter(|scope| scope.name.is_some_and(|name| name.as_str() == "tcp"))
+            .count();
+        assert_eq!(tcp_modules, 1);
+    });
+}
+
+#[test]
+fn mod_statement_without_a_file_is_reported() {
+    let files = [("main.fl", "mod missing;\n\nfn main() {\n    1\n}\n")];
+    build_tree(&files, |_, _, diag| {
+        assert_diagnostic(diag, 4011, "missing");
+        assert_eq!(
+            messages(diag),
+            [
+                "file not found for module `missing`: expected `missing.fl`, `missing/mod.fl` or `missing/`"
+            ]
+        );
+    });
+}
+
+#[test]
+fn two_impls_of_a_trait_for_one_type_conflict() {
+    let src = "trait Show {\n}\n\nimpl Show for Int {\n}\n\nimpl Show for Int {\n}\n";
+    lower_source_full(src, |lowered| {
+        let diag = lowered.diag;
+        assert_eq!(codes(diag), [3017], "{:?}", messages(diag));
+        assert_diagnostic(diag, 3017, "Show for Int");
+        assert_eq!(
+            messages(diag),
+            ["conflicting implementations: `Show` is already implemented for `Int`"]
+        );
+
+        // Both impls are labelled: the conflicting one, then the first.
+        // SAFETY: as in `codes`.
+        let diagnostics = unsafe { diag.diagnostics() };
+        let start = lowered.source_file.start_pos.0 as usize;
+        let label_starts: Vec<usize> = diagnostics[0]
+            .labels
+            .iter()
+            .map(|label| label.span.lo().0 as usize - start)
+            .collect();
+        let first_impl = src.find("Show for Int").unwrap();
+        let second_impl = src.rfind("Show for Int").unwrap();
+        assert_eq!(label_starts, [second_impl, first_impl]);
+    });
+}
+
+#[test]
+fn generic_impl_overlaps_a_concrete_one() {
+    let src =
+        "trait Show {\n}\n\nimpl Show for Vec<Int> {\n}\n\nimpl Show for Vec<T> where T {\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3017], "{:?}", messages(diag));
+        assert_diagnostic(diag, 3017, "Show for Vec<T>");
+    });
+}
+
+#[test]
+fn impls_for_different_types_do_not_conflict() {
+    let src = "trait Show {\n}\n\nimpl Show for Int {\n}\n\nimpl Show for Bool {\n}\n\nimpl Show for Vec<T> where T {\n}\n\nimpl Show for Option<Int> {\n}\n\nimpl Vec where T {\n}\n";
+    lower_source(src, |_, diag| {
+        assert!(!codes(diag).contains(&3017), "{:?}", messages(diag));
+    });
+}
+
+#[test]
+fn impls_are_indexed_by_the_definition_of_their_self_type() {
+    let src = "struct Point {\n    x: Int,\n}\n\nstruct Vec {\n    len: Int,\n}\n\ntrait Show {\n}\n\nimpl Point {\n    fn origin() -> Int {\n        0\n    }\n}\n\nimpl Show for Point {\n}\n\nimpl Show for Vec<T> where T {\n}\n\nmod geometry {\n    struct Point {\n        y: Int,\n    }\n\n    impl Point {\n    }\n}\n";
+    lower_source_full(src, |lowered| {
+        let (package, diag) = (lowered.package, lowered.diag);
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+        let def = |name: &str| {
+            lowered
+                .resolver
+                .resolve_name(name, lowered.file_scope, rustc_span::DUMMY_SP)
+                .unwrap()
+                .def_id
+        };
+        let impls = package.impls_of(def("Point"));
+        assert_eq!(impls.len(), 2);
+        let defs: Vec<_> = impls
+            .iter()
+            .map(|&owner| package.get_impl(owner).expect("not an impl"))
+            .collect();
+        assert!(defs[0].trait_ref.is_none());
+        assert_eq!(defs[0].items.len(), 1);
+        assert_eq!(
+            ident_name(defs[1].trait_ref.expect("not a trait impl")),
+            "Show"
+        );
+
+        assert_eq!(package.impls_of(def("Vec")).len(), 1);
+        assert!(package.impls_of(def("Show")).is_empty());
+
+        // The impl in `geometry` is for the `Point` of that module.
+        let geometry = lowered
+            .resolver
+            .scope_tree()
+            .iter()
+            .find(|scope| scope.name.is_some_and(|name| name.as_str() == "geometry"))
+            .expect("no scope `geometry`")
+            .id;
+        let inner = lowered
+            .resolver
+            .resolve_name("Point", geometry, rustc_span::DUMMY_SP)
+            .unwrap()
+            .def_id;
+        assert_ne!(inner, def("Point"));
+        assert_eq!(package.impls_of(inner).len(), 1);
+    });
+}
+
+#[test]
+fn the_methods_of_a_type_come_from_its_inherent_and_trait_impls() {
+    let src = "struct Point {\n    x: Int,\n}\n\ntrait Show {\n    fn show(self) -> Int {\n        0\n    }\n\n    fn describe(self) -> Int {\n        1\n    }\n}\n\nimpl Point {\n    fn norm(self) -> Int {\n        self.x\n    }\n}\n\nimpl Show for Point {\n    fn show(self) -> Int {\n        self.x\n    }\n}\n";
+    lower_source_full(src, |lowered| {
+        let (package, diag) = (lowered.package, lowered.diag);
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+        let point = lowered
+            .resolver
+            .resolve_name("Point", lowered.file_scope, rustc_span::DUMMY_SP)
+            .unwrap()
+            .def_id;
+        let methods = package.methods_of(point);
+        let names: Vec<_> = methods.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["norm", "show", "describe"]);
+
+        let impls = package.impls_of(point);
+        let trait_impl = package.get_impl(impls[1]).expect("not an impl");
+        assert_eq!(methods[1].1, trait_impl.items[0]);
+    });
+}
+
+#[test]
+fn provided_methods_come_from_the_trait_the_impl_names() {
+    let src = "mod m {\n    trait Show {\n        fn other(self) -> Int {\n            0\n        }\n    }\n}\n\nstruct Point {\n    x: Int,\n}\n\ntrait Show {\n    fn show(self) -> Int {\n        1\n    }\n}\n\nimpl Show for Point {\n}\n";
+    lower_source_full(src, |lowered| {
+        let (package, diag) = (lowered.package, lowered.diag);
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+        let point = lowered
+            .resolver
+            .resolve_name("Point", lowered.file_scope, rustc_span::DUMMY_SP)
+            .unwrap()
+            .def_id;
+        let methods = package.methods_of(point);
+        let names: Vec<_> = methods.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["show"]);
+    });
+}
+
+#[test]
+fn the_owner_chain_of_a_method_reaches_its_impl_module_and_file() {
+    let src = "mod geometry {\n    struct Point {\n        x: Int,\n    }\n\n    impl Point {\n        fn norm(self) -> Int {\n            1\n        }\n    }\n}\n";
+    lower_source(src, |package, diag| {
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+        let (method, item) = package
+            .owners()
+            .filter_map(|(owner, _)| Some((owner, package.item(owner)?)))
+            .find(|(_, item)| item.ident.name.as_str() == "norm")
+            .expect("no fn `norm`");
+        let hir::ItemKind::Fn(_, body) = &item.kind else {
+            panic!("`norm` is not a fn");
+        };
+        let value = package.body(*body).unwrap().value;
+
+        let chain = package.owner_chain(value.hir_id);
+        let names: Vec<_> = chain
+            .iter()
+            .map(|&owner| package.item(owner).unwrap().ident.name.as_str())
+            .collect();
+        assert_eq!(names, ["norm", "<impl>", "geometry", "<root>"]);
+        assert_eq!(chain[0], method);
+        assert_eq!(chain.last(), Some(&package.root_mod));
+
+        assert_eq!(package.enclosing_definition(value.hir_id), Some(method));
+        // The self type of the impl belongs to the impl, which names
+        // nothing, so the module is the nearest definition.
+        let self_ty = package.get_impl(chain[1]).unwrap().self_ty;
+        assert_eq!(package.enclosing_definition(self_ty.hir_id), Some(chain[2]));
+    });
+}
+
+#[test]
+fn field_accesses_name_a_field_or_method_of_the_struct() {
+    let src = "fn get(p: Point, q: *Point, r: Int) -> Int {\n    p.x + p.norm() + q.y + r.z\n}\n\nstruct Point {\n    x: Int,\n    y: Int,\n}\n\nimpl Point {\n    fn norm(self) -> Int {\n        self.x + self.y\n    }\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+    });
+
+    let src = "struct Point {\n    x: Int,\n}\n\nfn get(p: Point) -> Int {\n    p.x + p.z\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3026], "{:?}", messages(diag));
+        assert_diagnostic(diag, 3026, "z");
+        assert_eq!(
+            messages(diag),
+            ["unknown field: no field `z` on type `Point`"]
+        );
+    });
+}
+
+#[test]
+fn a_field_access_refers_to_the_field_of_the_struct() {
+    let src =
+        "fn get(p: *Point) -> Int {\n    p.y\n}\n\nstruct Point {\n    x: Int,\n    y: Int,\n}\n";
+    lower_source_full(src, |lowered| {
+        let diag = lowered.diag;
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        let point = lowered
+            .resolver
+            .resolve_name("Point", lowered.file_scope, rustc_span::DUMMY_SP)
+            .unwrap()
+            .def_id;
+        let access = lowered
+            .package
+            .all_exprs()
+            .find(|e| matches!(e.kind, ExprKind::Projection(..)))
+            .expect("no field access was lowered");
+        assert_eq!(
+            lowered.package.field_res(access.hir_id),
+            Some(hir::FieldRes {
+                def: point,
+                index: 1
+            })
+        );
+    });
+}
+
+#[test]
+fn a_field_access_has_the_declared_type_of_the_field() {
+    let src = "struct Sample {\n    value: i64,\n}\n\nfn shrink(s: Sample) -> Int {\n    let small = s.value.as(i8);\n    0\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+        assert_eq!(messages(diag), ["casting `i64` to `i8` may lose data"]);
+    });
+}
+
+#[test]
+fn name_lookup_distinguishes_missing_single_and_ambiguous_names() {
+    let src = "mod a {\n    fn foo() {\n        1\n    }\n}\n\nmod b {\n    fn foo() {\n        2\n    }\n}\n\nuse a.*;\nuse b.*;\n\nfn main() {\n    0\n}\n";
+    lower_source_full(src, |lowered| {
+        let resolver = lowered.resolver;
+        assert_eq!(
+            resolver.lookup_name("missing", lowered.file_scope),
+            NameLookup::NotFound
+        );
+
+        let NameLookup::Single(main) = resolver.lookup_name("main", lowered.file_scope) else {
+            panic!("expected `main` to resolve to one definition");
+        };
+        assert_eq!(main.kind, BindingKind::Function);
+
+        let NameLookup::Ambiguous(candidates) = resolver.lookup_name("foo", lowered.file_scope)
+        else {
+            panic!("expected `foo` to be ambiguous");
+        };
+        let origins: Vec<String> = candidates
+            .iter()
+            .map(|candidate| {
+                let scope = candidate.imported_from.unwrap();
+                resolver
+                    .scope_tree()
+                    .get(scope)
+                    .unwrap()
+                    .name
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(origins, ["a", "b"]);
+        assert_ne!(candidates[0].def_id, candidates[1].def_id);
+        assert!(candidates.iter().all(|c| c.kind == BindingKind::Function));
+    });
+}
+
+#[test]
+fn ambiguous_type_name_is_reported_once_and_left_unresolved() {
+    let src = "mod a {\n    struct Shape {\n        x: Int,\n    }\n}\n\nmod b {\n    struct Shape {\n        y: Int,\n    }\n}\n\nuse a.*;\nuse b.*;\n\nfn pick(s: Shape, t: Shape) {\n    s\n}\n";
+    lower_source_full(src, |lowered| {
+        let ambiguities = codes(lowered.diag)
+            .iter()
+            .filter(|&&code| code == 4010)
+            .count();
+        assert_eq!(ambiguities, 1, "{:?}", messages(lowered.diag));
+        assert!(
+            messages(lowered.diag)
+                .iter()
+                .any(|m| m == "`Shape` is ambiguous: it is glob-imported from `a` and `b`"),
+            "{:?}",
+            messages(lowered.diag)
+        );
+
+        let references = param_types(lowered.package, "pick");
+        assert_eq!(references.len(), 2);
+        for ty in references {
+            assert_eq!(lowered.package.type_res(ty.hir_id), None);
+        }
+    });
+}
+
+#[test]
+fn repeated_type_names_are_resolved_once() {
+    let src = "struct Point {\n    x: Int,\n}\n\nfn shift(p: Point, by: Point) -> Point {\n    p\n}\n\nfn origin(p: Point) {\n    p\n}\n";
+    lower_source_full(src, |lowered| {
+        assert_eq!(lowered.resolver.name_lookups(), 1);
+
+        let point = lowered
+            .resolver
+            .resolve_name("Point", lowered.file_scope, rustc_span::DUMMY_SP)
+            .unwrap()
+            .def_id;
+        let mut references = param_types(lowered.package, "shift");
+        references.extend(param_types(lowered.package, "origin"));
+        assert_eq!(references.len(), 3);
+        for ty in references {
+            assert_eq!(lowered.package.type_res(ty.hir_id), Some(point));
+        }
+    });
+}
+
+/// The type given to the receiver (first body parameter) of the fn `name`.
+fn receiver_type<'a>(package: &'a Package<'a>, name: &str) -> Option<&'a Expr<'a>> {
+    let item = package
+        .owners()
+        .filter_map(|(owner, _)| package.item(owner))
+        .find(|item| item.ident.name.as_str() == name)
+        .expect("no such fn");
+    let hir::ItemKind::Fn(_, body) = &item.kind else {
+        panic!("`{name}` is not a fn");
+    };
+    let param = &package.body(*body).expect("fn without a body").params[0];
+    assert_eq!(param.name.name.as_str(), "self");
+    param.ty
+}
+
+#[test]
+fn by_value_receiver_has_the_impl_target_type() {
+    let src = "struct Counter {\n    n: Int,\n}\n\nimpl Counter {\n    fn get(self) {\n        self\n    }\n}\n";
+    lower_source(src, |package, diag| {
+        let ty = receiver_type(package, "get").expect("untyped receiver");
+        assert_eq!(ident_name(ty), "Counter");
+        assert!(!codes(diag).contains(&3018), "{:?}", messages(diag));
+    });
+}
+
+#[test]
+fn ref_receiver_points_to_the_impl_target_type() {
+    let src = "struct Counter {\n    n: Int,\n}\n\nimpl Counter {\n    fn bump(*self) {\n        self\n    }\n}\n";
+    lower_source(src, |package, diag| {
+        let ty = receiver_type(package, "bump").expect("untyped receiver");
+        let ExprKind::TyPtr(target) = &ty.kind else {
+            panic!("expected a pointer type, got {:?}", ty.kind);
+        };
+        assert_eq!(ident_name(target), "Counter");
+        assert!(!codes(diag).contains(&3018), "{:?}", messages(diag));
+    });
+}
+
+#[test]
+fn receiver_on_a_free_function_is_rejected() {
+    let src = "fn lonely(self) {\n    1\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(receiver_type(package, "lonely").is_none());
+        assert_eq!(codes(diag), [3018], "{:?}", messages(diag));
+        assert!(messages(diag)[0].starts_with("receiver outside of a method: `self`"));
+    });
+}
+
+/// The body of the fn `name`.
+fn fn_body<'a>(package: &'a Package<'a>, name: &str) -> &'a hir::Body<'a> {
+    let item = package
+        .owners()
+        .filter_map(|(owner, _)| package.item(owner))
+        .find(|item| item.ident.name.as_str() == name)
+        .expect("no such fn");
+    let hir::ItemKind::Fn(_, body) = &item.kind else {
+        panic!("`{name}` is not a fn");
+    };
+    package.body(*body).expect("fn without a body")
+}
+
+/// The body parameters of the fn `name`.
+fn body_params<'a>(package: &'a Package<'a>, name: &str) -> &'a [hir::Param<'a>] {
+    fn_body(package, name).params
+}
+
+#[test]
+fn parameter_attributes_are_recorded() {
+    let src = "fn copy(^noalias dst: Int, ^frob src: Int) {\n    dst\n}\n";
+    lower_source(src, |package, diag| {
+        let params = body_params(package, "copy");
+        let names: Vec<_> = params.iter().map(|p| p.name.name.as_str()).collect();
+        assert_eq!(names, ["dst", "src"]);
+        assert!(params.iter().all(|p| p.ty.is_some()));
+
+        let attr_names: Vec<_> = params
+            .iter()
+            .map(|p| {
+                assert_eq!(p.attrs.len(), 1);
+                ident_name(&p.attrs[0])
+            })
+            .collect();
+        assert_eq!(attr_names, ["noalias", "frob"]);
+        assert_eq!(param_types(package, "copy").len(), 2);
+
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+        assert_eq!(messages(diag), ["unknown attribute `frob`"]);
+    });
+}
+
+#[test]
+fn comptime_params_need_constant_arguments() {
+    let src = "fn repeat(comptime n: Int, s: Str) {\n    s\n}\n\nfn fixed() {\n    repeat(2 * 3, \"a\")\n}\n\nfn forward(comptime m: Int) {\n    repeat(m, \"a\")\n}\n\nfn dynamic(k: Int) {\n    repeat(k, \"a\")\n}\n";
+    lower_source(src, |package, diag| {
+        let params = body_params(package, "repeat");
+        assert!(params[0].is_comptime() && !params[0].is_implicit());
+        assert_eq!(params[0].name.name.as_str(), "n");
+        assert!(!params[1].is_comptime());
+
+        assert_eq!(codes(diag), [3019], "{:?}", messages(diag));
+        assert_diagnostic(diag, 3019, "k");
+        assert!(messages(diag)[0].contains("`n` of `repeat` must be known at compile time"));
+    });
+}
+
+#[test]
+fn an_item_named_after_a_builtin_shadows_it() {
+    let src = "fn static_assert(cond: Bool, msg: Str) {\n    msg\n}\n\nfn sizes() {\n    static_assert(false, \"not a compile-time check\")\n}\n";
+    lower_source(src, |_, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+    });
+}
+
+#[test]
+fn constants_are_told_apart_from_runtime_values() {
+    let src = "const LIMIT: Int = 10;\n\ncomptime fn square(n: Int) -> Int {\n    n * n\n}\n\nfn runtime(n: Int) -> Int {\n    n\n}\n\nfn main(k: Int) {\n    let sum = 2 + 3;\n    let limit = LIMIT;\n    let folded = square(LIMIT + 1);\n    let called = runtime(2);\n    let mixed = square(k);\n    sum\n}\n";
+    lower_source_full(src, |lowered| {
+        let (package, diag, scope) = (lowered.package, lowered.diag, lowered.file_scope);
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        let res = |name: Symbol| match lowered.resolver.lookup_name(name.as_str(), scope) {
+            NameLookup::Single(res) => ValueRes::Def(res.def_id),
+            _ => ValueRes::Unresolved,
+        };
+        let is_const = |name| package.is_const(let_init(package, name).unwrap(), &res);
+        assert!(is_const("sum"));
+        assert!(is_const("limit"));
+        assert!(is_const("folded"));
+        assert!(!is_const("called"));
+        assert!(!is_const("mixed"));
+    });
+}
+
+#[test]
+fn a_local_shadowing_a_const_is_not_constant() {
+    let src = "const LIMIT: Int = 10;\n\nfn repeat(comptime n: Int, s: Str) {\n    s\n}\n\nfn global() {\n    repeat(LIMIT, \"a\")\n}\n\nfn shadowed(LIMIT: Int) {\n    repeat(LIMIT, \"a\")\n}\n\nfn unknown() {\n    repeat(missing, \"a\")\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3019, 3019], "{:?}", messages(diag));
+        assert_diagnostic(diag, 3019, "LIMIT");
+        assert_diagnostic(diag, 3019, "missing");
+    });
+}
+
+#[test]
+fn keywords_before_a_non_function_do_not_modify_its_methods() {
+    let src = "comptime struct Table {\n    size: Int,\n\n    fn get(self) -> Int {\n        self.size\n    }\n}\n\ncomptime fn square(n: Int) -> Int {\n    n * n\n}\n";
+    lower_source(src, |package, _| {
+        let modifiers = |name: &str| {
+            let item = package
+                .owners()
+                .filter_map(|(owner, _)| package.item(owner))
+                .find(|item| item.ident.name.as_str() == name)
+                .expect("no such fn");
+            let hir::ItemKind::Fn(sig, _) = &item.kind else {
+                panic!("`{name}` is not a fn");
+            };
+            sig.modifiers
+        };
+        assert!(!modifiers("get").is_comptime);
+        assert!(modifiers("square").is_comptime);
+    });
+}
+
+#[test]
+fn keyword_arguments_bind_parameters_by_name() {
+    let src = "fn repeat(comptime n: Int, s: Str) {\n    s\n}\n\nfn named() {\n    repeat(.s = \"a\", .n = 3)\n}\n\nfn mixed() {\n    repeat(3, .s = \"a\")\n}\n\nfn late(k: Int) {\n    repeat(k, .s = \"a\")\n}\n\nfn typo() {\n    repeat(3, .count = \"a\")\n}\n";
+    lower_source(src, |_package, diag| {
+        assert_eq!(codes(diag), [3019, 3028], "{:?}", messages(diag));
+        assert_diagnostic(diag, 3019, "k");
+        assert_diagnostic(diag, 3028, "count");
+        assert!(messages(diag)[1].contains("`repeat` has no parameter `count`"));
+    });
+}
+
+#[test]
+fn a_parameter_takes_one_argument() {
+    let src = "fn pair(a: Int, b: Int) {\n    a\n}\n\nfn twice() {\n    pair(1, .a = 2)\n}\n\nfn short() {\n    pair(.b = 2)\n}\n";
+    lower_source(src, |_package, diag| {
+        assert_eq!(codes(diag), [3029, 3015], "{:?}", messages(diag));
+        assert!(messages(diag)[0].contains("`pair` is given `a` more than once"));
+        assert!(messages(diag)[1].contains("`pair` is missing an argument for `a`"));
+    });
+}
+
+#[test]
+fn calls_are_checked_against_the_fn_their_name_resolves_to() {
+    let src = "mod one {\n    fn f(a: Int) {\n        a\n    }\n\n    fn g() {\n        f(1)\n    }\n}\n\nmod two {\n    fn f(a: Int, b: Int) {\n        a\n    }\n\n    fn g() {\n        f(1, 2);\n        f(1)\n    }\n}\n";
+    lower_source(src, |_package, diag| {
+        assert_eq!(codes(diag), [3015], "{:?}", messages(diag));
+        assert!(messages(diag)[0].contains("`f` is missing an argument for `b`"));
+        assert_diagnostic(diag, 3015, "f(1)");
+    });
+}
+
+#[test]
+fn keyword_arguments_to_function_values_are_checked() {
+    let src = "fn main() {\n    let add: fn(Int, Int) -> Int = |a, b| a + b;\n    add(\"x\", .b = 2)\n}\n";
+    lower_source(src, |_package, diag| {
+        assert_eq!(codes(diag), [3028, 3016], "{:?}", messages(diag));
+        assert!(messages(diag)[0].contains("`add` has no parameter `b`"));
+        assert_diagnostic(diag, 3016, "\"x\"");
+    });
+}
+
+#[test]
+fn positional_arguments_come_before_keyword_arguments() {
+    let src = "fn pair(a: Int, b: Int) {\n    a\n}\n\nfn main() {\n    pair(.a = 1, 2)\n}\n";
+    lower_source(src, |_package, diag| {
+        assert_eq!(codes(diag), [3027], "{:?}", messages(diag));
+        assert_diagnostic(diag, 3027, "2");
+    });
+}
+
+#[test]
+fn implicit_params_are_not_passed_positionally() {
+    let src = "fn log(implicit ctx: Int, msg: Str) {\n    msg\n}\n\nfn quiet(ctx: Int) {\n    log(\"hi\")\n}\n\nfn loud(ctx: Int) {\n    log(1, \"hi\")\n}\n";
+    lower_source(src, |package, diag| {
+        let params = body_params(package, "log");
+        assert!(params[0].is_implicit());
+        assert_eq!(params[0].name.name.as_str(), "ctx");
+
+        assert_eq!(codes(diag), [3015], "{:?}", messages(diag));
+        assert!(messages(diag)[0].contains("`log` takes 1 argument(s) but 2 were supplied"));
+    });
+}
+
+#[test]
+fn implicit_params_need_a_value_of_their_type_in_scope() {
+    let src = "fn log(implicit ctx: Int, msg: Str) {\n    msg\n}\n\nfn passed(implicit ctx: Int) {\n    log(\"hi\")\n}\n\nfn local() {\n    let level: Int = 2;\n    log(\"hi\")\n}\n\nfn wrong(ctx: Str) {\n    log(\"hi\")\n}\n\nfn none() {\n    log(\"hi\")\n}\n";
+    lower_source(src, |_package, diag| {
+        assert_eq!(codes(diag), [3030, 3030], "{:?}", messages(diag));
+        assert!(
+            messages(diag)[0].contains("no value of type `Int` is in scope for `ctx` of `log`")
+        );
+        assert_diagnostic(diag, 3030, "log(\"hi\")");
+    });
+}
+
+#[test]
+fn catch_param_is_bound_to_the_eliminated_error_set() {
+    let src = "fn handle(r: !ParseError Int) {\n    r ! {\n        catch e => e,\n    }\n}\n\nfn infallible(n: Int) {\n    n ! {\n        catch e => 0,\n    }\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(matches!(
+            param_types(package, "handle")[0].kind,
+            ExprKind::ErrorQualifiedType(..)
+        ));
+
+        let body = fn_body(package, "handle");
+        let ExprKind::Block(block) = &body.value.kind else {
+            panic!("expected a block body, got {:?}", body.value.kind);
+        };
+        let elimination = block.expr.or(block.stmts.first()).unwrap();
+        let elimination = match &elimination.kind {
+            ExprKind::Semi(e) => e,
+            _ => elimination,
+        };
+        let ExprKind::ErrorElimination {
+            catch: Some((param, arm_body)),
+            ..
+        } = &elimination.kind
+        else {
+            panic!("expected an error elimination, got {:?}", elimination.kind);
+        };
+        assert!(param.is_catch());
+        assert_eq!(param.name.name.as_str(), "e");
+        assert_eq!(ident_name(param.ty.unwrap()), "ParseError");
+        assert_eq!(ident_name(arm_body), "e");
+
+        assert_eq!(codes(diag), [3020], "{:?}", messages(diag));
+        assert_diagnostic(diag, 3020, "n");
+    });
+}
+
+#[test]
+fn static_assert_is_checked_at_compile_time() {
+    let src = "fn sizes() {\n    static_assert(2 + 3 == 5, \"math works\");\n    static_assert(not (4 < 1), \"ordering works\")\n}\n";
+    lower_source(src, |_, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+    });
+
+    let src = "fn sizes() {\n    static_assert(2 * 3 == 5, \"six is not five\")\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3021], "{:?}", messages(diag));
+        assert_eq!(messages(diag)[0], "static assertion failed: six is not five");
+    });
+}
+
+#[test]
+fn dividing_by_a_constant_zero_is_rejected() {
+    let src = "fn f() {\n    let a = 10 / 0;\n    let b = 10 % 0;\n    let c = 10 / (2 - 2);\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3022, 3022, 3022], "{:?}", messages(diag));
+        assert_diagnostic(diag, 3022, "0");
+        assert_eq!(
+            messages(diag)[..2],
+            [
+                "division by zero: the divisor of `/` is always zero",
+                "division by zero: the divisor of `%` is always zero",
+            ]
+        );
+    });
+}
+
+#[test]
+fn dividing_by_a_runtime_value_is_not_checked() {
+    let src = "fn f(x: Int, y: Float) {\n    let a = 10 / x;\n    let b = 10 % x;\n    let c = 1.5 / 0;\n    let d = y / 0;\n    let e = y % (1 - 1);\n}\n";
+    lower_source(src, |_, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+    });
+}
+
+#[test]
+fn unary_operators_over_literals_are_folded() {
+    let src = "fn main() {\n    let a = -5;\n    let b = not true;\n    let c = - -2;\n    a\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        let lit = |name| match &let_init(package, name).unwrap().kind {
+            ExprKind::Lit(lit) => lit.kind.clone(),
+            other => panic!("`{name}` was not folded: {other:?}"),
+        };
+        assert_eq!(lit("a"), LitKind::Integer(-5));
+        assert_eq!(lit("b"), LitKind::Bool(false));
+        assert_eq!(lit("c"), LitKind::Integer(2));
+    });
+}
+
+#[test]
+fn double_negation_cancels_but_runtime_operands_are_kept() {
+    let src = "fn main(x: Bool, n: Int) {\n    let a = not not x;\n    let b = not x;\n    let c = -n;\n    a\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        let init = |name| &let_init(package, name).unwrap().kind;
+        assert!(
+            matches!(init("a"), ExprKind::Ident(name) if name.as_str() == "x"),
+            "{:?}",
+            init("a")
+        );
+        assert!(
+            matches!(init("b"), ExprKind::Unary(UnOp::Not, _)),
+            "{:?}",
+            init("b")
+        );
+        assert!(
+            matches!(init("c"), ExprKind::Unary(UnOp::Neg, _)),
+            "{:?}",
+            init("c")
+        );
+    });
+}
+
+#[test]
+fn quantifiers_bind_their_variables_in_the_body_only() {
+    let src = "fn check(n: Int) {\n    let p = forall x: Int => x == x;\n    let q = exists y: Int => y == n;\n    let f = |k| forall z: Int => z == k + n;\n    p\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+
+        let p = let_init(package, "p").unwrap();
+        let ExprKind::Forall { params, body } = &p.kind else {
+            panic!("expected a forall, got {:?}", p.kind);
+        };
+        assert_eq!(params.len(), 1);
+        assert_eq!(params[0].pat.bindings()[0].name.as_str(), "x");
+        let Some(ExprKind::Ident(ty)) = params[0].ty.map(|ty| &ty.kind) else {
+            panic!("expected a named type, got {:?}", params[0].ty);
+        };
+        assert_eq!(ty.as_str(), "Int");
+        assert!(matches!(body.kind, ExprKind::Binary(hir::BinOp::Eq, _, _)));
+        assert!(p.free_variables().is_empty());
+
+        let q = let_init(package, "q").unwrap();
+        assert!(matches!(q.kind, ExprKind::Exists { .. }), "{:?}", q.kind);
+        let free: Vec<&str> = q.free_variables().iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(free, ["n"]);
+
+        // `z` is bound by the forall, so the lambda only captures `n`.
+        let ExprKind::Lambda { captures, .. } = &let_bound_lambda(package, "f").kind else {
+            unreachable!();
+        };
+        let captures: Vec<&str> = captures.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(captures, ["n"]);
+    });
+}
+
+#[test]
+fn forall_type_binds_its_type_variables() {
+    let src = "fn id_type() {\n    forall<T> T -> T\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+
+        let Some(scheme) = package
+            .all_exprs()
+            .find(|e| matches!(e.kind, ExprKind::TyForall { .. }))
+        else {
+            panic!("no forall type was lowered");
+        };
+        let ExprKind::TyForall { params, body } = &scheme.kind else {
+            unreachable!();
+        };
+        let [param] = params else {
+            panic!("expected one type parameter, got {params:?}");
+        };
+        let hir::common::TyParamKind::PositionalDependencyCatched(name, _) = &param.kind else {
+            panic!("the type variable is unnamed: {:?}", param.kind);
+        };
+        assert_eq!(name.name.as_str(), "T");
+        assert!(
+            matches!(body.kind, ExprKind::TyFn { .. }),
+            "{:?}",
+            body.kind
+        );
+        assert!(scheme.free_variables().is_empty());
+    });
+}
+
+#[test]
+fn spliced_code_renames_the_bindings_it_introduces() {
+    let src = "fn scaled(x: Int) -> Int {\n    let t = 1;\n    let double = quote { let t = x * 2; t };\n    splice(double) + t\n}\n";
+    lower_source_full(src, |lowered| {
+        let diag = lowered.diag;
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+
+        let exprs: Vec<_> = lowered.package.all_exprs().collect();
+        let Some((block, t)) = exprs.iter().find_map(|e| match &e.kind {
+            ExprKind::Binary(_, lhs, rhs) => match &lhs.kind {
+                ExprKind::Splice(block) => Some((*block, *rhs)),
+                _ => None,
+            },
+            _ => None,
+        }) else {
+            panic!("nothing was spliced");
+        };
+        let ExprKind::Let(decl) = &block.stmts[0].kind else {
+            panic!("expected a let, got {:?}", block.stmts[0].kind);
+        };
+        assert_eq!(decl.name.name.as_str(), "t#0");
+        let Some(ExprKind::Binary(_, x, _)) = decl.init.map(|init| &init.kind) else {
+            panic!("expected a product, got {:?}", decl.init);
+        };
+        // Free names refer to what is in scope at the splice site.
+        assert!(matches!(&x.kind, ExprKind::Ident(name) if name.as_str() == "x"));
+        let Some(ExprKind::Ident(result)) = block.expr.map(|e| &e.kind) else {
+            panic!("expected a name, got {:?}", block.expr);
+        };
+        assert_eq!(result.as_str(), "t#0");
+        // The `t` after the splice is still the function's own.
+        assert!(matches!(&t.kind, ExprKind::Ident(name) if name.as_str() == "t"));
+
+        // Spliced code keeps the spans of the quoted source.
+        let start = lowered.source_file.start_pos.0 as usize;
+        assert_eq!(
+            decl.name.span.lo().0 as usize - start,
+            src.find("t = x").unwrap()
+        );
+    });
+}
+
+#[test]
+fn errors_in_spliced_code_point_into_the_quote() {
+    let src = "fn f() -> Int {\n    let bad = quote { 10 / 0 };\n    splice(bad)\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3022], "{:?}", messages(diag));
+        assert_diagnostic(diag, 3022, "0");
+    });
+
+    // Quoted code is only checked where it is spliced.
+    let src = "fn f() {\n    let bad = quote { 10 / 0 };\n}\n";
+    lower_source(src, |_, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+    });
+
+    let src = "fn f(x: Int) -> Int {\n    splice(x)\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3019], "{:?}", messages(diag));
+        assert_diagnostic(diag, 3019, "x");
+    });
+
+    let src = "fn f() {\n    let q = quote { splice(q) };\n    splice(q)\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3023], "{:?}", messages(diag));
+    });
+}
+
+#[test]
+fn comparing_with_a_bool_literal_is_linted() {
+    let src = "fn check(x: Bool, y: Bool) {\n    let a = x == true;\n    let b = false != y;\n    let c = x == y;\n}\n";
+    lower_source(src, |package, diag| {
+        let comparisons = package
+            .all_exprs()
+            .filter(|e| matches!(e.kind, ExprKind::Binary(..)))
+            .count();
+        assert_eq!(comparisons, 3);
+
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+        assert_eq!(
+            messages(diag),
+            [
+                "comparison with `true` is redundant; use the operand directly",
+                "comparison with `false` is redundant; use the operand directly",
+            ]
+        );
+    });
+}
+
+#[test]
+fn item_named_after_a_builtin_is_warned_about() {
+    let src = "struct void {}\n\nstruct Flag {}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(diag.error_count(), 0);
+        assert_eq!(messages(diag), ["`void` shadows a builtin name"]);
+        assert_eq!(codes(diag), [resolve::SHADOWED_BUILTIN_NAME]);
+
+        // SAFETY: as in `codes`.
+        let warning = unsafe { &diag.diagnostics()[0] };
+        assert_eq!(
+            primary_span_text(diag, warning).as_deref(),
+            Some("struct void {}")
+        );
+    });
+}
+
+#[test]
+fn item_with_an_unrelated_name_is_not_warned_about() {
+    lower_source("struct Flag {}\n\nfn check(flag: Flag) {}\n", |_, diag| {
+        assert_eq!(diag.warning_count(), 0, "{:?}", messages(diag));
+    });
+}
+
+/// Names of the items in `package` other than modules, sorted.
+fn item_names(package: &Package<'_>) -> Vec<String> {
+    let mut names: Vec<String> = package
+        .owners()
+        .filter_map(|(owner, _)| package.item(owner))
+        .filter(|item| !matches!(item.kind, hir::ItemKind::Mod(_)))
+        .map(|item| item.ident.name.to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+/// The module `owner` as `(name items..)`, with its nested modules
+/// written the same way.
+fn module_outline(package: &Package<'_>, owner: hir::OwnerId) -> String {
+    let item = package.item(owner).expect("not an item");
+    let hir::ItemKind::Mod(def) = &item.kind else {
+        return item.ident.name.to_string();
+    };
+    let mut outline = format!("({}", item.ident.name);
+    for &child in &def.items {
+        outline.push(' ');
+        outline.push_str(&module_outline(package, child));
+    }
+    outline.push(')');
+    outline
+}
+
+#[test]
+fn a_package_nests_its_file_modules_under_one_root() {
+    let source_map = SourceMap::new(FilePathMapping::empty());
+    let diag_ctx = DiagnosticContext::new(&source_map);
+    let mut vfs = vfs::Vfs::new("app", PathBuf::from("app"));
+    for (path, src) in [
+        ("main.fl", "mod net;\n\nfn main() {\n    1\n}\n"),
+        ("net/tcp.fl", "fn connect() {\n    1\n}\n"),
+        ("net/tls/cert.fl", "fn verify() {\n    1\n}\n"),
+    ] {
+        let source_file =
+            source_map.new_source_file(FileName::Custom(format!("app/{path}")), src.into());
+        vfs.add_file(PathBuf::from(path), source_file);
+    }
+    let tree = resolve::build_module_tree_with(&source_map, &diag_ctx, &mut vfs, build_options());
+    assert!(tree.errors.is_empty(), "{:?}", tree.errors);
+    let resolver = Resolver::new(&tree);
+    let arena = HirArena::new();
+    let package = crate::lower_package(&vfs, 0, &arena, &source_map, &diag_ctx, &resolver);
+    assert!(codes(&diag_ctx).is_empty(), "{:?}", messages(&diag_ctx));
+
+    let roots = package
+        .owners()
+        .filter_map(|(owner, _)| package.item(owner))
+        .filter(|item| item.ident.name.as_str() == "<root>")
+        .count();
+    assert_eq!(roots, 1);
+    assert_eq!(
+        module_outline(&package, package.root_mod),
+        "(<root> main (net (tcp connect) (tls (cert verify))))"
+    );
+
+    // The owners enclosing an item lead up through the modules of its file
+    // to the root.
+    let verify = package
+        .owners()
+        .map(|(owner, _)| owner)
+        .find(|&owner| package.item(owner).unwrap().ident.name.as_str() == "verify")
+        .expect("no fn `verify`");
+    let chain: Vec<_> = package
+        .owner_chain(hir::HirId::make_owner(verify))
+        .into_iter()
+        .map(|owner| package.item(owner).unwrap().ident.name.to_string())
+        .collect();
+    assert_eq!(chain, ["verify", "cert", "tls", "net", "<root>"]);
+}
+
+#[test]
+fn workspace_packages_import_from_each_other() {
+    let source_map = SourceMap::new(FilePathMapping::empty());
+    let diag_ctx = DiagnosticContext::new(&source_map);
+    let mut packages = [
+        ("geometry", "lib.fl", "fn area() {\n    3\n}\n"),
+        (
+            "app",
+            "main.fl",
+            "use geometry.area;\n\nfn main() {\n    area()\n}\n",
+        ),
+    ]
+    .map(|(name, path, src)| {
+        let mut vfs = vfs::Vfs::new(name, PathBuf::from(name));
+        let source_file =
+            source_map.new_source_file(FileName::Custom(format!("{name}/{path}")), src.into());
+        vfs.add_file(PathBuf::from(path), source_file);
+        vfs
+    });
+    let tree = resolve::build_workspace_module_tree(
+        &source_map,
+        &diag_ctx,
+        &mut packages,
+        build_options(),
+    );
+    assert!(tree.errors.is_empty(), "{:?}", tree.errors);
+
+    // Both entry files are `FileId(0)` of their own VFS, yet each lands in
+    // its own package scope.
+    let file = vfs::FileId::from_raw(0);
+    let [geometry, app] = [0, 1].map(|package| tree.file_scope(package, file).unwrap());
+    assert_ne!(geometry, app);
+    assert_eq!(geometry, tree.packages[0].scope);
+    assert_eq!(app, tree.packages[1].scope);
+
+    let resolver = Resolver::new(&tree);
+    let defined = resolver
+        .resolve_name("area", geometry, rustc_span::DUMMY_SP)
+        .unwrap();
+    let imported = resolver
+        .resolve_name("area", app, rustc_span::DUMMY_SP)
+        .unwrap();
+    assert_eq!(imported.def_id, defined.def_id);
+
+    let arena = HirArena::new();
+    let lowered: Vec<_> = packages
+        .iter()
+        .enumerate()
+        .map(|(index, vfs)| {
+            crate::lower_package(vfs, index, &arena, &source_map, &diag_ctx, &resolver)
+        })
+        .collect();
+    assert!(codes(&diag_ctx).is_empty(), "{:?}", messages(&diag_ctx));
+    assert_eq!(item_names(&lowered[0]), ["area"]);
+    assert_eq!(item_names(&lowered[1]), ["main"]);
+}
+
+#[test]
+fn string_literals_are_unescaped_when_lowered() {
+    let src = "fn main() {\n    let s = \"tab\\there \\u{1F600}\";\n    s\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        let init = package
+            .bodies()
+            .filter_map(|(_, body)| match &body.value.kind {
+                ExprKind::Block(block) => Some(block.stmts.iter()),
+                _ => None,
+            })
+            .flatten()
+            .find_map(|stmt| match &stmt.kind {
+                ExprKind::Let(decl) => decl.init,
+                _ => None,
+            })
+            .expect("no let");
+        let ExprKind::Lit(lit) = &init.kind else {
+            panic!("expected a literal, got {:?}", init.kind);
+        };
+        assert_eq!(
+            lit.kind,
+            hir::common::LitKind::String("tab\there 😀".into())
+        );
+    });
+}
+
+#[test]
+fn invalid_string_escapes_are_reported_at_the_escape() {
+    let src = "fn main() {\n    let s = \"a\\qb\";\n    let t = \"\\u{110000}\";\n    s\n}\n";
+    lower_source(src, |_, diag| {
+        assert_diagnostic(diag, lex::error::LEX_INVALID_ESCAPE, "\\q");
+        assert_diagnostic(diag, lex::error::LEX_INVALID_ESCAPE, "\\u{110000}");
+    });
+}
+
+#[test]
+fn raw_string_literals_are_not_unescaped() {
+    let src = "fn main() {\n    let s = r\"a\\d\\q\";\n    s\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        let init = let_init(package, "s").expect("no s");
+        let ExprKind::Lit(lit) = &init.kind else {
+            panic!("expected a literal, got {:?}", init.kind);
+        };
+        assert_eq!(lit.kind, LitKind::String(r"a\d\q".into()));
+    });
+}
+
+#[test]
+fn pipe_prefers_a_method_of_the_operand_type_over_a_free_function() {
+    let src = "struct List {\n    n: Int,\n}\n\nimpl List {\n    fn len(self) {\n        1\n    }\n}\n\nfn double(x: Int) {\n    x * 2\n}\n\nfn f(xs: List, k: Int) {\n    let by_method = xs |> len;\n    let by_fn = k |> double;\n    by_method\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+
+        let by_method = let_init(package, "by_method").expect("no by_method");
+        let ExprKind::Application(callee, args) = &by_method.kind else {
+            panic!("expected a call, got {:?}", by_method.kind);
+        };
+        let ExprKind::Projection(receiver, method) = &callee.kind else {
+            panic!("expected a method, got {:?}", callee.kind);
+        };
+        assert_eq!(ident_name(receiver), "xs");
+        assert_eq!(method.name.as_str(), "len");
+        assert!(args.is_empty());
+
+        let by_fn = let_init(package, "by_fn").expect("no by_fn");
+        let ExprKind::Application(callee, args) = &by_fn.kind else {
+            panic!("expected a call, got {:?}", by_fn.kind);
+        };
+        assert_eq!(ident_name(callee), "double");
+        let [Arg::Positional(arg)] = args else {
+            panic!("expected one argument, got {:?}", args);
+        };
+        assert_eq!(ident_name(arg), "k");
+    });
+}
+
+#[test]
+fn pipe_into_a_call_prepends_the_operand() {
+    let src = "fn add(x: Int, y: Int) {\n    x + y\n}\n\nfn f(k: Int) {\n    let sum = k |> add(2);\n    sum\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        let sum = let_init(package, "sum").expect("no sum");
+        let ExprKind::Application(callee, args) = &sum.kind else {
+            panic!("expected a call, got {:?}", sum.kind);
+        };
+        assert_eq!(ident_name(callee), "add");
+        let [Arg::Positional(first), Arg::Positional(second)] = args else {
+            panic!("expected two arguments, got {:?}", args);
+        };
+        assert_eq!(ident_name(first), "k");
+        assert!(matches!(second.kind, ExprKind::Lit(_)));
+    });
+}
+
+#[test]
+fn pipe_into_an_unknown_name_is_unresolved() {
+    let src = "fn f(k: Int) {\n    k |> nowhere\n}\n";
+    lower_source(src, |_, diag| {
+        assert_diagnostic(diag, 4005, "nowhere");
+    });
+}
+
+#[test]
+fn pipe_into_a_function_of_another_module_is_unresolved() {
+    let src = "mod m {\n    fn hidden(x: Int) {\n        x\n    }\n\n    fn g(k: Int) {\n        k |> hidden\n    }\n}\n\nfn f(k: Int) {\n    k |> hidden\n}\n";
+    lower_source(src, |_, diag| {
+        // Only the pipe outside `m` fails to resolve.
+        let unresolved = codes(diag).iter().filter(|&&code| code == 4005).count();
+        assert_eq!(unresolved, 1);
+        assert_diagnostic(diag, 4005, "hidden");
+    });
+}
+
+#[test]
+fn builtin_type_names_lower_to_their_type_expressions() {
+    let src = "fn f(a: void, b: Any, c: NoReturn, d: i32, e: Nope) {\n    1\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        let tys = param_types(package, "f");
+        assert_eq!(tys[0].kind, ExprKind::TyVoid);
+        assert_eq!(tys[1].kind, ExprKind::TyAny);
+        assert_eq!(tys[2].kind, ExprKind::TyNoReturn);
+        // Primitives stay names, as do names that are not builtins.
+        assert_eq!(ident_name(tys[3]), "i32");
+        assert_eq!(ident_name(tys[4]), "Nope");
+    });
+}
+
+#[test]
+fn a_type_defined_in_source_shadows_the_builtin_of_that_name() {
+    let src = "struct Any {\n    x: Int,\n}\n\nfn g(a: Any) {\n    1\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        let tys = param_types(package, "g");
+        assert_eq!(ident_name(tys[0]), "Any");
+        assert!(package.type_res(tys[0].hir_id).is_some());
+    });
+}
+
+#[test]
+fn a_type_alias_of_itself_is_reported() {
+    let src = "typealias A = *A;\n";
+    lower_source(src, |_, diag| {
+        assert_diagnostic(diag, 3024, "A");
+        assert_eq!(
+            messages(diag),
+            ["cyclic type alias: type alias `A` refers to itself"]
+        );
+    });
+}
+
+#[test]
+fn mutually_cyclic_type_aliases_are_reported_once() {
+    let src = "typealias A = *B;\ntypealias B = A;\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3024]);
+        assert_eq!(
+            messages(diag),
+            ["cyclic type alias: type aliases refer to each other: `A` -> `B` -> `A`"]
+        );
+    });
+}
+
+#[test]
+fn aliases_sharing_a_name_are_not_a_cycle() {
+    let src =
+        "struct B {\n    x: Int,\n}\n\ntypealias A = B;\n\nmod m {\n    typealias B = A;\n}\n";
+    lower_source(src, |_, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+    });
+}
+
+#[test]
+fn a_chain_of_type_aliases_is_not_a_cycle() {
+    let src = "struct Point {\n    x: Int,\n}\n\ntypealias A = B;\ntypealias B = *C;\ntypealias C = Point;\ntypealias D = *Point;\n";
+    lower_source(src, |_, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+    });
+}
+
+#[test]
+fn an_alias_parameter_takes_values_of_the_aliased_type() {
+    let src = "typealias Id = Int;\n\nfn main() {\n    let find: fn(Id) -> Int = |id| id;\n    find(1);\n    find(\"x\")\n}\n";
+    lower_source(src, |_, diag| {
+        assert_diagnostic(diag, 3016, "\"x\"");
+        assert_eq!(codes(diag), [3016]);
+        assert_eq!(
+            messages(diag),
+            ["mismatched argument type: expected `Int`, found `String`"]
+        );
+    });
+}
+
+#[test]
+fn a_newtype_parameter_does_not_take_its_underlying_type() {
+    let src = "newtype Meters = Int;\n\nfn main() {\n    let walk: fn(Meters) -> Int = |m| m;\n    let n: Int = 1;\n    let d: Meters = n;\n    walk(d);\n    walk(1);\n    walk(n)\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(
+            package
+                .owners()
+                .filter_map(|(_, info)| info.node.as_item())
+                .any(|item| matches!(item.kind, hir::ItemKind::Newtype(_)))
+        );
+        assert_eq!(codes(diag), [3016, 3016]);
+        assert_eq!(
+            messages(diag),
+            [
+                "mismatched argument type: expected `Meters`, found `Int`",
+                "mismatched argument type: expected `Meters`, found `Int`"
+            ]
+        );
+    });
+}
+
+#[test]
+fn a_type_name_means_the_definition_it_resolves_to() {
+    let src = "typealias Meters = Int;\n\nmod units {\n    newtype Meters = Int;\n}\n\nfn main() {\n    let walk: fn(Meters) -> Int = |m| m;\n    walk(1);\n    walk(\"x\")\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3016]);
+        assert_eq!(
+            messages(diag),
+            ["mismatched argument type: expected `Int`, found `String`"]
+        );
+    });
+}
+
+#[test]
+fn a_struct_named_like_a_newtype_elsewhere_is_not_one() {
+    let src = "struct Meters {\n    x: Int,\n}\n\nmod units {\n    newtype Meters = Int;\n}\n\nfn walk(d: Meters) {\n    let count: fn(Int) -> Int = |n| n;\n    count(d)\n}\n";
+    lower_source(src, |_, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+    });
+}
+
+#[test]
+fn a_newtype_and_its_underlying_type_convert_only_with_a_cast() {
+    let src = "newtype Meters = Int;\n\nfn main() {\n    let walk: fn(Meters) -> Int = |m| m.as(Int);\n    let count: fn(Int) -> Int = |n| n;\n    let n: Int = 2;\n    let d = n.as(Meters);\n    count(d);\n    count(d.as(Int));\n    walk(n);\n    walk(n.as(Meters))\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3016, 3016]);
+        assert_eq!(
+            messages(diag),
+            [
+                "mismatched argument type: expected `Int`, found `Meters`",
+                "mismatched argument type: expected `Meters`, found `Int`"
+            ]
+        );
+        assert_diagnostic(diag, 3016, "d");
+        assert_diagnostic(diag, 3016, "n");
+    });
+}
+
+#[test]
+fn an_effect_qualified_type_keeps_its_effects_as_a_row() {
+    let src = "fn run(job: #{IO, State} Int) {\n    job\n}\n\nfn swap(job: #{State, IO} Int) {\n    job\n}\n";
+    lower_source(src, |package, diag| {
+        assert!(codes(diag).is_empty(), "{:?}", messages(diag));
+        let ty = param_types(package, "run")[0];
+        let ExprKind::EffectQualifiedType(row, value) = &ty.kind else {
+            panic!("expected an effect-qualified type, got {:?}", ty.kind);
+        };
+        let ExprKind::TyEffectRow(effects) = &row.kind else {
+            panic!("expected an effect row, got {:?}", row.kind);
+        };
+        assert_eq!(
+            effects.iter().map(ident_name).collect::<Vec<_>>(),
+            ["IO", "State"]
+        );
+        assert_eq!(ident_name(value), "Int");
+
+        // A row is a set: written in another order, it is the same type.
+        assert_eq!(ty.type_key(), param_types(package, "swap")[0].type_key());
+    });
+}
+
+#[test]
+fn a_computation_with_fewer_effects_is_accepted_where_more_are_expected() {
+    let src = "fn main(quiet: #{IO} Int, both: #{State, IO} Int, loud: #{IO, Net} Int) {\n    let run: fn(#{IO, State} Int) -> Int = |job| 0;\n    let n: Int = 1;\n    run(quiet);\n    run(both);\n    run(n);\n    run(loud)\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3016], "{:?}", messages(diag));
+        assert_eq!(
+            messages(diag),
+            [
+                "mismatched argument type: expected `#{IO, State} Int`, found `#{IO, Net} Int` with unhandled effect(s) `Net`"
+            ]
+        );
+        assert_diagnostic(diag, 3016, "loud");
+    });
+}
+
+#[test]
+fn numeric_casts_are_accepted_and_narrowing_ones_warned_about() {
+    let src = "fn scale(n: i64, x: Float) -> Float {\n    let wide = n.as(Float);\n    let small = n.as(i8);\n    let whole = x.as(Int);\n    wide\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+        assert_eq!(
+            messages(diag),
+            [
+                "casting `i64` to `i8` may lose data",
+                "casting `Float` to `Int` may lose data"
+            ]
+        );
+    });
+}
+
+#[test]
+fn a_newtype_casts_to_and_from_its_representation() {
+    let src = "struct Point {\n    x: Int,\n}\n\nnewtype Meters = Int;\n\nfn walk(n: Int, d: Meters) -> Int {\n    let there = n.as(Meters);\n    let back = d.as(Int);\n    let same = d.as(Meters);\n    let wrong = d.as(Point);\n    back\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(codes(diag), [3025]);
+        assert_eq!(
+            messages(diag),
+            ["invalid cast: cannot cast `Meters` to `Point`"]
+        );
+    });
+}
+
+#[test]
+fn a_struct_does_not_cast_like_a_newtype_of_the_same_name() {
+    let src = "struct Meters {\n    x: Int,\n}\n\nmod units {\n    newtype Meters = Int;\n}\n\nfn walk(d: Meters) -> Int {\n    d.as(Int)\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(
+            messages(diag),
+            ["invalid cast: cannot cast `Meters` to `Int`"]
+        );
+    });
+}
+
+#[test]
+fn casting_a_struct_to_an_integer_is_rejected() {
+    let src = "struct Point {\n    x: Int,\n}\n\nfn area(p: Point) -> Int {\n    p.as(Int)\n}\n";
+    lower_source(src, |_, diag| {
+        assert_diagnostic(diag, 3025, "p.as(Int)");
+        assert_eq!(
+            messages(diag),
+            ["invalid cast: cannot cast `Point` to `Int`"]
+        );
+    });
+}
+
+#[test]
+fn pointer_casts_need_unsafe_code() {
+    let src =
+        "fn addr(p: *u8) -> usize {\n    let a = unsafe { p.as(usize) };\n    p.as(usize)\n}\n";
+    lower_source(src, |_, diag| {
+        assert_eq!(
+            messages(diag),
+            ["invalid cast: casting `*u8` to `usize` is only allowed in `unsafe` code"]
+        );
+    });
+}
+
+#[test]
+fn the_hir_dump_follows_bodies_through_the_package() {
+    let src = "fn f() {\n    1 + 2 * 3\n}\n";
+    lower_source(src, |package, diag| {
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+        let (owner, item) = package
+            .owners()
+            .filter_map(|(owner, _)| Some((owner, package.item(owner)?)))
+            .find(|(_, item)| item.ident.name.as_str() == "f")
+            .expect("no fn `f`");
+        let hir::ItemKind::Fn(_, body) = &item.kind else {
+            panic!("`f` is not a fn");
+        };
+        let ExprKind::Block(block) = &package.body(*body).unwrap().value.kind else {
+            panic!("the body of `f` is not a block");
+        };
+        assert_eq!(
+            package.dump_expr(block.expr.expect("no tail expression")),
+            "(Binary + (Int 1) (Binary * (Int 2) (Int 3)))"
+        );
+        assert_eq!(
+            package.dump_item(owner),
+            "(Fn f (params) (body (Block (Binary + (Int 1) (Binary * (Int 2) (Int 3))))))"
+        );
+    });
+}
+
+#[test]
+fn function_bodies_bind_every_form_of_parameter() {
+    let src = "fn add(a: Int, b: Int) -> Int {\n    a + b\n}\n\nfn sum(first: Int, ...rest: Int) -> Int {\n    first\n}\n";
+    lower_source(src, |package, diag| {
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+        let owner = package
+            .owners()
+            .map(|(owner, _)| owner)
+            .find(|&owner| {
+                package
+                    .item(owner)
+                    .is_some_and(|item| item.ident.name.as_str() == "add")
+            })
+            .expect("no fn `add`");
+        assert_eq!(
+            package.dump_item(owner),
+            "(Fn add (params (a (Id Int)) (b (Id Int))) (ret (Id Int)) \
+             (body (param a (Id Int)) (param b (Id Int)) (Block (Binary + (Id a) (Id b)))))"
+        );
+
+        let params = body_params(package, "sum");
+        let names: Vec<_> = params.iter().map(|p| p.name.name.as_str()).collect();
+        assert_eq!(names, ["first", "rest"]);
+        assert_eq!(ident_name(params[1].ty.expect("untyped vararg")), "Int");
+    });
+}
+
+#[test]
+fn an_enum_lowers_every_variant_form_and_its_nested_definitions() {
+    let src = "enum Token {\n    Eof,\n    Any: _,\n    Code = 1,\n    Pair(Int, Int),\n    Range { lo: Int, hi: Int },\n    Group.{ Open, Close },\n\n    fn zero() -> Int {\n        0\n    }\n\n    pure fn one() -> Int {\n        1\n    }\n\n    typealias Width = Int;\n}\n";
+    lower_source(src, |package, diag| {
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+        let item = package
+            .owners()
+            .filter_map(|(owner, _)| package.item(owner))
+            .find(|item| item.ident.name.as_str() == "Token")
+            .expect("no enum `Token`");
+        let hir::ItemKind::Enum(def) = &item.kind else {
+            panic!("`Token` is not an enum");
+        };
+        let variants: Vec<_> = def
+            .variants
+            .iter()
+            .map(|variant| {
+                let kind = match &variant.kind {
+                    VariantKind::Unit => "unit".to_string(),
+                    VariantKind::Pattern(_) => "pattern".to_string(),
+                    VariantKind::Const(_) => "const".to_string(),
+                    VariantKind::Tuple(elems) => format!("tuple/{}", elems.len()),
+                    VariantKind::Struct(fields) => format!("struct/{}", fields.len()),
+                    VariantKind::SubEnum(subs) => format!("sub-enum/{}", subs.len()),
+                };
+                (variant.ident.name.as_str().to_string(), kind)
+            })
+            .collect();
+        assert_eq!(
+            variants,
+            [
+                ("Eof", "unit"),
+                ("Any", "pattern"),
+                ("Code", "const"),
+                ("Pair", "tuple/2"),
+                ("Range", "struct/2"),
+                ("Group", "sub-enum/2"),
+            ]
+            .map(|(name, kind)| (name.to_string(), kind.to_string()))
+        );
+        let nested: Vec<_> = def
+            .nested_items
+            .iter()
+            .filter_map(|&owner| package.item(owner))
+            .map(|item| item.ident.name.as_str().to_string())
+            .collect();
+        assert_eq!(nested, ["zero", "one", "Width"]);
+    });
+}
+
+#[test]
+fn enum_variants_decode_each_payload() {
+    let src = "enum Token {\n    Eof,\n    Any: _,\n    Code = 1,\n    Pair(Int, Str),\n    Range { lo: Int, hi: Int },\n    Group.{ Open, Close },\n}\n\nstruct Point {\n    x: Int,\n}\n";
+    lower_source(src, |package, diag| {
+        assert_eq!(diag.error_count(), 0, "{:?}", messages(diag));
+        let owner = |name: &str| {
+            package
+                .owners()
+                .find(|&(owner, _)| {
+                    package
+                        .item(owner)
+                        .is_some_and(|i| i.ident.name.as_str() == name)
+                })
+                .map(|(owner, _)| owner)
+                .unwrap()
+        };
+        assert_eq!(package.enum_variants(owner("Point")), None);
+
+        let variants = package
+            .enum_variants(owner("Token"))
+            .expect("`Token` is an enum");
+        let decoded: Vec<(&str, String)> = variants
+            .iter()
+            .map(|variant| {
+                let payload = match &variant.payload {
+                    VariantPayload::Unit => "unit".to_string(),
+                    VariantPayload::Pattern(_) => "pattern".to_string(),
+                    VariantPayload::Const(_) => "const".to_string(),
+                    VariantPayload::Tuple(tys) => {
+                        let tys: Vec<_> = tys.iter().map(|ty| ident_name(ty)).collect();
+                        format!("({})", tys.join(", "))
+                    }
+                    VariantPayload::Struct(fields) => {
+                        let fields: Vec<_> = fields
+                            .iter()
+                            .map(|(name, ty)| format!("{}: {}", name, ident_name(ty)))
+                            .collect();
+                        format!("{{{}}}", fields.join(", "))
+                    }
+                    VariantPayload::SubEnum(subs) => {
+                        let subs: Vec<_> = subs.iter().map(|sub| sub.name.as_str()).collect();
+                        format!(".{{{}}}", subs.join(", "))
+                    }
+                };
+                (variant.name.as_str(), payload)
+            })
+            .collect();
+        assert_eq!(
+            decoded,
+            [
+                ("Eof", "unit"),
+                ("Any", "pattern"),
+                ("Code", "const"),
+                ("Pair", "(Int, Str)"),
+                ("Range", "{lo: Int, hi: Int}"),
+                ("Group", ".{Open, Close}"),
+            ]
+            .map(|(name, payload)| (name, payload.to_string()))
+        );
+        let VariantPayload::SubEnum(subs) = &variants[5].payload else {
+            unreachable!()
+        };
+        assert!(subs.iter().all(|sub| sub.payload == VariantPayload::Unit));
+    });
+}
+
+#[test]
+fn a_definition_lowering_does_not_handle_is_reported_instead_of_panicking() {
+    let src = "union Number {\n    int: Int,\n    real: Float,\n}\n\nfn f() {\n    test inner {\n    }\n    1\n}\n\nfn g() -> Int {\n    2\n}\n";
+    lower_source(src, |package, diag| {
+        assert_eq!(codes(diag), [3001, 3001]);
+        assert_eq!(
+            messages(diag),
+            [
+                "unsupported AST node `UnionDef` during lowering",
+                "unsupported AST node `TestDef` during lowering"
+            ]
+        );
+        let names: Vec<_> = package
+            .owners()
+            .filter_map(|(owner, _)| package.item(owner))
+            .filter(|item| !matches!(item.kind, hir::ItemKind::Invalid))
+            .map(|item| item.ident.name.as_str().to_string())
+            .collect();
+        assert!(names.contains(&"f".to_string()) && names.contains(&"g".to_string()));
+    });
+}
+
+#[test]
+fn lowering_goes_on_past_an_item_that_fails_and_summarizes_the_file() {
+    let src = "union Number {\n    int: Int,\n    real: Float,\n}\n\nfn f() -> Int {\n    1\n}\n\nstruct Point {\n    x: Int,\n}\n";
+    lower_source_full(src, |lowered| {
+        let (package, summary) = (lowered.package, lowered.summary);
+        assert_eq!(codes(lowered.diag), [3001]);
+        assert_eq!(summary.errors, 1);
+        assert_eq!(summary.failed_items, 1);
+        assert!(!summary.is_ok());
+
+        let items: Vec<_> = package
+            .owners()
+            .filter_map(|(owner, _)| package.item(owner))
+            .collect();
+        assert_eq!(summary.items, items.len());
+        for name in ["f", "Point"] {
+            assert!(
+                items.iter().any(|item| item.ident.name.as_str() == name
+                    && !matches!(item.kind, hir::ItemKind::Invalid)),
+                "`{name}` did not lower"
+            );
+        }
+    });
+
+    lower_source_full("fn g() -> Int {\n    2\n}\n", |lowered| {
+        assert!(lowered.summary.is_ok());
+        assert_eq!(lowered.summary.failed_items, 0);
+        assert_eq!(lowered.summary.items, 1);
+    });
+}
+
+#[test]
+fn an_integer_literal_too_large_for_64_bits_is_reported() {
+    let src = "fn main() {\n    let a = 0xFFFF_FFFF_FFFF_FFFF;\n    let b = 9223372036854775808;\n    let c = 18446744073709551615;\n    let d = 0x1_0000_0000_0000_0000;\n    let e = 18446744073709551616;\n}\n";
+    lower_source(src, |package, diag| {
+        assert_eq!(codes(diag), [3031, 3031], "{:?}", messages(diag));
+        assert_diagnostic(diag, 3031, "0x1_0000_0000_0000_0000");
+        assert_diagnostic(diag, 3031, "18446744073709551616");
+
+        let lit = |name| match &let_init(package, name).unwrap().kind {
+            ExprKind::Lit(lit) => lit.kind.clone(),
+            other => panic!("`{name}` is not a literal: {other:?}"),
+        };
+        assert_eq!(lit("a"), LitKind::Integer(-1));
+        assert_eq!(lit("b"), LitKind::Integer(i64::MIN));
+        assert_eq!(lit("c"), LitKind::Integer(-1));
+    });
+}
+
+#[test]
+fn every_lowering_error_is_registered_under_its_name() {
+    use crate::error::{LoweringError, LoweringErrorKind::*};
+
+    let text = String::new;
+    let kinds = [
+        UnsupportedNode(text()),
+        MalformedAst(text()),
+        UnsupportedClause(text()),
+        InvalidParameter(text()),
+        InvalidPattern(text()),
+        InvalidItemInContext(text()),
+        InvalidEnumVariant(text()),
+        InvalidStructField(text()),
+        MissingIdentifier,
+        InvalidTypeExpr(text()),
+        MismatchedPattern(text()),
+        InconsistentOrBindings(text()),
+        UnknownPatternField(text()),
+        BindingInNotPattern(text()),
+        ArgumentCountMismatch(text()),
+        MismatchedArgument(text()),
+        ConflictingImpls(text(), Span::default()),
+        ReceiverOutsideMethod(text()),
+        NonConstantComptimeArgument(text()),
+        CatchWithoutErrors(text()),
+        StaticAssertionFailed(text()),
+        DivisionByZero(text()),
+        RecursiveSplice(text()),
+        CyclicTypeAlias(text()),
+        InvalidCast(text()),
+        UnknownField(text()),
+        MisorderedArgument(text()),
+        UnknownParameter(text()),
+        DuplicateArgument(text()),
+        UnresolvedImplicit(text()),
+        IntegerOverflow(text()),
+    ];
+    for kind in kinds {
+        assert_registered(&LoweringError::new(kind, Span::default()));
+    }
+}
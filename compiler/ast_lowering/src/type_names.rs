@@ -0,0 +1,93 @@
+//! Resolving the names written in type position.
+//!
+//! A type expression that is a plain name is resolved from the file's scope
+//! and the definition it names is recorded with [`Package::record_type_res`].
+//! Programs name the same few types over and over, so each `(scope, name)`
+//! pair goes to the resolver once per lowering and is then answered from
+//! [`LoweringContext::type_names`]. That cache lives exactly as long as the
+//! context, which is built for one module tree, so a rescan starts empty.
+//!
+//! A name that does not resolve may still be a builtin type, which has no
+//! definition in the module tree (see [`LangItem::builtin_type`]). `Any`,
+//! `void`, `NoReturn` and `type` lower to their own type expressions;
+//! primitives such as `bool` stay names the type checker knows. Other names
+//! that do not resolve are not reported here. A name that several glob
+//! imports supply is reported once per scope, at its first use, and is left
+//! unresolved.
+//!
+//! [`Package::record_type_res`]: hir::Package::record_type_res
+
+use ast::NodeIndex;
+use diagnostic::FlurryError;
+use hir::{
+    common::Symbol,
+    expr::{Expr, ExprKind},
+};
+use intrinsic::LangItem;
+use resolve::{NameLookup, Resolution};
+use rustc_span::Span;
+
+use crate::LoweringContext;
+
+impl<'hir, 'ast> LoweringContext<'hir, 'ast> {
+    /// Lower the type expression `node`, resolving it if it is a name.
+    pub(crate) fn lower_type(&mut self, node: NodeIndex) -> &'hir Expr<'hir> {
+        let mut ty = self.lower_expr(node);
+        if let ExprKind::Ident(name) = ty.kind {
+            match self.resolve_type_name(name, ty.span) {
+                Some(res) => self.package.record_type_res(ty.hir_id, res.def_id),
+                None => {
+                    if let Some(kind) = special_type(name) {
+                        ty.kind = kind;
+                    }
+                }
+            }
+        }
+        self.arena.alloc_expr(ty)
+    }
+
+    /// Resolve the names `ty` mentions at any depth, recording each
+    /// definition as [`Self::lower_type`] does for a type that is a name.
+    pub(crate) fn resolve_names_in_type(&mut self, ty: &'hir Expr<'hir>) {
+        if let ExprKind::Ident(name) = ty.kind
+            && let Some(res) = self.resolve_type_name(name, ty.span)
+        {
+            self.package.record_type_res(ty.hir_id, res.def_id);
+        }
+        for sub in ty.subexprs() {
+            self.resolve_names_in_type(sub);
+        }
+    }
+
+    pub(crate) fn resolve_type_name(&mut self, name: Symbol, span: Span) -> Option<Resolution> {
+        let key = (self.file_scope, name);
+        if let Some(cached) = self.type_names.get(&key) {
+            return cached.clone();
+        }
+        let res = match self.resolver.lookup_name(name.as_str(), self.file_scope) {
+            NameLookup::Single(res) => Some(res),
+            NameLookup::Ambiguous(candidates) => {
+                let err = self
+                    .resolver
+                    .ambiguity_error(name.as_str(), &candidates, span);
+                err.emit(self.diag_ctx, rustc_span::BytePos(0));
+                None
+            }
+            NameLookup::NotFound => None,
+        };
+        self.type_names.insert(key, res.clone());
+        res
+    }
+}
+
+/// The type expression of the builtin type `name` where it has one of its
+/// own, rather than being a primitive known by name.
+fn special_type<'hir>(name: Symbol) -> Option<ExprKind<'hir>> {
+    Some(match LangItem::builtin_type(name.as_str())? {
+        LangItem::AnyType => ExprKind::TyAny,
+        LangItem::Void => ExprKind::TyVoid,
+        LangItem::NoReturn => ExprKind::TyNoReturn,
+        LangItem::Type => ExprKind::TyType,
+        _ => return None,
+    })
+}
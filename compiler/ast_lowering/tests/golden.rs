@@ -0,0 +1,69 @@
+//! Golden tests of the whole front end.
+//!
+//! Each package under `tests/golden/` is scanned into a VFS, resolved into a
+//! module tree and lowered to HIR. The dumps of all three are compared with
+//! `<package>.vfs.lisp`, `<package>.scope.lisp` and `<package>.hir.lisp`
+//! next to the package. Run with `UPDATE_GOLDEN=1` to write the current
+//! dumps as the golden files instead.
+
+use std::{env, fs, path::Path};
+
+use diagnostic::DiagnosticContext;
+use hir::HirArena;
+use resolve::Resolver;
+use rustc_span::{SourceMap, source_map::FilePathMapping};
+
+/// The dumps of the package at `root`, each with the name of the golden
+/// file it is compared with.
+fn dumps(root: &Path) -> [(&'static str, String); 3] {
+    let source_map = SourceMap::new(FilePathMapping::empty());
+    let diag_ctx = DiagnosticContext::new(&source_map);
+
+    let mut vfs = vfs::Vfs::scan(root.to_path_buf(), &source_map, &[]);
+    let module_tree = resolve::build_module_tree(&source_map, &diag_ctx, &mut vfs);
+    let resolver = Resolver::new(&module_tree);
+    let arena = HirArena::new();
+    let package = ast_lowering::lower_package(&vfs, 0, &arena, &source_map, &diag_ctx, &resolver);
+
+    assert!(
+        module_tree.errors.is_empty() && !diag_ctx.has_errors(),
+        "{} does not compile",
+        root.display()
+    );
+    [
+        ("vfs", vfs.dump_to_lisp()),
+        ("scope", module_tree.dump_scope_tree()),
+        ("hir", package.dump_to_lisp()),
+    ]
+}
+
+/// Compare the dumps of the package `name` with its golden files, or
+/// rewrite them under `UPDATE_GOLDEN`.
+fn check_golden(name: &str) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    for (kind, dump) in dumps(&dir.join(name)) {
+        let golden = dir.join(format!("{name}.{kind}.lisp"));
+        if update {
+            fs::write(&golden, format!("{}\n", dump.trim_end())).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&golden).unwrap_or_else(|err| {
+            panic!(
+                "cannot read {}: {err}; run with UPDATE_GOLDEN=1 to create it",
+                golden.display()
+            )
+        });
+        assert_eq!(
+            dump.trim_end(),
+            expected.trim_end(),
+            "{} is out of date; run with UPDATE_GOLDEN=1 to update it",
+            golden.display()
+        );
+    }
+}
+
+#[test]
+fn a_package_with_a_module_a_struct_and_an_import() {
+    check_golden("geometry");
+}
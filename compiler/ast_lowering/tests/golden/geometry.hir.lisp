@@ -0,0 +1,9 @@
+(hir-package
+  (mod <root>)
+  (fn measure (params p) (ret ...) (body 0)
+    (body-params p))
+  (mod shapes)
+  (struct Point (fields x y))
+  (fn area (params p) (ret ...) (body 0)
+    (body-params p))
+)
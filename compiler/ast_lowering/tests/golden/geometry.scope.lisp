@@ -0,0 +1,18 @@
+(Root "<root>" ScopeId(0)
+  (def geometry Module DefId(0:1))
+  (Package "geometry" ScopeId(1)
+    (def measure Function DefId(0:2))
+    (def shapes Module DefId(0:3))
+    (import-multi ScopeId(3) ["Point", "area"])
+    (FnBody "<anon>" ScopeId(2)
+    )
+    (Module "shapes" ScopeId(3)
+      (def Point Struct DefId(0:4))
+      (def area Function DefId(0:5))
+      (AdtBody "Point" ScopeId(4)
+      )
+      (FnBody "<anon>" ScopeId(5)
+      )
+    )
+  )
+)
@@ -0,0 +1,3 @@
+(vfs geometry
+  (file 0 "main.fl" parsed)
+  (file 1 "shapes.fl" parsed))
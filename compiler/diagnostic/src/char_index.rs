@@ -0,0 +1,56 @@
+//! Byte offsets to character offsets.
+//!
+//! ariadne counts positions in characters while spans count bytes. A
+//! [`CharIndex`] remembers the byte and character offset each line of a
+//! file starts at, so converting an offset only counts the characters of
+//! its own line.
+
+/// The byte and character offset of the start of each line of a file.
+pub(crate) struct CharIndex {
+    /// `(byte, char)` for each line start, in order; the first is `(0, 0)`.
+    line_starts: Vec<(usize, usize)>,
+}
+
+impl CharIndex {
+    pub(crate) fn new(content: &str) -> Self {
+        let mut line_starts = vec![(0, 0)];
+        for (chars, (byte, c)) in content.char_indices().enumerate() {
+            if c == '\n' {
+                line_starts.push((byte + 1, chars + 1));
+            }
+        }
+        Self { line_starts }
+    }
+
+    /// The index of the character at byte offset `byte` of `content`, if
+    /// that is a character boundary. Offsets past the end are the end.
+    /// `content` must be the text the index was built from.
+    pub(crate) fn char_index(&self, content: &str, byte: usize) -> Option<usize> {
+        let byte = byte.min(content.len());
+        // The first line starts at 0, so at least one line starts by `byte`
+        let lines = self
+            .line_starts
+            .partition_point(|&(start, _)| start <= byte);
+        let (line_byte, line_char) = self.line_starts[lines - 1];
+        content
+            .get(line_byte..byte)
+            .map(|s| line_char + s.chars().count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_match_counting_from_the_start_of_the_file() {
+        let content = "fn 主() {\n    let é = \"😀\";\r\n\n    é\n}";
+        let index = CharIndex::new(content);
+        for byte in 0..=content.len() + 2 {
+            let naive = content
+                .get(..byte.min(content.len()))
+                .map(|s| s.chars().count());
+            assert_eq!(index.char_index(content, byte), naive, "at byte {byte}");
+        }
+    }
+}
@@ -0,0 +1,296 @@
+//! Diagnostics as JSON, for editors and other tools.
+//!
+//! Each diagnostic becomes one object:
+//!
+//! ```text
+//! {"level": "error", "code": 4007, "message": "...", "span": {...},
+//!  "labels": [{"span": {...}, "message": "...", "level": "error"}],
+//!  "related": [{"span": {...}, "message": "..."}],
+//!  "notes": ["..."], "helps": ["..."],
+//!  "suggestions": [{"span": {...}, "replacement": "...",
+//!                   "applicability": "maybe-incorrect"}]}
+//! ```
+//!
+//! A span is `{"file_name", "byte_start", "byte_end", "line_start",
+//! "line_end", "column_start", "column_end"}`, with byte offsets relative to
+//! the file and 1-based lines and columns (in characters); a dummy span is
+//! `null`. `labels` are drawn inline under the source, while `related` are
+//! the other locations a diagnostic points at, for editors to show as links.
+//!
+//! A context built [`with_format`](DiagnosticContext::with_format)
+//! [`DiagnosticFormat::Json`](crate::DiagnosticFormat::Json) prints each
+//! diagnostic it emits this way, one per line on stderr.
+
+use std::fmt::Write;
+
+use rustc_span::Span;
+
+use crate::{Diagnostic, DiagnosticContext};
+
+impl DiagnosticContext<'_> {
+    /// `diagnostic` as a JSON object.
+    pub fn to_json(&self, diagnostic: &Diagnostic) -> String {
+        let mut out = String::new();
+        out.push_str("{\"level\":");
+        write_str(&mut out, diagnostic.level.name());
+        out.push_str(",\"code\":");
+        match diagnostic.code {
+            Some(code) => write!(out, "{}", code).unwrap(),
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"message\":");
+        write_str(&mut out, &diagnostic.message);
+        out.push_str(",\"span\":");
+        self.write_span(&mut out, diagnostic.primary_span);
+
+        out.push_str(",\"labels\":[");
+        for (i, label) in diagnostic.labels.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"span\":");
+            self.write_span(&mut out, Some(label.span));
+            out.push_str(",\"message\":");
+            write_str(&mut out, &label.message);
+            out.push_str(",\"level\":");
+            write_str(&mut out, label.level.name());
+            out.push('}');
+        }
+
+        out.push_str("],\"related\":[");
+        for (i, (span, message)) in diagnostic.related.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"span\":");
+            self.write_span(&mut out, Some(*span));
+            out.push_str(",\"message\":");
+            write_str(&mut out, message);
+            out.push('}');
+        }
+
+        out.push_str("],\"notes\":");
+        write_str_list(&mut out, &diagnostic.notes);
+        out.push_str(",\"helps\":");
+        write_str_list(&mut out, &diagnostic.helps);
+
+        out.push_str(",\"suggestions\":[");
+        for (i, suggestion) in diagnostic.suggestions.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"span\":");
+            self.write_span(&mut out, Some(suggestion.span));
+            out.push_str(",\"replacement\":");
+            write_str(&mut out, &suggestion.replacement);
+            out.push_str(",\"applicability\":");
+            write_str(&mut out, suggestion.applicability.name());
+            out.push('}');
+        }
+        out.push_str("]}");
+        out
+    }
+
+    /// Every diagnostic emitted so far, as a JSON array. Panics unless
+    /// [`Self::finish`] ran after the last one was emitted.
+    pub fn emitted_json(&self) -> String {
+        assert!(
+            self.finished.get(),
+            "`finish` the diagnostics before reporting them as a batch"
+        );
+        let diagnostics = self.emitted_diagnostics.borrow();
+        let objects: Vec<String> = diagnostics.iter().map(|d| self.to_json(d)).collect();
+        format!("[{}]", objects.join(","))
+    }
+
+    fn write_span(&self, out: &mut String, span: Option<Span>) {
+        let Some(span) = span.filter(|span| !span.is_dummy()) else {
+            out.push_str("null");
+            return;
+        };
+        let lo = self.source_map.lookup_char_pos(span.lo());
+        let hi = self.source_map.lookup_char_pos(span.hi());
+        out.push_str("{\"file_name\":");
+        write_str(out, &self.file_display_name(&lo.file));
+        write!(
+            out,
+            ",\"byte_start\":{},\"byte_end\":{},\"line_start\":{},\"line_end\":{},\"column_start\":{},\"column_end\":{}}}",
+            span.lo().0 - lo.file.start_pos.0,
+            span.hi().0 - lo.file.start_pos.0,
+            lo.line,
+            hi.line,
+            lo.col.0 + 1,
+            hi.col.0 + 1,
+        )
+        .unwrap();
+    }
+}
+
+fn write_str_list(out: &mut String, items: &[String]) {
+    out.push('[');
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_str(out, item);
+    }
+    out.push(']');
+}
+
+/// Write `s` as a JSON string literal.
+fn write_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_span::{
+        BytePos, FileName, RealFileName, SourceMap, Span, source_map::FilePathMapping,
+    };
+
+    use crate::{Applicability, DiagnosticBuilder, DiagnosticContext};
+
+    #[test]
+    fn related_locations_are_listed_apart_from_labels() {
+        let src = "fn f() {}\nfn f() {}\n";
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(
+            FileName::Real(RealFileName::LocalPath("main.fl".into())),
+            src.into(),
+        );
+        let mut ctx = DiagnosticContext::new(&source_map);
+        let base = file.start_pos.0;
+        let first = Span::new(BytePos(base + 3), BytePos(base + 4));
+        let second = Span::new(BytePos(base + 13), BytePos(base + 14));
+
+        DiagnosticBuilder::error("duplicate definition of `f`".to_string())
+            .with_code(4007)
+            .with_primary_span(second)
+            .with_error_label(second, "redefined here".to_string())
+            .with_related(first, "first defined here".to_string())
+            .emit(&ctx);
+
+        ctx.finish();
+        let json = ctx.emitted_json();
+        assert_eq!(
+            json,
+            concat!(
+                "[{\"level\":\"error\",\"code\":4007,\"message\":\"duplicate definition of `f`\",",
+                "\"span\":{\"file_name\":\"main.fl\",\"byte_start\":13,\"byte_end\":14,",
+                "\"line_start\":2,\"line_end\":2,\"column_start\":4,\"column_end\":5},",
+                "\"labels\":[{\"span\":{\"file_name\":\"main.fl\",\"byte_start\":13,\"byte_end\":14,",
+                "\"line_start\":2,\"line_end\":2,\"column_start\":4,\"column_end\":5},",
+                "\"message\":\"redefined here\",\"level\":\"error\"}],",
+                "\"related\":[{\"span\":{\"file_name\":\"main.fl\",\"byte_start\":3,\"byte_end\":4,",
+                "\"line_start\":1,\"line_end\":1,\"column_start\":4,\"column_end\":5},",
+                "\"message\":\"first defined here\"}],",
+                "\"notes\":[],\"helps\":[],\"suggestions\":[]}]"
+            )
+        );
+    }
+
+    #[test]
+    fn labels_and_notes_are_located_by_line_and_column() {
+        let src = "fn main() {\n    let x = y;\n}\n";
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(
+            FileName::Real(RealFileName::LocalPath("main.fl".into())),
+            src.into(),
+        );
+        let ctx = DiagnosticContext::new(&source_map);
+        let base = file.start_pos.0;
+        let y = Span::new(BytePos(base + 24), BytePos(base + 25));
+
+        let diagnostic = DiagnosticBuilder::error("unresolved name `y`".to_string())
+            .with_code(4005)
+            .with_primary_span(y)
+            .with_error_label(y, "not found in this scope".to_string())
+            .with_note("values must be defined before they are used".to_string())
+            .build();
+
+        assert_eq!(
+            ctx.to_json(&diagnostic),
+            concat!(
+                "{\"level\":\"error\",\"code\":4005,\"message\":\"unresolved name `y`\",",
+                "\"span\":{\"file_name\":\"main.fl\",\"byte_start\":24,\"byte_end\":25,",
+                "\"line_start\":2,\"line_end\":2,\"column_start\":13,\"column_end\":14},",
+                "\"labels\":[{\"span\":{\"file_name\":\"main.fl\",\"byte_start\":24,\"byte_end\":25,",
+                "\"line_start\":2,\"line_end\":2,\"column_start\":13,\"column_end\":14},",
+                "\"message\":\"not found in this scope\",\"level\":\"error\"}],",
+                "\"related\":[],\"notes\":[\"values must be defined before they are used\"],",
+                "\"helps\":[],\"suggestions\":[]}"
+            )
+        );
+    }
+
+    #[test]
+    fn suggestions_give_their_replacement_and_applicability() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(
+            FileName::Real(RealFileName::LocalPath("main.fl".into())),
+            "f(1;\n".into(),
+        );
+        let ctx = DiagnosticContext::new(&source_map);
+        let at = BytePos(file.start_pos.0 + 3);
+
+        let diagnostic = DiagnosticBuilder::error("Expected `)`, found `;`".to_string())
+            .with_suggestion(
+                Span::new(at, at),
+                ")".to_string(),
+                Applicability::MaybeIncorrect,
+            )
+            .build();
+
+        let json = ctx.to_json(&diagnostic);
+        assert!(
+            json.ends_with(concat!(
+                "\"suggestions\":[{\"span\":{\"file_name\":\"main.fl\",\"byte_start\":3,",
+                "\"byte_end\":3,\"line_start\":1,\"line_end\":1,\"column_start\":4,",
+                "\"column_end\":4},\"replacement\":\")\",\"applicability\":\"maybe-incorrect\"}]}"
+            )),
+            "{}",
+            json
+        );
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let ctx = DiagnosticContext::new(&source_map);
+        let diagnostic = DiagnosticBuilder::error("bad \"quote\"\n\\".to_string())
+            .with_note("tab\there".to_string())
+            .build();
+
+        assert_eq!(
+            ctx.to_json(&diagnostic),
+            concat!(
+                "{\"level\":\"error\",\"code\":null,\"message\":\"bad \\\"quote\\\"\\n\\\\\",",
+                "\"span\":null,\"labels\":[],\"related\":[],\"notes\":[\"tab\\there\"],\"helps\":[],",
+                "\"suggestions\":[]}"
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "`finish` the diagnostics")]
+    fn batch_output_waits_for_the_diagnostics_to_be_finished() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let mut ctx = DiagnosticContext::new(&source_map);
+        DiagnosticBuilder::error("first".to_string()).emit(&ctx);
+        ctx.finish();
+        DiagnosticBuilder::error("second".to_string()).emit(&ctx);
+        ctx.emitted_json();
+    }
+}
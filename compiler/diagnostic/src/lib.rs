@@ -1,10 +1,22 @@
+mod char_index;
 pub mod emitter;
-
-use ariadne::{Color, ColorGenerator, Label, Report, ReportKind, Source};
-use rustc_span::{FileNameDisplayPreference, SourceMap, Span};
+pub mod json;
+pub mod registry;
+pub mod testing;
+
+use ariadne::{Color, ColorGenerator, Config, Label, Report, ReportKind};
+use char_index::CharIndex;
+use registry::CodeInfo;
+use rustc_span::{
+    BytePos, FileName, FileNameDisplayPreference, RealFileName, SourceFile, SourceMap, Span,
+};
 use std::{
     cell::{Cell, RefCell},
-    fmt, mem,
+    collections::{HashMap, HashSet},
+    fmt, io, mem,
+    ops::Range,
+    path::{self, PathBuf},
+    sync::Arc,
 };
 
 // 罢了, warning也用这个trait吧
@@ -60,6 +72,41 @@ impl DiagnosticMessage {
     }
 }
 
+/// How safely a [`Suggestion`] can be applied without a person checking
+/// the result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Applicability {
+    /// The fix is certainly what was meant; tools may apply it on their own.
+    MachineApplicable,
+    /// The fix is likely what was meant, but may not be.
+    MaybeIncorrect,
+    /// The replacement holds placeholders to be filled in by hand.
+    HasPlaceholders,
+    /// Nothing is known about how good the fix is.
+    Unspecified,
+}
+
+impl Applicability {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Applicability::MachineApplicable => "machine-applicable",
+            Applicability::MaybeIncorrect => "maybe-incorrect",
+            Applicability::HasPlaceholders => "has-placeholders",
+            Applicability::Unspecified => "unspecified",
+        }
+    }
+}
+
+/// A fix for a diagnostic: replace the text at `span` with `replacement`.
+/// An empty span inserts `replacement`, and an empty `replacement` deletes
+/// the text at `span`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub span: Span,
+    pub replacement: String,
+    pub applicability: Applicability,
+}
+
 /// A complete diagnostic with primary message and optional sub-diagnostics
 #[derive(Debug, Clone)]
 pub struct Diagnostic {
@@ -68,8 +115,13 @@ pub struct Diagnostic {
     pub message: String,
     pub primary_span: Option<Span>,
     pub labels: Vec<DiagnosticMessage>,
+    /// Other locations the diagnostic refers to, such as the first
+    /// definition of a duplicated name. Unlike `labels`, these are not drawn
+    /// inline; editors show them as clickable secondary locations.
+    pub related: Vec<(Span, String)>,
     pub notes: Vec<String>,
     pub helps: Vec<String>,
+    pub suggestions: Vec<Suggestion>,
 }
 
 impl Diagnostic {
@@ -80,8 +132,10 @@ impl Diagnostic {
             message,
             primary_span: None,
             labels: Vec::new(),
+            related: Vec::new(),
             notes: Vec::new(),
             helps: Vec::new(),
+            suggestions: Vec::new(),
         }
     }
 
@@ -163,6 +217,13 @@ impl DiagnosticBuilder {
         self.with_label(span, message, Level::Help)
     }
 
+    /// Refer to another location, e.g. where a clashing name was first
+    /// defined.
+    pub fn with_related(mut self, span: Span, message: String) -> Self {
+        self.diagnostic.related.push((span, message));
+        self
+    }
+
     pub fn with_note(mut self, note: String) -> Self {
         self.diagnostic.notes.push(note);
         self
@@ -173,6 +234,21 @@ impl DiagnosticBuilder {
         self
     }
 
+    /// Offer to replace the text at `span` with `replacement`.
+    pub fn with_suggestion(
+        mut self,
+        span: Span,
+        replacement: String,
+        applicability: Applicability,
+    ) -> Self {
+        self.diagnostic.suggestions.push(Suggestion {
+            span,
+            replacement,
+            applicability,
+        });
+        self
+    }
+
     pub fn build(self) -> Diagnostic {
         self.diagnostic
     }
@@ -182,40 +258,173 @@ impl DiagnosticBuilder {
     }
 }
 
+/// How emitted diagnostics are printed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiagnosticFormat {
+    /// Reports drawn under the source, for people.
+    #[default]
+    Human,
+    /// One JSON object per line (see [`json`]), for editors and tools.
+    Json,
+}
+
+/// Which warnings are reported, and at what level.
+///
+/// A code in `deny` or `allow` overrides `warnings_as_errors`; a code in
+/// both is denied. Errors are never suppressed, and warnings without a code
+/// only follow `warnings_as_errors`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagnosticPolicy {
+    /// Warnings with these codes are reported as errors.
+    pub deny: HashSet<u32>,
+    /// Warnings with these codes are not reported at all.
+    pub allow: HashSet<u32>,
+    /// Report every other warning as an error.
+    pub warnings_as_errors: bool,
+}
+
+impl DiagnosticPolicy {
+    /// The level a diagnostic of `level` with `code` is reported at, or
+    /// `None` if it is suppressed.
+    pub fn level_of(&self, level: Level, code: Option<u32>) -> Option<Level> {
+        if level != Level::Warning {
+            return Some(level);
+        }
+        match code {
+            Some(code) if self.deny.contains(&code) => Some(Level::Error),
+            Some(code) if self.allow.contains(&code) => None,
+            _ if self.warnings_as_errors => Some(Level::Error),
+            _ => Some(Level::Warning),
+        }
+    }
+}
+
 /// Context for managing and emitting diagnostics
 pub struct DiagnosticContext<'a> {
     source_map: &'a SourceMap,
+    /// Files below this directory are shown relative to it.
+    project_root: Option<PathBuf>,
+    format: DiagnosticFormat,
+    policy: DiagnosticPolicy,
     emitted_diagnostics: RefCell<Vec<Diagnostic>>,
     error_count: Cell<usize>,
     warning_count: Cell<usize>,
+    /// Whether [`Self::finish`] ran since the last diagnostic was emitted.
+    finished: Cell<bool>,
+    /// The character index of each file drawn so far, by its start
+    char_indices: RefCell<HashMap<BytePos, CharIndex>>,
 }
 
 impl<'a> DiagnosticContext<'a> {
     pub fn new(source_map: &'a SourceMap) -> Self {
+        debug_assert!(
+            registry::codes_are_unique(),
+            "two errors share a code in the registry"
+        );
         Self {
             source_map,
+            project_root: None,
+            format: DiagnosticFormat::Human,
+            policy: DiagnosticPolicy::default(),
             emitted_diagnostics: RefCell::new(Vec::new()),
             error_count: Cell::new(0),
             warning_count: Cell::new(0),
+            finished: Cell::new(false),
+            char_indices: RefCell::new(HashMap::new()),
         }
     }
 
+    /// Show the files below `root` by their path relative to it, e.g.
+    /// `src/foo.fl`, however they were loaded.
+    pub fn with_project_root(mut self, root: PathBuf) -> Self {
+        self.project_root = Some(root);
+        self
+    }
+
+    /// Print the diagnostics emitted from now on in `format`.
+    pub fn with_format(mut self, format: DiagnosticFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Promote and suppress the warnings emitted from now on by `policy`.
+    pub fn with_policy(mut self, policy: DiagnosticPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn policy(&self) -> &DiagnosticPolicy {
+        &self.policy
+    }
+
+    /// The name and description of the error `code`, as registered in
+    /// [`registry`].
+    pub fn describe_code(&self, code: u32) -> Option<&'static CodeInfo> {
+        registry::lookup(code)
+    }
+
+    pub fn format(&self) -> DiagnosticFormat {
+        self.format
+    }
+
     pub fn source_map(&self) -> &SourceMap {
         self.source_map
     }
 
-    pub fn emit(&self, diagnostic: Diagnostic) {
+    /// The name `source_file` is shown under in diagnostics.
+    ///
+    /// A file below the project root is named by its root-relative path
+    /// with `/` separators, whether it was loaded through an absolute or a
+    /// relative path, or was given a name of its own when it was refreshed;
+    /// any other file by its local display name.
+    pub fn file_display_name(&self, source_file: &SourceFile) -> String {
+        if let (
+            Some(root),
+            FileName::Real(
+                RealFileName::LocalPath(file)
+                | RealFileName::Remapped {
+                    local_path: Some(file),
+                    ..
+                },
+            ),
+        ) = (&self.project_root, &source_file.name)
+            && let (Ok(root), Ok(file)) = (path::absolute(root), path::absolute(file))
+            && let Ok(relative) = file.strip_prefix(&root)
+        {
+            let parts: Vec<_> = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect();
+            return parts.join("/");
+        }
+        source_file
+            .name
+            .display(FileNameDisplayPreference::Local)
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Report `diagnostic` at the level the [policy](DiagnosticPolicy)
+    /// gives it, or drop it if the policy suppresses it.
+    pub fn emit(&self, mut diagnostic: Diagnostic) {
+        let Some(level) = self.policy.level_of(diagnostic.level, diagnostic.code) else {
+            return;
+        };
+        diagnostic.level = level;
         match diagnostic.level {
             Level::Error => self.error_count.set(self.error_count.get() + 1),
             Level::Warning => self.warning_count.set(self.warning_count.get() + 1),
             _ => {}
         }
 
-        // Emit to ariadne
-        self.emit_to_ariadne(&diagnostic);
+        match self.format {
+            DiagnosticFormat::Human => self.emit_to_ariadne(&diagnostic),
+            DiagnosticFormat::Json => eprintln!("{}", self.to_json(&diagnostic)),
+        }
 
         // Store for later analysis
         self.emitted_diagnostics.borrow_mut().push(diagnostic);
+        self.finished.set(false);
     }
 
     pub fn error_count(&self) -> usize {
@@ -234,6 +443,33 @@ impl<'a> DiagnosticContext<'a> {
         self.warning_count.get() > 0
     }
 
+    /// Drop the repeats among the diagnostics emitted so far and order the
+    /// rest by where they point, ready for a report of all of them.
+    ///
+    /// Diagnostics with the same code, message and primary span are
+    /// repeats, as when a node is visited twice, and only the first is
+    /// kept. The rest are ordered by file name, then by position in the
+    /// file; those without a location come last. Diagnostics are still
+    /// printed as they are emitted; batch output such as
+    /// [`Self::emitted_json`] must come after `finish`.
+    pub fn finish(&mut self) {
+        let mut diagnostics = mem::take(self.emitted_diagnostics.get_mut());
+        let mut seen = HashSet::new();
+        diagnostics.retain(|d| seen.insert((d.code, d.message.clone(), d.primary_span)));
+        diagnostics.sort_by_cached_key(|d| {
+            let span = d.primary_span.filter(|span| !span.is_dummy());
+            let file = span
+                .map(|span| self.file_display_name(&self.source_map.lookup_source_file(span.lo())));
+            (span.is_none(), file, span.map(|span| span.lo()))
+        });
+
+        let count = |level| diagnostics.iter().filter(|d| d.level == level).count();
+        self.error_count.set(count(Level::Error));
+        self.warning_count.set(count(Level::Warning));
+        *self.emitted_diagnostics.get_mut() = diagnostics;
+        self.finished.set(true);
+    }
+
     pub unsafe fn diagnostics<'b>(&'b self) -> &'b [Diagnostic] {
         unsafe {
             mem::transmute::<&[Diagnostic], &'b [Diagnostic]>(
@@ -261,6 +497,29 @@ impl<'a> DiagnosticContext<'a> {
 
     /// Emit diagnostic using ariadne
     fn emit_to_ariadne(&self, diagnostic: &Diagnostic) {
+        if let Err(e) = self.write_ariadne(diagnostic, Config::default(), io::stdout()) {
+            eprintln!("Error printing diagnostic: {}", e);
+        }
+    }
+
+    /// `diagnostic` drawn as [`emit`](Self::emit) prints it, but without
+    /// colors, e.g. to compare with an expected rendering in a test.
+    pub fn render_to_string(&self, diagnostic: &Diagnostic) -> String {
+        let mut out = Vec::new();
+        self.write_ariadne(diagnostic, Config::default().with_color(false), &mut out)
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8(out).expect("ariadne writes UTF-8")
+    }
+
+    /// Draw `diagnostic` with ariadne to `out`. Every file the primary span
+    /// or a label points into is drawn, so a label in another file than
+    /// the primary span is shown under that file's lines.
+    fn write_ariadne(
+        &self,
+        diagnostic: &Diagnostic,
+        config: Config,
+        out: impl io::Write,
+    ) -> io::Result<()> {
         let primary_span = diagnostic.primary_span.unwrap_or_else(|| {
             // Use the first label span if no primary span is provided
             diagnostic
@@ -270,42 +529,26 @@ impl<'a> DiagnosticContext<'a> {
                 .unwrap_or(rustc_span::DUMMY_SP)
         });
 
-        let source_file = self.source_map.lookup_source_file(primary_span.lo());
-        let mut colors = ColorGenerator::new();
-        let file_id_str = format!(
-            "{}",
-            source_file
-                .name
-                .display(FileNameDisplayPreference::Local)
-                .to_string_lossy()
-        );
-
-        // Convert byte positions to character positions for ariadne
-        let source_content = match &source_file.src {
-            Some(content) => content.as_str(),
-            None => {
-                eprintln!("Error: Source file content not available");
-                return;
+        // The files drawn, by display name
+        let mut sources: Vec<(String, Arc<SourceFile>)> = Vec::new();
+        // Convert a span to the file it is in and its character range there
+        let mut locate = |span: Span| {
+            let source_file = self.source_map.lookup_source_file(span.lo());
+            let content = source_file.src.as_ref()?;
+            let name = self.file_display_name(&source_file);
+            let chars = self.char_range(&source_file, content, span);
+            if !sources.iter().any(|(file, _)| *file == name) {
+                sources.push((name.clone(), source_file.clone()));
             }
+            Some((name, chars))
         };
 
-        let byte_start = (primary_span.lo().0 - source_file.start_pos.0) as usize;
-        let byte_end = (primary_span.hi().0 - source_file.start_pos.0) as usize;
-
-        // Convert byte indices to character indices by counting UTF-8 chars
-        let char_start = source_content
-            .get(..byte_start.min(source_content.len()))
-            .map(|s| s.chars().count())
-            .unwrap_or(0);
-        let char_end = source_content
-            .get(..byte_end.min(source_content.len()))
-            .map(|s| s.chars().count())
-            .unwrap_or(char_start);
-
-        let mut report = Report::build(
-            diagnostic.level.to_ariadne_kind(),
-            (&file_id_str, char_start..char_end),
-        );
+        let Some(primary) = locate(primary_span) else {
+            eprintln!("Error: Source file content not available");
+            return Ok(());
+        };
+        let mut report =
+            Report::build(diagnostic.level.to_ariadne_kind(), primary).with_config(config);
 
         if let Some(code) = diagnostic.code {
             report = report.with_code(code);
@@ -313,34 +556,23 @@ impl<'a> DiagnosticContext<'a> {
 
         report = report.with_message(&diagnostic.message);
 
-        // Add labels - only from the same file for simplicity
+        // Add labels, from whichever file they are in
+        let mut colors = ColorGenerator::new();
         for label in &diagnostic.labels {
-            // 检查 span 是否来自同一个文件
-            let label_file = self.source_map.lookup_source_file(label.span.lo());
-            if std::ptr::eq(label_file.as_ref(), source_file.as_ref()) {
-                let color = colors.next();
-
-                let label_byte_start = (label.span.lo().0 - source_file.start_pos.0) as usize;
-                let label_byte_end = (label.span.hi().0 - source_file.start_pos.0) as usize;
-
-                // Convert byte indices to character indices for label
-                let label_char_start = source_content
-                    .get(..label_byte_start.min(source_content.len()))
-                    .map(|s| s.chars().count())
-                    .unwrap_or(0);
-                let label_char_end = source_content
-                    .get(..label_byte_end.min(source_content.len()))
-                    .map(|s| s.chars().count())
-                    .unwrap_or(label_char_start);
-
+            if let Some(span) = locate(label.span) {
                 report = report.with_label(
-                    Label::new((&file_id_str, label_char_start..label_char_end))
+                    Label::new(span)
                         .with_message(&label.message)
-                        .with_color(color),
+                        .with_color(colors.next()),
                 );
             }
         }
 
+        // Add related locations, which ariadne can't link to, as notes
+        for (span, message) in &diagnostic.related {
+            report = report.with_note(self.related_note(*span, message));
+        }
+
         // Add notes
         for note in &diagnostic.notes {
             report = report.with_note(note);
@@ -351,21 +583,66 @@ impl<'a> DiagnosticContext<'a> {
             report = report.with_help(help);
         }
 
-        // Print the report - use file_id as identifier
-        let source_content = match &source_file.src {
-            Some(content) => content.as_str(),
-            None => {
-                eprintln!("Error: Source file content not available");
-                return;
-            }
-        };
+        // Add suggestions, which ariadne can't apply, as helps
+        for suggestion in &diagnostic.suggestions {
+            report = report.with_help(self.suggestion_help(suggestion));
+        }
 
-        if let Err(e) = report
+        let sources = sources.iter().filter_map(|(name, source_file)| {
+            Some((name.clone(), source_file.src.as_ref()?.as_str()))
+        });
+        report
             .finish()
-            .print((&file_id_str, Source::from(source_content)))
-        {
-            eprintln!("Error printing diagnostic: {}", e);
-        }
+            .write_for_stdout(ariadne::sources(sources), out)
+    }
+}
+
+impl DiagnosticContext<'_> {
+    /// The characters `span` covers in `source_file`, whose text is
+    /// `content`, as ariadne counts them.
+    pub(crate) fn char_range(
+        &self,
+        source_file: &SourceFile,
+        content: &str,
+        span: Span,
+    ) -> Range<usize> {
+        let mut indices = self.char_indices.borrow_mut();
+        let index = indices
+            .entry(source_file.start_pos)
+            .or_insert_with(|| CharIndex::new(content));
+        let byte_start = (span.lo().0 - source_file.start_pos.0) as usize;
+        let byte_end = (span.hi().0 - source_file.start_pos.0) as usize;
+        let char_start = index.char_index(content, byte_start).unwrap_or(0);
+        let char_end = index.char_index(content, byte_end).unwrap_or(char_start);
+        char_start..char_end
+    }
+
+    /// `message` followed by where `span` starts, as `file:line:column`.
+    pub fn related_note(&self, span: Span, message: &str) -> String {
+        let loc = self.source_map.lookup_char_pos(span.lo());
+        format!(
+            "{} at {}:{}:{}",
+            message,
+            self.file_display_name(&loc.file),
+            loc.line,
+            loc.col.0 + 1
+        )
+    }
+
+    /// `suggestion` in words, followed by where it applies, e.g. "insert
+    /// `)` at main.fl:1:7".
+    pub fn suggestion_help(&self, suggestion: &Suggestion) -> String {
+        let Suggestion {
+            span, replacement, ..
+        } = suggestion;
+        let action = if span.lo() == span.hi() {
+            format!("insert `{}`", replacement)
+        } else if replacement.is_empty() {
+            "remove this".to_string()
+        } else {
+            format!("replace with `{}`", replacement)
+        };
+        self.related_note(*span, &action)
     }
 }
 
@@ -422,3 +699,239 @@ macro_rules! diag_help {
         $ctx.help(format!($msg, $($arg)*))
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_span::source_map::FilePathMapping;
+    use std::path::Path;
+
+    #[test]
+    fn files_below_the_project_root_are_named_relative_to_it() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let ctx = DiagnosticContext::new(&source_map).with_project_root(PathBuf::from("proj"));
+        let load = |path: PathBuf, src: &str| {
+            source_map.new_source_file(
+                FileName::Real(RealFileName::LocalPath(path)),
+                src.to_string(),
+            )
+        };
+
+        let relative = load(Path::new("proj").join("src").join("foo.fl"), "fn a() {}");
+        let absolute = load(
+            path::absolute("proj").unwrap().join("src").join("foo.fl"),
+            "fn b() {}",
+        );
+        let outside = load(PathBuf::from("other.fl"), "fn c() {}");
+
+        assert_eq!(ctx.file_display_name(&relative), "src/foo.fl");
+        assert_eq!(ctx.file_display_name(&absolute), "src/foo.fl");
+        assert_eq!(ctx.file_display_name(&outside), "other.fl");
+
+        // A report on the absolutely loaded file names it the same way.
+        let span = Span::new(
+            rustc_span::BytePos(absolute.start_pos.0 + 3),
+            rustc_span::BytePos(absolute.start_pos.0 + 4),
+        );
+        let diagnostic = DiagnosticBuilder::error("unused function `b`".to_string())
+            .with_primary_span(span)
+            .with_error_label(span, "never called".to_string())
+            .build();
+        let out = ctx.render_to_string(&diagnostic);
+        assert!(out.contains("src/foo.fl:1:4"), "{out}");
+        assert!(!out.contains("proj"), "{out}");
+    }
+
+    #[test]
+    fn finishing_drops_repeated_diagnostics_and_orders_the_rest_by_file_and_position() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let load = |name: &str, src: &str| {
+            source_map.new_source_file(
+                FileName::Real(RealFileName::LocalPath(PathBuf::from(name))),
+                src.to_string(),
+            )
+        };
+        let b = load("b.fl", "fn b() {}\n");
+        let a = load("a.fl", "fn a() {}\nfn c() {}\n");
+        let at = |file: &SourceFile, offset: u32| {
+            let pos = rustc_span::BytePos(file.start_pos.0 + offset);
+            Span::new(pos, pos)
+        };
+        let mut ctx = DiagnosticContext::new(&source_map);
+
+        for (message, span) in [
+            ("in b", Some(at(&b, 3))),
+            ("second in a", Some(at(&a, 13))),
+            ("first in a", Some(at(&a, 3))),
+            ("second in a", Some(at(&a, 13))),
+            ("nowhere", None),
+        ] {
+            let mut builder = DiagnosticBuilder::error(message.to_string()).with_code(4000);
+            if let Some(span) = span {
+                builder = builder.with_primary_span(span);
+            }
+            builder.emit(&ctx);
+        }
+        assert_eq!(ctx.error_count(), 5);
+
+        ctx.finish();
+        // SAFETY: nothing is emitted while the slice is borrowed.
+        let messages: Vec<_> = unsafe { ctx.diagnostics() }
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(messages, ["first in a", "second in a", "in b", "nowhere"]);
+        assert_eq!(ctx.error_count(), 4);
+    }
+
+    /// Emit a warning with each of `codes` under `policy`, and return the
+    /// error and warning counts and the levels reported.
+    fn warn_under(policy: DiagnosticPolicy, codes: &[u32]) -> (usize, usize, Vec<Level>) {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let ctx = DiagnosticContext::new(&source_map)
+            .with_format(DiagnosticFormat::Json)
+            .with_policy(policy);
+        for &code in codes {
+            DiagnosticBuilder::warning(format!("warning {code}"))
+                .with_code(code)
+                .emit(&ctx);
+        }
+        DiagnosticBuilder::error("an error".to_string())
+            .with_code(4005)
+            .emit(&ctx);
+        // SAFETY: nothing is emitted while the slice is borrowed.
+        let levels = unsafe { ctx.diagnostics() }
+            .iter()
+            .map(|d| d.level)
+            .collect();
+        (ctx.error_count(), ctx.warning_count(), levels)
+    }
+
+    #[test]
+    fn warnings_are_promoted_to_errors_when_denied() {
+        let (errors, warnings, levels) = warn_under(DiagnosticPolicy::default(), &[1, 2]);
+        assert_eq!((errors, warnings), (1, 2));
+        assert_eq!(levels, [Level::Warning, Level::Warning, Level::Error]);
+
+        let policy = DiagnosticPolicy {
+            warnings_as_errors: true,
+            ..Default::default()
+        };
+        let (errors, warnings, levels) = warn_under(policy, &[1, 2]);
+        assert_eq!((errors, warnings), (3, 0));
+        assert_eq!(levels, [Level::Error, Level::Error, Level::Error]);
+
+        let policy = DiagnosticPolicy {
+            deny: HashSet::from([2]),
+            ..Default::default()
+        };
+        let (errors, warnings, _) = warn_under(policy, &[1, 2]);
+        assert_eq!((errors, warnings), (2, 1));
+    }
+
+    #[test]
+    fn allowed_warnings_are_neither_counted_nor_kept() {
+        let policy = DiagnosticPolicy {
+            allow: HashSet::from([1, 4005]),
+            ..Default::default()
+        };
+        let (errors, warnings, levels) = warn_under(policy, &[1, 2]);
+        // Errors are never suppressed.
+        assert_eq!((errors, warnings), (1, 1));
+        assert_eq!(levels, [Level::Warning, Level::Error]);
+    }
+
+    #[test]
+    fn a_code_of_its_own_overrides_warnings_as_errors() {
+        let policy = DiagnosticPolicy {
+            deny: HashSet::from([3]),
+            allow: HashSet::from([1, 3]),
+            warnings_as_errors: true,
+        };
+        let (errors, warnings, levels) = warn_under(policy, &[1, 2, 3]);
+        // 1 is allowed despite `warnings_as_errors`; 3 is both, so denied.
+        assert_eq!((errors, warnings), (3, 0));
+        assert_eq!(levels, [Level::Error, Level::Error, Level::Error]);
+        assert_eq!(
+            DiagnosticPolicy::default().level_of(Level::Note, Some(1)),
+            Some(Level::Note)
+        );
+    }
+
+    #[test]
+    fn a_diagnostic_renders_with_its_label_and_note() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let ctx = DiagnosticContext::new(&source_map);
+        let file = source_map.new_source_file(
+            FileName::Real(RealFileName::LocalPath(PathBuf::from("main.fl"))),
+            "const N: Int = M;\n".to_string(),
+        );
+        let m = Span::new(
+            rustc_span::BytePos(file.start_pos.0 + 15),
+            rustc_span::BytePos(file.start_pos.0 + 16),
+        );
+
+        let diagnostic = DiagnosticBuilder::error("unresolved name `M`".to_string())
+            .with_code(4005)
+            .with_primary_span(m)
+            .with_error_label(m, "not found in this scope".to_string())
+            .with_note("values must be defined before they are used".to_string())
+            .build();
+        let rendered: String = ctx
+            .render_to_string(&diagnostic)
+            .lines()
+            .map(|line| format!("{}\n", line.trim_end()))
+            .collect();
+
+        assert_eq!(
+            rendered,
+            concat!(
+                "[4005] Error: unresolved name `M`\n",
+                "   ╭─[ main.fl:1:16 ]\n",
+                "   │\n",
+                " 1 │ const N: Int = M;\n",
+                "   │                ┬\n",
+                "   │                ╰── not found in this scope\n",
+                "   │\n",
+                "   │ Note: values must be defined before they are used\n",
+                "───╯\n",
+            )
+        );
+    }
+
+    #[test]
+    fn labels_in_other_files_are_drawn_under_their_own_file() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let ctx = DiagnosticContext::new(&source_map);
+        let load = |name: &str, src: &str| {
+            source_map.new_source_file(
+                FileName::Real(RealFileName::LocalPath(PathBuf::from(name))),
+                src.to_string(),
+            )
+        };
+        let a = load("a.fl", "fn first() {}\n");
+        let b = load("b.fl", "fn other() {}\nfn first() {}\n");
+        let span = |file: &SourceFile, lo: u32, hi: u32| {
+            Span::new(
+                rustc_span::BytePos(file.start_pos.0 + lo),
+                rustc_span::BytePos(file.start_pos.0 + hi),
+            )
+        };
+
+        let diagnostic = DiagnosticBuilder::error("duplicate definition of `first`".to_string())
+            .with_primary_span(span(&b, 17, 22))
+            .with_error_label(span(&b, 17, 22), "redefined here".to_string())
+            .with_note_label(span(&a, 3, 8), "first defined here".to_string())
+            .build();
+        let out = ctx.render_to_string(&diagnostic);
+
+        for expected in [
+            "b.fl:2:4",
+            "redefined here",
+            "a.fl:1:4",
+            "first defined here",
+        ] {
+            assert!(out.contains(expected), "{expected:?} not in\n{out}");
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Every error code the compiler emits, with a stable name and a short
+//! description, for tools such as `--explain`.
+//!
+//! Each phase numbers its errors up from a base of its own: the lexer from
+//! 1000, the parser from 2000, lowering from 3000 and name resolution from
+//! 4000. An entry's `name` is what the error's
+//! [`FlurryError::error_name`](crate::FlurryError::error_name) returns, so
+//! a new error is added here together with its code.
+//! Warnings with a code, which a
+//! [`DiagnosticPolicy`](crate::DiagnosticPolicy) can deny or allow, are
+//! numbered and registered alongside the errors of their phase.
+
+/// What is known about an error code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeInfo {
+    pub code: u32,
+    /// The error's [`error_name`](crate::FlurryError::error_name).
+    pub name: &'static str,
+    /// What the error means, in one line.
+    pub description: &'static str,
+}
+
+const fn info(code: u32, name: &'static str, description: &'static str) -> CodeInfo {
+    CodeInfo {
+        code,
+        name,
+        description,
+    }
+}
+
+/// The registered codes, in order.
+pub const CODES: &[CodeInfo] = &[
+    // Lexer
+    info(
+        1001,
+        "unterminated_string",
+        "a string literal is not closed",
+    ),
+    info(
+        1002,
+        "unterminated_char",
+        "a character literal is not closed",
+    ),
+    info(
+        1003,
+        "unterminated_comment",
+        "a block comment is not closed",
+    ),
+    info(1004, "unterminated_macro", "macro content is not closed"),
+    info(1005, "invalid_escape", "an escape sequence is not valid"),
+    info(1006, "invalid_number", "a number literal is malformed"),
+    info(1007, "unexpected_char", "a character that starts no token"),
+    info(1008, "empty_char", "a character literal holds no character"),
+    // Parser
+    info(
+        2001,
+        "unexpected_token",
+        "a token where the grammar expects another",
+    ),
+    info(
+        2002,
+        "invalid_syntax",
+        "a construct that is not valid syntax",
+    ),
+    info(
+        2003,
+        "meet_post_extended_call_start",
+        "an extended call where none can start",
+    ),
+    info(
+        2004,
+        "meet_post_id",
+        "a postfix identifier where none can follow",
+    ),
+    info(
+        2005,
+        "expected_block",
+        "a declaration is not followed by its block",
+    ),
+    info(
+        2006,
+        "unclosed_brackets",
+        "the file ends inside unclosed brackets",
+    ),
+    // Lowering
+    info(
+        3001,
+        "unsupported AST node",
+        "a construct lowering does not handle yet",
+    ),
+    info(
+        3002,
+        "malformed AST structure",
+        "an AST node is missing expected children",
+    ),
+    info(
+        3003,
+        "unsupported clause",
+        "a clause kind lowering does not handle",
+    ),
+    info(
+        3004,
+        "invalid parameter",
+        "a function parameter that cannot be lowered",
+    ),
+    info(
+        3005,
+        "invalid pattern",
+        "a pattern form that cannot be lowered",
+    ),
+    info(
+        3006,
+        "invalid item in context",
+        "an item where items are not allowed",
+    ),
+    info(
+        3007,
+        "invalid enum variant",
+        "an enum variant that cannot be lowered",
+    ),
+    info(3008, "invalid struct field", "a malformed struct field"),
+    info(
+        3009,
+        "missing identifier",
+        "an identifier is expected but missing",
+    ),
+    info(
+        3010,
+        "invalid type expression",
+        "a type expression that cannot be lowered",
+    ),
+    info(
+        3011,
+        "mismatched pattern",
+        "a pattern does not fit the scrutinee",
+    ),
+    info(
+        3012,
+        "inconsistent or-pattern bindings",
+        "the alternatives of an or-pattern bind different names",
+    ),
+    info(
+        3013,
+        "unknown field in pattern",
+        "a destructuring pattern names an unknown field",
+    ),
+    info(
+        3014,
+        "binding in `not` pattern",
+        "a binding inside a negated pattern",
+    ),
+    info(
+        3015,
+        "wrong number of arguments",
+        "a call with the wrong number of arguments",
+    ),
+    info(
+        3016,
+        "mismatched argument type",
+        "an argument does not fit the parameter type",
+    ),
+    info(
+        3017,
+        "conflicting implementation",
+        "two impls of one trait apply to the same type",
+    ),
+    info(
+        3018,
+        "receiver outside of a method",
+        "a `self` receiver on a function that is not a method",
+    ),
+    info(
+        3019,
+        "non-constant comptime argument",
+        "a comptime parameter given a value only known at run time",
+    ),
+    info(
+        3020,
+        "catch without errors",
+        "a catch arm on a value that has no error set",
+    ),
+    info(
+        3021,
+        "static assertion failed",
+        "a `static_assert` condition is false",
+    ),
+    info(
+        3022,
+        "division by zero",
+        "an integer division by a constant zero",
+    ),
+    info(3023, "recursive splice", "quoted code that splices itself"),
+    info(
+        3024,
+        "cyclic type alias",
+        "a type alias that expands to itself",
+    ),
+    info(
+        3025,
+        "invalid cast",
+        "a cast between types that cannot be converted",
+    ),
+    info(
+        3026,
+        "unknown field",
+        "a field access naming no field or method of a struct",
+    ),
+    info(
+        3027,
+        "misordered argument",
+        "a positional argument after a keyword argument",
+    ),
+    info(
+        3028,
+        "unknown parameter",
+        "a keyword argument naming no parameter of the callee",
+    ),
+    info(
+        3029,
+        "duplicate argument",
+        "a parameter given more than one argument",
+    ),
+    info(
+        3030,
+        "unresolved implicit argument",
+        "an implicit parameter with no value of its type in scope",
+    ),
+    info(
+        3031,
+        "integer literal out of range",
+        "an integer literal too large for 64 bits",
+    ),
+    // Name resolution
+    info(
+        4001,
+        "scope_creation_failed",
+        "a scope could not be created",
+    ),
+    info(
+        4002,
+        "module_not_found",
+        "a module named in a path does not exist",
+    ),
+    info(4003, "invalid_node_type", "an unexpected AST node kind"),
+    info(
+        4004,
+        "file_parsing_failed",
+        "a file could not be read or lexed",
+    ),
+    info(4005, "unresolved_name", "a name that is not in scope"),
+    info(
+        4006,
+        "cyclic_import",
+        "imports that lead back to themselves",
+    ),
+    info(
+        4007,
+        "duplicate_definition",
+        "a name defined twice in one namespace",
+    ),
+    info(
+        4008,
+        "unresolved_import_segment",
+        "a `use` path segment that does not resolve",
+    ),
+    info(
+        4009,
+        "internal_error",
+        "an internal error of name resolution",
+    ),
+    info(
+        4010,
+        "ambiguous_name",
+        "a name several glob imports supply, none taking precedence",
+    ),
+    info(
+        4011,
+        "module_file_not_found",
+        "a `mod` with neither a file nor a directory of its name",
+    ),
+    info(
+        4012,
+        "shadowed_builtin_name",
+        "an item named after a compiler builtin, which it shadows",
+    ),
+];
+
+/// The entry for `code`, if it is registered.
+pub fn lookup(code: u32) -> Option<&'static CodeInfo> {
+    CODES
+        .binary_search_by_key(&code, |info| info.code)
+        .ok()
+        .map(|index| &CODES[index])
+}
+
+/// Whether every code is registered once, with the table in order of code
+/// as [`lookup`] needs.
+pub(crate) fn codes_are_unique() -> bool {
+    CODES.windows(2).all(|pair| pair[0].code < pair[1].code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn no_two_errors_share_a_code_or_a_name() {
+        assert!(codes_are_unique());
+        let mut codes = HashSet::new();
+        let mut names = HashSet::new();
+        for info in CODES {
+            assert!(
+                codes.insert(info.code),
+                "code {} is registered twice",
+                info.code
+            );
+            assert!(
+                names.insert(info.name),
+                "name `{}` is registered twice",
+                info.name
+            );
+        }
+    }
+
+    #[test]
+    fn codes_are_looked_up_with_their_name_and_description() {
+        let info = lookup(4005).unwrap();
+        assert_eq!(info.name, "unresolved_name");
+        assert_eq!(info.description, "a name that is not in scope");
+        assert_eq!(lookup(3016).unwrap().name, "mismatched argument type");
+        assert_eq!(lookup(42), None);
+    }
+}
@@ -0,0 +1,129 @@
+//! Helpers for asserting on the diagnostics a pass emitted.
+//!
+//! Every diagnostic given to a [`DiagnosticContext`] is kept after it has
+//! been printed, so a test can run a pass and then check what was reported:
+//!
+//! ```ignore
+//! lower(src, &diag_ctx);
+//! diagnostic::testing::assert_diagnostic(&diag_ctx, 2013, "z");
+//! ```
+
+use crate::{Diagnostic, DiagnosticContext, FlurryError, registry};
+
+/// The source text covered by the primary span of `diagnostic`, if any.
+pub fn primary_span_text(ctx: &DiagnosticContext<'_>, diagnostic: &Diagnostic) -> Option<String> {
+    let span = diagnostic.primary_span?;
+    ctx.source_map.span_to_snippet(span).ok()
+}
+
+/// Assert that `ctx` collected a diagnostic with error code `code` whose
+/// primary span covers exactly `expected_span_text`.
+///
+/// On failure the panic message lists every collected diagnostic with its
+/// code and span text, so a wrong span is as easy to spot as a missing code.
+#[track_caller]
+pub fn assert_diagnostic(ctx: &DiagnosticContext<'_>, code: u32, expected_span_text: &str) {
+    let diagnostics = ctx.emitted_diagnostics.borrow();
+    let found = diagnostics.iter().any(|d| {
+        d.code == Some(code)
+            && primary_span_text(ctx, d).as_deref() == Some(expected_span_text)
+    });
+    if found {
+        return;
+    }
+
+    let collected: Vec<String> = diagnostics
+        .iter()
+        .map(|d| {
+            format!(
+                "  [{}] {:?} at {:?}",
+                d.code.map_or_else(|| "-".to_string(), |c| c.to_string()),
+                d.message,
+                primary_span_text(ctx, d).unwrap_or_default(),
+            )
+        })
+        .collect();
+    panic!(
+        "expected a diagnostic [{}] at {:?}, collected:\n{}",
+        code,
+        expected_span_text,
+        if collected.is_empty() {
+            "  (none)".to_string()
+        } else {
+            collected.join("\n")
+        }
+    );
+}
+
+/// Assert that the code of `error` is in the [`registry`] under the name
+/// the error gives itself.
+#[track_caller]
+pub fn assert_registered(error: &impl FlurryError) {
+    let code = error.error_code();
+    let info = registry::lookup(code).unwrap_or_else(|| {
+        panic!(
+            "code {} of `{}` is not registered",
+            code,
+            error.error_name()
+        )
+    });
+    assert_eq!(
+        info.name,
+        error.error_name(),
+        "code {} is registered under another name",
+        code
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DiagnosticBuilder;
+    use rustc_span::{BytePos, FileName, SourceMap, Span, source_map::FilePathMapping};
+
+    fn with_context(src: &str, f: impl FnOnce(&DiagnosticContext<'_>, BytePos)) {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(FileName::Custom("test.fl".into()), src.to_string());
+        let ctx = DiagnosticContext::new(&source_map);
+        f(&ctx, file.start_pos);
+    }
+
+    fn span(base: BytePos, lo: u32, hi: u32) -> Span {
+        Span::new(BytePos(base.0 + lo), BytePos(base.0 + hi))
+    }
+
+    #[test]
+    fn finds_diagnostic_by_code_and_span_text() {
+        with_context("let x = y;", |ctx, base| {
+            DiagnosticBuilder::error("unresolved name".to_string())
+                .with_code(2001)
+                .with_primary_span(span(base, 8, 9))
+                .emit(ctx);
+            assert_diagnostic(ctx, 2001, "y");
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a diagnostic [2001]")]
+    fn reports_missing_diagnostic() {
+        with_context("let x = y;", |ctx, base| {
+            DiagnosticBuilder::error("something else".to_string())
+                .with_code(2002)
+                .with_primary_span(span(base, 8, 9))
+                .emit(ctx);
+            assert_diagnostic(ctx, 2001, "y");
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "expected a diagnostic [2001] at \"x\"")]
+    fn reports_diagnostic_at_wrong_span() {
+        with_context("let x = y;", |ctx, base| {
+            DiagnosticBuilder::error("unresolved name".to_string())
+                .with_code(2001)
+                .with_primary_span(span(base, 8, 9))
+                .emit(ctx);
+            assert_diagnostic(ctx, 2001, "x");
+        });
+    }
+}
@@ -45,6 +45,7 @@ pub struct HirArena {
     ty_params: TypedArena<TyParam<'static>>,
     fn_params: TypedArena<FnSigParam<'static>>,
     cond_arms: TypedArena<CondictionArm<'static>>,
+    idents: TypedArena<Ident>,
 }
 
 impl Default for HirArena {
@@ -75,6 +76,7 @@ impl HirArena {
             ty_params: TypedArena::new(),
             fn_params: TypedArena::new(),
             cond_arms: TypedArena::new(),
+            idents: TypedArena::new(),
         }
     }
 }
@@ -165,4 +167,8 @@ impl HirArena {
         CondictionArm
     );
 
+    /// Allocate a contiguous slice of identifiers.
+    pub fn alloc_ident_slice(&self, idents: impl IntoIterator<Item = Ident>) -> &[Ident] {
+        self.idents.alloc_from_iter_reg(idents)
+    }
 }
@@ -7,6 +7,7 @@
 use rustc_span::Span;
 
 use crate::Ident;
+use crate::common::{TPARAM_CATCH, TPARAM_COMPTIME, TPARAM_IMPLICIT};
 pub use crate::hir_id::BodyId;
 use crate::hir_id::HirId;
 
@@ -21,5 +22,22 @@ pub struct Param<'hir> {
     pub hir_id: HirId,
     pub name: Ident,
     pub ty: Option<&'hir super::expr::Expr<'hir>>,
+    /// Attribute expressions written before the parameter (`^noalias x`),
+    /// outermost first.
+    pub attrs: &'hir [super::expr::Expr<'hir>],
+    /// `TPARAM_*` flags of the wrappers around the parameter.
+    pub flags: u32,
     pub span: Span,
 }
+
+impl Param<'_> {
+    pub fn is_comptime(&self) -> bool {
+        self.flags & TPARAM_COMPTIME != 0
+    }
+    pub fn is_implicit(&self) -> bool {
+        self.flags & TPARAM_IMPLICIT != 0
+    }
+    pub fn is_catch(&self) -> bool {
+        self.flags & TPARAM_CATCH != 0
+    }
+}
@@ -209,6 +209,7 @@ pub const TPARAM_QUOTE: u32 = 1 << 2;
 pub const TPARAM_ERROR: u32 = 1 << 3;
 pub const TPARAM_LAMBDA: u32 = 1 << 4;
 pub const TPARAM_ASSOC: u32 = 1 << 5;
+pub const TPARAM_CATCH: u32 = 1 << 6;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TyParam<'hir> {
@@ -237,6 +238,9 @@ impl TyParam<'_> {
     pub fn is_assoc(&self) -> bool {
         self.flags & TPARAM_ASSOC != 0
     }
+    pub fn is_catch(&self) -> bool {
+        self.flags & TPARAM_CATCH != 0
+    }
 }
 
 impl<'hir> TyParam<'hir> {
@@ -273,10 +277,25 @@ impl<'hir> TyParam<'hir> {
         self.flags |= TPARAM_ASSOC;
         self
     }
+    pub fn with_catch(mut self) -> Self {
+        self.flags |= TPARAM_CATCH;
+        self
+    }
     pub fn with_flags(mut self, flags: u32) -> Self {
         self.flags |= flags;
         self
     }
+
+    /// The type and default expressions of the parameter.
+    pub fn exprs(&self) -> Vec<&'hir Expr<'hir>> {
+        match &self.kind {
+            TyParamKind::PositionalDependencyCatched(_, ty)
+            | TyParamKind::Positional(ty)
+            | TyParamKind::Varadic(_, ty) => vec![*ty],
+            TyParamKind::Optional(_, ty, default) => vec![*ty, *default],
+            TyParamKind::Itself { .. } => Vec::new(),
+        }
+    }
 }
 
 /// Type alias for one entry in `FnSig::params` — lets the arena macro work
@@ -0,0 +1,234 @@
+//! Compile-time evaluation of expressions.
+//!
+//! [`Package::eval_const`] is the one evaluator: it folds literals, `const`
+//! items and operators over them to a [`ConstValue`], and tells the
+//! expressions known at compile time without a value here, such as a
+//! `comptime` parameter, apart from those only known at run time.
+//! [`Package::is_const`] is the predicate over it.
+
+use crate::common::{Arg, BinOp, DefId, LitKind, UnOp};
+use crate::expr::{Expr, ExprKind};
+use crate::item::ItemKind;
+use crate::{Package, Symbol, ValueRes};
+
+/// The value of an expression evaluated at compile time.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    Str(String),
+}
+
+impl ConstValue {
+    /// The kind of value, for messages: "an integer", "a bool", ...
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            ConstValue::Int(_) => "an integer",
+            ConstValue::Float(_) => "a float",
+            ConstValue::Bool(_) => "a bool",
+            ConstValue::Char(_) => "a char",
+            ConstValue::Str(_) => "a string",
+        }
+    }
+
+    /// The literal spelling out this value.
+    pub fn into_lit_kind(self) -> LitKind {
+        match self {
+            ConstValue::Int(n) => LitKind::Integer(n),
+            ConstValue::Float(x) => LitKind::Float(x),
+            ConstValue::Bool(b) => LitKind::Bool(b),
+            ConstValue::Char(c) => LitKind::Char(c),
+            ConstValue::Str(s) => LitKind::String(s),
+        }
+    }
+}
+
+/// What is known about an expression at compile time.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constness {
+    /// Known at compile time, with this value.
+    Value(ConstValue),
+    /// Known at compile time, but without a value here: it depends on a
+    /// `comptime` parameter or call, or the operation has no value
+    /// (overflow, division by zero, mismatched operands).
+    Unknown,
+    /// Only known at run time.
+    Runtime,
+}
+
+impl Constness {
+    /// The value, where it is known.
+    pub fn value(self) -> Option<ConstValue> {
+        match self {
+            Constness::Value(value) => Some(value),
+            Constness::Unknown | Constness::Runtime => None,
+        }
+    }
+}
+
+impl<'hir> Package<'hir> {
+    /// Evaluate `expr` at compile time: literals, names of `const` items and
+    /// `comptime` locals, operators over constants, and calls of `comptime`
+    /// functions whose arguments are all constants.
+    ///
+    /// `res` tells what each name used as a value refers to where `expr` is
+    /// written, so a local shadowing a `const` item is not taken for it.
+    pub fn eval_const(&self, expr: &Expr<'hir>, res: &dyn Fn(Symbol) -> ValueRes) -> Constness {
+        self.eval_const_in(expr, res, &mut Vec::new())
+    }
+
+    /// Whether `expr` is known at compile time; see [`Self::eval_const`].
+    pub fn is_const(&self, expr: &Expr<'hir>, res: &dyn Fn(Symbol) -> ValueRes) -> bool {
+        self.eval_const(expr, res) != Constness::Runtime
+    }
+
+    /// [`Self::eval_const`], with the `const` items whose values are being
+    /// evaluated, so one defined in terms of itself has no value.
+    fn eval_const_in(
+        &self,
+        expr: &Expr<'hir>,
+        res: &dyn Fn(Symbol) -> ValueRes,
+        evaluating: &mut Vec<DefId>,
+    ) -> Constness {
+        match &expr.kind {
+            ExprKind::Lit(lit) => match &lit.kind {
+                LitKind::Integer(n) => Constness::Value(ConstValue::Int(*n)),
+                LitKind::Float(x) => Constness::Value(ConstValue::Float(*x)),
+                LitKind::Bool(b) => Constness::Value(ConstValue::Bool(*b)),
+                LitKind::Char(c) => Constness::Value(ConstValue::Char(*c)),
+                LitKind::String(s) => Constness::Value(ConstValue::Str(s.clone())),
+                LitKind::Symbol(_) => Constness::Unknown,
+            },
+            ExprKind::Unary(op, operand) => match self.eval_const_in(operand, res, evaluating) {
+                Constness::Value(value) => {
+                    eval_unary(*op, value).map_or(Constness::Unknown, Constness::Value)
+                }
+                other => other,
+            },
+            ExprKind::Binary(op, lhs, rhs) => {
+                let lhs = self.eval_const_in(lhs, res, evaluating);
+                let rhs = self.eval_const_in(rhs, res, evaluating);
+                match (lhs, rhs) {
+                    (Constness::Value(a), Constness::Value(b)) => {
+                        eval_binary(*op, a, b).map_or(Constness::Unknown, Constness::Value)
+                    }
+                    (Constness::Runtime, _) | (_, Constness::Runtime) => Constness::Runtime,
+                    _ => Constness::Unknown,
+                }
+            }
+            ExprKind::Ident(name) => match res(*name) {
+                ValueRes::Local { comptime: true } => Constness::Unknown,
+                ValueRes::Def(def) => {
+                    let Some(ItemKind::Const(_, value)) = self.def_item(def).map(|item| &item.kind)
+                    else {
+                        return Constness::Runtime;
+                    };
+                    if evaluating.contains(&def) {
+                        return Constness::Unknown;
+                    }
+                    evaluating.push(def);
+                    let value = self.eval_const_in(value, res, evaluating);
+                    evaluating.pop();
+                    match value {
+                        Constness::Value(value) => Constness::Value(value),
+                        Constness::Unknown | Constness::Runtime => Constness::Unknown,
+                    }
+                }
+                ValueRes::Local { comptime: false } | ValueRes::Unresolved => Constness::Runtime,
+            },
+            ExprKind::Application(callee, args) => {
+                let ExprKind::Ident(name) = callee.kind else {
+                    return Constness::Runtime;
+                };
+                let ValueRes::Def(def) = res(name) else {
+                    return Constness::Runtime;
+                };
+                let comptime_fn = self.def_item(def).is_some_and(|item| match &item.kind {
+                    ItemKind::Fn(sig, _) => sig.modifiers.is_comptime,
+                    _ => false,
+                });
+                let const_args = args.iter().all(|arg| match arg {
+                    Arg::Positional(arg) | Arg::Named(_, arg) => {
+                        self.eval_const_in(arg, res, evaluating) != Constness::Runtime
+                    }
+                    _ => false,
+                });
+                if comptime_fn && const_args {
+                    Constness::Unknown
+                } else {
+                    Constness::Runtime
+                }
+            }
+            _ => Constness::Runtime,
+        }
+    }
+}
+
+/// Apply the unary `op` to `operand`; `None` when it has no value, as for
+/// an overflowing negation or an operand of the wrong kind.
+pub fn eval_unary(op: UnOp, operand: ConstValue) -> Option<ConstValue> {
+    match (op, operand) {
+        (UnOp::Neg, ConstValue::Int(n)) => n.checked_neg().map(ConstValue::Int),
+        (UnOp::Neg, ConstValue::Float(x)) => Some(ConstValue::Float(-x)),
+        (UnOp::Not, ConstValue::Bool(b)) => Some(ConstValue::Bool(!b)),
+        _ => None,
+    }
+}
+
+fn eval_binary(op: BinOp, lhs: ConstValue, rhs: ConstValue) -> Option<ConstValue> {
+    use ConstValue::*;
+    Some(match (lhs, rhs) {
+        (Int(a), Int(b)) => match op {
+            BinOp::Add => Int(a.checked_add(b)?),
+            BinOp::Sub => Int(a.checked_sub(b)?),
+            BinOp::Mul => Int(a.checked_mul(b)?),
+            BinOp::Div => Int(a.checked_div(b)?),
+            BinOp::Rem => Int(a.checked_rem(b)?),
+            BinOp::Eq => Bool(a == b),
+            BinOp::Ne => Bool(a != b),
+            BinOp::Lt => Bool(a < b),
+            BinOp::Gt => Bool(a > b),
+            BinOp::Le => Bool(a <= b),
+            BinOp::Ge => Bool(a >= b),
+            BinOp::And | BinOp::Or => return None,
+        },
+        (Float(a), Float(b)) => match op {
+            BinOp::Add => Float(a + b),
+            BinOp::Sub => Float(a - b),
+            BinOp::Mul => Float(a * b),
+            BinOp::Div => Float(a / b),
+            BinOp::Rem => Float(a % b),
+            BinOp::Eq => Bool(a == b),
+            BinOp::Ne => Bool(a != b),
+            BinOp::Lt => Bool(a < b),
+            BinOp::Gt => Bool(a > b),
+            BinOp::Le => Bool(a <= b),
+            BinOp::Ge => Bool(a >= b),
+            BinOp::And | BinOp::Or => return None,
+        },
+        (Bool(a), Bool(b)) => match op {
+            BinOp::And => Bool(a && b),
+            BinOp::Or => Bool(a || b),
+            BinOp::Eq => Bool(a == b),
+            BinOp::Ne => Bool(a != b),
+            _ => return None,
+        },
+        (Char(a), Char(b)) => compare(op, a, b)?,
+        (Str(a), Str(b)) => compare(op, a, b)?,
+        _ => return None,
+    })
+}
+
+fn compare<T: PartialOrd>(op: BinOp, a: T, b: T) -> Option<ConstValue> {
+    Some(ConstValue::Bool(match op {
+        BinOp::Eq => a == b,
+        BinOp::Ne => a != b,
+        BinOp::Lt => a < b,
+        BinOp::Gt => a > b,
+        BinOp::Le => a <= b,
+        BinOp::Ge => a >= b,
+        _ => return None,
+    }))
+}
@@ -0,0 +1,485 @@
+//! Structured s-expression dumps of HIR trees, for debugging lowering.
+//!
+//! Where [`Package::dump_to_lisp`] lists the package's items, these print a
+//! whole tree in the style of the AST dumper: every node is
+//! `(Kind children..)`, named after its HIR variant. Nodes the package
+//! stores out of line are followed through it, so a function or lambda is
+//! printed with its body, and an inline item with its definition.
+//!
+//! ```text
+//! 1 + 2 * 3   =>   (Binary + (Int 1) (Binary * (Int 2) (Int 3)))
+//! ```
+
+use crate::Package;
+use crate::body::BodyId;
+use crate::common::{Arg, BindingMode, Ident, LitKind, Path, Symbol, TyParam, TyParamKind};
+use crate::expr::{Block, ClosureParam, Expr, ExprKind};
+use crate::hir_id::OwnerId;
+use crate::item::{FieldDef, ItemKind, UseKind, Variant, VariantKind};
+use crate::pattern::{BoundType, FieldPat, Pattern, PatternArm, PatternKind};
+
+/// `(kind parts..)`.
+fn sexp(kind: &str, parts: impl IntoIterator<Item = String>) -> String {
+    let mut out = format!("({}", kind);
+    for part in parts {
+        out.push(' ');
+        out.push_str(&part);
+    }
+    out.push(')');
+    out
+}
+
+impl<'hir> Package<'hir> {
+    /// Dump the expression `expr` as an s-expression.
+    pub fn dump_expr(&self, expr: &Expr<'_>) -> String {
+        let dump = |e: &Expr<'_>| self.dump_expr(e);
+        let dump_opt = |e: Option<&Expr<'_>>| e.map(|e| self.dump_expr(e));
+        let dump_all = |es: &[Expr<'_>]| es.iter().map(|e| self.dump_expr(e)).collect::<Vec<_>>();
+        match &expr.kind {
+            ExprKind::Lit(lit) => match &lit.kind {
+                LitKind::Integer(n) => sexp("Int", [n.to_string()]),
+                LitKind::Float(x) => sexp("Float", [x.to_string()]),
+                LitKind::String(s) => sexp("Str", [format!("{:?}", s)]),
+                LitKind::Bool(b) => sexp("Bool", [b.to_string()]),
+                LitKind::Char(c) => sexp("Char", [format!("{:?}", c)]),
+                LitKind::Symbol(s) => sexp("Symbol", [s.to_string()]),
+            },
+            ExprKind::Path(path) => self.dump_path(path),
+            ExprKind::Ident(name) => sexp("Id", [name.to_string()]),
+            ExprKind::SelfValue => "(Self)".to_string(),
+            ExprKind::Index(a, b) => sexp("Index", [dump(a), dump(b)]),
+            ExprKind::Application(callee, args) => self.dump_application("App", callee, args),
+            ExprKind::ExtendedApplication(callee, args) => {
+                self.dump_application("ExtendedApp", callee, args)
+            }
+            ExprKind::NFApplication(callee, args) => self.dump_application("NFApp", callee, args),
+            ExprKind::Binary(op, a, b) => sexp("Binary", [op.to_string(), dump(a), dump(b)]),
+            ExprKind::Unary(op, e) => sexp("Unary", [op.to_string(), dump(e)]),
+            ExprKind::If(cond, then, els) => sexp(
+                "If",
+                [dump(cond), self.dump_block(then)]
+                    .into_iter()
+                    .chain(dump_opt(*els)),
+            ),
+            ExprKind::When(arms) => sexp(
+                "When",
+                arms.iter()
+                    .map(|arm| sexp("Arm", [dump(arm.cond), dump(arm.body)])),
+            ),
+            ExprKind::Block(block) => self.dump_block(block),
+            ExprKind::Loop(block) => sexp("Loop", [self.dump_block(block)]),
+            ExprKind::Match(scrutinee, arms) => sexp(
+                "Match",
+                std::iter::once(dump(scrutinee)).chain(self.dump_arms(arms)),
+            ),
+            ExprKind::ErrorElimination { expr, arms, catch } => sexp(
+                "ErrorElimination",
+                std::iter::once(dump(expr))
+                    .chain(catch.map(|(param, body)| {
+                        sexp(
+                            "Catch",
+                            [param.name.to_string()]
+                                .into_iter()
+                                .chain(dump_opt(param.ty))
+                                .chain([dump(body)]),
+                        )
+                    }))
+                    .chain(self.dump_arms(arms)),
+            ),
+            ExprKind::Assign(a, b) => sexp("Assign", [dump(a), dump(b)]),
+            ExprKind::AssignOp(op, a, b) => sexp("AssignOp", [op.to_string(), dump(a), dump(b)]),
+            ExprKind::Return(e) => sexp("Return", dump_opt(*e)),
+            ExprKind::Resume(e) => sexp("Resume", dump_opt(*e)),
+            ExprKind::Break(label) => sexp("Break", label_of(label)),
+            ExprKind::Continue(label) => sexp("Continue", label_of(label)),
+            ExprKind::Projection(e, field) => sexp("Projection", [dump(e), field.to_string()]),
+            ExprKind::Tuple(elems) => sexp("Tuple", dump_all(elems)),
+            ExprKind::List(elems) => sexp("List", dump_all(elems)),
+            ExprKind::Object(elems, fields) => sexp(
+                "Object",
+                dump_all(elems).into_iter().chain(
+                    fields
+                        .iter()
+                        .map(|field| sexp("Field", [field.ident.to_string(), dump(field.expr)])),
+                ),
+            ),
+            ExprKind::Ref(e) => sexp("Ref", [dump(e)]),
+            ExprKind::Deref(e) => sexp("Deref", [dump(e)]),
+            ExprKind::ErrorNew(e) => sexp("ErrorNew", [dump(e)]),
+            ExprKind::Lambda {
+                params,
+                body,
+                captures,
+                ret,
+                ..
+            } => {
+                let mut parts = vec![self.dump_closure_params(params)];
+                if !captures.is_empty() {
+                    parts.push(sexp("captures", captures.iter().map(Ident::to_string)));
+                }
+                parts.extend(ret.map(|ret| sexp("ret", [dump(ret)])));
+                parts.push(self.dump_body(*body));
+                sexp("Lambda", parts)
+            }
+            ExprKind::Cast(e, ty) => sexp("Cast", [dump(e), dump(ty)]),
+            ExprKind::Quote => "(Quote)".to_string(),
+            ExprKind::Splice(block) => sexp("Splice", [self.dump_block(block)]),
+            ExprKind::Let(decl) => sexp(
+                "Let",
+                [decl.name.to_string()]
+                    .into_iter()
+                    .chain(decl.ty.map(|ty| sexp("ty", [dump(ty)])))
+                    .chain(dump_opt(decl.init)),
+            ),
+            ExprKind::Semi(e) => sexp("Semi", [dump(e)]),
+            ExprKind::Item(owner_id) => self.dump_item(*owner_id),
+            ExprKind::Undefined => "(Undefined)".to_string(),
+            ExprKind::Null => "(Null)".to_string(),
+            ExprKind::Unit => "(Unit)".to_string(),
+            ExprKind::InlineIf {
+                cond,
+                then_expr,
+                else_expr,
+            } => sexp(
+                "InlineIf",
+                [dump(cond), dump(then_expr)]
+                    .into_iter()
+                    .chain(dump_opt(*else_expr)),
+            ),
+            ExprKind::InlineMatch(arms) => sexp("InlineMatch", self.dump_arms(arms)),
+            ExprKind::InlineFor {
+                label,
+                pat,
+                iter,
+                body,
+            } => sexp(
+                "InlineFor",
+                label.iter().map(Ident::to_string).chain([
+                    self.dump_pattern(pat),
+                    dump(iter),
+                    dump(body),
+                ]),
+            ),
+            ExprKind::TyPtr(e) => sexp("TyPtr", [dump(e)]),
+            ExprKind::TyOptional(e) => sexp("TyOptional", [dump(e)]),
+            ExprKind::TyFn { params, ret, .. } => sexp(
+                "TyFn",
+                [self.dump_ty_params(params)]
+                    .into_iter()
+                    .chain(ret.map(|ret| sexp("ret", [dump(ret)]))),
+            ),
+            ExprKind::TyNFFn(params) => sexp("TyNFFn", [self.dump_ty_params(params)]),
+            ExprKind::TyForall { params, body } => {
+                sexp("TyForall", [self.dump_ty_params(params), dump(body)])
+            }
+            ExprKind::TyClosureQualified(env, ty) => {
+                sexp("TyClosureQualified", [dump(env), dump(ty)])
+            }
+            ExprKind::ErrorQualifiedType(errors, ty) => {
+                sexp("ErrorQualifiedType", [dump(errors), dump(ty)])
+            }
+            ExprKind::EffectQualifiedType(effects, ty) => {
+                sexp("EffectQualifiedType", [dump(effects), dump(ty)])
+            }
+            ExprKind::TyEffectRow(effects) => sexp("TyEffectRow", dump_all(effects)),
+            ExprKind::Forall { params, body } => {
+                sexp("Forall", [self.dump_closure_params(params), dump(body)])
+            }
+            ExprKind::Exists { params, body } => {
+                sexp("Exists", [self.dump_closure_params(params), dump(body)])
+            }
+            // Leaves and nodes whose parts are not stored yet.
+            kind => format!("({:?})", kind),
+        }
+    }
+
+    /// Dump the pattern `pat` as an s-expression.
+    pub fn dump_pattern(&self, pat: &Pattern<'_>) -> String {
+        let dump = |p: &Pattern<'_>| self.dump_pattern(p);
+        let dump_all =
+            |ps: &[Pattern<'_>]| ps.iter().map(|p| self.dump_pattern(p)).collect::<Vec<_>>();
+        match &pat.kind {
+            PatternKind::Wild => "(Wild)".to_string(),
+            PatternKind::Projection(base, field) => {
+                sexp("Projection", [dump(base), field.to_string()])
+            }
+            PatternKind::Binding(mode, ident, sub) => sexp(
+                "Binding",
+                (*mode == BindingMode::ByRef)
+                    .then(|| "ref".to_string())
+                    .into_iter()
+                    .chain([ident.to_string()])
+                    .chain(sub.map(dump)),
+            ),
+            PatternKind::Const(e) => sexp("Const", [self.dump_expr(e)]),
+            PatternKind::Comptime(e) => sexp("Comptime", [self.dump_expr(e)]),
+            PatternKind::Type(ty) => sexp("Type", [self.dump_expr(ty)]),
+            PatternKind::Tuple(pats) => sexp("Tuple", dump_all(pats)),
+            PatternKind::Struct(path, fields, rest) => sexp(
+                "Struct",
+                std::iter::once(dump(path))
+                    .chain(self.dump_field_pats(fields))
+                    .chain(rest.then(|| "..".to_string())),
+            ),
+            PatternKind::List(pats, rest) => sexp(
+                "List",
+                dump_all(pats)
+                    .into_iter()
+                    .chain(rest.map(|rest| sexp("Rest", [dump(rest)]))),
+            ),
+            PatternKind::AppTuple(path, args) => sexp(
+                "AppTuple",
+                std::iter::once(dump(path)).chain(dump_all(args)),
+            ),
+            PatternKind::AppStruct(path, fields) => sexp(
+                "AppStruct",
+                std::iter::once(dump(path)).chain(self.dump_field_pats(fields)),
+            ),
+            PatternKind::OptionSome(inner) => sexp("OptionSome", [dump(inner)]),
+            PatternKind::OptionNull => "(OptionNull)".to_string(),
+            PatternKind::ErrorOk(inner) => sexp("ErrorOk", [dump(inner)]),
+            PatternKind::ErrorErr(inner) => sexp("ErrorErr", [dump(inner)]),
+            PatternKind::Or(alts) => sexp("Or", dump_all(alts)),
+            PatternKind::Not(inner) => sexp("Not", [dump(inner)]),
+            PatternKind::Ref(inner) => sexp("Ref", [dump(inner)]),
+            PatternKind::Range(lo, hi, bound) => {
+                let bound = match bound {
+                    BoundType::Inclusive => "..=",
+                    BoundType::Exclusive => "..",
+                };
+                let end = |e: Option<&Expr<'_>>| e.map_or("_".to_string(), |e| self.dump_expr(e));
+                sexp("Range", [end(*lo), bound.to_string(), end(*hi)])
+            }
+            PatternKind::Async(inner) => sexp("Async", [dump(inner)]),
+            PatternKind::BitVec => "(BitVec)".to_string(),
+            PatternKind::Invalid => "(Invalid)".to_string(),
+        }
+    }
+
+    /// Dump the item `owner_id` as an s-expression, with the bodies of its
+    /// functions and the items nested in it. Clauses are left out.
+    pub fn dump_item(&self, owner_id: OwnerId) -> String {
+        let Some(item) = self.item(owner_id) else {
+            return format!("(<missing item {}>)", owner_id.def_id.raw());
+        };
+        let name = item.ident.to_string();
+        let items = |owners: &[OwnerId]| {
+            owners
+                .iter()
+                .map(|&id| self.dump_item(id))
+                .collect::<Vec<_>>()
+        };
+        match &item.kind {
+            ItemKind::Fn(sig, body_id) => {
+                let params = sig
+                    .params
+                    .iter()
+                    .map(|(ident, param)| sexp(&ident.to_string(), [self.dump_ty_param(param)]));
+                sexp(
+                    "Fn",
+                    [name, sexp("params", params)]
+                        .into_iter()
+                        .chain(sig.return_ty.map(|ty| sexp("ret", [self.dump_expr(ty)])))
+                        .chain([self.dump_body(*body_id)]),
+                )
+            }
+            ItemKind::Struct(def) => sexp(
+                "Struct",
+                std::iter::once(name)
+                    .chain(self.dump_field_defs(def.fields))
+                    .chain(items(&def.nested_items)),
+            ),
+            ItemKind::Enum(def) => sexp(
+                "Enum",
+                std::iter::once(name)
+                    .chain(def.variants.iter().map(|v| self.dump_variant(v)))
+                    .chain(items(&def.nested_items)),
+            ),
+            ItemKind::Mod(def) => sexp("Mod", std::iter::once(name).chain(items(&def.items))),
+            ItemKind::Impl(def) => sexp(
+                "Impl",
+                std::iter::once(self.dump_expr(def.self_ty))
+                    .chain(def.trait_ref.map(|t| sexp("for", [self.dump_expr(t)])))
+                    .chain(items(&def.items)),
+            ),
+            ItemKind::Trait(def) => sexp("Trait", std::iter::once(name).chain(items(&def.items))),
+            ItemKind::TypeAlias(ty) => sexp("TypeAlias", [name, self.dump_expr(ty)]),
+            ItemKind::Newtype(ty) => sexp("Newtype", [name, self.dump_expr(ty)]),
+            ItemKind::Use(use_path) => {
+                let kind = match &use_path.kind {
+                    UseKind::Simple => None,
+                    UseKind::Glob => Some("*".to_string()),
+                    UseKind::Multi(names) => {
+                        Some(sexp("names", names.iter().map(Ident::to_string)))
+                    }
+                    UseKind::Alias(alias) => Some(sexp("as", [alias.to_string()])),
+                };
+                sexp(
+                    "Use",
+                    std::iter::once(use_path.path.to_string()).chain(kind),
+                )
+            }
+            ItemKind::Const(ty, value) => {
+                sexp("Const", [name, self.dump_expr(ty), self.dump_expr(value)])
+            }
+            ItemKind::Invalid => sexp("Invalid", [name]),
+        }
+    }
+
+    /// `(body params.. value)`, looked up in the package.
+    fn dump_body(&self, body_id: BodyId) -> String {
+        let Some(body) = self.body(body_id) else {
+            return "(body <missing>)".to_string();
+        };
+        let params = body.params.iter().map(|param| match param.ty {
+            Some(ty) => sexp("param", [param.name.to_string(), self.dump_expr(ty)]),
+            None => sexp("param", [param.name.to_string()]),
+        });
+        sexp("body", params.chain([self.dump_expr(body.value)]))
+    }
+
+    fn dump_block(&self, block: &Block<'_>) -> String {
+        sexp(
+            "Block",
+            block
+                .stmts
+                .iter()
+                .chain(block.expr)
+                .map(|e| self.dump_expr(e)),
+        )
+    }
+
+    fn dump_path(&self, path: &Path<'_>) -> String {
+        let args = path
+            .segments
+            .iter()
+            .filter(|segment| !segment.args.is_empty())
+            .map(|segment| {
+                sexp(
+                    &segment.ident.to_string(),
+                    segment.args.iter().map(|arg| self.dump_arg(arg)),
+                )
+            });
+        sexp("Path", std::iter::once(path.to_string()).chain(args))
+    }
+
+    fn dump_application(&self, kind: &str, callee: &Expr<'_>, args: &[Arg<'_>]) -> String {
+        sexp(
+            kind,
+            std::iter::once(self.dump_expr(callee))
+                .chain(args.iter().map(|arg| self.dump_arg(arg))),
+        )
+    }
+
+    fn dump_arg(&self, arg: &Arg<'_>) -> String {
+        match arg {
+            Arg::Positional(e) => self.dump_expr(e),
+            Arg::Named(name, e) => sexp("Named", [name.to_string(), self.dump_expr(e)]),
+            Arg::Expand(e) => sexp("Expand", [self.dump_expr(e)]),
+            Arg::Implicit(e) => sexp("Implicit", [self.dump_expr(e)]),
+        }
+    }
+
+    fn dump_arms(&self, arms: &[PatternArm<'_>]) -> Vec<String> {
+        arms.iter()
+            .map(|arm| {
+                sexp(
+                    "Arm",
+                    std::iter::once(self.dump_pattern(&arm.pat))
+                        .chain(arm.guard.map(|guard| sexp("if", [self.dump_expr(guard)])))
+                        .chain([self.dump_expr(arm.body)]),
+                )
+            })
+            .collect()
+    }
+
+    fn dump_closure_params(&self, params: &[ClosureParam<'_>]) -> String {
+        sexp(
+            "params",
+            params.iter().map(|param| {
+                sexp(
+                    "param",
+                    std::iter::once(self.dump_pattern(&param.pat))
+                        .chain(param.ty.map(|ty| self.dump_expr(ty))),
+                )
+            }),
+        )
+    }
+
+    fn dump_ty_params(&self, params: &[TyParam<'_>]) -> String {
+        sexp(
+            "params",
+            params.iter().map(|param| self.dump_ty_param(param)),
+        )
+    }
+
+    fn dump_ty_param(&self, param: &TyParam<'_>) -> String {
+        match &param.kind {
+            TyParamKind::PositionalDependencyCatched(ident, ty) => {
+                sexp("Dependent", [ident.to_string(), self.dump_expr(ty)])
+            }
+            TyParamKind::Positional(ty) => self.dump_expr(ty),
+            TyParamKind::Optional(ident, ty, default) => sexp(
+                "Optional",
+                [
+                    ident.to_string(),
+                    self.dump_expr(ty),
+                    self.dump_expr(default),
+                ],
+            ),
+            TyParamKind::Varadic(ident, ty) => {
+                sexp("Varadic", [ident.to_string(), self.dump_expr(ty)])
+            }
+            TyParamKind::Itself { is_ref: false } => "(Itself)".to_string(),
+            TyParamKind::Itself { is_ref: true } => "(RefItself)".to_string(),
+        }
+    }
+
+    fn dump_field_pats(&self, fields: &[FieldPat<'_>]) -> Vec<String> {
+        fields
+            .iter()
+            .map(|field| {
+                sexp(
+                    "Field",
+                    [field.ident.to_string(), self.dump_pattern(&field.pat)],
+                )
+            })
+            .collect()
+    }
+
+    fn dump_field_defs(&self, fields: &[FieldDef<'_>]) -> Vec<String> {
+        fields
+            .iter()
+            .map(|field| {
+                sexp(
+                    "Field",
+                    [field.ident.to_string(), self.dump_expr(field.ty)]
+                        .into_iter()
+                        .chain(field.default.map(|d| sexp("default", [self.dump_expr(d)]))),
+                )
+            })
+            .collect()
+    }
+
+    fn dump_variant(&self, variant: &Variant<'_>) -> String {
+        let name = variant.ident.to_string();
+        let payload = match &variant.kind {
+            VariantKind::Unit => Vec::new(),
+            VariantKind::Pattern(pat) => vec![self.dump_pattern(pat)],
+            VariantKind::Const(e) => vec![sexp("=", [self.dump_expr(e)])],
+            VariantKind::Tuple(elems) => {
+                vec![sexp("Tuple", elems.iter().map(|e| self.dump_expr(e)))]
+            }
+            VariantKind::Struct(fields) => self.dump_field_defs(fields),
+            VariantKind::SubEnum(variants) => {
+                variants.iter().map(|v| self.dump_variant(v)).collect()
+            }
+        };
+        sexp("Variant", std::iter::once(name).chain(payload))
+    }
+}
+
+/// The label of a `break` or `continue`, if it has one.
+fn label_of(label: &Ident) -> Option<String> {
+    (label.name != Symbol::invalid()).then(|| label.to_string())
+}
@@ -9,8 +9,8 @@
 use rustc_span::Span;
 use symbol::Symbol;
 
-use crate::body::BodyId;
-use crate::common::{Arg, BinOp, Ident, Lit, Path, TyParam, UnOp};
+use crate::body::{BodyId, Param};
+use crate::common::{Arg, BinOp, Ident, Lit, Path, TyParam, TyParamKind, UnOp};
 use crate::decl::LetDecl;
 use crate::hir_id::{HirId, OwnerId};
 use crate::pattern::{Pattern, PatternArm};
@@ -47,6 +47,17 @@ pub enum ExprKind<'hir> {
     Block(&'hir Block<'hir>),
     Loop(&'hir Block<'hir>),
     Match(&'hir Expr<'hir>, &'hir [PatternArm<'hir>]),
+    /// Error elimination `expr ! { catch e => body, Pat => body }`.
+    ///
+    /// `catch` is the catch arm: its parameter binds the caught error,
+    /// typed with the error set of `expr` when that is known, and is in
+    /// scope only in the arm body. The pattern arms handle the success
+    /// value.
+    ErrorElimination {
+        expr: &'hir Expr<'hir>,
+        arms: &'hir [PatternArm<'hir>],
+        catch: Option<(&'hir Param<'hir>, &'hir Expr<'hir>)>,
+    },
     Assign(&'hir Expr<'hir>, &'hir Expr<'hir>),
     AssignOp(BinOp, &'hir Expr<'hir>, &'hir Expr<'hir>),
     Return(Option<&'hir Expr<'hir>>),
@@ -63,8 +74,26 @@ pub enum ExprKind<'hir> {
     Ref(&'hir Expr<'hir>),
     Deref(&'hir Expr<'hir>),
     ErrorNew(&'hir Expr<'hir>),
-    Closure(&'hir [ClosureParam<'hir>], Option<&'hir Expr<'hir>>, BodyId),
+    /// Lambda `|params| body`.
+    ///
+    /// `captures` lists the locals of enclosing bodies that the lambda uses,
+    /// in order of first use. `ret` is the declared return type, or the one
+    /// inferred from the body, and `ty` the lambda's `TyFn` type.
+    Lambda {
+        params: &'hir [ClosureParam<'hir>],
+        body: BodyId,
+        captures: &'hir [Ident],
+        ret: Option<&'hir Expr<'hir>>,
+        ty: &'hir Expr<'hir>,
+    },
     Cast(&'hir Expr<'hir>, &'hir Expr<'hir>),
+    /// Quoted code `quote { .. }`: a compile-time value standing for the
+    /// unevaluated block, which is only lowered where it is spliced.
+    Quote,
+    /// Quoted code inserted by `splice(q)`. The block keeps the spans of the
+    /// quoted source, so errors in it point there; the expression's span is
+    /// the splice site.
+    Splice(&'hir Block<'hir>),
 
     /// Statement-as-expression: `let pat = init`
     Let(&'hir LetDecl<'hir>),
@@ -95,15 +124,48 @@ pub enum ExprKind<'hir> {
     TyPtr(&'hir Expr<'hir>),
     /// Optional type `??`.
     TyOptional(&'hir Expr<'hir>),
-    /// Function types are constructed using `TyFn` and `TyFnArrow`.
-    TyFn(&'hir [TyParam<'hir>]),
+    /// Function type `pure extern "C" fn(A, B) -> R`.
+    ///
+    /// `modifiers` is the `ast::FN_MOD_*` bitmask, `abi` the string after
+    /// `extern`, and `ret` the right-hand side of a `->` directly following
+    /// the parameter list. A bare arrow `A -> B` is a `fn` type with the
+    /// one parameter `A`; arrows associate to the right.
+    TyFn {
+        modifiers: u32,
+        abi: Option<Symbol>,
+        params: &'hir [TyParam<'hir>],
+        ret: Option<&'hir Expr<'hir>>,
+    },
     TyNFFn(&'hir [TyParam<'hir>]),
-    TyFnArrow(&'hir Expr<'hir>, &'hir Expr<'hir>),
+    /// Polymorphic type `forall<T, ..> body`. Each parameter binds a type
+    /// variable, named by its `PositionalDependencyCatched` kind, in `body`.
+    TyForall {
+        params: &'hir [TyParam<'hir>],
+        body: &'hir Expr<'hir>,
+    },
+    /// Closure-qualified type `^env T`: a `T` that is only valid inside
+    /// the environment `env` of some closure.
+    ///
+    /// `env` names the environment, either as a closure value (`^counter T`)
+    /// or as the tuple of the locals it holds (`^(n, base) T`). A value of
+    /// the qualified type may refer to those locals, so it must not outlive
+    /// them. Lowering gives every lambda that captures locals the type
+    /// `^(captures) fn(..) -> R`; a type without captures is left unqualified.
+    /// Anything that only needs the shape of `T`, such as checking a call,
+    /// looks through the qualification.
+    TyClosureQualified(&'hir Expr<'hir>, &'hir Expr<'hir>),
 
+    /// Error-qualified type `!errors T`: a `T`, or one of the errors in
+    /// `errors`.
+    ErrorQualifiedType(&'hir Expr<'hir>, &'hir Expr<'hir>),
     /// TODO
     ReachabilityType,
-    ErrorQualifiedType,
-    EffectQualifiedType,
+    /// Effect-qualified type `#effects T`: a `T` computed by performing at
+    /// most the effects of the row `effects`, a [`ExprKind::TyEffectRow`].
+    EffectQualifiedType(&'hir Expr<'hir>, &'hir Expr<'hir>),
+    /// Effect row `{IO, State}`: a set of effects, written in any order.
+    /// A single effect written alone (`#IO T`) is a row of one.
+    TyEffectRow(&'hir [Expr<'hir>]),
 
     /// Type inference placeholder `_`.
     TyPlaceholder,
@@ -127,9 +189,18 @@ pub enum ExprKind<'hir> {
     /// `T1 <: T2`
     Subtype,
 
-    /// TODO
-    Forall,
-    Exist,
+    /// `forall x: T, .. => body`: holds when `body` does for every value of
+    /// the parameters, which are bound in `body` only. Typed as `Bool`.
+    Forall {
+        params: &'hir [ClosureParam<'hir>],
+        body: &'hir Expr<'hir>,
+    },
+    /// `exists x: T, .. => body`: holds when `body` does for some value of
+    /// the parameters, which are bound in `body` only. Typed as `Bool`.
+    Exists {
+        params: &'hir [ClosureParam<'hir>],
+        body: &'hir Expr<'hir>,
+    },
 
     Invalid,
 }
@@ -164,3 +235,330 @@ pub struct ClosureParam<'hir> {
     pub ty: Option<&'hir Expr<'hir>>,
     pub span: Span,
 }
+
+impl<'hir> Expr<'hir> {
+    /// The expressions directly inside this one, in source order.
+    ///
+    /// Type annotations (of lambda parameters, `let`s, ..) count as
+    /// subexpressions. A lambda's body is a separate [`BodyId`] and is not
+    /// included; neither are expressions inside patterns.
+    pub fn subexprs(&self) -> Vec<&'hir Expr<'hir>> {
+        fn arg<'hir>(arg: &Arg<'hir>) -> &'hir Expr<'hir> {
+            let (Arg::Positional(e) | Arg::Named(_, e) | Arg::Expand(e) | Arg::Implicit(e)) = arg;
+            e
+        }
+        fn block<'hir>(block: &Block<'hir>, out: &mut Vec<&'hir Expr<'hir>>) {
+            out.extend(block.stmts.iter());
+            out.extend(block.expr);
+        }
+        fn arms<'hir>(arms: &'hir [PatternArm<'hir>], out: &mut Vec<&'hir Expr<'hir>>) {
+            for arm in arms {
+                out.extend(arm.guard);
+                out.push(arm.body);
+            }
+        }
+
+        let mut out = Vec::new();
+        match &self.kind {
+            ExprKind::Path(path) => {
+                for segment in path.segments {
+                    out.extend(segment.args.iter().map(arg));
+                }
+            }
+            ExprKind::Application(callee, args)
+            | ExprKind::ExtendedApplication(callee, args)
+            | ExprKind::NFApplication(callee, args) => {
+                out.push(*callee);
+                out.extend(args.iter().map(arg));
+            }
+            ExprKind::Index(a, b)
+            | ExprKind::Binary(_, a, b)
+            | ExprKind::Assign(a, b)
+            | ExprKind::AssignOp(_, a, b)
+            | ExprKind::Cast(a, b)
+            | ExprKind::TyClosureQualified(a, b)
+            | ExprKind::ErrorQualifiedType(a, b)
+            | ExprKind::EffectQualifiedType(a, b) => out.extend([*a, *b]),
+            ExprKind::Unary(_, e)
+            | ExprKind::Projection(e, _)
+            | ExprKind::Ref(e)
+            | ExprKind::Deref(e)
+            | ExprKind::ErrorNew(e)
+            | ExprKind::Semi(e)
+            | ExprKind::TyPtr(e)
+            | ExprKind::TyOptional(e) => out.push(*e),
+            ExprKind::Return(e) | ExprKind::Resume(e) => out.extend(*e),
+            ExprKind::If(cond, then, els) => {
+                out.push(*cond);
+                block(then, &mut out);
+                out.extend(*els);
+            }
+            ExprKind::When(conds) => {
+                for arm in conds.iter() {
+                    out.extend([arm.cond, arm.body]);
+                }
+            }
+            ExprKind::Block(b) | ExprKind::Loop(b) | ExprKind::Splice(b) => block(b, &mut out),
+            ExprKind::Match(scrutinee, match_arms) => {
+                out.push(*scrutinee);
+                arms(match_arms, &mut out);
+            }
+            ExprKind::ErrorElimination {
+                expr,
+                arms: match_arms,
+                catch,
+            } => {
+                out.push(*expr);
+                if let Some((param, body)) = catch {
+                    out.extend(param.ty);
+                    out.push(*body);
+                }
+                arms(match_arms, &mut out);
+            }
+            ExprKind::Tuple(elems) | ExprKind::List(elems) | ExprKind::TyEffectRow(elems) => {
+                out.extend(elems.iter())
+            }
+            ExprKind::Object(elems, fields) => {
+                out.extend(elems.iter());
+                out.extend(fields.iter().map(|field| field.expr));
+            }
+            ExprKind::Lambda {
+                params, ret, ty, ..
+            } => {
+                out.extend(params.iter().filter_map(|param| param.ty));
+                out.extend(*ret);
+                out.push(*ty);
+            }
+            ExprKind::Let(decl) => {
+                out.extend(decl.ty);
+                out.extend(decl.init);
+            }
+            ExprKind::InlineIf {
+                cond,
+                then_expr,
+                else_expr,
+            } => {
+                out.extend([*cond, *then_expr]);
+                out.extend(*else_expr);
+            }
+            ExprKind::InlineMatch(match_arms) => arms(match_arms, &mut out),
+            ExprKind::InlineFor { iter, body, .. } => out.extend([*iter, *body]),
+            ExprKind::TyFn { params, ret, .. } => {
+                out.extend(params.iter().flat_map(TyParam::exprs));
+                out.extend(*ret);
+            }
+            ExprKind::TyNFFn(params) => out.extend(params.iter().flat_map(TyParam::exprs)),
+            ExprKind::Forall { params, body } | ExprKind::Exists { params, body } => {
+                out.extend(params.iter().filter_map(|param| param.ty));
+                out.push(*body);
+            }
+            ExprKind::TyForall { params, body } => {
+                out.extend(params.iter().flat_map(TyParam::exprs));
+                out.push(*body);
+            }
+            _ => {}
+        }
+        out
+    }
+}
+
+impl Expr<'_> {
+    /// The name a type is written with, without its arguments: `Vec` for
+    /// `Vec<Int>`.
+    pub fn head_name(&self) -> Option<Symbol> {
+        match &self.kind {
+            ExprKind::Ident(name) => Some(*name),
+            ExprKind::Path(path) => path.segments.last().map(|segment| segment.ident.name),
+            ExprKind::Application(head, _)
+            | ExprKind::ExtendedApplication(head, _)
+            | ExprKind::NFApplication(head, _) => head.head_name(),
+            _ => None,
+        }
+    }
+
+    /// The names this expression uses without binding them itself, in
+    /// order of first use and spanned at that use.
+    ///
+    /// Names bound by `let`, match arms and `for` patterns are scoped to
+    /// their block or arm. A nested lambda contributes its captures.
+    pub fn free_variables(&self) -> Vec<Ident> {
+        let mut free: Vec<Ident> = Vec::new();
+        self.walk_scoped(&mut Vec::new(), &mut |expr, bound| {
+            let mut use_name = |ident: &Ident| {
+                if !bound.contains(&ident.name) && !free.iter().any(|f| f.name == ident.name) {
+                    free.push(ident.clone());
+                }
+            };
+            match &expr.kind {
+                ExprKind::Ident(name) => use_name(&Ident::new(*name, expr.span)),
+                ExprKind::Lambda { captures, .. } => captures.iter().for_each(use_name),
+                _ => {}
+            }
+            true
+        });
+        free
+    }
+}
+
+impl<'hir> Expr<'hir> {
+    /// Visit this expression and those within it, outermost first, each
+    /// with the names bound where it appears: `bound` to begin with, then
+    /// those of the enclosing `let`s, match arms, `for` patterns and
+    /// quantifier parameters. `visit` returns whether to go on into the
+    /// expression's subexpressions.
+    ///
+    /// A lambda's body is not entered; its captures are what it uses.
+    pub fn walk_scoped(
+        &self,
+        bound: &mut Vec<Symbol>,
+        visit: &mut impl FnMut(&Expr<'hir>, &[Symbol]) -> bool,
+    ) {
+        if !visit(self, bound.as_slice()) {
+            return;
+        }
+        match &self.kind {
+            ExprKind::Application(callee, args)
+            | ExprKind::ExtendedApplication(callee, args)
+            | ExprKind::NFApplication(callee, args) => {
+                callee.walk_scoped(bound, visit);
+                for arg in args.iter() {
+                    let (Arg::Positional(e) | Arg::Named(_, e) | Arg::Expand(e) | Arg::Implicit(e)) =
+                        arg;
+                    e.walk_scoped(bound, visit);
+                }
+            }
+            ExprKind::Index(a, b)
+            | ExprKind::Binary(_, a, b)
+            | ExprKind::Assign(a, b)
+            | ExprKind::AssignOp(_, a, b)
+            | ExprKind::Cast(a, b) => {
+                a.walk_scoped(bound, visit);
+                b.walk_scoped(bound, visit);
+            }
+            ExprKind::Unary(_, e)
+            | ExprKind::Projection(e, _)
+            | ExprKind::Ref(e)
+            | ExprKind::Deref(e)
+            | ExprKind::ErrorNew(e)
+            | ExprKind::Semi(e)
+            | ExprKind::Return(Some(e))
+            | ExprKind::Resume(Some(e)) => e.walk_scoped(bound, visit),
+            ExprKind::If(cond, then, els) => {
+                cond.walk_scoped(bound, visit);
+                let scope = bound.len();
+                for stmt in then.stmts.iter().chain(then.expr) {
+                    stmt.walk_scoped(bound, visit);
+                }
+                bound.truncate(scope);
+                if let Some(e) = els {
+                    e.walk_scoped(bound, visit);
+                }
+            }
+            ExprKind::Block(block) | ExprKind::Loop(block) | ExprKind::Splice(block) => {
+                let scope = bound.len();
+                for stmt in block.stmts.iter().chain(block.expr) {
+                    stmt.walk_scoped(bound, visit);
+                }
+                bound.truncate(scope);
+            }
+            ExprKind::When(arms) => {
+                for arm in arms.iter() {
+                    arm.cond.walk_scoped(bound, visit);
+                    arm.body.walk_scoped(bound, visit);
+                }
+            }
+            ExprKind::Match(scrutinee, arms) => {
+                scrutinee.walk_scoped(bound, visit);
+                walk_arms_scoped(arms, bound, visit);
+            }
+            ExprKind::ErrorElimination { expr, arms, catch } => {
+                expr.walk_scoped(bound, visit);
+                if let Some((param, body)) = catch {
+                    bound.push(param.name.name);
+                    body.walk_scoped(bound, visit);
+                    bound.pop();
+                }
+                walk_arms_scoped(arms, bound, visit);
+            }
+            ExprKind::Tuple(elems) | ExprKind::List(elems) => {
+                for e in elems.iter() {
+                    e.walk_scoped(bound, visit);
+                }
+            }
+            ExprKind::Object(elems, fields) => {
+                for e in elems.iter() {
+                    e.walk_scoped(bound, visit);
+                }
+                for field in fields.iter() {
+                    field.expr.walk_scoped(bound, visit);
+                }
+            }
+            ExprKind::Let(decl) => {
+                if let Some(init) = decl.init {
+                    init.walk_scoped(bound, visit);
+                }
+                bound.push(decl.name.name);
+            }
+            ExprKind::InlineIf {
+                cond,
+                then_expr,
+                else_expr,
+            } => {
+                cond.walk_scoped(bound, visit);
+                then_expr.walk_scoped(bound, visit);
+                if let Some(e) = else_expr {
+                    e.walk_scoped(bound, visit);
+                }
+            }
+            ExprKind::InlineFor {
+                pat, iter, body, ..
+            } => {
+                iter.walk_scoped(bound, visit);
+                let scope = bound.len();
+                pat.walk_bindings(&mut |ident| bound.push(ident.name));
+                body.walk_scoped(bound, visit);
+                bound.truncate(scope);
+            }
+            ExprKind::Forall { params, body } | ExprKind::Exists { params, body } => {
+                let scope = bound.len();
+                for param in params.iter() {
+                    if let Some(ty) = param.ty {
+                        ty.walk_scoped(bound, visit);
+                    }
+                    param.pat.walk_bindings(&mut |ident| bound.push(ident.name));
+                }
+                body.walk_scoped(bound, visit);
+                bound.truncate(scope);
+            }
+            ExprKind::TyForall { params, body } => {
+                let scope = bound.len();
+                for param in params.iter() {
+                    if let TyParamKind::PositionalDependencyCatched(ident, _) = &param.kind {
+                        bound.push(ident.name);
+                    }
+                }
+                body.walk_scoped(bound, visit);
+                bound.truncate(scope);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// [`Expr::walk_scoped`] over match arms, each of which binds the names of
+/// its pattern for its guard and body.
+fn walk_arms_scoped<'hir>(
+    arms: &[PatternArm<'hir>],
+    bound: &mut Vec<Symbol>,
+    visit: &mut impl FnMut(&Expr<'hir>, &[Symbol]) -> bool,
+) {
+    for arm in arms {
+        let scope = bound.len();
+        arm.pat.walk_bindings(&mut |ident| bound.push(ident.name));
+        if let Some(guard) = arm.guard {
+            guard.walk_scoped(bound, visit);
+        }
+        arm.body.walk_scoped(bound, visit);
+        bound.truncate(scope);
+    }
+}
@@ -192,7 +192,7 @@ impl Idx for ItemLocalId {
 /// independently (e.g. for incremental compilation).
 ///
 /// Analogous to rustc's `HirId`.
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct HirId {
     pub owner: OwnerId,
     pub local_id: ItemLocalId,
@@ -245,7 +245,7 @@ impl fmt::Display for HirId {
 /// The `hir_id` field points to the HIR node that *owns* the body – for
 /// a function, this is the function item itself; for a closure, the
 /// closure expression.
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct BodyId {
     pub hir_id: HirId,
 }
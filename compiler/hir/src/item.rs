@@ -9,7 +9,7 @@ use rustc_span::Span;
 
 use crate::body::BodyId;
 use crate::clause::ClauseConstraint;
-use crate::common::{FnSigParam, Ident, Path};
+use crate::common::{FnSigParam, Ident, Path, Symbol};
 use crate::expr::Expr;
 use crate::hir_id::{HirId, OwnerId};
 use crate::{ClauseParam, Pattern};
@@ -27,6 +27,19 @@ impl Item<'_> {
     pub fn hir_id(&self) -> HirId {
         HirId::make_owner(self.owner_id)
     }
+
+    /// The items this one lists as its own: those of a module, `impl` or
+    /// `trait`, and those nested in a `struct` or `enum`.
+    pub fn child_items(&self) -> Option<&[OwnerId]> {
+        match &self.kind {
+            ItemKind::Mod(def) => Some(&def.items),
+            ItemKind::Impl(def) => Some(&def.items),
+            ItemKind::Trait(def) => Some(&def.items),
+            ItemKind::Enum(def) => Some(&def.nested_items),
+            ItemKind::Struct(def) => Some(&def.nested_items),
+            _ => None,
+        }
+    }
 }
 
 /// Discriminates the concrete form of a top-level [`Item`].
@@ -39,6 +52,7 @@ pub enum ItemKind<'hir> {
     Impl(ImplDef<'hir>),
     Trait(TraitDef<'hir>),
     TypeAlias(&'hir Expr<'hir>),
+    Newtype(&'hir Expr<'hir>),
     Use(UsePath<'hir>),
     Const(&'hir Expr<'hir>, &'hir Expr<'hir>),
     Invalid,
@@ -122,6 +136,55 @@ pub enum VariantKind<'hir> {
     SubEnum(&'hir [Variant<'hir>]),
 }
 
+/// An enum variant with its payload decoded, as
+/// [`Package::enum_variants`](crate::Package::enum_variants) returns it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantInfo<'hir> {
+    pub name: Symbol,
+    pub payload: VariantPayload<'hir>,
+}
+
+/// The payload of an enum variant: what a pattern on it has to match.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariantPayload<'hir> {
+    /// `A`, no payload.
+    Unit,
+    /// `A: pat`, the values the pattern matches.
+    Pattern(&'hir Pattern<'hir>),
+    /// `A = expr`, a constant value.
+    Const(&'hir Expr<'hir>),
+    /// `A(T, U)`, the field types in order.
+    Tuple(Vec<&'hir Expr<'hir>>),
+    /// `A { x: T }`, each field's name and type in order.
+    Struct(Vec<(Symbol, &'hir Expr<'hir>)>),
+    /// `A.{ B, C }`, the nested variants.
+    SubEnum(Vec<VariantInfo<'hir>>),
+}
+
+impl<'hir> VariantInfo<'hir> {
+    pub fn of(variant: &Variant<'hir>) -> Self {
+        let payload = match &variant.kind {
+            VariantKind::Unit => VariantPayload::Unit,
+            VariantKind::Pattern(pat) => VariantPayload::Pattern(*pat),
+            VariantKind::Const(expr) => VariantPayload::Const(*expr),
+            VariantKind::Tuple(elems) => VariantPayload::Tuple(elems.iter().collect()),
+            VariantKind::Struct(fields) => VariantPayload::Struct(
+                fields
+                    .iter()
+                    .map(|field| (field.ident.name, field.ty))
+                    .collect(),
+            ),
+            VariantKind::SubEnum(variants) => {
+                VariantPayload::SubEnum(variants.iter().map(VariantInfo::of).collect())
+            }
+        };
+        VariantInfo {
+            name: variant.ident.name,
+            payload,
+        }
+    }
+}
+
 /// Definition body of a `mod` item — simply a list of child owners.
 #[derive(Debug, Clone, PartialEq)]
 pub struct ModDef {
@@ -175,6 +238,7 @@ pub enum DefKind {
     Impl,
     Trait,
     TypeAlias,
+    Newtype,
     Use,
     Const,
     Invalid,
@@ -190,6 +254,7 @@ impl DefKind {
             ItemKind::Impl(..) => DefKind::Impl,
             ItemKind::Trait(..) => DefKind::Trait,
             ItemKind::TypeAlias(..) => DefKind::TypeAlias,
+            ItemKind::Newtype(..) => DefKind::Newtype,
             ItemKind::Use(..) => DefKind::Use,
             ItemKind::Const(..) => DefKind::Const,
             ItemKind::Invalid => DefKind::Invalid,
@@ -20,11 +20,14 @@ pub mod arena;
 pub mod body;
 pub mod clause;
 pub mod common;
+pub mod consts;
 pub mod decl;
+pub mod dump;
 pub mod expr;
 pub mod hir_id;
 pub mod idx;
 pub mod item;
+pub mod mangle;
 pub mod node;
 pub mod owner;
 pub mod pattern;
@@ -32,18 +35,41 @@ pub mod pattern;
 pub use arena::HirArena;
 pub use body::{Body, Param};
 pub use clause::{ClauseConstraint, ClauseConstraintKind, ClauseParam, ClauseParamKind};
-pub use common::{BinOp, BindingMode, Ident, Lit, LitKind, Path, Symbol, UnOp};
+pub use common::{BinOp, BindingMode, DefId, Ident, Lit, LitKind, Path, Symbol, UnOp};
+pub use consts::{ConstValue, Constness};
 pub use decl::LetDecl;
 pub use expr::{Block, CondictionArm, Expr, ExprKind, FieldExpr};
 pub use hir_id::{BodyId, HirId, ItemLocalId, LocalDefId, OwnerId};
 pub use idx::{Idx, IndexVec};
 pub use item::{
     DefKind, EnumDef, FieldDef, FnSig, ImplDef, Item, ItemKind, ModDef, NFSig, StructDef, TraitDef,
-    Variant, VariantKind,
+    Variant, VariantInfo, VariantKind, VariantPayload,
 };
 pub use node::Node;
 pub use owner::{OwnerInfo, OwnerNode, OwnerNodes, ParentedNode};
-pub use pattern::{FieldPat, Pattern, PatternArm, PatternKind};
+pub use pattern::{FieldPat, Pattern, PatternArm, PatternKind, arms_are_exhaustive};
+
+/// What a name used as a value refers to where it is written, as far as
+/// [`Package::eval_const`] needs to know.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueRes {
+    /// A local of the enclosing body; `comptime` ones are known at compile
+    /// time.
+    Local { comptime: bool },
+    /// A definition the resolver found.
+    Def(DefId),
+    /// A name that does not resolve.
+    Unresolved,
+}
+
+/// The struct field a field access `value.field` refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldRes {
+    /// The struct the field belongs to.
+    pub def: DefId,
+    /// The position of the field among the struct's fields.
+    pub index: usize,
+}
 
 /// The top-level HIR container for a single Flurry package.
 ///
@@ -52,6 +78,18 @@ pub use pattern::{FieldPat, Pattern, PatternArm, PatternKind};
 pub struct Package<'hir> {
     owners: IndexVec<LocalDefId, Option<OwnerInfo<'hir>>>,
     bodies: FxHashMap<BodyId, Body<'hir>>,
+    /// The definitions named by type expressions, keyed by the expression.
+    type_res: FxHashMap<HirId, DefId>,
+    /// The owner each definition the resolver found was lowered to.
+    def_owners: FxHashMap<DefId, OwnerId>,
+    /// The fields named by field accesses, keyed by the access.
+    field_res: FxHashMap<HirId, FieldRes>,
+    /// The `impl` blocks of each type, keyed by the definition their self
+    /// type names, in the order they were lowered.
+    impls_by_type: FxHashMap<DefId, Vec<OwnerId>>,
+    /// The item listing each owner among its items, recorded as the item
+    /// is inserted.
+    parents: FxHashMap<OwnerId, OwnerId>,
     pub root_mod: OwnerId,
 }
 
@@ -60,6 +98,11 @@ impl<'hir> Package<'hir> {
         Package {
             owners: IndexVec::new(),
             bodies: FxHashMap::default(),
+            type_res: FxHashMap::default(),
+            def_owners: FxHashMap::default(),
+            field_res: FxHashMap::default(),
+            impls_by_type: FxHashMap::default(),
+            parents: FxHashMap::default(),
             root_mod: OwnerId::INVALID,
         }
     }
@@ -70,6 +113,11 @@ impl<'hir> Package<'hir> {
     }
 
     pub fn insert_owner(&mut self, owner_id: OwnerId, info: OwnerInfo<'hir>) {
+        if let Some(children) = info.node.as_item().and_then(Item::child_items) {
+            for &child in children {
+                self.parents.insert(child, owner_id);
+            }
+        }
         let def_id = owner_id.def_id;
         self.owners.ensure_contains(def_id);
         self.owners[def_id] = Some(info);
@@ -89,6 +137,105 @@ impl<'hir> Package<'hir> {
             .filter_map(|(id, opt)| opt.as_ref().map(|info| (OwnerId::new(id), info)))
     }
 
+    /// The `impl` block owned by `owner_id`, if that owner is one.
+    pub fn get_impl(&self, owner_id: OwnerId) -> Option<&'hir ImplDef<'hir>> {
+        match &self.owner(owner_id)?.node.as_item()?.kind {
+            ItemKind::Impl(def) => Some(def),
+            _ => None,
+        }
+    }
+
+    /// The variants of the enum owned by `owner_id`, with their payloads
+    /// decoded, in declaration order. `None` if that owner is not an enum.
+    pub fn enum_variants(&self, owner_id: OwnerId) -> Option<Vec<VariantInfo<'hir>>> {
+        match &self.item(owner_id)?.kind {
+            ItemKind::Enum(def) => Some(def.variants.iter().map(VariantInfo::of).collect()),
+            _ => None,
+        }
+    }
+
+    /// The owners of every `impl` block for which `pred` holds, in owner
+    /// order.
+    pub fn impls_matching(&self, mut pred: impl FnMut(&ImplDef<'hir>) -> bool) -> Vec<OwnerId> {
+        self.owners()
+            .filter_map(|(owner_id, _)| Some((owner_id, self.get_impl(owner_id)?)))
+            .filter(|(_, def)| pred(def))
+            .map(|(owner_id, _)| owner_id)
+            .collect()
+    }
+
+    /// Record that the `impl` block owned by `owner_id` is for the type
+    /// defined by `self_ty`.
+    pub fn register_impl(&mut self, self_ty: DefId, owner_id: OwnerId) {
+        self.impls_by_type
+            .entry(self_ty)
+            .or_default()
+            .push(owner_id);
+    }
+
+    /// The owners of the `impl` blocks registered for the type defined by
+    /// `self_ty`, inherent and trait impls alike.
+    pub fn impls_of(&self, self_ty: DefId) -> &[OwnerId] {
+        self.impls_by_type.get(&self_ty).map_or(&[], Vec::as_slice)
+    }
+
+    /// The methods of the type defined by `ty`, with their names: the functions
+    /// written in its definition and its inherent `impl` blocks, then those
+    /// of its trait impls, then those its implemented traits provide. The
+    /// first method of a name wins, so an inherent method shadows a trait
+    /// method of the same name and an impl's method overrides the one its
+    /// trait provides.
+    pub fn methods_of(&self, ty: DefId) -> Vec<(Symbol, OwnerId)> {
+        let (inherent, trait_impls): (Vec<_>, Vec<_>) = self
+            .impls_of(ty)
+            .iter()
+            .filter_map(|&owner| self.get_impl(owner))
+            .partition(|def| def.trait_ref.is_none());
+        let definition = self.def_item(ty).and_then(|item| match &item.kind {
+            ItemKind::Struct(def) => Some(&def.nested_items),
+            ItemKind::Enum(def) => Some(&def.nested_items),
+            _ => None,
+        });
+        let provided = trait_impls
+            .iter()
+            .filter_map(|def| self.head_def(def.trait_ref?))
+            .filter_map(|def| match &self.def_item(def)?.kind {
+                ItemKind::Trait(def) => Some(&def.items),
+                _ => None,
+            });
+
+        let mut methods: Vec<(Symbol, OwnerId)> = Vec::new();
+        let owners = definition
+            .into_iter()
+            .chain(inherent.iter().map(|def| &def.items))
+            .chain(trait_impls.iter().map(|def| &def.items))
+            .chain(provided)
+            .flatten();
+        for &owner in owners {
+            let Some(item) = self.item(owner) else {
+                continue;
+            };
+            if matches!(item.kind, ItemKind::Fn(..))
+                && !methods.iter().any(|&(name, _)| name == item.ident.name)
+            {
+                methods.push((item.ident.name, owner));
+            }
+        }
+        methods
+    }
+
+    /// The definition the type `ty` is named after, without its arguments,
+    /// if it was resolved: that of `Vec` for `Vec<Int>`.
+    fn head_def(&self, ty: &Expr<'_>) -> Option<DefId> {
+        match &ty.kind {
+            ExprKind::Path(path) => path.res,
+            ExprKind::Application(head, _)
+            | ExprKind::ExtendedApplication(head, _)
+            | ExprKind::NFApplication(head, _) => self.head_def(head),
+            _ => self.type_res(ty.hir_id),
+        }
+    }
+
     pub fn num_defs(&self) -> usize {
         self.owners.len()
     }
@@ -101,20 +248,162 @@ impl<'hir> Package<'hir> {
         self.bodies.get(&body_id)
     }
 
+    /// All bodies, ordered by [`BodyId`] so that every walk over them (and
+    /// every dump built from one) is reproducible.
     pub fn bodies(&self) -> impl Iterator<Item = (&BodyId, &Body<'hir>)> {
-        self.bodies.iter()
+        let mut bodies: Vec<_> = self.bodies.iter().collect();
+        bodies.sort_unstable_by_key(|(id, _)| **id);
+        bodies.into_iter()
     }
 
     pub fn num_bodies(&self) -> usize {
         self.bodies.len()
     }
 
+    /// Every expression of the package, for passes such as lints that scan
+    /// them all: the expressions of item signatures (parameter, field and
+    /// return types, constants, clauses) in owner order, then those of each
+    /// body. Each tree is walked pre-order through [`Expr::subexprs`].
+    pub fn all_exprs(&self) -> impl Iterator<Item = &'hir Expr<'hir>> {
+        use item::{ItemKind, VariantKind};
+
+        let mut roots: Vec<&'hir Expr<'hir>> = Vec::new();
+        let clauses = |clauses: &'hir [ClauseConstraint<'hir>], roots: &mut Vec<_>| {
+            for clause in clauses {
+                match &clause.kind {
+                    ClauseConstraintKind::Requires(e)
+                    | ClauseConstraintKind::Ensures(e)
+                    | ClauseConstraintKind::Decreases(e) => roots.push(*e),
+                    ClauseConstraintKind::Outcome => {}
+                }
+            }
+        };
+        let fields = |fields: &'hir [FieldDef<'hir>], roots: &mut Vec<_>| {
+            for field in fields {
+                roots.push(field.ty);
+                roots.extend(field.default);
+            }
+        };
+        for (owner_id, _) in self.owners() {
+            let Some(item) = self.item(owner_id) else {
+                continue;
+            };
+            match &item.kind {
+                ItemKind::Fn(sig, _) => {
+                    for (_, param) in sig.params {
+                        roots.extend(param.exprs());
+                    }
+                    roots.extend(sig.return_ty);
+                    clauses(sig.clause_constraints, &mut roots);
+                }
+                ItemKind::Struct(def) => {
+                    fields(def.fields, &mut roots);
+                    clauses(def.clause_constraints, &mut roots);
+                }
+                ItemKind::Enum(def) => {
+                    let mut variants: Vec<_> = def.variants.iter().rev().collect();
+                    while let Some(variant) = variants.pop() {
+                        match &variant.kind {
+                            VariantKind::Const(e) => roots.push(e),
+                            VariantKind::Tuple(elems) => roots.extend(elems.iter()),
+                            VariantKind::Struct(defs) => fields(defs, &mut roots),
+                            VariantKind::SubEnum(subs) => variants.extend(subs.iter().rev()),
+                            VariantKind::Unit | VariantKind::Pattern(_) => {}
+                        }
+                    }
+                    clauses(def.clause_constraints, &mut roots);
+                }
+                ItemKind::Impl(def) => {
+                    roots.push(def.self_ty);
+                    roots.extend(def.trait_ref);
+                    clauses(def.clause_constraints, &mut roots);
+                }
+                ItemKind::Trait(def) => clauses(def.clause_constraints, &mut roots),
+                ItemKind::TypeAlias(ty) | ItemKind::Newtype(ty) => roots.push(ty),
+                ItemKind::Const(ty, value) => roots.extend([*ty, *value]),
+                ItemKind::Mod(_) | ItemKind::Use(_) | ItemKind::Invalid => {}
+            }
+        }
+        for (_, body) in self.bodies() {
+            // Parameter types are part of the signature already.
+            roots.extend(body.params.iter().flat_map(|param| param.attrs.iter()));
+            roots.push(body.value);
+        }
+
+        roots.reverse();
+        std::iter::from_fn(move || {
+            let expr = roots.pop()?;
+            roots.extend(expr.subexprs().into_iter().rev());
+            Some(expr)
+        })
+    }
+
+    /// Record that the definition `def` was lowered to `owner_id`.
+    pub fn record_def_owner(&mut self, def: DefId, owner_id: OwnerId) {
+        self.def_owners.insert(def, owner_id);
+    }
+
+    /// The owner the definition `def` was lowered to, if it was lowered
+    /// into this package.
+    pub fn def_owner(&self, def: DefId) -> Option<OwnerId> {
+        self.def_owners.get(&def).copied()
+    }
+
+    /// The item the definition `def` was lowered to.
+    pub fn def_item(&self, def: DefId) -> Option<&'hir Item<'hir>> {
+        self.item(self.def_owner(def)?)
+    }
+
+    /// Record that the type expression `hir_id` names the definition `def`.
+    pub fn record_type_res(&mut self, hir_id: HirId, def: DefId) {
+        self.type_res.insert(hir_id, def);
+    }
+
+    /// The definition the type expression `hir_id` names, if it was
+    /// resolved.
+    pub fn type_res(&self, hir_id: HirId) -> Option<DefId> {
+        self.type_res.get(&hir_id).copied()
+    }
+
+    /// Record that the field access `hir_id` refers to the field `res`.
+    pub fn record_field_res(&mut self, hir_id: HirId, res: FieldRes) {
+        self.field_res.insert(hir_id, res);
+    }
+
+    /// The field the field access `hir_id` refers to, if it was resolved.
+    pub fn field_res(&self, hir_id: HirId) -> Option<FieldRes> {
+        self.field_res.get(&hir_id).copied()
+    }
+
     pub fn node(&self, hir_id: HirId) -> Option<&Node<'hir>> {
         let owner_info = self.owner(hir_id.owner)?;
         let parented = owner_info.nodes.get(hir_id.local_id)?;
         Some(&parented.node)
     }
 
+    /// The owners enclosing `hir_id`, innermost first: its own owner, then
+    /// each [parent item](Self::parent_item) in turn, ending at the
+    /// package's [root module](Self::root_mod).
+    pub fn owner_chain(&self, hir_id: HirId) -> Vec<OwnerId> {
+        let mut chain = vec![hir_id.owner];
+        let mut cursor = hir_id.owner;
+        while let Some(parent) = self.parent_item(cursor) {
+            chain.push(parent);
+            cursor = parent;
+        }
+        chain
+    }
+
+    /// The innermost definition enclosing `hir_id`: the first owner of its
+    /// [chain](Self::owner_chain) that is not an `impl` block, which names
+    /// nothing, or an item that failed to lower.
+    pub fn enclosing_definition(&self, hir_id: HirId) -> Option<OwnerId> {
+        self.owner_chain(hir_id).into_iter().find(|&owner| {
+            self.item(owner)
+                .is_some_and(|item| !matches!(item.kind, ItemKind::Impl(_) | ItemKind::Invalid))
+        })
+    }
+
     pub fn hir_id_allocator(&self, owner: OwnerId) -> HirIdAllocator {
         HirIdAllocator {
             owner,
@@ -129,10 +418,18 @@ impl Default for Package<'_> {
     }
 }
 
+/// Same as [`Package::dump_to_lisp`].
+impl std::fmt::Display for Package<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.dump_to_lisp())
+    }
+}
+
 impl<'hir> Package<'hir> {
     /// Serialize the HIR package as a Lisp-style s-expression string.
     ///
-    /// Outputs one line per top-level item showing its kind and name.
+    /// Outputs one line per top-level item showing its kind and name, in
+    /// owner order, so dumping the same package twice gives identical text.
     /// Function items also list their parameters (name: type) and
     /// their return type if present.  Bodies are printed on subsequent
     /// indented lines.
@@ -210,6 +507,9 @@ impl<'hir> Package<'hir> {
                 ItemKind::TypeAlias(_) => {
                     writeln!(out, "  (type-alias {})", item.ident.name).unwrap();
                 }
+                ItemKind::Newtype(_) => {
+                    writeln!(out, "  (newtype {})", item.ident.name).unwrap();
+                }
                 ItemKind::Use(_) => {
                     writeln!(out, "  (use {})", item.ident.name).unwrap();
                 }
@@ -0,0 +1,294 @@
+//! Symbol name mangling for codegen.
+//!
+//! A mangled name encodes the qualified path of a definition, and for
+//! functions a hash of the signature so that overloads sharing a path get
+//! distinct symbols:
+//!
+//! ```text
+//!   _F 5utils 6double E h0123456789abcdef
+//!   │  └── length-prefixed path segments ─┘ └── FNV-1a hash of the signature
+//!   └── prefix
+//! ```
+//!
+//! The path part is reversible: [`Package::demangle`] recovers the dotted
+//! qualified path for diagnostics. The hash is only a disambiguator and is
+//! not decoded.
+
+use std::fmt::Write as _;
+
+use crate::common::{Arg, TyParam, TyParamKind};
+use crate::expr::{Expr, ExprKind};
+use crate::hir_id::{HirId, OwnerId};
+use crate::item::{FnSig, ItemKind};
+use crate::{Package, Symbol};
+
+const MANGLE_PREFIX: &str = "_F";
+
+impl<'hir> Package<'hir> {
+    /// The item that lexically contains `owner`: the module, `impl`,
+    /// `trait`, `struct` or `enum` listing it among its items.
+    pub fn parent_item(&self, owner: OwnerId) -> Option<OwnerId> {
+        self.parents.get(&owner).copied()
+    }
+
+    /// The names of `owner` and its enclosing items, outermost first.
+    ///
+    /// The parentless root module is left out, so an item at the top level
+    /// of the root file has a single-segment path.
+    pub fn def_path(&self, owner: OwnerId) -> Vec<Symbol> {
+        let mut segments = Vec::new();
+        let mut cursor = owner;
+        while let Some(parent) = self.parent_item(cursor) {
+            if let Some(item) = self.item(cursor) {
+                segments.push(item.ident.name);
+            }
+            cursor = parent;
+        }
+        segments.reverse();
+        segments
+    }
+
+    /// A deterministic symbol name for the definition owning `def`.
+    ///
+    /// Functions get a signature hash appended so that overloads of the same
+    /// name mangle differently; other items are identified by path alone.
+    pub fn mangle(&self, def: HirId) -> String {
+        let mut out = String::from(MANGLE_PREFIX);
+        for segment in self.def_path(def.owner) {
+            let name = segment.as_str();
+            write!(out, "{}{}", name.len(), name).unwrap();
+        }
+        out.push('E');
+        if let Some(ItemKind::Fn(sig, _)) = self.item(def.owner).map(|item| &item.kind) {
+            write!(out, "h{:016x}", fnv1a(signature_key(sig).as_bytes())).unwrap();
+        }
+        out
+    }
+
+    /// Recover the dotted qualified path from a name produced by
+    /// [`Package::mangle`], or `None` if `mangled` is not in that format.
+    pub fn demangle(mangled: &str) -> Option<String> {
+        let mut rest = mangled.strip_prefix(MANGLE_PREFIX)?;
+        let mut segments = Vec::new();
+        while !rest.starts_with('E') {
+            let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+            let len: usize = rest[..digits].parse().ok()?;
+            rest = &rest[digits..];
+            segments.push(rest.get(..len)?);
+            rest = &rest[len..];
+        }
+        let hash = &rest[1..];
+        let hash_ok = hash.is_empty()
+            || (hash.len() == 17
+                && hash.starts_with('h')
+                && hash[1..].bytes().all(|b| b.is_ascii_hexdigit()));
+        hash_ok.then(|| segments.join("."))
+    }
+}
+
+impl Expr<'_> {
+    /// A canonical textual form of the type this expression writes. Two type
+    /// expressions spelled the same way get the same key, wherever they are.
+    pub fn type_key(&self) -> String {
+        let mut key = String::new();
+        ty_key(self, &mut key);
+        key
+    }
+}
+
+/// A canonical textual form of a signature's parameter and return types.
+fn signature_key(sig: &FnSig<'_>) -> String {
+    let mut key = String::new();
+    for (_, param) in sig.params {
+        ty_param_key(param, &mut key);
+        key.push(',');
+    }
+    key.push_str("->");
+    if let Some(ret) = sig.return_ty {
+        ty_key(ret, &mut key);
+    }
+    key
+}
+
+fn ty_param_key(param: &TyParam<'_>, out: &mut String) {
+    match &param.kind {
+        TyParamKind::PositionalDependencyCatched(_, ty) | TyParamKind::Positional(ty) => {
+            ty_key(ty, out)
+        }
+        TyParamKind::Optional(_, ty, _) => {
+            out.push('?');
+            ty_key(ty, out);
+        }
+        TyParamKind::Varadic(_, ty) => {
+            out.push_str("...");
+            ty_key(ty, out);
+        }
+        TyParamKind::Itself { is_ref } => out.push_str(if *is_ref { "*self" } else { "self" }),
+    }
+}
+
+/// Spans and `HirId`s are left out so the key only depends on how the type
+/// is written.
+fn ty_key(expr: &Expr<'_>, out: &mut String) {
+    let list = |exprs: &[Expr<'_>], out: &mut String| {
+        for expr in exprs {
+            ty_key(expr, out);
+            out.push(',');
+        }
+    };
+    match &expr.kind {
+        ExprKind::Ident(name) => out.push_str(name.as_str()),
+        ExprKind::Path(path) => {
+            write!(out, "{path}").unwrap();
+            for segment in path.segments.iter().filter(|s| !s.args.is_empty()) {
+                args_key(segment.args, out);
+            }
+        }
+        ExprKind::Projection(base, field) => {
+            ty_key(base, out);
+            out.push('.');
+            out.push_str(field.name.as_str());
+        }
+        ExprKind::Application(callee, args)
+        | ExprKind::ExtendedApplication(callee, args)
+        | ExprKind::NFApplication(callee, args) => {
+            ty_key(callee, out);
+            args_key(args, out);
+        }
+        ExprKind::TyPtr(inner) => {
+            out.push('*');
+            ty_key(inner, out);
+        }
+        ExprKind::TyOptional(inner) => {
+            out.push('?');
+            ty_key(inner, out);
+        }
+        ExprKind::TyFn {
+            modifiers,
+            abi,
+            params,
+            ret,
+        } => {
+            write!(out, "fn[{modifiers:x}").unwrap();
+            if let Some(abi) = abi {
+                write!(out, " {}", abi.as_str()).unwrap();
+            }
+            out.push_str("](");
+            params_key(params, out);
+            out.push(')');
+            if let Some(ret) = ret {
+                out.push_str("->");
+                ty_key(ret, out);
+            }
+        }
+        ExprKind::TyNFFn(params) => {
+            out.push_str("fn(");
+            params_key(params, out);
+            out.push(')');
+        }
+        ExprKind::TyForall { params, body } => {
+            out.push_str("forall<");
+            params_key(params, out);
+            out.push('>');
+            ty_key(body, out);
+        }
+        ExprKind::TyClosureQualified(env, ty) => {
+            out.push('^');
+            ty_key(env, out);
+            out.push(' ');
+            ty_key(ty, out);
+        }
+        ExprKind::EffectQualifiedType(effects, ty) => {
+            out.push('#');
+            ty_key(effects, out);
+            out.push(' ');
+            ty_key(ty, out);
+        }
+        ExprKind::TyEffectRow(effects) => {
+            // A row is a set: the same effects in another order are the
+            // same row.
+            let mut keys: Vec<String> = effects
+                .iter()
+                .map(|effect| {
+                    let mut key = String::new();
+                    ty_key(effect, &mut key);
+                    key
+                })
+                .collect();
+            keys.sort();
+            keys.dedup();
+            out.push('{');
+            for key in keys {
+                out.push_str(&key);
+                out.push(',');
+            }
+            out.push('}');
+        }
+        ExprKind::Tuple(elems) => {
+            out.push('(');
+            list(elems, out);
+            out.push(')');
+        }
+        ExprKind::List(elems) => {
+            out.push('[');
+            list(elems, out);
+            out.push(']');
+        }
+        ExprKind::Lit(lit) => write!(out, "{:?}", lit.kind).unwrap(),
+        ExprKind::TyPlaceholder => out.push('_'),
+        ExprKind::TyNoReturn => out.push('!'),
+        ExprKind::TyVoid => out.push_str("void"),
+        ExprKind::TyAny => out.push_str("any"),
+        ExprKind::TyType => out.push_str("type"),
+        ExprKind::TySelf => out.push_str("Self"),
+        ExprKind::Unit => out.push_str("()"),
+        // Anything else is not expected in type position; fall back to the
+        // variant name so distinct shapes still hash apart.
+        other => {
+            let debug = format!("{other:?}");
+            let variant = debug.split(['(', ' ', '{']).next().unwrap_or_default();
+            write!(out, "<{variant}>").unwrap();
+        }
+    }
+}
+
+fn params_key(params: &[TyParam<'_>], out: &mut String) {
+    for param in params {
+        ty_param_key(param, out);
+        out.push(',');
+    }
+}
+
+fn args_key(args: &[Arg<'_>], out: &mut String) {
+    out.push('<');
+    for arg in args {
+        match arg {
+            Arg::Positional(expr) => ty_key(expr, out),
+            Arg::Named(name, expr) => {
+                write!(out, "{}=", name.name.as_str()).unwrap();
+                ty_key(expr, out);
+            }
+            Arg::Expand(expr) => {
+                out.push_str("...");
+                ty_key(expr, out);
+            }
+            Arg::Implicit(expr) => {
+                out.push('\'');
+                ty_key(expr, out);
+            }
+        }
+        out.push(',');
+    }
+    out.push('>');
+}
+
+/// 64-bit FNV-1a; unlike `DefaultHasher` its output is fixed across
+/// toolchains, which symbol names must be.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
@@ -26,9 +26,14 @@ pub enum PatternKind<'hir> {
     Binding(BindingMode, Ident, Option<&'hir Pattern<'hir>>),
 
     Const(&'hir Expr<'hir>),
-    /// A comptime expression `<comptime_expr>` that is evaluated at compile
-    /// time to produce a constant value used as a match discriminant.
+    /// An embedded expression `< expr >`: matches when the scrutinee is
+    /// equal to the expression's value.  The expression is evaluated at
+    /// compile time when it is constant, otherwise once per match.
     Comptime(&'hir Expr<'hir>),
+    /// A type test `'T`: matches when the scrutinee's value is of type `T`
+    /// (a downcast from a union or `Any`).  Combine with `as` to bind the
+    /// value at that type: `'Int as n`.
+    Type(&'hir Expr<'hir>),
 
     /// Tuple pattern: `(pat1, pat2, …)`.
     Tuple(&'hir [Pattern<'hir>]),
@@ -55,6 +60,12 @@ pub enum PatternKind<'hir> {
     ErrorErr(&'hir Pattern<'hir>),
 
     Or(&'hir [Pattern<'hir>]),
+    /// Matches when the inner pattern does not: `not pat`.  Nothing is
+    /// destructured on success, so the inner pattern binds no names.
+    Not(&'hir Pattern<'hir>),
+    /// `ref pat` over a destructuring pattern: the value is matched in place
+    /// and every name inside binds by reference ([`BindingMode::ByRef`]).
+    /// A plain `ref x` lowers directly to a by-reference binding.
     Ref(&'hir Pattern<'hir>),
     Range(
         Option<&'hir super::expr::Expr<'hir>>,
@@ -62,21 +73,151 @@ pub enum PatternKind<'hir> {
         BoundType,
     ),
 
+    /// `async pat`: the scrutinee must be an async (awaitable) value; the
+    /// inner pattern is matched against its result once awaited.
+    Async(&'hir Pattern<'hir>),
+
     // TODO
-    Async,
     BitVec,
 
     Invalid,
 }
 
+impl<'hir> Pattern<'hir> {
+    /// Call `f` on every name this pattern binds, outermost first.
+    ///
+    /// For `pat as name` the whole-value binding `name` is visited before
+    /// the bindings introduced by `pat`.  Constructor paths (`Point { .. }`,
+    /// `Shape.Circle(r)`) name a definition and bind nothing.
+    pub fn walk_bindings(&self, f: &mut impl FnMut(&Ident)) {
+        match &self.kind {
+            PatternKind::Binding(_, ident, sub) => {
+                f(ident);
+                if let Some(sub) = sub {
+                    sub.walk_bindings(f);
+                }
+            }
+            PatternKind::Tuple(pats) | PatternKind::Or(pats) => {
+                for pat in pats.iter() {
+                    pat.walk_bindings(f);
+                }
+            }
+            PatternKind::Struct(_, fields, _) | PatternKind::AppStruct(_, fields) => {
+                for field in fields.iter() {
+                    field.pat.walk_bindings(f);
+                }
+            }
+            PatternKind::List(pats, rest) => {
+                for pat in pats.iter() {
+                    pat.walk_bindings(f);
+                }
+                if let Some(rest) = rest {
+                    rest.walk_bindings(f);
+                }
+            }
+            PatternKind::AppTuple(_, args) => {
+                for pat in args.iter() {
+                    pat.walk_bindings(f);
+                }
+            }
+            PatternKind::OptionSome(inner)
+            | PatternKind::ErrorOk(inner)
+            | PatternKind::ErrorErr(inner)
+            | PatternKind::Ref(inner)
+            | PatternKind::Async(inner) => inner.walk_bindings(f),
+            PatternKind::Wild
+            | PatternKind::Projection(..)
+            | PatternKind::Const(_)
+            | PatternKind::Comptime(_)
+            | PatternKind::Type(_)
+            | PatternKind::Not(_)
+            | PatternKind::OptionNull
+            | PatternKind::Range(..)
+            | PatternKind::BitVec
+            | PatternKind::Invalid => {}
+        }
+    }
+
+    /// All names bound by this pattern, in [`walk_bindings`](Self::walk_bindings) order.
+    pub fn bindings(&self) -> Vec<Ident> {
+        let mut out = Vec::new();
+        self.walk_bindings(&mut |ident| out.push(ident.clone()));
+        out
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct PatternArm<'hir> {
     pub hir_id: HirId,
     pub pat: Pattern<'hir>,
+    /// Optional `if cond` guard: the arm is taken only when `pat` matches
+    /// and `cond` evaluates to `true`.
+    pub guard: Option<&'hir Expr<'hir>>,
     pub body: &'hir Expr<'hir>,
     pub span: Span,
 }
 
+impl<'hir> PatternArm<'hir> {
+    /// Whether this arm matches every value that reaches it.
+    ///
+    /// A guarded arm is never total: the guard may reject the value even
+    /// when the pattern itself is irrefutable.
+    pub fn is_total(&self) -> bool {
+        self.guard.is_none() && self.pat.is_irrefutable()
+    }
+}
+
+impl<'hir> Pattern<'hir> {
+    /// Whether this pattern matches any value of the scrutinee's type.
+    pub fn is_irrefutable(&self) -> bool {
+        match &self.kind {
+            PatternKind::Wild | PatternKind::Binding(_, _, None) => true,
+            PatternKind::Binding(_, _, Some(sub))
+            | PatternKind::Ref(sub)
+            | PatternKind::Async(sub) => sub.is_irrefutable(),
+            PatternKind::Tuple(pats) => pats.iter().all(Pattern::is_irrefutable),
+            PatternKind::Or(alts) => alts.iter().any(Pattern::is_irrefutable),
+            _ => false,
+        }
+    }
+}
+
+/// Conservative exhaustiveness check over a list of arms.
+///
+/// Without type information a match is known to be exhaustive only if it
+/// has a total arm, or if its unguarded arms cover both sides of a result
+/// (`ok!` / `error e`) or optional (`x?` / `null`) scrutinee.  A negated
+/// side covers the other one (`not null` covers `x?`).  Guarded arms never
+/// contribute coverage.
+pub fn arms_are_exhaustive(arms: &[PatternArm<'_>]) -> bool {
+    if arms.iter().any(PatternArm::is_total) {
+        return true;
+    }
+
+    let (mut ok, mut err, mut some, mut null) = (false, false, false, false);
+    for arm in arms.iter().filter(|arm| arm.guard.is_none()) {
+        let mut alts = vec![&arm.pat];
+        while let Some(pat) = alts.pop() {
+            match &pat.kind {
+                PatternKind::ErrorOk(inner) => ok |= inner.is_irrefutable(),
+                PatternKind::ErrorErr(inner) => err |= inner.is_irrefutable(),
+                PatternKind::OptionSome(inner) => some |= inner.is_irrefutable(),
+                PatternKind::OptionNull => null = true,
+                PatternKind::Or(pats) => alts.extend(pats.iter()),
+                PatternKind::Not(negated) => match &negated.kind {
+                    PatternKind::ErrorOk(inner) => err |= inner.is_irrefutable(),
+                    PatternKind::ErrorErr(inner) => ok |= inner.is_irrefutable(),
+                    PatternKind::OptionSome(inner) => null |= inner.is_irrefutable(),
+                    PatternKind::OptionNull => some = true,
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+    }
+    (ok && err) || (some && null)
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum BoundType {
     Inclusive,
@@ -110,7 +110,10 @@ impl<'sess> CompilerInstance<'sess> {
         let sysroot_vfs = Self::load_sysroot(sess);
 
         CompilerInstance {
-            diag_ctx: DiagnosticContext::new(&sess.source_map),
+            diag_ctx: DiagnosticContext::new(&sess.source_map)
+                .with_project_root(sess.config.root.clone())
+                .with_format(sess.config.diagnostic_format)
+                .with_policy(sess.config.diagnostic_policy.clone()),
             vfs: Vfs::new(&sess.config.name, sess.config.root.clone()),
             sysroot_vfs,
             db,
@@ -3,6 +3,7 @@
 
 use std::path::PathBuf;
 
+use diagnostic::{DiagnosticFormat, DiagnosticPolicy};
 use intrinsic::sysroot::Sysroot;
 use rustc_span::source_map::{FilePathMapping, SourceMap};
 
@@ -17,6 +18,11 @@ pub struct CompilerConfig {
     /// Optional explicit sysroot path (`library/` dir). If `None` the
     /// session will try to discover it automatically.
     pub sysroot_override: Option<PathBuf>,
+    /// How diagnostics are printed: drawn for people, or as JSON lines
+    /// for an editor driving the compiler.
+    pub diagnostic_format: DiagnosticFormat,
+    /// Which warnings are denied, allowed, or all made errors.
+    pub diagnostic_policy: DiagnosticPolicy,
 }
 
 impl CompilerConfig {
@@ -32,6 +38,8 @@ impl CompilerConfig {
                 ".idea".into(),
             ],
             sysroot_override: None,
+            diagnostic_format: DiagnosticFormat::Human,
+            diagnostic_policy: DiagnosticPolicy::default(),
         }
     }
 }
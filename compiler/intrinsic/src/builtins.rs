@@ -0,0 +1,58 @@
+//! Built-in functions – functions the compiler implements itself rather
+//! than through a source definition.
+//!
+//! A call whose callee names a [`BuiltinFn`] (and is not shadowed by a
+//! local) is handled by the compiler. Comptime built-ins such as
+//! `static_assert` and `splice` are evaluated entirely while lowering; no
+//! call is left behind, only the code (if any) they expand to.
+
+use hir::common::Symbol;
+
+/// A function provided by the compiler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuiltinFn {
+    /// `static_assert(cond, msg)`: a compile error with `msg` when the
+    /// compile-time constant `cond` is false.
+    StaticAssert,
+    /// `splice(q)`: the quoted code `q` inserted in place of the call, with
+    /// the bindings it introduces renamed so they cannot capture names at
+    /// the call site.
+    Splice,
+}
+
+/// Every built-in function, for lookups by name.
+pub const ALL_BUILTINS: &[BuiltinFn] = &[BuiltinFn::StaticAssert, BuiltinFn::Splice];
+
+impl BuiltinFn {
+    /// The name the function is called by in source.
+    pub fn name(self) -> &'static str {
+        match self {
+            BuiltinFn::StaticAssert => "static_assert",
+            BuiltinFn::Splice => "splice",
+        }
+    }
+
+    /// The built-in function called `name`, if any.
+    pub fn lookup(name: Symbol) -> Option<BuiltinFn> {
+        ALL_BUILTINS
+            .iter()
+            .copied()
+            .find(|builtin| builtin.name() == name.as_str())
+    }
+
+    /// The number of arguments the function takes.
+    pub fn arity(self) -> usize {
+        match self {
+            BuiltinFn::StaticAssert => 2,
+            BuiltinFn::Splice => 1,
+        }
+    }
+
+    /// Whether the call is evaluated at compile time instead of being
+    /// lowered to runtime code.
+    pub fn is_comptime(self) -> bool {
+        match self {
+            BuiltinFn::StaticAssert | BuiltinFn::Splice => true,
+        }
+    }
+}
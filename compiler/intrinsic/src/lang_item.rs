@@ -63,9 +63,62 @@ pub enum LangItem {
     From,
 }
 
+/// The builtin types: primitives and the special types `Any`, `void`,
+/// `NoReturn` and `type`, all provided by the compiler.
+pub const BUILTIN_TYPES: &[LangItem] = &[
+    LangItem::AnyType,
+    LangItem::U8,
+    LangItem::U16,
+    LangItem::U32,
+    LangItem::U64,
+    LangItem::U128,
+    LangItem::Usize,
+    LangItem::I8,
+    LangItem::I16,
+    LangItem::I32,
+    LangItem::I64,
+    LangItem::I128,
+    LangItem::Isize,
+    LangItem::Integer,
+    LangItem::F16,
+    LangItem::F32,
+    LangItem::F64,
+    LangItem::Real,
+    LangItem::Bool,
+    LangItem::Char,
+    LangItem::Str,
+    LangItem::Void,
+    LangItem::NoReturn,
+    LangItem::Type,
+];
+
+/// The names of everything the compiler provides without a source
+/// definition: the [`BUILTIN_TYPES`] and the values `undefined` and `any`.
+/// An item reusing one of them shadows the builtin, which name resolution
+/// warns about.
+pub const BUILTIN_NAMES: [&str; BUILTIN_TYPES.len() + 2] = {
+    let mut names = [""; BUILTIN_TYPES.len() + 2];
+    let mut i = 0;
+    while i < BUILTIN_TYPES.len() {
+        names[i] = BUILTIN_TYPES[i].name();
+        i += 1;
+    }
+    names[i] = LangItem::Undefined.name();
+    names[i + 1] = LangItem::AnyValue.name();
+    names
+};
+
 impl LangItem {
+    /// The builtin type written `name` in source, if any.
+    pub fn builtin_type(name: &str) -> Option<LangItem> {
+        BUILTIN_TYPES
+            .iter()
+            .copied()
+            .find(|item| item.name() == name)
+    }
+
     /// The canonical string key for this lang item (used in annotations).
-    pub fn name(self) -> &'static str {
+    pub const fn name(self) -> &'static str {
         match self {
             LangItem::AnyType => "Any",
             LangItem::U8 => "u8",
@@ -148,6 +201,14 @@ impl LangItems {
         self.items.get(&item).copied()
     }
 
+    /// The builtin type written `name` in source and its registered
+    /// definition, or `None` if `name` is not a builtin type or the type
+    /// has not been registered.
+    pub fn builtin_type(&self, name: &str) -> Option<(LangItem, LangItemDef)> {
+        let item = LangItem::builtin_type(name)?;
+        Some((item, self.get(item)?))
+    }
+
     /// Returns `true` if the lang item has been registered.
     pub fn has(&self, item: LangItem) -> bool {
         self.items.contains_key(&item)
@@ -168,3 +229,30 @@ impl LangItems {
         self.items.is_empty()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_types_are_found_by_their_source_name() {
+        let mut lang_items = LangItems::new();
+        for &item in BUILTIN_TYPES {
+            lang_items.set(item, LangItemDef::Builtin);
+        }
+
+        let Some((bool_item, LangItemDef::Builtin)) = lang_items.builtin_type("bool") else {
+            panic!("`bool` is a registered builtin type");
+        };
+        assert_eq!(bool_item, LangItem::Bool);
+        let Some((void_item, LangItemDef::Builtin)) = lang_items.builtin_type("void") else {
+            panic!("`void` is a registered builtin type");
+        };
+        assert_eq!(void_item, LangItem::Void);
+
+        assert!(lang_items.builtin_type("Bool2").is_none());
+        // Lang items that are not types are not builtin types.
+        assert!(lang_items.builtin_type("Iterator").is_none());
+        assert!(LangItems::new().builtin_type("bool").is_none());
+    }
+}
@@ -31,13 +31,13 @@
 //! `CompilerInstance::new()` to populate the type context and lang-item
 //! table with compiler-provided definitions.
 
-// pub mod builtins;
+pub mod builtins;
 pub mod lang_item;
 pub mod symbols;
 pub mod sysroot;
 
-// pub use builtins::{ALL_BUILTINS, BuiltinFn, BuiltinFnId, BuiltinTy};
-pub use lang_item::{LangItem, LangItemDef, LangItems};
+pub use builtins::{ALL_BUILTINS, BuiltinFn};
+pub use lang_item::{BUILTIN_NAMES, BUILTIN_TYPES, LangItem, LangItemDef, LangItems};
 pub use sysroot::{PackageId, Sysroot, SysrootPackage};
 
 use middle::TyCtxt;
@@ -64,11 +64,7 @@ pub struct IntrinsicContext {
 pub fn initialize(_ty_ctxt: &TyCtxt) -> IntrinsicContext {
     // Register primitive types as lang items.
     let mut lang_items = LangItems::new();
-    use LangItem::*;
-    for item in [
-        AnyType, U8, U16, U32, U64, U128, Usize, I8, I16, I32, I64, I128, Isize, Integer, F16, F32,
-        F64, Real, Bool, Char, Str, Void, NoReturn, Type,
-    ] {
+    for &item in BUILTIN_TYPES {
         lang_items.set(item, LangItemDef::Builtin);
     }
 
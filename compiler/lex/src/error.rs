@@ -24,9 +24,10 @@ pub enum LexError {
     UnterminatedComment { start: u32, message: String },
     /// Unterminated macro content
     UnterminatedMacro { start: u32, message: String },
-    /// Invalid escape sequence
+    /// Invalid escape sequence, `len` bytes long from its backslash
     InvalidEscape {
         start: u32,
+        len: u32,
         escape_char: char,
         message: String,
     },
@@ -78,8 +79,8 @@ impl LexError {
             LexError::UnterminatedChar { .. } => start + 1,
             // 对于空字符，高亮整个 ''
             LexError::EmptyChar { .. } => start + 2,
-            // 对于无效转义，高亮转义序列 (如 \q 为 2 个字符)
-            LexError::InvalidEscape { .. } => start + 2,
+            // 对于无效转义，高亮整个转义序列 (如 \q 为 2 个字符)
+            LexError::InvalidEscape { len, .. } => start + len,
             // 对于无效数字，只高亮开始位置
             LexError::InvalidNumber { .. } => start + 1,
             // 对于意外字符，高亮整个字符（考虑Unicode）
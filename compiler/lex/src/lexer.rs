@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use super::token::{Token, TokenKind, Index};
 use super::error::{LexError, LexResult};
 use rustc_span::BytePos;
@@ -25,7 +24,6 @@ fn is_identifier_continue(c: char) -> bool {
 pub struct Lexer<'a> {
     src: &'a str,
     cursor: Index,
-    keywords: HashMap<&'static str, TokenKind>,
     base_pos: BytePos,
     remaining: &'a str,
 }
@@ -35,7 +33,6 @@ impl<'a> Lexer<'a> {
         Self {
             src,
             cursor: 0,
-            keywords: Token::keywords(),
             base_pos,
             remaining: src,
         }
@@ -198,6 +195,8 @@ impl<'a> Lexer<'a> {
                 }
             }
 
+            '/' if self.remaining.starts_with("///") => self.recognize_line_doc_comment(start),
+            '/' if self.remaining.starts_with("/**") => self.recognize_block_doc_comment(start),
             '/' => {
                 self.advance();
                 if let Some('=') = self.current_char() {
@@ -335,6 +334,9 @@ impl<'a> Lexer<'a> {
                 }
             }
 
+            // Raw string r"..." / r#"..."#
+            'r' if self.raw_string_hashes().is_some() => self.recognize_raw_string(start),
+
             // 字母、数字、下划线或其他有效 Unicode 标识符字符
             c if is_identifier_start(c) => {
                 self.recognize_identifier(start)
@@ -394,6 +396,10 @@ impl<'a> Lexer<'a> {
     }
 
     fn recognize_string(&mut self, start: Index) -> LexResult<Token> {
+        if self.remaining.starts_with("\"\"\"") {
+            return self.recognize_multiline_string(start);
+        }
+
         // Move past opening quote
         self.advance();
 
@@ -405,65 +411,19 @@ impl<'a> Lexer<'a> {
                     return Ok(Token::new(TokenKind::Str, start, self.cursor));
                 }
                 '\\' => {
-                    // Handle escape sequence
-                    let escape_start = self.cursor; // 记录转义序列的开始位置
+                    // Skip the escaped character so that `\"` does not end the
+                    // string; escapes are checked and decoded by `unescape_str`
+                    // when the literal is lowered.
                     self.advance();
-                    if let Some(escape_char) = self.current_char() {
-                        // Validate escape character
-                        match escape_char {
-                            // 基本转义字符
-                            'n' | 't' | 'r' | '\\' | '\'' | '"' | '0' => {
-                                self.advance();
-                            }
-                            // 更多转义字符
-                            'a' | 'b' | 'f' | 'v' => {
-                                self.advance();
-                            }
-                            // Unicode 转义序列 \x{...}
-                            'x' => {
-                                self.advance();
-                                if let Some('{') = self.current_char() {
-                                    self.advance();
-                                    if let Err(e) = self.consume_unicode_escape(escape_start as u32) {
-                                        return Err(e);
-                                    }
-                                } else {
-                                    return Err(LexError::InvalidEscape {
-                                        start: escape_start as u32,
-                                        escape_char: 'x',
-                                        message: "Unicode escape sequence must be in format \\x{...}".to_string(),
-                                    });
-                                }
-                            }
-                            // 十六进制转义序列 \xHH
-                            'u' => {
-                                self.advance();
-                                if let Some('{') = self.current_char() {
-                                    self.advance();
-                                    if let Err(e) = self.consume_unicode_escape(escape_start as u32) {
-                                        return Err(e);
-                                    }
-                                } else {
-                                    return Err(LexError::InvalidEscape {
-                                        start: escape_start as u32,
-                                        escape_char: 'u',
-                                        message: "Unicode escape sequence must be in format \\u{...}".to_string(),
-                                    });
-                                }
-                            }
-                            _ => {
-                                return Err(LexError::InvalidEscape {
-                                    start: escape_start as u32, // 使用转义序列的开始位置
-                                    escape_char,
-                                    message: format!("Invalid escape sequence '\\{}'", escape_char),
-                                });
-                            }
+                    match self.current_char() {
+                        Some('\n') => {}
+                        Some(_) => self.advance(),
+                        None => {
+                            return Err(LexError::UnterminatedString {
+                                start: start as u32,
+                                message: "Unterminated string literal with escape at end of file".to_string(),
+                            });
                         }
-                    } else {
-                        return Err(LexError::UnterminatedString {
-                            start: start as u32,
-                            message: "Unterminated string literal with escape at end of file".to_string(),
-                        });
                     }
                 }
                 '\n' => {
@@ -485,6 +445,71 @@ impl<'a> Lexer<'a> {
         })
     }
 
+    /// A `"""..."""` string, which may span lines. Escapes are processed as
+    /// in a `"..."` string.
+    fn recognize_multiline_string(&mut self, start: Index) -> LexResult<Token> {
+        // Move past the opening quotes
+        for _ in 0..3 {
+            self.advance();
+        }
+
+        while let Some(c) = self.current_char() {
+            if self.remaining.starts_with("\"\"\"") {
+                for _ in 0..3 {
+                    self.advance();
+                }
+                return Ok(Token::new(TokenKind::Str, start, self.cursor));
+            }
+            self.advance();
+            // Skip the escaped character so that `\"` cannot close the string
+            if c == '\\' {
+                self.advance();
+            }
+        }
+
+        Err(LexError::UnterminatedString {
+            start: start as u32,
+            message: "Unterminated multiline string literal, reached end of file".to_string(),
+        })
+    }
+
+    /// If a raw string `r"`, `r#"`, `r##"`, ... starts at the cursor, the
+    /// number of `#`s it opens with.
+    fn raw_string_hashes(&self) -> Option<usize> {
+        let rest = self.remaining.strip_prefix('r')?;
+        let hashes = rest.len() - rest.trim_start_matches('#').len();
+        rest[hashes..].starts_with('"').then_some(hashes)
+    }
+
+    /// A raw string, which ends at the first `"` followed by as many `#`s as
+    /// it opened with. Nothing inside is an escape, and it may span lines.
+    fn recognize_raw_string(&mut self, start: Index) -> LexResult<Token> {
+        let hashes = self.raw_string_hashes().unwrap_or(0);
+        // Move past `r`, the hashes and the opening quote
+        for _ in 0..hashes + 2 {
+            self.advance();
+        }
+
+        let closing = format!("\"{}", "#".repeat(hashes));
+        match self.remaining.find(&closing) {
+            Some(offset) => {
+                let len = offset + closing.len();
+                self.cursor += len;
+                self.remaining = &self.remaining[len..];
+                Ok(Token::new(TokenKind::Str, start, self.cursor))
+            }
+            None => {
+                // The rest of the file is inside the string
+                self.cursor = self.src.len();
+                self.remaining = "";
+                Err(LexError::UnterminatedString {
+                    start: start as u32,
+                    message: "Unterminated raw string literal, reached end of file".to_string(),
+                })
+            }
+        }
+    }
+
     /// 识别引号、字符字面量或宏内容
     fn recognize_quote_or_char_or_macro(&mut self, start: Index) -> LexResult<Token> {
         // 移动过开始的 '
@@ -596,6 +621,7 @@ impl<'a> Lexer<'a> {
                             } else {
                                 return Err(LexError::InvalidEscape {
                                     start: escape_start as u32,
+                                    len: 2,
                                     escape_char,
                                     message: format!("Unicode escape sequence must be in format \\{}{{...}}", escape_char),
                                 });
@@ -604,6 +630,7 @@ impl<'a> Lexer<'a> {
                         _ => {
                             let error = LexError::InvalidEscape {
                                 start: escape_start as u32,
+                                len: 2,
                                 escape_char,
                                 message: format!("Invalid escape sequence '\\{}'", escape_char),
                             };
@@ -656,6 +683,7 @@ impl<'a> Lexer<'a> {
                     if hex_digits > 6 {
                         return Err(LexError::InvalidEscape {
                             start: escape_start,
+                            len: 2,
                             escape_char: 'x',
                             message: "Unicode escape sequence too long (max 6 hex digits)".to_string(),
                         });
@@ -666,6 +694,7 @@ impl<'a> Lexer<'a> {
                     if hex_digits == 0 {
                         return Err(LexError::InvalidEscape {
                             start: escape_start,
+                            len: 2,
                             escape_char: 'x',
                             message: "Unicode escape sequence must contain at least one hex digit".to_string(),
                         });
@@ -676,6 +705,7 @@ impl<'a> Lexer<'a> {
                 _ => {
                     return Err(LexError::InvalidEscape {
                         start: escape_start,
+                        len: 2,
                         escape_char: 'x',
                         message: "Invalid character in Unicode escape sequence".to_string(),
                     });
@@ -697,20 +727,21 @@ impl<'a> Lexer<'a> {
                 Some('b') | Some('B') => {
                     // 二进制数字
                     self.advance();
-                    return self.recognize_binary_number(start);
+                    return self.recognize_radix_number(start, 2, "binary");
                 }
                 Some('o') | Some('O') => {
                     // 八进制数字
                     self.advance();
-                    return self.recognize_octal_number(start);
+                    return self.recognize_radix_number(start, 8, "octal");
                 }
                 Some('x') | Some('X') => {
                     // 十六进制数字
                     self.advance();
-                    return self.recognize_hex_number(start);
+                    return self.recognize_radix_number(start, 16, "hexadecimal");
                 }
                 Some('0'..='9') | Some('_') => {
-                    // 继续作为十进制数字处理
+                    // 继续作为十进制数字处理, 开头的 0 已被消费
+                    return self.recognize_decimal_number(start, true);
                 }
                 Some('.') => {
                 // Floating point number starting with 0.
@@ -795,100 +826,47 @@ impl<'a> Lexer<'a> {
         Ok(Token::new(token_kind, start, self.cursor))
     }
 
-    fn recognize_binary_number(&mut self, start: Index) -> LexResult<Token> {
+    /// Recognize the digits of a `0b` / `0o` / `0x` literal, after the prefix.
+    ///
+    /// The literal lexes as a plain [`TokenKind::Int`]; its text keeps the
+    /// prefix and separators, so the radix is recovered from the source.
+    fn recognize_radix_number(&mut self, start: Index, radix: u32, name: &str) -> LexResult<Token> {
         let mut has_digits = false;
-        
-        while let Some(c) = self.current_char() {
-            match c {
-                '0' | '1' => {
-                    has_digits = true;
-                    self.advance();
-                }
-                '_' => {
-                    self.advance();
-                    if !matches!(self.current_char(), Some('0' | '1')) {
-                        return Err(LexError::InvalidNumber {
-                            start: start as u32,
-                            message: "Invalid underscore position in binary number".to_string(),
-                        });
-                    }
-                }
-                _ => break,
-            }
-        }
-        
-        if !has_digits {
-            return Err(LexError::InvalidNumber {
-                start: start as u32,
-                message: "Binary number must contain at least one digit".to_string(),
-            });
-        }
-        
-        Ok(Token::new(TokenKind::IntBin, start, self.cursor))
-    }
 
-    fn recognize_octal_number(&mut self, start: Index) -> LexResult<Token> {
-        let mut has_digits = false;
-        
         while let Some(c) = self.current_char() {
-            match c {
-                '0'..='7' => {
-                    has_digits = true;
-                    self.advance();
-                }
-                '_' => {
-                    self.advance();
-                    if !matches!(self.current_char(), Some('0'..='7')) {
-                        return Err(LexError::InvalidNumber {
-                            start: start as u32,
-                            message: "Invalid underscore position in octal number".to_string(),
-                        });
-                    }
+            if c.is_digit(radix) {
+                has_digits = true;
+                self.advance();
+            } else if c == '_' {
+                self.advance();
+                if !self.current_char().is_some_and(|c| c.is_digit(radix)) {
+                    return Err(LexError::InvalidNumber {
+                        start: start as u32,
+                        message: format!("Invalid underscore position in {} number", name),
+                    });
                 }
-                _ => break,
+            } else {
+                break;
             }
         }
-        
+
         if !has_digits {
             return Err(LexError::InvalidNumber {
                 start: start as u32,
-                message: "Octal number must contain at least one digit".to_string(),
+                message: format!("Missing digits in {} number", name),
             });
         }
-        
-        Ok(Token::new(TokenKind::IntOct, start, self.cursor))
-    }
 
-    fn recognize_hex_number(&mut self, start: Index) -> LexResult<Token> {
-        let mut has_digits = false;
-        
-        while let Some(c) = self.current_char() {
-            match c {
-                '0'..='9' | 'a'..='f' | 'A'..='F' => {
-                    has_digits = true;
-                    self.advance();
-                }
-                '_' => {
-                    self.advance();
-                    if !matches!(self.current_char(), Some('0'..='9' | 'a'..='f' | 'A'..='F')) {
-                        return Err(LexError::InvalidNumber {
-                            start: start as u32,
-                            message: "Invalid underscore position in hexadecimal number".to_string(),
-                        });
-                    }
-                }
-                _ => break,
-            }
-        }
-        
-        if !has_digits {
+        // `0b12`: a digit outside the radix is not a new token, but a letter
+        // starts a literal extension, as in `0x10px`.
+        if let Some(c) = self.current_char().filter(|c| c.is_ascii_digit()) {
             return Err(LexError::InvalidNumber {
                 start: start as u32,
-                message: "Hexadecimal number must contain at least one digit".to_string(),
+                message: format!("Invalid digit '{}' in {} number", c, name),
             });
         }
-        
-        Ok(Token::new(TokenKind::IntHex, start, self.cursor))
+
+        Ok(Token::new(TokenKind::Int, start, self.cursor))
     }
 
     fn recognize_scientific_notation(&mut self, start: Index, _has_dot: bool) -> LexResult<Token> {
@@ -946,9 +924,7 @@ impl<'a> Lexer<'a> {
 
         let text = &self.src[start..self.cursor];
 
-        let token_kind = self.keywords.get(text)
-            .copied()
-            .unwrap_or(TokenKind::Id);
+        let token_kind = TokenKind::from_keyword(text).unwrap_or(TokenKind::Id);
 
         Ok(Token::new(token_kind, start, self.cursor))
     }
@@ -960,6 +936,32 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Recognize a line doc comment `/// ...`, up to the end of the line
+    fn recognize_line_doc_comment(&mut self, start: Index) -> LexResult<Token> {
+        self.skip_line_comment();
+        Ok(Token::new(TokenKind::DocComment, start, self.cursor))
+    }
+
+    /// Recognize a block doc comment `/** ... */`
+    fn recognize_block_doc_comment(&mut self, start: Index) -> LexResult<Token> {
+        // Consume '/*', leaving the second '*' so that `/**/` is closed
+        self.advance(); // /
+        self.advance(); // *
+
+        while let Some(c) = self.current_char() {
+            self.advance();
+            if c == '*' && self.current_char() == Some('/') {
+                self.advance(); // Consume '/'
+                return Ok(Token::new(TokenKind::DocComment, start, self.cursor));
+            }
+        }
+
+        Err(LexError::UnterminatedComment {
+            start: start as u32,
+            message: "Unterminated doc comment, expected '*/'".to_string(),
+        })
+    }
+
     /// Recognize block comment {- ... -}
     fn recognize_block_comment(&mut self, start: Index) -> LexResult<Token> {
         // Consume '{-'
@@ -1,6 +1,7 @@
 pub mod error;
 pub mod lexer;
 pub mod token;
+pub mod unescape;
 
 use std::collections::HashMap;
 
@@ -9,6 +10,7 @@ pub use lexer::Lexer;
 use rustc_span::BytePos;
 pub use symbol::Symbol;
 pub use token::{Index, Token, TokenKind};
+pub use unescape::{StrStyle, str_literal_value, unescape_str};
 
 /// Lex `src` into tokens, an index-to-symbol map, and errors.
 ///
@@ -49,3 +51,219 @@ pub fn lex(src: &str, base_pos: BytePos) -> (Vec<Token>, HashMap<usize, Symbol>,
 
     (tokens, symbols, errors)
 }
+
+#[cfg(test)]
+mod tests {
+    use diagnostic::testing::assert_registered;
+    use rustc_span::BytePos;
+
+    use crate::{LexError, StrStyle, TokenKind, lex, str_literal_value, unescape_str};
+
+    /// Kinds and texts of the tokens of `src`, without the SOF / EOF
+    /// sentinels; fails on any lex error.
+    fn tokens(src: &str) -> Vec<(TokenKind, &str)> {
+        let (tokens, _, errors) = lex(src, BytePos(0));
+        assert!(errors.is_empty(), "{:?}", errors);
+        tokens[1..tokens.len() - 1]
+            .iter()
+            .map(|t| (t.kind, &src[t.from..t.to]))
+            .collect()
+    }
+
+    /// Messages of the lex errors of `src`.
+    fn errors(src: &str) -> Vec<String> {
+        let (_, _, errors) = lex(src, BytePos(0));
+        errors.iter().map(LexError::to_string).collect()
+    }
+
+    fn span(lo: u32, hi: u32) -> rustc_span::Span {
+        rustc_span::Span::new(BytePos(lo), BytePos(hi))
+    }
+
+    #[test]
+    fn integer_literals_in_every_radix() {
+        for src in [
+            "1_000_000",
+            "0b1010_0101",
+            "0o7_77",
+            "0xFF_FF",
+            "0XdeadBEEF",
+            "0_1",
+        ] {
+            assert_eq!(tokens(src), [(TokenKind::Int, src)]);
+        }
+        assert_eq!(
+            tokens("0x10px"),
+            [(TokenKind::Int, "0x10"), (TokenKind::Id, "px")]
+        );
+        assert_eq!(
+            tokens("0x1F.max"),
+            [
+                (TokenKind::Int, "0x1F"),
+                (TokenKind::Dot, "."),
+                (TokenKind::Id, "max"),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_integer_literals_are_rejected() {
+        assert_eq!(errors("0x"), ["Missing digits in hexadecimal number"]);
+        assert_eq!(errors("0b "), ["Missing digits in binary number"]);
+        assert_eq!(errors("0o8"), ["Missing digits in octal number"]);
+        assert_eq!(errors("0b102"), ["Invalid digit '2' in binary number"]);
+        assert_eq!(errors("1_000_"), ["Invalid underscore position in number"]);
+        assert_eq!(
+            errors("0xFF_"),
+            ["Invalid underscore position in hexadecimal number"]
+        );
+    }
+
+    #[test]
+    fn every_keyword_round_trips_through_the_table() {
+        for (text, kind) in TokenKind::keywords() {
+            assert_eq!(TokenKind::from_keyword(text), Some(kind));
+            assert_eq!(kind.lexme(), text);
+            assert_eq!(tokens(text), [(kind, text)]);
+        }
+        for text in ["", "x", "fnord", "Let", "selfish", "__flurry_kw_pure"] {
+            assert_eq!(TokenKind::from_keyword(text), None, "{text:?}");
+        }
+    }
+
+    #[test]
+    fn string_escapes_are_decoded() {
+        assert_eq!(
+            unescape_str(r#"a\tb\n\"q\" \\ \0"#).unwrap(),
+            "a\tb\n\"q\" \\ \0"
+        );
+        assert_eq!(unescape_str(r"\u{41}\u{1F600}\x{e9}").unwrap(), "A😀é");
+        assert_eq!(unescape_str("no escapes").unwrap(), "no escapes");
+    }
+
+    #[test]
+    fn invalid_string_escapes_span_the_whole_escape() {
+        let err = unescape_str(r"ab\qc").unwrap_err();
+        assert_eq!(err.message(), "Invalid escape sequence '\\q'");
+        assert_eq!(err.to_span(BytePos(0)), span(2, 4));
+
+        let err = unescape_str(r"x\u{110000}").unwrap_err();
+        assert_eq!(
+            err.message(),
+            "Unicode escape 110000 is not a valid character (must be at most 10FFFF and not a surrogate)"
+        );
+        assert_eq!(err.to_span(BytePos(0)), span(1, 11));
+
+        assert!(unescape_str(r"\u{D800}").is_err());
+        assert!(unescape_str(r"\u{1234567}").is_err());
+        assert!(unescape_str(r"\u{}").is_err());
+        assert!(unescape_str(r"\u41").is_err());
+    }
+
+    #[test]
+    fn escaped_quote_does_not_end_a_string() {
+        assert_eq!(
+            tokens(r#""say \"hi\"" x"#),
+            [(TokenKind::Str, r#""say \"hi\"""#), (TokenKind::Id, "x")]
+        );
+    }
+
+    #[test]
+    fn raw_strings_keep_escapes_as_written() {
+        assert_eq!(
+            tokens(r#"r"a\nb" x"#),
+            [(TokenKind::Str, r#"r"a\nb""#), (TokenKind::Id, "x")]
+        );
+        assert_eq!(str_literal_value(r#"r"a\nb""#).unwrap(), r"a\nb");
+
+        let literal = r###"r#"say "hi" \d+"#"###;
+        assert_eq!(
+            tokens(&format!("{} x", literal)),
+            [(TokenKind::Str, literal), (TokenKind::Id, "x")]
+        );
+        assert_eq!(StrStyle::of(literal).0, StrStyle::Raw(1));
+        assert_eq!(str_literal_value(literal).unwrap(), r#"say "hi" \d+"#);
+
+        // `r` alone is still an identifier.
+        assert_eq!(
+            tokens("r.len"),
+            [
+                (TokenKind::Id, "r"),
+                (TokenKind::Dot, "."),
+                (TokenKind::Id, "len"),
+            ]
+        );
+        assert_eq!(
+            errors(r##"r#"open""##),
+            ["Unterminated raw string literal, reached end of file"]
+        );
+    }
+
+    #[test]
+    fn multiline_strings_span_lines() {
+        let literal = r#""""one
+  "two"\t
+three""""#;
+        assert_eq!(
+            tokens(&format!("{} x", literal)),
+            [(TokenKind::Str, literal), (TokenKind::Id, "x")]
+        );
+        assert_eq!(StrStyle::of(literal).0, StrStyle::Multiline);
+        assert_eq!(
+            str_literal_value(literal).unwrap(),
+            "one\n  \"two\"\t\nthree"
+        );
+
+        // Escape positions count from the start of the whole literal.
+        let err = str_literal_value(r#""""a\q""""#).unwrap_err();
+        assert_eq!(err.to_span(BytePos(0)), span(4, 6));
+        assert_eq!(
+            errors("\"\"\"never closed\n"),
+            ["Unterminated multiline string literal, reached end of file"]
+        );
+    }
+
+    #[test]
+    fn every_error_is_registered_under_its_name() {
+        let errors = [
+            LexError::UnterminatedString {
+                start: 0,
+                message: String::new(),
+            },
+            LexError::UnterminatedChar {
+                start: 0,
+                message: String::new(),
+            },
+            LexError::UnterminatedComment {
+                start: 0,
+                message: String::new(),
+            },
+            LexError::UnterminatedMacro {
+                start: 0,
+                message: String::new(),
+            },
+            LexError::InvalidEscape {
+                start: 0,
+                len: 2,
+                escape_char: 'q',
+                message: String::new(),
+            },
+            LexError::InvalidNumber {
+                start: 0,
+                message: String::new(),
+            },
+            LexError::UnexpectedChar {
+                position: 0,
+                char: '$',
+                message: String::new(),
+            },
+            LexError::EmptyChar {
+                start: 0,
+                message: String::new(),
+            },
+        ];
+        for error in &errors {
+            assert_registered(error);
+        }
+    }
+}
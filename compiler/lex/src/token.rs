@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 pub type Index = usize;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -63,10 +61,7 @@ pub enum TokenKind {
 
     // primitive literals
     Str,     // "..."
-    Int,     // 123
-    IntBin,  // 0b1010
-    IntOct,  // 0o777
-    IntHex,  // 0xFF
+    Int,     // 123, 1_000, 0b1010, 0o777, 0xFF_FF
     Real,    // 123.45
     RealSci, // 1.23e-4 (scientific notation)
     Char,    // 'a' or '\n' or '\x{1F600}'
@@ -165,10 +160,11 @@ pub enum TokenKind {
     MacroContent, // macro content, 当lexer碰到`'`字符后, 向后看到第一个非空字符为 `{` 的时候,
     // 认为是宏内容, 开始识别macro content, 并按栈式处理遇到的 `{` 和 `}`, 当最后一个 `}` 闭合栈时,
     // 结束macro content, 这时的 `from` 是 `'` 的位置, `to` 是最后一个 `}` 的位置
-    Comment, // -- comment or {- comment -}
-    Invalid, // invalid token
-    Sof,     // start of file
-    Eof,     // end of file
+    Comment,    // -- comment or {- comment -}
+    DocComment, // /// doc comment or /** doc comment */
+    Invalid,    // invalid token
+    Sof,        // start of file
+    Eof,        // end of file
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -185,6 +181,16 @@ impl AsRef<[TokenKind]> for TokenKind {
 }
 
 impl TokenKind {
+    /// The keyword spelled `text`, or `None` if `text` is an identifier.
+    pub fn from_keyword(text: &str) -> Option<TokenKind> {
+        keyword_kind(text)
+    }
+
+    /// Every keyword with its text.
+    pub fn keywords() -> impl Iterator<Item = (&'static str, TokenKind)> {
+        KEYWORDS.iter().copied()
+    }
+
     pub fn lexme(self) -> &'static str {
         match self {
             TokenKind::Plus => "+",
@@ -332,14 +338,12 @@ impl TokenKind {
             TokenKind::Id => "<identifier>",
             TokenKind::MacroContent => "<macro content>",
             TokenKind::Comment => "<comment>",
+            TokenKind::DocComment => "<doc comment>",
             TokenKind::Invalid => "<invalid>",
             TokenKind::Sof => "<start of file>",
             TokenKind::Eof => "<end of file>",
             TokenKind::Str => "<string literal>",
             TokenKind::Int => "<integer literal>",
-            TokenKind::IntBin => "<binary integer literal>",
-            TokenKind::IntOct => "<octal integer literal>",
-            TokenKind::IntHex => "<hexadecimal integer literal>",
             TokenKind::Real => "<real literal>",
             TokenKind::RealSci => "<scientific notation literal>",
             TokenKind::Char => "<character literal>",
@@ -351,97 +355,114 @@ impl Token {
     pub fn new(kind: TokenKind, from: Index, to: Index) -> Self {
         Self { kind, from, to }
     }
+}
 
-    pub fn keywords() -> HashMap<&'static str, TokenKind> {
-        let mut map = HashMap::new();
-        map.insert("and", TokenKind::And);
-        map.insert("as", TokenKind::As);
-        map.insert("assoc", TokenKind::Assoc);
-        map.insert("asserts", TokenKind::Asserts);
-        map.insert("assumes", TokenKind::Assumes);
-        map.insert("async", TokenKind::Async);
-        map.insert("atomic", TokenKind::Atomic);
-        map.insert("axiom", TokenKind::Axiom);
-        map.insert("await", TokenKind::Await);
-        map.insert("bool", TokenKind::Bool);
-        map.insert("break", TokenKind::Break);
-        map.insert("case", TokenKind::Case);
-        map.insert("catch", TokenKind::Catch);
-        map.insert("comptime", TokenKind::Comptime);
-        map.insert("const", TokenKind::Const);
-        map.insert("continue", TokenKind::Continue);
-        map.insert("decreases", TokenKind::Decreases);
-        map.insert("default", TokenKind::Default);
-        map.insert("define", TokenKind::Define);
-        map.insert("derive", TokenKind::Derive);
-        map.insert("do", TokenKind::Do);
-        map.insert("dyn", TokenKind::Dyn);
-        map.insert("effect", TokenKind::Effect);
-        map.insert("else", TokenKind::Else);
-        map.insert("ensures", TokenKind::Ensures);
-        map.insert("enum", TokenKind::Enum);
-        map.insert("error", TokenKind::Error);
-        map.insert("exists", TokenKind::Exists);
-        map.insert("extend", TokenKind::Extend);
-        map.insert("extern", TokenKind::Extern);
-        map.insert("false", TokenKind::False);
-        map.insert("fn", TokenKind::Fn);
-        map.insert("Fn", TokenKind::FnCap);
-        map.insert("for", TokenKind::For);
-        map.insert("forall", TokenKind::Forall);
-        map.insert("ghost", TokenKind::Ghost);
-        map.insert("handles", TokenKind::Handles);
-        map.insert("if", TokenKind::If);
-        map.insert("implicit", TokenKind::Implicit);
-        map.insert("impl", TokenKind::Impl);
-        map.insert("in", TokenKind::In);
-        map.insert("inline", TokenKind::Inline);
-        map.insert("invariant", TokenKind::Invariant);
-        map.insert("is", TokenKind::Is);
-        map.insert("itself", TokenKind::Itself);
-        map.insert("lambda", TokenKind::Lambda);
-        map.insert("lemma", TokenKind::Lemma);
-        map.insert("let", TokenKind::Let);
-        map.insert("lift", TokenKind::Lift);
-        map.insert("match", TokenKind::Match);
-        map.insert("matches", TokenKind::Matches);
-        map.insert("move", TokenKind::Move);
-        map.insert("mod", TokenKind::Mod);
-        map.insert("newtype", TokenKind::Newtype);
-        map.insert("not", TokenKind::Not);
-        map.insert("null", TokenKind::Null);
-        map.insert("opaque", TokenKind::Opaque);
-        map.insert("opens", TokenKind::Opens);
-        map.insert("or", TokenKind::Or);
-        map.insert("outcomes", TokenKind::Outcomes);
-        map.insert("predicate", TokenKind::Predicate);
-        map.insert("private", TokenKind::Private);
-        map.insert("pub", TokenKind::Pub);
-        map.insert("pure", TokenKind::Pure);
-        map.insert("quote", TokenKind::KwQuote);
-        map.insert("ref", TokenKind::Ref);
-        map.insert("refines", TokenKind::Refines);
-        map.insert("requires", TokenKind::Requires);
-        map.insert("resume", TokenKind::Resume);
-        map.insert("return", TokenKind::Return);
-        map.insert("self", TokenKind::SelfLower);
-        map.insert("Self", TokenKind::SelfCap);
-        map.insert("spec", TokenKind::Spec);
-        map.insert("static", TokenKind::Static);
-        map.insert("struct", TokenKind::Struct);
-        map.insert("test", TokenKind::Test);
-        map.insert("trait", TokenKind::Trait);
-        map.insert("true", TokenKind::True);
-        map.insert("typealias", TokenKind::Typealias);
-        map.insert("union", TokenKind::Union);
-        map.insert("unsafe", TokenKind::Unsafe);
-        map.insert("undefined", TokenKind::Undefined);
-        map.insert("use", TokenKind::Use);
-        map.insert("verified", TokenKind::Verified);
-        map.insert("when", TokenKind::When);
-        map.insert("while", TokenKind::While);
-        map.insert("where", TokenKind::Where);
-        map.insert("_", TokenKind::Underscore);
-        map
-    }
+/// Define [`KEYWORDS`] and [`keyword_kind`] from one list of keyword
+/// texts and their token kinds.
+macro_rules! keywords {
+    ($($text:literal => $kind:ident,)*) => {
+        /// Keyword texts and their token kinds.
+        const KEYWORDS: &[(&str, TokenKind)] = &[$(($text, TokenKind::$kind),)*];
+
+        /// The keyword spelled `text`. A `match` on the text compiles to a
+        /// dispatch on its length and bytes, which beats both hashing the
+        /// text and a binary search over [`KEYWORDS`].
+        fn keyword_kind(text: &str) -> Option<TokenKind> {
+            match text {
+                $($text => Some(TokenKind::$kind),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+keywords! {
+    "Fn" => FnCap,
+    "Self" => SelfCap,
+    "_" => Underscore,
+    "and" => And,
+    "as" => As,
+    "asserts" => Asserts,
+    "assoc" => Assoc,
+    "assumes" => Assumes,
+    "async" => Async,
+    "atomic" => Atomic,
+    "await" => Await,
+    "axiom" => Axiom,
+    "bool" => Bool,
+    "break" => Break,
+    "case" => Case,
+    "catch" => Catch,
+    "comptime" => Comptime,
+    "const" => Const,
+    "continue" => Continue,
+    "decreases" => Decreases,
+    "default" => Default,
+    "define" => Define,
+    "derive" => Derive,
+    "do" => Do,
+    "dyn" => Dyn,
+    "effect" => Effect,
+    "else" => Else,
+    "ensures" => Ensures,
+    "enum" => Enum,
+    "error" => Error,
+    "exists" => Exists,
+    "extend" => Extend,
+    "extern" => Extern,
+    "false" => False,
+    "fn" => Fn,
+    "for" => For,
+    "forall" => Forall,
+    "ghost" => Ghost,
+    "handles" => Handles,
+    "if" => If,
+    "impl" => Impl,
+    "implicit" => Implicit,
+    "in" => In,
+    "inline" => Inline,
+    "invariant" => Invariant,
+    "is" => Is,
+    "itself" => Itself,
+    "lambda" => Lambda,
+    "lemma" => Lemma,
+    "let" => Let,
+    "lift" => Lift,
+    "match" => Match,
+    "matches" => Matches,
+    "mod" => Mod,
+    "move" => Move,
+    "newtype" => Newtype,
+    "not" => Not,
+    "null" => Null,
+    "opaque" => Opaque,
+    "opens" => Opens,
+    "or" => Or,
+    "outcomes" => Outcomes,
+    "predicate" => Predicate,
+    "private" => Private,
+    "pub" => Pub,
+    "pure" => Pure,
+    "quote" => KwQuote,
+    "ref" => Ref,
+    "refines" => Refines,
+    "requires" => Requires,
+    "resume" => Resume,
+    "return" => Return,
+    "self" => SelfLower,
+    "spec" => Spec,
+    "static" => Static,
+    "struct" => Struct,
+    "test" => Test,
+    "trait" => Trait,
+    "true" => True,
+    "typealias" => Typealias,
+    "undefined" => Undefined,
+    "union" => Union,
+    "unsafe" => Unsafe,
+    "use" => Use,
+    "verified" => Verified,
+    "when" => When,
+    "where" => Where,
+    "while" => While,
 }
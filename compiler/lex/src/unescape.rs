@@ -0,0 +1,187 @@
+//! Decoding the escape sequences of string literals.
+//!
+//! The lexer only finds where a string literal ends; what its escapes mean
+//! is decided here, when the literal is lowered to its runtime value.
+//!
+//! Every string literal is a [`TokenKind::Str`] token; its [`StrStyle`],
+//! read off its delimiters, says whether escapes are processed at all.
+//!
+//! [`TokenKind::Str`]: crate::TokenKind::Str
+
+use std::ops::Range;
+
+use crate::LexError;
+
+/// How a string literal is delimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StrStyle {
+    /// `"..."`
+    Cooked,
+    /// `"""..."""`, which may span lines; escapes are processed as in a
+    /// cooked string.
+    Multiline,
+    /// `r"..."`, `r#"..."#`, ... with that many `#`s; nothing inside is an
+    /// escape.
+    Raw(usize),
+}
+
+impl StrStyle {
+    /// The style of the string literal token `text` (delimiters included)
+    /// and the byte range of its contents.
+    pub fn of(text: &str) -> (StrStyle, Range<usize>) {
+        if let Some(rest) = text.strip_prefix('r') {
+            let hashes = rest.len() - rest.trim_start_matches('#').len();
+            // `r`, the hashes and a quote on the left; a quote and the
+            // hashes on the right.
+            let start = (hashes + 2).min(text.len());
+            let end = text.len().saturating_sub(hashes + 1).max(start);
+            return (StrStyle::Raw(hashes), start..end);
+        }
+        if text.len() >= 6 && text.starts_with(r#"""""#) && text.ends_with(r#"""""#) {
+            return (StrStyle::Multiline, 3..text.len() - 3);
+        }
+        let start = usize::from(text.starts_with('"'));
+        let end = if text.len() > start && text.ends_with('"') {
+            text.len() - 1
+        } else {
+            text.len()
+        };
+        (StrStyle::Cooked, start..end)
+    }
+}
+
+/// The value of the string literal token `text`, delimiters included.
+///
+/// An invalid escape is returned as with [`unescape_str`], but positioned
+/// relative to the start of `text`.
+pub fn str_literal_value(text: &str) -> Result<String, LexError> {
+    let (style, contents) = StrStyle::of(text);
+    let body = &text[contents.clone()];
+    match style {
+        StrStyle::Raw(_) => Ok(body.to_string()),
+        StrStyle::Cooked | StrStyle::Multiline => unescape_str(body).map_err(|mut err| {
+            if let LexError::InvalidEscape { start, .. } = &mut err {
+                *start += contents.start as u32;
+            }
+            err
+        }),
+    }
+}
+
+/// Decode the escape sequences in `text`, the contents of a string literal
+/// without its quotes.
+///
+/// The first invalid escape is returned as [`LexError::InvalidEscape`],
+/// positioned relative to the start of `text` and spanning the whole escape.
+pub fn unescape_str(text: &str) -> Result<String, LexError> {
+    let mut out = String::with_capacity(text.len());
+    let mut pos = 0;
+    while let Some(offset) = text[pos..].find('\\') {
+        let start = pos + offset;
+        out.push_str(&text[pos..start]);
+        let (c, len) = unescape_one(&text[start..], start)?;
+        out.push(c);
+        pos = start + len;
+    }
+    out.push_str(&text[pos..]);
+    Ok(out)
+}
+
+/// Decode the escape sequence at the start of `escape`, whose backslash is
+/// at byte `start` of the literal. Returns the character and the length of
+/// the sequence in bytes.
+fn unescape_one(escape: &str, start: usize) -> Result<(char, usize), LexError> {
+    let invalid = |len: usize, escape_char: char, message: String| LexError::InvalidEscape {
+        start: start as u32,
+        len: len as u32,
+        escape_char,
+        message,
+    };
+
+    let Some(escape_char) = escape[1..].chars().next() else {
+        return Err(invalid(
+            1,
+            '\\',
+            "Escape sequence at the end of the string".to_string(),
+        ));
+    };
+    let c = match escape_char {
+        'n' => '\n',
+        't' => '\t',
+        'r' => '\r',
+        '\\' => '\\',
+        '\'' => '\'',
+        '"' => '"',
+        '0' => '\0',
+        'a' => '\x07',
+        'b' => '\x08',
+        'f' => '\x0C',
+        'v' => '\x0B',
+        'x' | 'u' => {
+            return unescape_unicode(escape, escape_char)
+                .map_err(|(len, message)| invalid(len, escape_char, message));
+        }
+        _ => {
+            return Err(invalid(
+                1 + escape_char.len_utf8(),
+                escape_char,
+                format!("Invalid escape sequence '\\{}'", escape_char),
+            ));
+        }
+    };
+    Ok((c, 2))
+}
+
+/// Decode a `\u{...}` (or `\x{...}`) escape at the start of `escape`. On
+/// error, returns the length of the bad part and a message.
+fn unescape_unicode(escape: &str, escape_char: char) -> Result<(char, usize), (usize, String)> {
+    let Some(body) = escape[2..].strip_prefix('{') else {
+        return Err((
+            2,
+            format!(
+                "Unicode escape sequence must be in format \\{}{{...}}",
+                escape_char
+            ),
+        ));
+    };
+    let Some(close) = body.find('}') else {
+        return Err((
+            escape.len(),
+            "Unterminated Unicode escape sequence".to_string(),
+        ));
+    };
+    let digits = &body[..close];
+    // `\u{` + digits + `}`
+    let len = close + 4;
+
+    if digits.is_empty() {
+        return Err((
+            len,
+            "Unicode escape sequence must contain at least one hex digit".to_string(),
+        ));
+    }
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err((
+            len,
+            "Invalid character in Unicode escape sequence".to_string(),
+        ));
+    }
+    if digits.len() > 6 {
+        return Err((
+            len,
+            "Unicode escape sequence too long (max 6 hex digits)".to_string(),
+        ));
+    }
+
+    let value = u32::from_str_radix(digits, 16).expect("at most 6 hex digits");
+    match char::from_u32(value) {
+        Some(c) => Ok((c, len)),
+        None => Err((
+            len,
+            format!(
+                "Unicode escape {:X} is not a valid character (must be at most 10FFFF and not a surrogate)",
+                value
+            ),
+        )),
+    }
+}
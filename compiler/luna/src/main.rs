@@ -59,14 +59,25 @@ fn main() {
 
     {
         let ast = instance.vfs.get_ast(file_id).expect("AST not found");
-        let lisp = ast.dump_to_s_expression(ast.root, &sess.source_map);
-        std::fs::write("ast.lisp", &lisp).expect("failed to write ast.lisp");
-        println!("ast dumped to ast.lisp ({} nodes)", ast.nodes.len());
+        match ast.dump_to_s_expression(ast.root, &sess.source_map) {
+            Ok(lisp) => {
+                std::fs::write("ast.lisp", &lisp).expect("failed to write ast.lisp");
+                println!("ast dumped to ast.lisp ({} nodes)", ast.nodes.len());
+            }
+            Err(err) => println!("ast dump failed: {}", err),
+        }
     }
 
     // ── Name resolution ──────────────────────────────────────────────────────
-    let module_tree =
-        resolve::build_module_tree(&sess.source_map, &instance.diag_ctx, &mut instance.vfs);
+    let options = resolve::BuildOptions {
+        builtin_names: &interface::intrinsic::BUILTIN_NAMES,
+    };
+    let module_tree = resolve::build_module_tree_with(
+        &sess.source_map,
+        &instance.diag_ctx,
+        &mut instance.vfs,
+        options,
+    );
     if !module_tree.errors.is_empty() {
         println!("resolve: {} error(s)", module_tree.errors.len());
         for err in &module_tree.errors {
@@ -79,10 +90,13 @@ fn main() {
         module_tree.scope_tree.len(),
     );
 
+    std::fs::write("scope.lisp", module_tree.dump_scope_tree())
+        .expect("failed to write scope.lisp");
+    std::fs::write("vfs.lisp", instance.vfs.dump_to_lisp()).expect("failed to write vfs.lisp");
+    println!("scope tree dumped to scope.lisp, VFS to vfs.lisp");
+
     let file_scope = module_tree
-        .file_scopes
-        .get(&file_id)
-        .copied()
+        .file_scope(0, file_id)
         .unwrap_or(resolve::ScopeId::ROOT);
 
     // ── Register providers ────────────────────────────────────────────────────
@@ -129,4 +143,14 @@ fn main() {
     std::fs::write("hir.lisp", &hir_lisp).expect("failed to write hir.lisp");
     println!("HIR dumped to hir.lisp");
     println!("{}", hir_lisp);
+
+    // ── Diagnostics ───────────────────────────────────────────────────────────
+    instance.diag_ctx.finish();
+    std::fs::write("diagnostics.json", instance.diag_ctx.emitted_json())
+        .expect("failed to write diagnostics.json");
+    println!(
+        "diagnostics dumped to diagnostics.json: {} error(s), {} warning(s)",
+        instance.diag_ctx.error_count(),
+        instance.diag_ctx.warning_count(),
+    );
 }
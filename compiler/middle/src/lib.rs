@@ -24,12 +24,14 @@
 pub mod hir_package;
 pub mod hir_query;
 pub mod queries;
+pub mod symbol_index;
 pub mod ty;
 
 // Convenience re-exports for the most commonly used items.
 pub use hir_package::HirPackageBox;
 pub use hir_query::HirQueryInput;
 pub use queries::{Db, LunaDatabase, Providers};
+pub use symbol_index::SymbolIndex;
 pub use ty::{
     AdtDef, CommonTypes, FieldDef, InferTy, NFId, PrimTy, Ty, TyCtxt, TyInterner, TyKind,
 };
@@ -0,0 +1,440 @@
+//! Persistent, per-file symbol index.
+//!
+//! [`SymbolIndex`] maps the qualified name of every definition
+//! (`utils.helper`) to where it is defined and where it is referenced, so
+//! go-to-definition and find-references can be answered after a restart
+//! without recompiling the project.
+//!
+//! The index is keyed by file.  When a file changes, [`SymbolIndex::update_file`]
+//! re-collects that file's entries from the freshly lowered HIR and replaces
+//! only them; every other file keeps its saved entries.
+//!
+//! # On-disk format
+//!
+//! A line-oriented text file:
+//!
+//! ```text
+//! luna-symbol-index 1
+//! file src/utils.fl
+//! def fn 3 12 40 utils.helper
+//! ref 88 94 utils.helper
+//! ```
+//!
+//! Offsets are byte offsets relative to the start of their file, so they stay
+//! valid across sessions regardless of the order files are loaded into the
+//! `SourceMap`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+use hir::{
+    ExprKind, Package,
+    common::Symbol,
+    expr::Expr,
+    hir_id::OwnerId,
+    item::{Item, ItemKind},
+};
+use resolve::{Resolver, ScopeId, scope::ScopeKind};
+use rustc_span::{FileNameDisplayPreference, SourceFile, Span};
+
+const HEADER: &str = "luna-symbol-index 1";
+
+/// The kind of definition a [`SymbolDef`] describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Function,
+    Struct,
+    Enum,
+    Module,
+    Impl,
+    Trait,
+    TypeAlias,
+    Newtype,
+    Const,
+}
+
+impl SymbolKind {
+    fn of(item: &Item<'_>) -> Option<Self> {
+        Some(match item.kind {
+            ItemKind::Fn(..) => SymbolKind::Function,
+            ItemKind::Struct(_) => SymbolKind::Struct,
+            ItemKind::Enum(_) => SymbolKind::Enum,
+            ItemKind::Mod(_) => SymbolKind::Module,
+            ItemKind::Impl(_) => SymbolKind::Impl,
+            ItemKind::Trait(_) => SymbolKind::Trait,
+            ItemKind::TypeAlias(_) => SymbolKind::TypeAlias,
+            ItemKind::Newtype(_) => SymbolKind::Newtype,
+            ItemKind::Const(..) => SymbolKind::Const,
+            ItemKind::Use(_) | ItemKind::Invalid => return None,
+        })
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            SymbolKind::Function => "fn",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Enum => "enum",
+            SymbolKind::Module => "mod",
+            SymbolKind::Impl => "impl",
+            SymbolKind::Trait => "trait",
+            SymbolKind::TypeAlias => "typealias",
+            SymbolKind::Newtype => "newtype",
+            SymbolKind::Const => "const",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        Some(match s {
+            "fn" => SymbolKind::Function,
+            "struct" => SymbolKind::Struct,
+            "enum" => SymbolKind::Enum,
+            "mod" => SymbolKind::Module,
+            "impl" => SymbolKind::Impl,
+            "trait" => SymbolKind::Trait,
+            "typealias" => SymbolKind::TypeAlias,
+            "newtype" => SymbolKind::Newtype,
+            "const" => SymbolKind::Const,
+            _ => return None,
+        })
+    }
+}
+
+/// A definition recorded in the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolDef {
+    /// Dot-separated path from the package root, e.g. `utils.helper`.
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Raw `LocalDefId` of the item's owner in the package the entry was
+    /// collected from.  Only meaningful while that package is alive.
+    pub owner: u32,
+    /// File-relative byte range of the definition.
+    pub lo: u32,
+    pub hi: u32,
+}
+
+/// A use of a definition, recorded by the qualified name it resolves to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolRef {
+    pub target: String,
+    pub lo: u32,
+    pub hi: u32,
+}
+
+/// A file-relative source location returned by index lookups.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolLocation {
+    pub file: String,
+    pub lo: u32,
+    pub hi: u32,
+}
+
+/// Everything the index knows about one file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileSymbols {
+    pub defs: Vec<SymbolDef>,
+    pub refs: Vec<SymbolRef>,
+}
+
+/// Project-wide symbol index; see the module documentation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolIndex {
+    files: BTreeMap<String, FileSymbols>,
+}
+
+impl SymbolIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Re-collect the entries of `source_file` from the lowered `package`
+    /// and replace whatever the index held for that file before.
+    ///
+    /// `file_scope` is the resolver scope owning the file's top-level names
+    /// (`ModuleTree::file_scope`).
+    pub fn update_file(
+        &mut self,
+        source_file: &SourceFile,
+        package: &Package<'_>,
+        resolver: &Resolver<'_>,
+        file_scope: ScopeId,
+    ) {
+        let symbols = FileSymbols::collect(source_file, package, resolver, file_scope);
+        self.files.insert(file_key(source_file), symbols);
+    }
+
+    /// Forget everything recorded for `file` (e.g. after it was deleted).
+    pub fn remove_file(&mut self, file: &str) {
+        self.files.remove(file);
+    }
+
+    /// The entries recorded for `file`.
+    pub fn file(&self, file: &str) -> Option<&FileSymbols> {
+        self.files.get(file)
+    }
+
+    /// Go-to-definition: the file and definition named `name`.
+    pub fn definition(&self, name: &str) -> Option<(&str, &SymbolDef)> {
+        self.files.iter().find_map(|(file, symbols)| {
+            symbols
+                .defs
+                .iter()
+                .find(|def| def.name == name)
+                .map(|def| (file.as_str(), def))
+        })
+    }
+
+    /// Every recorded use of the definition named `name`, ordered by file
+    /// and position.
+    pub fn find_references(&self, name: &str) -> Vec<SymbolLocation> {
+        let mut locations = Vec::new();
+        for (file, symbols) in &self.files {
+            for r in symbols.refs.iter().filter(|r| r.target == name) {
+                locations.push(SymbolLocation {
+                    file: file.clone(),
+                    lo: r.lo,
+                    hi: r.hi,
+                });
+            }
+        }
+        locations
+    }
+
+    /// Write the index to `path`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, self.serialize())
+    }
+
+    /// Read an index previously written by [`save`](Self::save).
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::deserialize(&std::fs::read_to_string(path)?)
+    }
+
+    fn serialize(&self) -> String {
+        let mut out = String::new();
+        out.push_str(HEADER);
+        out.push('\n');
+        for (file, symbols) in &self.files {
+            let _ = writeln!(out, "file {}", file);
+            for def in &symbols.defs {
+                let _ = writeln!(
+                    out,
+                    "def {} {} {} {} {}",
+                    def.kind.as_str(),
+                    def.owner,
+                    def.lo,
+                    def.hi,
+                    def.name
+                );
+            }
+            for r in &symbols.refs {
+                let _ = writeln!(out, "ref {} {} {}", r.lo, r.hi, r.target);
+            }
+        }
+        out
+    }
+
+    fn deserialize(text: &str) -> io::Result<Self> {
+        let invalid = |line: usize, what: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("symbol index line {}: {}", line + 1, what),
+            )
+        };
+
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, HEADER)) => {}
+            _ => return Err(invalid(0, "missing or unsupported header")),
+        }
+
+        let mut index = SymbolIndex::new();
+        let mut current: Option<&mut FileSymbols> = None;
+        for (n, line) in lines {
+            if let Some(file) = line.strip_prefix("file ") {
+                current = Some(index.files.entry(file.to_string()).or_default());
+                continue;
+            }
+            let symbols = current
+                .as_deref_mut()
+                .ok_or_else(|| invalid(n, "entry outside of a `file` section"))?;
+            let fields: Vec<&str> = line.split(' ').collect();
+            let num = |s: &str| s.parse::<u32>().map_err(|_| invalid(n, "bad number"));
+            match fields.as_slice() {
+                ["def", kind, owner, lo, hi, name] => symbols.defs.push(SymbolDef {
+                    name: name.to_string(),
+                    kind: SymbolKind::from_str(kind).ok_or_else(|| invalid(n, "bad kind"))?,
+                    owner: num(owner)?,
+                    lo: num(lo)?,
+                    hi: num(hi)?,
+                }),
+                ["ref", lo, hi, target] => symbols.refs.push(SymbolRef {
+                    target: target.to_string(),
+                    lo: num(lo)?,
+                    hi: num(hi)?,
+                }),
+                [""] => {}
+                _ => return Err(invalid(n, "unrecognised entry")),
+            }
+        }
+        Ok(index)
+    }
+}
+
+/// The key a source file is stored under: its local display path.
+pub fn file_key(source_file: &SourceFile) -> String {
+    source_file
+        .name
+        .display(FileNameDisplayPreference::Local)
+        .to_string_lossy()
+        .into_owned()
+}
+
+impl FileSymbols {
+    /// Collect the definitions and references of `source_file`.
+    ///
+    /// References are found by resolving the names used in each of the
+    /// file's bodies from the scope of the definition owning it, so a body
+    /// in an inline `mod` or an `impl` sees that scope's names; `file_scope`
+    /// is used for a body whose owner opens no scope. Names bound locally
+    /// (parameters, `let`s, pattern bindings) are skipped.
+    pub fn collect(
+        source_file: &SourceFile,
+        package: &Package<'_>,
+        resolver: &Resolver<'_>,
+        file_scope: ScopeId,
+    ) -> Self {
+        let start = source_file.start_pos.0;
+        let end = source_file.end_pos.0;
+        let in_file = |span: Span| start <= span.lo().0 && span.lo().0 < end;
+        let prefix = scope_path(resolver, file_scope);
+
+        let mut symbols = FileSymbols::default();
+        for (owner, info) in package.owners() {
+            let Some(item) = info.node.as_item() else {
+                continue;
+            };
+            let Some(kind) = SymbolKind::of(item) else {
+                continue;
+            };
+            // The parentless module of each file stands for the file scope,
+            // whose name comes from the resolver prefix.
+            let is_file_root = |id: OwnerId| package.parent_item(id).is_none();
+            if !in_file(item.span) || is_file_root(owner) {
+                continue;
+            }
+            let mut segments = vec![item.ident.name.as_str().to_string()];
+            let mut cursor = owner;
+            while let Some(parent) = package.parent_item(cursor) {
+                if is_file_root(parent) {
+                    break;
+                }
+                if let Some(parent_item) = package.item(parent) {
+                    segments.push(parent_item.ident.name.as_str().to_string());
+                }
+                cursor = parent;
+            }
+            segments.extend(prefix.iter().rev().cloned());
+            segments.reverse();
+            symbols.defs.push(SymbolDef {
+                name: segments.join("."),
+                kind,
+                owner: owner.def_id.index() as u32,
+                lo: item.span.lo().0 - start,
+                hi: item.span.hi().0 - start,
+            });
+        }
+
+        // The first scope a definition opens is its own, e.g. a function's
+        // body scope; those of nested blocks come after it.
+        let mut owner_scopes = HashMap::new();
+        for scope in resolver.scope_tree().iter() {
+            if let Some(owner) = package.def_owner(scope.owner_def) {
+                owner_scopes.entry(owner).or_insert(scope.id);
+            }
+        }
+
+        let mut bodies: Vec<_> = package
+            .bodies()
+            .filter(|(_, body)| in_file(body.value.span))
+            .collect();
+        bodies.sort_by_key(|(_, body)| body.value.span.lo());
+        for (body_id, body) in bodies {
+            let scope = package
+                .owner_chain(body_id.hir_id)
+                .into_iter()
+                .find_map(|owner| owner_scopes.get(&owner).copied())
+                .unwrap_or(file_scope);
+            let mut locals: Vec<Symbol> = body.params.iter().map(|p| p.name.name).collect();
+            let mut uses = Vec::new();
+            collect_uses(body.value, &mut locals, &mut uses);
+            for (segments, span) in uses {
+                let Some(target) = resolve_target(resolver, scope, &segments, span) else {
+                    continue;
+                };
+                symbols.refs.push(SymbolRef {
+                    target,
+                    lo: span.lo().0 - start,
+                    hi: span.hi().0 - start,
+                });
+            }
+        }
+        symbols
+    }
+}
+
+/// Names of the module scopes from the package root down to `scope`.
+fn scope_path(resolver: &Resolver<'_>, scope: ScopeId) -> Vec<String> {
+    let mut names: Vec<String> = resolver
+        .scope_tree()
+        .ancestors(scope)
+        .take_while(|s| !matches!(s.kind, ScopeKind::Package | ScopeKind::Root))
+        .filter_map(|s| s.name.map(|n| n.as_str().to_string()))
+        .collect();
+    names.reverse();
+    names
+}
+
+/// Resolve a used path to the qualified name of its definition.
+fn resolve_target(
+    resolver: &Resolver<'_>,
+    scope: ScopeId,
+    segments: &[String],
+    span: Span,
+) -> Option<String> {
+    let res = resolver.resolve_path(segments, scope, span).ok()?;
+    let name = segments.last()?;
+    let mut path = scope_path(resolver, res.defined_in);
+    path.push(name.clone());
+    Some(path.join("."))
+}
+
+/// Collect every non-local name (or `a.b.c` projection chain over one)
+/// used in `expr`, together with its span.
+fn collect_uses(expr: &Expr<'_>, locals: &mut Vec<Symbol>, out: &mut Vec<(Vec<String>, Span)>) {
+    expr.walk_scoped(locals, &mut |expr, bound| {
+        let Some(segments) = name_chain(expr) else {
+            return true;
+        };
+        if !bound.contains(&segments[0]) {
+            let segments = segments.iter().map(|s| s.as_str().to_string()).collect();
+            out.push((segments, expr.span));
+        }
+        false
+    });
+}
+
+/// `a` or `a.b.c` where every link is a plain name.
+fn name_chain(expr: &Expr<'_>) -> Option<Vec<Symbol>> {
+    match &expr.kind {
+        ExprKind::Ident(name) => Some(vec![*name]),
+        ExprKind::Projection(base, ident) => {
+            let mut chain = name_chain(base)?;
+            chain.push(ident.name);
+            Some(chain)
+        }
+        _ => None,
+    }
+}
@@ -306,6 +306,82 @@ impl TyCtxt {
     }
 }
 
+/// A type held by a [`TyCtxt`] that is not the interned allocation of its
+/// kind, as found by [`TyCtxt::audit_interning`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NonInternedTy<'tcx> {
+    /// The stray allocation.
+    pub ty: Ty<'tcx>,
+    /// The interned type equal to it, if its kind was ever interned.
+    pub canonical: Option<Ty<'tcx>>,
+}
+
+#[cfg(debug_assertions)]
+impl TyCtxt {
+    /// Find the types held by the context – the types of definitions and
+    /// nodes, the field types of ADTs, and their parts – that were not made
+    /// through [`TyCtxt::intern`], and so are not pointer-equal to the types
+    /// they are equal to. Debug builds only.
+    ///
+    /// Each stray allocation is reported once. A type built from a stray
+    /// part is not reported itself: its kind holds the stray pointer, so it
+    /// cannot be interned either.
+    pub fn audit_interning<'tcx>(&'tcx self) -> Vec<NonInternedTy<'tcx>> {
+        let mut roots: Vec<Ty<'static>> = Vec::new();
+        roots.extend(self.def_types.borrow().values());
+        roots.extend(self.node_types.borrow().values());
+        for adt in self.adt_defs.borrow().values() {
+            roots.extend(adt.fields.iter().map(|field| field.ty));
+        }
+
+        let mut seen = FxHashMap::default();
+        let mut found = Vec::new();
+        for ty in roots {
+            self.audit_ty(ty, &mut seen, &mut found);
+        }
+        found
+    }
+
+    /// Audit `ty` and its parts; returns whether `ty` is interned.
+    fn audit_ty<'tcx>(
+        &'tcx self,
+        ty: Ty<'tcx>,
+        seen: &mut FxHashMap<*const TyKind<'tcx>, bool>,
+        found: &mut Vec<NonInternedTy<'tcx>>,
+    ) -> bool {
+        let key = ty.0 as *const TyKind<'tcx>;
+        if let Some(&interned) = seen.get(&key) {
+            return interned;
+        }
+        let parts: Vec<Ty<'tcx>> = match ty.kind() {
+            TyKind::Ptr(inner, _) | TyKind::Optional(inner) => vec![*inner],
+            TyKind::Fn(tys)
+            | TyKind::NornmalForm(tys)
+            | TyKind::ErrorQualified(tys)
+            | TyKind::NFApplication(_, tys) => tys.to_vec(),
+            TyKind::FnArrow(from, to) => vec![*from, *to],
+            TyKind::Param
+            | TyKind::Primitive(_)
+            | TyKind::Void
+            | TyKind::Infer(_)
+            | TyKind::NoReturn
+            | TyKind::EffectQualified => Vec::new(),
+        };
+        let mut parts_interned = true;
+        for part in parts {
+            parts_interned &= self.audit_ty(part, seen, found);
+        }
+
+        let canonical = self.interner.lookup(ty.kind());
+        let interned = canonical == Some(ty);
+        if parts_interned && !interned {
+            found.push(NonInternedTy { ty, canonical });
+        }
+        seen.insert(key, interned);
+        interned
+    }
+}
+
 impl Default for TyCtxt {
     fn default() -> Self {
         Self::new()
@@ -319,3 +395,41 @@ impl Default for TyCtxt {
 // `Send + Sync` for salsa 0.22's `HasStorage` bound.
 unsafe impl Sync for TyCtxt {}
 unsafe impl Send for TyCtxt {}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use hir::hir_id::LocalDefId;
+
+    use crate::ty::{NonInternedTy, PrimTy, Ty, TyCtxt, TyKind};
+
+    #[test]
+    fn interned_types_pass_the_interning_audit() {
+        let tcx = TyCtxt::new();
+        let int = tcx.mk_primitive(PrimTy::I64);
+        let opt = tcx.mk_optional(int);
+        tcx.register_def_ty(LocalDefId::new(0), tcx.mk_fn(&[int, opt]));
+        tcx.register_def_ty(
+            LocalDefId::new(1),
+            tcx.mk_fn_arrow(opt, tcx.mk_optional(int)),
+        );
+        assert!(tcx.audit_interning().is_empty());
+    }
+
+    #[test]
+    fn a_type_built_outside_the_interner_is_reported_once() {
+        let tcx = TyCtxt::new();
+        let int = tcx.mk_primitive(PrimTy::I64);
+        let opt = tcx.mk_optional(int);
+        let stray_kind = TyKind::Optional(int);
+        let stray = Ty(&stray_kind);
+        tcx.register_def_ty(LocalDefId::new(0), tcx.mk_fn(&[stray, int]));
+        tcx.register_def_ty(LocalDefId::new(1), stray);
+        assert_eq!(
+            tcx.audit_interning(),
+            [NonInternedTy {
+                ty: stray,
+                canonical: Some(opt)
+            }]
+        );
+    }
+}
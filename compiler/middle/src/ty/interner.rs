@@ -72,6 +72,16 @@ impl TyInterner {
         Ty(unsafe { mem::transmute::<&'static TyKind<'static>, &'tcx TyKind<'tcx>>(allocated) })
     }
 
+    /// The interned allocation of `kind`, without interning it.
+    pub(crate) fn lookup<'tcx>(&'tcx self, kind: &TyKind<'tcx>) -> Option<Ty<'tcx>> {
+        // SAFETY: as in `intern`; the reference is only used for the lookup.
+        let kind_static = unsafe { mem::transmute::<&TyKind<'tcx>, &TyKind<'static>>(kind) };
+        let set = self.set.borrow();
+        set.get(kind_static).map(|&existing| {
+            Ty(unsafe { mem::transmute::<&'static TyKind<'static>, &'tcx TyKind<'tcx>>(existing) })
+        })
+    }
+
     /// Allocate a slice of `Ty` values in the arena.
     ///
     /// Used by the [`TyCtxt`](crate::TyCtxt) to intern sub-type slices
@@ -8,6 +8,6 @@ mod context;
 mod interner;
 mod types;
 
-pub use context::{CommonTypes, TyCtxt};
+pub use context::{CommonTypes, NonInternedTy, TyCtxt};
 pub use interner::TyInterner;
 pub use types::*;
@@ -13,13 +13,9 @@ impl Parser<'_> {
             }
             // consume '^'
             p.eat_tokens(1);
-            let expr = p.try_expr()?;
+            let expr = p.try_attribute_expr()?;
             if expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected attribute expression after `^`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected attribute expression after `^`".to_string()));
             }
             Ok(expr)
         })
@@ -140,9 +136,35 @@ impl Parser<'_> {
 
     // rules是有序的, 从最左边的规则开始尝试, 选中第一个成功的
     pub fn try_multi(&mut self, rules: &[Rule]) -> Result<Vec<NodeIndex>, ParseError> {
+        self.try_multi_impl(rules, None, None)
+    }
+
+    /// Parse the items of a file like [`Self::try_multi`]. An item that
+    /// fails to parse is recorded and skipped with [`Self::skip_item`], and
+    /// parsing goes on with the next one.
+    pub fn try_items_recovering(&mut self, rules: &[Rule]) -> Result<Vec<NodeIndex>, ParseError> {
+        self.try_multi_impl(rules, Some(Self::skip_item), None)
+    }
+
+    /// `recover`, if given, skips an element that failed to parse, from
+    /// the cursor before it; the error is recorded and the list goes on.
+    ///
+    /// `close` is the bracket ending the list, if it is bracketed. An
+    /// element followed by neither a `,` nor `close` may be missing its
+    /// comma: if another element parses right after it, the missing comma
+    /// is reported and the list goes on as if it were there.
+    fn try_multi_impl(
+        &mut self,
+        rules: &[Rule],
+        recover: Option<fn(&mut Self, usize)>,
+        close: Option<TokenKind>,
+    ) -> Result<Vec<NodeIndex>, ParseError> {
         self.scoped(|p| {
             let mut nodes = Vec::new();
+            // The error for a comma missing before the element parsed next
+            let mut missing_comma = None;
             'outer: loop {
+                let start = p.cursor;
                 // New: attempt attribute chain at start of each element parse round.
                 let attrs = p.try_attribute_prefix_chain()?; // may be empty, consumption rolled back automatically
                 let mut matched_any_rule = false;
@@ -150,10 +172,28 @@ impl Parser<'_> {
                     let node: NodeIndex = match (rule.parser)(p) {
                         Ok(0) => continue 'inner,
                         Ok(node) => node,
-                        Err(err) => return Err(err),
+                        Err(err) => match recover {
+                            Some(skip) => {
+                                // The comma is missing before this element
+                                // whether or not the element parses.
+                                if let Some(error) = missing_comma.take() {
+                                    p.parse_error(error);
+                                }
+                                p.parse_error(err);
+                                skip(p, start);
+                                if p.cursor == start {
+                                    break 'outer;
+                                }
+                                continue 'outer;
+                            }
+                            None => return Err(err),
+                        },
                     };
 
                     matched_any_rule = true;
+                    if let Some(error) = missing_comma.take() {
+                        p.parse_error(error);
+                    }
                     let final_node = if !attrs.is_empty() {
                         p.wrap_with_attributes(node, &attrs)
                     } else {
@@ -173,6 +213,14 @@ impl Parser<'_> {
                         }
                     } else {
                         if !p.eat_token(rule.separator) {
+                            let next = p.peek_next_token().kind;
+                            if rule.separator == TokenKind::Comma
+                                && close.is_some_and(|close| next != close)
+                                && next != TokenKind::Eof
+                            {
+                                missing_comma = Some(p.missing_comma());
+                                continue 'outer;
+                            }
                             break 'outer; // 如果没有匹配到分隔符, 退出外层循环
                         } else {
                             continue 'outer; // 如果匹配到分隔符, 继续外层循环
@@ -199,30 +247,138 @@ impl Parser<'_> {
         self.current_token().kind == TokenKind::RBrace
     }
 
+    /// Skip the rest of a statement that failed to parse, starting over
+    /// from the token after `start`: up to and including the next `;`, or
+    /// up to the `}` that closes the enclosing block. Braces opened on the
+    /// way are skipped whole.
+    fn skip_statement(&mut self, start: usize) {
+        self.cursor = start;
+        let mut depth = 0usize;
+        loop {
+            match self.peek_next_token().kind {
+                TokenKind::Eof => break,
+                TokenKind::Semi if depth == 0 => {
+                    self.eat_tokens(1);
+                    break;
+                }
+                TokenKind::RBrace if depth == 0 => break,
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace => depth -= 1,
+                _ => {}
+            }
+            self.eat_tokens(1);
+        }
+    }
+
+    /// Skip the rest of a top-level item that failed to parse, starting
+    /// over from the token after `start`: up to and including the next `;`
+    /// or the `}` that closes the item's body, or up to the keyword that
+    /// starts the next item. Braces opened on the way are skipped whole.
+    fn skip_item(&mut self, start: usize) {
+        use TokenKind::*;
+        self.cursor = start;
+        // The item's own first token never starts the next one.
+        self.eat_tokens(1);
+        let mut depth = 0usize;
+        loop {
+            match self.peek_next_token().kind {
+                Eof => break,
+                Semi if depth == 0 => {
+                    self.eat_tokens(1);
+                    break;
+                }
+                Struct | Enum | Union | Trait | Impl | Extend | Derive | Fn | Mod | Effect
+                | Typealias | Newtype | Case | Test | Use | Pub | Private | Caret
+                    if depth == 0 =>
+                {
+                    break;
+                }
+                LBrace => depth += 1,
+                RBrace if depth == 1 => {
+                    self.eat_tokens(1);
+                    break;
+                }
+                RBrace => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            self.eat_tokens(1);
+        }
+    }
+
     pub fn try_multi_with_bracket(
         &mut self,
         rules: &[Rule],
         bracket: (TokenKind, TokenKind),
+    ) -> Result<Vec<NodeIndex>, ParseError> {
+        self.try_multi_with_bracket_impl(rules, bracket, None)
+    }
+
+    /// Parse the statements of a block, `{ stmt; stmt; .. }`. A statement
+    /// that fails to parse is recorded and skipped with
+    /// [`Self::skip_statement`], and parsing goes on with the next one, so
+    /// one bad statement does not hide the errors after it.
+    pub fn try_statements_in_braces(
+        &mut self,
+        rules: &[Rule],
+    ) -> Result<Vec<NodeIndex>, ParseError> {
+        self.try_multi_with_bracket_impl(
+            rules,
+            (TokenKind::LBrace, TokenKind::RBrace),
+            Some(Self::skip_statement),
+        )
+    }
+
+    fn try_multi_with_bracket_impl(
+        &mut self,
+        rules: &[Rule],
+        bracket: (TokenKind, TokenKind),
+        recover: Option<fn(&mut Self, usize)>,
     ) -> Result<Vec<NodeIndex>, ParseError> {
         self.scoped_with_expected_prefix(&[bracket.0], |p| {
+            let depth = p.open_brackets.len();
+            p.open_brackets.push((bracket.0, p.next_token_span()));
             p.eat_tokens(1); // 吃掉左括号
-            let nodes = match p.try_multi(rules) {
-                Ok(nodes) => nodes,
-                Err(err) => return Err(err),
-            };
-            if !p.eat_token(bracket.1) {
-                let expected = rules
-                    .iter()
-                    .map(|rule| rule.name)
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                return Err(ParseError::InvalidSyntax {
-                    message: format!("Expected {} or `{}`", expected, bracket.1.lexme()),
-                    found: p.next_token().kind,
-                    span: p.current_span(),
+            let result = p
+                .try_multi_impl(rules, recover, Some(bracket.1))
+                .and_then(|nodes| p.eat_close_bracket(rules, bracket).map(|()| nodes));
+            p.open_brackets.truncate(depth);
+            result
+        })
+    }
+
+    /// Eat the bracket closing a list opened with `bracket.0`. At the end of
+    /// the file the close is taken as read: every bracket still open is
+    /// reported in one error, at the innermost list, and the lists around it
+    /// end quietly.
+    fn eat_close_bracket(
+        &mut self,
+        rules: &[Rule],
+        bracket: (TokenKind, TokenKind),
+    ) -> Result<(), ParseError> {
+        if self.eat_token(bracket.1) {
+            return Ok(());
+        }
+        if self.peek_next_token().kind == TokenKind::Eof {
+            let unclosed = std::mem::take(&mut self.open_brackets);
+            if !unclosed.is_empty() {
+                self.parse_error(ParseError::UnclosedBrackets {
+                    message: format!("Expected `{}`, found end of file", bracket.1.lexme()),
+                    unclosed,
+                    span: self.next_token_span(),
                 });
             }
-            Ok(nodes)
+            return Ok(());
+        }
+        let expected = rules
+            .iter()
+            .map(|rule| rule.name)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let found = self.next_token();
+        Err(ParseError::InvalidSyntax {
+            message: format!("Expected {} or `{}`", expected, bracket.1.lexme()),
+            found: found.kind,
+            span: self.token_span(&found),
         })
     }
 
@@ -256,18 +412,16 @@ impl Parser<'_> {
             if id == 0 {
                 return Err(ParseError::invalid_syntax(
                     "Expected an identifier before `:`".to_string(),
-                    TokenKind::Id,
-                    p.current_span(),
+                    p.peek_next_token().kind,
+                    p.next_token_span(),
                 ));
             }
             p.eat_tokens(1); // 吃掉冒号
             let expr = p.try_expr()?;
             if expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected an expression after `:` in `id: expr`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected an expression after `:` in `id: expr`".to_string())
+                );
             }
 
             Ok(NodeBuilder::new(NodeKind::Property, p.current_span())
@@ -283,11 +437,7 @@ impl Parser<'_> {
             p.eat_tokens(3); // consume '...'
             let expr = p.try_expr()?;
             if expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected an expression after `...`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected an expression after `...`".to_string()));
             }
             Ok(NodeBuilder::new(NodeKind::ExpandArg, p.current_span())
                 .add_single_child(expr)
@@ -301,26 +451,14 @@ impl Parser<'_> {
             p.eat_tokens(1); // 吃掉点号
             let id = p.try_id()?;
             if id == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected an identifier after `.`".to_string(),
-                    TokenKind::Id,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected an identifier after `.`".to_string()));
             }
             if !p.eat_token(TokenKind::Eq) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Eq,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Eq));
             }
             let expr = p.try_expr()?;
             if expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected an expression after `=`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected an expression after `=`".to_string()));
             }
 
             Ok(NodeBuilder::new(NodeKind::OptionalArg, p.current_span())
@@ -1,21 +1,39 @@
-use diagnostic::{DiagnosticContext, FlurryError};
+use diagnostic::{DiagnosticContext, FlurryError, Suggestion};
 use lex::TokenKind;
 
 pub const PARSE_ERROR_BASE: u32 = 2000;
 
 #[derive(Debug, Clone)]
 pub enum ParseError {
+    /// `suggestion`, if any, puts the expected token in.
     UnexpectedToken {
         message: String,
         expected: TokenKind,
         found: TokenKind,
         span: rustc_span::Span,
+        suggestion: Option<Suggestion>,
     },
     InvalidSyntax {
         message: String,
         found: TokenKind,
         span: rustc_span::Span,
     },
+    /// A declaration is not followed by its block; `help` and `note`
+    /// explain the common mistakes.
+    ExpectedBlock {
+        message: String,
+        help: Option<String>,
+        note: Option<String>,
+        found: TokenKind,
+        span: rustc_span::Span,
+    },
+    /// The file ends inside brackets that were never closed. `unclosed`
+    /// holds each of them, outermost first; `span` is the end of the file.
+    UnclosedBrackets {
+        message: String,
+        unclosed: Vec<(TokenKind, rustc_span::Span)>,
+        span: rustc_span::Span,
+    },
 
     // 这两个仅用于控制流, 非错误
     MeetPostExtendedCallStart,
@@ -27,6 +45,8 @@ impl ParseError {
         match self {
             ParseError::UnexpectedToken { message, .. } => message,
             ParseError::InvalidSyntax { message, .. } => message,
+            ParseError::ExpectedBlock { message, .. } => message,
+            ParseError::UnclosedBrackets { message, .. } => message,
             ParseError::MeetPostExtendedCallStart => {
                 "Received unexpected MeetPostExtendedCallStart, this is a bug"
             }
@@ -40,6 +60,7 @@ impl ParseError {
             expected,
             found,
             span,
+            suggestion: None,
         }
     }
 
@@ -51,10 +72,20 @@ impl ParseError {
         }
     }
 
+    /// `self` offering `fix`, if it is a [`ParseError::UnexpectedToken`].
+    pub fn with_suggestion(mut self, fix: Suggestion) -> Self {
+        if let ParseError::UnexpectedToken { suggestion, .. } = &mut self {
+            *suggestion = Some(fix);
+        }
+        self
+    }
+
     pub fn to_span(&self) -> rustc_span::Span {
         match self {
             ParseError::UnexpectedToken { span, .. } => span.clone(),
             ParseError::InvalidSyntax { span, .. } => span.clone(),
+            ParseError::ExpectedBlock { span, .. } => span.clone(),
+            ParseError::UnclosedBrackets { span, .. } => span.clone(),
             ParseError::MeetPostExtendedCallStart => rustc_span::DUMMY_SP,
             ParseError::MeetPostId => rustc_span::DUMMY_SP,
         }
@@ -68,18 +99,44 @@ impl FlurryError for ParseError {
             ParseError::InvalidSyntax { .. } => PARSE_ERROR_BASE + 2,
             ParseError::MeetPostExtendedCallStart => PARSE_ERROR_BASE + 3,
             ParseError::MeetPostId => PARSE_ERROR_BASE + 4,
+            ParseError::ExpectedBlock { .. } => PARSE_ERROR_BASE + 5,
+            ParseError::UnclosedBrackets { .. } => PARSE_ERROR_BASE + 6,
         }
     }
 
     fn emit(&self, diag_ctx: &DiagnosticContext, _base_pos: rustc_span::BytePos) {
         let span = self.to_span();
 
-        diag_ctx
+        let mut builder = diag_ctx
             .error(self.message().to_string())
             .with_code(self.error_code())
             .with_error_label(span, self.message().to_string())
-            .with_primary_span(span)
-            .emit(diag_ctx);
+            .with_primary_span(span);
+        if let ParseError::ExpectedBlock { help, note, .. } = self {
+            if let Some(help) = help {
+                builder = builder.with_help(help.clone());
+            }
+            if let Some(note) = note {
+                builder = builder.with_note(note.clone());
+            }
+        }
+        if let ParseError::UnexpectedToken {
+            suggestion: Some(suggestion),
+            ..
+        } = self
+        {
+            builder = builder.with_suggestion(
+                suggestion.span,
+                suggestion.replacement.clone(),
+                suggestion.applicability,
+            );
+        }
+        if let ParseError::UnclosedBrackets { unclosed, .. } = self {
+            for (open, span) in unclosed {
+                builder = builder.with_note_label(*span, format!("unclosed `{}`", open.lexme()));
+            }
+        }
+        builder.emit(diag_ctx);
     }
 
     fn error_name(&self) -> &'static str {
@@ -88,6 +145,8 @@ impl FlurryError for ParseError {
             ParseError::InvalidSyntax { .. } => "invalid_syntax",
             ParseError::MeetPostExtendedCallStart => "meet_post_extended_call_start",
             ParseError::MeetPostId => "meet_post_id",
+            ParseError::ExpectedBlock { .. } => "expected_block",
+            ParseError::UnclosedBrackets { .. } => "unclosed_brackets",
         }
     }
 }
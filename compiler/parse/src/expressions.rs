@@ -9,6 +9,9 @@ use lex::TokenKind;
 #[derive(Debug, Clone, Copy)]
 pub struct ExprOption {
     pub no_extended_call: bool,
+    /// Stop before an identifier following the expression instead of
+    /// reading it as a literal extension (`10px`).
+    pub no_post_id: bool,
     pub precedence: i32,
 }
 
@@ -16,6 +19,7 @@ impl ExprOption {
     pub fn new() -> Self {
         Self {
             no_extended_call: false,
+            no_post_id: false,
             precedence: 0,
         }
     }
@@ -25,6 +29,11 @@ impl ExprOption {
         self
     }
 
+    pub fn with_no_post_id(mut self, no_post_id: bool) -> Self {
+        self.no_post_id = no_post_id;
+        self
+    }
+
     pub fn with_precedence(mut self, precedence: i32) -> Self {
         self.precedence = precedence;
         self
@@ -53,6 +62,14 @@ impl Parser<'_> {
         self.try_expr_with_option(ExprOption::new().with_no_extended_call(true))
     }
 
+    #[inline]
+    /// Parse the expression of an attribute prefix (`^expr`), which ends
+    /// before the identifier that starts the attributed item. The error list
+    /// of `!errors T` ends the same way.
+    pub fn try_attribute_expr(&mut self) -> ParseResult {
+        self.try_expr_with_option(ExprOption::new().with_no_post_id(true))
+    }
+
     /// 使用 Pratt 解析法解析表达式
     ///
     /// # 参数
@@ -98,16 +115,12 @@ impl Parser<'_> {
                         p.eat_tokens(1);
 
                         // 解析右操作数（递归调用，使用更高优先级）
-                        let right = match p.try_expr_pratt(op_info.prec + 1, option)? {
+                        let right = match p.try_expr_pratt(op_info.right_operand_prec(), option)? {
                             0 => {
-                                return Err(ParseError::invalid_syntax(
-                                    format!(
-                                        "Expected a right operand after binary operator `{}`",
-                                        p.peek_next_token().kind.lexme()
-                                    ),
-                                    p.peek_next_token().kind,
-                                    p.next_token_span(),
-                                ));
+                                return Err(p.expected_after(format!(
+                                    "Expected a right operand after binary operator `{}`",
+                                    p.peek_next_token().kind.lexme()
+                                )));
                             }
 
                             node => node,
@@ -199,6 +212,9 @@ impl Parser<'_> {
                         p.try_fn_type()
                     }
                 }
+                TokenKind::KwQuote => {
+                    p.try_keyword_block_expr(TokenKind::KwQuote, NodeKind::QuoteBlock)
+                }
                 TokenKind::Atomic => p.try_atomic_block_expr(),
 
                 // fn_type -> pure? comptime? inline? (unsafe|spec|verified)? (extern "ABI")? fn(parameter_type*)
@@ -245,11 +261,7 @@ impl Parser<'_> {
                 elements.insert(0, first_expr); // 将第一个表达式添加到元素列表
 
                 if !p.eat_token(TokenKind::RParen) {
-                    return Err(ParseError::unexpected_token(
-                        TokenKind::RParen,
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.unexpected(TokenKind::RParen));
                 }
 
                 Ok(NodeBuilder::new(NodeKind::Tuple, p.current_span())
@@ -258,11 +270,7 @@ impl Parser<'_> {
             } else {
                 // 这是一个括号表达式
                 if !p.eat_token(TokenKind::RParen) {
-                    return Err(ParseError::unexpected_token(
-                        TokenKind::RParen,
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.unexpected(TokenKind::RParen));
                 }
 
                 Ok(first_expr)
@@ -278,11 +286,7 @@ impl Parser<'_> {
                 if p.eat_token(TokenKind::Eq) {
                     let to = p.try_expr_with_option(option)?;
                     if to == 0 {
-                        return Err(ParseError::invalid_syntax(
-                            "Expected expression after '..='".to_string(),
-                            p.peek_next_token().kind,
-                            p.next_token_span(),
-                        ));
+                        return Err(p.expected_after("Expected expression after '..='".to_string()));
                     }
 
                     return Ok(
@@ -350,11 +354,9 @@ impl Parser<'_> {
             let return_type = if p.eat_token(TokenKind::Arrow) {
                 let rt = p.try_expr()?;
                 if rt == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected return type after '->' in lambda".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(
+                        p.expected_after("Expected return type after '->' in lambda".to_string())
+                    );
                 }
                 rt
             } else {
@@ -402,10 +404,8 @@ impl Parser<'_> {
 
                 let expr = p.try_expr_with_option(option)?;
                 if expr == 0 {
-                    return Err(ParseError::invalid_syntax(
+                    return Err(p.expected_after(
                         "Expected expression after forall type parameters".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
                     ));
                 }
 
@@ -419,28 +419,20 @@ impl Parser<'_> {
                 let params =
                     p.try_multi(&[Rule::comma("type bound parameter", |p| p.try_param())])?;
                 if params.is_empty() {
-                    return Err(ParseError::invalid_syntax(
+                    return Err(p.expected_after(
                         "Expected at least one parameter after `forall`".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
                     ));
                 }
 
                 if !p.eat_token(TokenKind::FatArrow) {
-                    return Err(ParseError::unexpected_token(
-                        TokenKind::FatArrow,
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.unexpected(TokenKind::FatArrow));
                 }
 
                 let body = p.try_expr_with_option(option)?;
                 if body == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected expression after `=>` in forall".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(
+                        p.expected_after("Expected expression after `=>` in forall".to_string())
+                    );
                 }
 
                 Ok(NodeBuilder::new(NodeKind::BoolForall, p.current_span())
@@ -458,28 +450,20 @@ impl Parser<'_> {
 
             let params = p.try_multi(&[Rule::comma("type bound parameter", |p| p.try_param())])?;
             if params.is_empty() {
-                return Err(ParseError::invalid_syntax(
-                    "Expected at least one parameter after `exists`".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected at least one parameter after `exists`".to_string())
+                );
             }
 
             if !p.eat_token(TokenKind::FatArrow) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::FatArrow,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::FatArrow));
             }
 
             let body = p.try_expr_with_option(option)?;
             if body == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected expression after `=>` in exists".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected expression after `=>` in exists".to_string())
+                );
             }
 
             Ok(NodeBuilder::new(NodeKind::BoolExists, p.current_span())
@@ -498,11 +482,7 @@ impl Parser<'_> {
             // 解析效果列表
             let effect_list = p.try_expr_without_extended_call()?;
             if effect_list == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected effect list after '#'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected effect list after '#'".to_string()));
             }
 
             // 解析类型表达式
@@ -531,13 +511,9 @@ impl Parser<'_> {
             p.eat_tokens(1); // 消耗 '!'
 
             // 解析错误列表
-            let error_list = p.try_expr_without_extended_call()?;
+            let error_list = p.try_attribute_expr()?;
             if error_list == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected error list after '!'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected error list after '!'".to_string()));
             }
 
             // 解析类型表达式
@@ -567,11 +543,7 @@ impl Parser<'_> {
             // 解析可达性列表
             let reachability_set = p.try_expr_without_extended_call()?;
             if reachability_set == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected reachability list after '&'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected reachability list after '&'".to_string()));
             }
 
             // 解析类型表达式
@@ -599,11 +571,7 @@ impl Parser<'_> {
             p.eat_tokens(1); // consume 'do'
             let block = p.try_block()?;
             if block == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected block after `do`".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected block after `do`".to_string()));
             }
             Ok(NodeBuilder::new(NodeKind::DoBlock, p.current_span())
                 .add_single_child(block)
@@ -620,11 +588,7 @@ impl Parser<'_> {
             }
             let block = p.try_block()?;
             if block == 0 {
-                return Err(ParseError::invalid_syntax(
-                    format!("Expected block after `{}`", keyword.lexme()),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after(format!("Expected block after `{}`", keyword.lexme())));
             }
             Ok(NodeBuilder::new(node_kind, p.current_span())
                 .add_single_child(block)
@@ -642,11 +606,7 @@ impl Parser<'_> {
             )?;
             let block = p.try_block()?;
             if block == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected block after `atomic(...)`.".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected block after `atomic(...)`.".to_string()));
             }
             // AtomicBlock is (a, b, N) = DoubleWithMultiChildren?
             // Actually it's N, a in current code comment. Let me use the correct layout.
@@ -668,11 +628,7 @@ impl Parser<'_> {
 
             let closure_expr = p.try_expr_without_extended_call()?;
             if closure_expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected closure expression after `^`".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected closure expression after `^`".to_string()));
             }
 
             let type_expr = p.try_expr_without_extended_call()?;
@@ -710,11 +666,9 @@ impl Parser<'_> {
             )?;
 
             if expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    format!("Expected expression after '{}'", token.lexme()),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after(format!("Expected expression after '{}'", token.lexme()))
+                );
             }
 
             Ok(NodeBuilder::new(node_kind, p.current_span())
@@ -743,6 +697,7 @@ impl Parser<'_> {
             TokenKind::Match => p.try_post_match_expr(left),
             TokenKind::Do => p.try_post_lambda_expr(left, opt),
             TokenKind::Matches => p.try_matches_expr(left, opt),
+            TokenKind::Id if opt.no_post_id => Err(ParseError::MeetPostId),
             TokenKind::Id => p.try_literal_extension_expr(left),
             _ => Ok(0),
         })
@@ -821,11 +776,9 @@ impl Parser<'_> {
             // 解析索引调用参数
             let expr = p.try_expr()?;
             if expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected expression after '[' in index call".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected expression after '[' in index call".to_string())
+                );
             }
 
             // 创建索引调用节点
@@ -871,27 +824,21 @@ impl Parser<'_> {
             p.eat_tokens(1); // 消耗处理器应用操作符
 
             if !p.eat_token(TokenKind::LParen) {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected '(' after 'use' in handler apply expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
             let handler_expr = p.try_expr()?;
             if handler_expr == 0 {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected expression after '(' in handler apply expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
             if !p.eat_token(TokenKind::RParen) {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected ')' after expression in handler apply expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
@@ -908,11 +855,9 @@ impl Parser<'_> {
     /// 引用表达式
     fn try_refer_expr(&mut self, left: NodeIndex) -> ParseResult {
         if !self.eat_token(TokenKind::Ref) {
-            return Err(ParseError::invalid_syntax(
-                "Expected 'ref' after '.' in refer expression".to_string(),
-                self.peek_next_token().kind,
-                self.next_token_span(),
-            ));
+            return Err(
+                self.expected_after("Expected 'ref' after '.' in refer expression".to_string())
+            );
         }
 
         Ok(NodeBuilder::new(NodeKind::Refer, self.current_span())
@@ -923,11 +868,9 @@ impl Parser<'_> {
     /// 等待表达式
     fn try_await_expr(&mut self, left: NodeIndex) -> ParseResult {
         if !self.eat_token(TokenKind::Await) {
-            return Err(ParseError::invalid_syntax(
-                "Expected 'await' after '.' in await expression".to_string(),
-                self.peek_next_token().kind,
-                self.next_token_span(),
-            ));
+            return Err(
+                self.expected_after("Expected 'await' after '.' in await expression".to_string())
+            );
         }
 
         Ok(NodeBuilder::new(NodeKind::Await, self.current_span())
@@ -939,11 +882,8 @@ impl Parser<'_> {
     fn try_select_expr(&mut self, left: NodeIndex) -> ParseResult {
         let id = self.try_id()?;
         if id == 0 {
-            return Err(ParseError::invalid_syntax(
-                "Expected identifier after '.' in select expression".to_string(),
-                self.peek_next_token().kind,
-                self.next_token_span(),
-            ));
+            return Err(self
+                .expected_after("Expected identifier after '.' in select expression".to_string()));
         }
 
         Ok(NodeBuilder::new(NodeKind::Projection, self.current_span())
@@ -960,10 +900,8 @@ impl Parser<'_> {
 
         let id = self.try_id()?;
         if id == 0 {
-            return Err(ParseError::invalid_syntax(
+            return Err(self.expected_after(
                 "Expected identifier after `'` in take-view expression".to_string(),
-                self.peek_next_token().kind,
-                self.next_token_span(),
             ));
         }
         Ok(NodeBuilder::new(NodeKind::TakeView, self.current_span())
@@ -978,27 +916,21 @@ impl Parser<'_> {
             p.eat_tokens(1);
 
             if !p.eat_token(TokenKind::LParen) {
-                return Err(ParseError::invalid_syntax(
-                    "Expected '(' after 'dyn' in as dyn expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected '(' after 'dyn' in as dyn expression".to_string())
+                );
             }
 
             let dyn_expr = p.try_expr()?;
             if dyn_expr == 0 {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected expression after '(' in as dyn expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
             if !p.eat_token(TokenKind::RParen) {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected ')' after expression in as dyn expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
@@ -1016,27 +948,21 @@ impl Parser<'_> {
             p.eat_tokens(1); // 消耗 'as'
 
             if !p.eat_token(TokenKind::LParen) {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected '(' after 'as' in type cast expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
             let type_expr = p.try_expr()?;
             if type_expr == 0 {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected expression after '(' in type cast expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
             if !p.eat_token(TokenKind::RParen) {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected ')' after expression in type cast expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
@@ -1054,10 +980,8 @@ impl Parser<'_> {
             self.eat_tokens(2);
             let end = self.try_expr_without_extended_call()?;
             if end == 0 {
-                return Err(ParseError::invalid_syntax(
+                return Err(self.expected_after(
                     "Expected expression after '..=' in range expression".to_string(),
-                    self.peek_next_token().kind,
-                    self.next_token_span(),
                 ));
             }
             Ok(
@@ -1098,11 +1022,7 @@ impl Parser<'_> {
             let arms = p.try_multi(&[Rule::comma("effect handling arm", |p| p.try_case_arm())])?;
 
             if !p.eat_token(TokenKind::RBrace) {
-                return Err(ParseError::invalid_syntax(
-                    "Expected '}' after effect handling arms".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected '}' after effect handling arms".to_string()));
             }
 
             Ok(
@@ -1133,11 +1053,7 @@ impl Parser<'_> {
             ])?;
 
             if !p.eat_token(TokenKind::RBrace) {
-                return Err(ParseError::invalid_syntax(
-                    "Expected '}' after error handling arms".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected '}' after error handling arms".to_string()));
             }
 
             Ok(
@@ -1164,11 +1080,9 @@ impl Parser<'_> {
 
             let handling_block = p.try_block()?;
             if handling_block == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected block after '?' in option expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected block after '?' in option expression".to_string())
+                );
             }
 
             Ok(
@@ -1186,10 +1100,8 @@ impl Parser<'_> {
             p.eat_tokens(1);
 
             if !p.peek(TokenKind::LBrace.as_ref()) {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected '{' after 'match' in post match expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
@@ -1216,10 +1128,8 @@ impl Parser<'_> {
                 p.try_pattern()?
             };
             if pattern == 0 {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected pattern after 'matches' in matches expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
@@ -1247,10 +1157,8 @@ impl Parser<'_> {
             };
 
             if body == 0 {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected lambda, block, or expression after `do`".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
@@ -13,26 +13,14 @@ impl Parser<'_> {
             p.eat_tokens(3); // consume '...'
             let id = p.try_id()?;
             if id == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected an identifier after `...`".to_string(),
-                    TokenKind::Id,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected an identifier after `...`".to_string()));
             }
             if !p.eat_token(TokenKind::Colon) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Colon,
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Colon));
             }
             let ty = p.try_expr_without_extended_call()?;
             if ty == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a type after `:`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected a type after `:`".to_string()));
             }
             Ok(NodeBuilder::new(kind, p.current_span())
                 .add_single_child(id)
@@ -49,26 +37,14 @@ impl Parser<'_> {
             p.eat_tokens(1); // eat ':'
             let ty = p.try_expr_without_extended_call()?;
             if ty == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a type after `:`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected a type after `:`".to_string()));
             }
             if !p.eat_token(TokenKind::Eq) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Eq,
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Eq));
             }
             let default_value = p.try_expr_without_extended_call()?;
             if default_value == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a default value after `=`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected a default value after `=`".to_string()));
             }
             Ok(NodeBuilder::new(kind, p.current_span())
                 .add_single_child(id)
@@ -85,11 +61,7 @@ impl Parser<'_> {
             p.eat_tokens(1); // eat ':'
             let ty = p.try_expr_without_extended_call()?;
             if ty == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a type after `:`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected a type after `:`".to_string()));
             }
             Ok(NodeBuilder::new(kind, p.current_span())
                 .add_single_child(id)
@@ -105,11 +77,7 @@ impl Parser<'_> {
             p.eat_tokens(1); // eat ':-'
             let trait_bound = p.try_expr_without_extended_call()?;
             if trait_bound == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a trait bound after `:-`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected a trait bound after `:-`".to_string()));
             }
             Ok(NodeBuilder::new(kind, p.current_span())
                 .add_single_child(id)
@@ -127,14 +95,10 @@ impl Parser<'_> {
             p.eat_tokens(1); // consume the wrapper keyword
             let inner = inner_parser(p)?;
             if inner == 0 {
-                return Err(ParseError::invalid_syntax(
-                    format!(
-                        "Expected a parameter after `{}`",
-                        p.current_token().kind.lexme()
-                    ),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after(format!(
+                    "Expected a parameter after `{}`",
+                    p.current_token().kind.lexme()
+                )));
             }
             Ok(NodeBuilder::new(kind, p.current_span())
                 .add_single_child(inner)
@@ -148,7 +112,7 @@ impl Parser<'_> {
     pub fn try_param(&mut self) -> ParseResult {
         self.scoped(|p| {
             // Wrapper parameter prefixes: comptime/error/catch/lambda/implicit/quote
-            match p.peek_next_token().kind {
+            match p.peek_nth_kind(0) {
                 TokenKind::Comptime => {
                     return p.parse_wrapper_param(NodeKind::ComptimeParam, |p| p.try_param());
                 }
@@ -173,20 +137,16 @@ impl Parser<'_> {
             // ^expr parameter (attribute parameter)
             if p.peek(TokenKind::Caret.as_ref()) {
                 p.eat_tokens(1); // consume '^'
-                let attr_expr = p.try_expr_without_extended_call()?;
+                let attr_expr = p.try_attribute_expr()?;
                 if attr_expr == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected attribute expression after `^`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
+                    return Err(
+                        p.expected_after("Expected attribute expression after `^`".to_string())
+                    );
                 }
                 let inner = p.try_param()?;
                 if inner == 0 {
-                    return Err(ParseError::invalid_syntax(
+                    return Err(p.expected_after(
                         "Expected a parameter after attribute expression".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
                     ));
                 }
                 return Ok(NodeBuilder::new(NodeKind::AttrParam, p.current_span())
@@ -219,36 +179,17 @@ impl Parser<'_> {
                 return Ok(trait_bound);
             }
 
-            // *self
-            if p.peek(&[TokenKind::Star, TokenKind::SelfLower]) {
-                p.eat_tokens(2);
-                return Ok(
-                    NodeBuilder::new(NodeKind::SelfRefParam, p.current_span()).build(&mut p.ast)
-                );
-            }
-
-            // *itself
-            if p.peek(&[TokenKind::Star, TokenKind::Itself]) {
-                p.eat_tokens(2);
-                return Ok(
-                    NodeBuilder::new(NodeKind::ItselfRefParam, p.current_span()).build(&mut p.ast)
-                );
-            }
-
-            // self
-            if p.peek(TokenKind::SelfLower.as_ref()) {
-                p.eat_tokens(1);
-                return Ok(
-                    NodeBuilder::new(NodeKind::SelfParam, p.current_span()).build(&mut p.ast)
-                );
-            }
-
-            // itself
-            if p.peek(TokenKind::Itself.as_ref()) {
-                p.eat_tokens(1);
-                return Ok(
-                    NodeBuilder::new(NodeKind::ItselfParam, p.current_span()).build(&mut p.ast)
-                );
+            // self | itself | *self | *itself
+            let receiver = match (p.peek_nth_kind(0), p.peek_nth_kind(1)) {
+                (TokenKind::SelfLower, _) => Some((NodeKind::SelfParam, 1)),
+                (TokenKind::Itself, _) => Some((NodeKind::ItselfParam, 1)),
+                (TokenKind::Star, TokenKind::SelfLower) => Some((NodeKind::SelfRefParam, 2)),
+                (TokenKind::Star, TokenKind::Itself) => Some((NodeKind::ItselfRefParam, 2)),
+                _ => None,
+            };
+            if let Some((kind, len)) = receiver {
+                p.eat_tokens(len);
+                return Ok(NodeBuilder::new(kind, p.current_span()).build(&mut p.ast));
             }
 
             // trivial parameter: just an id
@@ -286,20 +227,16 @@ impl Parser<'_> {
             // ^expr parameter (attribute parameter)
             if p.peek(TokenKind::Caret.as_ref()) {
                 p.eat_tokens(1); // consume '^'
-                let attr_expr = p.try_expr_without_extended_call()?;
+                let attr_expr = p.try_attribute_expr()?;
                 if attr_expr == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected attribute expression after `^`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
+                    return Err(
+                        p.expected_after("Expected attribute expression after `^`".to_string())
+                    );
                 }
                 let inner = p.try_type_param()?;
                 if inner == 0 {
-                    return Err(ParseError::invalid_syntax(
+                    return Err(p.expected_after(
                         "Expected a parameter after attribute expression".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
                     ));
                 }
                 return Ok(NodeBuilder::new(NodeKind::AttrParam, p.current_span())
@@ -339,7 +276,7 @@ impl Parser<'_> {
 
     /// Parameter type (used in fn_type).
     /// parameter_type -> itself | *itself | comptime/error/catch/lambda/implicit/quote/assoc param_type
-    ///                 | ...id:type | .id:type | id:type | id:-trait | expr
+    ///                 | ...id:type | .id:type=default | id:type | id:-trait | expr
     pub fn try_param_type(&mut self) -> ParseResult {
         self.scoped(|p| {
             // Wrapper prefixes (recursive)
@@ -390,19 +327,10 @@ impl Parser<'_> {
                 return Ok(vararg);
             }
 
-            // .id : type (optional, no default in param_type context)
-            // Uses decl_clause rules: .decl_clause = just `.` + a decl_clause form
-            if p.peek(&[TokenKind::Dot, TokenKind::Id, TokenKind::Colon]) {
-                p.eat_tokens(1); // eat '.'
-                let inner = p.try_clause()?;
-                if inner == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a declaration clause after `.`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
-                }
-                return Ok(inner); // The clause itself carries the info
+            // .id : type = default (optional)
+            let optional = p.parse_optional_decl(NodeKind::OptionalDeclClause)?;
+            if optional != 0 {
+                return Ok(optional);
             }
 
             // id : type (type bound decl clause)
@@ -473,11 +401,9 @@ impl Parser<'_> {
 
             // Expect 'fn'
             if !p.eat_token(TokenKind::Fn) {
-                return Err(ParseError::invalid_syntax(
-                    "Expected `fn` after function type modifiers".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected `fn` after function type modifiers".to_string())
+                );
             }
 
             // Parse parameter types: (parameter_type*)
@@ -499,11 +425,7 @@ impl Parser<'_> {
         let label_id = if self.eat_token(TokenKind::Colon) {
             let id = self.try_id()?;
             if id == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected an identifier after `:`".to_string(),
-                    TokenKind::Id,
-                    self.current_span(),
-                ));
+                return Err(self.expected_after("Expected an identifier after `:`".to_string()));
             }
             id
         } else {
@@ -512,14 +434,10 @@ impl Parser<'_> {
 
         let predicate = self.try_expr_without_extended_call()?;
         if predicate == 0 {
-            return Err(ParseError::invalid_syntax(
-                format!(
-                    "Expected a predicate expression after `{}`",
-                    keyword.lexme()
-                ),
-                self.peek_next_token().kind,
-                self.current_span(),
-            ));
+            return Err(self.expected_after(format!(
+                "Expected a predicate expression after `{}`",
+                keyword.lexme()
+            )));
         }
 
         Ok(NodeBuilder::new(
@@ -595,11 +513,9 @@ impl Parser<'_> {
             p.eat_tokens(1); // eat the 'where'
             let clauses = p.try_multi(&[Rule::comma("clause", |p| p.try_clause())])?;
             if clauses.is_empty() {
-                return Err(ParseError::invalid_syntax(
-                    "Expected at least one clause after `where`".to_string(),
-                    TokenKind::Id,
-                    p.current_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected at least one clause after `where`".to_string())
+                );
             }
             Ok(clauses)
         })
@@ -615,11 +531,7 @@ impl Parser<'_> {
                 p.eat_tokens(1); // eat the colon
                 let ty = p.try_expr_without_extended_call()?;
                 if ty == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a type after `:`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
+                    return Err(p.expected_after("Expected a type after `:`".to_string()));
                 }
                 return Ok(NodeBuilder::new(NodeKind::ResultWithId, p.current_span())
                     .add_single_child(id)
@@ -629,11 +541,7 @@ impl Parser<'_> {
 
             let ty = p.try_expr_without_extended_call()?;
             if ty == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a type after `->`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected a type after `->`".to_string()));
             }
             Ok(ty)
         })
@@ -647,20 +555,14 @@ impl Parser<'_> {
             let clauses = p.try_clauses()?;
 
             if !p.peek(TokenKind::LBrace.as_ref()) {
-                return Err(ParseError::invalid_syntax(
-                    "expected a block after `struct` declaration".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_block("`struct`", false));
             }
 
             let nodes = p.try_multi_with_bracket(
                 &[
                     Rule::comma("struct field", |p| p.try_struct_field()),
                     Rule::comma("property", |p| p.try_property()),
-                    Rule::semicolon("statement or definition", |p| {
-                        p.try_statement_or_definition()
-                    }),
+                    Rule::semicolon("statement or definition", |p| p.try_item()),
                 ],
                 (TokenKind::LBrace, TokenKind::RBrace),
             )?;
@@ -683,30 +585,18 @@ impl Parser<'_> {
             let id = p.try_id()?;
 
             if !p.eat_token(TokenKind::Colon) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Colon,
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Colon));
             }
 
             let ty = p.try_expr()?;
             if ty == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a type after `:`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected a type after `:`".to_string()));
             }
 
             let default_value = if p.eat_token(TokenKind::Eq) {
                 let value = p.try_expr_without_extended_call()?;
                 if value == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a default value after `=`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
+                    return Err(p.expected_after("Expected a default value after `=`".to_string()));
                 }
                 value
             } else {
@@ -729,20 +619,14 @@ impl Parser<'_> {
             let clauses = p.try_clauses()?;
 
             if !p.peek(TokenKind::LBrace.as_ref()) {
-                return Err(ParseError::invalid_syntax(
-                    "expected a block after `enum` declaration".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_block("`enum`", false));
             }
 
             let nodes = p.try_multi_with_bracket(
                 &[
                     Rule::comma("enum variant", |p| p.try_enum_variant()),
                     Rule::comma("property", |p| p.try_property()),
-                    Rule::semicolon("statement or definition", |p| {
-                        p.try_statement_or_definition()
-                    }),
+                    Rule::semicolon("statement or definition", |p| p.try_item()),
                 ],
                 (TokenKind::LBrace, TokenKind::RBrace),
             )?;
@@ -792,11 +676,9 @@ impl Parser<'_> {
         self.eat_tokens(1); // eat ':'
         let pattern = self.try_pattern()?;
         if pattern == 0 {
-            return Err(ParseError::invalid_syntax(
-                "Expected a pattern after `:` in enum variant".to_string(),
-                self.peek_next_token().kind,
-                self.current_span(),
-            ));
+            return Err(
+                self.expected_after("Expected a pattern after `:` in enum variant".to_string())
+            );
         }
 
         Ok(
@@ -813,11 +695,9 @@ impl Parser<'_> {
         self.eat_tokens(1); // eat '='
         let expr = self.try_expr_without_extended_call()?;
         if expr == 0 {
-            return Err(ParseError::invalid_syntax(
-                "Expected an expression after `=` in enum variant".to_string(),
-                self.peek_next_token().kind,
-                self.current_span(),
-            ));
+            return Err(
+                self.expected_after("Expected an expression after `=` in enum variant".to_string())
+            );
         }
 
         Ok(
@@ -850,11 +730,7 @@ impl Parser<'_> {
         let fields = self.try_multi(&[Rule::comma("struct field", |p| p.try_struct_field())])?;
 
         if !self.eat_token(TokenKind::RBrace) {
-            return Err(ParseError::unexpected_token(
-                TokenKind::RBrace,
-                self.peek_next_token().kind,
-                self.current_span(),
-            ));
+            return Err(self.unexpected(TokenKind::RBrace));
         }
 
         Ok(
@@ -873,11 +749,7 @@ impl Parser<'_> {
         })])?;
 
         if !self.eat_token(TokenKind::RParen) {
-            return Err(ParseError::unexpected_token(
-                TokenKind::RParen,
-                self.peek_next_token().kind,
-                self.current_span(),
-            ));
+            return Err(self.unexpected(TokenKind::RParen));
         }
 
         Ok(
@@ -898,11 +770,7 @@ impl Parser<'_> {
             let super_trait = if p.eat_token(TokenKind::ColonMinus) {
                 let st = p.try_expr_without_extended_call()?;
                 if st == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a super trait after `:-`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
+                    return Err(p.expected_after("Expected a super trait after `:-`".to_string()));
                 }
                 st
             } else {
@@ -912,20 +780,14 @@ impl Parser<'_> {
             let clauses = p.try_clauses()?;
 
             if !p.peek(TokenKind::LBrace.as_ref()) {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a block after `trait` declaration".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_block("`trait`", false));
             }
 
             let nodes = p.try_multi_with_bracket(
                 &[
                     Rule::semicolon("associated declaration", |p| p.try_assoc_decl()),
                     Rule::comma("property", |p| p.try_property()),
-                    Rule::semicolon("statement or definition", |p| {
-                        p.try_statement_or_definition()
-                    }),
+                    Rule::semicolon("statement or definition", |p| p.try_item()),
                 ],
                 (TokenKind::LBrace, TokenKind::RBrace),
             )?;
@@ -949,11 +811,7 @@ impl Parser<'_> {
             p.eat_tokens(1); // consume 'assoc'
             let id = p.try_id()?;
             if id == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected an identifier after `assoc`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected an identifier after `assoc`".to_string()));
             }
 
             // Optional type parameters: <param*>
@@ -964,18 +822,12 @@ impl Parser<'_> {
 
             // : type
             if !p.eat_token(TokenKind::Colon) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Colon,
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Colon));
             }
             let type_expr = p.try_expr_without_extended_call()?;
             if type_expr == 0 {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected a type after `:` in `assoc` declaration".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
                 ));
             }
 
@@ -983,11 +835,9 @@ impl Parser<'_> {
             let default_expr = if p.eat_token(TokenKind::Eq) {
                 let d = p.try_expr_without_extended_call()?;
                 if d == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a default expression after `=`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
+                    return Err(
+                        p.expected_after("Expected a default expression after `=`".to_string())
+                    );
                 }
                 d
             } else {
@@ -1012,11 +862,7 @@ impl Parser<'_> {
             p.eat_tokens(1);
             let id = p.try_id()?;
             if !p.peek(TokenKind::LParen.as_ref()) {
-                return Err(ParseError::invalid_syntax(
-                    "expected `(` after function name".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("expected `(` after function name".to_string()));
             }
             let params = p.try_multi_with_bracket(
                 &[Rule::comma("parameter", |p| p.try_param())],
@@ -1028,10 +874,8 @@ impl Parser<'_> {
             let handles = if p.eat_token(TokenKind::Handles) {
                 let eff = p.try_expr_without_extended_call()?;
                 if eff == 0 {
-                    return Err(ParseError::invalid_syntax(
+                    return Err(p.expected_after(
                         "Expected an effect expression after `handles`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
                     ));
                 }
                 eff
@@ -1045,22 +889,14 @@ impl Parser<'_> {
                 && !p.peek(TokenKind::Eq.as_ref())
                 && !p.peek(TokenKind::Semi.as_ref())
             {
-                return Err(ParseError::invalid_syntax(
-                    "expected a block or `=` after function declaration".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_block("function", true));
             }
 
             if p.peek(TokenKind::Eq.as_ref()) {
                 p.eat_tokens(1);
                 let expr = p.try_expr()?;
                 if expr == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected an expression after `=`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
+                    return Err(p.expected_after("Expected an expression after `=`".to_string()));
                 }
                 Ok(NodeBuilder::new(NodeKind::Function, p.current_span())
                     .add_single_child(id)
@@ -1071,15 +907,10 @@ impl Parser<'_> {
                     .add_single_child(expr)
                     .build(&mut p.ast))
             } else if p.peek(TokenKind::LBrace.as_ref()) {
-                let nodes = p.try_multi_with_bracket(
-                    &[
-                        Rule::comma("property", |p| p.try_property()),
-                        Rule::semicolon("statement or definition", |p| {
-                            p.try_statement_or_definition()
-                        }),
-                    ],
-                    (TokenKind::LBrace, TokenKind::RBrace),
-                )?;
+                let nodes = p.try_statements_in_braces(&[
+                    Rule::comma("property", |p| p.try_property()),
+                    Rule::semicolon("statement or definition", |p| p.try_body_statement()),
+                ])?;
 
                 let block = NodeBuilder::new(NodeKind::Block, p.current_span())
                     .add_multiple_children(nodes)
@@ -1103,35 +934,29 @@ impl Parser<'_> {
                     .add_single_child(0)
                     .build(&mut p.ast))
             } else {
-                Err(ParseError::invalid_syntax(
-                    "expected a block or `=` after function declaration".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ))
+                Err(p.expected_block("function", true))
             }
         })
     }
 
     // mod id { (definition | statement)* }
+    // mod id
     pub fn try_module(&mut self) -> ParseResult {
         self.scoped_with_expected_prefix(TokenKind::Mod.as_ref(), |p| {
             p.eat_tokens(1);
             let id = p.try_id()?;
 
+            // Without a block the module's body lives in a file of its own.
             if !p.peek(TokenKind::LBrace.as_ref()) {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a block after `mod` declaration".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Ok(NodeBuilder::new(NodeKind::ModStatement, p.current_span())
+                    .add_single_child(id)
+                    .build(&mut p.ast));
             }
 
             let nodes = p.try_multi_with_bracket(
                 &[
                     Rule::comma("property", |p| p.try_property()),
-                    Rule::semicolon("statement or definition", |p| {
-                        p.try_statement_or_definition()
-                    }),
+                    Rule::semicolon("statement or definition", |p| p.try_item()),
                 ],
                 (TokenKind::LBrace, TokenKind::RBrace),
             )?;
@@ -1152,40 +977,26 @@ impl Parser<'_> {
             p.eat_tokens(1);
             let expr = p.try_expr_without_extended_call()?;
             if expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a type or trait after `impl`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected a type or trait after `impl`".to_string()));
             }
 
             if p.eat_token(TokenKind::For) {
                 // impl expr for expr clauses? { (assoc_decl | definition | statement)* }
                 let ty = p.try_expr_without_extended_call()?;
                 if ty == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a type after `for`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
+                    return Err(p.expected_after("Expected a type after `for`".to_string()));
                 }
                 let clauses = p.try_clauses()?;
 
                 if !p.peek(TokenKind::LBrace.as_ref()) {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a block after `impl ... for` declaration".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.expected_block("`impl ... for`", false));
                 }
 
                 let nodes = p.try_multi_with_bracket(
                     &[
                         Rule::semicolon("associated declaration", |p| p.try_assoc_decl()),
                         Rule::comma("property", |p| p.try_property()),
-                        Rule::semicolon("statement or definition", |p| {
-                            p.try_statement_or_definition()
-                        }),
+                        Rule::semicolon("statement or definition", |p| p.try_item()),
                     ],
                     (TokenKind::LBrace, TokenKind::RBrace),
                 )?;
@@ -1204,19 +1015,13 @@ impl Parser<'_> {
                 // impl expr clauses? { (definition | statement)* }
                 let clauses = p.try_clauses()?;
                 if !p.peek(TokenKind::LBrace.as_ref()) {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a block after `impl` declaration".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.expected_block("`impl`", false));
                 }
 
                 let nodes = p.try_multi_with_bracket(
                     &[
                         Rule::comma("property", |p| p.try_property()),
-                        Rule::semicolon("statement or definition", |p| {
-                            p.try_statement_or_definition()
-                        }),
+                        Rule::semicolon("statement or definition", |p| p.try_item()),
                     ],
                     (TokenKind::LBrace, TokenKind::RBrace),
                 )?;
@@ -1240,40 +1045,26 @@ impl Parser<'_> {
 
             let expr = p.try_expr_without_extended_call()?;
             if expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a type or trait after `extend`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected a type or trait after `extend`".to_string()));
             }
 
             if p.eat_token(TokenKind::For) {
                 // extend expr for expr clauses? { (assoc_decl | definition | statement)* }
                 let ty = p.try_expr_without_extended_call()?;
                 if ty == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a type after `for`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
+                    return Err(p.expected_after("Expected a type after `for`".to_string()));
                 }
                 let clauses = p.try_clauses()?;
 
                 if !p.peek(TokenKind::LBrace.as_ref()) {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a block after `extend ... for` declaration".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.expected_block("`extend ... for`", false));
                 }
 
                 let nodes = p.try_multi_with_bracket(
                     &[
                         Rule::semicolon("associated declaration", |p| p.try_assoc_decl()),
                         Rule::comma("property", |p| p.try_property()),
-                        Rule::semicolon("statement or definition", |p| {
-                            p.try_statement_or_definition()
-                        }),
+                        Rule::semicolon("statement or definition", |p| p.try_item()),
                     ],
                     (TokenKind::LBrace, TokenKind::RBrace),
                 )?;
@@ -1292,19 +1083,13 @@ impl Parser<'_> {
                 // extend expr clauses? { (definition | statement)* }
                 let clauses = p.try_clauses()?;
                 if !p.peek(TokenKind::LBrace.as_ref()) {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a block after `extend` declaration".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.expected_block("`extend`", false));
                 }
 
                 let nodes = p.try_multi_with_bracket(
                     &[
                         Rule::comma("property", |p| p.try_property()),
-                        Rule::semicolon("statement or definition", |p| {
-                            p.try_statement_or_definition()
-                        }),
+                        Rule::semicolon("statement or definition", |p| p.try_item()),
                     ],
                     (TokenKind::LBrace, TokenKind::RBrace),
                 )?;
@@ -1329,28 +1114,20 @@ impl Parser<'_> {
 
             let trait_expr = p.try_expr_without_extended_call()?;
             if trait_expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a trait expression after `derive`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a trait expression after `derive`".to_string())
+                );
             }
 
             if !p.eat_token(TokenKind::For) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::For,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::For));
             }
 
             let target = p.try_expr_without_extended_call()?;
             if target == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a target type after `for` in derive".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a target type after `for` in derive".to_string())
+                );
             }
 
             let clauses = p.try_clauses()?;
@@ -1369,11 +1146,7 @@ impl Parser<'_> {
             p.eat_tokens(1); // eat the 'effect'
             let id = p.try_id()?;
             if !p.peek(TokenKind::LParen.as_ref()) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::LParen,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::LParen));
             }
             let params = p.try_multi_with_bracket(
                 &[Rule::comma("parameter", |p| p.try_param())],
@@ -1386,26 +1159,17 @@ impl Parser<'_> {
 
             // Optional body: block | (= expr)
             let body = if p.peek(TokenKind::LBrace.as_ref()) {
-                let nodes = p.try_multi_with_bracket(
-                    &[
-                        Rule::comma("property", |p| p.try_property()),
-                        Rule::semicolon("statement or definition", |p| {
-                            p.try_statement_or_definition()
-                        }),
-                    ],
-                    (TokenKind::LBrace, TokenKind::RBrace),
-                )?;
+                let nodes = p.try_statements_in_braces(&[
+                    Rule::comma("property", |p| p.try_property()),
+                    Rule::semicolon("statement or definition", |p| p.try_body_statement()),
+                ])?;
                 NodeBuilder::new(NodeKind::Block, p.current_span())
                     .add_multiple_children(nodes)
                     .build(&mut p.ast)
             } else if p.eat_token(TokenKind::Eq) {
                 let expr = p.try_expr()?;
                 if expr == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected an expression after `=`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
+                    return Err(p.expected_after("Expected an expression after `=`".to_string()));
                 }
                 expr
             } else {
@@ -1432,20 +1196,14 @@ impl Parser<'_> {
             let clauses = p.try_clauses()?;
 
             if !p.peek(TokenKind::LBrace.as_ref()) {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a block after `union` declaration".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_block("`union`", false));
             }
 
             let nodes = p.try_multi_with_bracket(
                 &[
                     Rule::comma("union variant", |p| p.try_union_variant()),
                     Rule::comma("property", |p| p.try_property()),
-                    Rule::semicolon("statement or definition", |p| {
-                        p.try_statement_or_definition()
-                    }),
+                    Rule::semicolon("statement or definition", |p| p.try_item()),
                 ],
                 (TokenKind::LBrace, TokenKind::RBrace),
             )?;
@@ -1469,11 +1227,9 @@ impl Parser<'_> {
             p.eat_tokens(1); // eat ':'
             let ty = p.try_expr_without_extended_call()?;
             if ty == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a type after `:` in union variant".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a type after `:` in union variant".to_string())
+                );
             }
             Ok(NodeBuilder::new(NodeKind::UnionVariant, p.current_span())
                 .add_single_child(id)
@@ -1488,21 +1244,13 @@ impl Parser<'_> {
             p.eat_tokens(1); // eat 'const'
             let id = p.try_id()?;
             if id == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected an identifier after `const`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected an identifier after `const`".to_string()));
             }
 
             let ty = if p.eat_token(TokenKind::Colon) {
                 let t = p.try_expr_without_extended_call()?;
                 if t == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a type after `:`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
+                    return Err(p.expected_after("Expected a type after `:`".to_string()));
                 }
                 t
             } else {
@@ -1510,19 +1258,13 @@ impl Parser<'_> {
             };
 
             if !p.eat_token(TokenKind::Eq) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Eq,
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Eq));
             }
 
             let init = p.try_expr()?;
             if init == 0 {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected an expression after `=` in const definition".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
                 ));
             }
 
@@ -1540,11 +1282,7 @@ impl Parser<'_> {
             p.eat_tokens(1);
             let id = p.try_id()?;
             if id == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected identifier after 'typealias'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected identifier after 'typealias'".to_string()));
             }
 
             let params = p.try_multi_with_bracket(
@@ -1553,19 +1291,11 @@ impl Parser<'_> {
             )?;
 
             if !p.eat_token(TokenKind::Eq) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Eq,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Eq));
             }
             let type_expr = p.try_expr_without_extended_call()?;
             if type_expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected type expression after '='".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected type expression after '='".to_string()));
             }
 
             Ok(NodeBuilder::new(NodeKind::TypealiasDef, p.current_span())
@@ -1582,11 +1312,7 @@ impl Parser<'_> {
             p.eat_tokens(1);
             let id = p.try_id()?;
             if id == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected identifier after 'newtype'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected identifier after 'newtype'".to_string()));
             }
 
             let params = p.try_multi_with_bracket(
@@ -1595,19 +1321,11 @@ impl Parser<'_> {
             )?;
 
             if !p.eat_token(TokenKind::Eq) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Eq,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Eq));
             }
             let type_expr = p.try_expr_without_extended_call()?;
             if type_expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected type expression after '='".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected type expression after '='".to_string()));
             }
 
             Ok(NodeBuilder::new(NodeKind::NewtypeDef, p.current_span())
@@ -1624,21 +1342,12 @@ impl Parser<'_> {
             p.eat_tokens(1); // eat the 'test'
             let id = p.try_id()?;
             if !p.peek(TokenKind::LBrace.as_ref()) {
-                return Err(ParseError::invalid_syntax(
-                    "expected a block after `test` declaration".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_block("`test`", false));
             }
-            let nodes = p.try_multi_with_bracket(
-                &[
-                    Rule::comma("property", |p| p.try_property()),
-                    Rule::semicolon("statement or definition", |p| {
-                        p.try_statement_or_definition()
-                    }),
-                ],
-                (TokenKind::LBrace, TokenKind::RBrace),
-            )?;
+            let nodes = p.try_statements_in_braces(&[
+                Rule::comma("property", |p| p.try_property()),
+                Rule::semicolon("statement or definition", |p| p.try_body_statement()),
+            ])?;
             let block = NodeBuilder::new(NodeKind::Block, p.current_span())
                 .add_multiple_children(nodes)
                 .build(&mut p.ast);
@@ -1655,11 +1364,7 @@ impl Parser<'_> {
             p.eat_tokens(1); // eat the 'case'
             let id = p.try_id()?;
             if id == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected an identifier after `case`".to_string(),
-                    TokenKind::Id,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected an identifier after `case`".to_string()));
             }
 
             let params = p.try_multi_with_bracket(
@@ -1669,11 +1374,7 @@ impl Parser<'_> {
 
             let return_type = p.try_return_type()?;
             if return_type == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a return type after `->`".to_string(),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after("Expected a return type after `->`".to_string()));
             }
 
             let clauses = p.try_clauses()?;
@@ -1681,11 +1382,7 @@ impl Parser<'_> {
             if p.eat_token(TokenKind::Eq) {
                 let expr = p.try_expr()?;
                 if expr == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected an expression after `=`".to_string(),
-                        p.peek_next_token().kind,
-                        p.current_span(),
-                    ));
+                    return Err(p.expected_after("Expected an expression after `=`".to_string()));
                 }
                 Ok(NodeBuilder::new(NodeKind::CaseDef, p.current_span())
                     .add_single_child(id)
@@ -1695,15 +1392,10 @@ impl Parser<'_> {
                     .add_single_child(expr)
                     .build(&mut p.ast))
             } else if p.peek(TokenKind::LBrace.as_ref()) {
-                let nodes = p.try_multi_with_bracket(
-                    &[
-                        Rule::comma("property", |p| p.try_property()),
-                        Rule::semicolon("statement or definition", |p| {
-                            p.try_statement_or_definition()
-                        }),
-                    ],
-                    (TokenKind::LBrace, TokenKind::RBrace),
-                )?;
+                let nodes = p.try_statements_in_braces(&[
+                    Rule::comma("property", |p| p.try_property()),
+                    Rule::semicolon("statement or definition", |p| p.try_body_statement()),
+                ])?;
 
                 let block = NodeBuilder::new(NodeKind::Block, p.current_span())
                     .add_multiple_children(nodes)
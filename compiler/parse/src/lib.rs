@@ -9,3 +9,666 @@ pub mod items;
 pub mod others;
 pub mod patterns;
 pub mod statements;
+
+use std::collections::HashMap;
+
+use ast::Ast;
+use error::ParseError;
+use lex::{Symbol, Token};
+use parser::Parser;
+use rustc_span::{BytePos, SourceMap};
+
+/// Parse the tokens of a file into its tree, with every error found.
+///
+/// As with [`lex::lex`], an error does not end the parse: a bad item or
+/// statement is recorded and skipped, and the tree holds everything else.
+pub fn parse_file(
+    source_map: &SourceMap,
+    tokens: Vec<Token>,
+    symbols: HashMap<usize, Symbol>,
+    start_pos: BytePos,
+) -> (Ast, Vec<ParseError>) {
+    let mut parser = Parser::new(source_map, tokens, symbols, start_pos);
+    let errors = parser.parse_recovering();
+    (parser.finalize(), errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use ast::{
+        Ast, DecodeError, NodeIndex, NodeKind,
+        ast_visitor::{AstWalker, NodeKindCounter, count_ast_nodes},
+    };
+    use diagnostic::{
+        DiagnosticContext,
+        testing::{assert_diagnostic, assert_registered},
+    };
+    use rustc_span::{DUMMY_SP, FileName, SourceMap, source_map::FilePathMapping};
+
+    use crate::{error::ParseError, parser::Parser};
+
+    /// Parse `src` as a file and return its tree; fails on any diagnostic.
+    fn parse(source_map: &SourceMap, src: &str) -> Ast {
+        let file = source_map.new_source_file(FileName::Custom("main.fl".into()), src.into());
+        let diag_ctx = DiagnosticContext::new(source_map);
+        let (tokens, symbols, lex_errors) = lex::lex(src, file.start_pos);
+        assert!(lex_errors.is_empty(), "{:?}", lex_errors);
+        let mut parser = Parser::new(source_map, tokens, symbols, file.start_pos);
+        parser.parse(&diag_ctx);
+        assert_eq!(diag_ctx.error_count(), 0);
+        parser.finalize()
+    }
+
+    /// The top-level items of a parsed file.
+    fn items(ast: &Ast) -> &[ast::NodeIndex] {
+        let list = ast.get_children(ast.root)[0];
+        ast.get_multi_child_slice(list).unwrap()
+    }
+
+    #[test]
+    fn node_kind_counter_walks_every_node_of_a_parsed_function() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let src = "fn add(a: Int, b: Int) -> Int {\n    a + b\n}\n";
+        let ast = parse(&source_map, src);
+
+        let mut counter = NodeKindCounter::new();
+        counter.walk_node(&ast, ast.root);
+
+        assert_eq!(counter.count(NodeKind::FileScope), 1);
+        assert_eq!(counter.count(NodeKind::Function), 1);
+        assert_eq!(counter.count(NodeKind::Add), 1);
+        assert_eq!(counter.count(NodeKind::Struct), 0);
+        assert_eq!(
+            counter.total(),
+            count_ast_nodes(&ast, ast.root, &source_map) as usize
+        );
+    }
+
+    #[test]
+    fn leave_runs_after_the_children() {
+        struct Depth {
+            depth: usize,
+            add_depth: Option<usize>,
+        }
+        impl AstWalker for Depth {
+            fn enter(&mut self, _ast: &Ast, _node: NodeIndex, kind: NodeKind) {
+                self.depth += 1;
+                if kind == NodeKind::Add {
+                    self.add_depth = Some(self.depth);
+                }
+            }
+            fn leave(&mut self, _ast: &Ast, _node: NodeIndex, _kind: NodeKind) {
+                self.depth -= 1;
+            }
+        }
+
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let src = "fn add(a: Int, b: Int) -> Int {\n    a + b\n}\n";
+        let ast = parse(&source_map, src);
+        let mut walker = Depth {
+            depth: 0,
+            add_depth: None,
+        };
+        walker.walk_node(&ast, ast.root);
+
+        assert_eq!(walker.depth, 0);
+        // The sum sits inside the file, the function and its body.
+        assert!(walker.add_depth.is_some_and(|depth| depth > 3));
+    }
+
+    #[test]
+    fn encoded_ast_decodes_to_the_same_tree() {
+        let src = "struct Point {\n    x: Int,\n    y: Int,\n}\n\nfn norm(p: Point) -> Int {\n    let d = p.x * p.x + p.y * p.y;\n    if d > 0 { d } else { -d }\n}\n";
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(FileName::Custom("main.fl".into()), src.into());
+        let diag_ctx = DiagnosticContext::new(&source_map);
+        let (tokens, symbols, _) = lex::lex(src, file.start_pos);
+        let mut parser = Parser::new(&source_map, tokens, symbols, file.start_pos);
+        parser.parse(&diag_ctx);
+        let ast = parser.finalize();
+        assert_eq!(diag_ctx.error_count(), 0);
+
+        let dump = ast.dump_to_s_expression(ast.root, &source_map).unwrap();
+
+        let bytes = ast.encode();
+        let decoded = Ast::decode(&bytes).expect("round trip");
+        assert_eq!(
+            decoded.dump_to_s_expression(decoded.root, &source_map),
+            Ok(dump)
+        );
+        assert_eq!(decoded.parents, ast.parents);
+
+        assert_eq!(
+            Ast::decode(&bytes[..bytes.len() - 4]).unwrap_err(),
+            DecodeError::Truncated
+        );
+        assert_eq!(Ast::decode(b"{}").unwrap_err(), DecodeError::BadMagic);
+    }
+
+    #[test]
+    fn line_doc_comment_is_attached_to_the_next_item() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let src =
+            "/// hello\nfn greet() {\n    1\n}\n\n-- not a doc comment\nfn plain() {\n    2\n}\n";
+        let ast = parse(&source_map, src);
+        let items = items(&ast);
+        assert_eq!(ast.get_node_kind(items[0]), Some(NodeKind::Function));
+        assert_eq!(
+            ast.doc_comment(items[0], &source_map).as_deref(),
+            Some("hello")
+        );
+        assert_eq!(ast.doc_comment(items[1], &source_map), None);
+    }
+
+    #[test]
+    fn doc_comments_merge_and_pass_through_pub() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let src = "/// A point.\n/// In the plane.\npub struct Point {\n    x: Int,\n}\n\n/**\n * Twice `x`.\n */\nfn double(x: Int) -> Int {\n    x * 2\n}\n";
+        let ast = parse(&source_map, src);
+        let items = items(&ast);
+        assert_eq!(ast.get_node_kind(items[0]), Some(NodeKind::Pub));
+        let point = ast.get_children(items[0])[0];
+        assert_eq!(
+            ast.doc_comment(point, &source_map).as_deref(),
+            Some("A point.\nIn the plane.")
+        );
+        assert_eq!(
+            ast.doc_comment(items[1], &source_map).as_deref(),
+            Some("Twice `x`.")
+        );
+
+        let decoded = Ast::decode(&ast.encode()).expect("round trip");
+        assert_eq!(decoded.doc_comments, ast.doc_comments);
+    }
+
+    #[test]
+    fn union_variants_parse_as_id_and_type() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let src = "union Number {\n    int: Int,\n    real: Float,\n}\n";
+        let ast = parse(&source_map, src);
+        let items = items(&ast);
+        assert_eq!(ast.get_node_kind(items[0]), Some(NodeKind::UnionDef));
+        assert_eq!(
+            ast.dump_to_s_expression(items[0], &source_map).unwrap(),
+            "(UnionDef (Id Number) [] (Block (UnionVariant (Id int) (Id Int)) (UnionVariant (Id real) (Id Float))))"
+        );
+    }
+
+    #[test]
+    fn const_definitions_at_item_scope_parse_as_const_def() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let src = "const LIMIT: Int = 10;\nconst ZERO = 0;\n";
+        let ast = parse(&source_map, src);
+        let items = items(&ast);
+        assert_eq!(
+            ast.dump_to_s_expression(items[0], &source_map).unwrap(),
+            "(ConstDef (Id LIMIT) (Id Int) (Int 10))"
+        );
+        assert_eq!(
+            ast.dump_to_s_expression(items[1], &source_map).unwrap(),
+            "(ConstDef (Id ZERO) (<invalid node>) (Int 0))"
+        );
+    }
+
+    #[test]
+    fn const_in_a_function_body_stays_a_local_declaration() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let src = "fn main() {\n    const x = 1;\n    x\n}\n";
+        let ast = parse(&source_map, src);
+        let dump = ast
+            .dump_to_s_expression(items(&ast)[0], &source_map)
+            .unwrap();
+        assert!(dump.contains("(ConstDecl (Id x) "), "{}", dump);
+        assert!(!dump.contains("ConstDef"), "{}", dump);
+    }
+
+    #[test]
+    fn use_statements_parse_each_path_shape() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let src = "use a.b.c;\nuse a.{b, c};\nuse a.*;\nuse .super.x;\nuse ..x;\nuse @pkg.x;\nuse a.b as c;\n";
+        let ast = parse(&source_map, src);
+        let dumps: Vec<String> = items(&ast)
+            .iter()
+            .map(|&item| ast.dump_to_s_expression(item, &source_map).unwrap())
+            .collect();
+        assert_eq!(
+            dumps,
+            [
+                "(UseStatement (ProjectionPath (ProjectionPath (Id a) (Id b)) (Id c)))",
+                "(UseStatement (ProjectionMultiPath (Id a) (Id b) (Id c)))",
+                "(UseStatement (ProjectionAllPath (Id a)))",
+                "(UseStatement (SuperPath (ProjectionPath (Id super) (Id x))))",
+                "(UseStatement (SuperPath (SuperPath (Id x))))",
+                "(UseStatement (PackagePath (ProjectionPath (Id pkg) (Id x))))",
+                "(UseStatement (PathAsBind (ProjectionPath (Id a) (Id b)) (Id c)))",
+            ]
+        );
+    }
+
+    #[test]
+    fn bare_mod_parses_as_mod_statement_and_braced_mod_as_module_def() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let src = "mod shapes;\npub mod net;\nmod geo {\n    fn f() {\n        1\n    }\n}\n";
+        let ast = parse(&source_map, src);
+        let items = items(&ast);
+        assert_eq!(items.len(), 3);
+        assert_eq!(
+            ast.dump_to_s_expression(items[0], &source_map).unwrap(),
+            "(ModStatement (Id shapes))"
+        );
+        assert_eq!(
+            ast.dump_to_s_expression(items[1], &source_map).unwrap(),
+            "(Pub (ModStatement (Id net)))"
+        );
+        assert_eq!(ast.get_node_kind(items[2]), Some(NodeKind::ModuleDef));
+    }
+
+    #[test]
+    fn caret_attribute_wraps_the_definition_it_precedes() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let src = "^deprecated fn old() {\n    1\n}\n";
+        let ast = parse(&source_map, src);
+        let items = items(&ast);
+        assert_eq!(ast.get_node_kind(items[0]), Some(NodeKind::Attribute));
+        let [attr, def] = ast.get_children(items[0]) else {
+            panic!("an attribute has two children");
+        };
+        assert_eq!(
+            ast.dump_to_s_expression(*attr, &source_map).unwrap(),
+            "(Id deprecated)"
+        );
+        assert_eq!(ast.get_node_kind(*def), Some(NodeKind::Function));
+    }
+
+    #[test]
+    fn stacked_attributes_and_modifiers_nest_outermost_first() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let src = "^hot ^cold fn f() {\n    1\n}\n\ninline pure fn g() {\n    2\n}\n";
+        let ast = parse(&source_map, src);
+        let items = items(&ast);
+
+        // Strip the attribute wrappers off a node, recording each wrapper's
+        // kind and the text of its attribute.
+        let unwrap = |mut node: ast::NodeIndex| {
+            let mut layers = Vec::new();
+            while let Some(kind @ (NodeKind::Attribute | NodeKind::AttributeSetTrue)) =
+                ast.get_node_kind(node)
+            {
+                let children = ast.get_children(node);
+                let attr = ast.get_span(children[0]).unwrap();
+                layers.push((kind, source_map.span_to_snippet(attr).unwrap()));
+                node = children[1];
+            }
+            (layers, ast.get_node_kind(node))
+        };
+
+        let (layers, def) = unwrap(items[0]);
+        assert_eq!(
+            layers,
+            [
+                (NodeKind::Attribute, "hot".to_string()),
+                (NodeKind::Attribute, "cold".to_string()),
+            ]
+        );
+        assert_eq!(def, Some(NodeKind::Function));
+
+        let (layers, def) = unwrap(items[1]);
+        assert_eq!(
+            layers,
+            [
+                (NodeKind::AttributeSetTrue, "inline".to_string()),
+                (NodeKind::AttributeSetTrue, "pure".to_string()),
+            ]
+        );
+        assert_eq!(def, Some(NodeKind::Function));
+    }
+
+    #[test]
+    fn bad_statements_in_a_block_are_each_reported_and_skipped() {
+        let src = "fn main() {\n    let a = ;\n    let b = 1 + ;\n    let c = 3;\n    c\n}\n";
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(FileName::Custom("main.fl".into()), src.into());
+        let diag_ctx = DiagnosticContext::new(&source_map);
+        let (tokens, symbols, _) = lex::lex(src, file.start_pos);
+        let mut parser = Parser::new(&source_map, tokens, symbols, file.start_pos);
+        parser.parse(&diag_ctx);
+        let ast = parser.finalize();
+        assert_eq!(diag_ctx.error_count(), 2);
+
+        let items = items(&ast);
+        assert_eq!(ast.get_node_kind(items[0]), Some(NodeKind::Function));
+        let body = *ast.get_children(items[0]).last().unwrap();
+        let stmts = ast
+            .get_multi_child_slice(ast.get_children(body)[0])
+            .unwrap();
+        assert_eq!(stmts.len(), 2);
+        assert_eq!(ast.get_node_kind(stmts[0]), Some(NodeKind::LetDecl));
+        assert_eq!(
+            ast.dump_to_s_expression(ast.get_children(stmts[0])[0], &source_map)
+                .unwrap(),
+            "(Id c)"
+        );
+    }
+
+    #[test]
+    fn broken_items_are_each_reported_and_the_rest_of_the_file_parses() {
+        let src = "struct P\n\nfn one() {\n    1\n}\n\nenum E\n\nfn two() {\n    2\n}\n\nfn bad() => 4\n\nfn three() {\n    3\n}\n";
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(FileName::Custom("main.fl".into()), src.into());
+        let (tokens, symbols, _) = lex::lex(src, file.start_pos);
+        let (ast, errors) = crate::parse_file(&source_map, tokens, symbols, file.start_pos);
+
+        let error_snippets: Vec<String> = errors
+            .iter()
+            .map(|error| source_map.span_to_snippet(error.to_span()).unwrap())
+            .collect();
+        assert_eq!(error_snippets, ["fn", "fn", "=>"]);
+
+        let names: Vec<String> = items(&ast)
+            .iter()
+            .map(|&item| {
+                let id = ast.get_children(item)[0];
+                ast.dump_to_s_expression(id, &source_map).unwrap()
+            })
+            .collect();
+        assert_eq!(names, ["(Id one)", "(Id two)", "(Id three)"]);
+    }
+
+    #[test]
+    fn lookahead_past_the_end_is_eof() {
+        use lex::TokenKind;
+
+        let src = "fn f";
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(FileName::Custom("main.fl".into()), src.into());
+        let (tokens, symbols, _) = lex::lex(src, file.start_pos);
+        let mut parser = Parser::new(&source_map, tokens, symbols, file.start_pos);
+
+        assert_eq!(parser.peek_nth_kind(0), TokenKind::Fn);
+        assert_eq!(parser.peek_nth_kind(1), TokenKind::Id);
+        assert_eq!(parser.peek_nth_kind(2), TokenKind::Eof);
+        assert_eq!(parser.peek_nth_kind(3), TokenKind::Eof);
+        assert_eq!(parser.peek_nth_kind(usize::MAX), TokenKind::Eof);
+
+        parser.eat_tokens(2);
+        assert_eq!(parser.peek_nth_kind(0), TokenKind::Eof);
+        assert_eq!(parser.peek_nth(5).kind, TokenKind::Eof);
+        assert_eq!(parser.peek_nth(5).from, src.len());
+    }
+
+    #[test]
+    fn bit_vector_patterns_parse_in_each_radix() {
+        let src = "fn f(v: Int) {\n    let y = v match {\n        0b1010 => 1,\n        0O7_7 => 2,\n        0xFF 10 (rest: Int) => 3,\n        _ => 0,\n    };\n    y\n}\n";
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let ast = parse(&source_map, src);
+        let dump = ast.dump_to_s_expression(ast.root, &source_map).unwrap();
+
+        for pattern in [
+            "(BitVecBinPattern (Int 0b1010))",
+            "(BitVecOctPattern (Int 0O7_7))",
+            "(BitVecHexPattern (Int 0xFF) (Int 10) (TypeBoundDeclClause (Id rest) (Id Int)))",
+        ] {
+            assert!(dump.contains(pattern), "no {} in {}", pattern, dump);
+        }
+    }
+
+    #[test]
+    fn loop_exits_take_an_optional_label_and_while_guard() {
+        let src = "fn f(n: Int) {\n    while :outer n > 0 {\n        break;\n        break :outer;\n        break while n > 1;\n        break :outer while n > 2;\n        continue;\n        continue :outer;\n        continue while n > 3;\n        continue :outer while n > 4;\n        resume 1 while n > 5;\n        return while n > 6;\n    }\n}\n";
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let ast = parse(&source_map, src);
+        let dump = ast.dump_to_s_expression(ast.root, &source_map).unwrap();
+
+        for statement in [
+            "(BreakStatement (<invalid node>) (<invalid node>))",
+            "(BreakStatement (Id outer) (<invalid node>))",
+            "(BreakStatement (<invalid node>) (BoolGt (Id n) (Int 1)))",
+            "(BreakStatement (Id outer) (BoolGt (Id n) (Int 2)))",
+            "(ContinueStatement (<invalid node>) (<invalid node>))",
+            "(ContinueStatement (Id outer) (<invalid node>))",
+            "(ContinueStatement (<invalid node>) (BoolGt (Id n) (Int 3)))",
+            "(ContinueStatement (Id outer) (BoolGt (Id n) (Int 4)))",
+            "(ResumeStatement (Int 1) (BoolGt (Id n) (Int 5)))",
+            "(ReturnStatement (<invalid node>) (BoolGt (Id n) (Int 6)))",
+        ] {
+            assert!(dump.contains(statement), "no {} in {}", statement, dump);
+        }
+    }
+
+    /// Parse `src`, expecting it to fail, and return the diagnostic context
+    /// holding the error.
+    fn parse_error<R>(src: &str, f: impl FnOnce(&DiagnosticContext<'_>) -> R) -> R {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(FileName::Custom("main.fl".into()), src.into());
+        let diag_ctx = DiagnosticContext::new(&source_map);
+        let (tokens, symbols, _) = lex::lex(src, file.start_pos);
+        let mut parser = Parser::new(&source_map, tokens, symbols, file.start_pos);
+        parser.parse(&diag_ctx);
+        f(&diag_ctx)
+    }
+
+    #[test]
+    fn struct_without_a_block_notes_the_line_break() {
+        let src = "struct Point\n\nfn main() {\n    1\n}\n";
+        parse_error(src, |diag| {
+            assert_diagnostic(diag, 2005, "fn");
+            // SAFETY: nothing is emitted while the slice is borrowed.
+            let diagnostics = unsafe { diag.diagnostics() };
+            assert_eq!(
+                diagnostics[0].message,
+                "expected a block after `struct` declaration"
+            );
+            assert_eq!(
+                diagnostics[0].notes,
+                [
+                    "a block `{ ... }` is required here; the declaration cannot end at the line break"
+                ]
+            );
+            assert!(diagnostics[0].helps.is_empty());
+        });
+    }
+
+    #[test]
+    fn fn_with_a_fat_arrow_body_suggests_the_eq_form() {
+        let src = "fn answer() => 42\n";
+        parse_error(src, |diag| {
+            assert_diagnostic(diag, 2005, "=>");
+            // SAFETY: nothing is emitted while the slice is borrowed.
+            let diagnostics = unsafe { diag.diagnostics() };
+            assert_eq!(
+                diagnostics[0].message,
+                "expected a block or `=` after function declaration"
+            );
+            assert_eq!(
+                diagnostics[0].helps,
+                ["write `= expr` for a body that is a single expression"]
+            );
+            assert!(diagnostics[0].notes.is_empty());
+        });
+    }
+
+    #[test]
+    fn bit_vector_segments_must_use_the_pattern_radix() {
+        let src = "fn f(v: Int) {\n    let y = v match {\n        0b10 12 => 1,\n        _ => 0,\n    };\n    y\n}\n";
+        parse_error(src, |diag| {
+            assert_diagnostic(diag, 2002, "12");
+            // SAFETY: nothing is emitted while the slice is borrowed.
+            let diagnostics = unsafe { diag.diagnostics() };
+            assert_eq!(
+                diagnostics[0].message,
+                "Invalid digit '2' in binary bit vector pattern"
+            );
+        });
+
+        let src = "fn f(v: Int) {\n    let y = v match {\n        0o17 0x1 => 1,\n        _ => 0,\n    };\n    y\n}\n";
+        parse_error(src, |diag| assert_diagnostic(diag, 2002, "0x1"));
+    }
+
+    #[test]
+    fn a_label_colon_must_be_followed_by_a_name() {
+        parse_error(
+            "fn f() {\n    while true {\n        break :;\n    }\n}\n",
+            |diag| {
+                assert_diagnostic(diag, 2002, ";");
+            },
+        );
+    }
+
+    #[test]
+    fn a_missing_token_is_reported_at_the_token_found_instead() {
+        parse_error("const N: Int;\n", |diag| {
+            assert_diagnostic(diag, 2001, ";");
+        });
+        parse_error("const N: Int = ;\n", |diag| {
+            assert_diagnostic(diag, 2002, ";");
+        });
+    }
+
+    #[test]
+    fn a_missing_closing_parenthesis_is_suggested_after_the_last_token() {
+        parse_error("const N: Int = (1 + 2;\n", |diag| {
+            assert_diagnostic(diag, 2001, ";");
+            // SAFETY: nothing is emitted while the slice is borrowed.
+            let diagnostics = unsafe { diag.diagnostics() };
+            let [suggestion] = diagnostics[0].suggestions.as_slice() else {
+                panic!("expected one suggestion: {:?}", diagnostics[0]);
+            };
+            assert_eq!(suggestion.replacement, ")");
+            assert_eq!(suggestion.span.lo(), suggestion.span.hi());
+            let at = diag.source_map().lookup_char_pos(suggestion.span.lo());
+            assert_eq!((at.line, at.col.0), (1, "const N: Int = (1 + 2".len()));
+        });
+    }
+
+    /// Parse `src`, whose first item is a `const`, and return the
+    /// s-expressions of the elements of the list its value holds, found
+    /// with `elements`, along with the errors of the parse.
+    fn const_list_elements(
+        src: &str,
+        elements: impl FnOnce(&Ast, ast::NodeIndex) -> ast::NodeIndex,
+    ) -> (Vec<String>, Vec<crate::error::ParseError>, SourceMap) {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let file = source_map.new_source_file(FileName::Custom("main.fl".into()), src.into());
+        let (tokens, symbols, _) = lex::lex(src, file.start_pos);
+        let (ast, errors) = crate::parse_file(&source_map, tokens, symbols, file.start_pos);
+        let value = ast.get_children(items(&ast)[0])[2];
+        let list = elements(&ast, value);
+        let dumps = ast
+            .get_multi_child_slice(list)
+            .unwrap()
+            .iter()
+            .map(|&element| ast.dump_to_s_expression(element, &source_map).unwrap())
+            .collect();
+        (dumps, errors, source_map)
+    }
+
+    #[test]
+    fn a_missing_comma_between_call_arguments_is_reported_and_both_are_parsed() {
+        let src = "const N: Int = add(1 2);\n";
+        let (args, errors, source_map) = const_list_elements(src, |ast, value| {
+            assert_eq!(ast.get_node_kind(value), Some(NodeKind::Application));
+            ast.get_children(value)[1]
+        });
+        assert_eq!(args, ["(Int 1)", "(Int 2)"]);
+
+        let [error] = errors.as_slice() else {
+            panic!("expected one error: {:?}", errors);
+        };
+        assert_eq!(error.message(), "Missing `,` between list elements");
+        assert_eq!(source_map.span_to_snippet(error.to_span()).unwrap(), "2");
+        let crate::error::ParseError::UnexpectedToken {
+            suggestion: Some(suggestion),
+            ..
+        } = error
+        else {
+            panic!("expected a suggestion: {:?}", error);
+        };
+        assert_eq!(suggestion.replacement, ",");
+        let at = source_map.lookup_char_pos(suggestion.span.lo());
+        assert_eq!(at.col.0, "const N: Int = add(1".len());
+    }
+
+    #[test]
+    fn a_missing_comma_between_list_elements_is_reported_and_both_are_parsed() {
+        let src = "const L = [1 2, 3];\n";
+        let (elements, errors, source_map) = const_list_elements(src, |ast, value| {
+            assert_eq!(ast.get_node_kind(value), Some(NodeKind::ListOf));
+            ast.get_children(value)[0]
+        });
+        assert_eq!(elements, ["(Int 1)", "(Int 2)", "(Int 3)"]);
+        let snippets: Vec<String> = errors
+            .iter()
+            .map(|error| source_map.span_to_snippet(error.to_span()).unwrap())
+            .collect();
+        assert_eq!(snippets, ["2"]);
+    }
+
+    #[test]
+    fn a_missing_expression_at_a_line_break_is_reported_after_what_precedes_it() {
+        parse_error("const N: Int =\n", |diag| {
+            assert_diagnostic(diag, 2002, "=");
+            // SAFETY: nothing is emitted while the slice is borrowed.
+            let diagnostics = unsafe { diag.diagnostics() };
+            assert_eq!(
+                diagnostics[0].message,
+                "Expected an expression after `=` in const definition"
+            );
+        });
+    }
+
+    #[test]
+    fn a_struct_missing_its_closing_brace_is_closed_at_the_end_of_the_file() {
+        let src = "struct Point {\n    x: Int,\n    y: Int,\n\nfn main() {\n    1\n}\n";
+        parse_error(src, |diag| {
+            assert_eq!(diag.error_count(), 1);
+            assert_diagnostic(diag, 2006, "");
+            // SAFETY: nothing is emitted while the slice is borrowed.
+            let diagnostics = unsafe { diag.diagnostics() };
+            assert_eq!(diagnostics[0].message, "Expected `}`, found end of file");
+            let unclosed: Vec<(String, &str)> = diagnostics[0].labels[1..]
+                .iter()
+                .map(|label| {
+                    let text = diag.source_map().span_to_snippet(label.span).unwrap();
+                    let start = diag.source_map().lookup_char_pos(label.span.lo());
+                    (format!("{}:{}", start.line, text), label.message.as_str())
+                })
+                .collect();
+            assert_eq!(unclosed, [("1:{".to_string(), "unclosed `{`")]);
+        });
+
+        let src = "struct Point {\n    fn norm() {\n        1\n";
+        parse_error(src, |diag| {
+            assert_eq!(diag.error_count(), 1);
+            // SAFETY: nothing is emitted while the slice is borrowed.
+            let diagnostics = unsafe { diag.diagnostics() };
+            assert_eq!(diagnostics[0].labels.len(), 3);
+        });
+    }
+
+    #[test]
+    fn every_error_is_registered_under_its_name() {
+        use lex::TokenKind;
+
+        let errors = [
+            ParseError::unexpected_token(TokenKind::Semi, TokenKind::Eof, DUMMY_SP),
+            ParseError::invalid_syntax(String::new(), TokenKind::Eof, DUMMY_SP),
+            ParseError::ExpectedBlock {
+                message: String::new(),
+                help: None,
+                note: None,
+                found: TokenKind::Eof,
+                span: DUMMY_SP,
+            },
+            ParseError::UnclosedBrackets {
+                message: String::new(),
+                unclosed: Vec::new(),
+                span: DUMMY_SP,
+            },
+            ParseError::MeetPostExtendedCallStart,
+            ParseError::MeetPostId,
+        ];
+        for error in &errors {
+            assert_registered(error);
+        }
+    }
+}
@@ -12,6 +12,16 @@ impl ExprOpInfo {
     pub fn new(prec: i32, node_kind: NodeKind) -> Self {
         Self { prec, node_kind }
     }
+
+    /// The lowest precedence an operator of the right operand may have.
+    /// `->` associates to the right, so `A -> B -> C` is `A -> (B -> C)`;
+    /// every other binary operator associates to the left.
+    pub fn right_operand_prec(&self) -> i32 {
+        match self.node_kind {
+            NodeKind::Arrow => self.prec,
+            _ => self.prec + 1,
+        }
+    }
 }
 
 pub fn get_expr_op_info(token_kind: TokenKind) -> ExprOpInfo {
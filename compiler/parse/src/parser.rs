@@ -2,7 +2,7 @@ use rustc_span::BytePos;
 
 use super::error::*;
 use ast::*;
-use diagnostic::{DiagnosticContext, FlurryError};
+use diagnostic::{Applicability, DiagnosticContext, FlurryError, Suggestion};
 use lex::{Symbol, Token, TokenKind};
 
 // hand-write peg parser
@@ -16,6 +16,16 @@ pub struct Parser<'a> {
     pub(crate) cursor: usize,
     pub(crate) cursor_stack: Vec<usize>,
     pub start_pos: BytePos,
+    /// Doc comments, keyed by the index of the token they document. Doc
+    /// comment tokens are taken out of `tokens` when the parser is created.
+    doc_comments: std::collections::HashMap<usize, rustc_span::Span>,
+    /// Whether statements are being parsed directly inside a file, module or
+    /// type body, where `const id = expr` defines an item rather than a
+    /// local.
+    pub(crate) item_scope: bool,
+    /// The opening brackets of the bracketed lists being parsed, outermost
+    /// first.
+    pub(crate) open_brackets: Vec<(TokenKind, rustc_span::Span)>,
 
     errors: Vec<ParseError>,
 }
@@ -27,14 +37,20 @@ impl<'a> Parser<'a> {
         symbols: std::collections::HashMap<usize, Symbol>,
         start_pos: BytePos,
     ) -> Parser<'a> {
+        let (tokens, symbols, doc_comments) = split_doc_comments(tokens, symbols, start_pos);
+        // 节点数大致与 token 数相当, 子节点槽位约为其两倍
+        let ast = Ast::with_capacity(tokens.len(), tokens.len() * 2);
         let mut result = Parser {
             source_map,
             tokens,
             symbols,
+            doc_comments,
             cursor: 0,
             cursor_stack: Vec::new(),
+            item_scope: false,
+            open_brackets: Vec::new(),
             errors: Vec::new(),
-            ast: Ast::new(),
+            ast,
             start_pos,
         };
         result.enter();
@@ -42,22 +58,31 @@ impl<'a> Parser<'a> {
     }
 
     pub fn parse(&mut self, diag_ctx: &DiagnosticContext) {
+        let errors = self.parse_recovering();
+        let base_pos = self
+            .source_map
+            .lookup_source_file(self.current_span().lo())
+            .start_pos;
+        for error in errors {
+            error.emit(diag_ctx, base_pos);
+        }
+    }
+
+    /// Parse the file, recovering from errors in its items and blocks, and
+    /// return every error found, in the order they were found.
+    pub fn parse_recovering(&mut self) -> Vec<ParseError> {
         match self.try_file_scope() {
             Ok(node_index) => {
                 self.ast.root = node_index;
             }
-            Err(error) => {
-                // Handle parse error
-                let base_pos = self
-                    .source_map
-                    .lookup_source_file(self.current_span().lo())
-                    .start_pos;
-                error.emit(diag_ctx, base_pos);
-            }
+            Err(error) => self.parse_error(error),
         }
+        std::mem::take(&mut self.errors)
     }
 
-    fn parse_error(&mut self, error: ParseError) {
+    /// Record an error the parser recovered from; it is returned by
+    /// [`Self::parse_recovering`].
+    pub(crate) fn parse_error(&mut self, error: ParseError) {
         self.errors.push(error);
     }
 
@@ -99,7 +124,31 @@ impl<'a> Parser<'a> {
         result
     }
 
-    pub fn finalize(self) -> Ast {
+    /// Run `f` with `item_scope` set as given, restoring it afterwards.
+    pub(crate) fn with_item_scope<T>(
+        &mut self,
+        item_scope: bool,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let outer = std::mem::replace(&mut self.item_scope, item_scope);
+        let result = f(self);
+        self.item_scope = outer;
+        result
+    }
+
+    /// Attach the doc comment written before the token at `first_token`,
+    /// if any, to `node`.
+    pub(crate) fn attach_doc_comment(&mut self, first_token: usize, node: NodeIndex) {
+        if node == 0 {
+            return;
+        }
+        if let Some(&span) = self.doc_comments.get(&first_token) {
+            self.ast.attach_doc_comment(node, span);
+        }
+    }
+
+    pub fn finalize(mut self) -> Ast {
+        self.ast.shrink_to_fit();
         self.ast
     }
 
@@ -140,7 +189,7 @@ impl<'a> Parser<'a> {
     /// Get the next token and advance cursor
     pub fn next_token(&mut self) -> Token {
         if self.cursor + 1 >= self.tokens.len() {
-            Token::new(TokenKind::Eof, 0, 0)
+            self.eof_token()
         } else {
             let token = self.tokens[self.cursor + 1];
             self.cursor += 1;
@@ -151,16 +200,41 @@ impl<'a> Parser<'a> {
     /// Peek at the next token without consuming it
     pub fn peek_next_token(&self) -> Token {
         if self.cursor + 1 >= self.tokens.len() {
-            Token::new(TokenKind::Eof, 0, 0)
+            self.eof_token()
         } else {
             self.tokens[self.cursor + 1]
         }
     }
 
+    /// Peek at the token `n` places after the next one (`peek_nth(0)` is
+    /// the next token) without consuming anything. Past the end of the
+    /// tokens this is the trailing `Eof` token.
+    pub fn peek_nth(&self, n: usize) -> Token {
+        match self.tokens.get((self.cursor + 1).saturating_add(n)) {
+            Some(&token) => token,
+            None => self.eof_token(),
+        }
+    }
+
+    /// The kind of [`Self::peek_nth`].
+    pub fn peek_nth_kind(&self, n: usize) -> TokenKind {
+        self.peek_nth(n).kind
+    }
+
+    /// The `Eof` token the lexer ends the tokens with, so that whatever is
+    /// reported past the end points at the end of the file.
+    fn eof_token(&self) -> Token {
+        match self.tokens.last() {
+            Some(&token) if token.kind == TokenKind::Eof => token,
+            Some(&token) => Token::new(TokenKind::Eof, token.to, token.to),
+            None => Token::new(TokenKind::Eof, 0, 0),
+        }
+    }
+
     /// Get the current token without advancing
     pub fn current_token(&self) -> Token {
         if self.cursor >= self.tokens.len() {
-            Token::new(TokenKind::Eof, 0, 0)
+            self.eof_token()
         } else {
             self.tokens[self.cursor]
         }
@@ -169,7 +243,7 @@ impl<'a> Parser<'a> {
     /// Get a token at a specific index
     pub fn get_token(&self, index: usize) -> Token {
         if index >= self.tokens.len() {
-            Token::new(TokenKind::Eof, 0, 0)
+            self.eof_token()
         } else {
             self.tokens[index]
         }
@@ -178,7 +252,7 @@ impl<'a> Parser<'a> {
     /// Get the previous token (relative to current cursor position)
     pub fn previous_token(&self) -> Token {
         if self.cursor == 0 {
-            Token::new(TokenKind::Eof, 0, 0)
+            self.eof_token()
         } else {
             self.tokens[self.cursor - 1]
         }
@@ -240,6 +314,120 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// The error for a declaration of `what` (e.g. "`struct`") that is not
+    /// followed by its block. `takes_expr_body` is set for functions, whose
+    /// body may also be written `= expr`.
+    pub fn expected_block(&self, what: &str, takes_expr_body: bool) -> ParseError {
+        let found = self.peek_next_token().kind;
+        let message = if takes_expr_body {
+            format!("expected a block or `=` after {} declaration", what)
+        } else {
+            format!("expected a block after {} declaration", what)
+        };
+        let help = match found {
+            TokenKind::FatArrow if takes_expr_body => {
+                Some("write `= expr` for a body that is a single expression".to_string())
+            }
+            TokenKind::Eq if !takes_expr_body => Some(format!(
+                "only functions take an `= expr` body; the body of a {} declaration is a block `{{ ... }}`",
+                what
+            )),
+            _ => None,
+        };
+        let note = self.next_token_on_new_line().then(|| {
+            "a block `{ ... }` is required here; the declaration cannot end at the line break"
+                .to_string()
+        });
+        ParseError::ExpectedBlock {
+            message,
+            help,
+            note,
+            found,
+            span: self.next_token_span(),
+        }
+    }
+
+    /// The error for a missing `expected` token. The caret points at the
+    /// token found in its place. A missing closing bracket is suggested
+    /// right after the current token.
+    pub fn unexpected(&self, expected: TokenKind) -> ParseError {
+        let error = ParseError::unexpected_token(
+            expected,
+            self.peek_next_token().kind,
+            self.next_token_span(),
+        );
+        if !matches!(
+            expected,
+            TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace
+        ) {
+            return error;
+        }
+        let end = self.token_span(&self.current_token()).hi();
+        error.with_suggestion(Suggestion {
+            span: rustc_span::Span::new(end, end),
+            replacement: expected.lexme().to_string(),
+            applicability: Applicability::MaybeIncorrect,
+        })
+    }
+
+    /// The error for a `,` missing between the list element just parsed
+    /// and the next one. It points at the next element and suggests the
+    /// comma right after the current token.
+    pub fn missing_comma(&self) -> ParseError {
+        let end = self.token_span(&self.current_token()).hi();
+        ParseError::UnexpectedToken {
+            message: "Missing `,` between list elements".to_string(),
+            expected: TokenKind::Comma,
+            found: self.peek_next_token().kind,
+            span: self.next_token_span(),
+            suggestion: Some(Suggestion {
+                span: rustc_span::Span::new(end, end),
+                replacement: ",".to_string(),
+                applicability: Applicability::MaybeIncorrect,
+            }),
+        }
+    }
+
+    /// The error for something missing after the current token, as in
+    /// "Expected a type after `:`". The caret points at the token found
+    /// instead when it is on the same line, and otherwise at the current
+    /// token, so it never lands on a later line the error is not about.
+    pub fn expected_after(&self, message: String) -> ParseError {
+        let found = self.peek_next_token().kind;
+        let span = if found == TokenKind::Eof || self.next_token_on_new_line() {
+            self.token_span(&self.current_token())
+        } else {
+            self.next_token_span()
+        };
+        ParseError::invalid_syntax(message, found, span)
+    }
+
+    /// The span of `token`.
+    pub fn token_span(&self, token: &Token) -> rustc_span::Span {
+        rustc_span::Span::new(
+            self.start_pos + BytePos(token.from as u32),
+            self.start_pos + BytePos(token.to as u32),
+        )
+    }
+
+    /// Whether a line break separates the current token from the next one.
+    fn next_token_on_new_line(&self) -> bool {
+        let (current, next) = (self.current_token(), self.peek_next_token());
+        if next.from < current.to {
+            return false;
+        }
+        let lo = self.start_pos + BytePos(current.to as u32);
+        let sf = self.source_map.lookup_source_file(lo);
+        let Some(content) = &sf.src else {
+            return false;
+        };
+        let byte_start = (lo.0 - sf.start_pos.0) as usize;
+        let byte_end = byte_start + (next.from - current.to);
+        content
+            .get(byte_start..byte_end)
+            .is_some_and(|gap| gap.contains('\n'))
+    }
+
     /// Get the source text of a token.
     pub fn token_text(&self, token: &Token) -> String {
         let lo = self.start_pos + BytePos(token.from as u32);
@@ -256,3 +444,44 @@ impl<'a> Parser<'a> {
 }
 
 pub type ParseResult = Result<NodeIndex, ParseError>;
+
+/// Take the doc comment tokens out of `tokens`, re-keying `symbols` to the
+/// remaining tokens. Consecutive doc comments are merged into one span,
+/// keyed by the index of the token that follows them.
+fn split_doc_comments(
+    tokens: Vec<Token>,
+    symbols: std::collections::HashMap<usize, Symbol>,
+    start_pos: BytePos,
+) -> (
+    Vec<Token>,
+    std::collections::HashMap<usize, Symbol>,
+    std::collections::HashMap<usize, rustc_span::Span>,
+) {
+    let mut kept = Vec::with_capacity(tokens.len());
+    let mut kept_symbols = std::collections::HashMap::with_capacity(symbols.len());
+    let mut doc_comments = std::collections::HashMap::new();
+    let mut pending: Option<(usize, usize)> = None;
+
+    for (index, token) in tokens.into_iter().enumerate() {
+        if token.kind == TokenKind::DocComment {
+            let from = pending.map_or(token.from, |(from, _)| from);
+            pending = Some((from, token.to));
+            continue;
+        }
+        if let Some((from, to)) = pending.take() {
+            doc_comments.insert(
+                kept.len(),
+                rustc_span::Span::new(
+                    start_pos + BytePos(from as u32),
+                    start_pos + BytePos(to as u32),
+                ),
+            );
+        }
+        if let Some(&symbol) = symbols.get(&index) {
+            kept_symbols.insert(kept.len(), symbol);
+        }
+        kept.push(token);
+    }
+
+    (kept, kept_symbols, doc_comments)
+}
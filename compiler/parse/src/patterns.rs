@@ -1,6 +1,7 @@
 use super::basic::Rule;
 use super::error::*;
-use super::operators::get_pattern_op_info;
+use super::expressions::ExprOption;
+use super::operators::{get_expr_op_info, get_pattern_op_info};
 use super::parser::*;
 use ast::*;
 use lex::TokenKind;
@@ -117,17 +118,10 @@ impl Parser<'_> {
     pub fn try_prefix_pattern(&mut self, option: PatternOption) -> ParseResult {
         self.scoped(|p| {
             // Check for bit vector patterns (0b..., 0o..., 0x...)
-            // TODO: you need to check two tokens instead of one
-            if p.peek_next_token().kind == TokenKind::Int {
-                let token = p.peek_next_token();
-                let text = p.token_text(&token);
-                if text.starts_with("0b") {
-                    return p.try_bit_vec_pattern(NodeKind::BitVecBinPattern);
-                } else if text.starts_with("0o") {
-                    return p.try_bit_vec_pattern(NodeKind::BitVecOctPattern);
-                } else if text.starts_with("0x") || text.starts_with("0X") {
-                    return p.try_bit_vec_pattern(NodeKind::BitVecHexPattern);
-                }
+            if p.peek_nth_kind(0) == TokenKind::Int
+                && let Some(radix) = radix_prefix(&p.token_text(&p.peek_nth(0)))
+            {
+                return p.try_bit_vec_pattern(radix);
             }
 
             let token = p.peek_next_token();
@@ -155,11 +149,9 @@ impl Parser<'_> {
                     p.eat_tokens(1);
                     let pattern = match p.try_pattern_with_option(option)? {
                         0 => {
-                            return Err(ParseError::invalid_syntax(
-                                "Expected a pattern after `ref`".to_string(),
-                                p.peek_next_token().kind,
-                                p.next_token_span(),
-                            ));
+                            return Err(
+                                p.expected_after("Expected a pattern after `ref`".to_string())
+                            );
                         }
                         node => node,
                     };
@@ -172,11 +164,9 @@ impl Parser<'_> {
                     p.eat_tokens(1);
                     let pattern = match p.try_pattern_with_option(option)? {
                         0 => {
-                            return Err(ParseError::invalid_syntax(
-                                "Expected a pattern after `async`".to_string(),
-                                p.peek_next_token().kind,
-                                p.next_token_span(),
-                            ));
+                            return Err(
+                                p.expected_after("Expected a pattern after `async`".to_string())
+                            );
                         }
                         node => node,
                     };
@@ -189,11 +179,9 @@ impl Parser<'_> {
                     p.eat_tokens(1);
                     let pattern = match p.try_pattern_with_option(option)? {
                         0 => {
-                            return Err(ParseError::invalid_syntax(
-                                "Expected a pattern after `not`".to_string(),
-                                p.peek_next_token().kind,
-                                p.next_token_span(),
-                            ));
+                            return Err(
+                                p.expected_after("Expected a pattern after `not`".to_string())
+                            );
                         }
                         node => node,
                     };
@@ -206,11 +194,9 @@ impl Parser<'_> {
                     p.eat_tokens(1);
                     let pattern = match p.try_pattern_with_option(option)? {
                         0 => {
-                            return Err(ParseError::invalid_syntax(
-                                "Expected a pattern after `error`".to_string(),
-                                p.peek_next_token().kind,
-                                p.next_token_span(),
-                            ));
+                            return Err(
+                                p.expected_after("Expected a pattern after `error`".to_string())
+                            );
                         }
                         node => node,
                     };
@@ -223,11 +209,9 @@ impl Parser<'_> {
                     p.eat_tokens(1);
                     let id = match p.try_id()? {
                         0 => {
-                            return Err(ParseError::invalid_syntax(
-                                "Expected an identifier after `'`".to_string(),
-                                p.peek_next_token().kind,
-                                p.next_token_span(),
-                            ));
+                            return Err(
+                                p.expected_after("Expected an identifier after `'`".to_string())
+                            );
                         }
                         node => node,
                     };
@@ -296,10 +280,8 @@ impl Parser<'_> {
                         if p.eat_token(TokenKind::Eq) {
                             let end = match p.try_pattern_with_option(option_)? {
                                 0 => {
-                                    return Err(ParseError::invalid_syntax(
+                                    return Err(p.expected_after(
                                         "Expected a pattern after `..=`".to_string(),
-                                        p.peek_next_token().kind,
-                                        p.next_token_span(),
                                     ));
                                 }
                                 node => node,
@@ -332,11 +314,9 @@ impl Parser<'_> {
                         p.eat_tokens(1);
                         let id = p.try_id()?;
                         if id == 0 {
-                            return Err(ParseError::invalid_syntax(
-                                "Expected an identifier after `.`".to_string(),
-                                TokenKind::Id,
-                                p.current_span(),
-                            ));
+                            return Err(
+                                p.expected_after("Expected an identifier after `.`".to_string())
+                            );
                         }
                         Ok(NodeBuilder::new(NodeKind::Projection, p.current_span())
                             .add_single_child(left)
@@ -357,6 +337,7 @@ impl Parser<'_> {
                                 p.try_property_pattern(option)
                             }),
                             Rule::comma("id", |p| p.try_id()),
+                            Rule::comma("struct rest pattern", |p| p.try_struct_rest_pattern()),
                         ],
                         (TokenKind::LBrace, TokenKind::RBrace),
                     )?;
@@ -374,11 +355,9 @@ impl Parser<'_> {
                     p.eat_tokens(1);
                     let id = match p.try_id()? {
                         0 => {
-                            return Err(ParseError::invalid_syntax(
-                                "Expected an identifier after `as`".to_string(),
-                                p.peek_next_token().kind,
-                                p.next_token_span(),
-                            ));
+                            return Err(
+                                p.expected_after("Expected an identifier after `as`".to_string())
+                            );
                         }
                         node => node,
                     };
@@ -393,11 +372,9 @@ impl Parser<'_> {
                     p.eat_tokens(1);
                     let guard = match p.try_expr_without_extended_call()? {
                         0 => {
-                            return Err(ParseError::invalid_syntax(
-                                "Expected an expression after `if`".to_string(),
-                                p.peek_next_token().kind,
-                                p.next_token_span(),
-                            ));
+                            return Err(
+                                p.expected_after("Expected an expression after `if`".to_string())
+                            );
                         }
                         node => node,
                     };
@@ -412,30 +389,22 @@ impl Parser<'_> {
                     p.eat_tokens(1);
                     let expr = match p.try_expr()? {
                         0 => {
-                            return Err(ParseError::invalid_syntax(
-                                "Expected an expression after `and`".to_string(),
-                                p.peek_next_token().kind,
-                                p.next_token_span(),
-                            ));
+                            return Err(
+                                p.expected_after("Expected an expression after `and`".to_string())
+                            );
                         }
                         node => node,
                     };
 
                     if !p.eat_token(TokenKind::Is) {
-                        return Err(ParseError::invalid_syntax(
-                            "Missing 'is' after 'and'".to_string(),
-                            p.peek_next_token().kind,
-                            p.next_token_span(),
-                        ));
+                        return Err(p.expected_after("Missing 'is' after 'and'".to_string()));
                     }
 
                     let pattern = match p.try_pattern_pratt(min_prec, option)? {
                         0 => {
-                            return Err(ParseError::invalid_syntax(
-                                "Expected a pattern after `is`".to_string(),
-                                p.peek_next_token().kind,
-                                p.next_token_span(),
-                            ));
+                            return Err(
+                                p.expected_after("Expected a pattern after `is`".to_string())
+                            );
                         }
                         node => node,
                     };
@@ -473,11 +442,21 @@ impl Parser<'_> {
     /// Parse a bit vector pattern.
     /// bit_vec_*_pattern -> 0b/0o/0x (integer | (id: expr) | <expr>)+
     /// Returns a MultiChildren node whose children are the segments.
-    fn try_bit_vec_pattern(&mut self, kind: NodeKind) -> ParseResult {
+    ///
+    /// Every integer segment is written in the radix of the first one, with
+    /// or without repeating its prefix: `0b1110 10` and `0b1110 0b10` are the
+    /// same pattern, while `0b1110 12` is rejected.
+    fn try_bit_vec_pattern(&mut self, radix: u32) -> ParseResult {
+        let (kind, radix_name) = match radix {
+            2 => (NodeKind::BitVecBinPattern, "binary"),
+            8 => (NodeKind::BitVecOctPattern, "octal"),
+            _ => (NodeKind::BitVecHexPattern, "hexadecimal"),
+        };
         self.scoped(|p| {
             let mut segments = Vec::new();
 
             // First segment is the initial integer literal (e.g., 0b1110)
+            p.check_bit_vec_segment(radix, radix_name)?;
             let first = NodeBuilder::new(NodeKind::Int, p.next_token_span()).build(&mut p.ast);
             p.eat_tokens(1);
             segments.push(first);
@@ -488,6 +467,7 @@ impl Parser<'_> {
                 match next.kind {
                     // Another integer literal segment (e.g., 10 in 0b1110 10)
                     TokenKind::Int => {
+                        p.check_bit_vec_segment(radix, radix_name)?;
                         let seg =
                             NodeBuilder::new(NodeKind::Int, p.next_token_span()).build(&mut p.ast);
                         p.eat_tokens(1);
@@ -541,7 +521,7 @@ impl Parser<'_> {
                     TokenKind::SeparatedLt => {
                         let seg = p.scoped(|p2| {
                             p2.eat_tokens(1); // eat ' < '
-                            let expr = p2.try_expr()?;
+                            let expr = p2.try_embedded_expr()?;
                             if expr == 0 {
                                 return Err(ParseError::invalid_syntax(
                                     "Expected expression in bit vector computed field".to_string(),
@@ -573,28 +553,62 @@ impl Parser<'_> {
         })
     }
 
+    /// Check that the next token, an integer segment of a bit vector
+    /// pattern, has at least one digit and only digits of `radix`.
+    fn check_bit_vec_segment(&self, radix: u32, radix_name: &str) -> Result<(), ParseError> {
+        let token = self.peek_nth(0);
+        let text = self.token_text(&token);
+        let invalid = |message: String| {
+            Err(ParseError::invalid_syntax(
+                message,
+                token.kind,
+                self.next_token_span(),
+            ))
+        };
+        let digits = match radix_prefix(&text) {
+            Some(prefix) if prefix == radix => &text[2..],
+            Some(_) => {
+                return invalid(format!(
+                    "Expected a {} segment in the bit vector pattern, found `{}`",
+                    radix_name, text
+                ));
+            }
+            None => text.as_str(),
+        };
+        if let Some(c) = digits.chars().find(|&c| c != '_' && !c.is_digit(radix)) {
+            return invalid(format!(
+                "Invalid digit '{}' in {} bit vector pattern",
+                c, radix_name
+            ));
+        }
+        if digits.chars().all(|c| c == '_') {
+            return invalid(format!("Empty {} bit vector pattern segment", radix_name));
+        }
+        Ok(())
+    }
+
+    /// Parse the expression inside ` < expr > `.  The closing ` > ` would
+    /// otherwise be taken as a greater-than operator, so comparisons inside
+    /// the brackets must be parenthesised.
+    fn try_embedded_expr(&mut self) -> ParseResult {
+        let comparison_prec = get_expr_op_info(TokenKind::SeparatedGt).prec;
+        self.scoped(|p| p.try_expr_pratt(comparison_prec + 1, ExprOption::default()))
+    }
+
     /// 尝试解析从表达式构建的模式 < expr >
     fn try_pattern_from_expr(&mut self) -> ParseResult {
         self.scoped_with_expected_prefix(&[TokenKind::SeparatedLt], |p| {
             p.eat_tokens(1);
 
-            let expr = match p.try_expr()? {
+            let expr = match p.try_embedded_expr()? {
                 0 => {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected an expression after ` < `".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.expected_after("Expected an expression after ` < `".to_string()));
                 }
                 node => node,
             };
 
             if !p.eat_token(TokenKind::SeparatedGt) {
-                return Err(ParseError::invalid_syntax(
-                    "Expected ` > ` after expression".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected ` > ` after expression".to_string()));
             }
 
             Ok(NodeBuilder::new(NodeKind::ExprAsPattern, p.current_span())
@@ -610,11 +624,7 @@ impl Parser<'_> {
                 p.eat_tokens(3); // 消耗 "..="
                 let end = match p.try_pattern_with_option(option)? {
                     0 => {
-                        return Err(ParseError::invalid_syntax(
-                            "Expected a pattern after `..=`".to_string(),
-                            p.peek_next_token().kind,
-                            p.next_token_span(),
-                        ));
+                        return Err(p.expected_after("Expected a pattern after `..=`".to_string()));
                     }
                     node => node,
                 };
@@ -627,11 +637,7 @@ impl Parser<'_> {
                 p.eat_tokens(2); // 消耗 ".."
                 let end = match p.try_pattern_with_option(option)? {
                     0 => {
-                        return Err(ParseError::invalid_syntax(
-                            "Expected a pattern after `..`".to_string(),
-                            p.peek_next_token().kind,
-                            p.next_token_span(),
-                        ));
+                        return Err(p.expected_after("Expected a pattern after `..`".to_string()));
                     }
                     node => node,
                 };
@@ -667,11 +673,7 @@ impl Parser<'_> {
 
             let pattern = match p.try_pattern_with_option(option)? {
                 0 => {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a pattern after `:`".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.expected_after("Expected a pattern after `:`".to_string()));
                 }
                 node => node,
             };
@@ -692,6 +694,7 @@ impl Parser<'_> {
                 &[
                     Rule::comma("property pattern", move |p| p.try_property_pattern(option)),
                     Rule::comma("id", |p| p.try_id()),
+                    Rule::comma("struct rest pattern", |p| p.try_struct_rest_pattern()),
                 ],
                 (TokenKind::LBrace, TokenKind::RBrace),
             )?;
@@ -702,6 +705,17 @@ impl Parser<'_> {
         })
     }
 
+    /// Try parse struct rest pattern: `...`
+    fn try_struct_rest_pattern(&mut self) -> ParseResult {
+        self.scoped(|p| {
+            if !p.peek(&[TokenKind::Dot, TokenKind::Dot, TokenKind::Dot]) {
+                return Ok(0);
+            }
+            p.eat_tokens(3); // consume '...'
+            Ok(NodeBuilder::new(NodeKind::StructRestPattern, p.current_span()).build(&mut p.ast))
+        })
+    }
+
     /// Try parse list rest pattern: `...id`
     fn try_list_rest_pattern(&mut self) -> ParseResult {
         self.scoped(|p| {
@@ -711,10 +725,8 @@ impl Parser<'_> {
             p.eat_tokens(3); // consume '...'
             let id = p.try_id()?;
             if id == 0 {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected an identifier after `...` in list rest pattern".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
             Ok(
@@ -756,3 +768,13 @@ impl Parser<'_> {
         })
     }
 }
+
+/// The radix of an integer literal written with a `0b`, `0o` or `0x` prefix.
+fn radix_prefix(text: &str) -> Option<u32> {
+    match text.get(..2)? {
+        "0b" | "0B" => Some(2),
+        "0o" | "0O" => Some(8),
+        "0x" | "0X" => Some(16),
+        _ => None,
+    }
+}
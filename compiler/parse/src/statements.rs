@@ -5,12 +5,44 @@ use ast::*;
 use lex::TokenKind;
 
 impl Parser<'_> {
+    /// Parse a statement or definition and attach the doc comment written
+    /// before it. A doc comment before `pub` / `private` documents the
+    /// wrapped definition.
     pub fn try_statement_or_definition(&mut self) -> ParseResult {
+        let first_token = self.cursor + 1;
+        let node = self.try_undocumented_statement_or_definition()?;
+        let documented = match self.ast.get_node_kind(node) {
+            Some(NodeKind::Pub | NodeKind::Private) => self.ast.get_children(node)[0],
+            _ => node,
+        };
+        self.attach_doc_comment(first_token, documented);
+        Ok(node)
+    }
+
+    /// Parse a statement or definition directly inside a file, module or
+    /// type body.
+    pub fn try_item(&mut self) -> ParseResult {
+        self.with_item_scope(true, |p| p.try_statement_or_definition())
+    }
+
+    /// Parse a statement or definition inside a block or function body.
+    pub fn try_body_statement(&mut self) -> ParseResult {
+        self.with_item_scope(false, |p| p.try_statement_or_definition())
+    }
+
+    fn try_undocumented_statement_or_definition(&mut self) -> ParseResult {
         self.scoped(|p| {
             let token = p.peek_next_token();
             match token.kind {
                 // statements
                 TokenKind::Let => p.try_let_statement(),
+                TokenKind::Const
+                    if p.item_scope
+                        && (p.peek(&[TokenKind::Const, TokenKind::Id, TokenKind::Colon])
+                            || p.peek(&[TokenKind::Const, TokenKind::Id, TokenKind::Eq])) =>
+                {
+                    p.try_const_def()
+                }
                 TokenKind::Const => p.try_const_statement(),
                 TokenKind::If => p.try_if_statement(),
                 TokenKind::Use => p.try_use_statement(),
@@ -22,6 +54,14 @@ impl Parser<'_> {
                 TokenKind::Break => p.try_break_statement(),
                 TokenKind::Continue => p.try_continue_statement(),
                 TokenKind::LBrace => p.try_block(),
+                // `inline fn ..` and `inline pure fn ..` modify a definition;
+                // any other `inline` starts an inline statement.
+                TokenKind::Inline
+                    if p.peek(&[TokenKind::Inline, TokenKind::Fn])
+                        || p.peek(&[TokenKind::Inline, TokenKind::Pure]) =>
+                {
+                    p.try_keyword_modified_definition()
+                }
                 TokenKind::Inline => p.try_inline_statement(),
 
                 TokenKind::Pub => {
@@ -127,11 +167,9 @@ impl Parser<'_> {
             p.eat_tokens(1); // consume 'inline'
             let inner = p.try_statement_or_definition()?;
             if inner == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a statement expression after `inline`".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a statement expression after `inline`".to_string())
+                );
             }
             Ok(
                 NodeBuilder::new(NodeKind::InlineStatement, p.current_span())
@@ -146,22 +184,16 @@ impl Parser<'_> {
             p.eat_tokens(1);
             let pattern = p.try_pattern()?;
             if pattern == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected pattern after 'let'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected pattern after 'let'".to_string()));
             }
 
             let mut expr: u32 = 0;
             if p.eat_token(TokenKind::Colon) {
                 expr = p.try_expr()?;
                 if expr == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a type expression after ':'".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(
+                        p.expected_after("Expected a type expression after ':'".to_string())
+                    );
                 }
             }
 
@@ -169,10 +201,8 @@ impl Parser<'_> {
             if p.eat_token(TokenKind::Eq) {
                 init = p.try_expr()?;
                 if init == 0 {
-                    return Err(ParseError::invalid_syntax(
+                    return Err(p.expected_after(
                         "Expected an initializer expression after '='".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
                     ));
                 }
             }
@@ -190,22 +220,16 @@ impl Parser<'_> {
             p.eat_tokens(1);
             let pattern = p.try_pattern()?;
             if pattern == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected pattern after 'const'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected pattern after 'const'".to_string()));
             }
 
             let mut expr: u32 = 0;
             if p.eat_token(TokenKind::Colon) {
                 expr = p.try_expr()?;
                 if expr == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a type expression after ':'".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(
+                        p.expected_after("Expected a type expression after ':'".to_string())
+                    );
                 }
             }
 
@@ -213,10 +237,8 @@ impl Parser<'_> {
             if p.eat_token(TokenKind::Eq) {
                 init = p.try_expr()?;
                 if init == 0 {
-                    return Err(ParseError::invalid_syntax(
+                    return Err(p.expected_after(
                         "Expected an initializer expression after '='".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
                     ));
                 }
             }
@@ -234,11 +256,7 @@ impl Parser<'_> {
             p.eat_tokens(1);
             let path = p.try_path()?;
             if path == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a path after 'use'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected a path after 'use'".to_string()));
             }
 
             Ok(NodeBuilder::new(NodeKind::UseStatement, p.current_span())
@@ -247,9 +265,35 @@ impl Parser<'_> {
         })
     }
 
+    /// A `use` path. Leading `.` (super) and `@` (package) anchors wrap the
+    /// whole path, so `.a.b` is `(SuperPath (ProjectionPath a b))`.
     pub fn try_path(&mut self) -> ParseResult {
         self.scoped(|p| {
-            let mut left = p.try_prefix_path()?;
+            let next = p.peek_next_token();
+            let (kind, anchor) = match next.kind {
+                TokenKind::Dot => (NodeKind::SuperPath, "."),
+                TokenKind::At => (NodeKind::PackagePath, "@"),
+                _ => return p.try_projection_path(),
+            };
+            p.eat_tokens(1);
+            let sub_path = p.try_path()?;
+            if sub_path == 0 {
+                return Err(p.expected_after(format!("Expected a sub-path after '{}'", anchor)));
+            }
+            Ok(NodeBuilder::new(kind, p.current_span())
+                .add_single_child(sub_path)
+                .build(&mut p.ast))
+        })
+    }
+
+    /// An unanchored path: an identifier followed by `.id`, `.*` and
+    /// `.{ paths }` projections, optionally renamed with `as id`.
+    pub fn try_projection_path(&mut self) -> ParseResult {
+        self.scoped(|p| {
+            if !p.peek(TokenKind::Id.as_ref()) {
+                return Ok(0);
+            }
+            let mut left = p.try_id()?;
 
             while p.eat_token(TokenKind::Dot) {
                 let next = p.peek_next_token();
@@ -271,7 +315,7 @@ impl Parser<'_> {
                         p.eat_tokens(1);
                         let mut items = vec![];
                         while !p.peek(TokenKind::RBrace.as_ref()) {
-                            let item = p.try_path()?;
+                            let item = p.try_projection_path()?;
                             if item == 0 {
                                 return Err(ParseError::invalid_syntax(
                                     "Expected pathes inside `{}`".to_string(),
@@ -282,21 +326,13 @@ impl Parser<'_> {
                             items.push(item);
                             if !p.eat_token(TokenKind::Comma) {
                                 if !p.peek(TokenKind::RBrace.as_ref()) {
-                                    return Err(ParseError::unexpected_token(
-                                        TokenKind::Comma,
-                                        p.peek_next_token().kind,
-                                        p.next_token_span(),
-                                    ));
+                                    return Err(p.unexpected(TokenKind::Comma));
                                 }
                                 break;
                             }
                         }
                         if !p.eat_token(TokenKind::RBrace) {
-                            return Err(ParseError::unexpected_token(
-                                TokenKind::RBrace,
-                                p.peek_next_token().kind,
-                                p.next_token_span(),
-                            ));
+                            return Err(p.unexpected(TokenKind::RBrace));
                         }
                         left = NodeBuilder::new(NodeKind::ProjectionMultiPath, p.current_span())
                             .add_single_child(left)
@@ -304,54 +340,25 @@ impl Parser<'_> {
                             .build(&mut p.ast);
                     }
                     _ => {
-                        return Err(ParseError::invalid_syntax(
-                            "Expected an identifier or `{` or `*` or `as` after '.'".to_string(),
-                            next.kind,
-                            p.next_token_span(),
+                        return Err(p.expected_after(
+                            "Expected an identifier or `{` or `*` after '.'".to_string(),
                         ));
                     }
                 }
             }
 
-            Ok(left)
-        })
-    }
-
-    pub fn try_prefix_path(&mut self) -> ParseResult {
-        self.scoped(|p| {
-            let next = p.peek_next_token();
-            match next.kind {
-                TokenKind::Id => p.try_id(),
-                TokenKind::Dot => {
-                    p.eat_tokens(1);
-                    let sub_path = p.try_prefix_path()?;
-                    if sub_path == 0 {
-                        return Err(ParseError::invalid_syntax(
-                            "Expected a sub-path after '.'".to_string(),
-                            p.peek_next_token().kind,
-                            p.next_token_span(),
-                        ));
-                    }
-                    Ok(NodeBuilder::new(NodeKind::SuperPath, p.current_span())
-                        .add_single_child(sub_path)
-                        .build(&mut p.ast))
-                }
-                TokenKind::At => {
-                    p.eat_tokens(1);
-                    let sub_path = p.try_prefix_path()?;
-                    if sub_path == 0 {
-                        return Err(ParseError::invalid_syntax(
-                            "Expected a sub-path after '@'".to_string(),
-                            p.peek_next_token().kind,
-                            p.next_token_span(),
-                        ));
-                    }
-                    Ok(NodeBuilder::new(NodeKind::PackagePath, p.current_span())
-                        .add_single_child(sub_path)
-                        .build(&mut p.ast))
+            if p.eat_token(TokenKind::As) {
+                if !p.peek(TokenKind::Id.as_ref()) {
+                    return Err(p.expected_after("Expected a name after 'as'".to_string()));
                 }
-                _ => Ok(0),
+                let alias = p.try_id()?;
+                left = NodeBuilder::new(NodeKind::PathAsBind, p.current_span())
+                    .add_single_child(left)
+                    .add_single_child(alias)
+                    .build(&mut p.ast);
             }
+
+            Ok(left)
         })
     }
 
@@ -360,11 +367,9 @@ impl Parser<'_> {
             p.eat_tokens(1);
             let expr = p.try_expr_without_extended_call()?;
             if expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a condition expression after 'if'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a condition expression after 'if'".to_string())
+                );
             }
 
             if p.eat_token(TokenKind::Is) {
@@ -372,20 +377,16 @@ impl Parser<'_> {
             }
 
             if !p.peek(TokenKind::LBrace.as_ref()) {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected '{' after 'if' to start a block of statements".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
             let body = p.try_block()?;
             if body == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a block of statements after 'if'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a block of statements after 'if'".to_string())
+                );
             }
 
             let else_ = if p.eat_token(TokenKind::Else) {
@@ -394,10 +395,8 @@ impl Parser<'_> {
                     p.try_if_statement()?
                 } else {
                     if !p.peek(TokenKind::LBrace.as_ref()) {
-                        return Err(ParseError::invalid_syntax(
+                        return Err(p.expected_after(
                             "Expected '{' after 'else' to start a block of statements".to_string(),
-                            p.peek_next_token().kind,
-                            p.next_token_span(),
                         ));
                     }
                     p.try_block()?
@@ -424,21 +423,15 @@ impl Parser<'_> {
             }
 
             if !p.eat_token(TokenKind::Do) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Do,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Do));
             }
 
             let body = p.try_block()?;
 
             if body == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a block of statements after 'do'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a block of statements after 'do'".to_string())
+                );
             }
 
             let else_ = if p.eat_token(TokenKind::Else) {
@@ -447,10 +440,8 @@ impl Parser<'_> {
                     p.try_if_statement()?
                 } else {
                     if !p.peek(TokenKind::LBrace.as_ref()) {
-                        return Err(ParseError::invalid_syntax(
+                        return Err(p.expected_after(
                             "Expected '{' after 'else' to start a block of statements".to_string(),
-                            p.peek_next_token().kind,
-                            p.next_token_span(),
                         ));
                     }
                     p.try_block()?
@@ -473,11 +464,7 @@ impl Parser<'_> {
     fn try_if_match(&mut self, expr: NodeIndex) -> ParseResult {
         self.scoped(|p| {
             if !p.eat_token(TokenKind::Do) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Do,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Do));
             }
 
             let arms = p.try_multi_with_bracket(
@@ -497,27 +484,13 @@ impl Parser<'_> {
         self.scoped_with_expected_prefix(TokenKind::While.as_ref(), |p| {
             p.eat_tokens(1);
 
-            let label = if p.eat_token(TokenKind::Colon) {
-                let l = p.try_id()?;
-                if l == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a label after ':'".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
-                }
-                l
-            } else {
-                0
-            };
+            let label = p.try_label()?;
 
             let expr = p.try_expr_without_extended_call()?;
             if expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a condition expression after 'while'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a condition expression after 'while'".to_string())
+                );
             }
 
             if p.eat_token(TokenKind::Is) {
@@ -525,20 +498,16 @@ impl Parser<'_> {
             }
 
             if !p.peek(TokenKind::LBrace.as_ref()) {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected '{' after 'while' to start a block of statements".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
             let body = p.try_block()?;
             if body == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a block of statements after 'while'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a block of statements after 'while'".to_string())
+                );
             }
 
             Ok(NodeBuilder::new(NodeKind::WhileStatement, p.current_span())
@@ -559,20 +528,14 @@ impl Parser<'_> {
             }
 
             if !p.eat_token(TokenKind::Do) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Do,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Do));
             }
 
             let body = p.try_block()?;
             if body == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a block of statements after 'do'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a block of statements after 'do'".to_string())
+                );
             }
 
             Ok(NodeBuilder::new(NodeKind::WhileIsMatch, p.current_span())
@@ -589,11 +552,7 @@ impl Parser<'_> {
     fn try_while_match(&mut self, label: NodeIndex, expr: NodeIndex) -> ParseResult {
         self.scoped(|p| {
             if !p.eat_token(TokenKind::Do) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Do,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Do));
             }
 
             let arms = p.try_multi_with_bracket(
@@ -614,19 +573,7 @@ impl Parser<'_> {
         self.scoped_with_expected_prefix(TokenKind::For.as_ref(), |p| {
             p.eat_tokens(1);
 
-            let label = if p.eat_token(TokenKind::Colon) {
-                let l = p.try_id()?;
-                if l == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected a label after ':'".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
-                }
-                l
-            } else {
-                0
-            };
+            let label = p.try_label()?;
 
             let pattern = p.try_pattern()?;
             if pattern == 0 {
@@ -638,20 +585,14 @@ impl Parser<'_> {
             }
 
             if !p.eat_token(TokenKind::In) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::In,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::In));
             }
 
             let expr = p.try_expr_without_extended_call()?;
             if expr == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected an iterable expression after 'in'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected an iterable expression after 'in'".to_string())
+                );
             }
 
             if !p.peek(TokenKind::LBrace.as_ref()) {
@@ -684,10 +625,8 @@ impl Parser<'_> {
         self.scoped_with_expected_prefix(TokenKind::When.as_ref(), |p| {
             p.eat_tokens(1);
             if !p.peek(TokenKind::LBrace.as_ref()) {
-                return Err(ParseError::invalid_syntax(
+                return Err(p.expected_after(
                     "Expected '{' after 'when' to start a block of condition arms".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
                 ));
             }
 
@@ -710,20 +649,14 @@ impl Parser<'_> {
             }
 
             if !p.eat_token(TokenKind::FatArrow) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::FatArrow,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::FatArrow));
             }
 
             let body = p.try_block_or_statement()?;
             if body == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a statement, expr or block after '=>'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a statement, expr or block after '=>'".to_string())
+                );
             }
 
             Ok(NodeBuilder::new(NodeKind::ConditionArm, p.current_span())
@@ -741,20 +674,14 @@ impl Parser<'_> {
             }
 
             if !p.eat_token(TokenKind::FatArrow) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::FatArrow,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::FatArrow));
             }
 
             let body = p.try_block_or_statement()?;
             if body == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a statement, expr or block after '=>'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a statement, expr or block after '=>'".to_string())
+                );
             }
 
             Ok(NodeBuilder::new(NodeKind::CaseArm, p.current_span())
@@ -770,28 +697,18 @@ impl Parser<'_> {
 
             let id = p.try_id()?;
             if id == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected an identifier after 'catch'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.expected_after("Expected an identifier after 'catch'".to_string()));
             }
 
             if !p.eat_token(TokenKind::FatArrow) {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::FatArrow,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::FatArrow));
             }
 
             let body = p.try_block_or_statement()?;
             if body == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a statement, expr or block after '=>'".to_string(),
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(
+                    p.expected_after("Expected a statement, expr or block after '=>'".to_string())
+                );
             }
 
             Ok(NodeBuilder::new(NodeKind::CatchArm, p.current_span())
@@ -803,12 +720,10 @@ impl Parser<'_> {
 
     pub fn try_block(&mut self) -> ParseResult {
         self.scoped_with_expected_prefix(TokenKind::LBrace.as_ref(), |p| {
-            let nodes = p.try_multi_with_bracket(
-                &[Rule::semicolon("statement or definition", |p| {
-                    p.try_statement_or_definition()
-                })],
-                (TokenKind::LBrace, TokenKind::RBrace),
-            )?;
+            let nodes = p
+                .try_statements_in_braces(&[Rule::semicolon("statement or definition", |p| {
+                    p.try_body_statement()
+                })])?;
 
             Ok(NodeBuilder::new(NodeKind::Block, p.current_span())
                 .add_multiple_children(nodes)
@@ -856,11 +771,10 @@ impl Parser<'_> {
             // Parse the inner definition or another keyword modifier
             let inner = p.try_statement_or_definition()?;
             if inner == 0 {
-                return Err(ParseError::invalid_syntax(
-                    format!("Expected a definition after `{}`", token.kind.lexme()),
-                    p.peek_next_token().kind,
-                    p.current_span(),
-                ));
+                return Err(p.expected_after(format!(
+                    "Expected a definition after `{}`",
+                    token.kind.lexme()
+                )));
             }
 
             Ok(
@@ -902,11 +816,11 @@ impl Parser<'_> {
         })
     }
 
-    // break id? (while expr)?
+    // break (: label)? (while expr)?
     pub fn try_break_statement(&mut self) -> ParseResult {
         self.scoped_with_expected_prefix(TokenKind::Break.as_ref(), |p| {
             p.eat_tokens(1);
-            let label = p.try_id()?;
+            let label = p.try_label()?;
             let guard = p.try_while_guard()?;
             Ok(NodeBuilder::new(NodeKind::BreakStatement, p.current_span())
                 .add_single_child(label)
@@ -915,11 +829,11 @@ impl Parser<'_> {
         })
     }
 
-    // continue id? (while expr)?
+    // continue (: label)? (while expr)?
     pub fn try_continue_statement(&mut self) -> ParseResult {
         self.scoped_with_expected_prefix(TokenKind::Continue.as_ref(), |p| {
             p.eat_tokens(1);
-            let label = p.try_id()?;
+            let label = p.try_label()?;
             let guard = p.try_while_guard()?;
             Ok(
                 NodeBuilder::new(NodeKind::ContinueStatement, p.current_span())
@@ -930,16 +844,26 @@ impl Parser<'_> {
         })
     }
 
+    /// Parse an optional `: label` of a loop, `break` or `continue`;
+    /// 0 when there is none.
+    fn try_label(&mut self) -> ParseResult {
+        if !self.eat_token(TokenKind::Colon) {
+            return Ok(0);
+        }
+        let label = self.try_id()?;
+        if label == 0 {
+            return Err(self.expected_after("Expected a label after ':'".to_string()));
+        }
+        Ok(label)
+    }
+
     /// Parse optional `while expr` guard
     fn try_while_guard(&mut self) -> ParseResult {
         if self.eat_token(TokenKind::While) {
             let guard = self.try_expr()?;
             if guard == 0 {
-                return Err(ParseError::invalid_syntax(
-                    "Expected a condition expression after `while`".to_string(),
-                    self.peek_next_token().kind,
-                    self.next_token_span(),
-                ));
+                return Err(self
+                    .expected_after("Expected a condition expression after `while`".to_string()));
             }
             Ok(guard)
         } else {
@@ -963,10 +887,8 @@ impl Parser<'_> {
             if p.eat_token(TokenKind::Eq) {
                 let init = p.try_expr()?;
                 if init == 0 {
-                    return Err(ParseError::invalid_syntax(
+                    return Err(p.expected_after(
                         "Expected an initializer expression after '='".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
                     ));
                 }
                 return Ok(NodeBuilder::new(NodeKind::Assign, p.current_span())
@@ -976,11 +898,7 @@ impl Parser<'_> {
             } else if p.eat_token(TokenKind::PlusEq) {
                 let init = p.try_expr()?;
                 if init == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected an expression after '+='".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.expected_after("Expected an expression after '+='".to_string()));
                 }
                 return Ok(NodeBuilder::new(NodeKind::AddAssign, p.current_span())
                     .add_single_child(expr)
@@ -989,11 +907,7 @@ impl Parser<'_> {
             } else if p.eat_token(TokenKind::MinusEq) {
                 let init = p.try_expr()?;
                 if init == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected an expression after '-='".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.expected_after("Expected an expression after '-='".to_string()));
                 }
                 return Ok(NodeBuilder::new(NodeKind::SubAssign, p.current_span())
                     .add_single_child(expr)
@@ -1002,11 +916,7 @@ impl Parser<'_> {
             } else if p.eat_token(TokenKind::StarEq) {
                 let init = p.try_expr()?;
                 if init == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected an expression after '*='".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.expected_after("Expected an expression after '*='".to_string()));
                 }
                 return Ok(NodeBuilder::new(NodeKind::MulAssign, p.current_span())
                     .add_single_child(expr)
@@ -1015,11 +925,7 @@ impl Parser<'_> {
             } else if p.eat_token(TokenKind::SlashEq) {
                 let init = p.try_expr()?;
                 if init == 0 {
-                    return Err(ParseError::invalid_syntax(
-                        "Expected an expression after '/='".to_string(),
-                        p.peek_next_token().kind,
-                        p.next_token_span(),
-                    ));
+                    return Err(p.expected_after("Expected an expression after '/='".to_string()));
                 }
                 return Ok(NodeBuilder::new(NodeKind::DivAssign, p.current_span())
                     .add_single_child(expr)
@@ -1035,18 +941,12 @@ impl Parser<'_> {
 
     pub fn try_file_scope(&mut self) -> ParseResult {
         self.scoped(|p| {
-            let nodes = p.try_multi(&[
+            let nodes = p.try_items_recovering(&[
                 Rule::comma("property", |p| p.try_property()),
-                Rule::semicolon("statement or definition", |p| {
-                    p.try_statement_or_definition()
-                }),
+                Rule::semicolon("statement or definition", |p| p.try_item()),
             ])?;
             if p.peek_next_token().kind != TokenKind::Eof {
-                return Err(ParseError::unexpected_token(
-                    TokenKind::Eof,
-                    p.peek_next_token().kind,
-                    p.next_token_span(),
-                ));
+                return Err(p.unexpected(TokenKind::Eof));
             }
             Ok(NodeBuilder::new(NodeKind::FileScope, p.current_span())
                 .add_multiple_children(nodes)
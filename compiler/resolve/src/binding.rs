@@ -32,6 +32,8 @@ pub enum BindingKind {
     Union,
     /// A type alias.
     TypeAlias,
+    /// A `const` item.
+    Const,
     /// A trait definition.
     Trait,
     /// An `extend` block. An extension could be named and be referred to.
@@ -97,3 +99,21 @@ impl Resolution {
         self
     }
 }
+
+/// Every definition a simple name could refer to, as found by
+/// [`Resolver::lookup_name`](crate::resolver::Resolver::lookup_name).
+///
+/// Unlike [`Resolver::resolve_name`](crate::resolver::Resolver::resolve_name),
+/// which turns anything but a single definition into an error, this lets the
+/// caller decide how to report a missing or ambiguous name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NameLookup {
+    /// No enclosing scope has the name.
+    NotFound,
+    /// The name refers to exactly one definition.
+    Single(Resolution),
+    /// The nearest scope that has the name gets a different definition from
+    /// each of several glob imports; each candidate records the scope it is
+    /// imported from.
+    Ambiguous(Vec<Resolution>),
+}
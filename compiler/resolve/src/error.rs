@@ -3,12 +3,17 @@
 use diagnostic::{DiagnosticContext, FlurryError};
 use rustc_span::Span;
 
+use crate::ids::ScopeId;
+
 /// Convenience alias.
 pub type ResolveResult<T> = Result<T, ResolveError>;
 
 /// Base error code for name-resolution errors.
 pub const RESOLVE_ERROR_BASE: u32 = 4000;
 
+/// Code of the warning for an item named after a compiler builtin.
+pub const SHADOWED_BUILTIN_NAME: u32 = RESOLVE_ERROR_BASE + 12;
+
 /// Errors that can occur during early name resolution.
 #[derive(Debug, Clone)]
 pub enum ResolveError {
@@ -53,6 +58,21 @@ pub enum ResolveError {
         segment: String,
         span: Span,
     },
+    /// A name is supplied by more than one glob import and none of them
+    /// takes precedence.
+    AmbiguousName {
+        name: String,
+        span: Span,
+        /// The scopes whose glob imports supply the name, paired with
+        /// their names for display.
+        candidates: Vec<(ScopeId, String)>,
+    },
+    /// A `mod id` statement has neither an `id.fl` file nor an `id/`
+    /// directory next to the file declaring it.
+    ModuleFileNotFound {
+        name: String,
+        span: Span,
+    },
     /// Generic internal error.
     InternalError(String),
 }
@@ -74,6 +94,25 @@ impl ResolveError {
             Self::UnresolvedImportSegment { segment, .. } => {
                 format!("unresolved import path segment `{}`", segment)
             }
+            Self::AmbiguousName {
+                name, candidates, ..
+            } => {
+                let origins: Vec<String> = candidates
+                    .iter()
+                    .map(|(_, origin)| format!("`{}`", origin))
+                    .collect();
+                format!(
+                    "`{}` is ambiguous: it is glob-imported from {}",
+                    name,
+                    origins.join(" and ")
+                )
+            }
+            Self::ModuleFileNotFound { name, .. } => {
+                format!(
+                    "file not found for module `{}`: expected `{}.fl`, `{}/mod.fl` or `{}/`",
+                    name, name, name, name
+                )
+            }
             Self::InternalError(msg) => msg.clone(),
         }
     }
@@ -86,7 +125,9 @@ impl ResolveError {
             | Self::FileParsingFailed { span, .. }
             | Self::UnresolvedName { span, .. }
             | Self::CyclicImport { span, .. }
-            | Self::UnresolvedImportSegment { span, .. } => *span,
+            | Self::UnresolvedImportSegment { span, .. }
+            | Self::AmbiguousName { span, .. }
+            | Self::ModuleFileNotFound { span, .. } => *span,
             Self::DuplicateDefinition { second_span, .. } => *second_span,
             Self::InternalError(_) => rustc_span::DUMMY_SP,
         }
@@ -105,6 +146,8 @@ impl FlurryError for ResolveError {
             Self::DuplicateDefinition { .. } => RESOLVE_ERROR_BASE + 7,
             Self::UnresolvedImportSegment { .. } => RESOLVE_ERROR_BASE + 8,
             Self::InternalError(_) => RESOLVE_ERROR_BASE + 9,
+            Self::AmbiguousName { .. } => RESOLVE_ERROR_BASE + 10,
+            Self::ModuleFileNotFound { .. } => RESOLVE_ERROR_BASE + 11,
         }
     }
 
@@ -119,6 +162,8 @@ impl FlurryError for ResolveError {
             Self::DuplicateDefinition { .. } => "duplicate_definition",
             Self::UnresolvedImportSegment { .. } => "unresolved_import_segment",
             Self::InternalError(_) => "internal_error",
+            Self::AmbiguousName { .. } => "ambiguous_name",
+            Self::ModuleFileNotFound { .. } => "module_file_not_found",
         }
     }
 
@@ -126,11 +171,82 @@ impl FlurryError for ResolveError {
         let span = self.span();
         let message = self.message();
 
-        diag_ctx
+        let mut builder = diag_ctx
             .error(message.clone())
             .with_code(self.error_code())
             .with_error_label(span, message)
-            .with_primary_span(span)
-            .emit(diag_ctx);
+            .with_primary_span(span);
+        if let Self::DuplicateDefinition { first_span, .. } = self
+            && !first_span.is_dummy()
+        {
+            builder = builder.with_related(*first_span, "first defined here".to_string());
+        }
+        builder.emit(diag_ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use diagnostic::testing::assert_registered;
+    use rustc_span::DUMMY_SP;
+
+    use super::*;
+
+    #[test]
+    fn every_error_is_registered_under_its_name() {
+        let errors = [
+            ResolveError::ScopeCreationFailed {
+                message: String::new(),
+                span: DUMMY_SP,
+            },
+            ResolveError::ModuleNotFound {
+                message: String::new(),
+                span: DUMMY_SP,
+            },
+            ResolveError::InvalidNodeType {
+                message: String::new(),
+                span: DUMMY_SP,
+            },
+            ResolveError::FileParsingFailed {
+                message: String::new(),
+                span: DUMMY_SP,
+            },
+            ResolveError::UnresolvedName {
+                name: String::new(),
+                span: DUMMY_SP,
+            },
+            ResolveError::CyclicImport {
+                message: String::new(),
+                span: DUMMY_SP,
+            },
+            ResolveError::DuplicateDefinition {
+                name: String::new(),
+                first_span: DUMMY_SP,
+                second_span: DUMMY_SP,
+            },
+            ResolveError::UnresolvedImportSegment {
+                segment: String::new(),
+                span: DUMMY_SP,
+            },
+            ResolveError::AmbiguousName {
+                name: String::new(),
+                span: DUMMY_SP,
+                candidates: Vec::new(),
+            },
+            ResolveError::ModuleFileNotFound {
+                name: String::new(),
+                span: DUMMY_SP,
+            },
+            ResolveError::InternalError(String::new()),
+        ];
+        for error in &errors {
+            assert_registered(error);
+        }
+    }
+
+    #[test]
+    fn the_shadowed_builtin_warning_is_registered() {
+        let info = diagnostic::registry::lookup(SHADOWED_BUILTIN_NAME).unwrap();
+        assert_eq!(info.name, "shadowed_builtin_name");
     }
 }
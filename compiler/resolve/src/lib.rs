@@ -7,7 +7,8 @@
 //!
 //! 1. **Build phase** ([`build_module_tree`]) – scans the VFS, parses `.fl`
 //!    files, constructs the scope tree, and resolves `use` imports in a
-//!    fixpoint loop.  Produces a [`ModuleTree`].
+//!    fixpoint loop.  Produces a [`ModuleTree`].  A workspace of several
+//!    packages is built into one tree with [`build_workspace_module_tree`].
 //!
 //! 2. **Query phase** ([`Resolver`]) – borrows the immutable `ModuleTree`
 //!    and answers name/path resolution queries from the AST lowering pass.
@@ -45,13 +46,16 @@ pub mod rib;
 pub mod scanner;
 pub mod scope;
 
-pub use binding::{Binding, BindingKind, Resolution};
-pub use error::{ResolveError, ResolveResult};
+pub use binding::{Binding, BindingKind, NameLookup, Resolution};
+pub use error::{ResolveError, ResolveResult, SHADOWED_BUILTIN_NAME};
 pub use ids::{DefId, ScopeId};
 pub use impl_directive::{ImplDirective, ImplKind};
 pub use import::{ImportDirective, ImportKind};
 pub use item_scope::ItemScope;
-pub use module_builder::{ModuleTree, build_module_tree};
+pub use module_builder::{
+    BuildOptions, ModuleTree, PackageRoot, build_module_tree, build_module_tree_with,
+    build_workspace_module_tree,
+};
 pub use resolver::Resolver;
 pub use rib::{Rib, RibKind, RibStack};
 pub use scope::Scope;
@@ -40,10 +40,25 @@ pub struct ModuleTree {
     pub impls: Vec<ImplDirective>,
     /// Errors collected (non-fatal) during the build phase.
     pub errors: Vec<ResolveError>,
+    /// The top-level packages, in the order their VFSes were given.
+    pub packages: Vec<PackageRoot>,
+}
+
+/// A top-level package scanned into a [`ModuleTree`].
+///
+/// Every package gets its own [`ScopeKind::Package`] scope under the root,
+/// and its name is bound in the root scope so other packages can import
+/// from it (`use other.item`).
+pub struct PackageRoot {
+    /// Package name, as bound in the root scope.
+    pub name: Symbol,
+    /// The package scope.
+    pub scope: ScopeId,
     /// VFS FileId → the scope that owns the top-level definitions of that file.
     ///
     /// For entry files (`main.fl` / `lib.fl`) this is the package scope;
-    /// for named files it is the file-level module scope.
+    /// for named files it is the file-level module scope. FileIds are only
+    /// unique within one package's VFS, hence the map per package.
     pub file_scopes: HashMap<vfs::FileId, ScopeId>,
 }
 
@@ -55,20 +70,57 @@ pub struct ModuleTree {
 ///
 /// The resulting [`ModuleTree`] is then consumed by
 /// [`Resolver::new`](crate::resolver::Resolver::new) for name-resolution
-/// queries during AST lowering.
+/// queries during AST lowering. It is built with the default
+/// [`BuildOptions`]; see [`build_module_tree_with`].
 pub fn build_module_tree(
     source_map: &SourceMap,
     diag_ctx: &DiagnosticContext<'_>,
     vfs: &mut vfs::Vfs,
 ) -> ModuleTree {
-    let mut builder = ModuleBuilder::new(source_map, diag_ctx);
-    builder.build(vfs)
+    build_module_tree_with(source_map, diag_ctx, vfs, BuildOptions::default())
+}
+
+/// Build one module tree for several top-level packages, e.g. the members
+/// of a workspace.
+///
+/// Each package becomes a sibling scope under the root, so their scopes stay
+/// disjoint while imports may name another package by its VFS name.
+pub fn build_workspace_module_tree(
+    source_map: &SourceMap,
+    diag_ctx: &DiagnosticContext<'_>,
+    packages: &mut [vfs::Vfs],
+    options: BuildOptions,
+) -> ModuleTree {
+    let mut builder = ModuleBuilder::new(source_map, diag_ctx, options);
+    builder.build(packages)
+}
+
+/// Options for [`build_module_tree_with`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuildOptions {
+    /// Names of the compiler builtins, such as `void`; an item named after
+    /// one is warned about. They are defined by the `intrinsic` crate, above
+    /// this one, so the driver passes them in. The `builtin` package itself
+    /// defines those names and leaves this empty.
+    pub builtin_names: &'static [&'static str],
+}
+
+/// [`build_module_tree`] with explicit [`BuildOptions`].
+pub fn build_module_tree_with(
+    source_map: &SourceMap,
+    diag_ctx: &DiagnosticContext<'_>,
+    vfs: &mut vfs::Vfs,
+    options: BuildOptions,
+) -> ModuleTree {
+    let mut builder = ModuleBuilder::new(source_map, diag_ctx, options);
+    builder.build(std::slice::from_mut(vfs))
 }
 
 /// Internal builder that owns mutable state while constructing a [`ModuleTree`].
 struct ModuleBuilder<'a> {
     source_map: &'a SourceMap,
     diag_ctx: &'a DiagnosticContext<'a>,
+    options: BuildOptions,
     scope_tree: ScopeTree,
     def_gen: DefIdGen,
     scope_gen: ScopeIdGen,
@@ -84,12 +136,16 @@ struct ModuleBuilder<'a> {
     def_names: Vec<(DefId, Symbol)>,
     /// Index: DefId → ScopeId (built once after scan phase).
     def_to_scope: HashMap<DefId, ScopeId>,
-    /// VFS FileId → the scope that owns the file's top-level definitions.
-    file_scopes: HashMap<vfs::FileId, ScopeId>,
+    /// The packages scanned so far.
+    packages: Vec<PackageRoot>,
 }
 
 impl<'a> ModuleBuilder<'a> {
-    fn new(source_map: &'a SourceMap, diag_ctx: &'a DiagnosticContext<'a>) -> Self {
+    fn new(
+        source_map: &'a SourceMap,
+        diag_ctx: &'a DiagnosticContext<'a>,
+        options: BuildOptions,
+    ) -> Self {
         let mut scope_gen = ScopeIdGen::new();
         let mut def_gen = DefIdGen::new(0); // pkg=0 is the local package
         let mut scope_tree = ScopeTree::new();
@@ -110,6 +166,7 @@ impl<'a> ModuleBuilder<'a> {
         Self {
             source_map,
             diag_ctx,
+            options,
             scope_tree,
             def_gen,
             scope_gen,
@@ -119,15 +176,17 @@ impl<'a> ModuleBuilder<'a> {
             errors: Vec::new(),
             def_names: vec![(root_def, Symbol::intern("<root>"))],
             def_to_scope: HashMap::new(),
-            file_scopes: HashMap::new(),
+            packages: Vec::new(),
         }
     }
 
     /// Run both build phases and produce a [`ModuleTree`].
-    fn build(&mut self, vfs: &mut vfs::Vfs) -> ModuleTree {
+    fn build(&mut self, packages: &mut [vfs::Vfs]) -> ModuleTree {
         // Phase 1: VFS scan → scope tree + unresolved imports
-        if let Err(e) = self.scan_phase(vfs) {
-            self.errors.push(e);
+        for vfs in packages {
+            if let Err(e) = self.scan_phase(vfs) {
+                self.errors.push(e);
+            }
         }
 
         // Build DefId→ScopeId index now that the scope tree is complete.
@@ -148,7 +207,7 @@ impl<'a> ModuleBuilder<'a> {
         let impls = std::mem::take(&mut self.impls);
         let errors = std::mem::take(&mut self.errors);
         let scope_tree = std::mem::replace(&mut self.scope_tree, ScopeTree::new());
-        let file_scopes = std::mem::take(&mut self.file_scopes);
+        let packages = std::mem::take(&mut self.packages);
 
         ModuleTree {
             scope_tree,
@@ -156,7 +215,7 @@ impl<'a> ModuleBuilder<'a> {
             def_count,
             impls,
             errors,
-            file_scopes,
+            packages,
         }
     }
 
@@ -169,14 +228,19 @@ impl<'a> ModuleBuilder<'a> {
             &mut self.def_gen,
             &mut self.scope_gen,
         );
+        scanner.builtin_names = self.options.builtin_names;
 
-        scanner.scan_package(self.root_scope)?;
+        let scope = scanner.scan_package(self.root_scope)?;
 
         let (imports, impls, def_names, file_scopes) = scanner.into_results();
-        self.unresolved_imports = imports;
-        self.impls = impls;
+        self.unresolved_imports.extend(imports);
+        self.impls.extend(impls);
         self.def_names.extend(def_names);
-        self.file_scopes = file_scopes;
+        self.packages.push(PackageRoot {
+            name: Symbol::intern(&vfs.name),
+            scope,
+            file_scopes,
+        });
 
         Ok(())
     }
@@ -318,10 +382,16 @@ impl<'a> ModuleBuilder<'a> {
             PathAnchor::Package => self.root_scope,
         };
 
-        // Walk name segments.
-        for name in segments {
+        // Walk name segments. A local path may also start with the name of a
+        // top-level package, which only the root scope binds.
+        for (i, name) in segments.iter().enumerate() {
             let binding = self
                 .lookup_in_scope(name.as_str(), scope_id)
+                .or_else(|| {
+                    (i == 0 && anchor == PathAnchor::Local)
+                        .then(|| self.lookup_in_scope(name.as_str(), self.root_scope))
+                        .flatten()
+                })
                 .ok_or_else(|| ResolveError::UnresolvedImportSegment {
                     segment: name.as_str().to_owned(),
                     span,
@@ -415,7 +485,47 @@ impl<'a> ModuleBuilder<'a> {
 }
 
 impl ModuleTree {
+    /// The scope owning the top-level definitions of `file` in the
+    /// `package`-th package.
+    pub fn file_scope(&self, package: usize, file: vfs::FileId) -> Option<ScopeId> {
+        self.packages.get(package)?.file_scopes.get(&file).copied()
+    }
+
+    /// The file whose top-level names `file_scope` holds.
+    pub fn scope_file(&self, file_scope: ScopeId) -> Option<vfs::FileId> {
+        self.packages.iter().find_map(|package| {
+            package
+                .file_scopes
+                .iter()
+                .find(|&(_, &scope)| scope == file_scope)
+                .map(|(&file, _)| file)
+        })
+    }
+
+    /// The definitions written in the file whose top-level names `file_scope`
+    /// owns, keyed by the AST node that introduces them. Lowering uses it to
+    /// tell which [`DefId`] each item it lowers is.
+    pub fn file_defs(&self, file_scope: ScopeId) -> HashMap<ast::NodeIndex, DefId> {
+        let mut defs = HashMap::new();
+        let Some(file) = self.scope_file(file_scope) else {
+            return defs;
+        };
+        let mut scopes = vec![file_scope];
+        while let Some(scope) = scopes.pop().and_then(|id| self.scope_tree.get(id)) {
+            for binding in scope.items.declarations().values() {
+                if let Some(ast_ref) = binding.ast_ref.filter(|r| r.file == file) {
+                    defs.insert(ast_ref.node, binding.def_id);
+                }
+            }
+            scopes.extend(scope.children.iter().copied());
+        }
+        defs
+    }
+
     /// Dump the scope tree as an S-expression string (for debugging).
+    ///
+    /// Declarations are listed by name, so building the same package twice
+    /// dumps the same text.
     pub fn dump_scope_tree(&self) -> String {
         let mut out = String::new();
         for scope in self.scope_tree.iter() {
@@ -440,7 +550,9 @@ impl ModuleTree {
         ));
 
         // Declarations
-        for (decl_name, binding) in scope.items.declarations() {
+        let mut declarations: Vec<_> = scope.items.declarations().iter().collect();
+        declarations.sort_unstable_by_key(|&(name, _)| name.as_str());
+        for (decl_name, binding) in declarations {
             out.push_str(&format!(
                 "{}  (def {} {:?} {:?})\n",
                 pad, decl_name, binding.kind, binding.def_id
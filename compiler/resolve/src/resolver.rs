@@ -1,6 +1,8 @@
+use std::cell::Cell;
+
 use symbol::{DefId, PathAnchor, Symbol};
 
-use crate::binding::{Binding, Resolution};
+use crate::binding::{Binding, NameLookup, Resolution};
 use crate::error::{ResolveError, ResolveResult};
 use crate::ids::ScopeId;
 use crate::import::ResolvedImport;
@@ -24,6 +26,8 @@ pub struct Resolver<'a> {
     tree: &'a ModuleTree,
     /// Rib stack for ordered (lexical) scopes.
     ribs: RibStack,
+    /// Number of [`Resolver::lookup_name`] calls so far.
+    name_lookups: Cell<usize>,
 }
 
 impl<'a> Resolver<'a> {
@@ -32,36 +36,64 @@ impl<'a> Resolver<'a> {
         Self {
             tree,
             ribs: RibStack::new(),
+            name_lookups: Cell::new(0),
+        }
+    }
+
+    /// How many names have been looked up through [`Resolver::lookup_name`]
+    /// (including via [`Resolver::resolve_name`]), for callers checking their
+    /// own caching.
+    pub fn name_lookups(&self) -> usize {
+        self.name_lookups.get()
+    }
+
+    /// Look up a simple name starting from `scope_id`, walking up the scope
+    /// chain, and report every definition it could refer to.
+    ///
+    /// The search stops at the nearest scope that has the name, so an
+    /// ambiguity further out is never reported once a nearer scope settles
+    /// the name.
+    pub fn lookup_name(&self, name: &str, scope_id: ScopeId) -> NameLookup {
+        self.name_lookups.set(self.name_lookups.get() + 1);
+
+        // 1. Check the rib stack first (lexical / ordered bindings).
+        if let Some(binding) = self.ribs.lookup(name) {
+            return NameLookup::Single(Resolution::from_binding(binding));
+        }
+
+        // 2. Walk up the scope tree.
+        for scope in self.scope_tree().ancestors(scope_id) {
+            match self.lookup_in_scope(name, scope.id) {
+                Ok(Some(binding)) => return NameLookup::Single(Resolution::from_binding(&binding)),
+                Ok(None) => {}
+                Err(candidates) => return NameLookup::Ambiguous(candidates),
+            }
         }
+
+        NameLookup::NotFound
     }
 
     /// Resolve a simple name starting from `scope_id`, walking up the scope
     /// chain.
     ///
     /// Returns `Err(ResolveError::UnresolvedName)` if the name cannot be
-    /// found in any enclosing scope.
+    /// found in any enclosing scope, and `Err(ResolveError::AmbiguousName)`
+    /// if the nearest scope that has it gets it from several glob imports.
+    /// Use [`Resolver::lookup_name`] to handle those cases yourself.
     pub fn resolve_name(
         &self,
         name: &str,
         scope_id: ScopeId,
         span: rustc_span::Span,
     ) -> ResolveResult<Resolution> {
-        // 1. Check the rib stack first (lexical / ordered bindings).
-        if let Some(binding) = self.ribs.lookup(name) {
-            return Ok(Resolution::from_binding(binding));
-        }
-
-        // 2. Walk up the scope tree.
-        for scope in self.scope_tree().ancestors(scope_id) {
-            if let Some(binding) = self.lookup_in_scope(name, scope.id) {
-                return Ok(Resolution::from_binding(&binding));
-            }
+        match self.lookup_name(name, scope_id) {
+            NameLookup::Single(resolution) => Ok(resolution),
+            NameLookup::Ambiguous(candidates) => Err(self.ambiguity_error(name, &candidates, span)),
+            NameLookup::NotFound => Err(ResolveError::UnresolvedName {
+                name: name.to_string(),
+                span,
+            }),
         }
-
-        Err(ResolveError::UnresolvedName {
-            name: name.to_string(),
-            span,
-        })
     }
 
     /// Resolve a dotted path (e.g. `a.b.c`) starting from `scope_id`.
@@ -93,12 +125,13 @@ impl<'a> Resolver<'a> {
 
         // Resolve the final name in that scope (direct only, no ancestor walk).
         let name = &tail[0];
-        let binding = self.lookup_in_scope(name, target_scope).ok_or_else(|| {
-            ResolveError::UnresolvedName {
+        let binding = self
+            .lookup_in_scope(name, target_scope)
+            .map_err(|candidates| self.ambiguity_error(name, &candidates, span))?
+            .ok_or_else(|| ResolveError::UnresolvedName {
                 name: name.clone(),
                 span,
-            }
-        })?;
+            })?;
 
         Ok(Resolution::from_binding(&binding).with_import_source(target_scope))
     }
@@ -133,7 +166,8 @@ impl<'a> Resolver<'a> {
                 self.resolve_name_to_binding(segment, scope_id)
             } else {
                 self.lookup_in_scope(segment, scope_id)
-            };
+            }
+            .map_err(|candidates| self.ambiguity_error(segment, &candidates, span))?;
 
             let binding = binding.ok_or_else(|| ResolveError::UnresolvedImportSegment {
                 segment: segment.clone(),
@@ -164,45 +198,64 @@ impl<'a> Resolver<'a> {
     }
 
     /// Look up a name by walking up the scope tree (no rib stack).
-    /// Returns `None` if not found.
-    fn resolve_name_to_binding(&self, name: &str, scope_id: ScopeId) -> Option<Binding> {
+    /// Returns `Ok(None)` if not found.
+    fn resolve_name_to_binding(
+        &self,
+        name: &str,
+        scope_id: ScopeId,
+    ) -> Result<Option<Binding>, Vec<Resolution>> {
         for scope in self.scope_tree().ancestors(scope_id) {
-            if let Some(b) = self.lookup_in_scope(name, scope.id) {
-                return Some(b);
+            if let Some(b) = self.lookup_in_scope(name, scope.id)? {
+                return Ok(Some(b));
             }
         }
-        None
+        Ok(None)
     }
 
     /// Look up a name in a single scope (direct declarations + resolved
     /// imports).
-    fn lookup_in_scope(&self, name: &str, scope_id: ScopeId) -> Option<Binding> {
-        let scope = self.scope_tree().get(scope_id)?;
+    ///
+    /// Declarations shadow imports, and named imports shadow glob imports.
+    /// When several glob imports supply different definitions for the name,
+    /// each of them is returned as the error, tagged with the scope it is
+    /// imported from.
+    fn lookup_in_scope(
+        &self,
+        name: &str,
+        scope_id: ScopeId,
+    ) -> Result<Option<Binding>, Vec<Resolution>> {
+        let Some(scope) = self.scope_tree().get(scope_id) else {
+            return Ok(None);
+        };
 
         // Direct declarations (including clauses).
         if let Some(b) = scope.items.get_local(name) {
-            return Some(b.clone());
+            return Ok(Some(b.clone()));
         }
 
-        // Resolved imports.
+        // Resolved imports; globs are only consulted once no named import
+        // matched.
+        let mut globs: Vec<(ScopeId, Binding)> = Vec::new();
         for import in scope.items.imports() {
             match import {
                 ResolvedImport::Glob(source_scope) => {
-                    if let Some(b) = self.lookup_direct(name, *source_scope) {
-                        return Some(b);
+                    if let Some(b) = self.lookup_direct(name, *source_scope)
+                        && !globs.iter().any(|(_, seen)| seen.def_id == b.def_id)
+                    {
+                        globs.push((*source_scope, b));
                     }
                 }
                 ResolvedImport::Multi(source_scope, names) => {
                     if names.iter().any(|n| n == name) {
                         if let Some(b) = self.lookup_direct(name, *source_scope) {
-                            return Some(b);
+                            return Ok(Some(b));
                         }
                     }
                 }
                 ResolvedImport::Single(source_scope, imported_name) => {
                     if imported_name == name {
                         if let Some(b) = self.lookup_direct(name, *source_scope) {
-                            return Some(b);
+                            return Ok(Some(b));
                         }
                     }
                 }
@@ -213,14 +266,48 @@ impl<'a> Resolver<'a> {
                 } => {
                     if alias == name {
                         if let Some(b) = self.lookup_direct(original.as_str(), *source_scope) {
-                            return Some(b);
+                            return Ok(Some(b));
                         }
                     }
                 }
             }
         }
 
-        None
+        match globs.len() {
+            0 => Ok(None),
+            1 => Ok(globs.pop().map(|(_, b)| b)),
+            _ => Err(globs
+                .into_iter()
+                .map(|(source, b)| Resolution::from_binding(&b).with_import_source(source))
+                .collect()),
+        }
+    }
+
+    /// The error for `name` being supplied by several glob imports, given
+    /// the candidates of a [`NameLookup::Ambiguous`].
+    pub fn ambiguity_error(
+        &self,
+        name: &str,
+        candidates: &[Resolution],
+        span: rustc_span::Span,
+    ) -> ResolveError {
+        let candidates = candidates
+            .iter()
+            .map(|candidate| {
+                let scope_id = candidate.imported_from.unwrap_or(candidate.defined_in);
+                let origin = self
+                    .scope_tree()
+                    .get(scope_id)
+                    .and_then(|scope| scope.name.map(|name| name.to_string()))
+                    .unwrap_or_else(|| "<anon>".to_string());
+                (scope_id, origin)
+            })
+            .collect();
+        ResolveError::AmbiguousName {
+            name: name.to_string(),
+            span,
+            candidates,
+        }
     }
 
     /// Look up a name in direct declarations only (no imports, no ancestor walk).
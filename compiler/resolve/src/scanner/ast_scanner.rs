@@ -4,16 +4,35 @@
 //! Migrated and refactored from `luna/src/scan/ast_scanner.rs`.
 
 use ast::{Ast, NodeIndex, NodeKind};
-use rustc_span::SourceMap;
+use diagnostic::DiagnosticContext;
+use rustc_span::{SourceMap, Span};
 use symbol::{PathAnchor, Symbol};
 
 use crate::binding::{Binding, BindingKind, Visibility};
-use crate::error::{ResolveError, ResolveResult};
+use crate::error::{ResolveError, ResolveResult, SHADOWED_BUILTIN_NAME};
 use crate::ids::{AstNodeRef, DefId, DefIdGen, ScopeId, ScopeIdGen};
 use crate::impl_directive::{ImplDirective, ImplKind};
 use crate::import::{ImportDirective, ImportKind};
 use crate::scope::{Scope, ScopeKind, ScopeTree};
 
+/// A `mod id` statement, whose module body lives in another file.
+///
+/// The statement is only recorded during the AST scan; the
+/// [`VfsScanner`](super::VfsScanner) links it to `id.fl` or `id/` next to
+/// the declaring file once that file is done.
+#[derive(Debug, Clone)]
+pub struct ModDeclaration {
+    /// The scope the module is declared in.
+    pub parent_scope: ScopeId,
+    /// The declared module name.
+    pub name: Symbol,
+    /// The `ModStatement` node, in the declaring file.
+    pub node: AstNodeRef,
+    /// Span of the module name.
+    pub span: Span,
+    pub vis: Visibility,
+}
+
 /// Context carried through an AST scan of one file.
 pub struct AstScanner<'a> {
     /// The AST being scanned.
@@ -32,8 +51,15 @@ pub struct AstScanner<'a> {
     pub imports: &'a mut Vec<ImportDirective>,
     /// Collected impl directives.
     pub impls: &'a mut Vec<ImplDirective>,
+    /// Collected `mod id` statements.
+    pub mod_decls: &'a mut Vec<ModDeclaration>,
     /// Mapping from DefId → name (for debug / dump).
     pub def_names: &'a mut Vec<(DefId, Symbol)>,
+    /// Where to report warnings.
+    pub diag_ctx: &'a DiagnosticContext<'a>,
+    /// Names of the compiler builtins; items named after one are warned
+    /// about.
+    pub builtin_names: &'a [&'a str],
 }
 
 impl<'a> AstScanner<'a> {
@@ -53,57 +79,99 @@ impl<'a> AstScanner<'a> {
         for &item in items {
             // Strip a leading `pub` wrapper if present and determine visibility.
             let (inner, vis) = self.strip_pub_wrapper(item);
-            let item_kind = self
-                .ast
-                .get_node_kind(inner)
-                .ok_or_else(|| ResolveError::InternalError("Invalid node index".into()))?;
-
-            match item_kind {
-                NodeKind::ModuleDef => {
-                    self.scan_module_def(parent_scope, inner, vis)?;
-                }
-                NodeKind::StructDef => {
-                    self.scan_adt_def(
-                        parent_scope,
-                        inner,
-                        BindingKind::Struct,
-                        ScopeKind::AdtBody,
-                        vis,
-                    )?;
-                }
-                NodeKind::EnumDef => {
-                    self.scan_adt_def(
-                        parent_scope,
-                        inner,
-                        BindingKind::Enum,
-                        ScopeKind::AdtBody,
-                        vis,
-                    )?;
-                }
-                NodeKind::UnionDef => {
-                    self.scan_adt_def(
-                        parent_scope,
-                        inner,
-                        BindingKind::Union,
-                        ScopeKind::AdtBody,
-                        vis,
-                    )?;
-                }
-                NodeKind::Function => {
-                    self.scan_function_def(parent_scope, inner, vis)?;
-                }
-                NodeKind::ImplDef => {
-                    self.scan_impl_def(parent_scope, inner, ImplKind::Inherent)?;
-                }
-                NodeKind::ImplTraitDef => {
-                    self.scan_impl_def(parent_scope, inner, ImplKind::TraitImpl)?;
-                }
-                NodeKind::UseStatement => {
-                    self.collect_import(parent_scope, inner, vis == Visibility::Public)?;
-                }
-                _ => {
-                    // Other node kinds are ignored during the scan pass.
-                }
+            self.scan_item(parent_scope, inner, vis)?;
+        }
+        Ok(())
+    }
+
+    /// Scan one item node, already stripped of its `pub` wrapper.
+    fn scan_item(
+        &mut self,
+        parent_scope: ScopeId,
+        inner: NodeIndex,
+        vis: Visibility,
+    ) -> ResolveResult<()> {
+        let item_kind = self
+            .ast
+            .get_node_kind(inner)
+            .ok_or_else(|| ResolveError::InternalError("Invalid node index".into()))?;
+
+        match item_kind {
+            NodeKind::ModuleDef => {
+                self.scan_module_def(parent_scope, inner, vis)?;
+            }
+            NodeKind::StructDef => {
+                self.scan_adt_def(
+                    parent_scope,
+                    inner,
+                    BindingKind::Struct,
+                    ScopeKind::AdtBody,
+                    vis,
+                )?;
+            }
+            NodeKind::EnumDef => {
+                self.scan_adt_def(
+                    parent_scope,
+                    inner,
+                    BindingKind::Enum,
+                    ScopeKind::AdtBody,
+                    vis,
+                )?;
+            }
+            NodeKind::UnionDef => {
+                self.scan_adt_def(
+                    parent_scope,
+                    inner,
+                    BindingKind::Union,
+                    ScopeKind::AdtBody,
+                    vis,
+                )?;
+            }
+            NodeKind::TraitDef => {
+                self.scan_adt_def(
+                    parent_scope,
+                    inner,
+                    BindingKind::Trait,
+                    ScopeKind::TraitBody,
+                    vis,
+                )?;
+            }
+            NodeKind::Function => {
+                self.scan_function_def(parent_scope, inner, vis)?;
+            }
+            NodeKind::ConstDef | NodeKind::ConstDecl => {
+                self.scan_named_def(parent_scope, inner, BindingKind::Const, vis)?;
+            }
+            NodeKind::TypealiasDef | NodeKind::NewtypeDef => {
+                self.scan_named_def(parent_scope, inner, BindingKind::TypeAlias, vis)?;
+            }
+            // Attribute-wrapped and keyword-modified definitions, such
+            // as `comptime fn ..`, define the name of what they wrap.
+            NodeKind::Attribute | NodeKind::AttributeSetTrue => {
+                let target = self.ast.get_children(inner)[1];
+                self.scan_item(parent_scope, target, vis)?;
+            }
+            NodeKind::ImplDef => {
+                self.scan_impl_def(parent_scope, inner, ImplKind::Inherent)?;
+            }
+            NodeKind::ImplTraitDef => {
+                self.scan_impl_def(parent_scope, inner, ImplKind::TraitImpl)?;
+            }
+            NodeKind::UseStatement => {
+                self.collect_import(parent_scope, inner, vis == Visibility::Public)?;
+            }
+            NodeKind::ModStatement => {
+                let id_node = self.ast.get_children(inner)[0];
+                self.mod_decls.push(ModDeclaration {
+                    parent_scope,
+                    name: self.extract_name(id_node)?,
+                    node: AstNodeRef::new(self.file_id, inner),
+                    span: self.ast.get_span(id_node).unwrap_or_default(),
+                    vis,
+                });
+            }
+            _ => {
+                // Other node kinds are ignored during the scan pass.
             }
         }
         Ok(())
@@ -152,11 +220,8 @@ impl<'a> AstScanner<'a> {
         )?;
         self.def_names.push((def_id, name));
 
-        // Scan clauses
-        self.scan_clauses(scope_id, def_id, item, 1)?;
-
-        // Scan the module body (child[2] = block, block child[0] = items)
-        let block_index = self.ast.get_children(item)[2];
+        // Scan the module body (child[1] = block, block child[0] = items)
+        let block_index = self.ast.get_children(item)[1];
         let block_items_index = self.ast.get_children(block_index)[0];
         if let Some(block_items) = self.ast.get_multi_child_slice(block_items_index) {
             self.scan_items(scope_id, block_items)?;
@@ -191,11 +256,18 @@ impl<'a> AstScanner<'a> {
         self.define_in_scope(parent_scope, name, def_id, kind, Some(item), vis)?;
         self.def_names.push((def_id, name));
 
+        // A trait has its super traits before the clauses:
+        // `[id, super_traits, clauses_N, body]`.
+        let (clauses, body) = match self.ast.get_node_kind(item) {
+            Some(NodeKind::TraitDef) => (2, 3),
+            _ => (1, 2),
+        };
+
         // Scan clauses
-        self.scan_clauses(scope_id, def_id, item, 1)?;
+        self.scan_clauses(scope_id, def_id, item, clauses)?;
 
-        // Scan body items (child[2] = body block, child[0] = items list)
-        let body_index = self.ast.get_children(item)[2];
+        // Scan body items (child[body] = body block, child[0] = items list)
+        let body_index = self.ast.get_children(item)[body];
         let body_items_index = self.ast.get_children(body_index)[0];
         if let Some(body_items) = self.ast.get_multi_child_slice(body_items_index) {
             self.scan_items(scope_id, body_items)?;
@@ -243,6 +315,23 @@ impl<'a> AstScanner<'a> {
         Ok(())
     }
 
+    /// Scan a `const`, `typealias` or `newtype` item: its name, the first
+    /// child of `item`, is defined in `parent_scope`. It opens no scope of
+    /// its own.
+    fn scan_named_def(
+        &mut self,
+        parent_scope: ScopeId,
+        item: NodeIndex,
+        kind: BindingKind,
+        vis: Visibility,
+    ) -> ResolveResult<()> {
+        let name = self.extract_name(self.ast.get_children(item)[0])?;
+        let def_id = self.def_gen.next();
+        self.define_in_scope(parent_scope, name, def_id, kind, Some(item), vis)?;
+        self.def_names.push((def_id, name));
+        Ok(())
+    }
+
     /// Scan an `impl` or `impl Trait for Type` block.
     ///
     /// Layout:
@@ -601,13 +690,30 @@ impl<'a> AstScanner<'a> {
             vis,
         };
 
+        if self.builtin_names.contains(&name.as_str())
+            && let Some(span) = ast_node.and_then(|n| self.ast.get_span(n))
+        {
+            let message = format!("`{}` shadows a builtin name", name.as_str());
+            self.diag_ctx
+                .warning(message)
+                .with_code(SHADOWED_BUILTIN_NAME)
+                .with_warning_label(span, "defined here".to_string())
+                .with_primary_span(span)
+                .emit(self.diag_ctx);
+        }
+
         if let Some(scope) = self.scope_tree.get_mut(scope_id) {
-            if let Err(_old) = scope.items.define(name, binding) {
+            if let Err(old) = scope.items.define(name, binding) {
                 // In an unordered scope, duplicate is an error
                 if !scope.ordered {
                     return Err(ResolveError::DuplicateDefinition {
                         name: name.as_str().to_string(),
-                        first_span: rustc_span::DUMMY_SP,
+                        // Only a definition in this file has its span at hand.
+                        first_span: old
+                            .ast_ref
+                            .filter(|r| r.file == self.file_id)
+                            .and_then(|r| self.ast.get_span(r.node))
+                            .unwrap_or_default(),
                         second_span: ast_node
                             .and_then(|n| self.ast.get_span(n))
                             .unwrap_or_default(),
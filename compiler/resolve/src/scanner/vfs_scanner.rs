@@ -3,24 +3,35 @@
 //!
 //! Migrated and refactored from `luna/src/scan/vfs_scanner.rs`.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use ast::Ast;
+use ast::{Ast, NodeIndex, NodeKind};
 use diagnostic::{DiagnosticContext, FlurryError};
-use symbol::Symbol;
 use lex::lex;
-use parse::parser::Parser;
+use parse::parse_file;
 use rustc_span::{SourceFile, SourceMap};
+use symbol::Symbol;
 
 use crate::binding::{BindingKind, Visibility};
 use crate::error::{ResolveError, ResolveResult};
-use crate::ids::{DefId, DefIdGen, ScopeId, ScopeIdGen};
+use crate::ids::{AstNodeRef, DefId, DefIdGen, ScopeId, ScopeIdGen};
 use crate::impl_directive::ImplDirective;
 use crate::import::ImportDirective;
 use crate::scope::{Scope, ScopeKind, ScopeTree};
 
-use super::ast_scanner::AstScanner;
+use super::ast_scanner::{AstScanner, ModDeclaration};
+
+/// Where the body of a `mod id` statement lives.
+enum ModuleSource {
+    /// `id.fl` next to the declaring file, or the entry file `id/mod.fl`.
+    File(vfs::FileId),
+    /// The directory `id/` next to the declaring file, and the files below
+    /// it. Its subdirectories are submodules, and an entry file in a
+    /// directory holds the items of that directory's module.
+    Dir(PathBuf, Vec<vfs::FileId>),
+}
 
 /// Walks the VFS, parses source files, and builds the package scope tree.
 pub struct VfsScanner<'a> {
@@ -40,10 +51,15 @@ pub struct VfsScanner<'a> {
     pub imports: Vec<ImportDirective>,
     /// Collected impl directives.
     pub impls: Vec<ImplDirective>,
+    /// `mod id` statements of the file being scanned, not yet linked.
+    pub mod_decls: Vec<ModDeclaration>,
     /// DefId → name mapping for diagnostics.
     pub def_names: Vec<(DefId, Symbol)>,
     /// VFS FileId → the scope that owns that file's top-level definitions.
     pub file_scopes: HashMap<vfs::FileId, ScopeId>,
+    /// Names of the compiler builtins; items named after one are warned
+    /// about.
+    pub builtin_names: &'static [&'static str],
 }
 
 impl<'a> VfsScanner<'a> {
@@ -64,15 +80,18 @@ impl<'a> VfsScanner<'a> {
             scope_gen,
             imports: Vec::new(),
             impls: Vec::new(),
+            mod_decls: Vec::new(),
             def_names: Vec::new(),
             file_scopes: HashMap::new(),
+            builtin_names: &[],
         }
     }
 
-    /// Scan the package rooted in the VFS.
+    /// Scan the package rooted in the VFS into a new package scope under
+    /// `root_scope`.
     ///
-    /// Returns the collected import directives; the scope tree is mutated in place.
-    pub fn scan_package(&mut self, root_scope: ScopeId) -> ResolveResult<()> {
+    /// Returns the package scope; the scope tree is mutated in place.
+    pub fn scan_package(&mut self, root_scope: ScopeId) -> ResolveResult<ScopeId> {
         let package_name = Symbol::intern(&self.vfs.name);
         let package_def = self.def_gen.next();
         let package_scope_id = self.scope_gen.next();
@@ -104,14 +123,140 @@ impl<'a> VfsScanner<'a> {
 
         self.def_names.push((package_def, package_name));
 
-        // Scan all source files
-        let file_count = self.vfs.file_count();
-        for i in 0..file_count {
-            let file_id = vfs::FileId::from_raw(i as u32);
-            self.scan_source_file(file_id, package_scope_id, package_def)?;
+        // Parse everything up front so `mod` statements are known before
+        // any file is placed in the tree.
+        let mut file_ids: Vec<vfs::FileId> = Vec::new();
+        self.vfs
+            .walk(Path::new(""), &mut |file_id, _| file_ids.push(file_id));
+        for &file_id in &file_ids {
+            if self.vfs.get_ast(file_id).is_none() {
+                let source_file = self.vfs.file(file_id).source_file.clone();
+                self.parse_file(file_id, &source_file)?;
+            }
+        }
+
+        // Files linked by a `mod` statement are scanned as part of the
+        // declaring module; the rest become modules of the package.
+        let linked = self.linked_files();
+        for file_id in file_ids {
+            if !linked.contains(&file_id) {
+                self.scan_source_file(file_id, package_scope_id, package_def)?;
+            }
         }
 
-        Ok(())
+        Ok(package_scope_id)
+    }
+
+    /// Every file that some `mod` statement in the package links to.
+    fn linked_files(&self) -> HashSet<vfs::FileId> {
+        let mut linked = HashSet::new();
+        for (file_id, _) in self.vfs.files() {
+            linked.extend(self.mod_targets(file_id));
+        }
+        linked
+    }
+
+    /// The files that the `mod` statements of `file_id` link to.
+    fn mod_targets(&self, file_id: vfs::FileId) -> Vec<vfs::FileId> {
+        let Some(ast) = self.vfs.get_ast(file_id) else {
+            return Vec::new();
+        };
+        let Some(&items) = ast.get_children(ast.root).first() else {
+            return Vec::new();
+        };
+        let mut names = Vec::new();
+        collect_mod_statements(ast, items, &mut names);
+        let mut targets = Vec::new();
+        for name in names {
+            match self.module_source(file_id, name) {
+                Some(ModuleSource::File(id)) => targets.push(id),
+                Some(ModuleSource::Dir(_, ids)) => targets.extend(ids),
+                None => {}
+            }
+        }
+        targets
+    }
+
+    /// Find the file or directory backing `mod name` declared in `declaring_file`.
+    fn module_source(&self, declaring_file: vfs::FileId, name: Symbol) -> Option<ModuleSource> {
+        let dir = self
+            .vfs
+            .file(declaring_file)
+            .rel_path
+            .parent()
+            .unwrap_or(Path::new(""));
+
+        if let Some(file_id) = self.vfs.find_module_file(dir, name.as_str()) {
+            return Some(ModuleSource::File(file_id));
+        }
+
+        let module_dir = dir.join(name.as_str());
+        if let Some(file_id) = self.vfs.find_module_file(&module_dir, "mod") {
+            return Some(ModuleSource::File(file_id));
+        }
+
+        let mut files = Vec::new();
+        self.vfs
+            .walk(&module_dir, &mut |file_id, _| files.push(file_id));
+        (!files.is_empty()).then_some(ModuleSource::Dir(module_dir, files))
+    }
+
+    /// Link a `mod id` statement to its file or directory and scan it into a
+    /// new module scope. Reports a diagnostic if neither exists.
+    fn link_module(&mut self, decl: ModDeclaration) -> ResolveResult<()> {
+        let Some(source) = self.module_source(decl.node.file, decl.name) else {
+            ResolveError::ModuleFileNotFound {
+                name: decl.name.as_str().to_string(),
+                span: decl.span,
+            }
+            .emit(self.diag_ctx, rustc_span::BytePos(0));
+            return Ok(());
+        };
+
+        let (mod_scope_id, mod_def) =
+            self.declare_module(decl.parent_scope, decl.name, Some(decl.node), decl.vis);
+        match source {
+            ModuleSource::File(file_id) => self.scan_file_into(file_id, mod_scope_id),
+            ModuleSource::Dir(module_dir, files) => {
+                // Files that a `mod` statement inside the directory links to
+                // are scanned along with that statement.
+                let claimed: HashSet<vfs::FileId> = files
+                    .iter()
+                    .flat_map(|&file_id| self.mod_targets(file_id))
+                    .collect();
+                let mut dir_modules = HashMap::from([(module_dir, (mod_scope_id, mod_def))]);
+                for file_id in files {
+                    if claimed.contains(&file_id) {
+                        continue;
+                    }
+                    let rel_path = &self.vfs.file(file_id).rel_path;
+                    let dir = rel_path.parent().unwrap_or(Path::new("")).to_path_buf();
+                    let (scope, def) = self.dir_module(&mut dir_modules, &dir);
+                    self.scan_source_file(file_id, scope, def)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The module of `dir`, a directory below that of a `mod id` statement,
+    /// declaring it and the modules of the directories between them as
+    /// needed. `dir_modules` holds the modules declared so far, starting
+    /// with that of the statement's directory.
+    fn dir_module(
+        &mut self,
+        dir_modules: &mut HashMap<PathBuf, (ScopeId, DefId)>,
+        dir: &Path,
+    ) -> (ScopeId, DefId) {
+        if let Some(&module) = dir_modules.get(dir) {
+            return module;
+        }
+        let parent = dir.parent().unwrap_or(Path::new(""));
+        let (parent_scope, _) = self.dir_module(dir_modules, parent);
+        let name = Symbol::intern(&dir.file_name().unwrap_or_default().to_string_lossy());
+        let module = self.declare_module(parent_scope, name, None, Visibility::Public);
+        dir_modules.insert(dir.to_path_buf(), module);
+        module
     }
 
     /// Scan a single source file.
@@ -125,20 +270,9 @@ impl<'a> VfsScanner<'a> {
         let rel_path = entry.rel_path.clone();
         let source_file = entry.source_file.clone();
 
-        // Determine module name from file path
-        let file_name = rel_path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown");
-
-        let is_entry = file_name == "main.fl" || file_name == "lib.fl";
-        let module_name: Option<Symbol> = if is_entry {
-            None // Entry files merge into parent scope
-        } else if file_name.ends_with(".fl") {
-            Some(Symbol::intern(&file_name[..file_name.len() - 3]))
-        } else {
-            Some(Symbol::intern(file_name))
-        };
+        // Determine module name from file path; entry files merge into the
+        // parent scope
+        let module_name = self.vfs.module_name(&rel_path).map(Symbol::intern);
 
         // Parse the file if not already parsed
         if self.vfs.get_ast(file_id).is_none() {
@@ -147,38 +281,60 @@ impl<'a> VfsScanner<'a> {
 
         // Determine the scope to scan into
         let scan_scope = if let Some(mod_name) = module_name {
-            let mod_def = self.def_gen.next();
-            let mod_scope_id = self.scope_gen.next();
-
-            let scope = Scope::new(
-                mod_scope_id,
-                ScopeKind::Module,
-                Some(parent_scope),
-                Some(mod_name),
-                mod_def,
-                false,
-            );
-            self.scope_tree.add_scope(scope);
-            self.scope_tree.add_child(parent_scope, mod_scope_id);
-
-            // Register the file module in the parent scope
-            let binding = crate::binding::Binding {
-                kind: BindingKind::Module,
-                def_id: mod_def,
-                defined_in: parent_scope,
-                ast_ref: None,
-                vis: Visibility::Public,
-            };
-            if let Some(ps) = self.scope_tree.get_mut(parent_scope) {
-                let _ = ps.items.define(mod_name, binding);
-            }
-            self.def_names.push((mod_def, mod_name));
-
-            mod_scope_id
+            self.declare_module(parent_scope, mod_name, None, Visibility::Public)
+                .0
         } else {
             parent_scope
         };
 
+        self.scan_file_into(file_id, scan_scope)
+    }
+
+    /// Create a module scope under `parent_scope` and register its name there.
+    fn declare_module(
+        &mut self,
+        parent_scope: ScopeId,
+        name: Symbol,
+        ast_ref: Option<AstNodeRef>,
+        vis: Visibility,
+    ) -> (ScopeId, DefId) {
+        let mod_def = self.def_gen.next();
+        let mod_scope_id = self.scope_gen.next();
+
+        let scope = Scope::new(
+            mod_scope_id,
+            ScopeKind::Module,
+            Some(parent_scope),
+            Some(name),
+            mod_def,
+            false,
+        );
+        self.scope_tree.add_scope(scope);
+        self.scope_tree.add_child(parent_scope, mod_scope_id);
+
+        let binding = crate::binding::Binding {
+            kind: BindingKind::Module,
+            def_id: mod_def,
+            defined_in: parent_scope,
+            ast_ref,
+            vis,
+        };
+        if let Some(ps) = self.scope_tree.get_mut(parent_scope) {
+            let _ = ps.items.define(name, binding);
+        }
+        self.def_names.push((mod_def, name));
+
+        (mod_scope_id, mod_def)
+    }
+
+    /// Scan a parsed file's top-level items into `scan_scope`, then link the
+    /// `mod` statements it declares.
+    fn scan_file_into(&mut self, file_id: vfs::FileId, scan_scope: ScopeId) -> ResolveResult<()> {
+        // A file linked by two `mod` statements is only scanned once.
+        if self.file_scopes.contains_key(&file_id) {
+            return Ok(());
+        }
+
         // Scan the AST
         let ast = self
             .vfs
@@ -196,7 +352,10 @@ impl<'a> VfsScanner<'a> {
             scope_gen: self.scope_gen,
             imports: &mut self.imports,
             impls: &mut self.impls,
+            mod_decls: &mut self.mod_decls,
             def_names: &mut self.def_names,
+            diag_ctx: self.diag_ctx,
+            builtin_names: self.builtin_names,
         };
 
         scanner.scan_file_items(scan_scope)?;
@@ -204,6 +363,10 @@ impl<'a> VfsScanner<'a> {
         // Record the mapping from FileId to the scope that holds its definitions.
         self.file_scopes.insert(file_id, scan_scope);
 
+        for decl in std::mem::take(&mut self.mod_decls) {
+            self.link_module(decl)?;
+        }
+
         Ok(())
     }
 
@@ -227,9 +390,11 @@ impl<'a> VfsScanner<'a> {
             err.emit(self.diag_ctx, source_file.start_pos);
         }
 
-        let mut parser = Parser::new(self.source_map, tokens, symbols, source_file.start_pos);
-        parser.parse(self.diag_ctx);
-        let ast = parser.finalize();
+        let (ast, parse_errors) =
+            parse_file(self.source_map, tokens, symbols, source_file.start_pos);
+        for err in parse_errors {
+            err.emit(self.diag_ctx, source_file.start_pos);
+        }
         self.vfs.set_ast(file_id, ast);
 
         Ok(())
@@ -247,3 +412,26 @@ impl<'a> VfsScanner<'a> {
         (self.imports, self.impls, self.def_names, self.file_scopes)
     }
 }
+
+/// Names declared by the `mod id` statements among `items`, including those
+/// inside inline `mod id { }` bodies.
+fn collect_mod_statements(ast: &Ast, items: NodeIndex, names: &mut Vec<Symbol>) {
+    for &item in ast.get_multi_child_slice(items).unwrap_or(&[]) {
+        let item = match ast.get_node_kind(item) {
+            Some(NodeKind::Pub) => ast.get_children(item)[0],
+            _ => item,
+        };
+        match ast.get_node_kind(item) {
+            Some(NodeKind::ModStatement) => {
+                let id = ast.get_children(ast.get_children(item)[0]);
+                // SAFETY: hi/lo produced by Symbol::to_raw_parts() in the parser.
+                names.push(unsafe { Symbol::from_raw_parts(id[0], id[1]) });
+            }
+            Some(NodeKind::ModuleDef) => {
+                let block = ast.get_children(item)[1];
+                collect_mod_statements(ast, ast.get_children(block)[0], names);
+            }
+            _ => {}
+        }
+    }
+}
@@ -6,6 +6,7 @@
 
 use std::fmt;
 
+use rustc_data_structures::fx::FxHashSet;
 use symbol::Symbol;
 
 use crate::ids::{DefId, ScopeId};
@@ -148,14 +149,36 @@ impl ScopeTree {
         }
     }
 
-    /// Walk up the parent chain from `scope_id`.
+    /// Walk up the parent chain from `scope_id`. Should the chain loop, the
+    /// walk stops once it has taken as many steps as there are scopes.
     pub fn ancestors(&self, scope_id: ScopeId) -> AncestorIter<'_> {
         AncestorIter {
             tree: self,
             current: Some(scope_id),
+            steps_left: self.scopes.len(),
         }
     }
 
+    /// The scopes from the outermost ancestor of `scope_id` down to it,
+    /// however deep it is. `None` if the parent chain loops back on itself.
+    pub fn path_from_root(&self, scope_id: ScopeId) -> Option<Vec<ScopeId>> {
+        let mut visited = FxHashSet::default();
+        let mut path = Vec::new();
+        let mut current = Some(scope_id);
+        while let Some(id) = current {
+            if !visited.insert(id) {
+                return None;
+            }
+            let Some(scope) = self.get(id) else {
+                break;
+            };
+            path.push(id);
+            current = scope.parent;
+        }
+        path.reverse();
+        Some(path)
+    }
+
     /// Number of allocated scopes.
     pub fn len(&self) -> usize {
         self.scopes.iter().filter(|s| s.id.is_valid()).count()
@@ -180,15 +203,65 @@ impl Default for ScopeTree {
 pub struct AncestorIter<'a> {
     tree: &'a ScopeTree,
     current: Option<ScopeId>,
+    /// A chain longer than the tree has scopes visits one of them twice.
+    steps_left: usize,
 }
 
 impl<'a> Iterator for AncestorIter<'a> {
     type Item = &'a Scope;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let id = self.current?;
+        let id = self.current.take()?;
+        self.steps_left = self.steps_left.checked_sub(1)?;
         let scope = self.tree.get(id)?;
         self.current = scope.parent;
         Some(scope)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tree of `parents.len()` module scopes, scope `i` having parent
+    /// `parents[i]`.
+    fn tree(parents: &[Option<u32>]) -> ScopeTree {
+        let mut tree = ScopeTree::new();
+        for (i, parent) in parents.iter().enumerate() {
+            tree.add_scope(Scope::new(
+                ScopeId::new(i as u32),
+                ScopeKind::Module,
+                parent.map(ScopeId::new),
+                None,
+                DefId::INVALID,
+                false,
+            ));
+        }
+        tree
+    }
+
+    #[test]
+    fn a_deep_scope_has_its_whole_path() {
+        let parents: Vec<_> = (0..150).map(|i| i.checked_sub(1)).collect();
+        let tree = tree(&parents);
+        let deepest = ScopeId::new(149);
+
+        let path = tree.path_from_root(deepest).unwrap();
+        assert_eq!(path.len(), 150);
+        assert_eq!(path.first(), Some(&ScopeId::ROOT));
+        assert_eq!(path.last(), Some(&deepest));
+        assert_eq!(tree.ancestors(deepest).count(), 150);
+    }
+
+    #[test]
+    fn a_parent_cycle_is_detected() {
+        let tree = tree(&[None, Some(3), Some(1), Some(2)]);
+        assert_eq!(tree.path_from_root(ScopeId::new(1)), None);
+        // Scope 1 again, then the walk gives up.
+        assert_eq!(tree.ancestors(ScopeId::new(1)).count(), 4);
+        assert_eq!(
+            tree.path_from_root(ScopeId::ROOT),
+            Some(vec![ScopeId::ROOT])
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! `.gitignore` patterns, for leaving files out of a scan.
+//!
+//! The common subset of gitignore is understood: blank lines and `#`
+//! comments are skipped, `!` includes a path again, and a trailing `/` only
+//! matches directories. A pattern with a `/` anywhere else is matched
+//! against the path relative to the directory of its `.gitignore`; one
+//! without is matched against the name alone, at any depth. `*` and `?`
+//! match within one path component, `**` across components, and `\`
+//! escapes the character after it.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// One pattern of a `.gitignore`.
+struct Rule {
+    /// The directory of the `.gitignore`, relative to the package root.
+    base: PathBuf,
+    pattern: Vec<char>,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl Rule {
+    fn parse(base: &Path, line: &str) -> Option<Rule> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+        Some(Rule {
+            base: base.to_path_buf(),
+            pattern: line.chars().collect(),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, rel_path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let Ok(sub_path) = rel_path.strip_prefix(&self.base) else {
+            return false;
+        };
+        let text: Vec<char> = if self.anchored {
+            let components: Vec<_> = sub_path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect();
+            components.join("/").chars().collect()
+        } else {
+            match sub_path.file_name() {
+                Some(name) => name.to_string_lossy().chars().collect(),
+                None => return false,
+            }
+        };
+        glob_match(&self.pattern, &text)
+    }
+}
+
+/// The `.gitignore` patterns in effect in the directory being scanned:
+/// those of the package root and of every directory down to it.
+#[derive(Default)]
+pub(crate) struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreRules {
+    /// Add the patterns of `root/rel_dir/.gitignore`, if there is one.
+    /// Returns the number of patterns before, for [`IgnoreRules::truncate`]
+    /// once the scan leaves the directory.
+    pub(crate) fn enter_dir(&mut self, root: &Path, rel_dir: &Path) -> usize {
+        let len = self.rules.len();
+        if let Ok(text) = fs::read_to_string(root.join(rel_dir).join(".gitignore")) {
+            self.rules
+                .extend(text.lines().filter_map(|line| Rule::parse(rel_dir, line)));
+        }
+        len
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.rules.truncate(len);
+    }
+
+    /// Whether the file or directory at `rel_path` below the package root
+    /// is ignored. The last pattern matching it decides.
+    pub(crate) fn is_ignored(&self, rel_path: &Path, is_dir: bool) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(rel_path, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        // `**/` also matches no directory at all.
+        ['*', '*', '/', rest @ ..] => {
+            glob_match(rest, text)
+                || (0..text.len()).any(|i| text[i] == '/' && glob_match(rest, &text[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match(rest, &text[i..])),
+        ['?', rest @ ..] => match text {
+            [c, text @ ..] => *c != '/' && glob_match(rest, text),
+            [] => false,
+        },
+        ['\\', c, rest @ ..] | [c, rest @ ..] => match text {
+            [t, text @ ..] => t == c && glob_match(rest, text),
+            [] => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignored(gitignore: &str, path: &str, is_dir: bool) -> bool {
+        let rules = IgnoreRules {
+            rules: gitignore
+                .lines()
+                .filter_map(|line| Rule::parse(Path::new(""), line))
+                .collect(),
+        };
+        rules.is_ignored(Path::new(path), is_dir)
+    }
+
+    #[test]
+    fn patterns_match_names_anywhere_or_paths_from_their_directory() {
+        assert!(ignored("*.tmp.fl", "src/a.tmp.fl", false));
+        assert!(!ignored("*.tmp.fl", "src/a.fl", false));
+        assert!(ignored("build/*.fl", "build/gen.fl", false));
+        assert!(!ignored("build/*.fl", "src/build/gen.fl", false));
+        assert!(!ignored("build/*.fl", "build/nested/gen.fl", false));
+        assert!(ignored("**/gen/*.fl", "gen/a.fl", false));
+        assert!(ignored("**/gen/*.fl", "src/deep/gen/a.fl", false));
+        assert!(ignored("/out/", "out", true));
+        assert!(!ignored("/out/", "out", false));
+        assert!(ignored("te?t.fl", "test.fl", false));
+    }
+
+    #[test]
+    fn the_last_matching_pattern_decides() {
+        let gitignore = "# generated\n*.fl\n!keep.fl\n\n";
+        assert!(ignored(gitignore, "drop.fl", false));
+        assert!(!ignored(gitignore, "keep.fl", false));
+        assert!(!ignored("\\!keep.fl", "keep.fl", false));
+        assert!(ignored("\\!keep.fl", "!keep.fl", false));
+    }
+}
@@ -2,17 +2,29 @@
 //!
 //! The VFS manages source files and their parsed ASTs. It is a **storage and
 //! lookup layer only** – parsing is the caller's responsibility.
+//!
+//! File contents come from a [`ContentProvider`]: the disk by default, or an
+//! [`Overlay`] of in-memory buffers that take precedence over it.
+
+mod ignore;
+mod provider;
+
+pub use provider::{ContentProvider, FileSystem, Overlay};
 
 use std::{
     fs,
-    path::{Path, PathBuf},
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+    path::{self, Component, Path, PathBuf},
     sync::Arc,
 };
 
-use rustc_span::{SourceFile, SourceMap};
+use rustc_span::{FileName, RealFileName, SourceFile, SourceMap};
 
 use ast::{Ast, NodeIndex};
 
+use crate::ignore::IgnoreRules;
+
 /// Identifies a source file within a package's [`Vfs`].
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct FileId(u32);
@@ -87,6 +99,25 @@ pub struct SourceEntry {
     pub source_file: Arc<SourceFile>,
 }
 
+/// Which files of a package directory are its sources.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanOptions {
+    /// Extensions of the files loaded by a scan, without the dot.
+    pub extensions: Vec<String>,
+    /// File stems of entry files, whose items belong to the module of their
+    /// directory rather than to a module of their own.
+    pub entry_stems: Vec<String>,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            extensions: vec!["fl".into()],
+            entry_stems: vec!["main".into(), "lib".into(), "mod".into()],
+        }
+    }
+}
+
 /// Virtual File System for a single package.
 ///
 /// Stores source files and their parsed ASTs. Parsing is performed externally
@@ -96,8 +127,11 @@ pub struct Vfs {
     pub name: String,
     /// Absolute path to the package root directory.
     pub root: PathBuf,
-    /// Source files, indexed by [`FileId`].
-    files: Vec<SourceEntry>,
+    /// Which files are sources, for scanning and for module names.
+    pub options: ScanOptions,
+    /// Source files, indexed by [`FileId`]. `None` once removed, so the
+    /// ids of the files after it stay the same.
+    files: Vec<Option<SourceEntry>>,
     /// Parsed ASTs, indexed by [`FileId`]. `None` until parsing is complete.
     asts: Vec<Option<Ast>>,
 }
@@ -108,6 +142,7 @@ impl Vfs {
         Vfs {
             name: name.into(),
             root,
+            options: ScanOptions::default(),
             files: Vec::new(),
             asts: Vec::new(),
         }
@@ -116,40 +151,126 @@ impl Vfs {
     /// Add a source file and return its [`FileId`].
     pub fn add_file(&mut self, rel_path: PathBuf, source_file: Arc<SourceFile>) -> FileId {
         let id = FileId(self.files.len() as u32);
-        self.files.push(SourceEntry {
+        self.files.push(Some(SourceEntry {
             rel_path,
             source_file,
-        });
+        }));
         self.asts.push(None);
         id
     }
 
     /// Get the source entry for a file.
+    ///
+    /// Panics if the file has been removed.
     #[inline]
     pub fn file(&self, id: FileId) -> &SourceEntry {
-        &self.files[id.index()]
+        self.files[id.index()]
+            .as_ref()
+            .unwrap_or_else(|| panic!("{id:?} has been removed"))
     }
 
     /// Look up a file by its relative path.
     pub fn find_file(&self, rel_path: &Path) -> Option<FileId> {
-        self.files
+        self.files()
+            .find(|(_, e)| e.rel_path == rel_path)
+            .map(|(id, _)| id)
+    }
+
+    /// Look up a file by a path an editor has for it: absolute, or relative
+    /// to the package root like [`SourceEntry::rel_path`].
+    ///
+    /// Both are compared once `.` and `..` components and repeated
+    /// separators are resolved, without touching the file system, so
+    /// `./src/../src/main.fl` names `src/main.fl`.
+    pub fn file_for_path(&self, path: &Path) -> Option<FileId> {
+        let rel_path = self.rel_path(path)?;
+        self.files()
+            .find(|(_, e)| normalize(&e.rel_path) == rel_path)
+            .map(|(id, _)| id)
+    }
+
+    /// `path` relative to the package root, normalized as for
+    /// [`Vfs::file_for_path`]. `None` if it is absolute and outside the root.
+    fn rel_path(&self, path: &Path) -> Option<PathBuf> {
+        if path.is_absolute() {
+            let root = normalize(&path::absolute(&self.root).ok()?);
+            Some(normalize(path).strip_prefix(&root).ok()?.to_path_buf())
+        } else {
+            Some(normalize(path))
+        }
+    }
+
+    /// Look up the file of the module `name` in `dir`, `dir/name.fl` by
+    /// default, trying the source extensions in order.
+    pub fn find_module_file(&self, dir: &Path, name: &str) -> Option<FileId> {
+        self.options
+            .extensions
             .iter()
-            .position(|e| e.rel_path == rel_path)
-            .map(|i| FileId(i as u32))
+            .find_map(|ext| self.find_file(&dir.join(format!("{name}.{ext}"))))
+    }
+
+    /// Whether `path` has one of the source extensions.
+    pub fn is_source(&self, path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|ext| self.options.extensions.iter().any(|e| ext == e.as_str()))
     }
 
-    /// Number of source files in this VFS.
+    /// The name of the module the file at `rel_path` declares: its name
+    /// without the source extension, or `None` for an entry file such as
+    /// `main.fl` or `mod.fl`.
+    pub fn module_name<'p>(&self, rel_path: &'p Path) -> Option<&'p str> {
+        if !self.is_source(rel_path) {
+            return rel_path.file_name()?.to_str();
+        }
+        let stem = rel_path.file_stem()?.to_str()?;
+        (!self.options.entry_stems.iter().any(|e| e == stem)).then_some(stem)
+    }
+
+    /// Number of source files in this VFS, not counting removed ones.
     #[inline]
     pub fn file_count(&self) -> usize {
-        self.files.len()
+        self.files.iter().flatten().count()
     }
 
-    /// Iterate over all files with their [`FileId`]s.
+    /// Iterate over all files with their [`FileId`]s, skipping removed ones.
     pub fn files(&self) -> impl Iterator<Item = (FileId, &SourceEntry)> {
         self.files
             .iter()
             .enumerate()
-            .map(|(i, entry)| (FileId(i as u32), entry))
+            .filter_map(|(i, entry)| Some((FileId(i as u32), entry.as_ref()?)))
+    }
+
+    /// The files directly inside `dir`, relative to the package root, in
+    /// [`FileId`] order. [`Self::files`] yields every file.
+    pub fn files_in<'a>(
+        &'a self,
+        dir: &'a Path,
+    ) -> impl Iterator<Item = (FileId, &'a SourceEntry)> {
+        self.files()
+            .filter(move |(_, e)| e.rel_path.parent() == Some(dir))
+    }
+
+    /// Visit the files below `dir`, relative to the package root,
+    /// depth-first in path order, as a scan of the directory would find
+    /// them. `visitor` is given each file and how many directories down from
+    /// `dir` it is.
+    pub fn walk(&self, dir: &Path, visitor: &mut impl FnMut(FileId, usize)) {
+        let mut below: Vec<(FileId, &Path)> = self
+            .files()
+            .filter_map(|(id, e)| Some((id, e.rel_path.strip_prefix(dir).ok()?)))
+            .collect();
+        below.sort_by_key(|&(_, path)| path);
+        for (id, path) in below {
+            visitor(id, path.components().count().saturating_sub(1));
+        }
+    }
+
+    /// Remove a file and its AST, returning its entry, or `None` if it was
+    /// removed already or `id` is not one of this VFS. The [`FileId`]s of
+    /// the other files do not change, and `id` is never handed out again.
+    pub fn remove_file(&mut self, id: FileId) -> Option<SourceEntry> {
+        *self.asts.get_mut(id.index())? = None;
+        self.files.get_mut(id.index())?.take()
     }
 
     /// Store a parsed AST for a file. Replaces any previous AST.
@@ -181,26 +302,165 @@ impl Vfs {
         Some((ast, id.node_index()))
     }
 
+    /// Load the file at `rel_path` below the package root through `provider`
+    /// and add it.
+    pub fn load_file(
+        &mut self,
+        rel_path: PathBuf,
+        source_map: &SourceMap,
+        provider: &dyn ContentProvider,
+    ) -> io::Result<FileId> {
+        let source_file = provider.load(source_map, &self.root.join(&rel_path))?;
+        Ok(self.add_file(rel_path, source_file))
+    }
+
+    /// Reload the file at `path` from disk after it changed, or add it if
+    /// it is new. `path` is absolute or relative to the package root, as
+    /// for [`Vfs::file_for_path`].
+    ///
+    /// A file already in the VFS keeps its [`FileId`] and loses its AST, so
+    /// it is parsed again from the new contents. Those are read once and get
+    /// a source file of their own even though the source map already has
+    /// one for `path`; contents seen before reuse the source file they got.
+    pub fn refresh_file(&mut self, path: &Path, source_map: &SourceMap) -> io::Result<FileId> {
+        self.refresh_file_with(path, source_map, &FileSystem)
+    }
+
+    /// Like [`Vfs::refresh_file`], but load the contents through `provider`.
+    pub fn refresh_file_with(
+        &mut self,
+        path: &Path,
+        source_map: &SourceMap,
+        provider: &dyn ContentProvider,
+    ) -> io::Result<FileId> {
+        let Some(id) = self.file_for_path(path) else {
+            let rel_path = self.rel_path(path).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} is outside of the package root", path.display()),
+                )
+            })?;
+            return self.load_file(rel_path, source_map, provider);
+        };
+        let entry = self.files[id.index()]
+            .as_mut()
+            .expect("`file_for_path` only finds files that are present");
+        let path = self.root.join(&entry.rel_path);
+        let text = provider.read(&path)?;
+        let name = FileName::Real(RealFileName::LocalPath(path.clone()));
+        let mut source_file = source_map.new_source_file(name, text.clone());
+        if source_file.src.as_deref().map(String::as_str) != Some(text.as_str()) {
+            // The source map hands back the file it already has under a
+            // name, so text it has not seen for `path` is registered under
+            // a name derived from its hash. Refreshing to the same text
+            // again finds that file instead of adding another.
+            let mut hasher = DefaultHasher::new();
+            text.hash(&mut hasher);
+            let name = RealFileName::Remapped {
+                local_path: Some(path.clone()),
+                virtual_name: PathBuf::from(format!("{}#{:016x}", path.display(), hasher.finish())),
+            };
+            source_file = source_map.new_source_file(FileName::Real(name), text);
+        }
+        entry.source_file = source_file;
+        self.asts[id.index()] = None;
+        Ok(id)
+    }
+
     /// Scan a package directory and populate the VFS with all `.fl` source
-    /// files found recursively.
+    /// files found recursively, reading them from disk. See
+    /// [`Vfs::scan_with_options`] for other extensions.
     ///
-    /// Directories whose names appear in `ignores` are skipped.
+    /// Directories whose names appear in `ignores` are skipped, and so are
+    /// the files and directories the `.gitignore` files of the package
+    /// exclude: the one at the root and those of the directories below.
     pub fn scan(root: PathBuf, source_map: &SourceMap, ignores: &[&str]) -> Self {
+        Self::scan_with(root, source_map, ignores, &FileSystem)
+    }
+
+    /// Like [`Vfs::scan`], but load file contents through `provider`.
+    ///
+    /// The set of files still comes from the directory listing; `provider`
+    /// only decides what each of them contains.
+    pub fn scan_with(
+        root: PathBuf,
+        source_map: &SourceMap,
+        ignores: &[&str],
+        provider: &dyn ContentProvider,
+    ) -> Self {
+        Self::scan_with_options(root, source_map, ignores, ScanOptions::default(), provider)
+    }
+
+    /// Like [`Vfs::scan_with`], but load the files `options` names as
+    /// sources instead of `.fl` files.
+    pub fn scan_with_options(
+        root: PathBuf,
+        source_map: &SourceMap,
+        ignores: &[&str],
+        options: ScanOptions,
+        provider: &dyn ContentProvider,
+    ) -> Self {
         let name = root
             .file_name()
             .map(|n| n.to_string_lossy().into_owned())
             .unwrap_or_else(|| "unnamed".into());
 
         let mut vfs = Vfs::new(name, root.clone());
-        vfs.scan_dir(source_map, &root, &root, ignores);
+        vfs.options = options;
+        vfs.scan_dir(
+            source_map,
+            provider,
+            &root,
+            ignores,
+            &mut IgnoreRules::default(),
+        );
         vfs
     }
 
-    fn scan_dir(&mut self, source_map: &SourceMap, base: &Path, dir: &Path, ignores: &[&str]) {
+    /// Serialize the VFS as a Lisp-style s-expression string: one line per
+    /// file with its [`FileId`], its path relative to the root and whether
+    /// it has an AST yet.
+    ///
+    /// # Format
+    /// ```text
+    /// (vfs demo
+    ///   (file 0 "main.fl" parsed)
+    ///   (file 1 "src/util.fl" unparsed))
+    /// ```
+    pub fn dump_to_lisp(&self) -> String {
+        let mut out = format!("(vfs {}", self.name);
+        for (id, entry) in self.files() {
+            let state = match self.get_ast(id) {
+                Some(_) => "parsed",
+                None => "unparsed",
+            };
+            out.push_str(&format!(
+                "\n  (file {} {:?} {})",
+                id.raw(),
+                entry.rel_path.display().to_string(),
+                state
+            ));
+        }
+        out.push(')');
+        out
+    }
+
+    fn scan_dir(
+        &mut self,
+        source_map: &SourceMap,
+        provider: &dyn ContentProvider,
+        dir: &Path,
+        ignores: &[&str],
+        gitignore: &mut IgnoreRules,
+    ) {
+        let rel_dir = dir.strip_prefix(&self.root).unwrap_or(dir).to_path_buf();
+        let outer_rules = gitignore.enter_dir(&self.root, &rel_dir);
+
         let entries = match fs::read_dir(dir) {
             Ok(e) => e,
             Err(e) => {
                 eprintln!("warning: cannot read directory {:?}: {}", dir, e);
+                gitignore.truncate(outer_rules);
                 return;
             }
         };
@@ -213,18 +473,308 @@ impl Vfs {
             let path = entry.path();
             let name = entry.file_name().to_string_lossy().into_owned();
 
-            if ignores.contains(&name.as_str()) {
+            let is_dir = path.is_dir();
+            if ignores.contains(&name.as_str())
+                || gitignore.is_ignored(&rel_dir.join(&name), is_dir)
+            {
                 continue;
             }
 
-            if path.is_dir() {
-                self.scan_dir(source_map, base, &path, ignores);
-            } else if path.extension().is_some_and(|ext| ext == "fl") {
-                if let Ok(source_file) = source_map.load_file(&path) {
-                    let rel_path = path.strip_prefix(base).unwrap_or(&path).to_path_buf();
-                    self.add_file(rel_path, source_file);
-                }
+            if is_dir {
+                self.scan_dir(source_map, provider, &path, ignores, gitignore);
+            } else if self.is_source(&path) {
+                let rel_path = path.strip_prefix(&self.root).unwrap_or(&path).to_path_buf();
+                let _ = self.load_file(rel_path, source_map, provider);
             }
         }
+        gitignore.truncate(outer_rules);
+    }
+}
+
+/// `path` with its `.` components dropped and each `..` taking away the
+/// component before it, where there is one.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match out.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    out.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => out.push(".."),
+            },
+            component => out.push(component),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_span::source_map::FilePathMapping;
+
+    use super::*;
+
+    fn package(source_map: &SourceMap) -> Vfs {
+        let root = path::absolute("demo").unwrap();
+        let mut vfs = Vfs::new("demo", root);
+        for (rel_path, src) in [("main.fl", "fn main() {}"), ("src/util.fl", "fn f() {}")] {
+            let source_file = source_map.new_source_file(
+                FileName::Real(RealFileName::LocalPath(rel_path.into())),
+                src.to_string(),
+            );
+            vfs.add_file(PathBuf::from(rel_path), source_file);
+        }
+        vfs
+    }
+
+    #[test]
+    fn files_are_found_by_their_path_relative_to_the_root() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let vfs = package(&source_map);
+        let util = vfs.find_file(Path::new("src/util.fl")).unwrap();
+
+        assert_eq!(vfs.file_for_path(Path::new("src/util.fl")), Some(util));
+        assert_eq!(vfs.file_for_path(Path::new("./src//util.fl")), Some(util));
+        assert_eq!(
+            vfs.file_for_path(Path::new("src/../src/./util.fl")),
+            Some(util)
+        );
+        assert_eq!(vfs.file_for_path(Path::new("util.fl")), None);
+        assert_eq!(vfs.file_for_path(Path::new("../demo/main.fl")), None);
+    }
+
+    #[test]
+    fn files_are_found_by_their_absolute_path() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let vfs = package(&source_map);
+        let main = vfs.find_file(Path::new("main.fl")).unwrap();
+        let util = vfs.find_file(Path::new("src/util.fl")).unwrap();
+
+        assert_eq!(vfs.file_for_path(&vfs.root.join("main.fl")), Some(main));
+        assert_eq!(
+            vfs.file_for_path(&vfs.root.join("src").join("..").join("main.fl")),
+            Some(main)
+        );
+        assert_eq!(
+            vfs.file_for_path(&vfs.root.join("src").join("util.fl")),
+            Some(util)
+        );
+        let outside = vfs.root.parent().unwrap().join("main.fl");
+        assert_eq!(vfs.file_for_path(&outside), None);
+    }
+
+    #[test]
+    fn gitignored_files_and_directories_are_not_scanned() {
+        let root = std::env::temp_dir().join(format!("luna-gitignore-{}", std::process::id()));
+        let files = [
+            (".gitignore", "build/*.fl\nscratch/\n"),
+            ("main.fl", "fn main() {}"),
+            ("build/gen.fl", "fn gen() {}"),
+            ("build/nested/kept.fl", "fn kept() {}"),
+            ("scratch/try.fl", "fn attempt() {}"),
+            ("src/.gitignore", "*.tmp.fl\n"),
+            ("src/util.fl", "fn util() {}"),
+            ("src/util.tmp.fl", "fn util() {}"),
+            ("src/build/gen.fl", "fn gen() {}"),
+        ];
+        for (rel_path, text) in files {
+            let path = root.join(rel_path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let vfs = Vfs::scan(root.clone(), &source_map, &[]);
+        fs::remove_dir_all(&root).unwrap();
+
+        let mut scanned: Vec<_> = vfs.files().map(|(_, e)| e.rel_path.clone()).collect();
+        scanned.sort();
+        let expected: Vec<PathBuf> = [
+            "build/nested/kept.fl",
+            "main.fl",
+            "src/build/gen.fl",
+            "src/util.fl",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        assert_eq!(scanned, expected);
+    }
+
+    #[test]
+    fn a_scan_loads_the_files_with_the_configured_extensions() {
+        let root = std::env::temp_dir().join(format!("luna-extensions-{}", std::process::id()));
+        let files = [
+            ("main.flurry", "fn main() {}"),
+            ("shapes.flurry", "fn area() {}"),
+            ("old.fl", "fn old() {}"),
+            ("notes.txt", "not a source"),
+        ];
+        fs::create_dir_all(&root).unwrap();
+        for (rel_path, text) in files {
+            fs::write(root.join(rel_path), text).unwrap();
+        }
+
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let options = ScanOptions {
+            extensions: vec!["flurry".into()],
+            ..ScanOptions::default()
+        };
+        let vfs = Vfs::scan_with_options(root.clone(), &source_map, &[], options, &FileSystem);
+        fs::remove_dir_all(&root).unwrap();
+
+        let scanned: Vec<_> = vfs.files().map(|(_, e)| e.rel_path.clone()).collect();
+        assert_eq!(
+            scanned,
+            [PathBuf::from("main.flurry"), PathBuf::from("shapes.flurry")]
+        );
+        assert_eq!(vfs.module_name(Path::new("main.flurry")), None);
+        assert_eq!(vfs.module_name(Path::new("shapes.flurry")), Some("shapes"));
+        assert_eq!(vfs.module_name(Path::new("main.fl")), Some("main.fl"));
+        let shapes = vfs.find_file(Path::new("shapes.flurry"));
+        assert_eq!(vfs.find_module_file(Path::new(""), "shapes"), shapes);
+    }
+
+    #[test]
+    fn overlay_buffers_take_precedence_over_disk() {
+        let root = std::env::temp_dir().join(format!("luna-overlay-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("main.fl"), "fn on_disk() {}").unwrap();
+
+        let mut overlay = Overlay::new();
+        overlay.insert(root.join("main.fl"), "fn unsaved() {}");
+
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let vfs = Vfs::scan_with(root.clone(), &source_map, &[], &overlay);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(vfs.file_count(), 1);
+        let (_, entry) = vfs.files().next().unwrap();
+        assert_eq!(entry.source_file.src.as_deref().unwrap(), "fn unsaved() {}");
+    }
+
+    #[test]
+    fn a_walk_visits_files_depth_first_in_path_order() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let mut vfs = package(&source_map);
+        let deep = source_map.new_source_file(
+            FileName::Real(RealFileName::LocalPath("src/deep/leaf.fl".into())),
+            "fn leaf() {}".to_string(),
+        );
+        vfs.add_file(PathBuf::from("src/deep/leaf.fl"), deep);
+
+        let mut visited = Vec::new();
+        vfs.walk(Path::new(""), &mut |id, depth| {
+            visited.push((vfs.file(id).rel_path.clone(), depth))
+        });
+        assert_eq!(visited.len(), vfs.file_count());
+        assert_eq!(
+            visited,
+            [
+                (PathBuf::from("main.fl"), 0),
+                (PathBuf::from("src/deep/leaf.fl"), 2),
+                (PathBuf::from("src/util.fl"), 1),
+            ]
+        );
+
+        let mut below_src = Vec::new();
+        vfs.walk(Path::new("src"), &mut |id, depth| {
+            below_src.push((id.raw(), depth))
+        });
+        assert_eq!(below_src, [(2, 1), (1, 0)]);
+
+        let in_src: Vec<_> = vfs
+            .files_in(Path::new("src"))
+            .map(|(id, _)| id.raw())
+            .collect();
+        assert_eq!(in_src, [1]);
+    }
+
+    #[test]
+    fn dump_lists_files_in_order_with_their_state() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let mut vfs = package(&source_map);
+        vfs.set_ast(FileId::from_raw(0), Ast::new());
+
+        assert_eq!(
+            vfs.dump_to_lisp(),
+            "(vfs demo\n  (file 0 \"main.fl\" parsed)\n  (file 1 \"src/util.fl\" unparsed))"
+        );
+    }
+
+    #[test]
+    fn a_refreshed_file_keeps_its_id_and_is_parsed_again() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let mut vfs = package(&source_map);
+        let util = vfs.find_file(Path::new("src/util.fl")).unwrap();
+        vfs.set_ast(util, Ast::new());
+
+        let mut overlay = Overlay::new();
+        overlay.insert(vfs.root.join("src/util.fl"), "fn g() {}");
+        overlay.insert(vfs.root.join("src/new.fl"), "fn h() {}");
+
+        let refreshed = vfs
+            .refresh_file_with(Path::new("./src/util.fl"), &source_map, &overlay)
+            .unwrap();
+        assert_eq!(refreshed, util);
+        assert!(vfs.get_ast(util).is_none());
+        let src = vfs.file(util).source_file.src.clone().unwrap();
+        assert_eq!(src.as_str(), "fn g() {}");
+
+        let added = vfs
+            .refresh_file_with(&vfs.root.join("src/new.fl"), &source_map, &overlay)
+            .unwrap();
+        assert_eq!(vfs.find_file(Path::new("src/new.fl")), Some(added));
+        assert_eq!(vfs.file_count(), 3);
+    }
+
+    #[test]
+    fn refreshing_through_the_same_provider_and_path_loads_the_new_text() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let mut vfs = Vfs::new("demo", path::absolute("demo").unwrap());
+        let path = vfs.root.join("main.fl");
+        let mut overlay = Overlay::new();
+        overlay.insert(&path, "fn a() {}");
+        let main = vfs.refresh_file_with(&path, &source_map, &overlay).unwrap();
+
+        for text in ["fn b() {}", "fn c() {}", "fn a() {}"] {
+            overlay.insert(&path, text);
+            let refreshed = vfs.refresh_file_with(&path, &source_map, &overlay).unwrap();
+            assert_eq!(refreshed, main);
+            let src = vfs.file(main).source_file.src.clone().unwrap();
+            assert_eq!(src.as_str(), text);
+        }
+
+        let files = source_map.files().len();
+        for text in ["fn b() {}", "fn b() {}", "fn a() {}"] {
+            overlay.insert(&path, text);
+            vfs.refresh_file_with(&path, &source_map, &overlay).unwrap();
+        }
+        assert_eq!(source_map.files().len(), files);
+    }
+
+    #[test]
+    fn removing_a_file_keeps_the_ids_of_the_others() {
+        let source_map = SourceMap::new(FilePathMapping::empty());
+        let mut vfs = package(&source_map);
+        let main = vfs.find_file(Path::new("main.fl")).unwrap();
+        let util = vfs.find_file(Path::new("src/util.fl")).unwrap();
+        vfs.set_ast(main, Ast::new());
+
+        let removed = vfs.remove_file(main).unwrap();
+        assert_eq!(removed.rel_path, Path::new("main.fl"));
+        assert!(vfs.remove_file(main).is_none());
+        assert!(vfs.remove_file(FileId::INVALID).is_none());
+        assert!(vfs.get_ast(main).is_none());
+        assert_eq!(vfs.find_file(Path::new("main.fl")), None);
+        assert_eq!(vfs.file_for_path(Path::new("src/util.fl")), Some(util));
+        assert_eq!(vfs.file_count(), 1);
+        assert_eq!(
+            vfs.files().map(|(id, _)| id).collect::<Vec<_>>(),
+            vec![util]
+        );
     }
 }
@@ -0,0 +1,99 @@
+//! Where the VFS gets file contents from.
+//!
+//! Scanning asks a [`ContentProvider`] for each source file instead of reading
+//! the disk directly, so an editor can feed in unsaved buffers and tests can
+//! run without touching the file system.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rustc_span::{FileName, RealFileName, SourceFile, SourceMap};
+
+/// Loads the contents of a source file into a [`SourceMap`].
+pub trait ContentProvider {
+    /// Load the file at `path` (as found while scanning, i.e. joined onto the
+    /// package root) and register it with `source_map`.
+    fn load(&self, source_map: &SourceMap, path: &Path) -> io::Result<Arc<SourceFile>>;
+
+    /// The current contents of the file at `path`, without registering it.
+    fn read(&self, path: &Path) -> io::Result<String>;
+}
+
+/// Reads files from disk via [`SourceMap::load_file`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileSystem;
+
+impl ContentProvider for FileSystem {
+    fn load(&self, source_map: &SourceMap, path: &Path) -> io::Result<Arc<SourceFile>> {
+        source_map.load_file(path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+}
+
+/// In-memory buffers layered over another provider.
+///
+/// A path with a buffer is served from memory, even if the file also exists
+/// on disk; every other path falls through to `base`.
+#[derive(Debug, Default)]
+pub struct Overlay<P = FileSystem> {
+    buffers: HashMap<PathBuf, String>,
+    base: P,
+}
+
+impl Overlay {
+    /// Create an empty overlay on top of the file system.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<P: ContentProvider> Overlay<P> {
+    /// Create an empty overlay on top of `base`.
+    pub fn with_base(base: P) -> Self {
+        Overlay {
+            buffers: HashMap::new(),
+            base,
+        }
+    }
+
+    /// Set the buffer for `path`, replacing any previous one.
+    pub fn insert(&mut self, path: impl Into<PathBuf>, text: impl Into<String>) {
+        self.buffers.insert(path.into(), text.into());
+    }
+
+    /// Drop the buffer for `path`, so it is loaded from `base` again.
+    pub fn remove(&mut self, path: &Path) -> Option<String> {
+        self.buffers.remove(path)
+    }
+
+    /// The buffer for `path`, if any.
+    pub fn get(&self, path: &Path) -> Option<&str> {
+        self.buffers.get(path).map(String::as_str)
+    }
+}
+
+impl<P: ContentProvider> ContentProvider for Overlay<P> {
+    fn load(&self, source_map: &SourceMap, path: &Path) -> io::Result<Arc<SourceFile>> {
+        match self.buffers.get(path) {
+            Some(text) => Ok(source_map.new_source_file(
+                FileName::Real(RealFileName::LocalPath(path.to_path_buf())),
+                text.clone(),
+            )),
+            None => self.base.load(source_map, path),
+        }
+    }
+
+    fn read(&self, path: &Path) -> io::Result<String> {
+        match self.buffers.get(path) {
+            Some(text) => Ok(text.clone()),
+            None => self.base.read(path),
+        }
+    }
+}